@@ -0,0 +1,116 @@
+//! Chat layout on large synthetic sessions: wrapping, per-message layout and
+//! a full redraw of the conversation
+
+mod common;
+
+use agent_client_protocol::{ContentBlock, TextContent};
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use rat::acp::message::EditProposal;
+use rat::ui::chat::ChatView;
+use rat::{Message, MessageContent, SessionId};
+use ratatui::{backend::TestBackend, style::Style, Terminal};
+
+const MESSAGES: usize = 10_000;
+const WIDTH: usize = 100;
+
+fn text(text: String) -> ContentBlock {
+    ContentBlock::Text(TextContent {
+        text,
+        annotations: Default::default(),
+    })
+}
+
+/// A session of `count` messages: prompts, long and short agent replies,
+/// status updates and the odd error
+fn session(count: usize) -> Vec<Message> {
+    let session_id = SessionId("bench".to_string());
+    (0..count)
+        .map(|i| match i % 8 {
+            0 => Message::user_prompt(
+                session_id.clone(),
+                vec![text(format!("Please look at step {} again", i))],
+            ),
+            1 => Message::agent_response(session_id.clone(), text(common::paragraph(300))),
+            6 => Message::new(
+                session_id.clone(),
+                MessageContent::SessionStatus {
+                    status: format!("Running tool {}", i),
+                },
+            ),
+            7 if i % 64 == 7 => Message::error(session_id.clone(), "Tool call timed out".into()),
+            _ => Message::new(
+                session_id.clone(),
+                MessageContent::AgentMessageChunk {
+                    content: text(common::paragraph(20)),
+                },
+            ),
+        })
+        .collect()
+}
+
+fn edit(lines: usize) -> Message {
+    Message::new(
+        SessionId("bench".to_string()),
+        MessageContent::EditProposed {
+            edit: EditProposal {
+                id: "edit-1".to_string(),
+                file_path: "src/lib.rs".to_string(),
+                original_content: String::new(),
+                proposed_content: String::new(),
+                diff: common::large_diff(lines),
+                description: Some("Check every computation".to_string()),
+            },
+        },
+    )
+}
+
+fn wrap_styled(c: &mut Criterion) {
+    let view = ChatView::new(MESSAGES);
+    let paragraph = common::paragraph(2_000);
+    c.bench_function("chat/wrap_styled_2k_words", |b| {
+        b.iter_batched(
+            || paragraph.clone(),
+            |text| view.wrap_styled(text, Style::default(), black_box(WIDTH)),
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+fn format_message_lines(c: &mut Criterion) {
+    let view = ChatView::new(MESSAGES);
+    let messages = session(MESSAGES);
+    c.bench_function("chat/format_10k_messages", |b| {
+        b.iter(|| {
+            messages
+                .iter()
+                .map(|message| view.format_message_lines(message, black_box(WIDTH)).len())
+                .sum::<usize>()
+        })
+    });
+
+    let edit = edit(5_000);
+    c.bench_function("chat/format_5k_line_diff", |b| {
+        b.iter(|| view.format_message_lines(black_box(&edit), WIDTH))
+    });
+}
+
+fn render(c: &mut Criterion) {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    let mut view = ChatView::new(MESSAGES);
+    for message in session(MESSAGES) {
+        runtime.block_on(view.add_message(message)).unwrap();
+    }
+    let mut terminal = Terminal::new(TestBackend::new(WIDTH as u16 + 2, 50)).unwrap();
+    c.bench_function("chat/render_10k_messages", |b| {
+        b.iter(|| {
+            terminal
+                .draw(|frame| view.render(frame, frame.area()).unwrap())
+                .unwrap();
+        })
+    });
+}
+
+criterion_group!(benches, wrap_styled, format_message_lines, render);
+criterion_main!(benches);
@@ -0,0 +1,77 @@
+//! Synthetic inputs shared by the benchmarks
+
+#![allow(dead_code)]
+
+use std::fmt::Write;
+
+/// A unified diff of `lines` lines in hunks of 40: a few changed lines in
+/// each, surrounded by context, like a large refactor
+pub fn large_diff(lines: usize) -> String {
+    let mut diff = String::from("--- original\n+++ modified\n");
+    let mut line = 0;
+    while line < lines {
+        let _ = writeln!(diff, "@@ -{},36 +{},36 @@", line + 1, line + 1);
+        for i in 0..40.min(lines - line) {
+            let n = line + i;
+            match i % 10 {
+                4 => {
+                    let _ = writeln!(diff, "-    let value_{n} = compute({n});");
+                }
+                5 => {
+                    let _ = writeln!(diff, "+    let value_{n} = compute_checked({n})?;");
+                }
+                _ => {
+                    let _ = writeln!(diff, "     // context line {n} of the file being edited");
+                }
+            }
+        }
+        line += 40;
+    }
+    diff
+}
+
+/// `lines` lines of source-like text, with every tenth line changed in the
+/// modified copy
+pub fn source_pair(lines: usize) -> (String, String) {
+    let mut original = String::new();
+    let mut modified = String::new();
+    for n in 0..lines {
+        let _ = writeln!(original, "    let value_{n} = compute({n});");
+        if n % 10 == 4 {
+            let _ = writeln!(modified, "    let value_{n} = compute_checked({n})?;");
+        } else {
+            let _ = writeln!(modified, "    let value_{n} = compute({n});");
+        }
+    }
+    (original, modified)
+}
+
+/// Prose of about `words` words, as an agent response would contain
+pub fn paragraph(words: usize) -> String {
+    const WORDS: [&str; 12] = [
+        "the",
+        "agent",
+        "refactored",
+        "session",
+        "handling",
+        "so",
+        "that",
+        "each",
+        "turn",
+        "streams",
+        "incrementally",
+        "—",
+    ];
+    let mut text = String::new();
+    for i in 0..words {
+        if i > 0 {
+            text.push(' ');
+        }
+        text.push_str(WORDS[i % WORDS.len()]);
+        if i % 97 == 96 {
+            // An occasional unbroken token, like a path or URL
+            text.push_str(" https://example.com/a/very/long/path/that/does/not/fit/on/one/line");
+        }
+    }
+    text
+}
@@ -0,0 +1,28 @@
+//! Diff generation and parsing for large edits
+
+mod common;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use rat::utils::diff::DiffGenerator;
+
+fn parse(c: &mut Criterion) {
+    let diff = common::large_diff(5_000);
+    let mut group = c.benchmark_group("diff");
+    group.throughput(Throughput::Bytes(diff.len() as u64));
+    group.bench_function("parse_5k_lines", |b| {
+        b.iter(|| DiffGenerator::parse_diff(black_box(&diff)).unwrap())
+    });
+    group.finish();
+}
+
+fn generate(c: &mut Criterion) {
+    // The LCS table is quadratic in the line count, so this stays smaller
+    // than the parsed diff
+    let (original, modified) = common::source_pair(1_000);
+    c.bench_function("diff/generate_1k_lines", |b| {
+        b.iter(|| DiffGenerator::generate_diff(black_box(&original), black_box(&modified)))
+    });
+}
+
+criterion_group!(benches, parse, generate);
+criterion_main!(benches);
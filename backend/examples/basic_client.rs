@@ -6,7 +6,7 @@ use rat::config::Config;
 #[tokio::main]
 async fn main() -> Result<()> {
     // Initialize logging
-    env_logger::init();
+    tracing_subscriber::fmt::init();
 
     // Load or create default configuration
     let (config, config_file) = Config::load_or_create_default().await?;
@@ -1,10 +1,15 @@
 use anyhow::Result;
 use agent_client_protocol::{self as acp, Client};
 use clap::{ArgAction, Parser, ValueEnum};
-use std::cell::Cell;
-use std::path::PathBuf;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 use std::sync::Arc;
 use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::sync::{mpsc, oneshot};
 use tokio_util::compat::{TokioAsyncReadCompatExt as _, TokioAsyncWriteCompatExt as _};
 
@@ -14,6 +19,153 @@ enum Scenario {
     FailurePath,
     ImagesAndThoughts,
     CommandsUpdate,
+    /// Ask to write a file, then write it through the client if allowed
+    PermissionRequest,
+    /// Run commands through the bridge's `terminal/execute`: one that fails
+    /// with output on both streams, then a long one the client can kill
+    TerminalExecution,
+    /// Load for the renderer: thousands of chunks, a huge diff, a tool call
+    /// updated hundreds of times and tokens too long to wrap at spaces
+    Stress,
+}
+
+/// Ways to misbehave, to check that clients degrade gracefully instead of
+/// hanging or panicking
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+enum Fault {
+    /// Write a line that isn't JSON, then a message cut off mid-object
+    MalformedJson,
+    /// Stop responding: the turn never ends, not even when cancelled
+    Stall,
+    /// Exit without answering the prompt, closing stdout
+    CloseStdout,
+    /// Send every response twice, so the second one's id is already answered
+    DuplicateIds,
+}
+
+/// A scripted turn loaded with `--script`, replayed instead of a scenario.
+///
+/// Updates use the ACP wire format, so anything a real agent can send can be
+/// scripted:
+///
+/// ```yaml
+/// steps:
+///   - update:
+///       sessionUpdate: agent_message_chunk
+///       content: { type: text, text: "Hello" }
+///   - delay_ms: 500
+///     update:
+///       sessionUpdate: plan
+///       entries: [{ content: Read the code, priority: high, status: pending }]
+/// stop_reason: end_turn
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Script {
+    steps: Vec<ScriptStep>,
+    /// How the turn ends; `end_turn` unless set
+    #[serde(default)]
+    stop_reason: Option<acp::StopReason>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ScriptStep {
+    /// Pause before the update, scaled by `--speed` like the built-in scenarios
+    #[serde(default)]
+    delay_ms: u64,
+    /// Sent as a session notification; a step without one only waits
+    #[serde(default)]
+    update: Option<acp::SessionUpdate>,
+}
+
+impl Script {
+    /// Read a script from YAML, or JSON (which YAML also accepts)
+    fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("failed to read script {}: {}", path.display(), e))?;
+        let de = serde_yaml::Deserializer::from_str(&text);
+        serde_path_to_error::deserialize(de).map_err(|e| anyhow::anyhow!("invalid script {}: {}", path.display(), e))
+    }
+}
+
+/// Id of the auth method advertised with `--require-auth`
+const SIM_AUTH_METHOD: &str = "sim-login";
+
+/// File the permission-request scenario writes, relative to the session cwd
+const NOTES_FILE: &str = "sim-notes.md";
+const NOTES: &str = "# Notes\n\nWritten by sim_agent after the client allowed it.\n";
+
+/// Sizes of the stress scenario
+const STRESS_CHUNKS: usize = 5_000;
+const STRESS_DIFF_LINES: usize = 20_000;
+const STRESS_TOOL_UPDATES: usize = 500;
+/// Nesting of each update's raw output; well under serde_json's recursion limit
+const STRESS_NESTING: usize = 64;
+const STRESS_TOKEN_CHARS: usize = 50_000;
+
+fn text_chunk(text: String) -> acp::SessionUpdate {
+    acp::SessionUpdate::AgentMessageChunk { content: acp::ContentBlock::Text(acp::TextContent { annotations: None, text }) }
+}
+
+/// `{"level": 0, "child": {"level": 1, ... {"step": step}}}`, `depth` objects deep
+fn nested_output(depth: usize, step: usize) -> Value {
+    (0..depth).rev().fold(json!({ "step": step }), |child, level| json!({ "level": level, "child": child }))
+}
+
+/// Calls from the agent to the client, made on the connection task
+enum ClientCall {
+    Notify(acp::SessionNotification, oneshot::Sender<()>),
+    RequestPermission(acp::RequestPermissionRequest, oneshot::Sender<Result<acp::RequestPermissionResponse, acp::Error>>),
+    WriteTextFile(acp::WriteTextFileRequest, oneshot::Sender<Result<(), acp::Error>>),
+    /// A JSON-RPC request outside the ACP schema; answered with the whole response
+    Raw { method: String, params: Value, respond_to: oneshot::Sender<Value> },
+    /// A line written to stdout as is
+    Line(String),
+}
+
+/// Prefix of the ids of raw requests, so their responses can be told apart
+/// from the ACP connection's (numeric) ones
+const RAW_ID_PREFIX: &str = "sim-raw-";
+
+/// Raw requests waiting for their response, by id
+type RawPending = Rc<RefCell<HashMap<String, oneshot::Sender<Value>>>>;
+
+/// How a `terminal/execute` command ended, as the bridge reports it
+enum CommandOutcome {
+    Exited(i64),
+    Killed(String),
+    Failed(String),
+}
+
+impl CommandOutcome {
+    fn from_response(response: &Value) -> Self {
+        if let Some(error) = response.get("error") {
+            return match error["data"]["signal"].as_str() {
+                Some(signal) => CommandOutcome::Killed(signal.to_string()),
+                None => CommandOutcome::Failed(error["message"].as_str().unwrap_or("unknown error").to_string()),
+            };
+        }
+        match response["result"]["exitCode"].as_i64() {
+            Some(code) => CommandOutcome::Exited(code),
+            None => CommandOutcome::Failed("response has no exit code".to_string()),
+        }
+    }
+
+    fn status(&self) -> acp::ToolCallStatus {
+        match self {
+            CommandOutcome::Exited(0) => acp::ToolCallStatus::Completed,
+            _ => acp::ToolCallStatus::Failed,
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            CommandOutcome::Exited(code) => format!("exited with code {code}"),
+            CommandOutcome::Killed(signal) => format!("was stopped by the user ({signal})"),
+            CommandOutcome::Failed(message) => format!("could not run: {message}"),
+        }
+    }
 }
 
 #[derive(Parser, Debug)]
@@ -22,9 +174,26 @@ struct Cli {
     #[arg(long, value_enum, default_value_t = Scenario::HappyPathEdit)]
     scenario: Scenario,
 
+    /// YAML or JSON script of updates to replay instead of a scenario
+    #[arg(long, conflicts_with = "scenario")]
+    script: Option<PathBuf>,
+
     #[arg(long, default_value = "fast")]
     speed: String,
 
+    /// Misbehave during the turn
+    #[arg(long, value_enum)]
+    fault: Option<Fault>,
+
+    /// Session updates sent before the fault is injected
+    #[arg(long, default_value_t = 1)]
+    fault_after: usize,
+
+    /// Refuse new sessions with AUTH_REQUIRED until this file exists (an
+    /// external login creates it) or the client calls `authenticate`
+    #[arg(long, value_name = "MARKER")]
+    require_auth: Option<PathBuf>,
+
     #[arg(long, default_value_t = 0)]
     seed: u64,
 
@@ -33,25 +202,49 @@ struct Cli {
 }
 
 struct SimAgent {
-    session_update_tx: mpsc::UnboundedSender<(acp::SessionNotification, oneshot::Sender<()>)>,
+    client_tx: mpsc::UnboundedSender<ClientCall>,
     next_session_id: Cell<u64>,
+    /// Sessions created or loaded; prompts for any other are refused
+    sessions: RefCell<HashSet<String>>,
+    /// Working directory of the latest session
+    cwd: RefCell<PathBuf>,
     scenario: Scenario,
+    script: Option<Script>,
     speed_mult: f32,
     cancelling: Cell<bool>,
+    /// Fault to inject once `fault_after` updates of the turn were sent
+    fault: Option<Fault>,
+    fault_after: usize,
+    updates_sent: Cell<usize>,
+    /// Login marker file, when sessions need authentication
+    require_auth: Option<PathBuf>,
+    authenticated: Cell<bool>,
 }
 
 impl SimAgent {
     fn new(
-        tx: mpsc::UnboundedSender<(acp::SessionNotification, oneshot::Sender<()>)>,
+        tx: mpsc::UnboundedSender<ClientCall>,
         scenario: Scenario,
+        script: Option<Script>,
         speed_mult: f32,
+        fault: Option<Fault>,
+        fault_after: usize,
+        require_auth: Option<PathBuf>,
     ) -> Self {
         Self {
-            session_update_tx: tx,
+            client_tx: tx,
             next_session_id: Cell::new(1),
+            sessions: RefCell::default(),
+            cwd: RefCell::new(PathBuf::from(".")),
             scenario,
+            script,
             speed_mult,
             cancelling: Cell::new(false),
+            fault,
+            fault_after,
+            updates_sent: Cell::new(0),
+            require_auth,
+            authenticated: Cell::new(false),
         }
     }
 
@@ -75,14 +268,58 @@ impl SimAgent {
     }
 
     async fn send_update(&self, session_id: &acp::SessionId, update: acp::SessionUpdate) -> Result<()> {
+        // Like a real agent, stop streaming once the client cancelled the turn
+        if self.cancelling.get() {
+            anyhow::bail!("turn cancelled");
+        }
         let (tx, rx) = oneshot::channel();
-        self.session_update_tx
-            .send((acp::SessionNotification { session_id: session_id.clone(), update }, tx))?;
+        self.client_tx
+            .send(ClientCall::Notify(acp::SessionNotification { session_id: session_id.clone(), update }, tx))?;
         // Wait until the IO task forwards the notification to the client
         rx.await.map_err(|_| anyhow::anyhow!("session notification forwarding dropped"))?;
+        self.updates_sent.set(self.updates_sent.get() + 1);
+        if self.updates_sent.get() == self.fault_after {
+            self.inject_fault().await?;
+        }
         Ok(())
     }
 
+    async fn inject_fault(&self) -> Result<()> {
+        match self.fault {
+            Some(Fault::MalformedJson) => {
+                self.client_tx.send(ClientCall::Line("this is not JSON-RPC".to_string()))?;
+                self.client_tx.send(ClientCall::Line(
+                    r#"{"jsonrpc":"2.0","method":"session/update","params":{"sessionId":"#.to_string(),
+                ))?;
+            }
+            Some(Fault::Stall) => {
+                tracing::warn!("stalling the turn");
+                std::future::pending::<()>().await;
+            }
+            Some(Fault::CloseStdout) => {
+                tracing::warn!("exiting mid-turn");
+                std::process::exit(0);
+            }
+            // Applied to every response as it is written
+            Some(Fault::DuplicateIds) | None => {}
+        }
+        Ok(())
+    }
+
+    async fn request_permission(&self, request: acp::RequestPermissionRequest) -> Result<acp::RequestPermissionOutcome> {
+        let (tx, rx) = oneshot::channel();
+        self.client_tx.send(ClientCall::RequestPermission(request, tx))?;
+        let response = rx.await.map_err(|_| anyhow::anyhow!("permission request dropped"))?;
+        Ok(response.map_err(|e| anyhow::anyhow!("permission request failed: {e}"))?.outcome)
+    }
+
+    async fn write_text_file(&self, request: acp::WriteTextFileRequest) -> Result<()> {
+        let (tx, rx) = oneshot::channel();
+        self.client_tx.send(ClientCall::WriteTextFile(request, tx))?;
+        let response = rx.await.map_err(|_| anyhow::anyhow!("write request dropped"))?;
+        response.map_err(|e| anyhow::anyhow!("{e}"))
+    }
+
     async fn run_happy_path(&self, sid: &acp::SessionId) -> Result<()> {
         // Plan
         self.send_update(
@@ -196,6 +433,220 @@ impl SimAgent {
         Ok(())
     }
 
+    async fn run_permission_request(&self, sid: &acp::SessionId) -> Result<()> {
+        let path = self.cwd.borrow().join(NOTES_FILE);
+        let tool_id = acp::ToolCallId(Arc::from("call_write_1"));
+        let title = format!("Write {NOTES_FILE}");
+        let raw_input = json!({ "path": path, "content": NOTES });
+        self.send_update(
+            sid,
+            acp::SessionUpdate::ToolCall(acp::ToolCall {
+                id: tool_id.clone(),
+                title: title.clone(),
+                kind: acp::ToolKind::Edit,
+                status: acp::ToolCallStatus::Pending,
+                content: vec![acp::ToolCallContent::Diff { diff: acp::Diff { path: path.clone(), old_text: None, new_text: NOTES.into() } }],
+                locations: vec![acp::ToolCallLocation { path: path.clone(), line: None }],
+                raw_input: Some(raw_input.clone()),
+                raw_output: None,
+            }),
+        ).await?;
+        self.sleep_scaled(40).await;
+
+        let option = |id: &str, name: &str, kind| acp::PermissionOption { id: acp::PermissionOptionId(Arc::from(id)), name: name.into(), kind };
+        let outcome = self
+            .request_permission(acp::RequestPermissionRequest {
+                session_id: sid.clone(),
+                tool_call: acp::ToolCallUpdate {
+                    id: tool_id.clone(),
+                    fields: acp::ToolCallUpdateFields { kind: Some(acp::ToolKind::Edit), title: Some(title), raw_input: Some(raw_input), ..Default::default() },
+                },
+                options: vec![
+                    option("allow-once", "Allow once", acp::PermissionOptionKind::AllowOnce),
+                    option("allow-always", "Always allow edits", acp::PermissionOptionKind::AllowAlways),
+                    option("reject-once", "Reject", acp::PermissionOptionKind::RejectOnce),
+                    option("reject-always", "Never allow edits", acp::PermissionOptionKind::RejectAlways),
+                ],
+            })
+            .await?;
+        let allowed = match outcome {
+            acp::RequestPermissionOutcome::Selected { option_id } => option_id.0.starts_with("allow"),
+            // The client cancelled the turn while asking
+            acp::RequestPermissionOutcome::Cancelled => {
+                self.cancelling.set(true);
+                return Ok(());
+            }
+        };
+
+        let (status, text) = if !allowed {
+            (acp::ToolCallStatus::Failed, "Permission denied; nothing was written.".to_string())
+        } else {
+            match self.write_text_file(acp::WriteTextFileRequest { session_id: sid.clone(), path: path.clone(), content: NOTES.into() }).await {
+                Ok(()) => (acp::ToolCallStatus::Completed, format!("Wrote {}.", path.display())),
+                Err(e) => (acp::ToolCallStatus::Failed, format!("Could not write {}: {e}", path.display())),
+            }
+        };
+        self.send_update(
+            sid,
+            acp::SessionUpdate::ToolCallUpdate(acp::ToolCallUpdate {
+                id: tool_id,
+                fields: acp::ToolCallUpdateFields { status: Some(status), ..Default::default() },
+            }),
+        ).await?;
+        self.sleep_scaled(40).await;
+        self.send_update(
+            sid,
+            acp::SessionUpdate::AgentMessageChunk { content: acp::ContentBlock::Text(acp::TextContent { annotations: None, text }) },
+        ).await?;
+        Ok(())
+    }
+
+    async fn raw_request(&self, method: &str, params: Value) -> Result<Value> {
+        let (tx, rx) = oneshot::channel();
+        self.client_tx.send(ClientCall::Raw { method: method.to_string(), params, respond_to: tx })?;
+        rx.await.map_err(|_| anyhow::anyhow!("{method} request dropped"))
+    }
+
+    /// Run a command through the client as an execute tool call
+    async fn run_command(&self, sid: &acp::SessionId, call_id: &str, title: &str, cmd: &str, args: &[&str], cwd: &Path) -> Result<CommandOutcome> {
+        let tool_id = acp::ToolCallId(Arc::from(call_id));
+        self.send_update(
+            sid,
+            acp::SessionUpdate::ToolCall(acp::ToolCall {
+                id: tool_id.clone(),
+                title: title.into(),
+                kind: acp::ToolKind::Execute,
+                status: acp::ToolCallStatus::InProgress,
+                content: vec![],
+                locations: vec![],
+                raw_input: Some(json!({ "command": cmd, "args": args })),
+                raw_output: None,
+            }),
+        ).await?;
+
+        let response = self.raw_request("terminal/execute", json!({ "cmd": cmd, "args": args, "cwd": cwd })).await?;
+        let outcome = CommandOutcome::from_response(&response);
+        self.send_update(
+            sid,
+            acp::SessionUpdate::ToolCallUpdate(acp::ToolCallUpdate {
+                id: tool_id,
+                fields: acp::ToolCallUpdateFields {
+                    status: Some(outcome.status()),
+                    content: Some(vec![acp::ToolCallContent::Content { content: acp::ContentBlock::Text(acp::TextContent { annotations: None, text: format!("`{cmd}` {}", outcome.describe()) }) }]),
+                    raw_output: Some(response),
+                    ..Default::default()
+                },
+            }),
+        ).await?;
+        Ok(outcome)
+    }
+
+    async fn run_terminal_execution(&self, sid: &acp::SessionId) -> Result<()> {
+        let cwd = self.cwd.borrow().clone();
+        let build = self
+            .run_command(sid, "call_term_1", "Run the build", "sh", &["-c", "echo compiling; echo 'warning: unused variable' >&2; exit 3"], &cwd)
+            .await?;
+        self.sleep_scaled(60).await;
+
+        // Long enough to kill from the client at any speed
+        let secs = ((20.0 / self.speed_mult.max(0.01)).ceil() as u64).max(1).to_string();
+        let watch = self.run_command(sid, "call_term_2", "Watch for changes", "sleep", &[secs.as_str()], &cwd).await?;
+        self.sleep_scaled(40).await;
+
+        self.send_update(
+            sid,
+            acp::SessionUpdate::AgentMessageChunk {
+                content: acp::ContentBlock::Text(acp::TextContent {
+                    annotations: None,
+                    text: format!("The build {}; the watcher {}.", build.describe(), watch.describe()),
+                }),
+            },
+        ).await?;
+        Ok(())
+    }
+
+    async fn run_script(&self, sid: &acp::SessionId, script: &Script) -> Result<()> {
+        for step in &script.steps {
+            if step.delay_ms > 0 {
+                self.sleep_scaled(step.delay_ms).await;
+            }
+            if let Some(update) = &step.update {
+                self.send_update(sid, update.clone()).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn run_stress(&self, sid: &acp::SessionId) -> Result<()> {
+        // Rapid chunks, with no pause between them whatever the speed
+        for i in 0..STRESS_CHUNKS {
+            self.send_update(sid, text_chunk(format!("chunk {i} "))).await?;
+        }
+
+        // A generated file as one huge diff. It is new, so the client's line
+        // diff stays linear and the cost is all in rendering.
+        let tool_id = acp::ToolCallId(Arc::from("call_stress_diff"));
+        let path = self.cwd.borrow().join("src/generated.rs");
+        let new_text: String = (0..STRESS_DIFF_LINES).map(|i| format!("pub const VALUE_{i}: u64 = {i} * {i}; // generated line {i}\n")).collect();
+        self.send_update(
+            sid,
+            acp::SessionUpdate::ToolCall(acp::ToolCall {
+                id: tool_id.clone(),
+                title: format!("Generate {} lines", STRESS_DIFF_LINES),
+                kind: acp::ToolKind::Edit,
+                status: acp::ToolCallStatus::InProgress,
+                content: vec![acp::ToolCallContent::Diff { diff: acp::Diff { path: path.clone(), old_text: None, new_text } }],
+                locations: vec![acp::ToolCallLocation { path, line: Some(1) }],
+                raw_input: None,
+                raw_output: None,
+            }),
+        ).await?;
+        self.send_update(
+            sid,
+            acp::SessionUpdate::ToolCallUpdate(acp::ToolCallUpdate {
+                id: tool_id,
+                fields: acp::ToolCallUpdateFields { status: Some(acp::ToolCallStatus::Completed), ..Default::default() },
+            }),
+        ).await?;
+
+        // One tool call updated over and over, each time with deeply nested output
+        let tool_id = acp::ToolCallId(Arc::from("call_stress_nested"));
+        self.send_update(
+            sid,
+            acp::SessionUpdate::ToolCall(acp::ToolCall {
+                id: tool_id.clone(),
+                title: "Crawl dependency tree".into(),
+                kind: acp::ToolKind::Other,
+                status: acp::ToolCallStatus::InProgress,
+                content: vec![],
+                locations: vec![],
+                raw_input: Some(json!({ "depth": STRESS_NESTING })),
+                raw_output: None,
+            }),
+        ).await?;
+        for step in 0..STRESS_TOOL_UPDATES {
+            let status = (step + 1 == STRESS_TOOL_UPDATES).then_some(acp::ToolCallStatus::Completed);
+            self.send_update(
+                sid,
+                acp::SessionUpdate::ToolCallUpdate(acp::ToolCallUpdate {
+                    id: tool_id.clone(),
+                    fields: acp::ToolCallUpdateFields {
+                        status,
+                        content: Some(vec![acp::ToolCallContent::Content { content: acp::ContentBlock::Text(acp::TextContent { annotations: None, text: format!("Visited {} of {} nodes", step + 1, STRESS_TOOL_UPDATES) }) }]),
+                        raw_output: Some(nested_output(STRESS_NESTING, step)),
+                        ..Default::default()
+                    },
+                }),
+            ).await?;
+        }
+
+        // Tokens with nowhere to break: ASCII, a URL and double-width characters
+        self.send_update(sid, text_chunk(format!("\n{}\n", "x".repeat(STRESS_TOKEN_CHARS)))).await?;
+        self.send_update(sid, text_chunk(format!("https://example.com/{}\n", "a1b2c3/".repeat(STRESS_TOKEN_CHARS / 7)))).await?;
+        self.send_update(sid, text_chunk("漢字".repeat(STRESS_TOKEN_CHARS / 4))).await?;
+        Ok(())
+    }
+
     async fn run_commands_update(&self, sid: &acp::SessionId) -> Result<()> {
         // The AvailableCommandsUpdate session update is behind the `unstable` feature in ACP.
         // For now, just send a standard message chunk to indicate change.
@@ -217,35 +668,74 @@ impl acp::Agent for SimAgent {
                 prompt_capabilities: acp::PromptCapabilities { image: true, audio: false, embedded_context: true },
                 mcp_capabilities: acp::McpCapabilities::default(),
             },
-            auth_methods: vec![],
+            auth_methods: match self.require_auth {
+                Some(_) => vec![acp::AuthMethod {
+                    id: acp::AuthMethodId(Arc::from(SIM_AUTH_METHOD)),
+                    name: "Simulated login".to_string(),
+                    description: Some("Accepted as is; nothing is checked".to_string()),
+                }],
+                None => vec![],
+            },
         })
     }
 
-    async fn authenticate(&self, _arguments: acp::AuthenticateRequest) -> Result<(), acp::Error> {
+    async fn authenticate(&self, arguments: acp::AuthenticateRequest) -> Result<(), acp::Error> {
+        if arguments.method_id.0.as_ref() != SIM_AUTH_METHOD {
+            return Err(acp::Error::invalid_params());
+        }
+        self.authenticated.set(true);
         Ok(())
     }
 
-    async fn new_session(&self, _arguments: acp::NewSessionRequest) -> Result<acp::NewSessionResponse, acp::Error> {
+    async fn new_session(&self, arguments: acp::NewSessionRequest) -> Result<acp::NewSessionResponse, acp::Error> {
+        if let Some(marker) = &self.require_auth {
+            if !self.authenticated.get() && !marker.exists() {
+                tracing::info!("refusing session until {} exists", marker.display());
+                return Err(acp::Error::auth_required());
+            }
+        }
+        *self.cwd.borrow_mut() = arguments.cwd;
         let id = self.next_session_id.get();
         self.next_session_id.set(id + 1);
-        Ok(acp::NewSessionResponse { session_id: acp::SessionId(Arc::from(format!("sim-{id}"))), modes: None })
+        let session_id = format!("sim-{id}");
+        self.sessions.borrow_mut().insert(session_id.clone());
+        Ok(acp::NewSessionResponse { session_id: acp::SessionId(Arc::from(session_id)), modes: None })
     }
 
-    async fn load_session(&self, _arguments: acp::LoadSessionRequest) -> Result<acp::LoadSessionResponse, acp::Error> {
-        // Minimal: do nothing
+    async fn load_session(&self, arguments: acp::LoadSessionRequest) -> Result<acp::LoadSessionResponse, acp::Error> {
+        // Minimal: nothing to replay
+        self.sessions.borrow_mut().insert(arguments.session_id.0.to_string());
         Ok(acp::LoadSessionResponse { modes: None })
     }
 
     async fn prompt(&self, arguments: acp::PromptRequest) -> Result<acp::PromptResponse, acp::Error> {
+        if !self.sessions.borrow().contains(arguments.session_id.0.as_ref()) {
+            return Err(acp::Error::invalid_params());
+        }
         self.cancelling.set(false);
-        match self.scenario {
-            Scenario::HappyPathEdit => self.run_happy_path(&arguments.session_id).await.map_err(|_| acp::Error::internal_error())?,
-            Scenario::FailurePath => self.run_failure_path(&arguments.session_id).await.map_err(|_| acp::Error::internal_error())?,
-            Scenario::ImagesAndThoughts => self.run_images_and_thoughts(&arguments.session_id).await.map_err(|_| acp::Error::internal_error())?,
-            Scenario::CommandsUpdate => self.run_commands_update(&arguments.session_id).await.map_err(|_| acp::Error::internal_error())?,
+        self.updates_sent.set(0);
+        if let Some(script) = &self.script {
+            let result = self.run_script(&arguments.session_id, script).await;
+            if self.cancelling.get() {
+                return Ok(acp::PromptResponse { stop_reason: acp::StopReason::Cancelled });
+            }
+            result.map_err(|_| acp::Error::internal_error())?;
+            return Ok(acp::PromptResponse { stop_reason: script.stop_reason.unwrap_or(acp::StopReason::EndTurn) });
         }
-        let stop_reason = if self.cancelling.get() { acp::StopReason::Cancelled } else { acp::StopReason::EndTurn };
-        Ok(acp::PromptResponse { stop_reason })
+        let result = match self.scenario {
+            Scenario::HappyPathEdit => self.run_happy_path(&arguments.session_id).await,
+            Scenario::FailurePath => self.run_failure_path(&arguments.session_id).await,
+            Scenario::ImagesAndThoughts => self.run_images_and_thoughts(&arguments.session_id).await,
+            Scenario::CommandsUpdate => self.run_commands_update(&arguments.session_id).await,
+            Scenario::PermissionRequest => self.run_permission_request(&arguments.session_id).await,
+            Scenario::TerminalExecution => self.run_terminal_execution(&arguments.session_id).await,
+            Scenario::Stress => self.run_stress(&arguments.session_id).await,
+        };
+        if self.cancelling.get() {
+            return Ok(acp::PromptResponse { stop_reason: acp::StopReason::Cancelled });
+        }
+        result.map_err(|_| acp::Error::internal_error())?;
+        Ok(acp::PromptResponse { stop_reason: acp::StopReason::EndTurn })
     }
 
     async fn cancel(&self, _args: acp::CancelNotification) -> Result<(), acp::Error> {
@@ -256,32 +746,104 @@ impl acp::Agent for SimAgent {
 
 #[tokio::main(flavor = "current_thread")]
 async fn main() -> Result<()> {
-    env_logger::init();
+    tracing_subscriber::fmt::init();
 
     let cli = Cli::parse();
     let speed_mult = SimAgent::parse_speed(&cli.speed);
+    // Fail before the handshake so a broken script is reported, not a hung turn
+    let script = cli.script.as_deref().map(Script::load).transpose()?;
+    let duplicate_responses = cli.fault == Some(Fault::DuplicateIds);
 
     let (tx, mut rx) = mpsc::unbounded_channel();
 
-    let outgoing = tokio::io::stdout().compat_write();
-    let incoming = tokio::io::stdin().compat();
+    // The ACP connection talks through in-memory pipes so raw requests can
+    // share stdio with it: every line out goes through `out_tx`, and
+    // responses to raw requests are taken out of stdin before ACP sees them
+    let (acp_stdout, acp_output) = tokio::io::duplex(64 * 1024);
+    let (mut acp_feed, acp_stdin) = tokio::io::duplex(64 * 1024);
+    let outgoing = acp_stdout.compat_write();
+    let incoming = acp_stdin.compat();
+    let (out_tx, mut out_rx) = mpsc::unbounded_channel::<String>();
+    let pending = RawPending::default();
 
     let local_set = tokio::task::LocalSet::new();
     local_set
         .run_until(async move {
-            let agent = SimAgent::new(tx, cli.scenario, speed_mult);
+            tokio::task::spawn_local(async move {
+                let mut stdout = tokio::io::stdout();
+                while let Some(line) = out_rx.recv().await {
+                    let copies = if duplicate_responses && is_response(&line) { 2 } else { 1 };
+                    for _ in 0..copies {
+                        if stdout.write_all(format!("{line}\n").as_bytes()).await.is_err() || stdout.flush().await.is_err() {
+                            return;
+                        }
+                    }
+                }
+            });
+            let acp_out_tx = out_tx.clone();
+            tokio::task::spawn_local(async move {
+                let mut lines = BufReader::new(acp_output).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    if acp_out_tx.send(line).is_err() {
+                        break;
+                    }
+                }
+            });
+            let stdin_pending = pending.clone();
+            tokio::task::spawn_local(async move {
+                let mut lines = BufReader::new(tokio::io::stdin()).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    if let Some((id, response)) = raw_response(&line) {
+                        if let Some(respond_to) = stdin_pending.borrow_mut().remove(&id) {
+                            let _ = respond_to.send(response);
+                        }
+                        continue;
+                    }
+                    if acp_feed.write_all(format!("{line}\n").as_bytes()).await.is_err() {
+                        break;
+                    }
+                }
+                // Dropping the feed ends the connection
+            });
+
+            let agent = SimAgent::new(tx, cli.scenario, script, speed_mult, cli.fault, cli.fault_after, cli.require_auth);
             let (conn, handle_io) = acp::AgentSideConnection::new(agent, outgoing, incoming, |fut| {
                 tokio::task::spawn_local(fut);
             });
 
             tokio::task::spawn_local(async move {
-                while let Some((session_notification, tx)) = rx.recv().await {
-                    let result = conn.session_notification(session_notification).await;
-                    if let Err(e) = result {
-                        log::error!("{e}");
-                        break;
+                let mut next_raw_id = 1u64;
+                while let Some(call) = rx.recv().await {
+                    match call {
+                        ClientCall::Notify(session_notification, tx) => {
+                            let result = conn.session_notification(session_notification).await;
+                            if let Err(e) = result {
+                                tracing::error!("{e}");
+                                break;
+                            }
+                            let _ = tx.send(());
+                        }
+                        ClientCall::RequestPermission(request, tx) => {
+                            let _ = tx.send(conn.request_permission(request).await);
+                        }
+                        ClientCall::WriteTextFile(request, tx) => {
+                            let _ = tx.send(conn.write_text_file(request).await);
+                        }
+                        ClientCall::Line(line) => {
+                            if out_tx.send(line).is_err() {
+                                break;
+                            }
+                        }
+                        ClientCall::Raw { method, params, respond_to } => {
+                            let id = format!("{RAW_ID_PREFIX}{next_raw_id}");
+                            next_raw_id += 1;
+                            pending.borrow_mut().insert(id.clone(), respond_to);
+                            let request = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+                            if out_tx.send(request.to_string()).is_err() {
+                                break;
+                            }
+                        }
                     }
-                    let _ = tx.send(());
                 }
             });
 
@@ -289,3 +851,19 @@ async fn main() -> Result<()> {
         })
         .await
 }
+
+/// The id and body of a response to one of our raw requests
+fn raw_response(line: &str) -> Option<(String, Value)> {
+    let value: Value = serde_json::from_str(line).ok()?;
+    if value.get("method").is_some() {
+        return None;
+    }
+    let id = value.get("id")?.as_str().filter(|id| id.starts_with(RAW_ID_PREFIX))?.to_string();
+    Some((id, value))
+}
+
+/// Whether a line is a JSON-RPC response rather than a request or notification
+fn is_response(line: &str) -> bool {
+    serde_json::from_str::<Value>(line)
+        .is_ok_and(|value| value.get("method").is_none() && value.get("id").is_some())
+}
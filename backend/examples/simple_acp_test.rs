@@ -14,7 +14,7 @@ use rat::adapters::agent_installer::AgentInstaller;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    env_logger::init();
+    tracing_subscriber::fmt::init();
 
     println!("=== Simple ACP Test ===");
 
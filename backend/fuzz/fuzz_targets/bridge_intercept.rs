@@ -0,0 +1,25 @@
+//! Agent output lines the bridge parses to decide whether to answer a request
+//! locally (fs/*, terminal/execute) instead of forwarding it
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rat::utils::ndjson::{intercept, NdjsonFramer};
+
+fuzz_target!(|data: &[u8]| {
+    let mut framer = NdjsonFramer::default();
+    let mut frames = framer.push(data);
+    frames.extend(framer.finish());
+    for frame in frames {
+        let Ok(message) = serde_json::from_slice::<serde_json::Value>(&frame) else {
+            continue;
+        };
+        if let Some(intercepted) = intercept(&message) {
+            // A request is answered with the id it came with
+            assert_eq!(
+                &intercepted.id,
+                message.get("id").unwrap_or(&serde_json::Value::Null)
+            );
+        }
+    }
+});
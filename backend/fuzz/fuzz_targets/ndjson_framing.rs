@@ -0,0 +1,36 @@
+//! Arbitrary bytes through the bridge's stdio framing, split at an arbitrary
+//! point, and through the WebSocket text and gzip frame decoding
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rat::utils::ndjson::{gunzip, is_gzip, ws_text_frames, NdjsonFramer};
+
+fuzz_target!(|data: &[u8]| {
+    let Some((&split, data)) = data.split_first() else {
+        return;
+    };
+    let split = split as usize % (data.len() + 1);
+
+    // Splitting the input across reads must not change the frames
+    let mut whole = NdjsonFramer::default();
+    let mut expected = whole.push(data);
+    expected.extend(whole.finish());
+    let mut parts = NdjsonFramer::default();
+    let mut frames = parts.push(&data[..split]);
+    frames.extend(parts.push(&data[split..]));
+    frames.extend(parts.finish());
+    assert_eq!(frames, expected);
+    for frame in &frames {
+        assert!(!frame.is_empty() && !frame.contains(&b'\n'));
+    }
+
+    if let Ok(text) = std::str::from_utf8(data) {
+        for frame in ws_text_frames(text) {
+            assert!(!frame.is_empty() && !frame.contains('\n'));
+        }
+    }
+    if is_gzip(data) {
+        let _ = gunzip(data);
+    }
+});
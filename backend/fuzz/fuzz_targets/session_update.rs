@@ -0,0 +1,20 @@
+//! `session/update` notifications from an agent, decoded and turned into
+//! chat messages as the ACP client does
+
+#![no_main]
+
+use agent_client_protocol as acp;
+use libfuzzer_sys::fuzz_target;
+use rat::{Message, SessionId};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(notification) = serde_json::from_slice::<acp::SessionNotification>(data) else {
+        return;
+    };
+    let message = Message::from_session_update(
+        SessionId(notification.session_id.0.to_string()),
+        notification.update,
+    );
+    // Stored sessions serialize every message
+    let _ = serde_json::to_string(&message).unwrap();
+});
@@ -1,16 +1,19 @@
 use anyhow::{anyhow, Context, Result};
-use log::{debug, error, info, warn};
-use std::collections::HashMap;
+use tracing::{debug, error, info, info_span, warn, Instrument};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::path::PathBuf;
 use std::process::Stdio;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
 use std::thread;
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::{Child, Command};
-use tokio::sync::{mpsc, oneshot};
+use tokio::sync::{mpsc, oneshot, watch};
 use tokio_util::compat::{TokioAsyncReadCompatExt, TokioAsyncWriteCompatExt};
 
-use super::{Message, Session, SessionId};
-use crate::app::AppMessage;
+use super::{AgentError, AgentErrorKind, Message, Session, SessionId};
+use crate::events::{AppMessage, AppSender, PermissionReply};
+use crate::config::{PermissionsConfig, PolicyDecision};
 use agent_client_protocol::{self as acp, Agent};
 use which::which;
 
@@ -25,6 +28,13 @@ enum AcpCommand {
         prompt: Vec<acp::ContentBlock>,
         respond_to: oneshot::Sender<Result<()>>,
     },
+    LoadSession {
+        session_id: String,
+        respond_to: oneshot::Sender<Result<()>>,
+    },
+    Cancel {
+        session_id: String,
+    },
 }
 
 // Connection wrapper that communicates with ACP thread
@@ -37,22 +47,69 @@ impl RealAcpConnection {
         let (tx, rx) = oneshot::channel();
         self.command_tx
             .send(AcpCommand::CreateSession { respond_to: tx })
-            .map_err(|_| anyhow::anyhow!("ACP thread disconnected"))?;
-        rx.await
-            .map_err(|_| anyhow::anyhow!("ACP thread response failed"))?
+            .map_err(|_| disconnected())?;
+        rx.await.map_err(|_| {
+            AgentError::with_kind(AgentErrorKind::Protocol, "ACP thread response failed")
+        })?
     }
 
-    async fn send_prompt(&self, session_id: String, prompt: Vec<acp::ContentBlock>) -> Result<()> {
+    async fn load_session(&self, session_id: String) -> Result<()> {
         let (tx, rx) = oneshot::channel();
+        self.command_tx
+            .send(AcpCommand::LoadSession {
+                session_id,
+                respond_to: tx,
+            })
+            .map_err(|_| disconnected())?;
+        rx.await.map_err(|_| {
+            AgentError::with_kind(AgentErrorKind::Protocol, "ACP thread response failed")
+        })?
+    }
+
+    fn cancel(&self, session_id: String) -> Result<()> {
+        self.command_tx
+            .send(AcpCommand::Cancel { session_id })
+            .map_err(|_| disconnected())
+    }
+
+    fn start_prompt(&self, session_id: String, prompt: Vec<acp::ContentBlock>) -> Result<PendingTurn> {
+        let (tx, turn) = PendingTurn::channel();
         self.command_tx
             .send(AcpCommand::SendPrompt {
                 session_id,
                 prompt,
                 respond_to: tx,
             })
-            .map_err(|_| anyhow::anyhow!("ACP thread disconnected"))?;
-        rx.await
-            .map_err(|_| anyhow::anyhow!("ACP thread response failed"))?
+            .map_err(|_| disconnected())?;
+        Ok(turn)
+    }
+}
+
+fn disconnected() -> anyhow::Error {
+    AgentError::with_kind(AgentErrorKind::Protocol, "ACP thread disconnected")
+}
+
+/// A prompt turn the agent is working on; resolves when the turn ends
+pub struct PendingTurn(oneshot::Receiver<Result<()>>);
+
+impl PendingTurn {
+    /// A turn that ends when its result is sent on the returned sender
+    pub fn channel() -> (oneshot::Sender<Result<()>>, Self) {
+        let (tx, rx) = oneshot::channel();
+        (tx, Self(rx))
+    }
+
+    /// A turn that already ended with `result`
+    pub fn finished(result: Result<()>) -> Self {
+        let (tx, turn) = Self::channel();
+        let _ = tx.send(result);
+        turn
+    }
+
+    pub async fn wait(self) -> Result<()> {
+        self.0.await.map_err(|_| {
+            AgentError::with_kind(AgentErrorKind::Protocol, "ACP thread response failed")
+        })?
     }
 }
 
@@ -62,16 +119,43 @@ pub struct LoginCommand {
     pub args: Vec<String>,
 }
 
+/// How the ACP thread opens sessions: the directory they run in, and the
+/// login to run when the agent asks for authentication
+struct SessionSetup {
+    cwd: PathBuf,
+    login_cmd: Option<LoginCommand>,
+}
+
+/// `script` running the login command with a terminal of its own. The
+/// util-linux version takes the command as one shell string, the BSD one as
+/// trailing arguments.
+fn script_command(login_cmd: &LoginCommand) -> Command {
+    let mut c = Command::new("script");
+    if cfg!(target_os = "linux") {
+        let line = std::iter::once(login_cmd.path.to_string_lossy().into_owned())
+            .chain(login_cmd.args.iter().cloned())
+            .map(|word| shell_quote(&word))
+            .collect::<Vec<_>>()
+            .join(" ");
+        // -e: exit with the login's status rather than script's own
+        c.args(["-q", "-e", "-c"]).arg(line).arg("/dev/null");
+    } else {
+        c.arg("-q").arg("/dev/null").arg(&login_cmd.path);
+        c.args(&login_cmd.args);
+    }
+    c
+}
+
+/// Single-quote a word for `sh`
+fn shell_quote(word: &str) -> String {
+    format!("'{}'", word.replace('\'', r"'\''"))
+}
+
 // Best-effort interactive login runner.
 async fn run_login_if_needed(login_cmd: &LoginCommand) -> Result<()> {
     let use_script = which("script").is_ok();
     let mut cmd = if use_script {
-        let mut c = Command::new("script");
-        c.arg("-q").arg("/dev/null").arg(&login_cmd.path);
-        for a in &login_cmd.args {
-            c.arg(a);
-        }
-        c
+        script_command(login_cmd)
     } else {
         let mut c = Command::new(&login_cmd.path);
         c.args(&login_cmd.args);
@@ -176,28 +260,37 @@ async fn acp_thread_main(
     stdin: tokio::process::ChildStdin,
     stdout: tokio::process::ChildStdout,
     mut command_rx: mpsc::UnboundedReceiver<AcpCommand>,
-    login_cmd: Option<LoginCommand>,
-    app_tx: mpsc::UnboundedSender<crate::app::AppMessage>,
+    app_tx: AppSender,
+    setup: SessionSetup,
 ) {
     info!("ACP thread main starting for agent: {}", agent_name);
+    let SessionSetup { cwd, login_cmd } = setup;
 
-    // Convert tokio streams to compatibility layer for ACP
+    // Route the agent's stdio through the traffic tap, then convert the
+    // tokio streams to the compatibility layer for ACP
+    let (stdin, stdout) = super::traffic::tap(&agent_name, stdin, stdout);
     let stdin_compat = stdin.compat_write();
     let stdout_compat = stdout.compat();
 
     // Create ACP connection using LocalSet (which requires single-threaded runtime)
-    let (mut connection, io_task) =
+    let (connection, io_task) =
         acp::ClientSideConnection::new(client, stdin_compat, stdout_compat, |fut| {
             tokio::task::spawn_local(fut);
         });
+    // Shared with the tasks running prompt turns
+    let connection = Rc::new(connection);
 
     info!("Successfully established ACP connection for {}", agent_name);
 
-    // Start the IO task
+    // Start the IO task. Once it ends the connection never answers the
+    // requests still waiting, so turns watch for that instead of hanging.
+    let (closed_tx, closed_rx) = watch::channel(false);
     tokio::task::spawn_local(async move {
         if let Err(e) = io_task.await {
             error!("ACP IO task failed: {}", e);
         }
+        warn!("Agent closed its output");
+        let _ = closed_tx.send(true);
     });
 
     // Initialize the ACP connection with proper protocol version
@@ -251,7 +344,7 @@ async fn acp_thread_main(
                 let request_new_session = || async {
                     connection
                         .new_session(acp::NewSessionRequest {
-                            cwd: std::env::current_dir().unwrap_or_else(|_| "/tmp".into()),
+                            cwd: cwd.clone(),
                             mcp_servers: vec![],
                         })
                         .await
@@ -261,7 +354,7 @@ async fn acp_thread_main(
                     Ok(response) => {
                         let session_id_str = response.session_id.0.to_string();
                         sessions.insert(session_id_str.clone(), response.session_id);
-                        info!("Created ACP session: {}", session_id_str);
+                        info!(session_id = %session_id_str, "Created ACP session");
                         let _ = respond_to.send(Ok(session_id_str));
                     }
                     Err(e) => {
@@ -272,8 +365,10 @@ async fn acp_thread_main(
                         if auth_required {
                             warn!("Session creation requires authentication; attempting external login...");
                             if let Some(cmd) = &login_cmd {
-                                let _ = app_tx.send(crate::app::AppMessage::SuspendTui);
-                                if let Err(le) = run_login_if_needed(cmd).await {
+                                let _ = app_tx.send(crate::events::AppMessage::SuspendTui);
+                                let login = run_login_if_needed(cmd).await;
+                                let _ = app_tx.send(crate::events::AppMessage::ResumeTui);
+                                if let Err(le) = login {
                                     warn!("Login flow failed: {}", le);
                                 } else {
                                     // Retry once
@@ -287,53 +382,136 @@ async fn acp_thread_main(
                                         }
                                         Err(e2) => {
                                             error!("Failed to create session after login: {}", e2);
-                                            let _ = respond_to
-                                                .send(Err(anyhow::anyhow!("Failed to create session after login: {}", e2)));
+                                            let _ = respond_to.send(Err(AgentError::with_kind(
+                                                AgentErrorKind::AuthRequired,
+                                                format!(
+                                                    "Failed to create session after login: {}",
+                                                    e2
+                                                ),
+                                            )));
                                             continue;
                                         }
                                     }
                                 }
-                                let _ = app_tx.send(crate::app::AppMessage::ResumeTui);
                             } else {
                                 warn!("No login command configured; cannot authenticate");
                             }
                         }
 
                         error!("Failed to create ACP session: {}", e);
+                        let kind = if auth_required {
+                            AgentErrorKind::AuthRequired
+                        } else {
+                            AgentErrorKind::Protocol
+                        };
+                        let _ = respond_to.send(Err(AgentError::with_kind(
+                            kind,
+                            format!("Failed to create session: {}", e),
+                        )));
+                    }
+                }
+            }
+            AcpCommand::LoadSession {
+                session_id,
+                respond_to,
+            } => {
+                info!("Loading ACP session: {}", session_id);
+                let acp_session_id = acp::SessionId(session_id.clone().into());
+                // The agent replays the conversation as session updates before responding
+                match connection
+                    .load_session(acp::LoadSessionRequest {
+                        session_id: acp_session_id.clone(),
+                        cwd: cwd.clone(),
+                        mcp_servers: vec![],
+                    })
+                    .await
+                {
+                    Ok(_) => {
+                        sessions.insert(session_id.clone(), acp_session_id);
+                        info!("Loaded ACP session: {}", session_id);
+                        let _ = respond_to.send(Ok(()));
+                    }
+                    Err(e) => {
+                        warn!("Failed to load ACP session {}: {}", session_id, e);
                         let _ = respond_to
-                            .send(Err(anyhow::anyhow!("Failed to create session: {}", e)));
+                            .send(Err(anyhow::anyhow!("Failed to load session: {}", e)));
                     }
                 }
             }
+            AcpCommand::Cancel { session_id } => {
+                let Some(acp_session_id) = sessions.get(&session_id).cloned() else {
+                    warn!("Not cancelling unknown session: {}", session_id);
+                    continue;
+                };
+                // The running turn still ends normally, with StopReason::Cancelled
+                info!("Cancelling turn in session: {}", session_id);
+                if let Err(e) = connection
+                    .cancel(acp::CancelNotification {
+                        session_id: acp_session_id,
+                    })
+                    .await
+                {
+                    warn!("Failed to cancel session {}: {}", session_id, e);
+                }
+            }
             AcpCommand::SendPrompt {
                 session_id,
                 prompt,
                 respond_to,
             } => {
-                info!("Sending prompt to session: {}", session_id);
-                if let Some(acp_session_id) = sessions.get(&session_id) {
-                    match connection
-                        .prompt(acp::PromptRequest {
-                            session_id: acp_session_id.clone(),
-                            prompt: prompt,
-                        })
-                        .await
-                    {
-                        Ok(_response) => {
-                            debug!("Successfully sent prompt to session {}", session_id);
+                let Some(acp_session_id) = sessions.get(&session_id).cloned() else {
+                    error!("Session not found: {}", session_id);
+                    let _ =
+                        respond_to.send(Err(anyhow::anyhow!("Session not found: {}", session_id)));
+                    continue;
+                };
+                // Turns run on their own task so other sessions can be created
+                // and prompted while this one works. Everything logged while
+                // the turn runs carries its session.
+                let turn = info_span!("turn", session_id = %session_id);
+                let connection = connection.clone();
+                let app_tx = app_tx.clone();
+                let agent_name = agent_name.clone();
+                let mut closed = closed_rx.clone();
+                tokio::task::spawn_local(async move {
+                    info!("Sending prompt");
+                    let started = std::time::Instant::now();
+                    let request = connection.prompt(acp::PromptRequest {
+                        session_id: acp_session_id,
+                        prompt,
+                    });
+                    let result = tokio::select! {
+                        result = request => result.map_err(|e| {
+                            let kind = if e.code == acp::ErrorCode::AUTH_REQUIRED.code {
+                                AgentErrorKind::AuthRequired
+                            } else {
+                                AgentErrorKind::Protocol
+                            };
+                            AgentError::with_kind(kind, format!("Failed to send prompt: {}", e))
+                        }),
+                        _ = closed.wait_for(|closed| *closed) => Err(AgentError::with_kind(
+                            AgentErrorKind::Protocol,
+                            "Failed to send prompt: agent closed the connection mid-turn",
+                        )),
+                    };
+                    match result {
+                        Ok(response) => {
+                            debug!(stop_reason = ?response.stop_reason, "Prompt turn ended");
+                            crate::telemetry::turn_finished(started.elapsed());
+                            let _ = app_tx.send(AppMessage::TurnCompleted {
+                                agent_name: agent_name.clone(),
+                                session_id: SessionId(session_id.clone()),
+                                stop_reason: response.stop_reason,
+                            });
                             let _ = respond_to.send(Ok(()));
                         }
                         Err(e) => {
-                            error!("Failed to send prompt to session {}: {}", session_id, e);
-                            let _ = respond_to
-                                .send(Err(anyhow::anyhow!("Failed to send prompt: {}", e)));
+                            error!("{}", e);
+                            let _ = respond_to.send(Err(e));
                         }
                     }
-                } else {
-                    error!("Session not found: {}", session_id);
-                    let _ =
-                        respond_to.send(Err(anyhow::anyhow!("Session not found: {}", session_id)));
                 }
+                .instrument(turn));
             }
         }
     }
@@ -344,16 +522,119 @@ async fn acp_thread_main(
 /// Our implementation of the ACP Client trait
 pub struct RatClient {
     agent_name: String,
-    message_tx: mpsc::UnboundedSender<AppMessage>,
+    message_tx: AppSender,
+    policy: PermissionsConfig,
+    /// Sessions being reattached after a restart. The agent replays their
+    /// history, which the UI already shows, so those updates are dropped.
+    reattaching: Arc<Mutex<HashSet<String>>>,
 }
 
 impl RatClient {
-    pub fn new(agent_name: String, message_tx: mpsc::UnboundedSender<AppMessage>) -> Self {
+    pub fn new(agent_name: String, message_tx: AppSender) -> Self {
         Self {
             agent_name,
             message_tx,
+            policy: PermissionsConfig::default(),
+            reattaching: Arc::default(),
         }
     }
+
+    /// Answer a permission request from the policy, or from the user when
+    /// the policy says to ask
+    async fn resolve_permission(
+        &self,
+        args: acp::RequestPermissionRequest,
+    ) -> Result<acp::RequestPermissionResponse, acp::Error> {
+        info!(tool_call = ?args.tool_call, "Permission requested");
+
+        // Resolve using the configured policy, asking the host when it says so
+        let tool_kind = args.tool_call.fields.kind.as_ref().map(tool_kind_name);
+        let command = args
+            .tool_call
+            .fields
+            .raw_input
+            .as_ref()
+            .and_then(command_from_raw_input);
+        let mut decision = self.policy.decide(tool_kind.as_deref(), command.as_deref());
+        if decision == PolicyDecision::Ask {
+            let (reply, answer) = PermissionReply::new();
+            let _ = self.message_tx.send(AppMessage::PermissionRequested {
+                agent_name: self.agent_name.clone(),
+                session_id: SessionId(args.session_id.0.to_string()),
+                title: args.tool_call.fields.title.clone().unwrap_or_default(),
+                tool_kind: tool_kind.clone(),
+                command: command.clone(),
+                reply,
+            });
+            info!("Waiting for the user to answer the permission request");
+            decision = match answer.await {
+                Ok(true) => PolicyDecision::Allow,
+                Ok(false) | Err(_) => PolicyDecision::Deny,
+            };
+        }
+        let option = match decision {
+            PolicyDecision::Allow => args
+                .options
+                .iter()
+                .find(|o| {
+                    matches!(
+                        o.kind,
+                        acp::PermissionOptionKind::AllowOnce | acp::PermissionOptionKind::AllowAlways
+                    )
+                })
+                // Agents that don't label their options get the first one, as before
+                .or_else(|| args.options.first()),
+            PolicyDecision::Deny | PolicyDecision::Ask => args.options.iter().find(|o| {
+                matches!(
+                    o.kind,
+                    acp::PermissionOptionKind::RejectOnce | acp::PermissionOptionKind::RejectAlways
+                )
+            }),
+        };
+
+        info!(
+            ?decision,
+            tool_kind = tool_kind.as_deref(),
+            command = command.as_deref(),
+            "Permission decided"
+        );
+        let _ = self.message_tx.send(AppMessage::PermissionResolved {
+            agent_name: self.agent_name.clone(),
+            session_id: SessionId(args.session_id.0.to_string()),
+            title: args.tool_call.fields.title.clone().unwrap_or_default(),
+            tool_kind,
+            command,
+            decision,
+        });
+
+        let outcome = match option {
+            Some(option) => acp::RequestPermissionOutcome::Selected {
+                option_id: option.id.clone(),
+            },
+            None => acp::RequestPermissionOutcome::Cancelled,
+        };
+        Ok(acp::RequestPermissionResponse { outcome })
+    }
+}
+
+/// Lowercase ACP name of a tool kind, as used by the permission policy
+pub fn tool_kind_name(kind: &acp::ToolKind) -> String {
+    format!("{:?}", kind).to_lowercase()
+}
+
+/// Command line from an execute tool call's raw input (`command` plus optional `args`)
+fn command_from_raw_input(raw_input: &serde_json::Value) -> Option<String> {
+    let command = raw_input.get("command")?.as_str()?;
+    let args: Vec<&str> = raw_input
+        .get("args")
+        .and_then(|v| v.as_array())
+        .map(|arr| arr.iter().filter_map(|v| v.as_str()).collect())
+        .unwrap_or_default();
+    if args.is_empty() {
+        Some(command.to_string())
+    } else {
+        Some(format!("{} {}", command, args.join(" ")))
+    }
 }
 
 impl acp::Client for RatClient {
@@ -361,24 +642,15 @@ impl acp::Client for RatClient {
         &self,
         args: acp::RequestPermissionRequest,
     ) -> Result<acp::RequestPermissionResponse, acp::Error> {
-        info!(
-            "Permission requested for session {} - tool call: {:?}",
-            args.session_id.0, args.tool_call
+        // Requests arrive on the connection's IO task, outside the turn span,
+        // so carry the ids that tie the decision to its session and tool call
+        let span = info_span!(
+            "permission",
+            agent = %self.agent_name,
+            session_id = %args.session_id.0,
+            tool_call_id = %args.tool_call.id.0,
         );
-
-        // For now, we'll automatically approve all permissions
-        // TODO: Implement proper user permission dialog
-        if let Some(option) = args.options.first() {
-            Ok(acp::RequestPermissionResponse {
-                outcome: acp::RequestPermissionOutcome::Selected {
-                    option_id: option.id.clone(),
-                },
-            })
-        } else {
-            Ok(acp::RequestPermissionResponse {
-                outcome: acp::RequestPermissionOutcome::Cancelled,
-            })
-        }
+        self.resolve_permission(args).instrument(span).await
     }
 
     async fn write_text_file(&self, args: acp::WriteTextFileRequest) -> Result<(), acp::Error> {
@@ -387,6 +659,11 @@ impl acp::Client for RatClient {
         match tokio::fs::write(&args.path, &args.content).await {
             Ok(()) => {
                 debug!("Successfully wrote file: {:?}", args.path);
+                let _ = self.message_tx.send(AppMessage::FileWritten {
+                    agent_name: self.agent_name.clone(),
+                    session_id: SessionId(args.session_id.0.to_string()),
+                    path: args.path,
+                });
                 Ok(())
             }
             Err(e) => {
@@ -437,10 +714,21 @@ impl acp::Client for RatClient {
     }
 
     async fn session_notification(&self, args: acp::SessionNotification) -> Result<(), acp::Error> {
-        debug!(
-            "Session notification for {}: {:?}",
-            args.session_id.0, args.update
+        let span = info_span!(
+            "session",
+            agent = %self.agent_name,
+            session_id = %args.session_id.0,
         );
+        let _entered = span.enter();
+        debug!(update = ?args.update, "Session notification");
+        if self
+            .reattaching
+            .lock()
+            .unwrap()
+            .contains(&*args.session_id.0)
+        {
+            return Ok(());
+        }
 
         let session_id = SessionId(args.session_id.0.to_string());
         let message = Message::from_session_update(session_id.clone(), args.update);
@@ -469,51 +757,24 @@ pub struct AcpClient {
     connection: Option<RealAcpConnection>,
     acp_thread_handle: Option<thread::JoinHandle<()>>,
     login_command: Option<LoginCommand>,
+    working_dir: Option<PathBuf>,
     sessions: HashMap<SessionId, Session>,
-    message_tx: mpsc::UnboundedSender<AppMessage>,
+    message_tx: AppSender,
     client: RatClient,
+    /// The agent's most recent stderr lines, kept to explain a crash
+    stderr_tail: Arc<Mutex<VecDeque<String>>>,
 }
 
-impl AcpClient {
-    // Build extra CLI args for Claude Code to ensure file edit/tools are enabled.
-    // Defaults allow both ACP-bridged FS tools and Claude's built-in Edit/MultiEdit.
-    // Users can override via env:
-    // - RAT_PERMISSION_PROMPT_TOOL
-    // - RAT_ALLOWED_TOOLS (comma-separated)
-    // - RAT_DISALLOWED_TOOLS (comma-separated; empty to omit flag)
-    fn build_claude_tool_args() -> Vec<String> {
-        let mut args = Vec::new();
-
-        let permission_tool = std::env::var("RAT_PERMISSION_PROMPT_TOOL")
-            .ok()
-            .filter(|s| !s.is_empty())
-            .unwrap_or_else(|| "mcp__acp__permission".to_string());
-        args.push("--permission-prompt-tool".to_string());
-        args.push(permission_tool);
-
-        let allowed_default = "mcp__acp__read,mcp__acp__write,Read,Write,Edit,MultiEdit";
-        let allowed = std::env::var("RAT_ALLOWED_TOOLS")
-            .ok()
-            .filter(|s| !s.is_empty())
-            .unwrap_or_else(|| allowed_default.to_string());
-        args.push("--allowedTools".to_string());
-        args.push(allowed);
-
-        if let Ok(disallowed) = std::env::var("RAT_DISALLOWED_TOOLS") {
-            if !disallowed.trim().is_empty() {
-                args.push("--disallowedTools".to_string());
-                args.push(disallowed);
-            }
-        }
+/// Stderr lines kept per agent
+const STDERR_TAIL_LINES: usize = 10;
 
-        args
-    }
+impl AcpClient {
     pub fn new(
         agent_name: &str,
         command_path: &str,
         command_args: Vec<String>,
         command_env: Option<HashMap<String, String>>,
-        message_tx: mpsc::UnboundedSender<AppMessage>,
+        message_tx: AppSender,
         login_command: Option<LoginCommand>,
     ) -> Self {
         let client = RatClient::new(agent_name.to_string(), message_tx.clone());
@@ -527,27 +788,44 @@ impl AcpClient {
             connection: None,
             acp_thread_handle: None,
             login_command,
+            working_dir: None,
             sessions: HashMap::new(),
             message_tx,
             client,
+            stderr_tail: Arc::default(),
         }
     }
 
+    /// Policy applied to the agent's permission requests; takes effect on the next start
+    pub fn set_permission_policy(&mut self, policy: PermissionsConfig) {
+        self.client.policy = policy;
+    }
+
+    /// Directory the agent runs in and reports as the session cwd; defaults
+    /// to RAT's own working directory. Takes effect on the next start.
+    pub fn set_working_dir(&mut self, dir: Option<PathBuf>) {
+        self.working_dir = dir;
+    }
+
     pub async fn start(&mut self) -> Result<()> {
         info!("Starting ACP agent: {}", self.agent_name);
 
+        let cwd = match &self.working_dir {
+            Some(dir) => dir.clone(),
+            None => std::env::current_dir().unwrap_or_else(|_| "/tmp".into()),
+        };
+
         // Start the agent process
         let mut cmd = Command::new(&self.command_path);
         if !self.command_args.is_empty() {
             cmd.args(&self.command_args);
         }
-        // For Claude Code specifically, append args that enable file edits and tool usage
-        if self.agent_name == "claude-code" {
-            cmd.args(Self::build_claude_tool_args());
-        }
         if let Some(env) = &self.command_env {
             cmd.envs(env);
         }
+        if self.working_dir.is_some() {
+            cmd.current_dir(&cwd);
+        }
         let mut child = cmd
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
@@ -579,6 +857,8 @@ impl AcpClient {
         // Drain agent stderr in the background to prevent pipe backpressure deadlocks
         {
             let agent_name = self.agent_name.clone();
+            let tail = self.stderr_tail.clone();
+            tail.lock().unwrap().clear();
             tokio::spawn(async move {
                 use tokio::io::AsyncBufReadExt;
                 let mut reader = BufReader::new(stderr);
@@ -591,6 +871,11 @@ impl AcpClient {
                             let trimmed = line.trim_end();
                             if !trimmed.is_empty() {
                                 warn!("[{} stderr] {}", agent_name, trimmed);
+                                let mut tail = tail.lock().unwrap();
+                                if tail.len() == STDERR_TAIL_LINES {
+                                    tail.pop_front();
+                                }
+                                tail.push_back(trimmed.to_string());
                             }
                         }
                         Err(e) => {
@@ -603,8 +888,12 @@ impl AcpClient {
         }
 
         // Spawn ACP thread with single-threaded runtime
-        let login_cmd = self.login_command.clone();
+        let setup = SessionSetup {
+            cwd,
+            login_cmd: self.login_command.clone(),
+        };
         let app_tx = self.message_tx.clone();
+        let agent_span = info_span!("agent", agent = %agent_name);
         let acp_handle = thread::spawn(move || {
             info!("Starting ACP thread with single-threaded runtime");
 
@@ -623,9 +912,10 @@ impl AcpClient {
                         stdin,
                         stdout,
                         command_rx,
-                        login_cmd,
                         app_tx,
-                    ))
+                        setup,
+                    )
+                    .instrument(agent_span))
                     .await
             });
 
@@ -684,11 +974,78 @@ impl AcpClient {
         Ok(session_id)
     }
 
+    /// Ask the agent to restore a previous session (ACP `session/load`)
+    pub async fn load_session(&mut self, session_id: &SessionId) -> Result<()> {
+        let connection = self
+            .connection
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Client not connected"))?;
+
+        connection.load_session(session_id.0.clone()).await?;
+        self.sessions
+            .insert(session_id.clone(), Session::new(session_id.clone()));
+        Ok(())
+    }
+
+    /// Load a session the UI still shows, e.g. after the agent restarted,
+    /// without forwarding the agent's replay of its history
+    pub async fn reattach_session(&mut self, session_id: &SessionId) -> Result<()> {
+        let reattaching = self.client.reattaching.clone();
+        reattaching.lock().unwrap().insert(session_id.0.clone());
+        let result = self.load_session(session_id).await;
+        reattaching.lock().unwrap().remove(&session_id.0);
+        result
+    }
+
+    /// The last lines the agent wrote to stderr, oldest first
+    pub fn stderr_tail(&self) -> Vec<String> {
+        self.stderr_tail.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Whether the agent process or its ACP thread ended on its own while
+    /// connected
+    pub fn has_exited(&mut self) -> bool {
+        if self.connection.is_none() {
+            return false;
+        }
+        let thread_done = self
+            .acp_thread_handle
+            .as_ref()
+            .is_some_and(|handle| handle.is_finished());
+        let process_done = self
+            .process
+            .as_mut()
+            .is_some_and(|process| matches!(process.try_wait(), Ok(Some(_))));
+        thread_done || process_done
+    }
+
     pub async fn send_prompt(
         &self,
         session_id: &SessionId,
         prompt: Vec<acp::ContentBlock>,
     ) -> Result<()> {
+        self.start_prompt(session_id, prompt)?.wait().await?;
+
+        info!("Successfully sent prompt to session {}", session_id.0);
+        Ok(())
+    }
+
+    /// Ask the agent to stop the turn running in a session. Returns once the
+    /// request is sent; the turn itself ends with `StopReason::Cancelled`.
+    pub fn cancel(&self, session_id: &SessionId) -> Result<()> {
+        let connection = self
+            .connection
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Client not connected"))?;
+        connection.cancel(session_id.0.clone())
+    }
+
+    /// Hand a prompt to the agent without waiting for the turn to end
+    pub fn start_prompt(
+        &self,
+        session_id: &SessionId,
+        prompt: Vec<acp::ContentBlock>,
+    ) -> Result<PendingTurn> {
         let connection = self
             .connection
             .as_ref()
@@ -697,10 +1054,7 @@ impl AcpClient {
         debug!("Sending prompt to session {}", session_id.0);
 
         // Send prompt via ACP thread
-        connection.send_prompt(session_id.0.clone(), prompt).await?;
-
-        info!("Successfully sent prompt to session {}", session_id.0);
-        Ok(())
+        connection.start_prompt(session_id.0.clone(), prompt)
     }
 
     pub async fn send_message(&self, session_id: &SessionId, content: String) -> Result<()> {
@@ -731,8 +1085,6 @@ impl AcpClient {
         &self.agent_name
     }
 
-    // Note: ACP doesn't currently support session cancellation in the public API
-
     pub fn get_session_count(&self) -> usize {
         self.sessions.len()
     }
@@ -744,62 +1096,8 @@ impl Clone for RatClient {
         Self {
             agent_name: self.agent_name.clone(),
             message_tx: self.message_tx.clone(),
+            policy: self.policy.clone(),
+            reattaching: self.reattaching.clone(),
         }
     }
 }
-
-#[cfg(test)]
-mod tests {
-    use super::AcpClient;
-
-    fn with_env<T: FnOnce() -> ()>(kvs: &[(&str, &str)], f: T) {
-        // Save old
-        let saved: Vec<(String, Option<String>)> = kvs
-            .iter()
-            .map(|(k, _)| (k.to_string(), std::env::var(k).ok()))
-            .collect();
-        // Set new
-        for (k, v) in kvs.iter() {
-            std::env::set_var(k, v);
-        }
-        f();
-        // Restore
-        for (k, v) in saved.into_iter() {
-            match v {
-                Some(val) => std::env::set_var(k, val),
-                None => std::env::remove_var(k),
-            }
-        }
-    }
-
-    #[test]
-    fn claude_tool_args_default_and_overrides() {
-        // Default case (no env): should include permission tool and allowed list with Edit/MultiEdit
-        std::env::remove_var("RAT_PERMISSION_PROMPT_TOOL");
-        std::env::remove_var("RAT_ALLOWED_TOOLS");
-        std::env::remove_var("RAT_DISALLOWED_TOOLS");
-        let args = AcpClient::build_claude_tool_args();
-        let joined = args.join(" ");
-        assert!(joined.contains("--permission-prompt-tool mcp__acp__permission"));
-        assert!(joined.contains("--allowedTools"));
-        assert!(joined.contains("Edit"));
-        assert!(joined.contains("MultiEdit"));
-        assert!(!joined.contains("--disallowedTools"));
-
-        // Override all via env
-        with_env(
-            &[
-                ("RAT_PERMISSION_PROMPT_TOOL", "custom_perm"),
-                ("RAT_ALLOWED_TOOLS", "Foo,Bar"),
-                ("RAT_DISALLOWED_TOOLS", "Baz"),
-            ],
-            || {
-                let args = AcpClient::build_claude_tool_args();
-                let joined = args.join(" ");
-                assert!(joined.contains("--permission-prompt-tool custom_perm"));
-                assert!(joined.contains("--allowedTools Foo,Bar"));
-                assert!(joined.contains("--disallowedTools Baz"));
-            },
-        );
-    }
-}
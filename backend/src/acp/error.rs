@@ -0,0 +1,68 @@
+use std::fmt;
+
+/// Why talking to an agent failed, for callers that react to the cause
+/// (`rat exec` picks its exit code from it)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AgentErrorKind {
+    /// The agent is unknown, disabled, or could not be started
+    Unavailable,
+    /// The agent needs a login or API key
+    AuthRequired,
+    /// The agent did not answer in time
+    Timeout,
+    /// The connection failed or the agent answered with an error
+    Protocol,
+}
+
+/// An agent failure with its cause. It travels inside `anyhow::Error`,
+/// under any context added on the way; `kind_of` finds it again.
+#[derive(Debug)]
+pub struct AgentError {
+    pub kind: AgentErrorKind,
+    message: String,
+}
+
+impl AgentError {
+    pub fn with_kind(kind: AgentErrorKind, message: impl Into<String>) -> anyhow::Error {
+        anyhow::Error::new(Self {
+            kind,
+            message: message.into(),
+        })
+    }
+
+    /// The cause recorded anywhere in `error`'s chain, if any
+    pub fn kind_of(error: &anyhow::Error) -> Option<AgentErrorKind> {
+        error
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<AgentError>())
+            .map(|error| error.kind)
+    }
+}
+
+impl fmt::Display for AgentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for AgentError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn kind_survives_added_context() {
+        let error = AgentError::with_kind(AgentErrorKind::AuthRequired, "Failed to create session");
+        assert_eq!(error.to_string(), "Failed to create session");
+        let error = Err::<(), _>(error)
+            .context("Failed to send prompt to agent 'sim'")
+            .unwrap_err();
+        assert_eq!(
+            AgentError::kind_of(&error),
+            Some(AgentErrorKind::AuthRequired)
+        );
+        assert_eq!(AgentError::kind_of(&anyhow::anyhow!("other")), None);
+    }
+}
@@ -0,0 +1,83 @@
+use agent_client_protocol as acp;
+use tracing::warn;
+use std::path::{Path, PathBuf};
+
+use crate::config::PROJECT_CONFIG_FILE;
+
+/// Instruction files looked for in the project root, in order of preference
+pub const DEFAULT_INSTRUCTION_FILES: [&str; 2] = ["RAT.md", "AGENTS.md"];
+
+/// Project instructions (e.g. `RAT.md`) sent ahead of the first prompt of a
+/// new session as an embedded resource
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjectInstructions {
+    pub path: PathBuf,
+    pub text: String,
+}
+
+impl ProjectInstructions {
+    /// The first of `names` found in the root of the project containing `dir`
+    pub fn find(dir: &Path, names: &[String]) -> Option<Self> {
+        let root = project_root(dir);
+        names.iter().map(|name| root.join(name)).find_map(|path| {
+            if !path.is_file() {
+                return None;
+            }
+            match std::fs::read_to_string(&path) {
+                Ok(text) if !text.trim().is_empty() => Some(Self { path, text }),
+                Ok(_) => None,
+                Err(e) => {
+                    warn!("Failed to read project instructions {:?}: {}", path, e);
+                    None
+                }
+            }
+        })
+    }
+
+    pub fn to_block(&self) -> acp::ContentBlock {
+        acp::ContentBlock::Resource(acp::EmbeddedResource {
+            resource: acp::EmbeddedResourceResource::TextResourceContents(
+                acp::TextResourceContents {
+                    mime_type: Some("text/markdown".to_string()),
+                    text: self.text.clone(),
+                    uri: format!("file://{}", self.path.display()),
+                },
+            ),
+            annotations: Default::default(),
+        })
+    }
+}
+
+/// Nearest ancestor of `dir` holding `.git` or a `.rat.toml`, else `dir` itself
+pub fn project_root(dir: &Path) -> PathBuf {
+    dir.ancestors()
+        .find(|d| d.join(".git").exists() || d.join(PROJECT_CONFIG_FILE).is_file())
+        .unwrap_or(dir)
+        .to_path_buf()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_preferred_file_in_project_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("src").join("deep");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::create_dir(dir.path().join(".git")).unwrap();
+        let names: Vec<String> = DEFAULT_INSTRUCTION_FILES.iter().map(|n| n.to_string()).collect();
+
+        assert_eq!(ProjectInstructions::find(&nested, &names), None);
+
+        std::fs::write(dir.path().join("AGENTS.md"), "Use tabs.").unwrap();
+        let found = ProjectInstructions::find(&nested, &names).unwrap();
+        assert_eq!(found.path, dir.path().join("AGENTS.md"));
+        assert_eq!(found.text, "Use tabs.");
+
+        std::fs::write(dir.path().join("RAT.md"), "Use spaces.").unwrap();
+        let found = ProjectInstructions::find(&nested, &names).unwrap();
+        assert_eq!(found.path, dir.path().join("RAT.md"));
+        assert!(ProjectInstructions::find(&nested, &[]).is_none());
+    }
+}
@@ -68,6 +68,9 @@ pub struct ToolCallRequest {
     pub tool_name: String,
     pub parameters: serde_json::Value,
     pub requires_permission: bool,
+    /// Files and lines the tool call works on
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub locations: Vec<acp::ToolCallLocation>,
 }
 
 impl Message {
@@ -164,6 +167,7 @@ impl ToolCallRequest {
             tool_name: format!("{:?}", tool_call.kind),
             parameters: tool_call.raw_input.unwrap_or_default(),
             requires_permission: true, // Default to requiring permission
+            locations: tool_call.locations,
         }
     }
 }
\ No newline at end of file
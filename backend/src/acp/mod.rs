@@ -1,11 +1,18 @@
 pub mod client;
+pub mod error;
+pub mod instructions;
 pub mod message;
 pub mod permissions;
 pub mod session;
+pub mod store;
+pub mod traffic;
 
-pub use client::AcpClient;
+pub use client::{AcpClient, PendingTurn};
+pub use error::{AgentError, AgentErrorKind};
+pub use instructions::ProjectInstructions;
 pub use message::{Message, MessageContent};
 pub use permissions::PermissionRequest;
 pub use session::{Session, SessionId};
+pub use store::{SessionRecorder, SessionStore};
 
 pub use agent_client_protocol::{Plan, PlanEntry, PlanEntryPriority, PlanEntryStatus};
\ No newline at end of file
@@ -1,6 +1,8 @@
 
+use agent_client_protocol as acp;
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
+use std::fmt::Write as _;
 use uuid::Uuid;
 
 use super::{Message, MessageContent};
@@ -95,6 +97,47 @@ impl Session {
         }
     }
 
+    /// Add a message, folding consecutive agent text chunks into one message
+    /// so stored transcripts stay compact.
+    pub fn record_message(&mut self, message: Message) {
+        if let MessageContent::AgentMessageChunk {
+            content: acp::ContentBlock::Text(next),
+        } = &message.content
+        {
+            if let Some(MessageContent::AgentMessageChunk {
+                content: acp::ContentBlock::Text(last),
+            }) = self.messages.back_mut().map(|m| &mut m.content)
+            {
+                last.text.push_str(&next.text);
+                self.last_activity = chrono::Utc::now();
+                return;
+            }
+        }
+        self.add_message(message);
+    }
+
+    /// First line of the first user prompt, for listings
+    pub fn title(&self) -> Option<String> {
+        self.messages.iter().find_map(|msg| match &msg.content {
+            MessageContent::UserPrompt { content } => content.iter().find_map(|block| match block {
+                acp::ContentBlock::Text(text) => text.text.lines().next().map(String::from),
+                _ => None,
+            }),
+            _ => None,
+        })
+    }
+
+    /// First line of the most recent prompt
+    pub fn last_prompt(&self) -> Option<String> {
+        self.messages.iter().rev().find_map(|msg| match &msg.content {
+            MessageContent::UserPrompt { content } => content.iter().find_map(|block| match block {
+                acp::ContentBlock::Text(text) => text.text.lines().next().map(String::from),
+                _ => None,
+            }),
+            _ => None,
+        })
+    }
+
     pub fn get_messages(&self) -> impl Iterator<Item = &Message> {
         self.messages.iter()
     }
@@ -168,6 +211,60 @@ impl Session {
             })
             .collect()
     }
+
+    /// Render the transcript as Markdown
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "# {}\n",
+            self.title().unwrap_or_else(|| "Untitled session".to_string())
+        );
+        let _ = writeln!(out, "- Session: `{}`", self.id);
+        if let Some(agent) = &self.agent_name {
+            let _ = writeln!(out, "- Agent: {}", agent);
+        }
+        let _ = writeln!(out, "- Started: {}", self.created_at.to_rfc3339());
+        let _ = writeln!(out, "- Last activity: {}\n", self.last_activity.to_rfc3339());
+
+        for message in self.get_messages() {
+            match &message.content {
+                MessageContent::UserPrompt { content } => {
+                    let text: Vec<&str> = content.iter().filter_map(block_text).collect();
+                    let _ = writeln!(out, "## User\n\n{}\n", text.join("\n"));
+                }
+                MessageContent::AgentResponse { content }
+                | MessageContent::AgentMessageChunk { content } => {
+                    if let Some(text) = block_text(content) {
+                        let _ = writeln!(out, "## Agent\n\n{}\n", text.trim_end());
+                    }
+                }
+                MessageContent::ToolCall { tool_call } => {
+                    let _ = writeln!(out, "> Tool: `{}`\n", tool_call.tool_name);
+                }
+                MessageContent::EditProposed { edit } => {
+                    let _ = writeln!(
+                        out,
+                        "> Edit: `{}`\n\n```diff\n{}\n```\n",
+                        edit.file_path,
+                        edit.diff.trim_end()
+                    );
+                }
+                MessageContent::Error { error } => {
+                    let _ = writeln!(out, "> Error: {}\n", error);
+                }
+                _ => {}
+            }
+        }
+        out
+    }
+}
+
+fn block_text(block: &acp::ContentBlock) -> Option<&str> {
+    match block {
+        acp::ContentBlock::Text(text) => Some(&text.text),
+        _ => None,
+    }
 }
 
 impl std::fmt::Display for SessionStatus {
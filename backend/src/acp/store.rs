@@ -0,0 +1,286 @@
+use anyhow::{Context, Result};
+use tracing::{debug, warn};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use super::{Message, Session, SessionId};
+use crate::events::AppMessage;
+use crate::config::Config;
+use crate::utils::git::GitStatus;
+
+/// On-disk session history: one JSON file per session under the data dir
+pub struct SessionStore {
+    dir: PathBuf,
+}
+
+/// Listing entry for a stored session
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionSummary {
+    pub id: String,
+    pub agent_name: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub last_activity: chrono::DateTime<chrono::Utc>,
+    pub message_count: usize,
+    pub title: Option<String>,
+    pub working_directory: Option<String>,
+    /// Current git status of the working directory, when listed with it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git: Option<GitStatus>,
+}
+
+impl SessionSummary {
+    pub fn from_session(session: &Session) -> Self {
+        Self {
+            id: session.id.0.clone(),
+            agent_name: session.agent_name.clone(),
+            created_at: session.created_at,
+            last_activity: session.last_activity,
+            message_count: session.message_count(),
+            title: session.title(),
+            working_directory: session.context.working_directory.clone(),
+            git: None,
+        }
+    }
+}
+
+impl SessionStore {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(config.get_effective_data_dir().join("sessions"))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path_for(&self, id: &SessionId) -> PathBuf {
+        // Agent-issued ids are opaque; keep file names portable
+        let file_name: String = id
+            .0
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        self.dir.join(format!("{}.json", file_name))
+    }
+
+    pub async fn save(&self, session: &Session) -> Result<()> {
+        tokio::fs::create_dir_all(&self.dir)
+            .await
+            .with_context(|| format!("Failed to create session directory: {:?}", self.dir))?;
+
+        let path = self.path_for(&session.id);
+        let tmp = path.with_extension("json.tmp");
+        let content = serde_json::to_vec_pretty(session).context("Failed to serialize session")?;
+        tokio::fs::write(&tmp, content)
+            .await
+            .with_context(|| format!("Failed to write session file: {:?}", tmp))?;
+        tokio::fs::rename(&tmp, &path)
+            .await
+            .with_context(|| format!("Failed to write session file: {:?}", path))?;
+
+        debug!("Saved session {} to {:?}", session.id, path);
+        Ok(())
+    }
+
+    /// All stored sessions, most recently active first
+    pub async fn list(&self) -> Result<Vec<Session>> {
+        let mut sessions = Vec::new();
+        if !self.dir.exists() {
+            return Ok(sessions);
+        }
+
+        let mut entries = tokio::fs::read_dir(&self.dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            match Self::read(&path).await {
+                Ok(session) => sessions.push(session),
+                Err(e) => warn!("Skipping unreadable session file {:?}: {}", path, e),
+            }
+        }
+
+        sessions.sort_by_key(|session| std::cmp::Reverse(session.last_activity));
+        Ok(sessions)
+    }
+
+    /// Load a session by full id or unique id prefix
+    pub async fn load(&self, id: &str) -> Result<Session> {
+        let mut matches: Vec<Session> = self
+            .list()
+            .await?
+            .into_iter()
+            .filter(|s| s.id.0.starts_with(id))
+            .collect();
+
+        if let Some(exact) = matches.iter().position(|s| s.id.0 == id) {
+            return Ok(matches.swap_remove(exact));
+        }
+        match matches.len() {
+            0 => Err(anyhow::anyhow!("No stored session matches '{}'", id)),
+            1 => Ok(matches.remove(0)),
+            n => Err(anyhow::anyhow!(
+                "'{}' is ambiguous ({} sessions match); use more characters",
+                id,
+                n
+            )),
+        }
+    }
+
+    /// Delete a stored session by id or unique prefix; returns the removed file
+    pub async fn delete(&self, id: &str) -> Result<PathBuf> {
+        let session = self.load(id).await?;
+        let path = self.path_for(&session.id);
+        tokio::fs::remove_file(&path)
+            .await
+            .with_context(|| format!("Failed to delete session file: {:?}", path))?;
+        Ok(path)
+    }
+
+    async fn read(path: &Path) -> Result<Session> {
+        let content = tokio::fs::read(path).await?;
+        Ok(serde_json::from_slice(&content)?)
+    }
+}
+
+/// Builds session transcripts from the app message stream so they can be
+/// persisted by a [`SessionStore`].
+#[derive(Default)]
+pub struct SessionRecorder {
+    sessions: HashMap<SessionId, Session>,
+    dirty: HashSet<SessionId>,
+}
+
+impl SessionRecorder {
+    /// Track sessions and agent output from an app message. Sessions are
+    /// only persisted once they have messages, so an announced session that
+    /// never gets traffic (e.g. a failed reload) leaves the store untouched.
+    pub fn observe(&mut self, message: &AppMessage) {
+        match message {
+            AppMessage::SessionCreated {
+                agent_name,
+                session_id,
+            }
+            | AppMessage::SessionReplaced {
+                agent_name,
+                new_session_id: session_id,
+                ..
+            } => {
+                // A reloaded session is rebuilt from the agent's replay
+                self.sessions.insert(
+                    session_id.clone(),
+                    Session::with_agent(session_id.clone(), agent_name.clone()),
+                );
+            }
+            AppMessage::AgentMessage {
+                agent_name,
+                message,
+            } => self.record(agent_name, message.clone()),
+            _ => {}
+        }
+    }
+
+    /// Note where a session's agent runs, when that isn't RAT's own
+    /// working directory
+    pub fn set_working_directory(&mut self, session_id: &SessionId, dir: &Path) {
+        if let Some(session) = self.sessions.get_mut(session_id) {
+            session.set_working_directory(dir.display().to_string());
+        }
+    }
+
+    /// Record a message (e.g. the user's own prompt) for a session
+    pub fn record(&mut self, agent_name: &str, message: Message) {
+        let session_id = message.session_id.clone();
+        self.sessions
+            .entry(session_id.clone())
+            .or_insert_with(|| Session::with_agent(session_id.clone(), agent_name.to_string()))
+            .record_message(message);
+        self.dirty.insert(session_id);
+    }
+
+    /// Transcript recorded so far for a session
+    pub fn get(&self, session_id: &SessionId) -> Option<&Session> {
+        self.sessions.get(session_id)
+    }
+
+    /// Persist sessions that changed since the last flush; returns the
+    /// ids of the sessions written
+    pub async fn flush(&mut self, store: &SessionStore) -> Result<Vec<SessionId>> {
+        let mut saved = Vec::new();
+        for session_id in self.dirty.drain().collect::<Vec<_>>() {
+            if let Some(session) = self.sessions.get(&session_id) {
+                store.save(session).await?;
+                saved.push(session_id);
+            }
+        }
+        Ok(saved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session_id(id: &str) -> SessionId {
+        SessionId(id.to_string())
+    }
+
+    #[tokio::test]
+    async fn recorder_saves_only_sessions_with_messages() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = SessionStore::new(dir.path().join("sessions"));
+        let mut recorder = SessionRecorder::default();
+
+        for id in ["abc-1", "abd/2", "quiet"] {
+            recorder.observe(&AppMessage::SessionCreated {
+                agent_name: "sim".to_string(),
+                session_id: session_id(id),
+            });
+        }
+        for id in ["abc-1", "abd/2"] {
+            recorder.observe(&AppMessage::AgentMessage {
+                agent_name: "sim".to_string(),
+                message: Message::error(session_id(id), "boom".to_string()),
+            });
+        }
+
+        let mut saved = recorder.flush(&store).await.unwrap();
+        saved.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(saved, [session_id("abc-1"), session_id("abd/2")]);
+        assert!(recorder.flush(&store).await.unwrap().is_empty());
+        // Agent ids become portable file names
+        assert!(dir.path().join("sessions/abd_2.json").exists());
+
+        let listed = store.list().await.unwrap();
+        assert_eq!(listed.len(), 2);
+        assert_eq!(listed[0].agent_name.as_deref(), Some("sim"));
+    }
+
+    #[tokio::test]
+    async fn sessions_load_and_delete_by_unique_prefix() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = SessionStore::new(dir.path().to_path_buf());
+        for id in ["abc-1", "abd-2"] {
+            store.save(&Session::new(session_id(id))).await.unwrap();
+        }
+
+        assert_eq!(store.load("abc").await.unwrap().id, session_id("abc-1"));
+        assert!(store.load("ab").await.is_err());
+        assert!(store.load("zzz").await.is_err());
+
+        let removed = store.delete("abd").await.unwrap();
+        assert_eq!(removed, dir.path().join("abd-2.json"));
+        assert_eq!(store.load("ab").await.unwrap().id, session_id("abc-1"));
+    }
+}
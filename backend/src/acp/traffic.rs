@@ -0,0 +1,223 @@
+//! Raw JSON-RPC traffic between RAT and its agents. The ACP thread passes
+//! the agent's stdio through [`tap`], which copies every line written or
+//! read to whoever has called [`subscribe`] (the TUI's traffic pane). With
+//! nobody subscribed the lines are forwarded untouched.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::SystemTime;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader, DuplexStream};
+use tokio::sync::mpsc;
+use tracing::debug;
+
+/// Buffer between the connection and each pump
+const PIPE_CAPACITY: usize = 64 * 1024;
+
+static SUBSCRIBERS: Mutex<Vec<mpsc::UnboundedSender<Frame>>> = Mutex::new(Vec::new());
+/// Checked before copying a line, so an unwatched agent costs no allocations
+static WATCHED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// RAT to the agent
+    Outgoing,
+    /// The agent to RAT
+    Incoming,
+}
+
+impl Direction {
+    pub fn arrow(self) -> &'static str {
+        match self {
+            Direction::Outgoing => "→",
+            Direction::Incoming => "←",
+        }
+    }
+}
+
+/// One newline-delimited JSON-RPC message as it went over the pipe
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub agent_name: String,
+    pub direction: Direction,
+    pub at: SystemTime,
+    /// The line without its newline; not necessarily valid JSON
+    pub text: String,
+}
+
+impl Frame {
+    pub fn json(&self) -> Option<serde_json::Value> {
+        serde_json::from_str(&self.text).ok()
+    }
+
+    /// `params.sessionId`, carried by prompts, loads, cancels and updates
+    pub fn session_id(&self) -> Option<String> {
+        self.json()?
+            .get("params")?
+            .get("sessionId")?
+            .as_str()
+            .map(str::to_string)
+    }
+
+    /// The method of a request or notification, else `response #id`
+    pub fn summary(&self) -> String {
+        let Some(json) = self.json() else {
+            return "(not JSON)".to_string();
+        };
+        match (json.get("method").and_then(|m| m.as_str()), json.get("id")) {
+            (Some(method), Some(id)) => format!("{} #{}", method, id),
+            (Some(method), None) => method.to_string(),
+            (None, Some(id)) if json.get("error").is_some() => format!("error #{}", id),
+            (None, Some(id)) => format!("response #{}", id),
+            (None, None) => "(unknown)".to_string(),
+        }
+    }
+}
+
+/// Receive every frame sent to or from any agent from now on; dropping the
+/// receiver unsubscribes
+pub fn subscribe() -> mpsc::UnboundedReceiver<Frame> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let mut subscribers = SUBSCRIBERS.lock().unwrap();
+    subscribers.push(tx);
+    WATCHED.store(true, Ordering::Relaxed);
+    rx
+}
+
+fn publish(agent_name: &str, direction: Direction, line: &[u8]) {
+    if !WATCHED.load(Ordering::Relaxed) {
+        return;
+    }
+    let text = String::from_utf8_lossy(line).trim_end().to_string();
+    if text.is_empty() {
+        return;
+    }
+    let frame = Frame {
+        agent_name: agent_name.to_string(),
+        direction,
+        at: SystemTime::now(),
+        text,
+    };
+    let mut subscribers = SUBSCRIBERS.lock().unwrap();
+    subscribers.retain(|tx| tx.send(frame.clone()).is_ok());
+    WATCHED.store(!subscribers.is_empty(), Ordering::Relaxed);
+}
+
+/// Put taps between an ACP connection and the agent's stdin and stdout.
+/// Returns the ends the connection should write to and read from. The pumps
+/// run on the current runtime and close their side once the other does.
+pub fn tap<W, R>(agent_name: &str, stdin: W, stdout: R) -> (DuplexStream, DuplexStream)
+where
+    W: AsyncWrite + Unpin + Send + 'static,
+    R: AsyncRead + Unpin + Send + 'static,
+{
+    let (to_agent, outgoing) = tokio::io::duplex(PIPE_CAPACITY);
+    let (incoming, from_agent) = tokio::io::duplex(PIPE_CAPACITY);
+    tokio::spawn(pump(
+        agent_name.to_string(),
+        Direction::Outgoing,
+        outgoing,
+        stdin,
+    ));
+    tokio::spawn(pump(
+        agent_name.to_string(),
+        Direction::Incoming,
+        stdout,
+        incoming,
+    ));
+    (to_agent, from_agent)
+}
+
+async fn pump<R, W>(agent_name: String, direction: Direction, from: R, mut to: W)
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut reader = BufReader::new(from);
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        match reader.read_until(b'\n', &mut line).await {
+            Ok(0) => break,
+            Ok(_) => {
+                publish(&agent_name, direction, &line);
+                if let Err(e) = async {
+                    to.write_all(&line).await?;
+                    to.flush().await
+                }
+                .await
+                {
+                    debug!("[{}] {:?} pipe closed: {}", agent_name, direction, e);
+                    break;
+                }
+            }
+            Err(e) => {
+                debug!("[{}] {:?} pipe failed: {}", agent_name, direction, e);
+                break;
+            }
+        }
+    }
+    let _ = to.shutdown().await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+
+    #[test]
+    fn summarizes_requests_notifications_and_responses() {
+        let frame = |text: &str| Frame {
+            agent_name: "claude-code".to_string(),
+            direction: Direction::Incoming,
+            at: SystemTime::now(),
+            text: text.to_string(),
+        };
+        let prompt = frame(
+            r#"{"jsonrpc":"2.0","id":3,"method":"session/prompt","params":{"sessionId":"s1"}}"#,
+        );
+        assert_eq!(prompt.summary(), "session/prompt #3");
+        assert_eq!(prompt.session_id().as_deref(), Some("s1"));
+        assert_eq!(
+            frame(r#"{"jsonrpc":"2.0","method":"session/update","params":{}}"#).summary(),
+            "session/update"
+        );
+        assert_eq!(
+            frame(r#"{"jsonrpc":"2.0","id":3,"result":{}}"#).summary(),
+            "response #3"
+        );
+        assert_eq!(frame("Loading...").summary(), "(not JSON)");
+    }
+
+    #[tokio::test]
+    async fn forwards_and_publishes_both_directions() {
+        let mut frames = subscribe();
+        let (agent_stdin, mut agent_reads) = tokio::io::duplex(1024);
+        let (mut agent_writes, agent_stdout) = tokio::io::duplex(1024);
+        let (mut to_agent, mut from_agent) = tap("tapped", agent_stdin, agent_stdout);
+
+        to_agent.write_all(b"{\"id\":1}\n").await.unwrap();
+        let mut buf = [0u8; 9];
+        agent_reads.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"{\"id\":1}\n");
+
+        agent_writes.write_all(b"{\"id\":2}\n").await.unwrap();
+        from_agent.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"{\"id\":2}\n");
+
+        // Other tests' agents may be tapped concurrently
+        let mut seen = Vec::new();
+        while seen.len() < 2 {
+            let frame = frames.recv().await.unwrap();
+            if frame.agent_name == "tapped" {
+                seen.push((frame.direction, frame.text));
+            }
+        }
+        assert_eq!(
+            seen,
+            [
+                (Direction::Outgoing, "{\"id\":1}".to_string()),
+                (Direction::Incoming, "{\"id\":2}".to_string()),
+            ]
+        );
+    }
+}
@@ -1,5 +1,5 @@
 use anyhow::{Context, Result};
-use log::{debug, info};
+use tracing::{debug, info};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
@@ -35,6 +35,37 @@ impl AgentCommand {
     }
 }
 
+/// Agents RAT knows how to find and install
+pub const MANAGED_AGENTS: &[&str] = &["claude-code", "gemini"];
+
+/// Where a resolved agent command was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentSource {
+    /// Found on PATH
+    Path,
+    /// Found at a well-known global or project-local location
+    KnownLocation,
+    /// Installed by RAT under its data directory
+    Local,
+}
+
+impl std::fmt::Display for AgentSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AgentSource::Path => write!(f, "PATH"),
+            AgentSource::KnownLocation => write!(f, "known location"),
+            AgentSource::Local => write!(f, "local install"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentLocation {
+    pub command: AgentCommand,
+    pub source: AgentSource,
+}
+
 pub struct AgentInstaller {
     data_dir: PathBuf,
 }
@@ -54,10 +85,111 @@ impl AgentInstaller {
     pub async fn get_or_install_claude_code(&self) -> Result<AgentCommand> {
         info!("Looking for Claude Code agent...");
 
+        // Strategies 1-3: PATH, known locations, our local installation
+        if let Some(location) = self.locate_claude_code().await? {
+            return Ok(location.command);
+        }
+
+        // Strategy 4: Try to install locally
+        info!("Claude Code not found, attempting local installation...");
+        self.install_claude_code_locally().await
+    }
+
+    /// Find or install Gemini CLI with robust fallback strategy
+    pub async fn get_or_install_gemini(&self) -> Result<AgentCommand> {
+        info!("Looking for Gemini CLI agent...");
+
+        // Strategies 1-3: PATH, known locations, our local installation
+        if let Some(location) = self.locate_gemini().await? {
+            return Ok(location.command);
+        }
+
+        // Strategy 4: Try to install locally
+        info!("Gemini CLI not found, attempting local installation...");
+        self.install_gemini_locally().await
+    }
+
+    /// Resolve an agent without installing anything
+    pub async fn locate(&self, agent_name: &str) -> Result<Option<AgentLocation>> {
+        match agent_name {
+            "claude-code" => self.locate_claude_code().await,
+            "gemini" => self.locate_gemini().await,
+            other => Err(unknown_agent(other)),
+        }
+    }
+
+    /// Install the latest published version of an agent into the data directory.
+    /// Installing a version that is already present is a no-op.
+    pub async fn install(&self, agent_name: &str) -> Result<AgentCommand> {
+        match agent_name {
+            "claude-code" => self.install_claude_code_locally().await,
+            "gemini" => self.install_gemini_locally().await,
+            other => Err(unknown_agent(other)),
+        }
+    }
+
+    /// Versions of an agent installed under the data directory, oldest first
+    pub async fn local_versions(&self, agent_name: &str) -> Result<Vec<semver::Version>> {
+        let agent_dir = self.data_dir.join(local_dir_name(agent_name)?);
+        if !agent_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut versions = Vec::new();
+        let mut entries = fs::read_dir(&agent_dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            if entry.file_type().await?.is_dir() {
+                if let Some(version) = entry
+                    .file_name()
+                    .to_str()
+                    .and_then(|name| semver::Version::parse(name).ok())
+                {
+                    versions.push(version);
+                }
+            }
+        }
+        versions.sort();
+        Ok(versions)
+    }
+
+    /// Latest version of an agent published on npm
+    pub async fn latest_version(&self, agent_name: &str) -> Result<String> {
+        self.fetch_latest_version(npm_package_name(agent_name)?).await
+    }
+
+    /// Remove local installations of an agent (one version, or all of them).
+    /// Returns the removed directories.
+    pub async fn remove(
+        &self,
+        agent_name: &str,
+        version: Option<&semver::Version>,
+    ) -> Result<Vec<PathBuf>> {
+        let agent_dir = self.data_dir.join(local_dir_name(agent_name)?);
+        let mut removed = Vec::new();
+        for installed in self.local_versions(agent_name).await? {
+            if version.map(|v| v == &installed).unwrap_or(true) {
+                let dir = agent_dir.join(installed.to_string());
+                fs::remove_dir_all(&dir)
+                    .await
+                    .with_context(|| format!("Failed to remove {}", dir.display()))?;
+                removed.push(dir);
+            }
+        }
+        Ok(removed)
+    }
+
+    pub fn data_dir(&self) -> &Path {
+        &self.data_dir
+    }
+
+    async fn locate_claude_code(&self) -> Result<Option<AgentLocation>> {
         // Strategy 1: Check if it's already in PATH
         if let Some(path) = self.find_in_path("claude-code-acp").await {
             info!("Found claude-code-acp in PATH: {}", path.display());
-            return Ok(AgentCommand::new(path));
+            return Ok(Some(AgentLocation {
+                command: AgentCommand::new(path),
+                source: AgentSource::Path,
+            }));
         }
 
         // Strategy 2: Check known installation locations
@@ -68,29 +200,33 @@ impl AgentInstaller {
                     "Found claude-code-acp at known location: {}",
                     path.display()
                 );
-                return Ok(AgentCommand::new(path));
+                return Ok(Some(AgentLocation {
+                    command: AgentCommand::new(path),
+                    source: AgentSource::KnownLocation,
+                }));
             }
         }
 
         // Strategy 3: Check our local installation
-        if let Some(command) = self.get_local_claude_code().await? {
-            return Ok(command);
-        }
-
-        // Strategy 4: Try to install locally
-        info!("Claude Code not found, attempting local installation...");
-        self.install_claude_code_locally().await
+        Ok(self
+            .get_local_claude_code()
+            .await?
+            .map(|command| AgentLocation {
+                command,
+                source: AgentSource::Local,
+            }))
     }
 
-    /// Find or install Gemini CLI with robust fallback strategy
-    pub async fn get_or_install_gemini(&self) -> Result<AgentCommand> {
-        info!("Looking for Gemini CLI agent...");
-
+    async fn locate_gemini(&self) -> Result<Option<AgentLocation>> {
         // Strategy 1: Check if it's already in PATH
         if let Some(path) = self.find_in_path("gemini").await {
             info!("Found gemini in PATH: {}", path.display());
             // Add ACP flag if it supports it
-            return Ok(AgentCommand::new(path).with_args(vec!["--experimental-acp".to_string()]));
+            return Ok(Some(AgentLocation {
+                command: AgentCommand::new(path)
+                    .with_args(vec!["--experimental-acp".to_string()]),
+                source: AgentSource::Path,
+            }));
         }
 
         // Strategy 2: Check known installation locations
@@ -98,20 +234,19 @@ impl AgentInstaller {
         for path in known_paths {
             if self.is_executable(&path).await {
                 info!("Found gemini at known location: {}", path.display());
-                return Ok(
-                    AgentCommand::new(path).with_args(vec!["--experimental-acp".to_string()])
-                );
+                return Ok(Some(AgentLocation {
+                    command: AgentCommand::new(path)
+                        .with_args(vec!["--experimental-acp".to_string()]),
+                    source: AgentSource::KnownLocation,
+                }));
             }
         }
 
         // Strategy 3: Check our local installation
-        if let Some(command) = self.get_local_gemini().await? {
-            return Ok(command);
-        }
-
-        // Strategy 4: Try to install locally
-        info!("Gemini CLI not found, attempting local installation...");
-        self.install_gemini_locally().await
+        Ok(self.get_local_gemini().await?.map(|command| AgentLocation {
+            command,
+            source: AgentSource::Local,
+        }))
     }
 
     /// Build a login command for Claude Code similar to Zed's flow.
@@ -427,24 +562,7 @@ impl AgentInstaller {
             .with_context(|| format!("Failed to create directory: {}", agent_dir.display()))?;
 
         // Get latest version
-        info!("Fetching latest version of {}...", package_name);
-        let output = Command::new("npm")
-            .args(&["view", package_name, "version", "--json"])
-            .output()
-            .await
-            .with_context(|| format!("Failed to get version info for {}", package_name))?;
-
-        if !output.status.success() {
-            return Err(anyhow::anyhow!(
-                "Failed to fetch package version: {}",
-                String::from_utf8_lossy(&output.stderr)
-            ));
-        }
-
-        let version_str = String::from_utf8(output.stdout)?
-            .trim()
-            .trim_matches('"')
-            .to_string();
+        let version_str = self.fetch_latest_version(package_name).await?;
 
         let version_dir = agent_dir.join(&version_str);
 
@@ -569,6 +687,27 @@ impl AgentInstaller {
         Ok(version_dir)
     }
 
+    async fn fetch_latest_version(&self, package_name: &str) -> Result<String> {
+        info!("Fetching latest version of {}...", package_name);
+        let output = Command::new("npm")
+            .args(&["view", package_name, "version", "--json"])
+            .output()
+            .await
+            .with_context(|| format!("Failed to get version info for {}", package_name))?;
+
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "Failed to fetch package version: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        Ok(String::from_utf8(output.stdout)?
+            .trim()
+            .trim_matches('"')
+            .to_string())
+    }
+
     pub async fn verify_agent_command(&self, command: &AgentCommand) -> Result<String> {
         debug!("Verifying agent command: {:?}", command);
 
@@ -597,3 +736,63 @@ impl Default for AgentInstaller {
         Self::new().expect("Failed to create AgentInstaller")
     }
 }
+
+fn unknown_agent(agent_name: &str) -> anyhow::Error {
+    anyhow::anyhow!(
+        "Unknown agent '{}' (expected one of: {})",
+        agent_name,
+        MANAGED_AGENTS.join(", ")
+    )
+}
+
+fn npm_package_name(agent_name: &str) -> Result<&'static str> {
+    match agent_name {
+        "claude-code" => Ok("@zed-industries/claude-code-acp"),
+        "gemini" => Ok("@google/gemini-cli"),
+        other => Err(unknown_agent(other)),
+    }
+}
+
+// Directory under the data dir holding versioned installs
+fn local_dir_name(agent_name: &str) -> Result<&'static str> {
+    match agent_name {
+        "claude-code" => Ok("claude-code"),
+        "gemini" => Ok("gemini"),
+        other => Err(unknown_agent(other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn local_versions_are_sorted_and_removable_one_at_a_time() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let installer = AgentInstaller {
+            data_dir: data_dir.path().to_path_buf(),
+        };
+        assert!(installer.local_versions("gemini").await.unwrap().is_empty());
+
+        let gemini = data_dir.path().join("gemini");
+        for dir in ["0.10.0", "0.9.1", "not-a-version"] {
+            std::fs::create_dir_all(gemini.join(dir)).unwrap();
+        }
+        std::fs::write(gemini.join("1.0.0"), "a file, not an install").unwrap();
+
+        let versions = installer.local_versions("gemini").await.unwrap();
+        assert_eq!(
+            versions.iter().map(|v| v.to_string()).collect::<Vec<_>>(),
+            ["0.9.1", "0.10.0"]
+        );
+
+        let old = semver::Version::parse("0.9.1").unwrap();
+        let removed = installer.remove("gemini", Some(&old)).await.unwrap();
+        assert_eq!(removed, [gemini.join("0.9.1")]);
+        let removed = installer.remove("gemini", None).await.unwrap();
+        assert_eq!(removed, [gemini.join("0.10.0")]);
+        assert!(gemini.join("not-a-version").exists());
+
+        assert!(installer.local_versions("codex").await.is_err());
+    }
+}
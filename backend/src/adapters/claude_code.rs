@@ -1,37 +1,44 @@
+use agent_client_protocol as acp;
 use anyhow::{Context, Result};
 use async_trait::async_trait;
-use log::{debug, info, warn};
+use tracing::{debug, info, warn};
 use std::collections::HashMap;
+use std::path::PathBuf;
 
-use tokio::sync::mpsc;
 use tokio::time::Duration;
 
 use super::{
     agent_installer::{AgentCommand, AgentInstaller},
+    configured_login, merge_env,
     traits::{AgentAdapter, AgentCapabilities, AgentHealth},
 };
 use crate::acp::Session;
 use crate::acp::client::LoginCommand;
-use crate::acp::{AcpClient, SessionId};
-use crate::app::AppMessage;
+use crate::acp::{AcpClient, PendingTurn, SessionId};
+use crate::events::AppSender;
 use crate::config::agent::ClaudeCodeConfig;
+use crate::config::{LaunchProfile, PermissionsConfig};
 
 pub struct ClaudeCodeAdapter {
     name: String,
     config: ClaudeCodeConfig,
     client: Option<AcpClient>,
     sessions: HashMap<SessionId, Session>,
-    message_tx: mpsc::UnboundedSender<AppMessage>,
+    message_tx: AppSender,
     health: AgentHealth,
+    permission_policy: PermissionsConfig,
     last_health_check: Option<std::time::Instant>,
     installer: AgentInstaller,
     command: Option<AgentCommand>,
+    // Set when running as a launch profile
+    extra_env: HashMap<String, String>,
+    working_dir: Option<PathBuf>,
 }
 
 impl ClaudeCodeAdapter {
     pub async fn new(
         config: ClaudeCodeConfig,
-        message_tx: mpsc::UnboundedSender<AppMessage>,
+        message_tx: AppSender,
     ) -> Result<Self> {
         let installer = AgentInstaller::new().context("Failed to create agent installer")?;
 
@@ -42,20 +49,35 @@ impl ClaudeCodeAdapter {
             sessions: HashMap::new(),
             message_tx,
             health: AgentHealth::Disconnected,
+            permission_policy: PermissionsConfig::default(),
             last_health_check: None,
             installer,
             command: None,
+            extra_env: HashMap::new(),
+            working_dir: None,
         })
     }
 
+    /// Run as a launch profile: registered under the profile's name, with
+    /// its environment and working directory
+    pub fn with_launch_profile(mut self, profile: &LaunchProfile) -> Self {
+        self.name = profile.name.clone();
+        self.extra_env = profile.agent_env();
+        self.working_dir = profile.working_dir();
+        self
+    }
+
     async fn get_or_install_command(&mut self) -> Result<&AgentCommand> {
         if self.command.is_none() {
             info!("Getting or installing Claude Code agent...");
-            let command = self
-                .installer
-                .get_or_install_claude_code()
-                .await
-                .context("Failed to get or install Claude Code")?;
+            let command = match &self.config.command_path {
+                Some(path) => AgentCommand::new(path.clone()),
+                None => self
+                    .installer
+                    .get_or_install_claude_code()
+                    .await
+                    .context("Failed to get or install Claude Code")?,
+            };
 
             // Skip verification for now to avoid hanging
             // TODO: Implement non-blocking verification
@@ -131,25 +153,41 @@ impl AgentAdapter for ClaudeCodeAdapter {
         let name = self.name.clone();
         let message_tx = self.message_tx.clone();
 
-        // Prepare optional login command derived from installer (do this first to avoid borrow conflicts)
-        let login_cmd = match self.installer.get_claude_login_command().await {
-            Ok(cmd) => Some(LoginCommand { path: cmd.path, args: cmd.args }),
-            Err(_) => None,
+        // Prefer the configured login command, else derive one from the installer
+        // (do this first to avoid borrow conflicts)
+        let login_cmd = match configured_login(&self.config.login_command) {
+            Some(cmd) => Some(cmd),
+            None => match self.installer.get_claude_login_command().await {
+                Ok(cmd) => Some(LoginCommand { path: cmd.path, args: cmd.args }),
+                Err(_) => None,
+            },
         };
 
+        let mut extra_args = self.config.args.clone();
+        // Append args that enable file edits and tool usage
+        extra_args.extend(self.config.tool_args());
+        let mut extra_env = self.config.env.clone();
+        extra_env.extend(self.extra_env.clone());
+        let working_dir = self.working_dir.clone();
+
         // Get or install the command
         let command = self.get_or_install_command().await?;
+        let mut args = command.args.clone();
+        args.extend(extra_args);
+        let env = merge_env(command.env.clone(), extra_env);
 
         // Create and start ACP client (include args/env)
         let mut client = AcpClient::new(
             &name,
             command.path.to_str().unwrap(),
-            command.args.clone(),
-            command.env.clone(),
+            args,
+            env,
             message_tx,
             login_cmd,
         );
 
+        client.set_permission_policy(self.permission_policy.clone());
+        client.set_working_dir(working_dir);
         client.start().await.context("Failed to start ACP client")?;
 
         self.client = Some(client);
@@ -205,6 +243,73 @@ impl AgentAdapter for ClaudeCodeAdapter {
         Ok(())
     }
 
+    async fn send_prompt(
+        &mut self,
+        session_id: &SessionId,
+        prompt: Vec<acp::ContentBlock>,
+    ) -> Result<()> {
+        let client = self
+            .client
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Client not connected"))?;
+        client.send_prompt(session_id, prompt).await
+    }
+
+    async fn start_prompt(
+        &mut self,
+        session_id: &SessionId,
+        prompt: Vec<acp::ContentBlock>,
+    ) -> Result<PendingTurn> {
+        let client = self
+            .client
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Client not connected"))?;
+        client.start_prompt(session_id, prompt)
+    }
+
+    async fn load_session(&mut self, session_id: &SessionId) -> Result<()> {
+        let client = self
+            .client
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("Client not connected"))?;
+        client.load_session(session_id).await?;
+        self.sessions
+            .insert(session_id.clone(), Session::new(session_id.clone()));
+        Ok(())
+    }
+
+    async fn reattach_session(&mut self, session_id: &SessionId) -> Result<()> {
+        let client = self
+            .client
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("Client not connected"))?;
+        client.reattach_session(session_id).await?;
+        self.sessions
+            .insert(session_id.clone(), Session::new(session_id.clone()));
+        Ok(())
+    }
+
+    fn has_exited(&mut self) -> bool {
+        self.client
+            .as_mut()
+            .is_some_and(|client| client.has_exited())
+    }
+
+    fn cancel(&self, session_id: &SessionId) -> Result<()> {
+        let client = self
+            .client
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Client not connected"))?;
+        client.cancel(session_id)
+    }
+
+    fn stderr_tail(&self) -> Vec<String> {
+        self.client
+            .as_ref()
+            .map(|client| client.stderr_tail())
+            .unwrap_or_default()
+    }
+
     fn get_session_ids(&self) -> Vec<SessionId> {
         self.sessions.keys().cloned().collect()
     }
@@ -237,6 +342,10 @@ impl AgentAdapter for ClaudeCodeAdapter {
     fn capabilities(&self) -> AgentCapabilities {
         AgentCapabilities::claude_code()
     }
+
+    fn set_permission_policy(&mut self, policy: PermissionsConfig) {
+        self.permission_policy = policy;
+    }
 }
 
 impl Drop for ClaudeCodeAdapter {
@@ -1,12 +1,14 @@
+use agent_client_protocol as acp;
 use anyhow::{Context, Result};
 use async_trait::async_trait;
-use log::{info, warn};
+use tracing::info;
 use std::collections::HashMap;
-use tokio::sync::mpsc;
 
-use crate::acp::{AcpClient, Session, SessionId};
-use crate::app::AppMessage;
+use crate::acp::{AcpClient, PendingTurn, Session, SessionId};
+use crate::events::AppSender;
+use crate::config::PermissionsConfig;
 
+use super::configured_login;
 use super::traits::{AgentAdapter, AgentCapabilities, AgentHealth};
 
 #[derive(Debug, Clone)]
@@ -15,24 +17,28 @@ pub struct ExternalAgentSpec {
     pub path: String,
     pub args: Vec<String>,
     pub env: Option<HashMap<String, String>>,
+    /// Program and arguments run when the agent asks for authentication
+    pub login_command: Option<Vec<String>>,
 }
 
 pub struct ExternalCmdAdapter {
     spec: ExternalAgentSpec,
     client: Option<AcpClient>,
     sessions: HashMap<SessionId, Session>,
-    message_tx: mpsc::UnboundedSender<AppMessage>,
+    message_tx: AppSender,
     health: AgentHealth,
+    permission_policy: PermissionsConfig,
 }
 
 impl ExternalCmdAdapter {
-    pub fn new(spec: ExternalAgentSpec, message_tx: mpsc::UnboundedSender<AppMessage>) -> Self {
+    pub fn new(spec: ExternalAgentSpec, message_tx: AppSender) -> Self {
         Self {
             spec,
             client: None,
             sessions: HashMap::new(),
             message_tx,
             health: AgentHealth::Disconnected,
+            permission_policy: PermissionsConfig::default(),
         }
     }
 }
@@ -59,8 +65,9 @@ impl AgentAdapter for ExternalCmdAdapter {
             self.spec.args.clone(),
             self.spec.env.clone(),
             self.message_tx.clone(),
-            None,
+            configured_login(&self.spec.login_command),
         );
+        client.set_permission_policy(self.permission_policy.clone());
         client.start().await.context("Failed to start ACP client")?;
 
         self.client = Some(client);
@@ -96,6 +103,73 @@ impl AgentAdapter for ExternalCmdAdapter {
         client.send_message(session_id, content).await
     }
 
+    async fn send_prompt(
+        &mut self,
+        session_id: &SessionId,
+        prompt: Vec<acp::ContentBlock>,
+    ) -> Result<()> {
+        let client = self
+            .client
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Client not connected"))?;
+        client.send_prompt(session_id, prompt).await
+    }
+
+    async fn start_prompt(
+        &mut self,
+        session_id: &SessionId,
+        prompt: Vec<acp::ContentBlock>,
+    ) -> Result<PendingTurn> {
+        let client = self
+            .client
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Client not connected"))?;
+        client.start_prompt(session_id, prompt)
+    }
+
+    async fn load_session(&mut self, session_id: &SessionId) -> Result<()> {
+        let client = self
+            .client
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("Client not connected"))?;
+        client.load_session(session_id).await?;
+        self.sessions
+            .insert(session_id.clone(), Session::new(session_id.clone()));
+        Ok(())
+    }
+
+    async fn reattach_session(&mut self, session_id: &SessionId) -> Result<()> {
+        let client = self
+            .client
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("Client not connected"))?;
+        client.reattach_session(session_id).await?;
+        self.sessions
+            .insert(session_id.clone(), Session::new(session_id.clone()));
+        Ok(())
+    }
+
+    fn has_exited(&mut self) -> bool {
+        self.client
+            .as_mut()
+            .is_some_and(|client| client.has_exited())
+    }
+
+    fn cancel(&self, session_id: &SessionId) -> Result<()> {
+        let client = self
+            .client
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Client not connected"))?;
+        client.cancel(session_id)
+    }
+
+    fn stderr_tail(&self) -> Vec<String> {
+        self.client
+            .as_ref()
+            .map(|client| client.stderr_tail())
+            .unwrap_or_default()
+    }
+
     fn get_session_ids(&self) -> Vec<SessionId> {
         self.sessions.keys().cloned().collect()
     }
@@ -119,5 +193,9 @@ impl AgentAdapter for ExternalCmdAdapter {
     fn capabilities(&self) -> AgentCapabilities {
         AgentCapabilities::default()
     }
+
+    fn set_permission_policy(&mut self, policy: PermissionsConfig) {
+        self.permission_policy = policy;
+    }
 }
 
@@ -1,7 +1,10 @@
 use anyhow::{Context, Result};
-use log::{debug, error, info, warn};
-use std::collections::HashMap;
-use tokio::sync::mpsc;
+use tracing::{debug, error, info, warn};
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::rc::Rc;
+use std::time::Instant;
+use tokio::sync::{Mutex, MutexGuard};
 use tokio::time::{timeout, Duration as TokioDuration};
 
 use super::{
@@ -10,40 +13,113 @@ use super::{
     external::{ExternalAgentSpec, ExternalCmdAdapter},
     AgentAdapter,
 };
-use crate::acp::{Message, SessionId};
-use crate::app::AppMessage;
-use crate::config::AgentConfig;
+use crate::acp::{
+    AgentError, AgentErrorKind, Message, MessageContent, ProjectInstructions, Session, SessionId,
+};
+use crate::events::{AppMessage, AppSender};
+use crate::config::{AgentConfig, LaunchProfile, PermissionsConfig};
+
+/// An adapter behind its own lock: commands for one agent queue up in
+/// order while other agents carry on. Prompt turns only hold it while the
+/// prompt is handed over, not until the turn ends.
+type SharedAdapter = Rc<Mutex<Box<dyn AgentAdapter>>>;
+
+fn share(adapter: Box<dyn AgentAdapter>) -> SharedAdapter {
+    Rc::new(Mutex::new(adapter))
+}
+
+/// Prompts for one session run one after another, in the order sent
+#[derive(Default)]
+struct SessionTurns {
+    running: Mutex<()>,
+    /// Prompts waiting for the running turn to end
+    queued: Cell<usize>,
+}
+
+/// Counts a prompt as queued until it starts, or is given up on
+struct Queued<'a>(&'a Cell<usize>);
+
+impl<'a> Queued<'a> {
+    fn new(count: &'a Cell<usize>) -> Self {
+        count.set(count.get() + 1);
+        Self(count)
+    }
+}
+
+impl Drop for Queued<'_> {
+    fn drop(&mut self) {
+        self.0.set(self.0.get() - 1);
+    }
+}
+
+/// Where a session's prompts stand
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TurnState {
+    Idle,
+    /// A turn is running with `queued` more prompts waiting behind it
+    Running { queued: usize },
+}
 
+/// Agents and their sessions. Every method takes `&self`, so one manager can
+/// be shared (e.g. in an `Rc`) by tasks on a `LocalSet` working on different
+/// agents and sessions at once.
 pub struct AgentManager {
     config: AgentConfig,
-    agents: HashMap<String, Box<dyn AgentAdapter>>,
-    message_tx: mpsc::UnboundedSender<AppMessage>,
+    agents: RefCell<HashMap<String, SharedAdapter>>,
+    message_tx: AppSender,
+    permission_policy: RefCell<PermissionsConfig>,
+    /// Agents that were busy when the policy last changed; they get it the
+    /// next time they are locked
+    stale_policy: RefCell<HashSet<String>>,
+    /// Launch profiles by name; their adapters are created on first use
+    launch_profiles: RefCell<HashMap<String, LaunchProfile>>,
+    /// Project instruction file names (`project.instruction_files`)
+    instruction_files: RefCell<Vec<String>>,
+    /// New sessions whose first prompt still needs the project instructions
+    pending_instructions: RefCell<HashMap<SessionId, ProjectInstructions>>,
+    turns: RefCell<HashMap<SessionId, Rc<SessionTurns>>>,
+    /// Sessions a restarted agent could not reload, and the sessions that
+    /// took their place
+    replaced: RefCell<HashMap<SessionId, SessionId>>,
+    /// When each agent recently crashed, oldest first
+    crashes: RefCell<HashMap<String, VecDeque<Instant>>>,
+    /// Agents that crashed too often and stay stopped until retried
+    failed: RefCell<HashSet<String>>,
 }
 
 impl AgentManager {
     pub async fn new(
         config: AgentConfig,
-        message_tx: mpsc::UnboundedSender<AppMessage>,
+        message_tx: AppSender,
         external: Option<ExternalAgentSpec>,
     ) -> Result<Self> {
-        let mut manager = Self {
+        let manager = Self {
             config,
-            agents: HashMap::new(),
+            agents: RefCell::new(HashMap::new()),
             message_tx,
+            permission_policy: RefCell::new(PermissionsConfig::default()),
+            stale_policy: RefCell::new(HashSet::new()),
+            launch_profiles: RefCell::new(HashMap::new()),
+            instruction_files: RefCell::new(Vec::new()),
+            pending_instructions: RefCell::new(HashMap::new()),
+            turns: RefCell::new(HashMap::new()),
+            replaced: RefCell::new(HashMap::new()),
+            crashes: RefCell::new(HashMap::new()),
+            failed: RefCell::new(HashSet::new()),
         };
 
         manager.initialize_agents(external).await?;
         Ok(manager)
     }
 
-    async fn initialize_agents(&mut self, external: Option<ExternalAgentSpec>) -> Result<()> {
+    async fn initialize_agents(&self, external: Option<ExternalAgentSpec>) -> Result<()> {
         info!("Initializing agent adapters");
 
         // Register external adapter if provided
         if let Some(spec) = external {
             let name = spec.name.clone();
             let adapter = ExternalCmdAdapter::new(spec, self.message_tx.clone());
-            self.agents.insert(name, Box::new(adapter));
+            self.agents.borrow_mut().insert(name, share(Box::new(adapter)));
         }
 
         // Initialize Claude Code adapter if enabled
@@ -51,7 +127,9 @@ impl AgentManager {
             match self.create_claude_code_adapter().await {
                 Ok(adapter) => {
                     info!("Claude Code adapter initialized");
-                    self.agents.insert("claude-code".to_string(), adapter);
+                    self.agents
+                        .borrow_mut()
+                        .insert("claude-code".to_string(), share(adapter));
                 }
                 Err(e) => {
                     warn!("Failed to initialize Claude Code adapter: {}", e);
@@ -71,7 +149,9 @@ impl AgentManager {
             match self.create_gemini_adapter().await {
                 Ok(adapter) => {
                     info!("Gemini adapter initialized");
-                    self.agents.insert("gemini".to_string(), adapter);
+                    self.agents
+                        .borrow_mut()
+                        .insert("gemini".to_string(), share(adapter));
                 }
                 Err(e) => {
                     warn!("Failed to initialize Gemini adapter: {}", e);
@@ -100,21 +180,108 @@ impl AgentManager {
         Ok(Box::new(adapter))
     }
 
-    pub async fn connect_agent(&mut self, agent_name: &str) -> Result<()> {
+    /// The adapter for `agent_name`
+    fn agent(&self, agent_name: &str) -> Result<SharedAdapter> {
+        self.agents
+            .borrow()
+            .get(agent_name)
+            .cloned()
+            .ok_or_else(|| {
+                AgentError::with_kind(
+                    AgentErrorKind::Unavailable,
+                    format!("Agent '{}' not found", agent_name),
+                )
+            })
+    }
+
+    /// Lock an agent's adapter, first handing it a permission policy it
+    /// missed while it was busy
+    async fn lock<'a>(
+        &self,
+        agent_name: &str,
+        agent: &'a SharedAdapter,
+    ) -> MutexGuard<'a, Box<dyn AgentAdapter>> {
+        let mut agent = agent.lock().await;
+        self.catch_up_policy(agent_name, agent.as_mut());
+        agent
+    }
+
+    fn catch_up_policy(&self, agent_name: &str, agent: &mut dyn AgentAdapter) {
+        if self.stale_policy.borrow_mut().remove(agent_name) {
+            agent.set_permission_policy(self.permission_policy.borrow().clone());
+        }
+    }
+
+    /// Start the agent with the current permission policy
+    async fn start_agent(&self, agent: &mut dyn AgentAdapter) -> Result<()> {
+        agent.set_permission_policy(self.permission_policy.borrow().clone());
+        agent.start().await
+    }
+
+    /// Register the adapter for a launch profile the first time it is used
+    async fn ensure_agent(&self, agent_name: &str) -> Result<()> {
+        if self.agents.borrow().contains_key(agent_name) {
+            return Ok(());
+        }
+        let Some(profile) = self.launch_profiles.borrow().get(agent_name).cloned() else {
+            return Ok(());
+        };
+        let mut adapter: Box<dyn AgentAdapter> = match profile.agent.as_str() {
+            "gemini" => Box::new(
+                GeminiAdapter::new(self.config.gemini.clone(), self.message_tx.clone())
+                    .await?
+                    .with_launch_profile(&profile),
+            ),
+            _ => Box::new(
+                ClaudeCodeAdapter::new(self.config.claude_code.clone(), self.message_tx.clone())
+                    .await?
+                    .with_launch_profile(&profile),
+            ),
+        };
+        // Another command may have registered it while this one was building
+        adapter.set_permission_policy(self.permission_policy.borrow().clone());
+        self.agents
+            .borrow_mut()
+            .entry(profile.name.clone())
+            .or_insert_with(|| share(adapter));
+        info!("Registered launch profile '{}' ({})", profile.name, profile.agent);
+        Ok(())
+    }
+
+    /// Make launch profiles available as agents. A profile whose settings
+    /// changed is rebuilt on next use, unless it is currently running.
+    pub fn set_launch_profiles(&self, profiles: Vec<LaunchProfile>) {
+        for profile in &profiles {
+            let changed = self
+                .launch_profiles
+                .borrow()
+                .get(&profile.name)
+                .is_some_and(|old| old != profile);
+            if changed && !self.is_agent_connected(&profile.name)
+            {
+                self.agents.borrow_mut().remove(&profile.name);
+            }
+        }
+        *self.launch_profiles.borrow_mut() = profiles
+            .into_iter()
+            .map(|profile| (profile.name.clone(), profile))
+            .collect();
+    }
+
+    pub async fn connect_agent(&self, agent_name: &str) -> Result<()> {
         info!("Connecting to agent: {}", agent_name);
+        self.ensure_not_failed(agent_name)?;
+        self.ensure_agent(agent_name).await?;
 
-        let agent = self
-            .agents
-            .get_mut(agent_name)
-            .ok_or_else(|| anyhow::anyhow!("Agent '{}' not found", agent_name))?;
+        let agent = self.agent(agent_name)?;
+        let mut agent = self.lock(agent_name, &agent).await;
 
         if agent.is_connected() {
             debug!("Agent '{}' is already connected", agent_name);
             return Ok(());
         }
 
-        agent
-            .start()
+        self.start_agent(agent.as_mut())
             .await
             .with_context(|| format!("Failed to start agent '{}'", agent_name))?;
 
@@ -126,13 +293,11 @@ impl AgentManager {
         Ok(())
     }
 
-    pub async fn disconnect_agent(&mut self, agent_name: &str) -> Result<()> {
+    pub async fn disconnect_agent(&self, agent_name: &str) -> Result<()> {
         info!("Disconnecting from agent: {}", agent_name);
 
-        let agent = self
-            .agents
-            .get_mut(agent_name)
-            .ok_or_else(|| anyhow::anyhow!("Agent '{}' not found", agent_name))?;
+        let agent = self.agent(agent_name)?;
+        let mut agent = self.lock(agent_name, &agent).await;
 
         agent
             .stop()
@@ -147,10 +312,10 @@ impl AgentManager {
         Ok(())
     }
 
-    pub async fn disconnect_all(&mut self) -> Result<()> {
+    pub async fn disconnect_all(&self) -> Result<()> {
         info!("Disconnecting all agents");
 
-        let agent_names: Vec<String> = self.agents.keys().cloned().collect();
+        let agent_names = self.get_agent_names();
         for agent_name in agent_names {
             if let Err(e) = self.disconnect_agent(&agent_name).await {
                 error!("Failed to disconnect agent '{}': {}", agent_name, e);
@@ -160,20 +325,20 @@ impl AgentManager {
         Ok(())
     }
 
-    pub async fn create_session(&mut self, agent_name: &str) -> Result<SessionId> {
+    pub async fn create_session(&self, agent_name: &str) -> Result<SessionId> {
         debug!("Creating session for agent: {}", agent_name);
+        self.ensure_not_failed(agent_name)?;
+        self.ensure_agent(agent_name).await?;
 
-        let agent = self
-            .agents
-            .get_mut(agent_name)
-            .ok_or_else(|| anyhow::anyhow!("Agent '{}' not found", agent_name))?;
+        let agent = self.agent(agent_name)?;
+        let mut agent = self.lock(agent_name, &agent).await;
 
         if !agent.is_connected() {
             info!("Agent '{}' not connected; attempting to connect...", agent_name);
-            if let Err(e) = agent.start().await {
+            if let Err(e) = self.start_agent(agent.as_mut()).await {
                 let msg = format!("Failed to start agent '{}': {}", agent_name, e);
                 let _ = self.message_tx.send(AppMessage::Error { error: msg.clone() });
-                return Err(anyhow::anyhow!(msg));
+                return Err(AgentError::with_kind(AgentErrorKind::Unavailable, msg));
             }
             let _ = self.message_tx.send(AppMessage::AgentConnected {
                 agent_name: agent_name.to_string(),
@@ -183,10 +348,12 @@ impl AgentManager {
         let timeout_secs = self.config.connection_timeout_seconds.max(1);
         match timeout(TokioDuration::from_secs(timeout_secs), agent.create_session()).await {
             Ok(Ok(session_id)) => {
+                drop(agent);
                 let _ = self.message_tx.send(AppMessage::SessionCreated {
                     agent_name: agent_name.to_string(),
                     session_id: session_id.clone(),
                 });
+                self.attach_instructions(agent_name, &session_id);
 
                 info!("Created session {} for agent {}", session_id.0, agent_name);
                 Ok(session_id)
@@ -199,7 +366,8 @@ impl AgentManager {
                 let _ = self.message_tx.send(AppMessage::Error {
                     error: error_msg.clone(),
                 });
-                Err(anyhow::anyhow!(error_msg))
+                let kind = AgentError::kind_of(&e).unwrap_or(AgentErrorKind::Protocol);
+                Err(AgentError::with_kind(kind, error_msg))
             }
             Err(_) => {
                 let error_msg = format!(
@@ -207,13 +375,129 @@ impl AgentManager {
                     agent_name, timeout_secs
                 );
                 let _ = self.message_tx.send(AppMessage::Error { error: error_msg.clone() });
-                Err(anyhow::anyhow!(error_msg))
+                Err(AgentError::with_kind(AgentErrorKind::Timeout, error_msg))
             }
         }
     }
 
+    /// Queue the project instructions for a new session's first prompt
+    fn attach_instructions(&self, agent_name: &str, session_id: &SessionId) {
+        let files = self.instruction_files.borrow().clone();
+        if files.is_empty() {
+            return;
+        }
+        let dir = self
+            .launch_profiles
+            .borrow()
+            .get(agent_name)
+            .and_then(|profile| profile.working_dir())
+            .or_else(|| std::env::current_dir().ok());
+        let Some(instructions) = dir.and_then(|dir| ProjectInstructions::find(&dir, &files))
+        else {
+            return;
+        };
+        info!(
+            "Project instructions {:?} queued for session {}",
+            instructions.path, session_id.0
+        );
+        let _ = self.message_tx.send(AppMessage::InstructionsAttached {
+            agent_name: agent_name.to_string(),
+            session_id: session_id.clone(),
+            path: instructions.path.clone(),
+        });
+        self.pending_instructions
+            .borrow_mut()
+            .insert(session_id.clone(), instructions);
+    }
+
+    /// Files to look for when sending project instructions to new sessions;
+    /// empty disables them
+    pub fn set_instruction_files(&self, files: Vec<String>) {
+        *self.instruction_files.borrow_mut() = files;
+    }
+
+    /// Reopen a stored session. Agents that support `session/load` replay the
+    /// conversation themselves; otherwise a fresh session is created and the
+    /// stored transcript is replayed into it for reference.
+    pub async fn resume_session(&self, stored: Session) -> Result<SessionId> {
+        let stored_id = stored.id.clone();
+        let result = self.resume_session_inner(stored).await;
+        if let Err(e) = &result {
+            let _ = self.message_tx.send(AppMessage::Error {
+                error: format!("Failed to resume session {}: {}", stored_id, e),
+            });
+        }
+        result
+    }
+
+    async fn resume_session_inner(&self, stored: Session) -> Result<SessionId> {
+        let agent_name = stored
+            .agent_name
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("Stored session {} has no agent", stored.id))?;
+        self.connect_agent(&agent_name).await?;
+
+        // Announce first so replayed updates have somewhere to land
+        let _ = self.message_tx.send(AppMessage::SessionCreated {
+            agent_name: agent_name.clone(),
+            session_id: stored.id.clone(),
+        });
+
+        let timeout_secs = self.config.connection_timeout_seconds.max(1);
+        let agent = self.agent(&agent_name)?;
+        let mut agent = self.lock(&agent_name, &agent).await;
+        match timeout(TokioDuration::from_secs(timeout_secs), agent.load_session(&stored.id)).await {
+            Ok(Ok(())) => {
+                info!("Resumed session {} on agent {}", stored.id, agent_name);
+                return Ok(stored.id);
+            }
+            Ok(Err(e)) => info!("Agent could not load session {}: {}", stored.id, e),
+            Err(_) => warn!("Timed out loading session {}", stored.id),
+        }
+
+        let session_id = match timeout(TokioDuration::from_secs(timeout_secs), agent.create_session()).await {
+            Ok(result) => result?,
+            Err(_) => {
+                return Err(anyhow::anyhow!(
+                    "Timed out creating session for agent '{}' after {}s",
+                    agent_name,
+                    timeout_secs
+                ))
+            }
+        };
+        drop(agent);
+        let _ = self.message_tx.send(AppMessage::SessionReplaced {
+            agent_name: agent_name.clone(),
+            old_session_id: stored.id.clone(),
+            new_session_id: session_id.clone(),
+        });
+
+        let replayed = stored.messages.len();
+        for mut message in stored.messages {
+            message.session_id = session_id.clone();
+            let _ = self.message_tx.send(AppMessage::AgentMessage {
+                agent_name: agent_name.clone(),
+                message,
+            });
+        }
+        let _ = self.message_tx.send(AppMessage::AgentMessage {
+            agent_name: agent_name.clone(),
+            message: Message::new(
+                session_id.clone(),
+                MessageContent::SessionStatus {
+                    status: format!(
+                        "Replayed {} messages from session {}; the agent starts with a fresh context",
+                        replayed, stored.id
+                    ),
+                },
+            ),
+        });
+
+        Ok(session_id)
+    }
+
     pub async fn send_message(
-        &mut self,
+        &self,
         agent_name: &str,
         session_id: &SessionId,
         content: String,
@@ -222,23 +506,283 @@ impl AgentManager {
             "Sending message to agent '{}' session '{}'",
             agent_name, session_id.0
         );
+        let prompt = vec![agent_client_protocol::ContentBlock::Text(
+            agent_client_protocol::TextContent {
+                text: content,
+                annotations: Default::default(),
+            },
+        )];
+        self.send_prompt(agent_name, session_id, prompt).await
+    }
 
-        let agent = self
-            .agents
-            .get_mut(agent_name)
-            .ok_or_else(|| anyhow::anyhow!("Agent '{}' not found", agent_name))?;
+    /// Send a prompt and wait for its turn to end. A prompt for a session
+    /// that is still in a turn waits for that turn first; other sessions and
+    /// agents are not held up.
+    pub async fn send_prompt(
+        &self,
+        agent_name: &str,
+        session_id: &SessionId,
+        mut prompt: Vec<agent_client_protocol::ContentBlock>,
+    ) -> Result<()> {
+        let turns = self.session_turns(session_id);
+        let _running = {
+            let _queued = Queued::new(&turns.queued);
+            turns.running.lock().await
+        };
+
+        let instructions = self.pending_instructions.borrow_mut().remove(session_id);
+        if let Some(instructions) = instructions {
+            prompt.insert(0, instructions.to_block());
+        }
+        debug!(
+            "Sending {}-block prompt to agent '{}' session '{}'",
+            prompt.len(),
+            agent_name,
+            session_id.0
+        );
+
+        // The agent is only locked while the prompt is handed over. Prompts
+        // queued behind a turn the agent died in go to the restarted agent.
+        let turn = {
+            let agent = self.agent(agent_name)?;
+            let mut agent = self.lock(agent_name, &agent).await;
+            self.ensure_not_failed(agent_name)?;
+            if agent.has_exited() {
+                self.recover_agent(agent_name, agent.as_mut()).await?;
+            }
+            let session_id = self.current_session(session_id);
+            agent.start_prompt(&session_id, prompt).await
+        }
+        .with_context(|| format!("Failed to send prompt to agent '{}'", agent_name))?;
+        turn.wait()
+            .await
+            .with_context(|| format!("Failed to send prompt to agent '{}'", agent_name))?;
+
+        Ok(())
+    }
 
+    /// Ask the agent to stop the turn running in a session. The pending
+    /// `send_prompt` for it then resolves with a `Cancelled` stop reason;
+    /// prompts queued behind it still run.
+    pub async fn cancel_turn(&self, agent_name: &str, session_id: &SessionId) -> Result<()> {
+        let agent = self.agent(agent_name)?;
+        let agent = self.lock(agent_name, &agent).await;
         agent
-            .send_message(session_id, content)
+            .cancel(&self.current_session(session_id))
+            .with_context(|| format!("Failed to cancel turn of agent '{}'", agent_name))
+    }
+
+    /// Restart an agent that exited on its own and bring its sessions back:
+    /// reloaded where the agent supports it, otherwise replaced by new ones
+    /// with a fresh context.
+    async fn recover_agent(&self, agent_name: &str, agent: &mut dyn AgentAdapter) -> Result<()> {
+        if self.crashed_too_often(agent_name) {
+            let stderr = agent.stderr_tail();
+            if let Err(e) = agent.stop().await {
+                debug!("Cleaning up exited agent '{}': {}", agent_name, e);
+            }
+            let reason = format!(
+                "crashed {} times within {}s",
+                self.config.max_restarts + 1,
+                self.config.restart_window_seconds
+            );
+            error!("Agent '{}' {}; not restarting it", agent_name, reason);
+            self.failed.borrow_mut().insert(agent_name.to_string());
+            let _ = self.message_tx.send(AppMessage::AgentFailed {
+                agent_name: agent_name.to_string(),
+                reason: reason.clone(),
+                stderr,
+            });
+            return Err(AgentError::with_kind(
+                AgentErrorKind::Unavailable,
+                format!("Agent '{}' {}", agent_name, reason),
+            ));
+        }
+
+        let sessions = agent.get_session_ids();
+        warn!(
+            "Agent '{}' exited; restarting it for {} session(s)",
+            agent_name,
+            sessions.len()
+        );
+        crate::telemetry::agents_restarted(1);
+        if let Err(e) = agent.stop().await {
+            debug!("Cleaning up exited agent '{}': {}", agent_name, e);
+        }
+        if let Err(e) = self.start_agent(agent).await {
+            let _ = self.message_tx.send(AppMessage::AgentDisconnected {
+                agent_name: agent_name.to_string(),
+            });
+            return Err(e).with_context(|| format!("Failed to restart agent '{}'", agent_name));
+        }
+
+        let timeout_secs = self.config.connection_timeout_seconds.max(1);
+        for session_id in sessions {
+            match timeout(
+                TokioDuration::from_secs(timeout_secs),
+                agent.reattach_session(&session_id),
+            )
             .await
-            .with_context(|| format!("Failed to send message to agent '{}'", agent_name))?;
+            {
+                Ok(Ok(())) => {
+                    info!("Reloaded session {} on restarted agent {}", session_id, agent_name);
+                    let _ = self.message_tx.send(AppMessage::SessionRecovered {
+                        agent_name: agent_name.to_string(),
+                        session_id,
+                        reloaded: true,
+                    });
+                    continue;
+                }
+                Ok(Err(e)) => info!("Agent could not reload session {}: {}", session_id, e),
+                Err(_) => warn!("Timed out reloading session {}", session_id),
+            }
+
+            let new_session_id =
+                match timeout(TokioDuration::from_secs(timeout_secs), agent.create_session()).await {
+                    Ok(Ok(new_session_id)) => new_session_id,
+                    Ok(Err(e)) => {
+                        warn!("Failed to replace session {}: {}", session_id, e);
+                        continue;
+                    }
+                    Err(_) => {
+                        warn!("Timed out replacing session {}", session_id);
+                        continue;
+                    }
+                };
+            self.session_moved(&session_id, &new_session_id);
+            let _ = self.message_tx.send(AppMessage::SessionReplaced {
+                agent_name: agent_name.to_string(),
+                old_session_id: session_id,
+                new_session_id: new_session_id.clone(),
+            });
+            let _ = self.message_tx.send(AppMessage::SessionRecovered {
+                agent_name: agent_name.to_string(),
+                session_id: new_session_id,
+                reloaded: false,
+            });
+        }
+
+        let _ = self.message_tx.send(AppMessage::AgentConnected {
+            agent_name: agent_name.to_string(),
+        });
+        Ok(())
+    }
 
+    /// Record a crash and tell whether the agent has now crashed more than
+    /// `max_restarts` times within the restart window
+    fn crashed_too_often(&self, agent_name: &str) -> bool {
+        let window = TokioDuration::from_secs(self.config.restart_window_seconds);
+        let now = Instant::now();
+        let mut crashes = self.crashes.borrow_mut();
+        let crashes = crashes.entry(agent_name.to_string()).or_default();
+        crashes.push_back(now);
+        while crashes
+            .front()
+            .is_some_and(|crashed| now.duration_since(*crashed) > window)
+        {
+            crashes.pop_front();
+        }
+        crashes.len() > self.config.max_restarts as usize
+    }
+
+    fn ensure_not_failed(&self, agent_name: &str) -> Result<()> {
+        if self.is_agent_failed(agent_name) {
+            return Err(AgentError::with_kind(
+                AgentErrorKind::Unavailable,
+                format!(
+                    "Agent '{}' keeps crashing and was stopped; retry it from the agent list",
+                    agent_name
+                ),
+            ));
+        }
         Ok(())
     }
 
-    pub async fn tick(&mut self) -> Result<()> {
-        // Update all agents
-        for (agent_name, agent) in &mut self.agents {
+    /// Whether the agent crashed too often and waits for a retry
+    pub fn is_agent_failed(&self, agent_name: &str) -> bool {
+        self.failed.borrow().contains(agent_name)
+    }
+
+    /// Let a failed agent be started again, with a clean crash history
+    pub fn retry_agent(&self, agent_name: &str) {
+        if self.failed.borrow_mut().remove(agent_name) {
+            info!("Retrying agent '{}'", agent_name);
+        }
+        self.crashes.borrow_mut().remove(agent_name);
+    }
+
+    /// Carry a session's queued prompts and instructions over to the
+    /// session that replaced it
+    fn session_moved(&self, old: &SessionId, new: &SessionId) {
+        let turns = self.session_turns(old);
+        self.turns.borrow_mut().insert(new.clone(), turns);
+        let instructions = self.pending_instructions.borrow_mut().remove(old);
+        if let Some(instructions) = instructions {
+            self.pending_instructions
+                .borrow_mut()
+                .insert(new.clone(), instructions);
+        }
+        let mut replaced = self.replaced.borrow_mut();
+        // Earlier sessions replaced by `old` now lead to `new` as well
+        for target in replaced.values_mut() {
+            if target == old {
+                *target = new.clone();
+            }
+        }
+        replaced.insert(old.clone(), new.clone());
+    }
+
+    /// The session that now stands for `session_id`
+    fn current_session(&self, session_id: &SessionId) -> SessionId {
+        self.replaced
+            .borrow()
+            .get(session_id)
+            .cloned()
+            .unwrap_or_else(|| session_id.clone())
+    }
+
+    fn session_turns(&self, session_id: &SessionId) -> Rc<SessionTurns> {
+        self.turns
+            .borrow_mut()
+            .entry(session_id.clone())
+            .or_default()
+            .clone()
+    }
+
+    pub fn turn_state(&self, session_id: &SessionId) -> TurnState {
+        match self.turns.borrow().get(session_id) {
+            Some(turns) if turns.running.try_lock().is_err() => TurnState::Running {
+                queued: turns.queued.get(),
+            },
+            _ => TurnState::Idle,
+        }
+    }
+
+    pub async fn tick(&self) -> Result<()> {
+        // Update all agents; one busy with a command is skipped this time
+        let agents: Vec<(String, SharedAdapter)> = self
+            .agents
+            .borrow()
+            .iter()
+            .map(|(name, agent)| (name.clone(), agent.clone()))
+            .collect();
+        for (agent_name, agent) in agents {
+            let Ok(mut agent) = agent.try_lock() else {
+                continue;
+            };
+            self.catch_up_policy(&agent_name, agent.as_mut());
+            if agent.has_exited() && !self.is_agent_failed(&agent_name) {
+                if let Err(e) = self.recover_agent(&agent_name, agent.as_mut()).await {
+                    error!("{:#}", e);
+                    // A failed agent is reported by `AgentFailed`
+                    if !self.is_agent_failed(&agent_name) {
+                        let _ = self.message_tx.send(AppMessage::Error {
+                            error: format!("{:#}", e),
+                        });
+                    }
+                }
+                continue;
+            }
             if let Err(e) = agent.tick().await {
                 warn!("Agent '{}' tick error: {}", agent_name, e);
             }
@@ -247,36 +791,50 @@ impl AgentManager {
         Ok(())
     }
 
+    /// `f` applied to each agent not busy with a command
+    fn peek<T>(&self, f: impl Fn(&dyn AgentAdapter) -> T) -> Vec<(String, T)> {
+        self.agents
+            .borrow()
+            .iter()
+            .filter_map(|(name, agent)| {
+                let agent = agent.try_lock().ok()?;
+                Some((name.clone(), f(agent.as_ref())))
+            })
+            .collect()
+    }
+
     pub fn get_agent_names(&self) -> Vec<String> {
-        self.agents.keys().cloned().collect()
+        self.agents.borrow().keys().cloned().collect()
     }
 
+    /// An agent busy with a command counts as connected
     pub fn get_connected_agents(&self) -> Vec<String> {
-        self.agents
-            .iter()
-            .filter(|(_, agent)| agent.is_connected())
-            .map(|(name, _)| name.clone())
+        self.get_agent_names()
+            .into_iter()
+            .filter(|name| self.is_agent_connected(name))
             .collect()
     }
 
     pub fn get_active_sessions(&self) -> HashMap<String, Vec<SessionId>> {
-        self.agents
-            .iter()
-            .map(|(name, agent)| (name.clone(), agent.get_session_ids()))
+        self.peek(|agent| agent.get_session_ids())
+            .into_iter()
             .collect()
     }
 
+    /// An agent busy with a command counts as connected
     pub fn is_agent_connected(&self, agent_name: &str) -> bool {
         self.agents
+            .borrow()
             .get(agent_name)
-            .map(|agent| agent.is_connected())
+            .map(|agent| agent.try_lock().map_or(true, |agent| agent.is_connected()))
             .unwrap_or(false)
     }
 
     pub fn get_agent_health(&self, agent_name: &str) -> Option<super::traits::AgentHealth> {
         self.agents
+            .borrow()
             .get(agent_name)
-            .map(|agent| agent.health_status())
+            .and_then(|agent| agent.try_lock().ok().map(|agent| agent.health_status()))
     }
 
     pub fn get_agent_capabilities(
@@ -284,36 +842,55 @@ impl AgentManager {
         agent_name: &str,
     ) -> Option<super::traits::AgentCapabilities> {
         self.agents
+            .borrow()
             .get(agent_name)
-            .map(|agent| agent.capabilities())
+            .and_then(|agent| agent.try_lock().ok().map(|agent| agent.capabilities()))
     }
 
     pub fn get_all_agent_health(&self) -> HashMap<String, super::traits::AgentHealth> {
-        self.agents
-            .iter()
-            .map(|(name, agent)| (name.clone(), agent.health_status()))
+        self.peek(|agent| agent.health_status())
+            .into_iter()
             .collect()
     }
 
     #[cfg(test)]
     pub fn insert_agent_for_test(
-        &mut self,
+        &self,
         name: String,
         adapter: Box<dyn AgentAdapter>,
     ) {
-        self.agents.insert(name, adapter);
+        self.agents.borrow_mut().insert(name, share(adapter));
+    }
+
+    pub fn register_agent(&self, name: String, mut adapter: Box<dyn AgentAdapter>) {
+        adapter.set_permission_policy(self.permission_policy.borrow().clone());
+        self.agents.borrow_mut().insert(name, share(adapter));
     }
 
-    pub fn register_agent(&mut self, name: String, adapter: Box<dyn AgentAdapter>) {
-        self.agents.insert(name, adapter);
+    /// Apply a permission policy to all current and future agents. Agents
+    /// busy with a command get it as soon as the manager locks them again.
+    pub fn set_permission_policy(&self, policy: PermissionsConfig) {
+        let mut stale = self.stale_policy.borrow_mut();
+        for (name, agent) in self.agents.borrow().iter() {
+            match agent.try_lock() {
+                Ok(mut agent) => {
+                    agent.set_permission_policy(policy.clone());
+                    stale.remove(name);
+                }
+                Err(_) => {
+                    stale.insert(name.clone());
+                }
+            }
+        }
+        *self.permission_policy.borrow_mut() = policy;
     }
 
-    pub async fn auto_connect_agents(&mut self) -> Result<()> {
+    pub async fn auto_connect_agents(&self) -> Result<()> {
         info!("Auto-connecting configured agents");
 
         let auto_connect_list = self.config.auto_connect.clone();
         for agent_name in auto_connect_list {
-            if self.agents.contains_key(&agent_name) {
+            if self.agents.borrow().contains_key(&agent_name) {
                 if let Err(e) = self.connect_agent(&agent_name).await {
                     warn!("Failed to auto-connect agent '{}': {}", agent_name, e);
                     let _ = self.message_tx.send(AppMessage::Error {
@@ -329,9 +906,9 @@ impl AgentManager {
     }
 
     pub fn get_session_count(&self) -> usize {
-        self.agents
-            .values()
-            .map(|agent| agent.get_session_ids().len())
+        self.peek(|agent| agent.get_session_ids().len())
+            .into_iter()
+            .map(|(_, count)| count)
             .sum()
     }
 
@@ -340,10 +917,7 @@ impl AgentManager {
     }
 
     pub fn get_connected_count(&self) -> usize {
-        self.agents
-            .values()
-            .filter(|agent| agent.is_connected())
-            .count()
+        self.get_connected_agents().len()
     }
 
     pub fn can_connect_more_agents(&self) -> bool {
@@ -358,3 +932,252 @@ impl AgentManager {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::acp::PendingTurn;
+    use crate::adapters::traits::{AgentCapabilities, AgentHealth};
+    use async_trait::async_trait;
+    use std::sync::Arc;
+    use tokio::sync::Semaphore;
+
+    /// Turns end only once the test releases them through `gate`
+    struct GatedAdapter {
+        name: String,
+        sessions: HashMap<SessionId, Session>,
+        gate: Arc<Semaphore>,
+        created: usize,
+        /// Set to simulate the agent process dying
+        exited: Rc<Cell<bool>>,
+        prompted: Rc<RefCell<Vec<SessionId>>>,
+        policy: Rc<RefCell<PermissionsConfig>>,
+    }
+
+    impl GatedAdapter {
+        fn new(name: &str, gate: Arc<Semaphore>) -> Self {
+            Self {
+                name: name.to_string(),
+                sessions: HashMap::new(),
+                gate,
+                created: 0,
+                exited: Rc::default(),
+                prompted: Rc::default(),
+                policy: Rc::default(),
+            }
+        }
+    }
+
+    #[async_trait(?Send)]
+    impl AgentAdapter for GatedAdapter {
+        fn name(&self) -> &str {
+            &self.name
+        }
+        fn is_connected(&self) -> bool {
+            true
+        }
+        async fn start(&mut self) -> Result<()> {
+            self.exited.set(false);
+            Ok(())
+        }
+        async fn stop(&mut self) -> Result<()> {
+            self.sessions.clear();
+            Ok(())
+        }
+        fn has_exited(&mut self) -> bool {
+            self.exited.get()
+        }
+        async fn create_session(&mut self) -> Result<SessionId> {
+            self.created += 1;
+            let id = SessionId(format!("{}-{}", self.name, self.created));
+            self.sessions.insert(id.clone(), Session::new(id.clone()));
+            Ok(id)
+        }
+        async fn send_message(&mut self, _session_id: &SessionId, _content: String) -> Result<()> {
+            Ok(())
+        }
+        async fn start_prompt(
+            &mut self,
+            session_id: &SessionId,
+            _prompt: Vec<agent_client_protocol::ContentBlock>,
+        ) -> Result<PendingTurn> {
+            self.prompted.borrow_mut().push(session_id.clone());
+            let (done, turn) = PendingTurn::channel();
+            let gate = self.gate.clone();
+            tokio::task::spawn_local(async move {
+                gate.acquire().await.unwrap().forget();
+                let _ = done.send(Ok(()));
+            });
+            Ok(turn)
+        }
+        fn get_session_ids(&self) -> Vec<SessionId> {
+            self.sessions.keys().cloned().collect()
+        }
+        fn get_session(&self, session_id: &SessionId) -> Option<&Session> {
+            self.sessions.get(session_id)
+        }
+        fn get_session_mut(&mut self, session_id: &SessionId) -> Option<&mut Session> {
+            self.sessions.get_mut(session_id)
+        }
+        async fn tick(&mut self) -> Result<()> {
+            Ok(())
+        }
+        fn health_status(&self) -> AgentHealth {
+            AgentHealth::Healthy
+        }
+        fn capabilities(&self) -> AgentCapabilities {
+            AgentCapabilities::default()
+        }
+        fn set_permission_policy(&mut self, policy: PermissionsConfig) {
+            *self.policy.borrow_mut() = policy;
+        }
+    }
+
+    #[tokio::test]
+    async fn turns_queue_per_session_without_blocking_other_agents() {
+        tokio::task::LocalSet::new()
+            .run_until(async {
+                let mut config = AgentConfig::default();
+                config.claude_code.enabled = false;
+                config.gemini.enabled = false;
+                let (tx, _rx) = crate::events::channel(crate::events::DEFAULT_CAPACITY);
+                let manager = Rc::new(AgentManager::new(config, tx, None).await.unwrap());
+                let gate = Arc::new(Semaphore::new(0));
+                for name in ["slow", "fast"] {
+                    manager.insert_agent_for_test(
+                        name.to_string(),
+                        Box::new(GatedAdapter::new(name, gate.clone())),
+                    );
+                }
+
+                let session = manager.create_session("slow").await.unwrap();
+                let first = tokio::task::spawn_local({
+                    let (manager, session) = (manager.clone(), session.clone());
+                    async move { manager.send_message("slow", &session, "one".into()).await }
+                });
+                let second = tokio::task::spawn_local({
+                    let (manager, session) = (manager.clone(), session.clone());
+                    async move { manager.send_message("slow", &session, "two".into()).await }
+                });
+                tokio::task::yield_now().await;
+                assert_eq!(manager.turn_state(&session), TurnState::Running { queued: 1 });
+
+                // The slow agent's turn doesn't hold up the other agent, or a
+                // new session on the slow one
+                let other = manager.create_session("fast").await.unwrap();
+                assert_eq!(manager.turn_state(&other), TurnState::Idle);
+                manager.create_session("slow").await.unwrap();
+
+                gate.add_permits(2);
+                first.await.unwrap().unwrap();
+                second.await.unwrap().unwrap();
+                assert_eq!(manager.turn_state(&session), TurnState::Idle);
+            })
+            .await;
+    }
+
+    #[tokio::test]
+    async fn exited_agent_is_restarted_and_queued_prompts_replayed() {
+        tokio::task::LocalSet::new()
+            .run_until(async {
+                let mut config = AgentConfig::default();
+                config.claude_code.enabled = false;
+                config.gemini.enabled = false;
+                let (tx, mut rx) = crate::events::channel(crate::events::DEFAULT_CAPACITY);
+                let manager = AgentManager::new(config, tx, None).await.unwrap();
+                let adapter = GatedAdapter::new("agent", Arc::new(Semaphore::new(1)));
+                let (exited, prompted) = (adapter.exited.clone(), adapter.prompted.clone());
+                manager.insert_agent_for_test("agent".to_string(), Box::new(adapter));
+
+                let session = manager.create_session("agent").await.unwrap();
+                exited.set(true);
+                // The agent can't reload sessions, so the prompt goes to the
+                // session replacing the one it lost
+                manager
+                    .send_message("agent", &session, "hello".into())
+                    .await
+                    .unwrap();
+                let replacement = SessionId("agent-2".to_string());
+                assert_eq!(*prompted.borrow(), vec![replacement.clone()]);
+
+                let mut recovered = None;
+                while let Ok(message) = rx.try_recv() {
+                    if let AppMessage::SessionRecovered {
+                        session_id,
+                        reloaded,
+                        ..
+                    } = message
+                    {
+                        recovered = Some((session_id, reloaded));
+                    }
+                }
+                assert_eq!(recovered, Some((replacement, false)));
+            })
+            .await;
+    }
+
+    #[tokio::test]
+    async fn busy_agent_gets_a_new_policy_once_it_is_free() {
+        tokio::task::LocalSet::new()
+            .run_until(async {
+                let mut config = AgentConfig::default();
+                config.claude_code.enabled = false;
+                config.gemini.enabled = false;
+                let (tx, _rx) = crate::events::channel(crate::events::DEFAULT_CAPACITY);
+                let manager = AgentManager::new(config, tx, None).await.unwrap();
+                let adapter = GatedAdapter::new("agent", Arc::new(Semaphore::new(0)));
+                let policy = adapter.policy.clone();
+                manager.insert_agent_for_test("agent".to_string(), Box::new(adapter));
+
+                let deny = PermissionsConfig::deny_all();
+                let agent = manager.agent("agent").unwrap();
+                let busy = agent.lock().await;
+                manager.set_permission_policy(deny.clone());
+                assert_eq!(*policy.borrow(), PermissionsConfig::default());
+
+                drop(busy);
+                manager.create_session("agent").await.unwrap();
+                assert_eq!(*policy.borrow(), deny);
+            })
+            .await;
+    }
+
+    #[tokio::test]
+    async fn agent_crashing_too_often_waits_for_retry() {
+        tokio::task::LocalSet::new()
+            .run_until(async {
+                let mut config = AgentConfig::default();
+                config.claude_code.enabled = false;
+                config.gemini.enabled = false;
+                config.max_restarts = 1;
+                let (tx, mut rx) = crate::events::channel(crate::events::DEFAULT_CAPACITY);
+                let manager = AgentManager::new(config, tx, None).await.unwrap();
+                let adapter = GatedAdapter::new("agent", Arc::new(Semaphore::new(10)));
+                let exited = adapter.exited.clone();
+                manager.insert_agent_for_test("agent".to_string(), Box::new(adapter));
+
+                let session = manager.create_session("agent").await.unwrap();
+                exited.set(true);
+                manager.tick().await.unwrap();
+                assert!(!manager.is_agent_failed("agent"));
+
+                exited.set(true);
+                manager.tick().await.unwrap();
+                assert!(manager.is_agent_failed("agent"));
+                assert!(manager
+                    .send_message("agent", &session, "hello".into())
+                    .await
+                    .is_err());
+                assert!(manager.create_session("agent").await.is_err());
+                let mut failed = false;
+                while let Ok(message) = rx.try_recv() {
+                    failed |= matches!(message, AppMessage::AgentFailed { .. });
+                }
+                assert!(failed);
+
+                manager.retry_agent("agent");
+                manager.create_session("agent").await.unwrap();
+            })
+            .await;
+    }
+}
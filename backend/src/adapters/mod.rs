@@ -5,6 +5,62 @@ pub mod external;
 pub mod manager;
 pub mod traits;
 
-pub use manager::AgentManager;
+pub use manager::{AgentManager, TurnState};
 pub use traits::AgentAdapter;
 pub use external::{ExternalAgentSpec, ExternalCmdAdapter};
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::acp::client::LoginCommand;
+use crate::config::Config;
+
+/// Layer launch-profile variables over an agent command's own environment
+fn merge_env(
+    base: Option<HashMap<String, String>>,
+    extra: HashMap<String, String>,
+) -> Option<HashMap<String, String>> {
+    if extra.is_empty() {
+        return base;
+    }
+    let mut env = base.unwrap_or_default();
+    env.extend(extra);
+    Some(env)
+}
+
+/// Login command from an agent's `login_command` setting
+fn configured_login(command: &Option<Vec<String>>) -> Option<LoginCommand> {
+    let (program, args) = command.as_deref()?.split_first()?;
+    Some(LoginCommand {
+        path: program.into(),
+        args: args.to_vec(),
+    })
+}
+
+/// Best-effort authentication check from environment and credential files;
+/// `None` for agents whose credentials we don't know how to find
+pub fn auth_status(agent_name: &str, config: &Config) -> Option<String> {
+    let (key_env, credentials) = match agent_name {
+        "claude-code" => (
+            config.agents.claude_code.api_key_env.as_str(),
+            home_path(".claude/.credentials.json"),
+        ),
+        "gemini" => (
+            config.agents.gemini.api_key_env.as_str(),
+            home_path(".gemini/oauth_creds.json"),
+        ),
+        _ => return None,
+    };
+
+    if std::env::var(key_env).map(|v| !v.is_empty()).unwrap_or(false) {
+        return Some(format!("api key (${})", key_env));
+    }
+    Some(match credentials {
+        Some(path) if path.exists() => format!("logged in ({})", path.display()),
+        _ => "not logged in".to_string(),
+    })
+}
+
+fn home_path(relative: &str) -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(relative))
+}
@@ -1,9 +1,10 @@
+use agent_client_protocol as acp;
 use anyhow::Result;
 use async_trait::async_trait;
 use tokio::sync::mpsc;
 
-use crate::acp::{Message, Session, SessionId};
-use crate::app::AppMessage;
+use crate::acp::{PendingTurn, Session, SessionId};
+use crate::config::PermissionsConfig;
 
 #[async_trait(?Send)]
 pub trait AgentAdapter {
@@ -25,6 +26,63 @@ pub trait AgentAdapter {
     /// Send a message to a specific session
     async fn send_message(&mut self, session_id: &SessionId, content: String) -> Result<()>;
 
+    /// Send a prompt made of several content blocks (text, embedded resources, ...).
+    /// Adapters without native support receive the text blocks joined together.
+    async fn send_prompt(
+        &mut self,
+        session_id: &SessionId,
+        prompt: Vec<acp::ContentBlock>,
+    ) -> Result<()> {
+        let text = prompt
+            .iter()
+            .filter_map(|block| match block {
+                acp::ContentBlock::Text(text) => Some(text.text.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        self.send_message(session_id, text).await
+    }
+
+    /// Hand a prompt to the agent and return before the turn ends, so other
+    /// commands can use the adapter meanwhile. Adapters that can't split the
+    /// two run the whole turn here.
+    async fn start_prompt(
+        &mut self,
+        session_id: &SessionId,
+        prompt: Vec<acp::ContentBlock>,
+    ) -> Result<PendingTurn> {
+        let result = self.send_prompt(session_id, prompt).await;
+        Ok(PendingTurn::finished(result))
+    }
+
+    /// Ask the agent to stop the turn running in a session. The turn still
+    /// resolves, with `StopReason::Cancelled`.
+    fn cancel(&self, _session_id: &SessionId) -> Result<()> {
+        Err(anyhow::anyhow!("{} does not support cancelling turns", self.name()))
+    }
+
+    /// Restore a previously created session, if the agent supports it
+    async fn load_session(&mut self, _session_id: &SessionId) -> Result<()> {
+        Err(anyhow::anyhow!("{} does not support loading sessions", self.name()))
+    }
+
+    /// Load a session again after the agent restarted. Unlike `load_session`,
+    /// the agent's replay of the history is not forwarded to the UI.
+    async fn reattach_session(&mut self, session_id: &SessionId) -> Result<()> {
+        self.load_session(session_id).await
+    }
+
+    /// Whether the agent exited on its own since it was started
+    fn has_exited(&mut self) -> bool {
+        false
+    }
+
+    /// The last lines the agent wrote to stderr, to show why it failed
+    fn stderr_tail(&self) -> Vec<String> {
+        Vec::new()
+    }
+
     /// Get a list of active session IDs
     fn get_session_ids(&self) -> Vec<SessionId>;
 
@@ -42,6 +100,10 @@ pub trait AgentAdapter {
 
     /// Get agent capabilities
     fn capabilities(&self) -> AgentCapabilities;
+
+    /// Set the policy used to answer the agent's permission requests.
+    /// Applied the next time the agent is started.
+    fn set_permission_policy(&mut self, _policy: PermissionsConfig) {}
 }
 
 #[derive(Debug, Clone, PartialEq)]
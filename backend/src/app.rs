@@ -1,20 +1,48 @@
 use anyhow::{Context, Result};
-use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
-use log::{debug, error, info, warn};
+use crossterm::event::{Event, EventStream, KeyCode, KeyEvent, KeyEventKind};
+use futures::StreamExt;
+use tracing::{debug, error, info, warn};
 use ratatui::{
     prelude::*,
     widgets::{Block, Borders},
 };
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
 use std::io;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::time::Duration as StdDuration;
 use std::time::{Duration, Instant};
 use tokio::sync::{mpsc, oneshot};
 
-use crate::acp::{AcpClient, Message, SessionId};
-use crate::adapters::{AgentManager, ExternalAgentSpec};
-use crate::config::Config;
+use crate::acp::{AcpClient, Message, Session, SessionId, SessionRecorder, SessionStore};
+use crate::adapters::{auth_status, AgentManager, ExternalAgentSpec, TurnState};
+use crate::config::ui::EffectsConfig;
+use crate::config::{AutoCommit, Config, LaunchProfile};
+pub use crate::events::AppMessage;
+use crate::events::{self, AppReceiver, AppSender};
+use crate::integrations::editor::{self, Editor};
+use crate::integrations::issue::{self, IssueRef};
+use crate::integrations::pull_request::{self, PullRequest};
+use crate::integrations::tmux::{self, OutputPane, Placement};
+use crate::ui::components::agent_selector::AgentInfo;
+use crate::ui::components::ToastLevel;
+use crate::ui::notifications::{DesktopEvent, DesktopNotifier};
 use crate::ui::TuiManager;
+use crate::utils::git;
+use crate::utils::process::SuspendRequests;
+use crate::utils::terminal::TerminalGuard;
+
+/// Quiet period after a config file change before it is reloaded
+const CONFIG_RELOAD_DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// How long without input or agent traffic before `ui.idle_fps` applies
+const IDLE_AFTER: Duration = Duration::from_secs(3);
+
+/// Agent messages handled per loop pass before input and frames get a turn
+const MESSAGES_PER_PASS: usize = 256;
 
 // Messages sent from UI layer to App layer
 pub enum UiToApp {
@@ -25,12 +53,61 @@ pub enum UiToApp {
     ConnectAgent {
         agent_name: String,
     },
+    /// Allow an agent that crashed too often to start again
+    RetryAgent {
+        agent_name: String,
+    },
     SendMessage {
         agent_name: String,
         session_id: SessionId,
         content: String,
         respond_to: oneshot::Sender<anyhow::Result<()>>,
     },
+    /// Switch to the next config profile (wrapping back to no profile)
+    CycleProfile,
+    /// Write a session's transcript to a Markdown file
+    ExportSession {
+        session_id: SessionId,
+    },
+    /// Commit what the agent wrote in a session, push it to a branch and
+    /// open a pull request for it
+    CreatePullRequest {
+        agent_name: String,
+        session_id: SessionId,
+    },
+    /// Ask the agent to stop a session's running turn, and with it any
+    /// command it is running
+    CancelTurn {
+        agent_name: String,
+        session_id: SessionId,
+    },
+    /// Open the files the session's last turn changed in the editor
+    OpenChangedFiles {
+        agent_name: String,
+        session_id: SessionId,
+    },
+    /// Start another RAT with an agent in a tmux split or window
+    OpenInTmux {
+        agent_name: String,
+        placement: Placement,
+    },
+    ShowPermissionRules,
+    /// Persist the current panes and their sizes to `[ui.layout]` in the
+    /// config file
+    SaveLayout {
+        panes: Vec<String>,
+        split: String,
+        main_percent: u16,
+        terminal_height: u16,
+    },
+    /// Persist the theme and effects preset picked in the gallery to
+    /// `[ui.theme]` and `[ui.effects]` in the config file
+    SaveAppearance {
+        theme: String,
+        /// Preset from `EFFECT_PRESETS`; `None` leaves the effects alone
+        effects: Option<String>,
+    },
+    Quit,
 }
 
 pub struct App {
@@ -38,46 +115,43 @@ pub struct App {
     tui_manager: TuiManager,
     should_quit: bool,
     last_tick: Instant,
-    message_rx: Option<mpsc::UnboundedReceiver<AppMessage>>,
-    message_tx: mpsc::UnboundedSender<AppMessage>,
+    message_rx: Option<AppReceiver>,
+    message_tx: AppSender,
     ui_cmd_rx: Option<mpsc::UnboundedReceiver<UiToApp>>,
     ui_cmd_tx: mpsc::UnboundedSender<UiToApp>,
     manager_tx: mpsc::UnboundedSender<ManagerCmd>,
     manager_rx: Option<mpsc::UnboundedReceiver<ManagerCmd>>,
     external_override: Option<crate::adapters::ExternalAgentSpec>,
-}
-
-#[derive(Debug, Clone)]
-pub enum AppMessage {
-    AgentMessage {
-        agent_name: String,
-        message: Message,
-    },
-    AgentConnected {
-        agent_name: String,
-    },
-    AgentDisconnected {
-        agent_name: String,
-    },
-    SessionCreated {
-        agent_name: String,
-        session_id: SessionId,
-    },
-    Error {
-        error: String,
-    },
-    /// Temporarily leave TUI (raw mode + alt screen) so external UI can run
-    SuspendTui,
-    /// Return to TUI after external UI has finished
-    ResumeTui,
-    Quit,
+    recorder: SessionRecorder,
+    session_store: Option<SessionStore>,
+    /// Sessions whose first save has been announced
+    saved_sessions: HashSet<SessionId>,
+    notifier: DesktopNotifier,
+    /// Agents to open a session tab for when the main loop starts
+    startup_agents: Vec<String>,
+    /// Where the config came from, to reload it when its files change
+    config_source: Option<ConfigSource>,
+    /// Files written in each session's running turn, committed when it ends
+    /// with `auto_commit = "turn"`
+    uncommitted: HashMap<SessionId, Vec<PathBuf>>,
+    /// Every file each session's agent wrote, for its pull request
+    session_files: HashMap<SessionId, Vec<PathBuf>>,
+    /// Files written in each session's running turn, and in its last
+    /// finished turn that wrote any, for the editor
+    turn_files: HashMap<SessionId, Vec<PathBuf>>,
+    changed_files: HashMap<SessionId, Vec<PathBuf>>,
+    /// Where the editor last followed an agent to
+    followed: Option<(PathBuf, u32)>,
+    /// An editor command failed and nothing has worked since; further
+    /// failures are only logged
+    editor_failing: Arc<AtomicBool>,
 }
 
 impl App {
     pub async fn new(config: Config, external: Option<ExternalAgentSpec>) -> Result<Self> {
         info!("Initializing application");
 
-        let (message_tx, message_rx) = mpsc::unbounded_channel();
+        let (message_tx, message_rx) = events::channel(events::DEFAULT_CAPACITY);
         let (ui_cmd_tx, ui_cmd_rx) = mpsc::unbounded_channel();
         let (manager_tx, manager_rx) = mpsc::unbounded_channel();
 
@@ -87,9 +161,20 @@ impl App {
             .map(|e| e.name.clone())
             .unwrap_or_else(|| config.agents.default_agent.clone());
 
-        let tui_manager = TuiManager::new(config.ui.clone(), ui_cmd_tx.clone(), default_agent)?;
+        let mut tui_manager =
+            TuiManager::new(config.ui.clone(), ui_cmd_tx.clone(), default_agent)?;
+        tui_manager.set_agents(selector_agents(&config, external.as_ref()));
+        tui_manager.set_profile(config.active_profile.clone());
+        tui_manager.set_agent_dirs(agent_dirs(&config));
+        tui_manager.set_output_pane(OutputPane::from_config(&config.integrations.tmux));
+        tui_manager.set_log_file(config.get_effective_log_file());
+        let session_store = config
+            .general
+            .auto_save_sessions
+            .then(|| SessionStore::from_config(&config));
 
         Ok(Self {
+            notifier: DesktopNotifier::new(config.ui.notifications.clone()),
             config,
             tui_manager,
             should_quit: false,
@@ -101,11 +186,23 @@ impl App {
             manager_tx,
             manager_rx: Some(manager_rx),
             external_override: external,
+            recorder: SessionRecorder::default(),
+            session_store,
+            saved_sessions: HashSet::new(),
+            startup_agents: Vec::new(),
+            config_source: None,
+            uncommitted: HashMap::new(),
+            session_files: HashMap::new(),
+            turn_files: HashMap::new(),
+            changed_files: HashMap::new(),
+            followed: None,
+            editor_failing: Arc::new(AtomicBool::new(false)),
         })
     }
 
-    pub async fn connect_agent(&mut self, agent_name: &str) -> Result<()> {
-        info!("Connecting to agent: {}", agent_name);
+    /// Connect an agent and open a session tab for it once the app starts
+    pub fn open_agent(&mut self, agent_name: &str) -> Result<()> {
+        info!("Opening agent at startup: {}", agent_name);
         // Allow connecting to an externally provided agent (via --agent-cmd)
         if !self.config.agents.is_agent_enabled(agent_name) {
             let allowed_by_external = self
@@ -113,17 +210,327 @@ impl App {
                 .as_ref()
                 .map(|ext| ext.name == agent_name)
                 .unwrap_or(false);
-            if !allowed_by_external {
+            let is_launch_profile = launch_profiles(&self.config)
+                .iter()
+                .any(|profile| profile.name == agent_name);
+            if !allowed_by_external && !is_launch_profile {
                 return Err(anyhow::anyhow!("Agent '{}' is not enabled", agent_name));
             }
         }
-        let _ = self.manager_tx.send(ManagerCmd::ConnectAgent {
-            agent_name: agent_name.to_string(),
+        if !self.startup_agents.iter().any(|a| a == agent_name) {
+            self.startup_agents.push(agent_name.to_string());
+        }
+
+        Ok(())
+    }
+
+    /// Apply a named profile (or none) to the UI, default agent and permission
+    /// policy. Agents that are already running keep their settings and policy
+    /// until they reconnect.
+    pub fn switch_profile(&mut self, name: Option<&str>) -> Result<()> {
+        let config = match name {
+            Some(name) => self.config.with_profile(name)?,
+            None => self.config.without_profile(),
+        };
+        info!("Switching to profile: {}", name.unwrap_or("(none)"));
+
+        self.apply_config(config);
+        self.tui_manager
+            .show_status(format!("Profile: {}", name.unwrap_or("default")));
+        Ok(())
+    }
+
+    /// Reload settings whenever a file they were loaded from changes on disk:
+    /// `file` (`--config`, else the default config file) and the project
+    /// `.rat.toml`. `overrides` re-applies command-line settings (e.g.
+    /// `--no-effects`) on each reload.
+    pub fn watch_config(
+        &mut self,
+        file: Option<PathBuf>,
+        overrides: impl Fn(&mut Config) + 'static,
+    ) {
+        self.config_source = Some(ConfigSource {
+            file,
+            overrides: Box::new(overrides),
         });
+    }
+
+    /// Re-read the config files, keeping the active profile. Invalid files
+    /// are reported and the running config is left untouched.
+    async fn reload_config(&mut self) {
+        let Some(ConfigSource { file, overrides }) = &self.config_source else {
+            return;
+        };
+        info!("Reloading configuration");
 
+        let profile = self.config.active_profile.clone();
+        let result = Config::load(file.as_deref()).await.and_then(|config| {
+            let mut config = match &profile {
+                Some(name) => config.with_profile(name)?,
+                None => config,
+            };
+            overrides(&mut config);
+            config.validate()?;
+            Ok(config)
+        });
+
+        match result {
+            Ok(config) => {
+                self.apply_config(config);
+                self.tui_manager
+                    .notify(ToastLevel::Success, "Config reloaded".to_string());
+            }
+            Err(e) => {
+                warn!("Config reload failed: {:#}", e);
+                self.tui_manager
+                    .notify(ToastLevel::Error, format!("Config not reloaded: {:#}", e));
+            }
+        }
+    }
+
+    /// Make `config` the running config: UI (theme, effects, key bindings),
+    /// default agent, permission policy and launch profiles. Agent
+    /// definitions only take effect on restart.
+    fn apply_config(&mut self, config: Config) {
+        let default_agent = self
+            .external_override
+            .as_ref()
+            .map(|e| e.name.clone())
+            .unwrap_or_else(|| config.agents.default_agent.clone());
+        self.tui_manager.apply_config(config.ui.clone(), default_agent);
+        self.tui_manager.set_profile(config.active_profile.clone());
+        self.notifier.set_config(config.ui.notifications.clone());
+        self.tui_manager
+            .set_agents(selector_agents(&config, self.external_override.as_ref()));
+        let _ = self.manager_tx.send(ManagerCmd::SetPermissionPolicy {
+            policy: config.permissions.clone(),
+        });
+        let _ = self.manager_tx.send(ManagerCmd::SetLaunchProfiles {
+            profiles: launch_profiles(&config),
+        });
+        self.tui_manager.set_agent_dirs(agent_dirs(&config));
+        // Replacing the pane closes the open one, so only on a change
+        if config.integrations.tmux != self.config.integrations.tmux {
+            self.tui_manager
+                .set_output_pane(OutputPane::from_config(&config.integrations.tmux));
+        }
+        let _ = self.manager_tx.send(ManagerCmd::SetInstructionFiles {
+            files: config.project.instruction_files.clone(),
+        });
+        self.config = config;
+    }
+
+    /// Directory an agent works in: its launch profile's, else RAT's
+    fn agent_dir(&self, agent_name: &str) -> PathBuf {
+        agent_dirs(&self.config)
+            .remove(agent_name)
+            .or_else(|| std::env::current_dir().ok())
+            .unwrap_or_else(|| PathBuf::from("."))
+    }
+
+    /// Send a prompt naming issues once they are fetched and attached.
+    /// An issue that can't be fetched is reported and the prompt goes
+    /// without it.
+    fn send_with_issues(
+        &mut self,
+        agent_name: String,
+        session_id: SessionId,
+        text: String,
+        refs: Vec<IssueRef>,
+        respond_to: oneshot::Sender<Result<()>>,
+    ) {
+        let names: Vec<String> = refs.iter().map(IssueRef::to_string).collect();
+        self.tui_manager
+            .notify(ToastLevel::Info, format!("Fetching {}…", names.join(", ")));
+        let dir = self.agent_dir(&agent_name);
+        let remote = self.config.git.pull_request.remote.clone();
+        let integrations = self.config.integrations.clone();
+        let message_tx = self.message_tx.clone();
+        let manager_tx = self.manager_tx.clone();
+        tokio::spawn(async move {
+            let mut prompt = vec![agent_client_protocol::ContentBlock::Text(
+                agent_client_protocol::TextContent {
+                    text,
+                    annotations: Default::default(),
+                },
+            )];
+            for reference in &refs {
+                match issue::fetch(reference, &dir, &remote, &integrations).await {
+                    Ok(issue) => prompt.push(issue.to_block()),
+                    Err(e) => {
+                        let _ = message_tx.send(AppMessage::Error {
+                            error: format!("Issue {} not attached: {:#}", reference, e),
+                        });
+                    }
+                }
+            }
+            let _ = manager_tx.send(ManagerCmd::SendPrompt {
+                agent_name,
+                session_id,
+                prompt,
+                respond_to,
+            });
+        });
+    }
+
+    /// Open the files changed in a session's last turn (or the running one,
+    /// before any has finished) in the editor
+    fn open_changed_files(&mut self, agent_name: &str, session_id: &SessionId) -> Result<()> {
+        let paths = self
+            .changed_files
+            .get(session_id)
+            .or_else(|| self.turn_files.get(session_id))
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("The agent hasn't changed any files in this session"))?;
+        if Editor::from_config(&self.config.integrations.editor).is_none() {
+            anyhow::bail!("Set [integrations.editor] to open files in your editor");
+        }
+        self.open_in_editor(agent_name, paths);
         Ok(())
     }
 
+    fn open_in_editor(&self, agent_name: &str, paths: Vec<PathBuf>) {
+        let Some(editor) = Editor::from_config(&self.config.integrations.editor) else {
+            return;
+        };
+        let dir = self.agent_dir(agent_name);
+        self.run_editor(async move { editor.open(&dir, &paths).await });
+    }
+
+    /// Show the editor where a tool call works, once per new location
+    fn follow_in_editor(&mut self, agent_name: &str, message: &Message) {
+        let Some((path, line)) = editor::location(message) else {
+            return;
+        };
+        let Some(editor) = Editor::from_config(&self.config.integrations.editor) else {
+            return;
+        };
+        let dir = self.agent_dir(agent_name);
+        let path = dir.join(path);
+        if self.followed.as_ref() == Some(&(path.clone(), line)) {
+            return;
+        }
+        self.followed = Some((path.clone(), line));
+        self.run_editor(async move { editor.goto(&dir, &path, line).await });
+    }
+
+    /// Run an editor command in the background. Its first failure is shown;
+    /// repeats are only logged until a command works again.
+    fn run_editor(&self, command: impl Future<Output = Result<()>> + Send + 'static) {
+        let failing = self.editor_failing.clone();
+        let message_tx = self.message_tx.clone();
+        tokio::spawn(async move {
+            match command.await {
+                Ok(()) => failing.store(false, Ordering::Relaxed),
+                Err(e) => {
+                    warn!("Editor command failed: {:#}", e);
+                    if !failing.swap(true, Ordering::Relaxed) {
+                        let _ = message_tx.send(AppMessage::Error {
+                            error: format!("Editor: {:#}", e),
+                        });
+                    }
+                }
+            }
+        });
+    }
+
+    /// Commit files an agent wrote, as `[git] auto_commit` asks. Failures
+    /// (e.g. a file outside the repository) are shown but don't stop the
+    /// session.
+    async fn auto_commit(&mut self, agent_name: &str, session_id: &SessionId, paths: Vec<PathBuf>) {
+        let dir = self.agent_dir(agent_name);
+        let summary = self.recorder.get(session_id).and_then(Session::last_prompt);
+        let files: Vec<String> = paths
+            .iter()
+            .map(|path| path.strip_prefix(&dir).unwrap_or(path).display().to_string())
+            .collect();
+        let message = self.config.git.commit_message(
+            summary.as_deref(),
+            &session_id.0,
+            agent_name,
+            &files,
+        );
+        match git::commit_paths(&dir, &paths, &message).await {
+            Ok(Some(hash)) => {
+                info!("Committed {} for session {}: {}", hash, session_id.0, files.join(", "));
+                let plural = if files.len() == 1 { "" } else { "s" };
+                self.tui_manager.notify(
+                    ToastLevel::Info,
+                    format!("Committed {} ({} file{})", hash, files.len(), plural),
+                );
+            }
+            Ok(None) => debug!("Nothing to commit for session {}", session_id.0),
+            Err(e) => {
+                warn!("Auto-commit for session {} failed: {:#}", session_id.0, e);
+                self.tui_manager
+                    .notify(ToastLevel::Warning, format!("Not committed: {:#}", e));
+            }
+        }
+        self.tui_manager.files_changed();
+    }
+
+    /// Commit whatever the agent wrote in a session that isn't committed
+    /// yet, then push and open the pull request in the background; the
+    /// result arrives as a `PullRequestOpened` or an error
+    fn create_pull_request(&mut self, agent_name: &str, session_id: &SessionId) -> Result<()> {
+        let session = self
+            .recorder
+            .get(session_id)
+            .ok_or_else(|| anyhow::anyhow!("Nothing recorded for this session yet"))?;
+        let paths = self.session_files.get(session_id).cloned().unwrap_or_default();
+        if paths.is_empty() {
+            anyhow::bail!("The agent hasn't edited any files in this session");
+        }
+        let dir = self.agent_dir(agent_name);
+        let files: Vec<String> = paths
+            .iter()
+            .map(|path| path.strip_prefix(&dir).unwrap_or(path).display().to_string())
+            .collect();
+        let git_config = &self.config.git;
+        let message = git_config.commit_message(
+            session.last_prompt().as_deref(),
+            &session_id.0,
+            agent_name,
+            &files,
+        );
+        let pr = PullRequest::from_session(
+            session,
+            &files,
+            git_config.pull_request.branch_name(&session_id.0),
+            git_config.pull_request.draft,
+        );
+        let remote = git_config.pull_request.remote.clone();
+        let integrations = self.config.integrations.clone();
+        let message_tx = self.message_tx.clone();
+        let session_id = session_id.clone();
+        self.tui_manager
+            .notify(ToastLevel::Info, format!("Pushing {} to {}…", pr.branch, remote));
+        tokio::spawn(async move {
+            // Built on the base branch from the session's files alone, so
+            // the checkout and any other local work stay out of it
+            let opened = async {
+                let base = git::pull_request_base(&dir, &remote, &pr.branch).await?;
+                let commit = git::commit_onto(&dir, &base, &paths, &message)
+                    .await?
+                    .ok_or_else(|| anyhow::anyhow!("The session's files match the base branch"))?;
+                pull_request::open(&dir, &remote, &commit, &pr, &integrations).await
+            };
+            let _ = match opened.await {
+                Ok(url) => message_tx.send(AppMessage::PullRequestOpened { session_id, url }),
+                Err(e) => message_tx.send(AppMessage::Error {
+                    error: format!("Pull request not opened: {:#}", e),
+                }),
+            };
+        });
+        Ok(())
+    }
+
+    /// Reopen a stored session once the main loop starts
+    pub fn resume_session(&mut self, session: Session) {
+        info!("Resuming stored session: {}", session.id);
+        let _ = self.manager_tx.send(ManagerCmd::ResumeSession { session });
+    }
+
     pub async fn run(&mut self) -> Result<()> {
         info!("Starting RAT application");
         // Run the main loop inside a LocalSet so we can use spawn_local for non-Send tasks
@@ -132,13 +539,8 @@ impl App {
     }
 
     async fn run_inner(&mut self) -> Result<()> {
-        // Setup terminal
-        crossterm::terminal::enable_raw_mode().context("Failed to enable raw mode")?;
-        crossterm::execute!(
-            io::stdout(),
-            crossterm::terminal::EnterAlternateScreen,
-            crossterm::event::EnableMouseCapture
-        )?;
+        // Setup terminal; the guard restores it however this returns
+        let terminal_guard = TerminalGuard::enter().context("Failed to enable raw mode")?;
 
         let backend = CrosstermBackend::new(io::stdout());
         let mut terminal = Terminal::new(backend)?;
@@ -162,7 +564,7 @@ impl App {
             .ok_or_else(|| anyhow::anyhow!("Manager receiver already taken"))?;
         let message_tx = self.message_tx.clone();
         let agent_config = self.config.agents.clone();
-        let mut manager = match AgentManager::new(agent_config, message_tx.clone(), self.external_override.clone()).await {
+        let manager = match AgentManager::new(agent_config, message_tx.clone(), self.external_override.clone()).await {
             Ok(m) => m,
             Err(e) => {
                 let _ = message_tx.send(AppMessage::Error {
@@ -172,120 +574,178 @@ impl App {
                 return Err(e);
             }
         };
+        manager.set_permission_policy(self.config.permissions.clone());
+        manager.set_launch_profiles(launch_profiles(&self.config));
+        manager.set_instruction_files(self.config.project.instruction_files.clone());
 
         // Spawn the worker after successful initialization
         tokio::task::spawn_local(async move {
             manager_worker(manager, manager_rx).await;
         });
 
-        // Auto-connect configured agents via manager worker (non-blocking)
-        for agent_name in self.config.agents.auto_connect.clone() {
-            let _ = self
-                .manager_tx
-                .send(ManagerCmd::ConnectAgent { agent_name });
+        // Open a session for each configured and requested agent via the
+        // manager worker (non-blocking)
+        let requested = std::mem::take(&mut self.startup_agents);
+        for agent_name in startup_agents(&self.config.agents.auto_connect, requested) {
+            let _ = self.manager_tx.send(ManagerCmd::OpenSession { agent_name });
         }
 
         // Manager worker handles its own periodic tick.
 
-        // Spawn a blocking input thread to avoid starving the current-thread runtime
-        let (evt_tx, mut evt_rx) = mpsc::unbounded_channel::<Event>();
-        std::thread::spawn(move || {
-            loop {
-                match crossterm::event::read() {
-                    Ok(ev) => {
-                        // Ignore send errors when receiver dropped on shutdown
-                        let _ = evt_tx.send(ev);
+        // Terminal input as an async stream; `None` while an agent login has
+        // the terminal (see `suspend_input`)
+        let mut input = Some(EventStream::new());
+
+        // Watch the config files; the watcher must stay alive for the whole loop
+        let (config_tx, mut config_rx) = mpsc::unbounded_channel::<()>();
+        let _config_watcher = match &self.config_source {
+            Some(source) => {
+                let files = Config::source_files(source.file.as_deref());
+                match watch_config_files(&files, config_tx) {
+                    Ok(watcher) => Some(watcher),
+                    Err(e) => {
+                        warn!("Live config reload disabled: {:#}", e);
+                        None
                     }
-                    Err(_) => break,
                 }
             }
-        });
+            None => None,
+        };
+        // Editors often write a file in several steps; reload once they settle
+        let mut config_reload_at: Option<Instant> = None;
 
-        // Main event loop (event- and tick-driven, no busy spin)
-        let tick_rate = Duration::from_millis(50); // ~20 FPS
+        // Main event loop (event- and tick-driven, no busy spin). Housekeeping
+        // runs every `ui.tick_ms`. A frame is drawn only when something marked
+        // the screen dirty (an event, or a tick that changed what is shown), or
+        // while an effect animates, at `ui.fps` dropping to `ui.idle_fps` when
+        // nothing happens. Effects advance by wall-clock time, so pacing never
+        // changes their speed.
         let mut last_tick = Instant::now();
-        let mut next_frame_deadline = tokio::time::Instant::now() + tick_rate;
+        let mut last_activity = Instant::now();
+        let mut next_frame = tokio::time::Instant::now();
+        let mut dirty = true;
+        // SIGTERM, or SIGINT from outside the terminal, quits like Ctrl+C so
+        // agents are stopped and the terminal restored
+        let shutdown = crate::utils::process::shutdown_signal();
+        tokio::pin!(shutdown);
+        // `kill -TSTP`; Ctrl+Z arrives as a key press while in raw mode
+        let mut suspend_requests = SuspendRequests::new();
 
         loop {
+            let tick_rate = Duration::from_millis(self.config.ui.tick_ms);
+            let next_tick = tokio::time::Instant::from_std(last_tick + tick_rate);
+            let wake_at = if self.tui_manager.is_animating() {
+                next_frame.min(next_tick)
+            } else {
+                next_tick
+            };
+            let mut activity = true;
+
             // Wait for either an input event, an app/ui message, or next tick
             tokio::select! {
-                // Input events from blocking reader thread
-                maybe_ev = evt_rx.recv() => {
-                    if let Some(event) = maybe_ev {
-                        if let Event::Key(key) = event {
-                            if key.kind == KeyEventKind::Press {
-                                info!("Raw key event detected: {:?}", key);
-                                if self.handle_key_event(key).await? {
-                                    break;
-                                }
+                // Terminal input (keys, resizes, mouse)
+                maybe_ev = next_input(&mut input) => {
+                    match maybe_ev {
+                        Some(Ok(Event::Key(key))) if key.kind == KeyEventKind::Press && is_suspend_key(&key) => {
+                            self.suspend(&mut terminal, &mut input).await?;
+                        }
+                        Some(Ok(Event::Key(key))) if key.kind == KeyEventKind::Press => {
+                            info!("Raw key event detected: {:?}", key);
+                            if self.handle_key_event(key).await? {
+                                break;
                             }
                         }
-                    } else {
-                        // Input channel closed; continue handling other events
+                        Some(Ok(Event::FocusGained)) => self.notifier.set_focused(true),
+                        Some(Ok(Event::FocusLost)) => self.notifier.set_focused(false),
+                        Some(Ok(_)) => {}
+                        Some(Err(e)) => {
+                            warn!("Terminal input failed: {}", e);
+                            input = None;
+                        }
+                        None => input = None,
                     }
                 }
 
                 // App messages (agent updates, errors, etc.)
                 maybe_msg = message_rx.recv() => {
                     if let Some(msg) = maybe_msg {
+                        suspend_input(&mut input, &msg);
                         self.handle_app_message(msg).await?;
-                        // Drain any queued messages quickly without starving the loop
-                        while let Ok(msg) = message_rx.try_recv() {
+                        // Drain queued messages, but leave a backlog for the
+                        // next pass so input and frames are not starved
+                        for _ in 1..MESSAGES_PER_PASS {
+                            let Ok(msg) = message_rx.try_recv() else {
+                                break;
+                            };
+                            suspend_input(&mut input, &msg);
                             self.handle_app_message(msg).await?;
                         }
+                        self.tui_manager.set_backpressure(message_rx.backpressure());
                     }
                 }
 
                 // UI -> App commands
                 maybe_cmd = ui_cmd_rx.recv() => {
                     if let Some(cmd) = maybe_cmd {
-                        match cmd {
-                            UiToApp::CreateSession { agent_name, respond_to } => {
-                                let _ = self.manager_tx.send(ManagerCmd::CreateSession { agent_name, respond_to });
-                            }
-                            UiToApp::ConnectAgent { agent_name } => {
-                                let _ = self.manager_tx.send(ManagerCmd::ConnectAgent { agent_name });
-                            }
-                            UiToApp::SendMessage { agent_name, session_id, content, respond_to } => {
-                                let _ = self.manager_tx.send(ManagerCmd::SendMessage { agent_name, session_id, content, respond_to });
-                            }
-                        }
+                        self.handle_ui_command(cmd).await;
                         // Drain any queued commands
                         while let Ok(cmd) = ui_cmd_rx.try_recv() {
-                            match cmd {
-                                UiToApp::CreateSession { agent_name, respond_to } => {
-                                    let _ = self.manager_tx.send(ManagerCmd::CreateSession { agent_name, respond_to });
-                                }
-                                UiToApp::ConnectAgent { agent_name } => {
-                                    let _ = self.manager_tx.send(ManagerCmd::ConnectAgent { agent_name });
-                                }
-                                UiToApp::SendMessage { agent_name, session_id, content, respond_to } => {
-                                    let _ = self.manager_tx.send(ManagerCmd::SendMessage { agent_name, session_id, content, respond_to });
-                                }
-                            }
+                            self.handle_ui_command(cmd).await;
                         }
                     }
                 }
 
-                // Time-based tick for UI animations and housekeeping
-                _ = tokio::time::sleep_until(next_frame_deadline) => {
-                    // no-op here; ticking handled just below using last_tick check
+                // Config file changed on disk
+                Some(()) = config_rx.recv() => {
+                    config_reload_at = Some(Instant::now() + CONFIG_RELOAD_DEBOUNCE);
+                }
+
+                _ = suspend_requests.recv() => {
+                    self.suspend(&mut terminal, &mut input).await?;
+                }
+
+                _ = &mut shutdown => {
+                    info!("Termination signal received; shutting down");
+                    break;
+                }
+
+                // Next frame or housekeeping tick
+                _ = tokio::time::sleep_until(wake_at) => {
+                    activity = false;
                 }
             }
+            if activity {
+                last_activity = Instant::now();
+                dirty = true;
+            }
+
+            if config_reload_at.is_some_and(|at| Instant::now() >= at) {
+                config_reload_at = None;
+                self.reload_config().await;
+                dirty = true;
+            }
 
             // Perform periodic tick if due (ensures ticks even under constant events)
             if last_tick.elapsed() >= tick_rate {
-                self.tui_manager.tick().await?; // manager worker ticks independently
+                dirty |= self.tui_manager.tick().await?; // manager worker ticks independently
                 last_tick = Instant::now();
-                next_frame_deadline = tokio::time::Instant::now() + tick_rate;
             }
 
-            // Render a single frame in response to any of the above
-            terminal.draw(|f| {
-                if let Err(e) = self.render(f) {
-                    error!("Render error: {}", e);
-                }
-            })?;
+            // Render right away when dirty, otherwise only when an animation
+            // frame is due
+            let now = tokio::time::Instant::now();
+            if dirty || (self.tui_manager.is_animating() && now >= next_frame) {
+                dirty = false;
+                // Timed with the flush, which is where a slow link shows
+                let drawing = Instant::now();
+                terminal.draw(|f| {
+                    if let Err(e) = self.render(f) {
+                        error!("Render error: {}", e);
+                    }
+                })?;
+                self.tui_manager.record_frame(drawing.elapsed());
+                next_frame = now + frame_interval(&self.config.ui, last_activity.elapsed());
+            }
 
             if self.should_quit {
                 break;
@@ -296,31 +756,35 @@ impl App {
         self.cleanup().await?;
 
         // Restore terminal
-        crossterm::terminal::disable_raw_mode()?;
-        crossterm::execute!(
-            io::stdout(),
-            crossterm::terminal::LeaveAlternateScreen,
-            crossterm::event::DisableMouseCapture
-        )?;
+        drop(terminal_guard);
 
         info!("RAT application terminated");
         Ok(())
     }
 
+    /// Give the terminal back to the shell and stop, as Ctrl+Z does outside
+    /// raw mode; carries on where it left off once continued (`fg`)
+    async fn suspend(
+        &mut self,
+        terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+        input: &mut Option<EventStream>,
+    ) -> Result<()> {
+        info!("Suspending to the shell");
+        suspend_input(input, &AppMessage::SuspendTui);
+        self.handle_app_message(AppMessage::SuspendTui).await?;
+        crate::utils::process::suspend_self();
+        info!("Continued; restoring the TUI");
+        suspend_input(input, &AppMessage::ResumeTui);
+        self.handle_app_message(AppMessage::ResumeTui).await?;
+        // The alternate screen comes back blank; draw all of it again
+        terminal.clear()?;
+        Ok(())
+    }
+
     async fn handle_key_event(&mut self, key: KeyEvent) -> Result<bool> {
         info!("Key event received: {:?}", key);
 
-        // Global keybindings
-        if let Some(quit_key) = self.config.ui.get_keybinding("quit") {
-            info!("Quit key configured as: {}", quit_key);
-            if key.code == KeyCode::Char(quit_key.chars().next().unwrap_or('q')) {
-                info!("Quit key pressed! Setting should_quit = true");
-                self.should_quit = true;
-                return Ok(true);
-            }
-        }
-
-        // Handle Ctrl+C
+        // Ctrl+C always quits; the configurable quit key goes through the keymap
         if key.code == KeyCode::Char('c')
             && key
                 .modifiers
@@ -336,25 +800,220 @@ impl App {
         Ok(false)
     }
 
+    async fn handle_ui_command(&mut self, cmd: UiToApp) {
+        match cmd {
+            UiToApp::CreateSession {
+                agent_name,
+                respond_to,
+            } => {
+                let _ = self.manager_tx.send(ManagerCmd::CreateSession {
+                    agent_name,
+                    respond_to,
+                });
+            }
+            UiToApp::ConnectAgent { agent_name } => {
+                let _ = self.manager_tx.send(ManagerCmd::ConnectAgent { agent_name });
+            }
+            UiToApp::RetryAgent { agent_name } => {
+                let _ = self.manager_tx.send(ManagerCmd::RetryAgent { agent_name });
+            }
+            UiToApp::Quit => {
+                info!("Quit requested from UI");
+                self.should_quit = true;
+            }
+            UiToApp::CycleProfile => {
+                let names = self.config.profile_names();
+                if names.is_empty() {
+                    self.tui_manager
+                        .show_status("No config profiles defined".to_string());
+                    return;
+                }
+                let next = match &self.config.active_profile {
+                    None => names.first().cloned(),
+                    Some(current) => names
+                        .iter()
+                        .position(|n| n == current)
+                        .and_then(|i| names.get(i + 1).cloned()),
+                };
+                if let Err(e) = self.switch_profile(next.as_deref()) {
+                    self.tui_manager
+                        .notify(ToastLevel::Error, format!("{:#}", e));
+                }
+            }
+            UiToApp::ExportSession { session_id } => match self.export_session(&session_id) {
+                Ok(path) => self.tui_manager.notify(
+                    ToastLevel::Success,
+                    format!("Exported transcript to {}", path.display()),
+                ),
+                Err(e) => self
+                    .tui_manager
+                    .notify(ToastLevel::Error, format!("{:#}", e)),
+            },
+            UiToApp::CreatePullRequest {
+                agent_name,
+                session_id,
+            } => {
+                if let Err(e) = self.create_pull_request(&agent_name, &session_id) {
+                    self.tui_manager
+                        .notify(ToastLevel::Error, format!("{:#}", e));
+                }
+            }
+            UiToApp::CancelTurn {
+                agent_name,
+                session_id,
+            } => {
+                let _ = self.manager_tx.send(ManagerCmd::CancelTurn {
+                    agent_name,
+                    session_id,
+                });
+            }
+            UiToApp::OpenChangedFiles {
+                agent_name,
+                session_id,
+            } => {
+                if let Err(e) = self.open_changed_files(&agent_name, &session_id) {
+                    self.tui_manager
+                        .notify(ToastLevel::Error, format!("{:#}", e));
+                }
+            }
+            UiToApp::OpenInTmux {
+                agent_name,
+                placement,
+            } => {
+                let dir = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+                let mut args = vec!["--agent".to_string(), agent_name];
+                if let Some(profile) = &self.config.active_profile {
+                    args.extend(["--profile".to_string(), profile.clone()]);
+                }
+                if let Err(e) =
+                    tmux::open_rat(placement, &dir, &args, &self.config.integrations.tmux).await
+                {
+                    self.tui_manager
+                        .notify(ToastLevel::Error, format!("{:#}", e));
+                }
+            }
+            UiToApp::SaveLayout {
+                panes,
+                split,
+                main_percent,
+                terminal_height,
+            } => match self
+                .save_layout(panes, split, main_percent, terminal_height)
+                .await
+            {
+                Ok(path) => self.tui_manager.notify(
+                    ToastLevel::Success,
+                    format!("Layout saved to {}", path.display()),
+                ),
+                Err(e) => self
+                    .tui_manager
+                    .notify(ToastLevel::Error, format!("Layout not saved: {:#}", e)),
+            },
+            UiToApp::SaveAppearance { theme, effects } => {
+                match self.save_appearance(theme, effects).await {
+                    Ok(path) => self.tui_manager.notify(
+                        ToastLevel::Success,
+                        format!("Theme saved to {}", path.display()),
+                    ),
+                    Err(e) => self
+                        .tui_manager
+                        .notify(ToastLevel::Error, format!("Theme not saved: {:#}", e)),
+                }
+            }
+            UiToApp::ShowPermissionRules => {
+                let mut lines = self.config.permissions.describe();
+                lines.push(String::new());
+                lines.push("Edit [permissions] in the config file to change them".to_string());
+                self.tui_manager
+                    .show_info("Permission rules".to_string(), lines);
+            }
+            UiToApp::SendMessage {
+                agent_name,
+                session_id,
+                content,
+                respond_to,
+            } => {
+                self.recorder.record(
+                    &agent_name,
+                    Message::user_prompt(
+                        session_id.clone(),
+                        vec![agent_client_protocol::ContentBlock::Text(
+                            agent_client_protocol::TextContent {
+                                text: content.clone(),
+                                annotations: Default::default(),
+                            },
+                        )],
+                    ),
+                );
+                let (text, refs) = issue::extract(&content);
+                if refs.is_empty() {
+                    let _ = self.manager_tx.send(ManagerCmd::SendMessage {
+                        agent_name,
+                        session_id,
+                        content,
+                        respond_to,
+                    });
+                } else {
+                    self.send_with_issues(agent_name, session_id, text, refs, respond_to);
+                }
+            }
+        }
+    }
+
     async fn handle_app_message(&mut self, message: AppMessage) -> Result<()> {
         debug!("App message: {:?}", message);
+        self.recorder.observe(&message);
+        if let AppMessage::SessionCreated {
+            agent_name,
+            session_id,
+        }
+        | AppMessage::SessionReplaced {
+            agent_name,
+            new_session_id: session_id,
+            ..
+        } = &message
+        {
+            if let Some(dir) = agent_dirs(&self.config).get(agent_name) {
+                self.recorder.set_working_directory(session_id, dir);
+            }
+        }
 
         match message {
             AppMessage::AgentMessage {
                 agent_name,
                 message,
             } => {
+                if self.config.integrations.editor.follow {
+                    self.follow_in_editor(&agent_name, &message);
+                }
                 self.tui_manager.add_message(&agent_name, message).await?;
             }
             AppMessage::AgentConnected { agent_name } => {
                 info!("Agent connected: {}", agent_name);
                 self.tui_manager
                     .set_agent_status(&agent_name, "Connected".to_string());
+                self.tui_manager
+                    .notify(ToastLevel::Success, format!("{} connected", agent_name));
             }
             AppMessage::AgentDisconnected { agent_name } => {
                 warn!("Agent disconnected: {}", agent_name);
                 self.tui_manager
                     .set_agent_status(&agent_name, "Disconnected".to_string());
+                self.tui_manager
+                    .notify(ToastLevel::Warning, format!("{} disconnected", agent_name));
+            }
+            AppMessage::AgentFailed {
+                agent_name,
+                reason,
+                stderr,
+            } => {
+                error!("Agent {} {}; waiting for a retry", agent_name, reason);
+                self.notifier.notify(
+                    DesktopEvent::Error,
+                    format!("RAT: {} stopped", agent_name),
+                    reason.clone(),
+                );
+                self.tui_manager.set_agent_failed(&agent_name, reason, stderr);
             }
             AppMessage::SessionCreated {
                 agent_name,
@@ -369,7 +1028,158 @@ impl App {
             }
             AppMessage::Error { error } => {
                 error!("Application error: {}", error);
-                self.tui_manager.show_error(error);
+                self.notifier
+                    .notify(DesktopEvent::Error, "RAT: error".to_string(), error.clone());
+                self.tui_manager.report_error(error);
+            }
+            AppMessage::SessionReplaced {
+                agent_name,
+                old_session_id,
+                new_session_id,
+            } => {
+                info!(
+                    "Session {} for {} continues as {}",
+                    old_session_id.0, agent_name, new_session_id.0
+                );
+                self.tui_manager
+                    .replace_session(&agent_name, &old_session_id, new_session_id);
+            }
+            AppMessage::SessionRecovered {
+                agent_name,
+                session_id,
+                reloaded,
+            } => {
+                info!(
+                    "Session {} for {} recovered after a restart (reloaded: {})",
+                    session_id.0, agent_name, reloaded
+                );
+                self.tui_manager
+                    .set_recovered(&agent_name, &session_id, reloaded);
+            }
+            AppMessage::TurnCompleted {
+                agent_name,
+                session_id,
+                stop_reason,
+            } => {
+                debug!(
+                    "Turn completed for {} session {}: {:?}",
+                    agent_name, session_id.0, stop_reason
+                );
+                self.tui_manager.turn_completed(&agent_name, &session_id);
+                if let Some(paths) = self.uncommitted.remove(&session_id) {
+                    self.auto_commit(&agent_name, &session_id, paths).await;
+                }
+                if let Some(paths) = self.turn_files.remove(&session_id) {
+                    if self.config.integrations.editor.open_after_turn {
+                        self.open_in_editor(&agent_name, paths.clone());
+                    }
+                    self.changed_files.insert(session_id.clone(), paths);
+                }
+                self.notifier.notify(
+                    DesktopEvent::TurnComplete,
+                    format!("RAT: {} {}", agent_name, stop_reason_text(&stop_reason)),
+                    format!("Session {}", session_id.0.get(..8).unwrap_or(&session_id.0)),
+                );
+                if let Err(e) = self.save_state().await {
+                    warn!("Failed to save sessions: {}", e);
+                    self.tui_manager
+                        .notify(ToastLevel::Warning, format!("Session not saved: {}", e));
+                }
+            }
+            AppMessage::FileWritten {
+                agent_name,
+                session_id,
+                path,
+            } => {
+                info!("{} wrote {} in session {}", agent_name, path.display(), session_id.0);
+                let file_name = path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| path.display().to_string());
+                self.tui_manager
+                    .notify(ToastLevel::Success, format!("{} edited {}", agent_name, file_name));
+                for written in [
+                    self.session_files.entry(session_id.clone()).or_default(),
+                    self.turn_files.entry(session_id.clone()).or_default(),
+                ] {
+                    if !written.contains(&path) {
+                        written.push(path.clone());
+                    }
+                }
+                match self.config.git.auto_commit {
+                    AutoCommit::Off => self.tui_manager.files_changed(),
+                    AutoCommit::Edit => self.auto_commit(&agent_name, &session_id, vec![path]).await,
+                    AutoCommit::Turn => {
+                        let paths = self.uncommitted.entry(session_id).or_default();
+                        if !paths.contains(&path) {
+                            paths.push(path);
+                        }
+                        self.tui_manager.files_changed();
+                    }
+                }
+            }
+            AppMessage::InstructionsAttached {
+                agent_name,
+                session_id,
+                path,
+            } => {
+                let file_name = path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                self.tui_manager
+                    .set_instructions(&agent_name, &session_id, file_name);
+            }
+            AppMessage::PermissionRequested {
+                agent_name,
+                session_id,
+                title,
+                tool_kind,
+                command,
+                reply,
+            } => {
+                info!(
+                    "{} asks for permission ({}): {}",
+                    agent_name,
+                    tool_kind.as_deref().unwrap_or("unknown"),
+                    title
+                );
+                self.tui_manager
+                    .permission_requested(&agent_name, &session_id, title.clone(), command, reply);
+                self.notifier.bell();
+                self.notifier.notify(
+                    DesktopEvent::Permission,
+                    format!("RAT: {} asks for permission", agent_name),
+                    title,
+                );
+            }
+            AppMessage::PermissionResolved {
+                agent_name,
+                session_id,
+                title,
+                decision,
+                ..
+            } => {
+                info!(
+                    "Permission for {} session {} ({}): {:?}",
+                    agent_name, session_id.0, title, decision
+                );
+                self.tui_manager.permission_resolved(
+                    &agent_name,
+                    &session_id,
+                    decision == crate::config::PolicyDecision::Allow,
+                );
+                let verdict = match decision {
+                    crate::config::PolicyDecision::Allow => "allowed",
+                    crate::config::PolicyDecision::Deny | crate::config::PolicyDecision::Ask => {
+                        "denied"
+                    }
+                };
+                self.notifier.notify(
+                    DesktopEvent::Permission,
+                    format!("RAT: {} permission {}", agent_name, verdict),
+                    title,
+                );
             }
             AppMessage::SuspendTui => {
                 // Best-effort: leave raw/alt screen so external UI (login) is visible
@@ -377,6 +1187,7 @@ impl App {
                 let _ = crossterm::execute!(
                     io::stdout(),
                     crossterm::event::DisableMouseCapture,
+                    crossterm::event::DisableFocusChange,
                     crossterm::terminal::LeaveAlternateScreen
                 );
             }
@@ -386,22 +1197,23 @@ impl App {
                 let _ = crossterm::execute!(
                     io::stdout(),
                     crossterm::terminal::EnterAlternateScreen,
-                    crossterm::event::EnableMouseCapture
+                    crossterm::event::EnableMouseCapture,
+                    crossterm::event::EnableFocusChange
                 );
             }
-            AppMessage::Quit => {
-                self.should_quit = true;
+            AppMessage::PullRequestOpened { session_id, url } => {
+                info!("Pull request for session {}: {}", session_id.0, url);
+                self.tui_manager
+                    .notify(ToastLevel::Success, format!("Opened {}", url));
             }
         }
 
         Ok(())
     }
 
-    async fn tick(&mut self) -> Result<()> {
+    async fn tick(&mut self) -> Result<bool> {
         // Update TUI manager
-        self.tui_manager.tick().await?;
-
-        Ok(())
+        self.tui_manager.tick().await
     }
 
     fn render(&mut self, frame: &mut Frame) -> Result<()> {
@@ -428,10 +1240,113 @@ impl App {
         Ok(())
     }
 
-    async fn save_state(&self) -> Result<()> {
-        // Save sessions, preferences, etc.
-        // Implementation would depend on persistence requirements
-        info!("Application state saved");
+    /// Write the recorded transcript of a session to `rat-session-<id>.md`
+    /// in the working directory
+    fn export_session(&self, session_id: &SessionId) -> Result<PathBuf> {
+        let session = self
+            .recorder
+            .get(session_id)
+            .ok_or_else(|| anyhow::anyhow!("Nothing recorded for this session yet"))?;
+        let short_id = session_id.0.get(..8).unwrap_or(&session_id.0);
+        let path = PathBuf::from(format!("rat-session-{}.md", short_id));
+        std::fs::write(&path, session.to_markdown())
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(path)
+    }
+
+    /// The user config file settings were loaded from: `--config`, else the
+    /// default config file (created on first save)
+    fn config_file(&self) -> Result<PathBuf> {
+        let file = self.config_source.as_ref().and_then(|s| s.file.as_deref());
+        match Config::user_file(file) {
+            Some(path) => Ok(path),
+            None => Config::get_default_config_file(),
+        }
+    }
+
+    /// Write the pane layout into the config file in use, like `rat config
+    /// set` would: only the layout keys change
+    async fn save_layout(
+        &mut self,
+        panes: Vec<String>,
+        split: String,
+        main_percent: u16,
+        terminal_height: u16,
+    ) -> Result<PathBuf> {
+        let path = self.config_file()?;
+        let mut config = if path.exists() {
+            Config::from_file(&path).await?
+        } else {
+            Config::default()
+        };
+        config.ui.layout.panes = panes.clone();
+        config.ui.layout.split = split.clone();
+        config.ui.layout.main_percent = main_percent;
+        config.ui.layout.terminal_height = terminal_height;
+        config.validate()?;
+        config
+            .save_values(
+                &path,
+                &[
+                    "ui.layout.panes",
+                    "ui.layout.split",
+                    "ui.layout.main_percent",
+                    "ui.layout.terminal_height",
+                ],
+            )
+            .await?;
+
+        self.config.ui.layout.panes = panes;
+        self.config.ui.layout.split = split;
+        self.config.ui.layout.main_percent = main_percent;
+        self.config.ui.layout.terminal_height = terminal_height;
+        Ok(path)
+    }
+
+    /// Write the theme, and the effect toggles of the `effects` preset, into
+    /// the config file in use; nothing else in it changes
+    async fn save_appearance(&mut self, theme: String, effects: Option<String>) -> Result<PathBuf> {
+        let path = self.config_file()?;
+        let mut config = if path.exists() {
+            Config::from_file(&path).await?
+        } else {
+            Config::default()
+        };
+        config.ui.theme.name = theme.clone();
+        let mut keys = vec!["ui.theme.name".to_string()];
+        if let Some(preset) = &effects {
+            config.ui.effects.apply_preset(preset)?;
+            keys.extend(
+                EffectsConfig::PRESET_KEYS
+                    .iter()
+                    .map(|key| format!("ui.effects.{}", key)),
+            );
+        }
+        config.validate()?;
+        let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+        config.save_values(&path, &keys).await?;
+
+        self.config.ui.theme.name = theme;
+        if let Some(preset) = &effects {
+            self.config.ui.effects.apply_preset(preset)?;
+        }
+        Ok(path)
+    }
+
+    async fn save_state(&mut self) -> Result<()> {
+        if let Some(store) = &self.session_store {
+            let saved = self.recorder.flush(store).await?;
+            debug!("Sessions saved to {:?}", store.dir());
+            for session_id in saved {
+                if self.saved_sessions.insert(session_id.clone()) {
+                    let short_id = session_id.0.get(..8).unwrap_or(&session_id.0);
+                    self.tui_manager.notify(
+                        ToastLevel::Info,
+                        format!("Session {} saved to {}", short_id, store.dir().display()),
+                    );
+                }
+            }
+        }
         Ok(())
     }
 
@@ -463,7 +1378,153 @@ impl App {
     }
 }
 
-// Commands to the single-threaded manager worker
+/// Launch profiles in `config`; a broken profile file is logged and skipped
+fn launch_profiles(config: &Config) -> Vec<LaunchProfile> {
+    config.launch_profiles().unwrap_or_else(|e| {
+        warn!("Launch profiles unavailable: {:#}", e);
+        Vec::new()
+    })
+}
+
+/// Working directories of the launch profiles that set one
+fn agent_dirs(config: &Config) -> HashMap<String, PathBuf> {
+    launch_profiles(config)
+        .into_iter()
+        .filter_map(|profile| Some((profile.name.clone(), profile.working_dir()?)))
+        .collect()
+}
+
+/// Agent selector entries: the external agent, enabled agents, then launch profiles
+fn selector_agents(config: &Config, external: Option<&ExternalAgentSpec>) -> Vec<AgentInfo> {
+    let mut agents: Vec<AgentInfo> = external
+        .map(|e| AgentInfo::new(e.name.clone(), e.name.clone()))
+        .into_iter()
+        .collect();
+    agents.extend(
+        config
+            .agents
+            .get_enabled_agents()
+            .into_iter()
+            .map(|name| {
+                let auth = auth_status(&name, config);
+                AgentInfo::new(name.clone(), name).with_auth(auth)
+            }),
+    );
+    agents.extend(
+        launch_profiles(config)
+            .into_iter()
+            .map(|profile| {
+                AgentInfo::new(profile.name.clone(), profile.describe())
+                    .with_auth(auth_status(&profile.agent, config))
+            }),
+    );
+    agents
+}
+
+/// How a turn ended, for notifications: "finished", "was cancelled", ...
+fn stop_reason_text(stop_reason: &agent_client_protocol::StopReason) -> &'static str {
+    use agent_client_protocol::StopReason;
+    match stop_reason {
+        StopReason::EndTurn => "finished",
+        StopReason::MaxTokens => "hit the token limit",
+        StopReason::MaxTurnRequests => "hit the request limit",
+        StopReason::Refusal => "refused",
+        StopReason::Cancelled => "was cancelled",
+    }
+}
+
+/// Ctrl+Z, where the platform has job control to suspend with
+fn is_suspend_key(key: &KeyEvent) -> bool {
+    cfg!(unix)
+        && key.code == KeyCode::Char('z')
+        && key
+            .modifiers
+            .contains(crossterm::event::KeyModifiers::CONTROL)
+}
+
+/// The next terminal event; never resolves while input is suspended
+async fn next_input(input: &mut Option<EventStream>) -> Option<std::io::Result<Event>> {
+    match input {
+        Some(events) => events.next().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Stop reading the terminal while an external program (an agent login)
+/// owns it, so its keystrokes aren't swallowed, and read again once it
+/// hands the terminal back
+fn suspend_input(input: &mut Option<EventStream>, msg: &AppMessage) {
+    match msg {
+        AppMessage::SuspendTui => *input = None,
+        AppMessage::ResumeTui => *input = Some(EventStream::new()),
+        _ => {}
+    }
+}
+
+/// Agents to open a session for at startup: the configured `auto_connect`
+/// list, then the ones asked for on the command line, each once
+fn startup_agents(auto_connect: &[String], requested: Vec<String>) -> Vec<String> {
+    let mut agents = auto_connect.to_vec();
+    for agent_name in requested {
+        if !agents.contains(&agent_name) {
+            agents.push(agent_name);
+        }
+    }
+    agents
+}
+
+/// Time between frames: `ui.fps`, or `ui.idle_fps` once the UI has been
+/// idle for a few seconds (power saver)
+fn frame_interval(ui: &crate::config::UiConfig, idle_for: Duration) -> Duration {
+    let fps = match ui.idle_fps {
+        Some(idle_fps) if idle_for >= IDLE_AFTER => idle_fps,
+        _ => ui.fps,
+    };
+    Duration::from_secs(1) / fps.max(1)
+}
+
+/// Signal `tx` whenever one of `paths` changes. Watches their parent
+/// directories, since many editors save by replacing the file rather than
+/// writing in place, and a config file may not exist yet. Directories that
+/// don't exist are skipped.
+fn watch_config_files(
+    paths: &[PathBuf],
+    tx: mpsc::UnboundedSender<()>,
+) -> Result<notify::RecommendedWatcher> {
+    use notify::Watcher;
+
+    let files = paths.to_vec();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        match res {
+            Ok(event) if !event.kind.is_access() => {
+                if event.paths.iter().any(|p| files.contains(p)) {
+                    let _ = tx.send(());
+                }
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Config watcher error: {}", e),
+        }
+    })
+    .context("Failed to create config watcher")?;
+    let mut dirs: Vec<&Path> = paths.iter().filter_map(|path| path.parent()).collect();
+    dirs.sort();
+    dirs.dedup();
+    for dir in dirs.into_iter().filter(|dir| dir.is_dir()) {
+        watcher
+            .watch(dir, notify::RecursiveMode::NonRecursive)
+            .with_context(|| format!("Failed to watch {}", dir.display()))?;
+    }
+    Ok(watcher)
+}
+
+/// The `--config` file, if any, and the command-line settings to reapply
+/// over every reload
+struct ConfigSource {
+    file: Option<PathBuf>,
+    overrides: Box<dyn Fn(&mut Config)>,
+}
+
+// Commands to the manager worker, which runs on the UI thread's LocalSet
 pub enum ManagerCmd {
     ConnectAgent {
         agent_name: String,
@@ -478,15 +1539,51 @@ pub enum ManagerCmd {
         content: String,
         respond_to: oneshot::Sender<anyhow::Result<()>>,
     },
+    /// A prompt with content beyond its text, such as attached issues
+    SendPrompt {
+        agent_name: String,
+        session_id: SessionId,
+        prompt: Vec<agent_client_protocol::ContentBlock>,
+        respond_to: oneshot::Sender<anyhow::Result<()>>,
+    },
+    /// Ask the agent to stop the turn running in a session
+    CancelTurn {
+        agent_name: String,
+        session_id: SessionId,
+    },
     DisconnectAll {
         respond_to: oneshot::Sender<()>,
     },
+    ResumeSession {
+        session: Session,
+    },
+    /// Connect if needed and create a session, reporting failures via AppMessage
+    OpenSession {
+        agent_name: String,
+    },
+    SetPermissionPolicy {
+        policy: crate::config::PermissionsConfig,
+    },
+    SetLaunchProfiles {
+        profiles: Vec<LaunchProfile>,
+    },
+    SetInstructionFiles {
+        files: Vec<String>,
+    },
+    /// Clear an agent's crash history so it can be started again
+    RetryAgent {
+        agent_name: String,
+    },
 }
 
 pub async fn manager_worker(
-    mut manager: AgentManager,
+    manager: AgentManager,
     mut rx: mpsc::UnboundedReceiver<ManagerCmd>,
 ) {
+    // Commands run as their own tasks so a long turn or a slow agent start
+    // doesn't hold up other agents; the manager orders commands per agent
+    // and prompts per session
+    let manager = Rc::new(manager);
     let mut interval = tokio::time::interval(StdDuration::from_millis(50));
     loop {
         tokio::select! {
@@ -497,20 +1594,16 @@ pub async fn manager_worker(
             }
             cmd = rx.recv() => {
                 match cmd {
-                    Some(ManagerCmd::ConnectAgent { agent_name }) => {
-                        if let Err(e) = manager.connect_agent(&agent_name).await {
-                            warn!("Failed to connect agent '{}': {}", agent_name, e);
-                        }
-                    }
-                    Some(ManagerCmd::CreateSession { agent_name, respond_to }) => {
-                        let _ = respond_to.send(manager.create_session(&agent_name).await);
-                    }
-                    Some(ManagerCmd::SendMessage { agent_name, session_id, content, respond_to }) => {
-                        let _ = respond_to.send(manager.send_message(&agent_name, &session_id, content).await);
-                    }
-                    Some(ManagerCmd::DisconnectAll { respond_to }) => {
-                        let _ = manager.disconnect_all().await;
-                        let _ = respond_to.send(());
+                    // Settings apply in order, before any later command
+                    Some(
+                        cmd @ (ManagerCmd::SetPermissionPolicy { .. }
+                        | ManagerCmd::SetLaunchProfiles { .. }
+                        | ManagerCmd::SetInstructionFiles { .. }
+                        | ManagerCmd::RetryAgent { .. }
+                        | ManagerCmd::DisconnectAll { .. }),
+                    ) => run_manager_cmd(manager.clone(), cmd).await,
+                    Some(cmd) => {
+                        tokio::task::spawn_local(run_manager_cmd(manager.clone(), cmd));
                     }
                     None => break,
                 }
@@ -518,3 +1611,112 @@ pub async fn manager_worker(
         }
     }
 }
+
+async fn run_manager_cmd(manager: Rc<AgentManager>, cmd: ManagerCmd) {
+    match cmd {
+        ManagerCmd::ConnectAgent { agent_name } => {
+            if let Err(e) = manager.connect_agent(&agent_name).await {
+                warn!("Failed to connect agent '{}': {}", agent_name, e);
+            }
+        }
+        ManagerCmd::CreateSession { agent_name, respond_to } => {
+            let _ = respond_to.send(manager.create_session(&agent_name).await);
+        }
+        ManagerCmd::SendMessage { agent_name, session_id, content, respond_to } => {
+            if let TurnState::Running { queued } = manager.turn_state(&session_id) {
+                info!(
+                    "Prompt for session {} waits behind the running turn and {} queued",
+                    session_id.0, queued
+                );
+            }
+            let _ = respond_to.send(manager.send_message(&agent_name, &session_id, content).await);
+        }
+        ManagerCmd::SendPrompt { agent_name, session_id, prompt, respond_to } => {
+            let _ = respond_to.send(manager.send_prompt(&agent_name, &session_id, prompt).await);
+        }
+        ManagerCmd::CancelTurn { agent_name, session_id } => {
+            if let Err(e) = manager.cancel_turn(&agent_name, &session_id).await {
+                warn!("{:#}", e);
+            }
+        }
+        ManagerCmd::ResumeSession { session } => {
+            let session_id = session.id.clone();
+            if let Err(e) = manager.resume_session(session).await {
+                warn!("Failed to resume session {}: {}", session_id, e);
+            }
+        }
+        ManagerCmd::OpenSession { agent_name } => {
+            if let Err(e) = manager.create_session(&agent_name).await {
+                warn!("Failed to open session for '{}': {}", agent_name, e);
+            }
+        }
+        ManagerCmd::SetPermissionPolicy { policy } => {
+            manager.set_permission_policy(policy);
+        }
+        ManagerCmd::SetLaunchProfiles { profiles } => {
+            manager.set_launch_profiles(profiles);
+        }
+        ManagerCmd::SetInstructionFiles { files } => {
+            manager.set_instruction_files(files);
+        }
+        ManagerCmd::RetryAgent { agent_name } => {
+            manager.retry_agent(&agent_name);
+        }
+        ManagerCmd::DisconnectAll { respond_to } => {
+            let _ = manager.disconnect_all().await;
+            let _ = respond_to.send(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn startup_opens_configured_agents_then_requested_ones_once() {
+        let agents = startup_agents(
+            &names(&["claude-code"]),
+            names(&["gemini", "claude-code", "gemini"]),
+        );
+        assert_eq!(agents, names(&["claude-code", "gemini"]));
+        assert!(startup_agents(&[], Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn frames_slow_down_to_idle_fps_once_idle() {
+        let mut ui = crate::config::UiConfig {
+            fps: 50,
+            ..Default::default()
+        };
+        let (busy, idle) = (Duration::from_millis(20), Duration::from_millis(200));
+        assert_eq!(frame_interval(&ui, Duration::ZERO), busy);
+        assert_eq!(frame_interval(&ui, IDLE_AFTER * 10), busy);
+
+        ui.idle_fps = Some(5);
+        assert_eq!(frame_interval(&ui, Duration::from_secs(1)), busy);
+        assert_eq!(frame_interval(&ui, IDLE_AFTER), idle);
+    }
+
+    #[tokio::test]
+    async fn suspended_input_stays_quiet_until_resumed() {
+        // Only `ResumeTui` reads the terminal again (which a test has none of)
+        let mut input = None;
+        let error = AppMessage::Error {
+            error: "x".to_string(),
+        };
+        for message in [&error, &AppMessage::SuspendTui, &error] {
+            suspend_input(&mut input, message);
+            assert!(input.is_none());
+        }
+
+        // Suspended input never yields, so the loop keeps serving messages
+        let waited =
+            tokio::time::timeout(Duration::from_millis(20), next_input(&mut input)).await;
+        assert!(waited.is_err());
+    }
+}
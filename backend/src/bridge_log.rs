@@ -0,0 +1,128 @@
+//! Opt-in debug log of bridge connections (`--local-bridge-log <dir>`, `rat
+//! serve --bridge-log <dir>`), separate from the main log. Each WebSocket
+//! connection gets its own JSONL file recording the handshake, every frame
+//! relayed either way, the fs/terminal requests the bridge intercepted and
+//! the permission prompts it raised.
+
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+use std::fs::{File, OpenOptions};
+use std::io::{LineWriter, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+/// Frames longer than this are cut short in the log
+const MAX_LOGGED_BYTES: usize = 64 * 1024;
+
+static DIR: OnceLock<PathBuf> = OnceLock::new();
+static NEXT_CONNECTION: AtomicU64 = AtomicU64::new(1);
+
+/// Log every connection from now on, one file each in `dir`
+pub fn enable(dir: PathBuf) -> Result<()> {
+    std::fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed to create {}", dir.display()))?;
+    let _ = DIR.set(dir);
+    Ok(())
+}
+
+/// The event log of one connection
+pub struct BridgeLog {
+    connection: u64,
+    file: Mutex<LineWriter<File>>,
+}
+
+impl BridgeLog {
+    /// Start a log for a new connection, if logging is enabled. A log that
+    /// can't be created is reported and skipped; the connection goes on.
+    pub fn open() -> Option<Self> {
+        let dir = DIR.get()?;
+        let connection = NEXT_CONNECTION.fetch_add(1, Ordering::Relaxed);
+        let started = chrono::Local::now().format("%Y%m%dT%H%M%S");
+        let path = dir.join(format!(
+            "{}-{}-{}.jsonl",
+            started,
+            std::process::id(),
+            connection
+        ));
+        match OpenOptions::new().create(true).append(true).open(&path) {
+            Ok(file) => Some(Self {
+                connection,
+                file: Mutex::new(LineWriter::new(file)),
+            }),
+            Err(e) => {
+                tracing::warn!("Bridge log {} unavailable: {}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    pub fn connection(&self) -> u64 {
+        self.connection
+    }
+
+    /// Append one event; `fields` is an object of details
+    pub fn record(&self, event: &str, mut fields: Value) {
+        if !fields.is_object() {
+            fields = json!({ "data": fields });
+        }
+        fields["ts"] = json!(chrono::Utc::now().to_rfc3339());
+        fields["connection"] = json!(self.connection);
+        fields["event"] = json!(event);
+        let mut file = self.file.lock().unwrap();
+        let _ = writeln!(file, "{}", fields);
+    }
+
+    /// A frame relayed to (`out`) or from (`in`) the client
+    pub fn frame(&self, direction: &str, text: &str) {
+        self.record(
+            "frame",
+            json!({ "direction": direction, "message": loggable(text) }),
+        );
+    }
+}
+
+/// A frame as it goes in the log: parsed when it is JSON so the log stays
+/// queryable, and cut short when it is huge
+pub fn loggable(text: &str) -> Value {
+    if text.len() > MAX_LOGGED_BYTES {
+        let mut end = MAX_LOGGED_BYTES;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        return json!({ "truncated": text.len(), "text": &text[..end] });
+    }
+    serde_json::from_str(text).unwrap_or_else(|_| json!(text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_are_json_lines_with_connection_and_kind() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("conn.jsonl");
+        let log = BridgeLog {
+            connection: 3,
+            file: Mutex::new(LineWriter::new(File::create(&path).unwrap())),
+        };
+        log.record("handshake", json!({"session": "work"}));
+        log.frame("in", r#"{"jsonrpc":"2.0","id":1,"method":"initialize"}"#);
+        log.frame("out", "not json");
+
+        let lines: Vec<Value> = std::fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0]["event"], "handshake");
+        assert_eq!(lines[0]["connection"], 3);
+        assert_eq!(lines[1]["message"]["method"], "initialize");
+        assert_eq!(lines[2]["message"], "not json");
+
+        let huge = "é".repeat(MAX_LOGGED_BYTES);
+        assert_eq!(loggable(&huge)["truncated"], huge.len());
+    }
+}
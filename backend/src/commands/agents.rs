@@ -0,0 +1,168 @@
+use anyhow::{Context, Result};
+use clap::Subcommand;
+use serde::Serialize;
+
+use crate::adapters::agent_installer::{
+    AgentCommand, AgentInstaller, AgentSource, MANAGED_AGENTS,
+};
+use crate::adapters::auth_status;
+use crate::config::Config;
+
+#[derive(Debug, Subcommand)]
+pub enum AgentsAction {
+    /// Show known agents with resolved paths, installed versions and auth status
+    List {
+        /// Print JSON instead of a table
+        #[arg(long)]
+        json: bool,
+    },
+    /// Install the latest version of an agent into RAT's data directory
+    Install {
+        /// Agent name (claude-code, gemini)
+        name: String,
+    },
+    /// Update local installs to the latest published version
+    Update {
+        /// Agent name; all managed agents when omitted
+        name: Option<String>,
+    },
+    /// Remove local installs of an agent
+    Remove {
+        /// Agent name (claude-code, gemini)
+        name: String,
+
+        /// Only remove this version
+        #[arg(long)]
+        version: Option<String>,
+    },
+    /// Print the command RAT would run for an agent
+    Which {
+        /// Agent name (claude-code, gemini)
+        name: String,
+    },
+}
+
+#[derive(Debug, Serialize)]
+struct AgentInfo {
+    name: String,
+    enabled: bool,
+    source: Option<AgentSource>,
+    command: Option<String>,
+    local_versions: Vec<String>,
+    auth: String,
+}
+
+/// Run an `rat agents` action; returns the process exit code
+pub async fn run(action: AgentsAction, config: &Config) -> Result<i32> {
+    let installer = AgentInstaller::new().context("Failed to create agent installer")?;
+
+    match action {
+        AgentsAction::List { json } => {
+            let mut infos = Vec::new();
+            for name in MANAGED_AGENTS {
+                let location = installer.locate(name).await?;
+                infos.push(AgentInfo {
+                    name: name.to_string(),
+                    enabled: config.agents.is_agent_enabled(name),
+                    source: location.as_ref().map(|l| l.source),
+                    command: location.as_ref().map(|l| format_command(&l.command)),
+                    local_versions: installer
+                        .local_versions(name)
+                        .await?
+                        .iter()
+                        .map(|v| v.to_string())
+                        .collect(),
+                    auth: auth_status(name, config).unwrap_or_else(|| "unknown".to_string()),
+                });
+            }
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&infos)?);
+            } else {
+                for info in infos {
+                    println!(
+                        "{} ({})",
+                        info.name,
+                        if info.enabled { "enabled" } else { "disabled" }
+                    );
+                    match (info.source, info.command) {
+                        (Some(source), Some(command)) => {
+                            println!("  command:  {} [{}]", command, source)
+                        }
+                        _ => println!("  command:  not found (run `rat agents install {}`)", info.name),
+                    }
+                    if !info.local_versions.is_empty() {
+                        println!("  versions: {}", info.local_versions.join(", "));
+                    }
+                    println!("  auth:     {}", info.auth);
+                }
+                println!("data dir: {}", installer.data_dir().display());
+            }
+            Ok(0)
+        }
+        AgentsAction::Install { name } => {
+            let command = installer.install(&name).await?;
+            println!("Installed {}: {}", name, format_command(&command));
+            Ok(0)
+        }
+        AgentsAction::Update { name } => {
+            let names: Vec<String> = match name {
+                Some(name) => vec![name],
+                None => MANAGED_AGENTS.iter().map(|n| n.to_string()).collect(),
+            };
+            for name in names {
+                let installed = installer.local_versions(&name).await?;
+                let latest = installer.latest_version(&name).await?;
+                if installed.iter().any(|v| v.to_string() == latest) {
+                    println!("{} is up to date ({})", name, latest);
+                    continue;
+                }
+                installer.install(&name).await?;
+                println!("Updated {} to {}", name, latest);
+
+                if let Some(location) = installer.locate(&name).await? {
+                    if location.source != AgentSource::Local {
+                        println!(
+                            "  note: {} resolves from {} first ({})",
+                            name,
+                            location.source,
+                            format_command(&location.command)
+                        );
+                    }
+                }
+            }
+            Ok(0)
+        }
+        AgentsAction::Remove { name, version } => {
+            let version = version
+                .map(|v| semver::Version::parse(&v))
+                .transpose()
+                .context("Invalid version")?;
+            let removed = installer.remove(&name, version.as_ref()).await?;
+            if removed.is_empty() {
+                eprintln!("No local installs of {} to remove", name);
+                return Ok(1);
+            }
+            for dir in removed {
+                println!("Removed {}", dir.display());
+            }
+            Ok(0)
+        }
+        AgentsAction::Which { name } => match installer.locate(&name).await? {
+            Some(location) => {
+                println!("{}", format_command(&location.command));
+                Ok(0)
+            }
+            None => {
+                eprintln!("{} not found", name);
+                Ok(1)
+            }
+        },
+    }
+}
+
+fn format_command(command: &AgentCommand) -> String {
+    let mut parts = vec![command.path.display().to_string()];
+    parts.extend(command.args.iter().cloned());
+    parts.join(" ")
+}
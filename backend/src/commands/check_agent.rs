@@ -0,0 +1,766 @@
+//! `rat check-agent`: protocol checks against any ACP agent command.
+//!
+//! The checks talk raw JSON-RPC rather than going through `AcpClient`, so
+//! they see exactly what the agent sends (error codes, stray stdout lines,
+//! updates for the wrong session) instead of what the ACP library makes of it.
+
+use anyhow::{Context, Result};
+use clap::Args;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, Command};
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+use crate::adapters::ExternalAgentSpec;
+
+#[derive(Debug, Args)]
+pub struct CheckAgentArgs {
+    /// Seconds each check may take before it fails
+    #[arg(long, default_value_t = 30)]
+    pub timeout: u64,
+
+    /// Skip the checks that send real prompts (prompt turn, fs callbacks and
+    /// cancellation), e.g. for agents that bill per request
+    #[arg(long)]
+    pub no_prompt: bool,
+
+    /// Print the report as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// Protocol version RAT speaks
+const PROTOCOL_VERSION: u64 = 1;
+
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const AUTH_REQUIRED: i64 = -32000;
+
+/// File the prompt check asks the agent to read, in the session's directory
+const CHECK_FILE: &str = "rat-check.txt";
+const CHECK_FILE_CONTENT: &str = "rat-check-7f3a\n";
+
+/// How long the cancellation check waits for the turn to get going
+const CANCEL_AFTER: Duration = Duration::from_secs(5);
+
+const STOP_REASONS: [&str; 5] = [
+    "end_turn",
+    "max_tokens",
+    "max_turn_requests",
+    "refusal",
+    "cancelled",
+];
+
+const UPDATE_KINDS: [&str; 8] = [
+    "user_message_chunk",
+    "agent_message_chunk",
+    "agent_thought_chunk",
+    "tool_call",
+    "tool_call_update",
+    "plan",
+    "available_commands_update",
+    "current_mode_update",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Pass,
+    Warn,
+    Fail,
+    Skip,
+}
+
+impl Status {
+    fn label(self) -> &'static str {
+        match self {
+            Status::Pass => "PASS",
+            Status::Warn => "WARN",
+            Status::Fail => "FAIL",
+            Status::Skip => "SKIP",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: Status,
+    pub detail: String,
+}
+
+#[derive(Debug, Default)]
+struct Report {
+    checks: Vec<Check>,
+}
+
+impl Report {
+    fn push(&mut self, name: &'static str, status: Status, detail: impl Into<String>) {
+        self.checks.push(Check {
+            name,
+            status,
+            detail: detail.into(),
+        });
+    }
+
+    fn skip(&mut self, names: &[&'static str], reason: &str) {
+        for name in names {
+            self.push(name, Status::Skip, reason);
+        }
+    }
+
+    fn count(&self, status: Status) -> usize {
+        self.checks.iter().filter(|c| c.status == status).count()
+    }
+}
+
+/// Answer to one of our requests
+enum Reply {
+    Result(Value),
+    Error { code: i64, message: String },
+}
+
+/// How waiting on the agent ended
+enum Pumped {
+    Reply(Reply),
+    /// The caller's condition held before the reply came
+    Condition,
+    TimedOut,
+}
+
+/// What the agent sent on its own while we waited
+#[derive(Debug, Default)]
+struct Observed {
+    /// Params of `session/update` notifications
+    updates: Vec<Value>,
+    /// Requests the agent made of the client, by method
+    requests: Vec<(String, Value)>,
+    /// Stdout lines that aren't JSON
+    garbage: Vec<String>,
+}
+
+/// A running agent and the JSON-RPC plumbing to talk to it
+struct AgentConn {
+    child: Child,
+    stdin: ChildStdin,
+    lines: mpsc::UnboundedReceiver<String>,
+    next_id: u64,
+    /// Session directory; fs callbacks are served from it and nowhere else
+    cwd: PathBuf,
+    observed: Observed,
+}
+
+impl AgentConn {
+    fn spawn(spec: &ExternalAgentSpec, cwd: &Path) -> Result<Self> {
+        let mut command = Command::new(&spec.path);
+        command
+            .args(&spec.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            // Agents log there freely; it would bury the report
+            .stderr(Stdio::null())
+            .kill_on_drop(true);
+        if let Some(env) = &spec.env {
+            command.envs(env);
+        }
+        let mut child = command
+            .spawn()
+            .with_context(|| format!("failed to start agent: {}", spec.path))?;
+        let stdin = child.stdin.take().context("agent has no stdin")?;
+        let stdout = child.stdout.take().context("agent has no stdout")?;
+
+        let (tx, lines) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let mut reader = BufReader::new(stdout).lines();
+            while let Ok(Some(line)) = reader.next_line().await {
+                if tx.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Self {
+            child,
+            stdin,
+            lines,
+            next_id: 0,
+            cwd: cwd.to_path_buf(),
+            observed: Observed::default(),
+        })
+    }
+
+    async fn send(&mut self, message: Value) -> Result<()> {
+        let line = format!("{}\n", message);
+        self.stdin
+            .write_all(line.as_bytes())
+            .await
+            .context("agent closed its stdin")?;
+        self.stdin.flush().await?;
+        Ok(())
+    }
+
+    async fn send_request(&mut self, method: &str, params: Value) -> Result<u64> {
+        self.next_id += 1;
+        let id = self.next_id;
+        self.send(json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params}))
+            .await?;
+        Ok(id)
+    }
+
+    async fn notify(&mut self, method: &str, params: Value) -> Result<()> {
+        self.send(json!({"jsonrpc": "2.0", "method": method, "params": params}))
+            .await
+    }
+
+    /// Send a request and wait for its reply; `None` on timeout
+    async fn request(
+        &mut self,
+        method: &str,
+        params: Value,
+        timeout: Duration,
+    ) -> Result<Option<Reply>> {
+        let id = self.send_request(method, params).await?;
+        match self.pump(id, Instant::now() + timeout, |_| false).await? {
+            Pumped::Reply(reply) => Ok(Some(reply)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Handle agent traffic until the reply to `id` arrives, `until` holds
+    /// or the deadline passes. Errors when the agent's stdout closes.
+    async fn pump(
+        &mut self,
+        id: u64,
+        deadline: Instant,
+        until: impl Fn(&Observed) -> bool,
+    ) -> Result<Pumped> {
+        loop {
+            if until(&self.observed) {
+                return Ok(Pumped::Condition);
+            }
+            let line = match tokio::time::timeout_at(deadline, self.lines.recv()).await {
+                Ok(Some(line)) => line,
+                Ok(None) => anyhow::bail!("agent closed its stdout"),
+                Err(_) => return Ok(Pumped::TimedOut),
+            };
+            let Ok(message) = serde_json::from_str::<Value>(&line) else {
+                self.observed.garbage.push(line);
+                continue;
+            };
+            if let Some(method) = message.get("method").and_then(Value::as_str) {
+                let method = method.to_string();
+                let params = message.get("params").cloned().unwrap_or(Value::Null);
+                match message.get("id") {
+                    Some(request_id) => {
+                        let answer = self.answer(&method, &params);
+                        self.observed.requests.push((method, params));
+                        let mut response = json!({"jsonrpc": "2.0", "id": request_id});
+                        match answer {
+                            Ok(result) => response["result"] = result,
+                            Err((code, message)) => {
+                                response["error"] = json!({"code": code, "message": message})
+                            }
+                        }
+                        self.send(response).await?;
+                    }
+                    None if method == "session/update" => self.observed.updates.push(params),
+                    None => {}
+                }
+                continue;
+            }
+            if message.get("id").and_then(Value::as_u64) != Some(id) {
+                // A late reply to an earlier check
+                continue;
+            }
+            let reply = match message.get("error") {
+                Some(error) => Reply::Error {
+                    code: error["code"].as_i64().unwrap_or_default(),
+                    message: error["message"].as_str().unwrap_or_default().to_string(),
+                },
+                None => Reply::Result(message.get("result").cloned().unwrap_or(Value::Null)),
+            };
+            return Ok(Pumped::Reply(reply));
+        }
+    }
+
+    /// Answer a request from the agent like a permissive client would
+    fn answer(&self, method: &str, params: &Value) -> std::result::Result<Value, (i64, String)> {
+        match method {
+            "fs/read_text_file" => {
+                let path = self.check_path(params)?;
+                std::fs::read_to_string(&path)
+                    .map(|content| json!({"content": content}))
+                    .map_err(|e| (-32603, format!("{}: {}", path.display(), e)))
+            }
+            "fs/write_text_file" => {
+                let path = self.check_path(params)?;
+                let content = params["content"].as_str().unwrap_or_default();
+                std::fs::write(&path, content)
+                    .map(|_| Value::Null)
+                    .map_err(|e| (-32603, format!("{}: {}", path.display(), e)))
+            }
+            "session/request_permission" => {
+                let options = params["options"].as_array().cloned().unwrap_or_default();
+                let allow = options
+                    .iter()
+                    .find(|option| option["kind"] == "allow_once")
+                    .or_else(|| options.first());
+                Ok(match allow {
+                    Some(option) => {
+                        json!({"outcome": {"outcome": "selected", "optionId": option["optionId"]}})
+                    }
+                    None => json!({"outcome": {"outcome": "cancelled"}}),
+                })
+            }
+            _ => Err((METHOD_NOT_FOUND, format!("{} is not supported", method))),
+        }
+    }
+
+    /// Absolute path of an fs request, if it stays inside the session directory
+    fn check_path(&self, params: &Value) -> std::result::Result<PathBuf, (i64, String)> {
+        let path = PathBuf::from(params["path"].as_str().unwrap_or_default());
+        if !path.is_absolute() {
+            return Err((
+                INVALID_PARAMS,
+                format!("path must be absolute: {}", path.display()),
+            ));
+        }
+        if !path.starts_with(&self.cwd) {
+            return Err((
+                -32603,
+                format!("{} is outside the session directory", path.display()),
+            ));
+        }
+        Ok(path)
+    }
+
+    async fn shutdown(mut self) {
+        drop(self.stdin);
+        let _ = self.child.kill().await;
+    }
+}
+
+/// Run `rat check-agent`; returns the process exit code (1 when a check failed)
+pub async fn run(spec: &ExternalAgentSpec, args: &CheckAgentArgs) -> Result<i32> {
+    let dir = tempfile::tempdir()?;
+    let cwd = dir.path().canonicalize()?;
+    std::fs::write(cwd.join(CHECK_FILE), CHECK_FILE_CONTENT)?;
+
+    let mut agent = AgentConn::spawn(spec, &cwd)?;
+    let mut report = Report::default();
+    if let Err(e) = run_checks(&mut agent, args, &mut report).await {
+        report.push("connection", Status::Fail, format!("{:#}", e));
+    }
+    let garbage = &agent.observed.garbage;
+    match garbage.first() {
+        Some(line) => report.push(
+            "stdout",
+            Status::Fail,
+            format!(
+                "{} line(s) aren't JSON-RPC, e.g. {:?}",
+                garbage.len(),
+                line.chars().take(60).collect::<String>()
+            ),
+        ),
+        None => report.push("stdout", Status::Pass, "only JSON-RPC messages"),
+    }
+    agent.shutdown().await;
+
+    let command = std::iter::once(spec.path.as_str())
+        .chain(spec.args.iter().map(String::as_str))
+        .collect::<Vec<_>>()
+        .join(" ");
+    if args.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&json!({
+                "agent": command,
+                "checks": &report.checks,
+                "passed": report.count(Status::Fail) == 0,
+            }))?
+        );
+    } else {
+        print_report(&command, &report);
+    }
+    Ok(if report.count(Status::Fail) > 0 { 1 } else { 0 })
+}
+
+fn print_report(command: &str, report: &Report) {
+    println!("ACP checks for {}\n", command);
+    for check in &report.checks {
+        println!(
+            "  {}  {:<16} {}",
+            check.status.label(),
+            check.name,
+            check.detail
+        );
+    }
+    println!(
+        "\n{} passed, {} warnings, {} failed, {} skipped",
+        report.count(Status::Pass),
+        report.count(Status::Warn),
+        report.count(Status::Fail),
+        report.count(Status::Skip)
+    );
+}
+
+fn text_prompt(session_id: &str, text: &str) -> Value {
+    json!({"sessionId": session_id, "prompt": [{"type": "text", "text": text}]})
+}
+
+/// Why a reply isn't the one a check wanted
+fn unexpected(reply: Option<Reply>, timeout: Duration) -> String {
+    match reply {
+        None => format!("no reply within {}s", timeout.as_secs()),
+        Some(Reply::Error { code, message }) => format!("error {}: {}", code, message),
+        Some(Reply::Result(result)) => format!("unexpected result {}", result),
+    }
+}
+
+async fn run_checks(
+    agent: &mut AgentConn,
+    args: &CheckAgentArgs,
+    report: &mut Report,
+) -> Result<()> {
+    let timeout = Duration::from_secs(args.timeout);
+    let session_checks = [
+        "invalid params",
+        "prompt turn",
+        "fs callbacks",
+        "cancellation",
+        "session/load",
+    ];
+
+    // initialize and what the agent says it can do
+    let params = json!({
+        "protocolVersion": PROTOCOL_VERSION,
+        "clientCapabilities": {"fs": {"readTextFile": true, "writeTextFile": true}, "terminal": false},
+    });
+    let init = match agent.request("initialize", params, timeout).await? {
+        Some(Reply::Result(result)) => result,
+        reply => {
+            report.push("initialize", Status::Fail, unexpected(reply, timeout));
+            let mut rest = vec!["capabilities", "unknown method", "session/new"];
+            rest.extend(session_checks);
+            report.skip(&rest, "needs initialize");
+            return Ok(());
+        }
+    };
+    match init["protocolVersion"].as_u64() {
+        Some(PROTOCOL_VERSION) => report.push("initialize", Status::Pass, "protocol version 1"),
+        Some(version) => report.push(
+            "initialize",
+            Status::Warn,
+            format!(
+                "agent speaks protocol version {}; RAT speaks {}",
+                version, PROTOCOL_VERSION
+            ),
+        ),
+        None => report.push(
+            "initialize",
+            Status::Fail,
+            "response has no protocolVersion",
+        ),
+    }
+
+    let caps = &init["agentCapabilities"];
+    let load_session = caps["loadSession"].as_bool().unwrap_or(false);
+    if caps.is_object() {
+        let yes_no = |value: &Value| {
+            if value.as_bool().unwrap_or(false) {
+                "yes"
+            } else {
+                "no"
+            }
+        };
+        let auth: Vec<&str> = init["authMethods"]
+            .as_array()
+            .map(|methods| methods.iter().filter_map(|m| m["id"].as_str()).collect())
+            .unwrap_or_default();
+        report.push(
+            "capabilities",
+            Status::Pass,
+            format!(
+                "loadSession: {}, images: {}, audio: {}, embedded context: {}, auth methods: {}",
+                yes_no(&caps["loadSession"]),
+                yes_no(&caps["promptCapabilities"]["image"]),
+                yes_no(&caps["promptCapabilities"]["audio"]),
+                yes_no(&caps["promptCapabilities"]["embeddedContext"]),
+                if auth.is_empty() {
+                    "none".to_string()
+                } else {
+                    auth.join(", ")
+                }
+            ),
+        );
+    } else {
+        report.push(
+            "capabilities",
+            Status::Warn,
+            "no agentCapabilities; RAT assumes none",
+        );
+    }
+
+    // Errors come back with the JSON-RPC code for what went wrong
+    match agent
+        .request("rat/check-unknown-method", json!({}), timeout)
+        .await?
+    {
+        Some(Reply::Error {
+            code: METHOD_NOT_FOUND,
+            ..
+        }) => report.push("unknown method", Status::Pass, "method not found (-32601)"),
+        Some(Reply::Error { code, .. }) => report.push(
+            "unknown method",
+            Status::Warn,
+            format!("error {} instead of method not found (-32601)", code),
+        ),
+        reply => report.push("unknown method", Status::Fail, unexpected(reply, timeout)),
+    }
+
+    // Session lifecycle
+    let params = json!({"cwd": agent.cwd, "mcpServers": []});
+    let session_id = match agent.request("session/new", params, timeout).await? {
+        Some(Reply::Result(result)) if result["sessionId"].is_string() => {
+            let id = result["sessionId"].as_str().unwrap_or_default().to_string();
+            report.push("session/new", Status::Pass, format!("session {}", id));
+            id
+        }
+        Some(Reply::Error {
+            code: AUTH_REQUIRED,
+            ..
+        }) => {
+            report.push(
+                "session/new",
+                Status::Fail,
+                "authentication required; log in with the agent's own CLI and check again",
+            );
+            report.skip(&session_checks, "needs a session");
+            return Ok(());
+        }
+        reply => {
+            report.push("session/new", Status::Fail, unexpected(reply, timeout));
+            report.skip(&session_checks, "needs a session");
+            return Ok(());
+        }
+    };
+
+    let params = text_prompt("rat-check-no-such-session", "hello");
+    match agent.request("session/prompt", params, timeout).await? {
+        Some(Reply::Error {
+            code: INVALID_PARAMS,
+            ..
+        }) => report.push(
+            "invalid params",
+            Status::Pass,
+            "prompt for an unknown session refused (-32602)",
+        ),
+        Some(Reply::Error { code, .. }) => report.push(
+            "invalid params",
+            Status::Warn,
+            format!(
+                "prompt for an unknown session refused with {} instead of -32602",
+                code
+            ),
+        ),
+        Some(Reply::Result(_)) => report.push(
+            "invalid params",
+            Status::Fail,
+            "accepted a prompt for a session that doesn't exist",
+        ),
+        None => report.push("invalid params", Status::Fail, unexpected(None, timeout)),
+    }
+
+    if args.no_prompt {
+        report.skip(
+            &["prompt turn", "fs callbacks", "cancellation"],
+            "--no-prompt",
+        );
+    } else {
+        check_prompt_turn(agent, &session_id, timeout, report).await?;
+        check_cancellation(agent, &session_id, timeout, report).await?;
+    }
+
+    if load_session {
+        agent.observed.updates.clear();
+        let params = json!({"sessionId": session_id, "cwd": agent.cwd, "mcpServers": []});
+        match agent.request("session/load", params, timeout).await? {
+            Some(Reply::Result(_)) => report.push(
+                "session/load",
+                Status::Pass,
+                format!("{} update(s) replayed", agent.observed.updates.len()),
+            ),
+            reply => report.push("session/load", Status::Fail, unexpected(reply, timeout)),
+        }
+    } else {
+        report.push(
+            "session/load",
+            Status::Skip,
+            "agent doesn't advertise loadSession",
+        );
+    }
+    Ok(())
+}
+
+/// A whole turn that should read a file through the client
+async fn check_prompt_turn(
+    agent: &mut AgentConn,
+    session_id: &str,
+    timeout: Duration,
+    report: &mut Report,
+) -> Result<()> {
+    agent.observed.updates.clear();
+    agent.observed.requests.clear();
+    let text = format!(
+        "This is an automated protocol check by RAT. Read the file {} in the working \
+         directory and reply with its contents only.",
+        CHECK_FILE
+    );
+    let reply = agent
+        .request("session/prompt", text_prompt(session_id, &text), timeout)
+        .await?;
+    let stop = match reply {
+        Some(Reply::Result(result)) => result["stopReason"].as_str().map(str::to_string),
+        reply => {
+            report.push("prompt turn", Status::Fail, unexpected(reply, timeout));
+            report.skip(&["fs callbacks"], "needs a prompt turn");
+            return Ok(());
+        }
+    };
+
+    let updates = &agent.observed.updates;
+    let foreign = updates.iter().find(|u| u["sessionId"] != session_id);
+    let unknown: Vec<&str> = updates
+        .iter()
+        .filter_map(|u| u["update"]["sessionUpdate"].as_str())
+        .filter(|kind| !UPDATE_KINDS.contains(kind))
+        .collect();
+    match stop.as_deref() {
+        Some(stop) if !STOP_REASONS.contains(&stop) => report.push(
+            "prompt turn",
+            Status::Fail,
+            format!("stopReason {:?} isn't one ACP defines", stop),
+        ),
+        None => report.push("prompt turn", Status::Fail, "response has no stopReason"),
+        Some(_) if foreign.is_some() => report.push(
+            "prompt turn",
+            Status::Fail,
+            format!(
+                "update for session {} during a turn of {}",
+                foreign
+                    .map(|u| u["sessionId"].to_string())
+                    .unwrap_or_default(),
+                session_id
+            ),
+        ),
+        Some(stop) if !unknown.is_empty() => report.push(
+            "prompt turn",
+            Status::Warn,
+            format!("{}, but unknown update kinds: {}", stop, unknown.join(", ")),
+        ),
+        Some(stop) => report.push(
+            "prompt turn",
+            Status::Pass,
+            format!("{} after {} update(s)", stop, updates.len()),
+        ),
+    }
+
+    let reads: Vec<&str> = agent
+        .observed
+        .requests
+        .iter()
+        .filter(|(method, _)| method == "fs/read_text_file")
+        .filter_map(|(_, params)| params["path"].as_str())
+        .collect();
+    match reads.iter().find(|path| !Path::new(path).is_absolute()) {
+        Some(path) => report.push(
+            "fs callbacks",
+            Status::Fail,
+            format!("relative path {}; ACP requires absolute paths", path),
+        ),
+        None if reads.is_empty() => report.push(
+            "fs callbacks",
+            Status::Warn,
+            "no fs/read_text_file; RAT can't show or gate the agent's file access",
+        ),
+        None => report.push(
+            "fs callbacks",
+            Status::Pass,
+            format!("read {}", reads.join(", ")),
+        ),
+    }
+    Ok(())
+}
+
+/// A long turn cancelled once it has started streaming
+async fn check_cancellation(
+    agent: &mut AgentConn,
+    session_id: &str,
+    timeout: Duration,
+    report: &mut Report,
+) -> Result<()> {
+    agent.observed.updates.clear();
+    let text = "This is an automated protocol check by RAT. Count from 1 to 500, one number \
+                per line, with a short sentence about each number.";
+    let id = agent
+        .send_request("session/prompt", text_prompt(session_id, text))
+        .await?;
+    let started = agent
+        .pump(id, Instant::now() + CANCEL_AFTER, |observed| {
+            !observed.updates.is_empty()
+        })
+        .await?;
+    if let Pumped::Reply(reply) = started {
+        let detail = match reply {
+            Reply::Result(result) => format!(
+                "turn ended ({}) before it could be cancelled",
+                result["stopReason"].as_str().unwrap_or("no stopReason")
+            ),
+            Reply::Error { code, message } => format!("prompt failed: error {}: {}", code, message),
+        };
+        report.push("cancellation", Status::Warn, detail);
+        return Ok(());
+    }
+
+    agent
+        .notify("session/cancel", json!({"sessionId": session_id}))
+        .await?;
+    let cancelled_at = Instant::now();
+    match agent.pump(id, cancelled_at + timeout, |_| false).await? {
+        Pumped::Reply(Reply::Result(result)) if result["stopReason"] == "cancelled" => report.push(
+            "cancellation",
+            Status::Pass,
+            format!(
+                "stopped {} ms after session/cancel",
+                cancelled_at.elapsed().as_millis()
+            ),
+        ),
+        Pumped::Reply(Reply::Result(result)) => report.push(
+            "cancellation",
+            Status::Fail,
+            format!(
+                "ended with {} after session/cancel; ACP requires cancelled",
+                result["stopReason"]
+            ),
+        ),
+        Pumped::Reply(Reply::Error { code, .. }) => report.push(
+            "cancellation",
+            Status::Fail,
+            format!(
+                "error {} after session/cancel; ACP requires the cancelled stop reason",
+                code
+            ),
+        ),
+        Pumped::Condition | Pumped::TimedOut => report.push(
+            "cancellation",
+            Status::Fail,
+            format!("no reply within {}s of session/cancel", timeout.as_secs()),
+        ),
+    }
+    Ok(())
+}
@@ -0,0 +1,112 @@
+use anyhow::{Context, Result};
+use clap::Subcommand;
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigAction {
+    /// Print a setting, e.g. `rat config get agents.default_agent`
+    Get {
+        /// Dotted key path
+        key: String,
+    },
+    /// Change a setting; the value must match the setting's type
+    Set {
+        /// Dotted key path
+        key: String,
+        /// New value (strings as-is, lists as TOML, e.g. '["gemini"]')
+        value: String,
+    },
+    /// Open the config file in $VISUAL / $EDITOR and validate the result
+    Edit,
+    /// Check the config file for errors
+    Validate,
+    /// Print the config file path
+    Path,
+}
+
+/// Config file used by `rat config`: `--config` if given, else the default path
+pub fn config_path(explicit: Option<&str>) -> Result<PathBuf> {
+    match explicit {
+        Some(path) => Ok(PathBuf::from(path)),
+        None => Config::get_default_config_file(),
+    }
+}
+
+/// Run an `rat config` action; returns the process exit code
+pub async fn run(action: &ConfigAction, path: &Path) -> Result<i32> {
+    match action {
+        ConfigAction::Get { key } => {
+            let config = load(path).await?;
+            match config.get_value(key)? {
+                toml::Value::String(s) => println!("{}", s),
+                toml::Value::Table(table) => print!("{}", toml::to_string_pretty(&table)?),
+                value => println!("{}", value),
+            }
+            Ok(0)
+        }
+        ConfigAction::Set { key, value } => {
+            let mut config = load(path).await?;
+            config.set_value(key, value)?;
+            config.save_values(path, &[key.as_str()]).await?;
+            println!("{} = {}", key, config.get_value(key)?);
+            Ok(0)
+        }
+        ConfigAction::Edit => {
+            if !path.exists() {
+                Config::default().save_to_file(path).await?;
+            }
+            let editor = std::env::var("VISUAL")
+                .or_else(|_| std::env::var("EDITOR"))
+                .unwrap_or_else(|_| "vi".to_string());
+            // Allow editors with arguments, e.g. EDITOR="code --wait"
+            let mut parts = editor.split_whitespace();
+            let program = parts.next().unwrap_or("vi");
+            let status = tokio::process::Command::new(program)
+                .args(parts)
+                .arg(path)
+                .status()
+                .await
+                .with_context(|| format!("Failed to launch editor: {}", editor))?;
+            if !status.success() {
+                eprintln!("Editor exited with {}", status);
+                return Ok(1);
+            }
+            report_validation(path).await
+        }
+        ConfigAction::Validate => report_validation(path).await,
+        ConfigAction::Path => {
+            println!("{}", path.display());
+            Ok(0)
+        }
+    }
+}
+
+async fn load(path: &Path) -> Result<Config> {
+    if path.exists() {
+        Config::from_file(path).await
+    } else {
+        Ok(Config::default())
+    }
+}
+
+async fn report_validation(path: &Path) -> Result<i32> {
+    let result = Config::check_file(path).await.and_then(|(config, unknown_keys)| {
+        config.validate()?;
+        Ok(unknown_keys)
+    });
+    match result {
+        Ok(unknown_keys) => {
+            for key in &unknown_keys {
+                eprintln!("{}: warning: unknown key `{}` is ignored", path.display(), key);
+            }
+            println!("{}: ok", path.display());
+            Ok(0)
+        }
+        Err(e) => {
+            eprintln!("{}: {:#}", path.display(), e);
+            Ok(1)
+        }
+    }
+}
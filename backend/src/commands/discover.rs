@@ -0,0 +1,27 @@
+use anyhow::Result;
+use std::time::Duration;
+
+use crate::discovery;
+
+/// Run `rat discover`; returns the process exit code (1 when nothing answered)
+pub async fn run(timeout: Duration, json: bool) -> Result<i32> {
+    let peers = discovery::browse(timeout).await?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&peers)?);
+    } else if peers.is_empty() {
+        eprintln!("No RAT bridges found on the local network");
+    } else {
+        println!("{:<28} {:<10} URL", "NAME", "VERSION");
+        for peer in &peers {
+            println!(
+                "{:<28} {:<10} {}",
+                peer.name,
+                peer.version.as_deref().unwrap_or("-"),
+                peer.ws_url()
+                    .unwrap_or_else(|| format!("{} (no address)", peer.host)),
+            );
+        }
+    }
+    Ok(if peers.is_empty() { 1 } else { 0 })
+}
@@ -0,0 +1,10 @@
+//! Shell-facing subcommands that manage RAT state without starting the TUI.
+
+pub mod agents;
+pub mod check_agent;
+pub mod config;
+pub mod discover;
+pub mod pairing;
+pub mod relay;
+pub mod serve;
+pub mod sessions;
@@ -0,0 +1,68 @@
+use anyhow::Result;
+use clap::Subcommand;
+
+use crate::config::Config;
+use crate::pairing::store::PairingStore;
+
+#[derive(Debug, Subcommand)]
+pub enum PairingAction {
+    /// List browsers paired with this machine
+    Devices {
+        /// Print JSON instead of a table
+        #[arg(long)]
+        json: bool,
+    },
+    /// Unpair one browser; it is disconnected within seconds
+    Revoke {
+        /// Device id or unique prefix
+        id: String,
+    },
+    /// Generate a new pairing key and unpair every browser
+    Rotate,
+}
+
+/// Run an `rat pairing` action; returns the process exit code
+pub async fn run(action: PairingAction, config: &Config) -> Result<i32> {
+    let store = PairingStore::from_config(config);
+
+    match action {
+        PairingAction::Devices { json } => {
+            let devices = store
+                .load()
+                .await?
+                .map(|state| state.devices)
+                .unwrap_or_default();
+            if json {
+                println!("{}", serde_json::to_string_pretty(&devices)?);
+            } else if devices.is_empty() {
+                println!("No paired devices; run `rat --pair` to pair a browser");
+            } else {
+                println!("{:<16}  {:<16}  {:<16}  RELAY", "ID", "PAIRED", "LAST SEEN");
+                for device in devices {
+                    println!(
+                        "{:<16}  {:<16}  {:<16}  {}",
+                        device.id,
+                        device.paired_at.format("%Y-%m-%d %H:%M"),
+                        device.last_seen.format("%Y-%m-%d %H:%M"),
+                        device.relay
+                    );
+                }
+            }
+            Ok(0)
+        }
+        PairingAction::Revoke { id } => {
+            let device = store.revoke(&id).await?;
+            println!("Unpaired {}", device.id);
+            Ok(0)
+        }
+        PairingAction::Rotate => {
+            let (state, dropped) = store.rotate().await?;
+            println!("New pairing key {}", state.fingerprint());
+            println!(
+                "Unpaired {} device(s); run `rat --pair` to pair again",
+                dropped
+            );
+            Ok(0)
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use anyhow::{Context, Result};
+use clap::Args;
+use tracing::info;
+use tokio::net::TcpListener;
+
+use crate::utils::process::shutdown_signal;
+use crate::relay::{self, RelayOptions};
+
+/// Environment variable holding the token `rat --pair` must present
+pub const TOKEN_ENV: &str = "RAT_RELAY_TOKEN";
+
+#[derive(Debug, Args)]
+pub struct RelayArgs {
+    /// Address to listen on
+    #[arg(long, default_value = "0.0.0.0")]
+    pub bind: String,
+
+    /// Port to listen on
+    #[arg(long, default_value_t = 8080)]
+    pub port: u16,
+
+    /// URL clients reach the relay on, e.g. https://relay.example.com behind a TLS proxy
+    #[arg(long)]
+    pub public_url: Option<String>,
+
+    /// Browser origin allowed to attach (repeatable; default allows any)
+    #[arg(long = "allowed-origin")]
+    pub allowed_origins: Vec<String>,
+
+    /// Trust X-Forwarded-For for client addresses (only behind your own proxy)
+    #[arg(long)]
+    pub trust_proxy: bool,
+}
+
+/// Run a self-hosted pairing relay until SIGTERM or Ctrl-C; returns the
+/// process exit code. The pairing token comes from `RAT_RELAY_TOKEN`.
+pub async fn run(args: &RelayArgs) -> Result<i32> {
+    let listener = TcpListener::bind((args.bind.as_str(), args.port))
+        .await
+        .with_context(|| format!("Failed to listen on {}:{}", args.bind, args.port))?;
+    info!("rat relay listening on {}", listener.local_addr()?);
+
+    let options = RelayOptions {
+        token: std::env::var(TOKEN_ENV)
+            .ok()
+            .filter(|token| !token.is_empty()),
+        public_url: args.public_url.clone(),
+        allowed_origins: args.allowed_origins.clone(),
+        trust_proxy: args.trust_proxy,
+    };
+    relay::serve(listener, options, shutdown_signal()).await?;
+    info!("rat relay stopped");
+    Ok(0)
+}
@@ -0,0 +1,180 @@
+use anyhow::{Context, Result};
+use clap::{Args, ValueEnum};
+use tracing::{info, warn};
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::net::TcpListener;
+
+use crate::bridge_log;
+use crate::local_ws::{self, ConnectionOptions, PoolOptions, ServeOptions};
+use crate::metrics;
+use crate::utils::process::shutdown_signal;
+
+#[derive(Debug, Args)]
+pub struct ServeArgs {
+    /// Port to listen on when systemd doesn't pass in a socket
+    #[arg(long, default_value_t = 8081)]
+    pub port: u16,
+
+    /// Address to listen on; anything but loopback needs --token
+    #[arg(long, default_value = "127.0.0.1")]
+    pub bind: IpAddr,
+
+    /// Secret clients must present, as `Authorization: Bearer <token>` or `?token=<token>`
+    #[arg(long, env = "RAT_BRIDGE_TOKEN", hide_env_values = true)]
+    pub token: Option<String>,
+
+    /// Initialized agents kept ready for new connections (0 disables pooling)
+    #[arg(long, default_value_t = 1)]
+    pub pool_size: usize,
+
+    /// Seconds a pooled agent may sit unused before it is stopped
+    #[arg(long, default_value_t = 600)]
+    pub pool_idle_secs: u64,
+
+    /// Seconds between WebSocket pings to clients (0 disables them)
+    #[arg(long, default_value_t = 30)]
+    pub ping_secs: u64,
+
+    /// Close connections after this many minutes without messages, releasing their agent (0 never does)
+    #[arg(long, default_value_t = 0)]
+    pub idle_timeout_mins: u64,
+
+    /// Gzip messages of at least this many bytes for clients that ask for it (0 never does)
+    #[arg(long, default_value_t = 16384)]
+    pub compress_threshold: usize,
+
+    /// Directory of web UI assets (e.g. a built rat-web/dist) to serve instead of the built-in page
+    #[arg(long)]
+    pub web_root: Option<PathBuf>,
+
+    /// Advertise the bridge on the LAN via mDNS; needs --token
+    #[arg(long)]
+    pub mdns: bool,
+
+    /// Serve Prometheus metrics at /metrics on the bridge port
+    #[arg(long)]
+    pub metrics: bool,
+
+    /// Write a JSONL event log per connection (handshake, frames, permissions) into this directory
+    #[arg(long, value_name = "DIR")]
+    pub bridge_log: Option<PathBuf>,
+
+    /// Where logs go; `auto` uses the journal when systemd started us
+    #[arg(long, value_enum, default_value_t = ServeLog::Auto)]
+    pub log: ServeLog,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ServeLog {
+    Auto,
+    Journald,
+    File,
+}
+
+impl ServeArgs {
+    /// Whether to log to stderr in journald's format rather than to the log file
+    pub fn logs_to_journal(&self) -> bool {
+        match self.log {
+            ServeLog::Journald => true,
+            ServeLog::File => false,
+            // systemd sets this when stderr is connected to the journal
+            ServeLog::Auto => std::env::var_os("JOURNAL_STREAM").is_some(),
+        }
+    }
+}
+
+/// Run the bridge as a long-lived service until SIGTERM or Ctrl-C; returns
+/// the process exit code
+pub async fn run(args: &ServeArgs) -> Result<i32> {
+    let listener = match inherited_listener()? {
+        Some(listener) => {
+            info!("Using the socket passed in by systemd");
+            TcpListener::from_std(listener)?
+        }
+        None => TcpListener::bind((args.bind, args.port))
+            .await
+            .with_context(|| format!("Failed to listen on {}:{}", args.bind, args.port))?,
+    };
+    if args.mdns && args.token.is_none() {
+        anyhow::bail!("--mdns tells the whole network where the bridge is; it needs --token (or RAT_BRIDGE_TOKEN)");
+    }
+    let addr = listener.local_addr()?;
+    if !addr.ip().is_loopback() && args.token.is_none() {
+        anyhow::bail!(
+            "Refusing to serve on {} without a token: anyone on the network could drive the agents. \
+             Set --token (or RAT_BRIDGE_TOKEN), or listen on 127.0.0.1",
+            addr
+        );
+    }
+    info!("rat serve listening on {}", addr);
+
+    if args.metrics {
+        metrics::enable();
+    }
+    if let Some(dir) = &args.bridge_log {
+        bridge_log::enable(dir.clone())?;
+    }
+    let pool = PoolOptions {
+        size: args.pool_size,
+        idle_ttl: Duration::from_secs(args.pool_idle_secs),
+    };
+    let connection = ConnectionOptions {
+        ping_interval: Duration::from_secs(args.ping_secs),
+        idle_timeout: (args.idle_timeout_mins > 0)
+            .then_some(Duration::from_secs(args.idle_timeout_mins * 60)),
+        compress_threshold: (args.compress_threshold > 0).then_some(args.compress_threshold),
+    };
+    let options = ServeOptions {
+        agent: local_ws::resolve_agent().await,
+        pool,
+        connection,
+        web_root: args.web_root.clone(),
+        advertise: args.mdns,
+        token: args.token.clone(),
+    };
+    local_ws::serve(listener, options, shutdown_signal()).await?;
+    info!("rat serve stopped");
+    Ok(0)
+}
+
+/// The first socket handed over by systemd socket activation, as
+/// `sd_listen_fds(3)` would report it
+#[cfg(unix)]
+fn inherited_listener() -> Result<Option<std::net::TcpListener>> {
+    use std::os::unix::io::FromRawFd;
+    const SD_LISTEN_FDS_START: i32 = 3;
+
+    let for_us = std::env::var("LISTEN_PID")
+        .ok()
+        .and_then(|pid| pid.parse::<u32>().ok())
+        == Some(std::process::id());
+    let count: i32 = std::env::var("LISTEN_FDS")
+        .ok()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(0);
+    // Agents we spawn must not think the sockets are theirs
+    for var in ["LISTEN_PID", "LISTEN_FDS", "LISTEN_FDNAMES"] {
+        std::env::remove_var(var);
+    }
+    if !for_us || count < 1 {
+        return Ok(None);
+    }
+    if count > 1 {
+        warn!("systemd passed {} sockets; using the first", count);
+    }
+    // Keep the socket out of agent processes
+    if unsafe { libc::fcntl(SD_LISTEN_FDS_START, libc::F_SETFD, libc::FD_CLOEXEC) } != 0 {
+        return Err(std::io::Error::last_os_error()).context("Failed to set up the systemd socket");
+    }
+    // SAFETY: LISTEN_PID names this process, so systemd passed us this fd and nothing else owns it
+    let listener = unsafe { std::net::TcpListener::from_raw_fd(SD_LISTEN_FDS_START) };
+    listener.set_nonblocking(true)?;
+    Ok(Some(listener))
+}
+
+#[cfg(not(unix))]
+fn inherited_listener() -> Result<Option<std::net::TcpListener>> {
+    Ok(None)
+}
@@ -0,0 +1,140 @@
+use anyhow::Result;
+use clap::{Subcommand, ValueEnum};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::acp::store::SessionSummary;
+use crate::acp::SessionStore;
+use crate::config::Config;
+use crate::utils::git::GitStatus;
+
+#[derive(Debug, Subcommand)]
+pub enum SessionsAction {
+    /// List saved sessions, most recent first
+    List {
+        /// Print JSON instead of a table
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print a saved session transcript
+    Show {
+        /// Session id or unique prefix
+        id: String,
+
+        /// Print the stored JSON instead of a transcript
+        #[arg(long)]
+        json: bool,
+    },
+    /// Export a saved session to a file or stdout
+    Export {
+        /// Session id or unique prefix
+        id: String,
+
+        /// Export format
+        #[arg(long, value_enum, default_value_t = ExportFormat::Markdown)]
+        format: ExportFormat,
+
+        /// Write to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Delete a saved session
+    Delete {
+        /// Session id or unique prefix
+        id: String,
+    },
+    /// Reopen a saved session in the TUI
+    Resume {
+        /// Session id or unique prefix
+        id: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    Markdown,
+    Json,
+}
+
+/// Run an `rat sessions` action; returns the process exit code.
+/// `Resume` starts the TUI and is handled by the caller.
+pub async fn run(action: SessionsAction, config: &Config) -> Result<i32> {
+    let store = SessionStore::from_config(config);
+
+    match action {
+        SessionsAction::List { json } => {
+            let mut summaries: Vec<SessionSummary> = store
+                .list()
+                .await?
+                .iter()
+                .map(SessionSummary::from_session)
+                .collect();
+            // Sessions often share a directory; ask git once for each
+            let mut statuses: HashMap<String, Option<GitStatus>> = HashMap::new();
+            for summary in &mut summaries {
+                let Some(dir) = &summary.working_directory else {
+                    continue;
+                };
+                if !statuses.contains_key(dir) {
+                    statuses.insert(dir.clone(), GitStatus::read(Path::new(dir)).await);
+                }
+                summary.git = statuses[dir].clone();
+            }
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&summaries)?);
+            } else if summaries.is_empty() {
+                println!("No saved sessions in {}", store.dir().display());
+            } else {
+                for summary in summaries {
+                    println!(
+                        "{}  {}  {:<12} {:>4} msgs  {:<24} {}",
+                        short_id(&summary.id),
+                        summary.last_activity.format("%Y-%m-%d %H:%M"),
+                        summary.agent_name.as_deref().unwrap_or("-"),
+                        summary.message_count,
+                        summary.git.as_ref().map_or("-".to_string(), GitStatus::summary),
+                        summary.title.as_deref().unwrap_or("")
+                    );
+                }
+            }
+            Ok(0)
+        }
+        SessionsAction::Show { id, json } => {
+            let session = store.load(&id).await?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&session)?);
+            } else {
+                print!("{}", session.to_markdown());
+            }
+            Ok(0)
+        }
+        SessionsAction::Export { id, format, output } => {
+            let session = store.load(&id).await?;
+            let content = match format {
+                ExportFormat::Markdown => session.to_markdown(),
+                ExportFormat::Json => serde_json::to_string_pretty(&session)? + "\n",
+            };
+            match output {
+                Some(path) => {
+                    tokio::fs::write(&path, content).await?;
+                    eprintln!("Exported {} to {}", session.id, path.display());
+                }
+                None => print!("{}", content),
+            }
+            Ok(0)
+        }
+        SessionsAction::Delete { id } => {
+            let path = store.delete(&id).await?;
+            println!("Deleted {}", path.display());
+            Ok(0)
+        }
+        SessionsAction::Resume { .. } => Err(anyhow::anyhow!(
+            "resume is handled by the TUI entry point"
+        )),
+    }
+}
+
+fn short_id(id: &str) -> &str {
+    id.get(..8).unwrap_or(id)
+}
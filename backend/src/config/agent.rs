@@ -8,9 +8,16 @@ pub struct AgentConfig {
     pub claude_code: ClaudeCodeConfig,
     pub gemini: GeminiConfig,
     pub default_agent: String,
+    /// Agents connected with a session tab opened for each at startup
     pub auto_connect: Vec<String>,
     pub connection_timeout_seconds: u64,
     pub max_concurrent_agents: usize,
+    /// Crashes within `restart_window_seconds` after which an agent is no
+    /// longer restarted until the user retries it
+    #[serde(default = "default_max_restarts")]
+    pub max_restarts: u32,
+    #[serde(default = "default_restart_window_seconds")]
+    pub restart_window_seconds: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,6 +30,25 @@ pub struct ClaudeCodeConfig {
     pub temperature: Option<f32>,
     pub timeout_seconds: u64,
     pub auto_install: bool,
+    /// Extra arguments for the agent command
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Extra environment variables for the agent process
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Command run when the agent asks for authentication, e.g.
+    /// `["claude", "/login"]`; found next to the installed agent by default
+    #[serde(default)]
+    pub login_command: Option<Vec<String>>,
+    /// `--permission-prompt-tool`; empty to omit
+    #[serde(default = "default_permission_prompt_tool")]
+    pub permission_prompt_tool: String,
+    /// `--allowedTools`; empty to omit
+    #[serde(default = "default_allowed_tools")]
+    pub allowed_tools: Vec<String>,
+    /// `--disallowedTools`; empty to omit
+    #[serde(default)]
+    pub disallowed_tools: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -35,6 +61,35 @@ pub struct GeminiConfig {
     pub temperature: Option<f32>,
     pub timeout_seconds: u64,
     pub auto_install: bool,
+    /// Extra arguments for the agent command
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Extra environment variables for the agent process
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Command run when the agent asks for authentication
+    #[serde(default)]
+    pub login_command: Option<Vec<String>>,
+}
+
+fn default_max_restarts() -> u32 {
+    3
+}
+
+fn default_restart_window_seconds() -> u64 {
+    300
+}
+
+fn default_permission_prompt_tool() -> String {
+    "mcp__acp__permission".to_string()
+}
+
+// Both ACP-bridged FS tools and Claude's built-in edit tools
+fn default_allowed_tools() -> Vec<String> {
+    ["mcp__acp__read", "mcp__acp__write", "Read", "Write", "Edit", "MultiEdit"]
+        .iter()
+        .map(|tool| tool.to_string())
+        .collect()
 }
 
 impl Default for AgentConfig {
@@ -46,6 +101,8 @@ impl Default for AgentConfig {
             auto_connect: vec!["claude-code".to_string()],
             connection_timeout_seconds: 30,
             max_concurrent_agents: 5,
+            max_restarts: default_max_restarts(),
+            restart_window_seconds: default_restart_window_seconds(),
         }
     }
 }
@@ -61,6 +118,12 @@ impl Default for ClaudeCodeConfig {
             temperature: Some(0.7),
             timeout_seconds: 300,
             auto_install: true,
+            args: Vec::new(),
+            env: HashMap::new(),
+            login_command: None,
+            permission_prompt_tool: default_permission_prompt_tool(),
+            allowed_tools: default_allowed_tools(),
+            disallowed_tools: Vec::new(),
         }
     }
 }
@@ -76,6 +139,9 @@ impl Default for GeminiConfig {
             temperature: Some(0.7),
             timeout_seconds: 300,
             auto_install: true,
+            args: Vec::new(),
+            env: HashMap::new(),
+            login_command: None,
         }
     }
 }
@@ -94,6 +160,12 @@ impl AgentConfig {
             ));
         }
 
+        if self.restart_window_seconds == 0 {
+            return Err(anyhow::anyhow!(
+                "restart_window_seconds must be greater than 0"
+            ));
+        }
+
         let valid_agents = ["claude-code", "gemini"];
         if !valid_agents.contains(&self.default_agent.as_str()) {
             return Err(anyhow::anyhow!(
@@ -134,6 +206,12 @@ impl AgentConfig {
         if other.max_concurrent_agents != AgentConfig::default().max_concurrent_agents {
             self.max_concurrent_agents = other.max_concurrent_agents;
         }
+        if other.max_restarts != default_max_restarts() {
+            self.max_restarts = other.max_restarts;
+        }
+        if other.restart_window_seconds != default_restart_window_seconds() {
+            self.restart_window_seconds = other.restart_window_seconds;
+        }
     }
 
     pub fn get_agent_command_path(&self, agent_name: &str) -> Option<PathBuf> {
@@ -190,6 +268,8 @@ impl ClaudeCodeConfig {
             }
         }
 
+        validate_login_command(&self.login_command)?;
+
         Ok(())
     }
 
@@ -218,6 +298,55 @@ impl ClaudeCodeConfig {
         if other.auto_install != ClaudeCodeConfig::default().auto_install {
             self.auto_install = other.auto_install;
         }
+        if !other.args.is_empty() {
+            self.args = other.args;
+        }
+        self.env.extend(other.env);
+        if other.login_command.is_some() {
+            self.login_command = other.login_command;
+        }
+        if other.permission_prompt_tool != default_permission_prompt_tool() {
+            self.permission_prompt_tool = other.permission_prompt_tool;
+        }
+        if other.allowed_tools != default_allowed_tools() {
+            self.allowed_tools = other.allowed_tools;
+        }
+        if !other.disallowed_tools.is_empty() {
+            self.disallowed_tools = other.disallowed_tools;
+        }
+    }
+
+    /// Tool flags passed to Claude Code so file edits and tools work. The
+    /// `RAT_PERMISSION_PROMPT_TOOL`, `RAT_ALLOWED_TOOLS` and
+    /// `RAT_DISALLOWED_TOOLS` variables (comma-separated) override the config.
+    pub fn tool_args(&self) -> Vec<String> {
+        let env_var = |name: &str| std::env::var(name).ok();
+        let mut args = Vec::new();
+
+        let permission_tool = env_var("RAT_PERMISSION_PROMPT_TOOL")
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| self.permission_prompt_tool.clone());
+        if !permission_tool.is_empty() {
+            args.push("--permission-prompt-tool".to_string());
+            args.push(permission_tool);
+        }
+
+        let allowed = env_var("RAT_ALLOWED_TOOLS")
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| self.allowed_tools.join(","));
+        if !allowed.is_empty() {
+            args.push("--allowedTools".to_string());
+            args.push(allowed);
+        }
+
+        let disallowed = env_var("RAT_DISALLOWED_TOOLS")
+            .unwrap_or_else(|| self.disallowed_tools.join(","));
+        if !disallowed.trim().is_empty() {
+            args.push("--disallowedTools".to_string());
+            args.push(disallowed);
+        }
+
+        args
     }
 
     pub fn get_command_path(&self) -> Option<PathBuf> {
@@ -246,6 +375,8 @@ impl GeminiConfig {
             }
         }
 
+        validate_login_command(&self.login_command)?;
+
         Ok(())
     }
 
@@ -274,6 +405,13 @@ impl GeminiConfig {
         if other.auto_install != GeminiConfig::default().auto_install {
             self.auto_install = other.auto_install;
         }
+        if !other.args.is_empty() {
+            self.args = other.args;
+        }
+        self.env.extend(other.env);
+        if other.login_command.is_some() {
+            self.login_command = other.login_command;
+        }
     }
 
     pub fn get_command_path(&self) -> Option<PathBuf> {
@@ -283,3 +421,80 @@ impl GeminiConfig {
         })
     }
 }
+
+fn validate_login_command(login_command: &Option<Vec<String>>) -> Result<()> {
+    match login_command {
+        Some(command) if command.first().is_none_or(|program| program.is_empty()) => Err(
+            anyhow::anyhow!("login_command must start with a program to run"),
+        ),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ClaudeCodeConfig;
+
+    fn with_env<T: FnOnce()>(kvs: &[(&str, &str)], f: T) {
+        // Save old
+        let saved: Vec<(String, Option<String>)> = kvs
+            .iter()
+            .map(|(k, _)| (k.to_string(), std::env::var(k).ok()))
+            .collect();
+        // Set new
+        for (k, v) in kvs.iter() {
+            std::env::set_var(k, v);
+        }
+        f();
+        // Restore
+        for (k, v) in saved.into_iter() {
+            match v {
+                Some(val) => std::env::set_var(k, val),
+                None => std::env::remove_var(k),
+            }
+        }
+    }
+
+    #[test]
+    fn claude_tool_args_default_config_and_overrides() {
+        // Default case (no env): should include permission tool and allowed list with Edit/MultiEdit
+        std::env::remove_var("RAT_PERMISSION_PROMPT_TOOL");
+        std::env::remove_var("RAT_ALLOWED_TOOLS");
+        std::env::remove_var("RAT_DISALLOWED_TOOLS");
+        let args = ClaudeCodeConfig::default().tool_args();
+        let joined = args.join(" ");
+        assert!(joined.contains("--permission-prompt-tool mcp__acp__permission"));
+        assert!(joined.contains("--allowedTools"));
+        assert!(joined.contains("Edit"));
+        assert!(joined.contains("MultiEdit"));
+        assert!(!joined.contains("--disallowedTools"));
+
+        // Settings from the config file
+        let config = ClaudeCodeConfig {
+            permission_prompt_tool: String::new(),
+            allowed_tools: vec!["Read".to_string(), "Grep".to_string()],
+            disallowed_tools: vec!["Bash".to_string()],
+            ..ClaudeCodeConfig::default()
+        };
+        assert_eq!(
+            config.tool_args(),
+            vec!["--allowedTools", "Read,Grep", "--disallowedTools", "Bash"]
+        );
+
+        // Override all via env
+        with_env(
+            &[
+                ("RAT_PERMISSION_PROMPT_TOOL", "custom_perm"),
+                ("RAT_ALLOWED_TOOLS", "Foo,Bar"),
+                ("RAT_DISALLOWED_TOOLS", "Baz"),
+            ],
+            || {
+                let args = config.tool_args();
+                let joined = args.join(" ");
+                assert!(joined.contains("--permission-prompt-tool custom_perm"));
+                assert!(joined.contains("--allowedTools Foo,Bar"));
+                assert!(joined.contains("--disallowedTools Baz"));
+            },
+        );
+    }
+}
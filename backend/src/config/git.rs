@@ -0,0 +1,182 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// Placeholders `commit_message` may use
+const MESSAGE_FIELDS: [&str; 4] = ["summary", "session_id", "agent", "files"];
+
+/// How RAT works with the git repository agents edit.
+///
+/// ```toml
+/// [git]
+/// auto_commit = "turn"
+/// commit_message = "{agent}: {summary}\n\nRAT-Session: {session_id}"
+///
+/// [git.pull_request]
+/// remote = "origin"
+/// branch = "rat/{session_id}"
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct GitConfig {
+    /// Commit the files agents write, so each change can be reverted
+    pub auto_commit: AutoCommit,
+    /// Message of automatic commits. `{summary}` is the first line of the
+    /// prompt, `{session_id}` and `{agent}` name the session, and
+    /// `{files}` lists the files committed.
+    pub commit_message: String,
+    pub pull_request: PullRequestConfig,
+}
+
+/// Where the pull request action pushes a session's edits
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct PullRequestConfig {
+    pub remote: String,
+    /// Branch pushed to; `{session_id}` is the session id's first 8 characters
+    pub branch: String,
+    /// Open pull requests as drafts
+    pub draft: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AutoCommit {
+    #[default]
+    Off,
+    /// A commit for every file written
+    Edit,
+    /// One commit with everything written during a turn, when it ends
+    Turn,
+}
+
+impl Default for GitConfig {
+    fn default() -> Self {
+        Self {
+            auto_commit: AutoCommit::Off,
+            commit_message: "{agent}: {summary}\n\nRAT-Session: {session_id}".to_string(),
+            pull_request: PullRequestConfig::default(),
+        }
+    }
+}
+
+impl Default for PullRequestConfig {
+    fn default() -> Self {
+        Self {
+            remote: "origin".to_string(),
+            branch: "rat/{session_id}".to_string(),
+            draft: false,
+        }
+    }
+}
+
+impl GitConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.commit_message.trim().is_empty() {
+            return Err(anyhow::anyhow!("commit_message must not be empty"));
+        }
+        let mut rest = self.commit_message.as_str();
+        while let Some(start) = rest.find('{') {
+            let Some(end) = rest[start..].find('}') else {
+                break;
+            };
+            let field = &rest[start + 1..start + end];
+            if !MESSAGE_FIELDS.contains(&field) {
+                return Err(anyhow::anyhow!(
+                    "commit_message: unknown placeholder {{{}}} (expected one of {:?})",
+                    field,
+                    MESSAGE_FIELDS
+                ));
+            }
+            rest = &rest[start + end + 1..];
+        }
+        if self.pull_request.remote.is_empty() {
+            return Err(anyhow::anyhow!("pull_request.remote must not be empty"));
+        }
+        if self.pull_request.branch.is_empty() {
+            return Err(anyhow::anyhow!("pull_request.branch must not be empty"));
+        }
+        Ok(())
+    }
+
+    pub fn merge_with(&mut self, other: GitConfig) {
+        let defaults = GitConfig::default();
+        if other.auto_commit != defaults.auto_commit {
+            self.auto_commit = other.auto_commit;
+        }
+        if other.commit_message != defaults.commit_message {
+            self.commit_message = other.commit_message;
+        }
+        let defaults = defaults.pull_request;
+        if other.pull_request.remote != defaults.remote {
+            self.pull_request.remote = other.pull_request.remote;
+        }
+        if other.pull_request.branch != defaults.branch {
+            self.pull_request.branch = other.pull_request.branch;
+        }
+        if other.pull_request.draft != defaults.draft {
+            self.pull_request.draft = other.pull_request.draft;
+        }
+    }
+
+    /// `commit_message` with its placeholders filled in. The summary is cut
+    /// to fit a subject line.
+    pub fn commit_message(
+        &self,
+        summary: Option<&str>,
+        session_id: &str,
+        agent: &str,
+        files: &[String],
+    ) -> String {
+        let summary = summary.map(str::trim).filter(|s| !s.is_empty());
+        let summary = match summary {
+            Some(summary) if summary.chars().count() > 60 => {
+                format!("{}…", summary.chars().take(59).collect::<String>())
+            }
+            Some(summary) => summary.to_string(),
+            None => "update files".to_string(),
+        };
+        self.commit_message
+            .replace("{summary}", &summary)
+            .replace("{session_id}", session_id)
+            .replace("{agent}", agent)
+            .replace("{files}", &files.join(", "))
+    }
+}
+
+impl PullRequestConfig {
+    /// `branch` for a session
+    pub fn branch_name(&self, session_id: &str) -> String {
+        self.branch
+            .replace("{session_id}", session_id.get(..8).unwrap_or(session_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fills_in_the_commit_message() {
+        let config = GitConfig {
+            commit_message: "{summary}\n\n{files}\nSession: {session_id} ({agent})".to_string(),
+            ..GitConfig::default()
+        };
+        assert!(config.validate().is_ok());
+        let prompt = "Rename the config loader and update every caller to the new name please";
+        let files = ["src/config.rs".to_string(), "src/main.rs".to_string()];
+        assert_eq!(
+            config.commit_message(Some(prompt), "abc123", "gemini", &files),
+            "Rename the config loader and update every caller to the new…\n\n\
+             src/config.rs, src/main.rs\nSession: abc123 (gemini)"
+        );
+        assert!(GitConfig::default()
+            .commit_message(None, "abc123", "gemini", &[])
+            .starts_with("gemini: update files\n"));
+
+        let config = GitConfig {
+            commit_message: "{summary} {prompt}".to_string(),
+            ..GitConfig::default()
+        };
+        assert!(config.validate().is_err());
+    }
+}
@@ -0,0 +1,257 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// Services and tools RAT works with on the user's behalf. Without a token,
+/// GitHub and GitLab are reached through the `gh` and `glab` CLIs and their
+/// logins.
+///
+/// ```toml
+/// [integrations.github]
+/// token_env = "GITHUB_TOKEN"
+///
+/// [integrations.gitlab]
+/// url = "https://gitlab.example.com"
+/// token_env = "GITLAB_TOKEN"
+///
+/// [integrations.jira]
+/// url = "https://example.atlassian.net"
+/// email = "me@example.com"
+/// token_env = "JIRA_TOKEN"
+///
+/// [integrations.tmux]
+/// split = "horizontal"
+/// output_pane = true
+///
+/// [integrations.editor]
+/// kind = "vscode"
+/// follow = true
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct IntegrationsConfig {
+    pub github: ForgeConfig,
+    pub gitlab: ForgeConfig,
+    pub jira: JiraConfig,
+    pub tmux: TmuxConfig,
+    pub editor: EditorConfig,
+}
+
+/// Where a GitHub or GitLab instance lives and how to sign in to its API
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct ForgeConfig {
+    /// Web URL of a self-hosted instance; github.com or gitlab.com when unset
+    pub url: Option<String>,
+    /// API token; the API is used instead of the CLI when one is set
+    pub token: Option<String>,
+    /// Environment variable holding the token, to keep it out of the file
+    pub token_env: Option<String>,
+}
+
+/// A Jira site, for attaching issues to prompts
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct JiraConfig {
+    pub url: Option<String>,
+    /// Account the token belongs to on Jira Cloud; without one the token is
+    /// sent as a bearer token (a Data Center personal access token)
+    pub email: Option<String>,
+    pub token: Option<String>,
+    pub token_env: Option<String>,
+}
+
+/// How RAT uses tmux when it runs inside it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct TmuxConfig {
+    /// Direction of the panes RAT opens
+    pub split: TmuxSplit,
+    /// Size of the panes RAT opens: lines or columns, or a percentage
+    pub size: String,
+    /// Show the commands agents run and their output in a tmux pane instead
+    /// of the terminal panel
+    pub output_pane: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TmuxSplit {
+    /// Side by side
+    #[default]
+    Horizontal,
+    /// One above the other
+    Vertical,
+}
+
+/// The editor RAT opens changed files and tool call locations in
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct EditorConfig {
+    /// Use the usual commands of VS Code or Neovim; `open` and `goto`
+    /// replace them
+    pub kind: Option<EditorKind>,
+    /// Command opening files, e.g. `["code", "--reuse-window", "{files}"]`;
+    /// `{files}` becomes one argument per file
+    pub open: Option<Vec<String>>,
+    /// Command showing a line of a file; `{file}` and `{line}` are filled in
+    pub goto: Option<Vec<String>>,
+    /// Address of the Neovim server, `{server}` in the commands; `$NVIM`
+    /// (set in Neovim's terminal) when unset
+    pub server: Option<String>,
+    /// Jump to the file and line of each tool call as agents work
+    pub follow: bool,
+    /// Open the files a turn changed when it ends
+    pub open_after_turn: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EditorKind {
+    Vscode,
+    Neovim,
+}
+
+impl Default for TmuxConfig {
+    fn default() -> Self {
+        Self {
+            split: TmuxSplit::Horizontal,
+            size: "50%".to_string(),
+            output_pane: false,
+        }
+    }
+}
+
+impl IntegrationsConfig {
+    pub fn validate(&self) -> Result<()> {
+        self.github.validate("github")?;
+        self.gitlab.validate("gitlab")?;
+        validate_service("jira", &self.jira.url, &self.jira.token, &self.jira.token_env)?;
+        for (name, command) in [("open", &self.editor.open), ("goto", &self.editor.goto)] {
+            if command
+                .as_ref()
+                .is_some_and(|command| command.first().is_none_or(|program| program.is_empty()))
+            {
+                return Err(anyhow::anyhow!(
+                    "editor.{} must start with a program to run",
+                    name
+                ));
+            }
+        }
+        let size = self.tmux.size.strip_suffix('%').unwrap_or(&self.tmux.size);
+        if !size.parse::<u32>().is_ok_and(|size| size > 0) {
+            return Err(anyhow::anyhow!(
+                "tmux.size must be a number of lines or columns, or a percentage like \"50%\""
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn merge_with(&mut self, other: IntegrationsConfig) {
+        self.github.merge_with(other.github);
+        self.gitlab.merge_with(other.gitlab);
+        let jira = other.jira;
+        if jira.url.is_some() {
+            self.jira.url = jira.url;
+        }
+        if jira.email.is_some() {
+            self.jira.email = jira.email;
+        }
+        if jira.token.is_some() || jira.token_env.is_some() {
+            self.jira.token = jira.token;
+            self.jira.token_env = jira.token_env;
+        }
+        let defaults = TmuxConfig::default();
+        if other.tmux.split != defaults.split {
+            self.tmux.split = other.tmux.split;
+        }
+        if other.tmux.size != defaults.size {
+            self.tmux.size = other.tmux.size;
+        }
+        if other.tmux.output_pane != defaults.output_pane {
+            self.tmux.output_pane = other.tmux.output_pane;
+        }
+        let editor = other.editor;
+        if editor.kind.is_some() {
+            self.editor.kind = editor.kind;
+        }
+        if editor.open.is_some() {
+            self.editor.open = editor.open;
+        }
+        if editor.goto.is_some() {
+            self.editor.goto = editor.goto;
+        }
+        if editor.server.is_some() {
+            self.editor.server = editor.server;
+        }
+        if editor.follow {
+            self.editor.follow = true;
+        }
+        if editor.open_after_turn {
+            self.editor.open_after_turn = true;
+        }
+    }
+}
+
+impl JiraConfig {
+    /// The API token, if one is configured; a named but unset variable is an error
+    pub fn token(&self) -> Result<Option<String>> {
+        resolve_token(&self.token, &self.token_env)
+    }
+}
+
+impl ForgeConfig {
+    fn validate(&self, name: &str) -> Result<()> {
+        validate_service(name, &self.url, &self.token, &self.token_env)
+    }
+
+    fn merge_with(&mut self, other: ForgeConfig) {
+        if other.url.is_some() {
+            self.url = other.url;
+        }
+        if other.token.is_some() || other.token_env.is_some() {
+            self.token = other.token;
+            self.token_env = other.token_env;
+        }
+    }
+
+    /// The API token, if one is configured; a named but unset variable is an error
+    pub fn token(&self) -> Result<Option<String>> {
+        resolve_token(&self.token, &self.token_env)
+    }
+
+    /// Host name of the configured instance, if any
+    pub fn host(&self) -> Option<String> {
+        let url = url::Url::parse(self.url.as_deref()?).ok()?;
+        url.host_str().map(String::from)
+    }
+}
+
+fn validate_service(
+    name: &str,
+    url: &Option<String>,
+    token: &Option<String>,
+    token_env: &Option<String>,
+) -> Result<()> {
+    if let Some(raw) = url {
+        let url = url::Url::parse(raw).map_err(|e| anyhow::anyhow!("{}.url: {}", name, e))?;
+        if url.scheme() != "http" && url.scheme() != "https" {
+            return Err(anyhow::anyhow!("{}.url must be http:// or https://", name));
+        }
+    }
+    if token.is_some() && token_env.is_some() {
+        return Err(anyhow::anyhow!(
+            "{}: set either token or token_env, not both",
+            name
+        ));
+    }
+    Ok(())
+}
+
+fn resolve_token(token: &Option<String>, token_env: &Option<String>) -> Result<Option<String>> {
+    match token_env {
+        Some(var) => std::env::var(var)
+            .map(Some)
+            .map_err(|_| anyhow::anyhow!("token_env names {}, which is not set", var)),
+        None => Ok(token.clone()),
+    }
+}
@@ -0,0 +1,94 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Profile keys that describe an agent launch rather than a config overlay
+pub const LAUNCH_KEYS: [&str; 4] = ["agent", "model", "cwd", "env"];
+
+/// Agents a launch profile can be based on
+const LAUNCHABLE_AGENTS: [&str; 2] = ["claude-code", "gemini"];
+
+/// A profile with an `agent` key, e.g.
+/// `[profiles.review] agent = "claude-code", cwd = "~/work/api"`. Each one
+/// runs as its own agent process named after the profile.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct LaunchProfile {
+    #[serde(skip)]
+    pub name: String,
+    pub agent: String,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub cwd: Option<PathBuf>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+}
+
+impl LaunchProfile {
+    /// The launch settings in a profile table, or `None` for a plain overlay
+    pub fn from_table(name: &str, table: &toml::Table) -> Result<Option<Self>> {
+        if !table.contains_key("agent") {
+            return Ok(None);
+        }
+        let launch: toml::Table = table
+            .iter()
+            .filter(|(key, _)| LAUNCH_KEYS.contains(&key.as_str()))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        let mut profile: LaunchProfile = toml::Value::Table(launch)
+            .try_into()
+            .with_context(|| format!("Invalid launch settings in profile '{}'", name))?;
+        profile.name = name.to_string();
+        Ok(Some(profile))
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if !LAUNCHABLE_AGENTS.contains(&self.agent.as_str()) {
+            return Err(anyhow::anyhow!(
+                "agent must be one of: {:?}",
+                LAUNCHABLE_AGENTS
+            ));
+        }
+        if LAUNCHABLE_AGENTS.contains(&self.name.as_str()) {
+            return Err(anyhow::anyhow!(
+                "a launch profile cannot share its name with an agent"
+            ));
+        }
+        Ok(())
+    }
+
+    /// Working directory with a leading `~` expanded
+    pub fn working_dir(&self) -> Option<PathBuf> {
+        let cwd = self.cwd.as_ref()?;
+        match cwd.strip_prefix("~") {
+            Ok(rest) => dirs::home_dir().map(|home| home.join(rest)),
+            Err(_) => Some(cwd.clone()),
+        }
+    }
+
+    /// Environment for the agent process, including the model selection
+    pub fn agent_env(&self) -> HashMap<String, String> {
+        let mut env = self.env.clone();
+        if let Some(model) = &self.model {
+            let var = match self.agent.as_str() {
+                "gemini" => "GEMINI_MODEL",
+                _ => "ANTHROPIC_MODEL",
+            };
+            env.entry(var.to_string()).or_insert_with(|| model.clone());
+        }
+        env
+    }
+
+    /// One-line summary for the agent selector
+    pub fn describe(&self) -> String {
+        let mut parts = vec![self.agent.clone()];
+        if let Some(model) = &self.model {
+            parts.push(model.clone());
+        }
+        if let Some(cwd) = &self.cwd {
+            parts.push(format!("in {}", cwd.display()));
+        }
+        format!("{} ({})", self.name, parts.join(", "))
+    }
+}
@@ -0,0 +1,78 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Where RAT writes its log and when the file is rotated.
+///
+/// ```toml
+/// [logging]
+/// file = "/tmp/rat.log"
+/// max_size_mb = 10
+/// max_age_hours = 24
+/// keep = 5
+/// format = "json"
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct LoggingConfig {
+    /// Log file; defaults to `logs/rat.log` in the data directory
+    pub file: Option<PathBuf>,
+    /// Rotate once the file reaches this size (0 = never)
+    pub max_size_mb: u64,
+    /// Rotate once the file is this old (0 = never)
+    pub max_age_hours: u64,
+    /// Rotated files to keep (`rat.log.1` is the newest)
+    pub keep: usize,
+    /// `text` lines, or one JSON object per event with its spans
+    pub format: LogFormat,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            file: None,
+            max_size_mb: 10,
+            max_age_hours: 0,
+            keep: 5,
+            format: LogFormat::Text,
+        }
+    }
+}
+
+impl LoggingConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.keep == 0 && (self.max_size_mb > 0 || self.max_age_hours > 0) {
+            return Err(anyhow::anyhow!(
+                "keep must be at least 1 when rotation is enabled"
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn merge_with(&mut self, other: LoggingConfig) {
+        let defaults = LoggingConfig::default();
+        if other.file.is_some() {
+            self.file = other.file;
+        }
+        if other.max_size_mb != defaults.max_size_mb {
+            self.max_size_mb = other.max_size_mb;
+        }
+        if other.max_age_hours != defaults.max_age_hours {
+            self.max_age_hours = other.max_age_hours;
+        }
+        if other.keep != defaults.keep {
+            self.keep = other.keep;
+        }
+        if other.format != defaults.format {
+            self.format = other.format;
+        }
+    }
+}
@@ -1,14 +1,33 @@
 pub mod agent;
+pub mod git;
+pub mod integrations;
+pub mod launch;
+pub mod logging;
+pub mod permissions;
 pub mod project;
+pub mod relay;
+pub mod telemetry;
 pub mod ui;
 
 use anyhow::{Context, Result};
+use tracing::{info, warn};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
 pub use agent::AgentConfig;
+pub use git::{AutoCommit, GitConfig};
+pub use integrations::IntegrationsConfig;
+pub use launch::LaunchProfile;
+pub use logging::{LogFormat, LoggingConfig};
+pub use permissions::{PermissionsConfig, PolicyDecision};
 pub use project::ProjectConfig;
-pub use ui::UiConfig;
+pub use relay::RelayConfig;
+pub use telemetry::TelemetryConfig;
+pub use ui::{LayoutConfig, NotificationsConfig, UiConfig};
+
+/// Per-project config, found by walking up from the working directory
+pub const PROJECT_CONFIG_FILE: &str = ".rat.toml";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -16,6 +35,29 @@ pub struct Config {
     pub ui: UiConfig,
     pub project: ProjectConfig,
     pub general: GeneralConfig,
+    #[serde(default)]
+    pub permissions: PermissionsConfig,
+    #[serde(default)]
+    pub logging: LoggingConfig,
+    #[serde(default)]
+    pub relay: RelayConfig,
+    #[serde(default)]
+    pub telemetry: TelemetryConfig,
+    #[serde(default)]
+    pub git: GitConfig,
+    #[serde(default)]
+    pub integrations: IntegrationsConfig,
+    /// Named overlays selected with `--profile`, e.g. `[profiles.work.agents]`.
+    /// Profiles can also live in `<config dir>/profiles/<name>.toml`. A
+    /// profile with an `agent` key is also a [`LaunchProfile`].
+    #[serde(default)]
+    pub profiles: HashMap<String, toml::Table>,
+    /// Profile applied by [`Config::with_profile`], if any
+    #[serde(skip)]
+    pub active_profile: Option<String>,
+    // Config before the active profile was applied, so profiles don't stack
+    #[serde(skip)]
+    unprofiled: Option<Box<Config>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -35,6 +77,15 @@ impl Default for Config {
             ui: UiConfig::default(),
             project: ProjectConfig::default(),
             general: GeneralConfig::default(),
+            permissions: PermissionsConfig::default(),
+            logging: LoggingConfig::default(),
+            relay: RelayConfig::default(),
+            telemetry: TelemetryConfig::default(),
+            git: GitConfig::default(),
+            integrations: IntegrationsConfig::default(),
+            profiles: HashMap::new(),
+            active_profile: None,
+            unprofiled: None,
         }
     }
 }
@@ -54,16 +105,112 @@ impl Default for GeneralConfig {
 
 impl Config {
     pub async fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let content = tokio::fs::read_to_string(path.as_ref())
+        let (config, unknown_keys) = Self::check_file(path.as_ref()).await?;
+        for key in unknown_keys {
+            warn!("Ignoring unknown config key {} in {:?}", key, path.as_ref());
+        }
+        Ok(config)
+    }
+
+    /// Parse a config file, reporting the key path of any type error and
+    /// returning the keys RAT does not recognise (which would otherwise be
+    /// silently ignored). Keys the file leaves out keep their defaults.
+    pub async fn check_file(path: &Path) -> Result<(Self, Vec<String>)> {
+        let content = tokio::fs::read_to_string(path)
             .await
-            .with_context(|| format!("Failed to read config file: {:?}", path.as_ref()))?;
+            .with_context(|| format!("Failed to read config file: {:?}", path))?;
+        let overlay: toml::Table = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse config file: {:?}", path))?;
+
+        let mut root =
+            toml::Value::try_from(Config::default()).context("Failed to serialize config")?;
+        merge_toml(&mut root, toml::Value::Table(overlay));
+        deserialize_tracked(root)
+            .with_context(|| format!("Failed to parse config file: {:?}", path))
+    }
+
+    /// The effective config before command-line flags: the user config file
+    /// (see `user_file`; defaults without one), then the nearest project
+    /// `.rat.toml`, then `RAT_` environment overrides.
+    pub async fn load(file: Option<&Path>) -> Result<Self> {
+        let mut config = match Self::user_file(file) {
+            Some(path) => {
+                info!("Loading configuration from: {:?}", path);
+                Config::from_file(&path).await?
+            }
+            None => {
+                info!("Using default configuration");
+                Config::default()
+            }
+        };
 
-        let config: Config = toml::from_str(&content)
-            .with_context(|| format!("Failed to parse config file: {:?}", path.as_ref()))?;
+        let cwd = std::env::current_dir().context("Failed to read current directory")?;
+        if let Some(path) = find_project_config(&cwd) {
+            info!("Merging project configuration from: {:?}", path);
+            config.merge_with(Config::from_file(&path).await?);
+            config
+                .validate()
+                .with_context(|| format!("Invalid settings in {:?}", path))?;
+        }
 
+        config.apply_env_overrides()?;
         Ok(config)
     }
 
+    /// The user config file `load` reads: `file` if given, else the default
+    /// config file when there is one
+    pub fn user_file(file: Option<&Path>) -> Option<PathBuf> {
+        match file {
+            Some(path) => Some(path.to_path_buf()),
+            None => Self::get_default_config_file()
+                .ok()
+                .filter(|path| path.is_file()),
+        }
+    }
+
+    /// The files `load(file)` takes settings from, for watching: the user
+    /// config file (the default path even before it exists) and the nearest
+    /// project `.rat.toml`
+    pub fn source_files(file: Option<&Path>) -> Vec<PathBuf> {
+        let user = match file {
+            Some(path) => Some(path.to_path_buf()),
+            None => Self::get_default_config_file().ok(),
+        };
+        let project = std::env::current_dir()
+            .ok()
+            .and_then(|cwd| find_project_config(&cwd));
+        user.into_iter().chain(project).collect()
+    }
+
+    /// Write the settings at `keys` (dotted paths) as this config has them
+    /// into the file at `path`, creating it if needed. Everything else in
+    /// the file (comments, ordering, keys left unset) stays as it is.
+    pub async fn save_values(&self, path: &Path, keys: &[&str]) -> Result<()> {
+        let content = match tokio::fs::read_to_string(path).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read config file: {:?}", path))
+            }
+        };
+        let mut document: toml_edit::DocumentMut = content
+            .parse()
+            .with_context(|| format!("Failed to parse config file: {:?}", path))?;
+        for key in keys {
+            set_document_value(&mut document, key, &self.get_value(key)?)?;
+        }
+
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .with_context(|| format!("Failed to create config directory: {:?}", parent))?;
+        }
+        tokio::fs::write(path, document.to_string())
+            .await
+            .with_context(|| format!("Failed to write config file: {:?}", path))?;
+        Ok(())
+    }
+
     pub async fn save_to_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
         let content = toml::to_string_pretty(self).with_context(|| "Failed to serialize config")?;
 
@@ -127,21 +274,35 @@ impl Config {
 
     pub fn validate(&self) -> Result<()> {
         // Validate agent configurations
-        self.agents.validate()?;
+        self.agents.validate().context("agents")?;
 
         // Validate UI configuration
-        self.ui.validate()?;
+        self.ui.validate().context("ui")?;
+
+        self.permissions.validate().context("permissions")?;
+        self.logging.validate().context("logging")?;
+        self.relay.validate().context("relay")?;
+        self.telemetry.validate().context("telemetry")?;
+        self.git.validate().context("git")?;
+        self.integrations.validate().context("integrations")?;
+        for (name, table) in &self.profiles {
+            if let Some(profile) = LaunchProfile::from_table(name, table)? {
+                profile
+                    .validate()
+                    .with_context(|| format!("profiles.{}", name))?;
+            }
+        }
 
         // Validate general configuration
         if self.general.max_session_history == 0 {
             return Err(anyhow::anyhow!(
-                "max_session_history must be greater than 0"
+                "general.max_session_history must be greater than 0"
             ));
         }
 
         if self.general.permission_timeout_seconds == 0 {
             return Err(anyhow::anyhow!(
-                "permission_timeout_seconds must be greater than 0"
+                "general.permission_timeout_seconds must be greater than 0"
             ));
         }
 
@@ -153,6 +314,13 @@ impl Config {
         self.agents.merge_with(other.agents);
         self.ui.merge_with(other.ui);
         self.project.merge_with(other.project);
+        self.permissions.merge_with(other.permissions);
+        self.logging.merge_with(other.logging);
+        self.relay.merge_with(other.relay);
+        self.telemetry.merge_with(other.telemetry);
+        self.git.merge_with(other.git);
+        self.integrations.merge_with(other.integrations);
+        self.profiles.extend(other.profiles);
 
         // For general config, replace non-default values
         if other.general.log_level != GeneralConfig::default().log_level {
@@ -177,6 +345,174 @@ impl Config {
         }
     }
 
+    /// Look up a setting by dotted path, e.g. `agents.default_agent`
+    pub fn get_value(&self, key: &str) -> Result<toml::Value> {
+        let root = toml::Value::try_from(self).context("Failed to serialize config")?;
+        let mut value = &root;
+        for part in key.split('.') {
+            value = value
+                .get(part)
+                .ok_or_else(|| anyhow::anyhow!("Unknown config key: {}", key))?;
+        }
+        Ok(value.clone())
+    }
+
+    /// Set a setting by dotted path. The raw value is parsed to the type of
+    /// the current value (or as a TOML literal for unset optional keys), and
+    /// the result must still deserialize and validate.
+    pub fn set_value(&mut self, key: &str, raw: &str) -> Result<()> {
+        let mut root = toml::Value::try_from(&*self).context("Failed to serialize config")?;
+        let (parent_path, leaf) = match key.rsplit_once('.') {
+            Some((parent, leaf)) => (Some(parent), leaf),
+            None => (None, key),
+        };
+
+        let mut table = &mut root;
+        for part in parent_path.into_iter().flat_map(|p| p.split('.')) {
+            table = table
+                .get_mut(part)
+                .ok_or_else(|| anyhow::anyhow!("Unknown config key: {}", key))?;
+        }
+        let table = table
+            .as_table_mut()
+            .ok_or_else(|| anyhow::anyhow!("Unknown config key: {}", key))?;
+
+        let value = match table.get(leaf) {
+            Some(current) => parse_value_like(current, raw)
+                .with_context(|| format!("Invalid value for {}", key))?,
+            None => parse_literal(raw),
+        };
+        table.insert(leaf.to_string(), value);
+
+        let updated: Config = root
+            .try_into()
+            .with_context(|| format!("Invalid value for {}", key))?;
+        // Unknown keys are dropped by serde; make sure ours survived
+        updated.get_value(key)?;
+        updated.validate()?;
+        *self = updated;
+        Ok(())
+    }
+
+    /// Apply `RAT_<SECTION>__<KEY>` environment variables, e.g.
+    /// `RAT_UI__EFFECTS__ENABLED=false` sets `ui.effects.enabled`. These sit
+    /// between the config file and command-line flags.
+    pub fn apply_env_overrides(&mut self) -> Result<()> {
+        self.apply_overrides_from(std::env::vars())
+    }
+
+    /// Apply `RAT_`-style overrides from `(name, value)` pairs. Names without
+    /// a `__` separator (such as `RAT_ALLOWED_TOOLS`) are not config keys and
+    /// are skipped.
+    pub fn apply_overrides_from<I>(&mut self, vars: I) -> Result<()>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        let mut overrides: Vec<(String, String, String)> = vars
+            .into_iter()
+            .filter_map(|(name, value)| env_key(&name).map(|key| (key, name, value)))
+            .collect();
+        // Environment order is arbitrary; keep the result deterministic
+        overrides.sort();
+        for (key, name, value) in overrides {
+            self.set_value(&key, &value)
+                .with_context(|| format!("Invalid environment override {}", name))?;
+        }
+        Ok(())
+    }
+
+    /// Inline profiles plus profile files in the config dir, sorted
+    pub fn profile_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.profiles.keys().cloned().collect();
+        if let Ok(entries) = std::fs::read_dir(self.get_effective_config_dir().join("profiles")) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.extension().and_then(|e| e.to_str()) == Some("toml") {
+                    if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
+                        names.push(stem.to_string());
+                    }
+                }
+            }
+        }
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// This config with a named profile layered on top, and `RAT_`
+    /// environment overrides applied again so they still win over the
+    /// profile. Switching profiles starts again from the config as it was
+    /// before any profile.
+    pub fn with_profile(&self, name: &str) -> Result<Config> {
+        self.with_profile_and_overrides(name, std::env::vars())
+    }
+
+    /// `with_profile` with `RAT_`-style overrides from `(name, value)` pairs
+    /// in place of the environment
+    pub fn with_profile_and_overrides<I>(&self, name: &str, vars: I) -> Result<Config>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        let base = self.without_profile();
+        let mut overlay = base.profile_table(name)?;
+        // Launch settings describe a session, not config to overlay
+        overlay.retain(|key, _| !launch::LAUNCH_KEYS.contains(&key));
+
+        let mut root = toml::Value::try_from(&base).context("Failed to serialize config")?;
+        merge_toml(&mut root, toml::Value::Table(overlay));
+        let mut config: Config = root
+            .try_into()
+            .with_context(|| format!("Invalid settings in profile '{}'", name))?;
+        config.apply_overrides_from(vars)?;
+        config
+            .validate()
+            .with_context(|| format!("Invalid settings in profile '{}'", name))?;
+        config.active_profile = Some(name.to_string());
+        config.unprofiled = Some(Box::new(base));
+        Ok(config)
+    }
+
+    /// Raw table of an inline or file-based profile
+    fn profile_table(&self, name: &str) -> Result<toml::Table> {
+        if let Some(table) = self.profiles.get(name) {
+            return Ok(table.clone());
+        }
+        let path = self
+            .get_effective_config_dir()
+            .join("profiles")
+            .join(format!("{}.toml", name));
+        if !path.exists() {
+            return Err(anyhow::anyhow!(
+                "Unknown profile '{}' (available: {})",
+                name,
+                self.profile_names().join(", ")
+            ));
+        }
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read profile: {:?}", path))?;
+        toml::from_str(&content).with_context(|| format!("Failed to parse profile: {:?}", path))
+    }
+
+    /// Profiles that launch an agent, sorted by name
+    pub fn launch_profiles(&self) -> Result<Vec<LaunchProfile>> {
+        let base = self.without_profile();
+        let mut launches = Vec::new();
+        for name in base.profile_names() {
+            if let Some(profile) = LaunchProfile::from_table(&name, &base.profile_table(&name)?)? {
+                launches.push(profile);
+            }
+        }
+        Ok(launches)
+    }
+
+    /// This config without any profile applied
+    pub fn without_profile(&self) -> Config {
+        match &self.unprofiled {
+            Some(base) => (**base).clone(),
+            None => self.clone(),
+        }
+    }
+
     pub fn get_effective_config_dir(&self) -> PathBuf {
         self.general
             .config_dir
@@ -184,6 +520,14 @@ impl Config {
             .unwrap_or_else(|| Self::get_config_dir().unwrap_or_else(|_| PathBuf::from(".")))
     }
 
+    /// Log file from `[logging]`, or `logs/rat.log` in the data directory
+    pub fn get_effective_log_file(&self) -> PathBuf {
+        self.logging
+            .file
+            .clone()
+            .unwrap_or_else(|| self.get_effective_data_dir().join("logs").join("rat.log"))
+    }
+
     pub fn get_effective_data_dir(&self) -> PathBuf {
         self.general
             .data_dir
@@ -191,3 +535,347 @@ impl Config {
             .unwrap_or_else(|| Self::get_data_dir().unwrap_or_else(|_| PathBuf::from(".")))
     }
 }
+
+/// Deserialize a config, prefixing errors with the offending key path (e.g.
+/// `general.max_session_history: invalid type ...`) and collecting unknown keys
+fn deserialize_tracked<'de, D>(de: D) -> Result<(Config, Vec<String>)>
+where
+    D: serde::Deserializer<'de>,
+{
+    let mut unknown_keys = Vec::new();
+    let mut record = |path: serde_ignored::Path| unknown_keys.push(path.to_string());
+    let de = serde_ignored::Deserializer::new(de, &mut record);
+    let config = serde_path_to_error::deserialize(de).map_err(|e| anyhow::anyhow!("{}", e))?;
+    Ok((config, unknown_keys))
+}
+
+/// Nearest project config in `start` or one of its ancestors
+pub fn find_project_config(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .map(|dir| dir.join(PROJECT_CONFIG_FILE))
+        .find(|path| path.is_file())
+}
+
+/// Set dotted `key` in a TOML document to `value`, creating the tables on
+/// the way. A value already there keeps its surrounding comments.
+fn set_document_value(
+    document: &mut toml_edit::DocumentMut,
+    key: &str,
+    value: &toml::Value,
+) -> Result<()> {
+    let (parents, leaf) = match key.rsplit_once('.') {
+        Some((parents, leaf)) => (Some(parents), leaf),
+        None => (None, key),
+    };
+    let mut table: &mut dyn toml_edit::TableLike = document.as_table_mut();
+    for part in parents.into_iter().flat_map(|p| p.split('.')) {
+        if table.get(part).is_none() {
+            let mut created = toml_edit::Table::new();
+            created.set_implicit(true);
+            table.insert(part, toml_edit::Item::Table(created));
+        }
+        table = table
+            .get_mut(part)
+            .and_then(|item| item.as_table_like_mut())
+            .ok_or_else(|| anyhow::anyhow!("{} is not a table in the config file", part))?;
+    }
+
+    let item = match value {
+        toml::Value::Table(values) => {
+            let document: toml_edit::DocumentMut = toml::to_string(values)
+                .context("Failed to serialize config")?
+                .parse()
+                .context("Failed to serialize config")?;
+            toml_edit::Item::Table(document.as_table().clone())
+        }
+        value => {
+            let mut edited: toml_edit::Value = value
+                .to_string()
+                .parse()
+                .context("Failed to serialize config")?;
+            if let Some(current) = table.get(leaf).and_then(|item| item.as_value()) {
+                *edited.decor_mut() = current.decor().clone();
+            }
+            toml_edit::Item::Value(edited)
+        }
+    };
+    table.insert(leaf, item);
+    Ok(())
+}
+
+/// Recursively overlay `overlay` onto `base`; tables merge, other values replace
+fn merge_toml(base: &mut toml::Value, overlay: toml::Value) {
+    match (base, overlay) {
+        (toml::Value::Table(base), toml::Value::Table(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_toml(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// Dotted config key for a `RAT_A__B` environment variable name
+fn env_key(name: &str) -> Option<String> {
+    let rest = name.strip_prefix("RAT_")?;
+    if !rest.contains("__") {
+        return None;
+    }
+    let parts: Vec<String> = rest.split("__").map(|p| p.to_ascii_lowercase()).collect();
+    if parts.iter().any(|p| p.is_empty()) {
+        return None;
+    }
+    Some(parts.join("."))
+}
+
+/// Parse `raw` as the same TOML type as `current`
+fn parse_value_like(current: &toml::Value, raw: &str) -> Result<toml::Value> {
+    let value = match current {
+        toml::Value::String(_) => toml::Value::String(raw.to_string()),
+        toml::Value::Integer(_) => toml::Value::Integer(
+            raw.parse()
+                .with_context(|| format!("expected an integer, got '{}'", raw))?,
+        ),
+        toml::Value::Float(_) => toml::Value::Float(
+            raw.parse()
+                .with_context(|| format!("expected a number, got '{}'", raw))?,
+        ),
+        toml::Value::Boolean(_) => toml::Value::Boolean(
+            raw.parse()
+                .with_context(|| format!("expected true or false, got '{}'", raw))?,
+        ),
+        _ => {
+            let value = parse_literal(raw);
+            if value.type_str() != current.type_str() {
+                return Err(anyhow::anyhow!(
+                    "expected {}, got '{}'",
+                    current.type_str(),
+                    raw
+                ));
+            }
+            value
+        }
+    };
+    Ok(value)
+}
+
+/// Parse a TOML literal such as `["a", "b"]` or `42`, falling back to a bare string
+fn parse_literal(raw: &str) -> toml::Value {
+    toml::from_str::<toml::Table>(&format!("value = {}", raw))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(raw.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_and_set_dotted_keys() {
+        let mut config = Config::default();
+        config.set_value("agents.default_agent", "gemini").unwrap();
+        config.set_value("general.max_session_history", "50").unwrap();
+        config.set_value("agents.auto_connect", r#"["gemini"]"#).unwrap();
+
+        assert_eq!(config.agents.default_agent, "gemini");
+        assert_eq!(config.general.max_session_history, 50);
+        assert_eq!(
+            config.get_value("agents.auto_connect").unwrap(),
+            toml::Value::Array(vec![toml::Value::String("gemini".into())])
+        );
+    }
+
+    #[test]
+    fn profiles_overlay_without_stacking() {
+        let mut config = Config::default();
+        config.profiles.insert(
+            "work".to_string(),
+            toml::from_str("[agents]\ndefault_agent = \"gemini\"\n[permissions]\ndefault = \"deny\"")
+                .unwrap(),
+        );
+        config
+            .profiles
+            .insert("home".to_string(), toml::from_str("[general]\nlog_level = \"debug\"").unwrap());
+
+        let work = config.with_profile("work").unwrap();
+        assert_eq!(work.agents.default_agent, "gemini");
+        assert_eq!(work.permissions.default, PolicyDecision::Deny);
+        assert_eq!(work.active_profile.as_deref(), Some("work"));
+
+        let home = work.with_profile("home").unwrap();
+        assert_eq!(home.agents.default_agent, "claude-code");
+        assert_eq!(home.general.log_level, "debug");
+        assert!(config.with_profile("missing").is_err());
+    }
+
+    #[test]
+    fn env_overrides_win_over_the_profile() {
+        let mut config = Config::default();
+        config.profiles.insert(
+            "work".to_string(),
+            toml::from_str("[general]\nlog_level = \"debug\"\n[ui]\nfps = 30").unwrap(),
+        );
+        let env = [("RAT_GENERAL__LOG_LEVEL".to_string(), "warn".to_string())];
+        config.apply_overrides_from(env.clone()).unwrap();
+
+        let work = config.with_profile_and_overrides("work", env).unwrap();
+        assert_eq!(work.general.log_level, "warn");
+        assert_eq!(work.ui.fps, 30);
+        assert_eq!(work.without_profile().general.log_level, "warn");
+    }
+
+    #[test]
+    fn launch_profiles_are_read_from_profiles_with_an_agent() {
+        let mut config = Config::default();
+        config.profiles.insert(
+            "review".to_string(),
+            toml::from_str(
+                "agent = \"gemini\"\nmodel = \"gemini-pro\"\ncwd = \"~/work/api\"\nenv = { FOO = \"1\" }\n[permissions]\ndefault = \"deny\"",
+            )
+            .unwrap(),
+        );
+        config
+            .profiles
+            .insert("home".to_string(), toml::from_str("[general]\nlog_level = \"debug\"").unwrap());
+        config.validate().unwrap();
+
+        let launches = config.launch_profiles().unwrap();
+        assert_eq!(launches.len(), 1);
+        let review = &launches[0];
+        assert_eq!(review.name, "review");
+        assert_eq!(review.agent, "gemini");
+        assert_eq!(review.agent_env().get("GEMINI_MODEL").map(String::as_str), Some("gemini-pro"));
+        assert_eq!(review.agent_env().get("FOO").map(String::as_str), Some("1"));
+        assert!(!review.working_dir().unwrap().starts_with("~"));
+
+        // As a plain profile only the overlay part applies
+        let applied = config.with_profile("review").unwrap();
+        assert_eq!(applied.permissions.default, PolicyDecision::Deny);
+        assert_eq!(applied.agents.default_agent, "claude-code");
+
+        config
+            .profiles
+            .insert("gemini".to_string(), toml::from_str("agent = \"gemini\"").unwrap());
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn set_rejects_bad_types_and_unknown_keys() {
+        let mut config = Config::default();
+        assert!(config.set_value("general.max_session_history", "lots").is_err());
+        assert!(config.set_value("general.max_session_history", "0").is_err());
+        assert!(config.set_value("general.no_such_key", "1").is_err());
+        assert_eq!(config.general.max_session_history, 1000);
+    }
+
+    #[test]
+    fn effect_components_scope_the_toggles() {
+        let mut config = Config::default();
+        config
+            .set_value("ui.effects.components", r#"["border", "statusbar"]"#)
+            .unwrap();
+        let effects = &config.ui.effects;
+        assert!(effects.border_pulse_active());
+        assert!(effects.hue_drift_active());
+        assert!(!effects.chat_sweep_active());
+        assert!(!effects.glitch_active());
+
+        assert!(config.set_value("ui.effects.components", r#"["sidebar"]"#).is_err());
+        assert_eq!(config.ui.effects.components, ["border", "statusbar"]);
+    }
+
+    #[test]
+    fn env_overrides_set_nested_keys() {
+        let mut config = Config::default();
+        config
+            .apply_overrides_from([
+                ("RAT_UI__EFFECTS__ENABLED".to_string(), "false".to_string()),
+                ("RAT_GENERAL__LOG_LEVEL".to_string(), "debug".to_string()),
+                ("RAT_ALLOWED_TOOLS".to_string(), "Read".to_string()),
+                ("HOME".to_string(), "/root".to_string()),
+            ])
+            .unwrap();
+        assert!(!config.ui.effects.enabled);
+        assert_eq!(config.general.log_level, "debug");
+
+        let err = config
+            .apply_overrides_from([("RAT_GENERAL__NOPE".to_string(), "1".to_string())])
+            .unwrap_err();
+        assert!(format!("{:#}", err).contains("RAT_GENERAL__NOPE"));
+    }
+
+    #[test]
+    fn parse_errors_name_the_key_and_unknown_keys_are_reported() {
+        let content = toml::to_string(&Config::default())
+            .unwrap()
+            .replace("max_session_history = 1000", "max_session_history = \"lots\"");
+        let err = deserialize_tracked(toml::Deserializer::new(&content)).unwrap_err();
+        assert!(err.to_string().contains("general.max_session_history"), "{}", err);
+
+        let content = toml::to_string(&Config::default())
+            .unwrap()
+            .replace("[general]", "[general]\nlog_levle = \"debug\"");
+        let (_, unknown) = deserialize_tracked(toml::Deserializer::new(&content)).unwrap();
+        assert_eq!(unknown, vec!["general.log_levle".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn saving_values_edits_only_those_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(
+            &path,
+            "# My settings\n[agents]\ndefault_agent = \"claude-code\" # for now\n",
+        )
+        .unwrap();
+
+        // A partial file loads, with defaults for everything it leaves out
+        let mut config = Config::from_file(&path).await.unwrap();
+        assert_eq!(config.general.max_session_history, 1000);
+        config.set_value("agents.default_agent", "gemini").unwrap();
+        config.set_value("general.max_session_history", "50").unwrap();
+        config
+            .save_values(&path, &["agents.default_agent", "general.max_session_history"])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "# My settings\n[agents]\ndefault_agent = \"gemini\" # for now\n\n[general]\nmax_session_history = 50\n"
+        );
+        let saved = Config::from_file(&path).await.unwrap();
+        assert_eq!(saved.agents.default_agent, "gemini");
+        assert_eq!(saved.general.max_session_history, 50);
+
+        assert_eq!(Config::user_file(Some(&path)), Some(path.clone()));
+    }
+
+    #[tokio::test]
+    async fn project_config_found_in_ancestor_and_merged() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("src").join("deep");
+        std::fs::create_dir_all(&nested).unwrap();
+        let project_file = dir.path().join(PROJECT_CONFIG_FILE);
+        std::fs::write(
+            &project_file,
+            "[agents]\ndefault_agent = \"gemini\"\n[permissions.tools]\nexecute = \"deny\"\n",
+        )
+        .unwrap();
+
+        assert_eq!(find_project_config(&nested), Some(project_file.clone()));
+
+        let mut config = Config::default();
+        config.general.max_session_history = 42;
+        config.merge_with(Config::from_file(&project_file).await.unwrap());
+        assert_eq!(config.agents.default_agent, "gemini");
+        assert_eq!(config.general.max_session_history, 42);
+        assert_eq!(config.permissions.decide(Some("execute"), None), PolicyDecision::Deny);
+    }
+}
@@ -0,0 +1,236 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// ACP tool kinds that can carry a per-kind rule
+pub const TOOL_KINDS: &[&str] = &[
+    "read", "edit", "delete", "move", "search", "execute", "think", "fetch", "other",
+];
+
+/// Read-only commands allowed by `exec:safe` / `safe_commands`
+pub const DEFAULT_SAFE_COMMANDS: &[&str] = &[
+    "ls", "cat", "head", "tail", "wc", "pwd", "grep", "rg", "git status", "git diff", "git log",
+    "git show",
+];
+
+/// Outcome applied to an agent permission request
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PolicyDecision {
+    Allow,
+    Deny,
+    /// Hold the request until the user answers it in the TUI. Hosts with
+    /// nobody to ask, such as headless runs, deny it instead.
+    Ask,
+}
+
+/// Permission policy, keyed by ACP tool kind.
+///
+/// ```toml
+/// [permissions]
+/// default = "allow"
+///
+/// [permissions.tools]
+/// execute = "deny"
+/// ```
+///
+/// `safe_commands` lists command prefixes that are allowed even when
+/// `execute` is denied, as long as the command has no shell operators.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct PermissionsConfig {
+    pub default: PolicyDecision,
+    pub tools: HashMap<String, PolicyDecision>,
+    pub safe_commands: Vec<String>,
+}
+
+impl Default for PermissionsConfig {
+    fn default() -> Self {
+        Self {
+            default: PolicyDecision::Allow,
+            tools: HashMap::new(),
+            safe_commands: Vec::new(),
+        }
+    }
+}
+
+impl PermissionsConfig {
+    /// Deny every request
+    pub fn deny_all() -> Self {
+        Self {
+            default: PolicyDecision::Deny,
+            ..Self::default()
+        }
+    }
+
+    /// Build a policy from an `--approve` list such as `reads,exec:safe`.
+    /// Anything not listed is denied. Accepted entries are tool kinds plus
+    /// `all`, `reads` (read/search/think), `edits` (edit/move/delete),
+    /// `exec` and `exec:safe` (only read-only commands).
+    pub fn from_approve_list(spec: &str) -> Result<Self> {
+        let mut policy = Self::deny_all();
+        for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let kinds: &[&str] = match entry {
+                "all" => {
+                    policy.default = PolicyDecision::Allow;
+                    &[]
+                }
+                "reads" => &["read", "search", "think"],
+                "edits" => &["edit", "move", "delete"],
+                "exec" => &["execute"],
+                "exec:safe" => {
+                    policy.safe_commands =
+                        DEFAULT_SAFE_COMMANDS.iter().map(|c| c.to_string()).collect();
+                    &[]
+                }
+                kind if TOOL_KINDS.contains(&kind) => {
+                    policy.tools.insert(kind.to_string(), PolicyDecision::Allow);
+                    &[]
+                }
+                other => {
+                    return Err(anyhow::anyhow!(
+                        "Unknown approval '{}' (expected all, reads, edits, exec, exec:safe or one of {})",
+                        other,
+                        TOOL_KINDS.join(", ")
+                    ))
+                }
+            };
+            for kind in kinds {
+                policy.tools.insert(kind.to_string(), PolicyDecision::Allow);
+            }
+        }
+        Ok(policy)
+    }
+
+    /// Decide a request for the given tool kind (lowercase ACP name) and,
+    /// for execute requests, the command line if the agent supplied one
+    pub fn decide(&self, tool_kind: Option<&str>, command: Option<&str>) -> PolicyDecision {
+        let decision = tool_kind
+            .and_then(|kind| self.tools.get(kind))
+            .copied()
+            .unwrap_or(self.default);
+
+        if decision != PolicyDecision::Allow
+            && tool_kind == Some("execute")
+            && command.is_some_and(|c| self.is_safe_command(c))
+        {
+            return PolicyDecision::Allow;
+        }
+        decision
+    }
+
+    fn is_safe_command(&self, command: &str) -> bool {
+        const SHELL_OPERATORS: &[&str] = &[";", "&", "|", ">", "<", "`", "$(", "\n"];
+        let command = command.trim();
+        if SHELL_OPERATORS.iter().any(|op| command.contains(op)) {
+            return false;
+        }
+        self.safe_commands.iter().any(|safe| {
+            command == safe
+                || command
+                    .strip_prefix(safe.as_str())
+                    .is_some_and(|rest| rest.starts_with(' '))
+        })
+    }
+
+    /// Human-readable rules, one per line: the default, then per-kind overrides
+    pub fn describe(&self) -> Vec<String> {
+        let decision = |d: PolicyDecision| match d {
+            PolicyDecision::Allow => "allow",
+            PolicyDecision::Deny => "deny",
+            PolicyDecision::Ask => "ask",
+        };
+        let mut lines = vec![format!("default: {}", decision(self.default))];
+        let mut tools: Vec<_> = self.tools.iter().collect();
+        tools.sort_by_key(|(kind, _)| *kind);
+        lines.extend(
+            tools
+                .into_iter()
+                .map(|(kind, d)| format!("{}: {}", kind, decision(*d))),
+        );
+        if !self.safe_commands.is_empty() {
+            lines.push(format!("safe commands: {}", self.safe_commands.join(", ")));
+        }
+        lines
+    }
+
+    /// The same policy with every `ask` turned into `deny`, for hosts that
+    /// cannot put a question to the user
+    pub fn non_interactive(&self) -> Self {
+        let deny_asks = |d: PolicyDecision| match d {
+            PolicyDecision::Ask => PolicyDecision::Deny,
+            other => other,
+        };
+        Self {
+            default: deny_asks(self.default),
+            tools: self
+                .tools
+                .iter()
+                .map(|(kind, d)| (kind.clone(), deny_asks(*d)))
+                .collect(),
+            safe_commands: self.safe_commands.clone(),
+        }
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        for kind in self.tools.keys() {
+            if !TOOL_KINDS.contains(&kind.as_str()) {
+                return Err(anyhow::anyhow!(
+                    "Unknown tool kind in permissions: {} (expected one of {})",
+                    kind,
+                    TOOL_KINDS.join(", ")
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn merge_with(&mut self, other: PermissionsConfig) {
+        if other.default != PermissionsConfig::default().default {
+            self.default = other.default;
+        }
+        self.tools.extend(other.tools);
+        if other.safe_commands != PermissionsConfig::default().safe_commands {
+            self.safe_commands = other.safe_commands;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn approve_list_denies_everything_else() {
+        let policy = PermissionsConfig::from_approve_list("reads,exec:safe").unwrap();
+        assert_eq!(policy.decide(Some("read"), None), PolicyDecision::Allow);
+        assert_eq!(policy.decide(Some("edit"), None), PolicyDecision::Deny);
+        assert_eq!(policy.decide(None, None), PolicyDecision::Deny);
+        assert_eq!(
+            policy.decide(Some("execute"), Some("git diff --stat")),
+            PolicyDecision::Allow
+        );
+        assert_eq!(
+            policy.decide(Some("execute"), Some("git diff; rm -rf /")),
+            PolicyDecision::Deny
+        );
+        assert_eq!(policy.decide(Some("execute"), Some("lsof")), PolicyDecision::Deny);
+        assert!(PermissionsConfig::from_approve_list("reads,writes").is_err());
+    }
+
+    #[test]
+    fn ask_skips_safe_commands_and_denies_without_a_user() {
+        let mut policy = PermissionsConfig {
+            default: PolicyDecision::Ask,
+            safe_commands: vec!["ls".to_string()],
+            ..PermissionsConfig::default()
+        };
+        policy.tools.insert("read".to_string(), PolicyDecision::Allow);
+        assert_eq!(policy.decide(Some("edit"), None), PolicyDecision::Ask);
+        assert_eq!(policy.decide(Some("execute"), Some("ls -la")), PolicyDecision::Allow);
+
+        let headless = policy.non_interactive();
+        assert_eq!(headless.decide(Some("edit"), None), PolicyDecision::Deny);
+        assert_eq!(headless.decide(Some("read"), None), PolicyDecision::Allow);
+    }
+}
@@ -9,6 +9,17 @@ pub struct ProjectConfig {
     pub project_history: Vec<ProjectSettings>,
     pub auto_detect: bool,
     pub ignore_patterns: Vec<String>,
+    /// Files in the project root whose contents are sent ahead of the first
+    /// prompt of each new session; the first one found is used
+    #[serde(default = "default_instruction_files")]
+    pub instruction_files: Vec<String>,
+}
+
+fn default_instruction_files() -> Vec<String> {
+    crate::acp::instructions::DEFAULT_INSTRUCTION_FILES
+        .iter()
+        .map(|name| name.to_string())
+        .collect()
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -47,6 +58,7 @@ impl Default for ProjectConfig {
                 "*.log".to_string(),
                 "*.tmp".to_string(),
             ],
+            instruction_files: default_instruction_files(),
         }
     }
 }
@@ -88,6 +100,9 @@ impl ProjectConfig {
         if !other.ignore_patterns.is_empty() {
             self.ignore_patterns = other.ignore_patterns;
         }
+        if other.instruction_files != default_instruction_files() {
+            self.instruction_files = other.instruction_files;
+        }
     }
 
     pub fn set_current_project(&mut self, project: ProjectSettings) -> Result<()> {
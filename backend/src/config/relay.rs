@@ -0,0 +1,96 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// Relay used when neither `[relay] url` nor `RAT_RELAY_URL` is set
+pub const DEFAULT_RELAY_URL: &str = "http://localhost:8080";
+
+/// STUN servers for direct links when `ice_servers` is not set
+pub const DEFAULT_ICE_SERVERS: [&str; 1] = ["stun:stun.l.google.com:19302"];
+
+/// Which relay `rat --pair` registers with, e.g. a self-hosted `rat relay`.
+///
+/// ```toml
+/// [relay]
+/// url = "https://relay.example.com"
+/// token_env = "RAT_RELAY_TOKEN"
+/// ice_servers = ["stun:stun.example.com:3478"]
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct RelayConfig {
+    /// Base URL of the relay's HTTP API
+    pub url: Option<String>,
+    /// Bearer token the relay requires to start pairing
+    pub token: Option<String>,
+    /// Environment variable holding the token, to keep it out of the file
+    pub token_env: Option<String>,
+    /// STUN/TURN servers for direct WebRTC links to paired browsers
+    pub ice_servers: Option<Vec<String>>,
+}
+
+impl RelayConfig {
+    pub fn validate(&self) -> Result<()> {
+        if let Some(raw) = &self.url {
+            let url = url::Url::parse(raw).map_err(|e| anyhow::anyhow!("url: {}", e))?;
+            if url.scheme() != "http" && url.scheme() != "https" {
+                return Err(anyhow::anyhow!("url must be http:// or https://"));
+            }
+        }
+        if self.token.is_some() && self.token_env.is_some() {
+            return Err(anyhow::anyhow!("set either token or token_env, not both"));
+        }
+        for server in self.ice_servers.iter().flatten() {
+            if !["stun:", "stuns:", "turn:", "turns:"]
+                .iter()
+                .any(|p| server.starts_with(p))
+            {
+                return Err(anyhow::anyhow!(
+                    "ice_servers: {} must be a stun: or turn: URL",
+                    server
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn merge_with(&mut self, other: RelayConfig) {
+        if other.url.is_some() {
+            self.url = other.url;
+        }
+        if other.token.is_some() || other.token_env.is_some() {
+            self.token = other.token;
+            self.token_env = other.token_env;
+        }
+        if other.ice_servers.is_some() {
+            self.ice_servers = other.ice_servers;
+        }
+    }
+
+    /// The relay base URL: `RAT_RELAY_URL`, then `url`, then the default
+    pub fn base_url(&self) -> String {
+        std::env::var("RAT_RELAY_URL")
+            .ok()
+            .or_else(|| self.url.clone())
+            .unwrap_or_else(|| DEFAULT_RELAY_URL.to_string())
+            .trim_end_matches('/')
+            .to_string()
+    }
+
+    /// ICE servers for direct links, defaulting to a public STUN server
+    pub fn ice_servers(&self) -> Vec<String> {
+        match &self.ice_servers {
+            Some(servers) => servers.clone(),
+            None => DEFAULT_ICE_SERVERS.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    /// The bearer token, if one is configured; a named but unset variable is an error
+    pub fn token(&self) -> Result<Option<String>> {
+        match &self.token_env {
+            Some(var) => std::env::var(var)
+                .map(Some)
+                .map_err(|_| anyhow::anyhow!("relay.token_env names {}, which is not set", var)),
+            None => Ok(self.token.clone()),
+        }
+    }
+}
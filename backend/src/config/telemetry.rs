@@ -0,0 +1,115 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// Collector used when `[telemetry] endpoint` is not set (OTLP over gRPC)
+pub const DEFAULT_OTLP_ENDPOINT: &str = "http://localhost:4317";
+
+/// OpenTelemetry export, for a bridge shared by several people. Needs a
+/// build with the `otel` feature.
+///
+/// ```toml
+/// [telemetry]
+/// enabled = true
+/// endpoint = "http://collector:4317"
+/// service_name = "rat-bridge"
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct TelemetryConfig {
+    pub enabled: bool,
+    /// OTLP collector URL; gRPC, or HTTP/protobuf with `protocol = "http"`
+    pub endpoint: Option<String>,
+    pub protocol: OtlpProtocol,
+    /// `service.name` of everything exported
+    pub service_name: String,
+    /// Export spans as traces
+    pub traces: bool,
+    /// Export turn latency, tool call duration and agent restarts
+    pub metrics: bool,
+    /// Seconds between metric exports
+    pub export_interval_secs: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OtlpProtocol {
+    #[default]
+    Grpc,
+    Http,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: None,
+            protocol: OtlpProtocol::Grpc,
+            service_name: "rat".to_string(),
+            traces: true,
+            metrics: true,
+            export_interval_secs: 60,
+        }
+    }
+}
+
+impl TelemetryConfig {
+    pub fn validate(&self) -> Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        if !cfg!(feature = "otel") {
+            return Err(anyhow::anyhow!(
+                "enabled needs RAT built with the otel feature (cargo build --features otel)"
+            ));
+        }
+        if let Some(raw) = &self.endpoint {
+            let url = url::Url::parse(raw).map_err(|e| anyhow::anyhow!("endpoint: {}", e))?;
+            if url.scheme() != "http" && url.scheme() != "https" {
+                return Err(anyhow::anyhow!("endpoint must be http:// or https://"));
+            }
+        }
+        if self.service_name.is_empty() {
+            return Err(anyhow::anyhow!("service_name must not be empty"));
+        }
+        if self.export_interval_secs == 0 {
+            return Err(anyhow::anyhow!(
+                "export_interval_secs must be greater than 0"
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn merge_with(&mut self, other: TelemetryConfig) {
+        let defaults = TelemetryConfig::default();
+        if other.enabled != defaults.enabled {
+            self.enabled = other.enabled;
+        }
+        if other.endpoint.is_some() {
+            self.endpoint = other.endpoint;
+        }
+        if other.protocol != defaults.protocol {
+            self.protocol = other.protocol;
+        }
+        if other.service_name != defaults.service_name {
+            self.service_name = other.service_name;
+        }
+        if other.traces != defaults.traces {
+            self.traces = other.traces;
+        }
+        if other.metrics != defaults.metrics {
+            self.metrics = other.metrics;
+        }
+        if other.export_interval_secs != defaults.export_interval_secs {
+            self.export_interval_secs = other.export_interval_secs;
+        }
+    }
+
+    /// The collector URL: `endpoint`, or the default for the protocol
+    pub fn endpoint(&self) -> String {
+        match (&self.endpoint, self.protocol) {
+            (Some(url), _) => url.clone(),
+            (None, OtlpProtocol::Grpc) => DEFAULT_OTLP_ENDPOINT.to_string(),
+            (None, OtlpProtocol::Http) => "http://localhost:4318".to_string(),
+        }
+    }
+}
@@ -9,6 +9,74 @@ pub struct UiConfig {
     pub keybindings: KeybindingConfig,
     pub effects: EffectsConfig,
     pub editor: EditorConfig,
+    /// Frames per second while effects animate; otherwise frames are only
+    /// drawn when something on screen changes
+    #[serde(default = "default_fps")]
+    pub fps: u32,
+    /// Housekeeping interval (terminal output, status bar) in milliseconds
+    #[serde(default = "default_tick_ms")]
+    pub tick_ms: u64,
+    /// Power saver: animation frames per second after a few idle seconds (unset = keep `fps`)
+    #[serde(default)]
+    pub idle_fps: Option<u32>,
+    #[serde(default)]
+    pub notifications: NotificationsConfig,
+    #[serde(default)]
+    pub status_bar: StatusBarConfig,
+    /// Plain ASCII instead of box drawing and emoji, no animation, higher
+    /// contrast and panes stacked under the chat, for screen readers and
+    /// limited terminals
+    #[serde(default)]
+    pub accessibility: bool,
+    /// `auto` detects the terminal's colors; `truecolor`, `256` or `16`
+    /// forces a depth. Below truecolor the theme is mapped to the nearest
+    /// colors the terminal has.
+    #[serde(default = "default_colors")]
+    pub colors: String,
+    /// `default`, or `vim` for normal and insert modes in the input box,
+    /// `j`/`k` scrolling and a `:` command line
+    #[serde(default = "default_keymap")]
+    pub keymap: String,
+}
+
+/// Desktop notifications, sent only while the terminal is unfocused, and
+/// the terminal bell
+///
+/// ```toml
+/// [ui.notifications]
+/// desktop = true
+/// turn_complete = true
+/// errors = true
+/// permissions = false
+/// bell = true
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationsConfig {
+    /// Master switch
+    pub desktop: bool,
+    /// A prompt turn finished
+    pub turn_complete: bool,
+    /// An agent or session failed
+    pub errors: bool,
+    /// The permission policy answered an agent's request, or one waits
+    /// for an answer
+    pub permissions: bool,
+    /// Ring the terminal bell when a permission request waits for an
+    /// answer, whether or not the terminal is focused
+    pub bell: bool,
+}
+
+/// Status bar segments, left to right; leave one out to hide it
+///
+/// ```toml
+/// [ui.status_bar]
+/// segments = ["agent", "git", "latency", "clock"]
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StatusBarConfig {
+    pub segments: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -17,6 +85,10 @@ pub struct ThemeConfig {
     pub custom_colors: HashMap<String, String>,
     pub syntax_highlighting: bool,
     pub agent_colors: HashMap<String, String>,
+    /// Named palettes, e.g. `[ui.theme.themes.midnight]` with `background = "#000000"`.
+    /// Colors not listed come from `base` (default: cyberpunk).
+    #[serde(default)]
+    pub themes: HashMap<String, HashMap<String, String>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,6 +100,38 @@ pub struct LayoutConfig {
     pub sidebar_width: u16,
     pub terminal_height: u16,
     pub chat_history_limit: usize,
+    /// Panes opened beside the chat at startup: `plan`, `terminal`, `diff`,
+    /// `chat` (another session tab) or `logs`
+    #[serde(default)]
+    pub panes: Vec<String>,
+    /// `horizontal` puts panes beside the chat, `vertical` below it
+    #[serde(default = "default_split")]
+    pub split: String,
+    /// Share of the main area, in percent, the chat keeps when split
+    #[serde(default = "default_main_percent")]
+    pub main_percent: u16,
+    /// Rows of the message input box, borders included
+    #[serde(default = "default_input_height")]
+    pub input_height: u16,
+    /// Rows of the session tab bar; 1 drops its underline
+    #[serde(default = "default_tab_bar_height")]
+    pub tab_bar_height: u16,
+}
+
+fn default_split() -> String {
+    "horizontal".to_string()
+}
+
+fn default_main_percent() -> u16 {
+    60
+}
+
+fn default_input_height() -> u16 {
+    3
+}
+
+fn default_tab_bar_height() -> u16 {
+    3
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -52,12 +156,143 @@ pub struct EffectsConfig {
     pub status_animation: bool,
     pub smooth_scrolling: bool,
     pub startup: StartupEffectsConfig,
+    /// Keep the theme but turn off every animation
+    #[serde(default)]
+    pub reduce_motion: bool,
+    /// Sweep over the chat when a message arrives
+    #[serde(default = "default_true")]
+    pub chat_sweep: bool,
+    /// Brief glitch over new chat text
+    #[serde(default = "default_true")]
+    pub glitch: bool,
+    /// Pulsing outer border
+    #[serde(default = "default_true")]
+    pub border_pulse: bool,
+    /// Slow color drift on text
+    #[serde(default = "default_true")]
+    pub hue_drift: bool,
+    /// Pulse a waiting permission dialog and flash its status bar segment
+    #[serde(default = "default_true")]
+    pub permission_alert: bool,
+    /// Pause ambient effects while frames keep missing the `fps` budget
+    #[serde(default = "default_true")]
+    pub auto_degrade: bool,
+    /// Run effects even on terminals without truecolor or with a console
+    /// or VT `TERM`, where they are otherwise left off
+    #[serde(default)]
+    pub force: bool,
+    /// Parts of the screen effects may draw on, from `EFFECT_COMPONENTS`.
+    /// Leaving one out turns off every effect there, e.g. `chat` stops the
+    /// sweep and glitch on new messages and the drift over the conversation.
+    #[serde(default = "default_effect_components")]
+    pub components: Vec<String>,
+    /// Effects composed from tachyonfx primitives, by name
+    #[serde(default)]
+    pub custom: HashMap<String, CustomEffectConfig>,
+}
+
+/// Screen parts named in `ui.effects.components`
+pub const EFFECT_COMPONENTS: &[&str] = &["chat", "tabs", "panes", "statusbar", "border"];
+
+/// An effect composed in config; its steps run one after another
+///
+/// ```toml
+/// [ui.effects.custom.arrival]
+/// on = "message"
+/// target = "border"
+/// steps = [
+///   { type = "fade_from", color = "accent_a", ms = 300 },
+///   { type = "parallel", steps = [
+///     { type = "hsl_shift", hsl = [40.0, 0.0, 0.0], ms = 500 },
+///     { type = "sweep_in", color = "accent_b", ms = 400, interpolation = "quad_out" },
+///   ] },
+/// ]
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CustomEffectConfig {
+    /// `ambient` to repeat while effects run, or `message` to play once
+    /// when a message arrives
+    #[serde(default = "default_effect_trigger")]
+    pub on: String,
+    /// Where it draws: `screen`, `chat`, `border` or `statusbar`
+    #[serde(default = "default_effect_target")]
+    pub target: String,
+    pub steps: Vec<EffectStepConfig>,
+}
+
+/// One step of a custom effect. `sequence` and `parallel` group `steps`;
+/// the others are tachyonfx effects lasting `ms`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EffectStepConfig {
+    /// `sequence`, `parallel`, `fade_from`, `fade_to`, `sweep_in`,
+    /// `sweep_out`, `hsl_shift`, `dissolve`, `coalesce` or `sleep`
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub steps: Vec<EffectStepConfig>,
+    #[serde(default)]
+    pub ms: Option<u32>,
+    /// Theme color name (`accent_a`, `muted`, ...) or a color
+    #[serde(default)]
+    pub color: Option<String>,
+    /// Hue, saturation and lightness change for `hsl_shift`
+    #[serde(default)]
+    pub hsl: Option<[f32; 3]>,
+    /// Easing such as `linear` (the default), `sine_in_out` or `quad_out`
+    #[serde(default)]
+    pub interpolation: Option<String>,
+}
+
+/// Named effect sets offered by the theme gallery: (name, description)
+pub const EFFECT_PRESETS: &[(&str, &str)] = &[
+    ("full", "Every effect"),
+    ("subtle", "Message sweeps only"),
+    ("still", "No animation"),
+];
+
+fn default_effect_components() -> Vec<String> {
+    EFFECT_COMPONENTS.iter().map(|name| name.to_string()).collect()
+}
+
+fn default_effect_trigger() -> String {
+    "ambient".to_string()
+}
+
+fn default_effect_target() -> String {
+    "screen".to_string()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_fps() -> u32 {
+    20
+}
+
+fn default_tick_ms() -> u64 {
+    50
+}
+
+fn default_colors() -> String {
+    "auto".to_string()
+}
+
+fn default_startup_style() -> String {
+    "matrix".to_string()
+}
+
+fn default_keymap() -> String {
+    "default".to_string()
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StartupEffectsConfig {
     pub enabled: bool,
     pub duration_ms: u64,
+    /// `matrix`, `scanline`, `typewriter` or `none`
+    #[serde(default = "default_startup_style")]
+    pub style: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -77,6 +312,48 @@ impl Default for UiConfig {
             keybindings: KeybindingConfig::default(),
             effects: EffectsConfig::default(),
             editor: EditorConfig::default(),
+            fps: default_fps(),
+            tick_ms: default_tick_ms(),
+            idle_fps: None,
+            notifications: NotificationsConfig::default(),
+            status_bar: StatusBarConfig::default(),
+            accessibility: false,
+            colors: default_colors(),
+            keymap: default_keymap(),
+        }
+    }
+}
+
+impl Default for StatusBarConfig {
+    fn default() -> Self {
+        Self {
+            segments: [
+                "message",
+                "agent",
+                "mode",
+                "git",
+                "tokens",
+                "latency",
+                "permissions",
+                "backpressure",
+                "memory",
+                "clock",
+            ]
+            .iter()
+            .map(|s| s.to_string())
+            .collect(),
+        }
+    }
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        Self {
+            desktop: true,
+            turn_complete: true,
+            errors: true,
+            permissions: true,
+            bell: false,
         }
     }
 }
@@ -92,6 +369,7 @@ impl Default for ThemeConfig {
             custom_colors: HashMap::new(),
             syntax_highlighting: true,
             agent_colors,
+            themes: HashMap::new(),
         }
     }
 }
@@ -106,6 +384,11 @@ impl Default for LayoutConfig {
             sidebar_width: 25,
             terminal_height: 20,
             chat_history_limit: 100,
+            panes: Vec::new(),
+            split: default_split(),
+            main_percent: default_main_percent(),
+            input_height: default_input_height(),
+            tab_bar_height: default_tab_bar_height(),
         }
     }
 }
@@ -138,6 +421,16 @@ impl Default for EffectsConfig {
             status_animation: true,
             smooth_scrolling: true,
             startup: StartupEffectsConfig::default(),
+            reduce_motion: false,
+            chat_sweep: true,
+            glitch: true,
+            border_pulse: true,
+            hue_drift: true,
+            permission_alert: true,
+            auto_degrade: true,
+            force: false,
+            components: default_effect_components(),
+            custom: HashMap::new(),
         }
     }
 }
@@ -147,6 +440,7 @@ impl Default for StartupEffectsConfig {
         Self {
             enabled: true,
             duration_ms: 1800,
+            style: default_startup_style(),
         }
     }
 }
@@ -173,6 +467,14 @@ impl UiConfig {
             return Err(anyhow::anyhow!("terminal_height must be between 1 and 50"));
         }
 
+        if !(3..=10).contains(&self.layout.input_height) {
+            return Err(anyhow::anyhow!("input_height must be between 3 and 10"));
+        }
+
+        if !(1..=3).contains(&self.layout.tab_bar_height) {
+            return Err(anyhow::anyhow!("tab_bar_height must be between 1 and 3"));
+        }
+
         if self.layout.chat_history_limit == 0 {
             return Err(anyhow::anyhow!("chat_history_limit must be greater than 0"));
         }
@@ -187,6 +489,20 @@ impl UiConfig {
             return Err(anyhow::anyhow!("tab_size must be between 1 and 16"));
         }
 
+        if self.fps == 0 || self.fps > 120 {
+            return Err(anyhow::anyhow!("fps must be between 1 and 120"));
+        }
+
+        if self.tick_ms < 10 || self.tick_ms > 1000 {
+            return Err(anyhow::anyhow!("tick_ms must be between 10 and 1000"));
+        }
+
+        if let Some(idle_fps) = self.idle_fps {
+            if idle_fps == 0 || idle_fps > self.fps {
+                return Err(anyhow::anyhow!("idle_fps must be between 1 and fps"));
+            }
+        }
+
         let valid_layouts = ["tabbed", "split", "dashboard"];
         if !valid_layouts.contains(&self.layout.default_layout.as_str()) {
             return Err(anyhow::anyhow!(
@@ -195,6 +511,42 @@ impl UiConfig {
             ));
         }
 
+        for name in &self.effects.components {
+            if !EFFECT_COMPONENTS.contains(&name.as_str()) {
+                return Err(anyhow::anyhow!(
+                    "effects.components: unknown component '{}' (expected one of: {})",
+                    name,
+                    EFFECT_COMPONENTS.join(", ")
+                ));
+            }
+        }
+
+        // Keys, layouts, segments and themes are parsed by the TUI, which library
+        // builds leave out
+        #[cfg(feature = "tui")]
+        {
+            use anyhow::Context;
+
+            crate::ui::keymap::Keymap::from_ui_config(self)?;
+            crate::ui::layout::PaneLayout::from_config(&self.layout).context("layout")?;
+            for name in &self.status_bar.segments {
+                crate::ui::statusbar::Segment::parse(name)
+                    .ok_or_else(|| anyhow::anyhow!("status_bar: unknown segment '{}'", name))?;
+            }
+            // `auto` is left for startup, when the terminal is probed
+            if self.colors != "auto" {
+                crate::ui::color::ColorDepth::from_config(&self.colors)?;
+            }
+            crate::effects::cyberpunk::CyberTheme::from_config(&self.theme, &self.theme.name)
+                .context("theme")?;
+            crate::effects::startup::StartupStyle::parse(&self.effects.startup.style)
+                .context("effects.startup.style")?;
+            crate::effects::pipeline::compile(
+                &self.effects.custom,
+                &crate::effects::cyberpunk::CyberPalette::default(),
+            )?;
+        }
+
         Ok(())
     }
 
@@ -204,6 +556,28 @@ impl UiConfig {
         self.keybindings.merge_with(other.keybindings);
         self.effects.merge_with(other.effects);
         self.editor.merge_with(other.editor);
+        if other.fps != default_fps() {
+            self.fps = other.fps;
+        }
+        if other.tick_ms != default_tick_ms() {
+            self.tick_ms = other.tick_ms;
+        }
+        if other.idle_fps.is_some() {
+            self.idle_fps = other.idle_fps;
+        }
+        self.notifications.merge_with(other.notifications);
+        if other.status_bar != StatusBarConfig::default() {
+            self.status_bar = other.status_bar;
+        }
+        if other.accessibility {
+            self.accessibility = true;
+        }
+        if other.colors != default_colors() {
+            self.colors = other.colors;
+        }
+        if other.keymap != default_keymap() {
+            self.keymap = other.keymap;
+        }
     }
 
     pub fn get_agent_color(&self, agent_name: &str) -> Option<&String> {
@@ -213,18 +587,25 @@ impl UiConfig {
     pub fn get_custom_color(&self, color_name: &str) -> Option<&String> {
         self.theme.custom_colors.get(color_name)
     }
+}
 
-    pub fn get_keybinding(&self, action: &str) -> Option<&String> {
-        match action {
-            "quit" => Some(&self.keybindings.quit),
-            "new_session" => Some(&self.keybindings.new_session),
-            "switch_agent" => Some(&self.keybindings.switch_agent),
-            "accept_edit" => Some(&self.keybindings.accept_edit),
-            "reject_edit" => Some(&self.keybindings.reject_edit),
-            "toggle_terminal" => Some(&self.keybindings.toggle_terminal),
-            "next_tab" => Some(&self.keybindings.next_tab),
-            "prev_tab" => Some(&self.keybindings.prev_tab),
-            _ => self.keybindings.custom_bindings.get(action),
+impl NotificationsConfig {
+    pub fn merge_with(&mut self, other: NotificationsConfig) {
+        let defaults = NotificationsConfig::default();
+        if other.desktop != defaults.desktop {
+            self.desktop = other.desktop;
+        }
+        if other.turn_complete != defaults.turn_complete {
+            self.turn_complete = other.turn_complete;
+        }
+        if other.errors != defaults.errors {
+            self.errors = other.errors;
+        }
+        if other.permissions != defaults.permissions {
+            self.permissions = other.permissions;
+        }
+        if other.bell != defaults.bell {
+            self.bell = other.bell;
         }
     }
 }
@@ -239,6 +620,7 @@ impl ThemeConfig {
         }
         self.custom_colors.extend(other.custom_colors);
         self.agent_colors.extend(other.agent_colors);
+        self.themes.extend(other.themes);
     }
 }
 
@@ -265,6 +647,21 @@ impl LayoutConfig {
         if other.chat_history_limit != LayoutConfig::default().chat_history_limit {
             self.chat_history_limit = other.chat_history_limit;
         }
+        if !other.panes.is_empty() {
+            self.panes = other.panes;
+        }
+        if other.split != default_split() {
+            self.split = other.split;
+        }
+        if other.main_percent != default_main_percent() {
+            self.main_percent = other.main_percent;
+        }
+        if other.input_height != default_input_height() {
+            self.input_height = other.input_height;
+        }
+        if other.tab_bar_height != default_tab_bar_height() {
+            self.tab_bar_height = other.tab_bar_height;
+        }
     }
 }
 
@@ -325,6 +722,109 @@ impl EffectsConfig {
         if other.startup.duration_ms != StartupEffectsConfig::default().duration_ms {
             self.startup.duration_ms = other.startup.duration_ms;
         }
+        if other.startup.style != StartupEffectsConfig::default().style {
+            self.startup.style = other.startup.style;
+        }
+        let defaults = EffectsConfig::default();
+        if other.reduce_motion != defaults.reduce_motion {
+            self.reduce_motion = other.reduce_motion;
+        }
+        if other.chat_sweep != defaults.chat_sweep {
+            self.chat_sweep = other.chat_sweep;
+        }
+        if other.glitch != defaults.glitch {
+            self.glitch = other.glitch;
+        }
+        if other.border_pulse != defaults.border_pulse {
+            self.border_pulse = other.border_pulse;
+        }
+        if other.hue_drift != defaults.hue_drift {
+            self.hue_drift = other.hue_drift;
+        }
+        if other.permission_alert != defaults.permission_alert {
+            self.permission_alert = other.permission_alert;
+        }
+        if other.auto_degrade != defaults.auto_degrade {
+            self.auto_degrade = other.auto_degrade;
+        }
+        if other.force != defaults.force {
+            self.force = other.force;
+        }
+        if other.components != defaults.components {
+            self.components = other.components;
+        }
+        // Later layers add effects or replace them by name
+        self.custom.extend(other.custom);
+    }
+
+    /// The keys under `[ui.effects]` that `apply_preset` sets
+    pub const PRESET_KEYS: [&'static str; 7] = [
+        "enabled",
+        "reduce_motion",
+        "chat_sweep",
+        "glitch",
+        "border_pulse",
+        "hue_drift",
+        "permission_alert",
+    ];
+
+    /// Set the per-effect toggles to a named preset from `EFFECT_PRESETS`
+    pub fn apply_preset(&mut self, name: &str) -> Result<()> {
+        let (sweep, ambient, still) = match name {
+            "full" => (true, true, false),
+            "subtle" => (true, false, false),
+            "still" => (false, false, true),
+            _ => anyhow::bail!("unknown effects preset '{}'", name),
+        };
+        self.enabled = true;
+        self.reduce_motion = still;
+        self.chat_sweep = sweep;
+        self.glitch = ambient;
+        self.border_pulse = ambient;
+        self.hue_drift = ambient;
+        // An alert rather than ambience; only `still` stops it
+        self.permission_alert = !still;
+        Ok(())
+    }
+
+    /// Whether any animation may run (`enabled` and not `reduce_motion`)
+    pub fn animate(&self) -> bool {
+        self.enabled && !self.reduce_motion
+    }
+
+    pub fn startup_active(&self) -> bool {
+        self.animate() && self.startup.enabled && self.startup.style != "none"
+    }
+
+    /// Whether effects may draw on a component from `EFFECT_COMPONENTS`
+    pub fn on(&self, component: &str) -> bool {
+        self.components.iter().any(|name| name == component)
+    }
+
+    pub fn chat_sweep_active(&self) -> bool {
+        self.animate() && self.chat_sweep && self.on("chat")
+    }
+
+    pub fn glitch_active(&self) -> bool {
+        self.animate() && self.glitch && self.on("chat")
+    }
+
+    pub fn border_pulse_active(&self) -> bool {
+        self.animate() && self.border_pulse && self.on("border")
+    }
+
+    pub fn permission_alert_active(&self) -> bool {
+        self.animate() && self.permission_alert
+    }
+
+    /// The drift covers the text of whichever of the chat, tabs, panes and
+    /// status bar are in `components`
+    pub fn hue_drift_active(&self) -> bool {
+        self.animate()
+            && self.hue_drift
+            && ["chat", "tabs", "panes", "statusbar"]
+                .iter()
+                .any(|component| self.on(component))
     }
 }
 
@@ -347,3 +847,65 @@ impl EditorConfig {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reduce_motion_stops_every_effect_but_keeps_the_toggles() {
+        let mut effects = EffectsConfig {
+            glitch: false,
+            ..EffectsConfig::default()
+        };
+        assert!(!effects.reduce_motion);
+        assert!(effects.chat_sweep_active());
+        assert!(!effects.glitch_active());
+        assert!(effects.border_pulse_active());
+        assert!(effects.hue_drift_active());
+
+        effects.reduce_motion = true;
+        assert!(!effects.animate());
+        assert!(!effects.startup_active());
+        assert!(!effects.chat_sweep_active());
+        assert!(!effects.border_pulse_active());
+        assert!(!effects.hue_drift_active());
+        assert!(effects.chat_sweep && effects.border_pulse && effects.hue_drift);
+    }
+
+    #[test]
+    fn effect_toggles_merge_from_later_layers() {
+        let mut effects = EffectsConfig::default();
+        effects.merge_with(EffectsConfig {
+            reduce_motion: true,
+            hue_drift: false,
+            ..EffectsConfig::default()
+        });
+        assert!(effects.reduce_motion);
+        assert!(!effects.hue_drift);
+        assert!(effects.glitch);
+
+        // A layer that leaves a toggle at its default keeps the earlier value
+        effects.merge_with(EffectsConfig::default());
+        assert!(effects.reduce_motion);
+        assert!(!effects.hue_drift);
+    }
+
+    #[test]
+    fn frame_pacing_is_validated() {
+        let ui = UiConfig::default();
+        assert!(ui.validate().is_ok());
+
+        let check = |update: fn(&mut UiConfig)| {
+            let mut ui = UiConfig::default();
+            update(&mut ui);
+            ui.validate()
+        };
+        assert!(check(|ui| ui.fps = 0).is_err());
+        assert!(check(|ui| ui.fps = 121).is_err());
+        assert!(check(|ui| ui.tick_ms = 5).is_err());
+        assert!(check(|ui| ui.idle_fps = Some(0)).is_err());
+        assert!(check(|ui| ui.idle_fps = Some(ui.fps + 1)).is_err());
+        assert!(check(|ui| ui.idle_fps = Some(1)).is_ok());
+    }
+}
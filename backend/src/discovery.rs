@@ -0,0 +1,160 @@
+//! LAN discovery of RAT bridges over mDNS / DNS-SD (`_rat._tcp`).
+
+use anyhow::Result;
+use tracing::{info, warn};
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::time::Duration;
+
+/// DNS-SD service type advertised by `--local-ws` and `rat serve`
+pub const SERVICE_TYPE: &str = "_rat._tcp.local.";
+
+/// A RAT bridge found on the network
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Peer {
+    pub name: String,
+    pub host: String,
+    pub addresses: Vec<IpAddr>,
+    pub port: u16,
+    pub version: Option<String>,
+}
+
+impl Peer {
+    /// WebSocket URL for the first address, e.g. `ws://192.168.1.20:8081/`
+    pub fn ws_url(&self) -> Option<String> {
+        let addr = self.addresses.first()?;
+        Some(match addr {
+            IpAddr::V4(v4) => format!("ws://{}:{}/", v4, self.port),
+            IpAddr::V6(v6) => format!("ws://[{}]:{}/", v6, self.port),
+        })
+    }
+
+    fn from_info(info: &ServiceInfo) -> Self {
+        let name = info
+            .get_fullname()
+            .strip_suffix(SERVICE_TYPE)
+            .unwrap_or(info.get_fullname())
+            .trim_end_matches('.')
+            .to_string();
+        let mut addresses: Vec<IpAddr> = info.get_addresses().iter().copied().collect();
+        // IPv4 first; it's what most clients reach a LAN host on
+        addresses.sort_by_key(|addr| (addr.is_ipv6(), *addr));
+        Self {
+            name,
+            host: info.get_hostname().trim_end_matches('.').to_string(),
+            addresses,
+            port: info.get_port(),
+            version: info.get_property_val_str("version").map(str::to_string),
+        }
+    }
+}
+
+/// Keeps this machine's bridge advertised until dropped
+pub struct Advertisement {
+    daemon: ServiceDaemon,
+    fullname: String,
+}
+
+impl Drop for Advertisement {
+    fn drop(&mut self) {
+        let _ = self.daemon.unregister(&self.fullname);
+        let _ = self.daemon.shutdown();
+    }
+}
+
+/// Advertise the bridge listening on `port` as `<host>-<port>._rat._tcp.local.`
+pub fn advertise(port: u16) -> Result<Advertisement> {
+    let daemon = ServiceDaemon::new()?;
+    let host = host_name();
+    let instance = format!("{}-{}", host, port);
+    let properties = [
+        ("version", env!("CARGO_PKG_VERSION")),
+        ("path", "/"),
+        ("protocol", "acp.jsonrpc.v1"),
+    ];
+    let service = ServiceInfo::new(
+        SERVICE_TYPE,
+        &instance,
+        &format!("{}.local.", host),
+        "",
+        port,
+        &properties[..],
+    )?
+    .enable_addr_auto();
+    let fullname = service.get_fullname().to_string();
+    daemon.register(service)?;
+    info!("Advertising {} via mDNS", fullname);
+    Ok(Advertisement { daemon, fullname })
+}
+
+/// RAT bridges that answer within `timeout`, sorted by name
+pub async fn browse(timeout: Duration) -> Result<Vec<Peer>> {
+    let daemon = ServiceDaemon::new()?;
+    let events = daemon.browse(SERVICE_TYPE)?;
+    let mut peers = BTreeMap::new();
+    let deadline = tokio::time::sleep(timeout);
+    tokio::pin!(deadline);
+    loop {
+        tokio::select! {
+            event = events.recv_async() => match event {
+                Ok(ServiceEvent::ServiceResolved(info)) => {
+                    let peer = Peer::from_info(&info);
+                    peers.insert(peer.name.clone(), peer);
+                }
+                Ok(ServiceEvent::ServiceRemoved(_, fullname)) => {
+                    peers.retain(|_, peer: &mut Peer| !fullname.starts_with(&peer.name));
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    warn!("mDNS browse ended early: {}", e);
+                    break;
+                }
+            },
+            _ = &mut deadline => break,
+        }
+    }
+    let _ = daemon.shutdown();
+    Ok(peers.into_values().collect())
+}
+
+/// This machine's short host name, as used for `<host>.local`
+fn host_name() -> String {
+    #[cfg(unix)]
+    {
+        let mut buf = [0u8; 256];
+        let rc = unsafe { libc::gethostname(buf.as_mut_ptr().cast(), buf.len()) };
+        if rc == 0 {
+            let end = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+            let name = String::from_utf8_lossy(&buf[..end]);
+            if let Some(short) = name.split('.').next().filter(|s| !s.is_empty()) {
+                return short.to_string();
+            }
+        }
+    }
+    std::env::var("COMPUTERNAME")
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .unwrap_or_else(|_| "rat".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn peer_urls_prefer_ipv4_and_bracket_ipv6() {
+        let mut peer = Peer {
+            name: "laptop-8081".to_string(),
+            host: "laptop.local".to_string(),
+            addresses: vec!["fe80::1".parse().unwrap()],
+            port: 8081,
+            version: None,
+        };
+        assert_eq!(peer.ws_url().as_deref(), Some("ws://[fe80::1]:8081/"));
+        peer.addresses.insert(0, "192.168.1.20".parse().unwrap());
+        assert_eq!(peer.ws_url().as_deref(), Some("ws://192.168.1.20:8081/"));
+        peer.addresses.clear();
+        assert_eq!(peer.ws_url(), None);
+    }
+}
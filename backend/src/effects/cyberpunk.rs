@@ -1,7 +1,11 @@
+use anyhow::Result;
 use ratatui::style::{Color, Style};
+use std::str::FromStr;
 use tachyonfx::{fx, Effect, EffectTimer, Interpolation, CellFilter, IntoEffect};
 use tachyonfx::fx::Glitch;
 
+use crate::config::ui::ThemeConfig;
+
 // A bold, neon-infused color palette suitable for cyberpunk vibes
 #[derive(Clone, Copy, Debug)]
 pub struct CyberPalette {
@@ -13,6 +17,18 @@ pub struct CyberPalette {
     pub accent_c: Color,   // electric purple
     pub warning: Color,
     pub success: Color,
+    pub error: Color,
+    pub border: Color,     // chat and panel borders
+    pub muted: Color,      // hints, separators, inactive elements
+    pub diff_added: Color,
+    pub diff_removed: Color,
+    pub diff_context: Color,
+    pub diff_hunk: Color,  // @@ headers
+    pub diff_meta: Color,  // diff --git / index lines
+    pub user: Color,       // message role colors
+    pub agent: Color,
+    pub tool: Color,
+    pub system: Color,
 }
 
 impl Default for CyberPalette {
@@ -26,6 +42,87 @@ impl Default for CyberPalette {
             accent_c: Color::from_u32(0x8b5cf6), // purple
             warning: Color::from_u32(0xffb86c),
             success: Color::from_u32(0x50fa7b),
+            error: Color::Red,
+            border: Color::from_u32(0x18e5ff),
+            muted: Color::DarkGray,
+            diff_added: Color::Green,
+            diff_removed: Color::Red,
+            diff_context: Color::White,
+            diff_hunk: Color::Cyan,
+            diff_meta: Color::Magenta,
+            user: Color::Cyan,
+            agent: Color::Green,
+            tool: Color::Blue,
+            system: Color::Gray,
+        }
+    }
+}
+
+impl CyberPalette {
+    /// Color names accepted in `[ui.theme.themes.<name>]`
+    pub const KEYS: &'static [&'static str] = &[
+        "background", "surface", "text", "accent_a", "accent_b", "accent_c", "warning",
+        "success", "error", "border", "muted", "diff_added", "diff_removed", "diff_context",
+        "diff_hunk", "diff_meta", "user", "agent", "tool", "system",
+    ];
+
+    /// Set one color by its config name
+    pub fn set(&mut self, key: &str, color: Color) -> Result<()> {
+        let Some(slot) = self.slot(key) else {
+            return Err(anyhow::anyhow!(
+                "unknown theme color '{}' (expected one of: {})",
+                key,
+                Self::KEYS.join(", ")
+            ));
+        };
+        *slot = color;
+        Ok(())
+    }
+
+    /// One color by its config name
+    pub fn get(&self, key: &str) -> Option<Color> {
+        let mut palette = *self;
+        palette.slot(key).copied()
+    }
+
+    fn slot(&mut self, key: &str) -> Option<&mut Color> {
+        let slot = match key {
+            "background" => &mut self.crust,
+            "surface" => &mut self.surface,
+            "text" => &mut self.text,
+            "accent_a" => &mut self.accent_a,
+            "accent_b" => &mut self.accent_b,
+            "accent_c" => &mut self.accent_c,
+            "warning" => &mut self.warning,
+            "success" => &mut self.success,
+            "error" => &mut self.error,
+            "border" => &mut self.border,
+            "muted" => &mut self.muted,
+            "diff_added" => &mut self.diff_added,
+            "diff_removed" => &mut self.diff_removed,
+            "diff_context" => &mut self.diff_context,
+            "diff_hunk" => &mut self.diff_hunk,
+            "diff_meta" => &mut self.diff_meta,
+            "user" => &mut self.user,
+            "agent" => &mut self.agent,
+            "tool" => &mut self.tool,
+            "system" => &mut self.system,
+            _ => return None,
+        };
+        Some(slot)
+    }
+
+    /// Same hues on a black background, with text, hints and inactive
+    /// elements raised to full brightness (accessibility mode)
+    pub fn high_contrast(self) -> Self {
+        Self {
+            crust: Color::Black,
+            surface: Color::Black,
+            text: Color::White,
+            muted: Color::Gray,
+            system: Color::White,
+            diff_context: Color::White,
+            ..self
         }
     }
 }
@@ -42,6 +139,77 @@ impl Default for CyberTheme {
 }
 
 impl CyberTheme {
+    /// Themes that need no configuration
+    pub const BUILTIN: &'static [&'static str] = &[
+        "cyberpunk",
+        "gruvbox",
+        "dracula",
+        "solarized-dark",
+        "solarized-light",
+        "monochrome",
+    ];
+
+    pub fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "default" | "cyberpunk" => Some(Self::default()),
+            _ => super::themes::preset(name).map(|palette| Self { palette }),
+        }
+    }
+
+    /// Resolve a theme by name: a `[ui.theme.themes.<name>]` section (colors
+    /// layered over its `base` theme, cyberpunk if unset) or a built-in
+    pub fn from_config(config: &ThemeConfig, name: &str) -> Result<Self> {
+        Self::resolve(config, name, 0)
+    }
+
+    fn resolve(config: &ThemeConfig, name: &str, depth: usize) -> Result<Self> {
+        if depth > 8 {
+            return Err(anyhow::anyhow!("theme '{}': base themes form a cycle", name));
+        }
+        let Some(colors) = config.themes.get(name) else {
+            return Self::builtin(name).ok_or_else(|| {
+                anyhow::anyhow!(
+                    "unknown theme '{}' (available: {})",
+                    name,
+                    Self::names(config).join(", ")
+                )
+            });
+        };
+
+        let mut theme = match colors.get("base") {
+            // A config theme may restyle the built-in of the same name
+            Some(base) if base == name => Self::builtin(name).unwrap_or_default(),
+            Some(base) => Self::resolve(config, base, depth + 1)?,
+            None => Self::default(),
+        };
+        for (key, value) in colors.iter().filter(|(key, _)| key.as_str() != "base") {
+            let color = Color::from_str(value).map_err(|_| {
+                anyhow::anyhow!("theme '{}': invalid color '{}' for {}", name, value, key)
+            })?;
+            theme
+                .palette
+                .set(key, color)
+                .map_err(|e| anyhow::anyhow!("theme '{}': {}", name, e))?;
+        }
+        Ok(theme)
+    }
+
+    /// Built-in themes followed by configured ones, for the theme switcher
+    pub fn names(config: &ThemeConfig) -> Vec<String> {
+        let mut custom: Vec<String> = config
+            .themes
+            .keys()
+            .filter(|name| !Self::BUILTIN.contains(&name.as_str()))
+            .cloned()
+            .collect();
+        custom.sort();
+        Self::BUILTIN
+            .iter()
+            .map(|name| name.to_string())
+            .chain(custom)
+            .collect()
+    }
+
     pub fn surface_style(&self) -> Style {
         Style::default().bg(self.palette.surface).fg(self.palette.text)
     }
@@ -52,7 +220,7 @@ impl CyberTheme {
         Style::default().fg(self.palette.accent_b)
     }
     pub fn border_inactive(&self) -> Style {
-        Style::default().fg(Color::DarkGray)
+        Style::default().fg(self.palette.muted)
     }
     pub fn title_active(&self) -> Style {
         Style::default()
@@ -60,7 +228,7 @@ impl CyberTheme {
             .add_modifier(ratatui::style::Modifier::BOLD)
     }
     pub fn title_inactive(&self) -> Style {
-        Style::default().fg(Color::Gray)
+        Style::default().fg(self.palette.system)
     }
 }
 
@@ -73,6 +241,18 @@ pub fn neon_pulse_border(theme: &CyberTheme) -> Effect {
     )
 }
 
+// Fast pulse on the border of a dialog that is waiting on the user
+pub fn permission_pulse(color: Color) -> Effect {
+    let pulse = fx::fade_to_fg(color, (450, Interpolation::SineInOut))
+        .with_filter(CellFilter::Outer(ratatui::layout::Margin::new(1, 1)));
+    fx::repeating(fx::ping_pong(pulse))
+}
+
+// Blink a status bar segment between its colors and a highlight
+pub fn permission_flash(fg: Color, bg: Color) -> Effect {
+    fx::repeating(fx::ping_pong(fx::fade_to(fg, bg, (500, Interpolation::QuadInOut))))
+}
+
 // Subtle foreground hue drift on text to make UI feel alive
 pub fn subtle_hsl_drift() -> Effect {
     // Softly breathe the foreground between two nearby tones
@@ -103,3 +283,46 @@ pub fn glitch_burst() -> Effect {
         .into_effect()
         .with_filter(CellFilter::Text)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_theme_layers_over_base() {
+        let mut config = ThemeConfig::default();
+        config.themes.insert(
+            "midnight".to_string(),
+            [("background", "#000000"), ("user", "yellow")]
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        );
+
+        let theme = CyberTheme::from_config(&config, "midnight").unwrap();
+        assert_eq!(theme.palette.crust, Color::Rgb(0, 0, 0));
+        assert_eq!(theme.palette.user, Color::Yellow);
+        assert_eq!(theme.palette.accent_a, CyberPalette::default().accent_a);
+        assert_eq!(CyberTheme::names(&config).last().unwrap(), "midnight");
+
+        config
+            .themes
+            .get_mut("midnight")
+            .unwrap()
+            .insert("sparkle".to_string(), "red".to_string());
+        assert!(CyberTheme::from_config(&config, "midnight").is_err());
+        assert!(CyberTheme::from_config(&config, "nope").is_err());
+    }
+
+    #[test]
+    fn every_builtin_resolves() {
+        let config = ThemeConfig::default();
+        for name in CyberTheme::BUILTIN {
+            assert!(CyberTheme::from_config(&config, name).is_ok(), "{}", name);
+        }
+        assert_ne!(
+            CyberTheme::builtin("solarized-light").unwrap().palette.crust,
+            CyberTheme::builtin("solarized-dark").unwrap().palette.crust
+        );
+    }
+}
@@ -0,0 +1,263 @@
+// User-defined effects from `[ui.effects.custom.<name>]`, compiled into
+// tachyonfx effects when the config is loaded
+use anyhow::{Context, Result};
+use ratatui::layout::Margin;
+use ratatui::style::Color;
+use std::collections::HashMap;
+use std::str::FromStr;
+use tachyonfx::{fx, CellFilter, Effect, EffectTimer, Interpolation, Motion, RefRect};
+
+use super::cyberpunk::CyberPalette;
+use crate::config::ui::{CustomEffectConfig, EffectStepConfig};
+
+/// Easing names accepted in `interpolation`
+const INTERPOLATIONS: &[(&str, Interpolation)] = &[
+    ("linear", Interpolation::Linear),
+    ("reverse", Interpolation::Reverse),
+    ("back_in", Interpolation::BackIn),
+    ("back_out", Interpolation::BackOut),
+    ("back_in_out", Interpolation::BackInOut),
+    ("bounce_in", Interpolation::BounceIn),
+    ("bounce_out", Interpolation::BounceOut),
+    ("bounce_in_out", Interpolation::BounceInOut),
+    ("circ_in", Interpolation::CircIn),
+    ("circ_out", Interpolation::CircOut),
+    ("circ_in_out", Interpolation::CircInOut),
+    ("cubic_in", Interpolation::CubicIn),
+    ("cubic_out", Interpolation::CubicOut),
+    ("cubic_in_out", Interpolation::CubicInOut),
+    ("elastic_in", Interpolation::ElasticIn),
+    ("elastic_out", Interpolation::ElasticOut),
+    ("elastic_in_out", Interpolation::ElasticInOut),
+    ("expo_in", Interpolation::ExpoIn),
+    ("expo_out", Interpolation::ExpoOut),
+    ("expo_in_out", Interpolation::ExpoInOut),
+    ("quad_in", Interpolation::QuadIn),
+    ("quad_out", Interpolation::QuadOut),
+    ("quad_in_out", Interpolation::QuadInOut),
+    ("quart_in", Interpolation::QuartIn),
+    ("quart_out", Interpolation::QuartOut),
+    ("quart_in_out", Interpolation::QuartInOut),
+    ("quint_in", Interpolation::QuintIn),
+    ("quint_out", Interpolation::QuintOut),
+    ("quint_in_out", Interpolation::QuintInOut),
+    ("sine_in", Interpolation::SineIn),
+    ("sine_out", Interpolation::SineOut),
+    ("sine_in_out", Interpolation::SineInOut),
+];
+
+/// When a custom effect runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trigger {
+    /// Over and over while effects are on
+    Ambient,
+    /// Once each time a message arrives
+    Message,
+}
+
+/// Part of the screen a custom effect draws on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    Screen,
+    /// The active tab's conversation
+    Chat,
+    /// The outermost ring of cells
+    Border,
+    StatusBar,
+}
+
+impl Target {
+    /// Entry in `ui.effects.components` that must be on for it to draw
+    pub fn component(self) -> Option<&'static str> {
+        match self {
+            Target::Screen => None,
+            Target::Chat => Some("chat"),
+            Target::Border => Some("border"),
+            Target::StatusBar => Some("statusbar"),
+        }
+    }
+}
+
+/// A custom effect ready to run; `instance` hands out copies
+#[derive(Debug, Clone)]
+pub struct CustomEffect {
+    pub trigger: Trigger,
+    pub target: Target,
+    effect: Effect,
+}
+
+impl CustomEffect {
+    /// A fresh copy confined to its target, following the chat and status
+    /// bar areas as they move. Ambient effects repeat.
+    pub fn instance(&self, chat: &RefRect, status_bar: &RefRect) -> Effect {
+        let effect = match self.target {
+            Target::Screen => self.effect.clone(),
+            Target::Border => self
+                .effect
+                .clone()
+                .with_filter(CellFilter::Outer(Margin::new(1, 1))),
+            Target::Chat => fx::dynamic_area(chat.clone(), self.effect.clone()),
+            Target::StatusBar => fx::dynamic_area(status_bar.clone(), self.effect.clone()),
+        };
+        match self.trigger {
+            Trigger::Ambient => fx::repeating(effect),
+            Trigger::Message => effect,
+        }
+    }
+}
+
+/// Compile every custom effect, sorted by name, with colors taken from
+/// `palette`. Errors name the effect and the step at fault.
+pub fn compile(
+    configs: &HashMap<String, CustomEffectConfig>,
+    palette: &CyberPalette,
+) -> Result<Vec<CustomEffect>> {
+    let mut names: Vec<&String> = configs.keys().collect();
+    names.sort();
+    names
+        .into_iter()
+        .map(|name| {
+            compile_one(&configs[name], palette)
+                .with_context(|| format!("effects.custom.{}", name))
+        })
+        .collect()
+}
+
+fn compile_one(config: &CustomEffectConfig, palette: &CyberPalette) -> Result<CustomEffect> {
+    let trigger = match config.on.as_str() {
+        "ambient" => Trigger::Ambient,
+        "message" => Trigger::Message,
+        other => anyhow::bail!("on must be 'ambient' or 'message', got '{}'", other),
+    };
+    let target = match config.target.as_str() {
+        "screen" => Target::Screen,
+        "chat" => Target::Chat,
+        "border" => Target::Border,
+        "statusbar" => Target::StatusBar,
+        other => anyhow::bail!(
+            "target must be one of screen, chat, border, statusbar; got '{}'",
+            other
+        ),
+    };
+    Ok(CustomEffect {
+        trigger,
+        target,
+        effect: fx::sequence(&compile_steps(&config.steps, palette)?),
+    })
+}
+
+fn compile_steps(steps: &[EffectStepConfig], palette: &CyberPalette) -> Result<Vec<Effect>> {
+    if steps.is_empty() {
+        anyhow::bail!("steps must not be empty");
+    }
+    steps
+        .iter()
+        .enumerate()
+        .map(|(i, step)| compile_step(step, palette).with_context(|| format!("step {}", i + 1)))
+        .collect()
+}
+
+fn compile_step(step: &EffectStepConfig, palette: &CyberPalette) -> Result<Effect> {
+    match step.kind.as_str() {
+        "sequence" => return Ok(fx::sequence(&compile_steps(&step.steps, palette)?)),
+        "parallel" => return Ok(fx::parallel(&compile_steps(&step.steps, palette)?)),
+        _ => {}
+    }
+    let ms = step
+        .ms
+        .ok_or_else(|| anyhow::anyhow!("'{}' needs ms", step.kind))?;
+    let interpolation = match &step.interpolation {
+        Some(name) => INTERPOLATIONS
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, interpolation)| *interpolation)
+            .ok_or_else(|| anyhow::anyhow!("unknown interpolation '{}'", name))?,
+        None => Interpolation::Linear,
+    };
+    let timer = EffectTimer::from_ms(ms, interpolation);
+    let color = || -> Result<Color> {
+        let value = step
+            .color
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("'{}' needs a color", step.kind))?;
+        palette
+            .get(value)
+            .or_else(|| Color::from_str(value).ok())
+            .ok_or_else(|| anyhow::anyhow!("'{}' is not a theme color or a color", value))
+    };
+    Ok(match step.kind.as_str() {
+        "fade_from" => fx::fade_from_fg(color()?, timer),
+        "fade_to" => fx::fade_to_fg(color()?, timer),
+        "sweep_in" => fx::sweep_in(Motion::LeftToRight, 10, 2, color()?, timer),
+        "sweep_out" => fx::sweep_out(Motion::LeftToRight, 10, 2, color()?, timer),
+        "hsl_shift" => {
+            let hsl = step
+                .hsl
+                .ok_or_else(|| anyhow::anyhow!("'hsl_shift' needs hsl = [hue, saturation, lightness]"))?;
+            fx::hsl_shift_fg(hsl, timer)
+        }
+        "dissolve" => fx::dissolve(timer),
+        "coalesce" => fx::coalesce(timer),
+        "sleep" => fx::sleep(timer),
+        other => anyhow::bail!(
+            "unknown type '{}' (expected sequence, parallel, fade_from, fade_to, sweep_in, \
+             sweep_out, hsl_shift, dissolve, coalesce or sleep)",
+            other
+        ),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compile_toml(toml: &str) -> Result<Vec<CustomEffect>> {
+        let configs: HashMap<String, CustomEffectConfig> = toml::from_str(toml).unwrap();
+        compile(&configs, &CyberPalette::default())
+    }
+
+    #[test]
+    fn compiles_nested_steps_and_names_the_bad_one() {
+        let effects = compile_toml(
+            r##"
+            [arrival]
+            on = "message"
+            target = "border"
+            steps = [
+              { type = "fade_from", color = "accent_a", ms = 300 },
+              { type = "parallel", steps = [
+                { type = "hsl_shift", hsl = [40.0, 0.0, 0.0], ms = 500, interpolation = "sine_in_out" },
+                { type = "sweep_in", color = "#ff0000", ms = 400 },
+              ] },
+            ]
+
+            [glow]
+            steps = [{ type = "fade_to", color = "muted", ms = 900 }]
+            "##,
+        )
+        .unwrap();
+        assert_eq!(effects[0].trigger, Trigger::Message);
+        assert_eq!(effects[0].target, Target::Border);
+        assert_eq!(effects[1].trigger, Trigger::Ambient);
+        assert_eq!(effects[1].target, Target::Screen);
+
+        let error = compile_toml(
+            r#"
+            [arrival]
+            steps = [
+              { type = "sleep", ms = 100 },
+              { type = "parallel", steps = [{ type = "dissolve", ms = 1 }, { type = "explode", ms = 1 }] },
+            ]
+            "#,
+        )
+        .unwrap_err();
+        assert!(
+            format!("{:#}", error).starts_with("effects.custom.arrival: step 2: step 2: unknown type 'explode'"),
+            "{:#}",
+            error
+        );
+
+        let error = compile_toml("[arrival]\nsteps = [{ type = \"sweep_in\", ms = 100 }]").unwrap_err();
+        assert!(format!("{:#}", error).contains("'sweep_in' needs a color"), "{:#}", error);
+    }
+}
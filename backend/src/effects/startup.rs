@@ -1,5 +1,6 @@
+use anyhow::Result;
 use ratatui::{
-    buffer::Buffer,
+    buffer::{Buffer, Cell},
     layout::{Position, Rect},
     style::Color,
 };
@@ -12,6 +13,64 @@ use tachyonfx::{
     RefCount,
 };
 
+/// Background the intros draw on before the UI shows through
+const INTRO_BG: Color = Color::Rgb(2, 6, 8);
+
+/// Intro played over the first frame, named as in `ui.effects.startup.style`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StartupStyle {
+    /// Digital rain that dissolves into the UI
+    Matrix,
+    /// A bright line sweeping down, uncovering the UI row by row
+    Scanline,
+    /// The logo typed out on a dark screen, then dissolving into the UI
+    Typewriter,
+    None,
+}
+
+impl StartupStyle {
+    pub const ALL: [StartupStyle; 4] = [
+        StartupStyle::Matrix,
+        StartupStyle::Scanline,
+        StartupStyle::Typewriter,
+        StartupStyle::None,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            StartupStyle::Matrix => "matrix",
+            StartupStyle::Scanline => "scanline",
+            StartupStyle::Typewriter => "typewriter",
+            StartupStyle::None => "none",
+        }
+    }
+
+    pub fn parse(name: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|style| style.name() == name)
+            .ok_or_else(|| {
+                let names: Vec<_> = Self::ALL.iter().map(|style| style.name()).collect();
+                anyhow::anyhow!(
+                    "unknown startup style '{}' (expected one of: {})",
+                    name,
+                    names.join(", ")
+                )
+            })
+    }
+
+    /// The intro morphing into `target`, a snapshot of the first frame;
+    /// `None` for no intro
+    pub fn effect(self, target: RefCount<Buffer>, duration_ms: u64) -> Option<Effect> {
+        match self {
+            StartupStyle::Matrix => Some(matrix_rain_morph_with_duration(target, duration_ms)),
+            StartupStyle::Scanline => Some(scanline_sweep_with_duration(target, duration_ms)),
+            StartupStyle::Typewriter => Some(typewriter_logo_with_duration(target, duration_ms)),
+            StartupStyle::None => None,
+        }
+    }
+}
+
 #[derive(Clone)]
 struct RainState {
     width: u16,
@@ -63,16 +122,7 @@ pub fn matrix_rain_morph_with_duration(target: RefCount<Buffer>, duration_ms: u6
         let dt = ctx.last_tick.as_secs_f32().max(0.0);
         state.frame = state.frame.wrapping_add(1);
 
-        // Background
-        let bg = Color::Rgb(2, 6, 8);
-        for y in 0..state.height {
-            for x in 0..state.width {
-                if let Some(c) = buf.cell_mut(Position::new(buf.area.x + x, buf.area.y + y)) {
-                    c.set_char(' ');
-                    c.set_bg(bg);
-                }
-            }
-        }
+        clear_to_intro_bg(buf);
 
         // Advance columns
         for (x, col) in state.cols.iter_mut().enumerate() {
@@ -125,14 +175,8 @@ pub fn matrix_rain_morph_with_duration(target: RefCount<Buffer>, duration_ms: u6
             // Probability mask per cell → reveal target as alpha grows
             for y in 0..copy_h {
                 for x in 0..copy_w {
-                    let seed = ((x as u64) << 32) ^ (y as u64) ^ 0xD1B54A32D192ED03u64;
-                    let r = (mix_u32(seed) as f32) / (u32::MAX as f32);
-                    if r < alpha.powf(1.4) {
-                        if let Some(src) = tbuf.cell(Position::new(tbuf.area.x + x, tbuf.area.y + y)).cloned() {
-                            if let Some(dst) = buf.cell_mut(Position::new(buf.area.x + x, buf.area.y + y)) {
-                                *dst = src;
-                            }
-                        }
+                    if revealed(x, y, alpha) {
+                        copy_target_cell(&tbuf, buf, x, y);
                     } else {
                         // Slightly dim rain as we approach full reveal
                         if let Some(dst) = buf.cell_mut(Position::new(buf.area.x + x, buf.area.y + y)) {
@@ -157,6 +201,163 @@ pub fn matrix_rain_morph(target: RefCount<Buffer>) -> Effect {
     matrix_rain_morph_with_duration(target, 1800)
 }
 
+/// A bright scanline sweeps down the dark screen; the target shows above it,
+/// tinted by its glow for a few rows.
+pub fn scanline_sweep_with_duration(target: RefCount<Buffer>, duration_ms: u64) -> Effect {
+    const GLOW_ROWS: f32 = 3.0;
+    let timer = EffectTimer::from_ms(duration_ms as u32, Interpolation::SineInOut);
+
+    fx::effect_fn_buf((), timer, move |_, ctx, buf| {
+        if buf.area.width < 1 || buf.area.height < 1 {
+            return;
+        }
+        let tbuf = target.borrow();
+        let height = buf.area.height;
+        // The line starts above the screen and ends below it with its glow
+        let line = ctx.alpha().clamp(0.0, 1.0) * (height as f32 + GLOW_ROWS) - 1.0;
+
+        for y in 0..height {
+            let distance = line - y as f32;
+            for x in 0..buf.area.width {
+                let pos = Position::new(buf.area.x + x, buf.area.y + y);
+                if distance < 0.0 {
+                    // Not reached yet: dark, with faint CRT lines
+                    if let Some(cell) = buf.cell_mut(pos) {
+                        cell.set_char(' ');
+                        cell.set_bg(if y % 2 == 0 { INTRO_BG } else { Color::Rgb(6, 14, 18) });
+                    }
+                    continue;
+                }
+                copy_target_cell(&tbuf, buf, x, y);
+                if distance < GLOW_ROWS {
+                    if let Some(cell) = buf.cell_mut(pos) {
+                        let glow = 1.0 - distance / GLOW_ROWS;
+                        cell.set_fg(blend(cell.fg, Color::Rgb(200, 255, 255), glow));
+                        cell.set_bg(blend(cell.bg, Color::Rgb(0, 120, 140), glow * 0.6));
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// The RAT logo typed out in the middle of a dark screen with a block
+/// cursor, then dissolving into the target.
+pub fn typewriter_logo_with_duration(target: RefCount<Buffer>, duration_ms: u64) -> Effect {
+    // Share of the run spent typing; the rest dissolves into the UI
+    const TYPING: f32 = 0.6;
+    let timer = EffectTimer::from_ms(duration_ms as u32, Interpolation::Linear);
+
+    fx::effect_fn_buf((), timer, move |_, ctx, buf| {
+        if buf.area.width < 1 || buf.area.height < 1 {
+            return;
+        }
+        let alpha = ctx.alpha().clamp(0.0, 1.0);
+        let logo: &[&str] = if buf.area.width as usize >= LOGO[0].chars().count() + 2
+            && buf.area.height as usize >= LOGO.len() + 2
+        {
+            LOGO
+        } else {
+            &["RAT"]
+        };
+        let total: usize = logo.iter().map(|line| line.chars().count()).sum();
+        let typed = ((alpha / TYPING).min(1.0) * total as f32) as usize;
+
+        clear_to_intro_bg(buf);
+        let width = logo.iter().map(|line| line.chars().count()).max().unwrap_or(0) as u16;
+        let left = buf.area.x + buf.area.width.saturating_sub(width) / 2;
+        let top = buf.area.y + buf.area.height.saturating_sub(logo.len() as u16) / 2;
+        let mut remaining = typed;
+        let mut cursor = None;
+        for (row, line) in logo.iter().enumerate() {
+            let y = top + row as u16;
+            for (col, ch) in line.chars().enumerate() {
+                let pos = Position::new(left + col as u16, y);
+                if remaining == 0 {
+                    cursor.get_or_insert(pos);
+                    break;
+                }
+                remaining -= 1;
+                if let Some(cell) = buf.cell_mut(pos) {
+                    cell.set_char(ch);
+                    cell.set_fg(Color::Rgb(0, 255, 200));
+                }
+            }
+        }
+        // Blinks about three times a second while typing
+        let blink_on = (ctx.timer.remaining().as_millis() / 160) % 2 == 0;
+        if let (Some(pos), true) = (cursor, blink_on) {
+            if let Some(cell) = buf.cell_mut(pos) {
+                cell.set_char('█');
+                cell.set_fg(Color::Rgb(0, 255, 200));
+            }
+        }
+
+        if alpha > TYPING {
+            let progress = (alpha - TYPING) / (1.0 - TYPING);
+            let tbuf = target.borrow();
+            for y in 0..buf.area.height.min(tbuf.area.height) {
+                for x in 0..buf.area.width.min(tbuf.area.width) {
+                    if revealed(x, y, progress) {
+                        copy_target_cell(&tbuf, buf, x, y);
+                    }
+                }
+            }
+        }
+    })
+}
+
+// Block letters typed by the typewriter intro
+static LOGO: &[&str] = &[
+    " ____      _  _____ ",
+    "|  _ \\    / \\|_   _|",
+    "| |_) |  / _ \\ | |  ",
+    "|  _ <  / ___ \\| |  ",
+    "|_| \\_\\/_/   \\_\\_|  ",
+];
+
+fn clear_to_intro_bg(buf: &mut Buffer) {
+    let area = buf.area;
+    for y in area.top()..area.bottom() {
+        for x in area.left()..area.right() {
+            if let Some(cell) = buf.cell_mut(Position::new(x, y)) {
+                cell.set_char(' ');
+                cell.set_bg(INTRO_BG);
+            }
+        }
+    }
+}
+
+/// Whether cell (`x`, `y`) of the target shows once the reveal is at
+/// `progress`; a fixed per-cell threshold, so revealed cells stay put
+fn revealed(x: u16, y: u16, progress: f32) -> bool {
+    let seed = ((x as u64) << 32) ^ (y as u64) ^ 0xD1B54A32D192ED03u64;
+    let r = (mix_u32(seed) as f32) / (u32::MAX as f32);
+    r < progress.powf(1.4)
+}
+
+/// Put the target's cell at (`x`, `y`), relative to both areas, into `buf`
+fn copy_target_cell(target: &Buffer, buf: &mut Buffer, x: u16, y: u16) {
+    let src: Option<Cell> = target
+        .cell(Position::new(target.area.x + x, target.area.y + y))
+        .cloned();
+    if let (Some(src), Some(dst)) = (src, buf.cell_mut(Position::new(buf.area.x + x, buf.area.y + y))) {
+        *dst = src;
+    }
+}
+
+/// Mix RGB `color` towards `to` by `amount` (0..1); other colors jump at half
+fn blend(color: Color, to: Color, amount: f32) -> Color {
+    match (color, to) {
+        (Color::Rgb(r, g, b), Color::Rgb(r2, g2, b2)) => {
+            let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * amount) as u8;
+            Color::Rgb(mix(r, r2), mix(g, g2), mix(b, b2))
+        }
+        _ if amount >= 0.5 => to,
+        _ => color,
+    }
+}
+
 fn new_col(seed: u64, height: u16) -> Column {
     // speed ~ 8..24 cps
     let speed = 8.0 + (mix_u32(seed ^ 0xB5297A4D) % 17) as f32;
@@ -205,3 +406,47 @@ static KATAKANA: &[char] = &[
     '0', '1', '2', '3', '4', '5', '6', '7', '8', '9',
     '・', '･', '＝', '≡', '∵', '∴', '◇', '◆', '○', '●',
 ];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tachyonfx::ref_count;
+
+    fn run(style: StartupStyle, elapsed_ms: u32) -> Buffer {
+        let area = Rect::new(0, 0, 30, 10);
+        let mut target = Buffer::empty(area);
+        for y in 0..area.height {
+            target.set_string(0, y, "x".repeat(area.width as usize), ratatui::style::Style::default());
+        }
+        let mut effect = style.effect(ref_count(target), 1000).unwrap();
+        let mut buf = Buffer::empty(area);
+        effect.process(Duration::from_millis(elapsed_ms), &mut buf, area);
+        buf
+    }
+
+    fn row(buf: &Buffer, y: u16) -> String {
+        (0..buf.area.width).map(|x| buf[(x, y)].symbol()).collect()
+    }
+
+    #[test]
+    fn intros_end_on_the_target() {
+        assert_eq!(StartupStyle::parse("scanline").unwrap(), StartupStyle::Scanline);
+        assert!(StartupStyle::parse("fireworks").is_err());
+        assert!(StartupStyle::None.effect(ref_count(Buffer::empty(Rect::default())), 1000).is_none());
+
+        // Halfway through, the scanline has uncovered the top but not the bottom
+        let buf = run(StartupStyle::Scanline, 500);
+        assert_eq!(row(&buf, 0), "x".repeat(30));
+        assert_eq!(row(&buf, 9), " ".repeat(30));
+
+        // The typewriter has typed part of the logo and shows none of the UI
+        let buf = run(StartupStyle::Typewriter, 300);
+        let screen: String = (0..10).map(|y| row(&buf, y)).collect();
+        assert!(screen.contains("____") && !screen.contains('x'));
+
+        for style in [StartupStyle::Matrix, StartupStyle::Scanline, StartupStyle::Typewriter] {
+            let buf = run(style, 1000);
+            assert_eq!(row(&buf, 5), "x".repeat(30), "{:?}", style);
+        }
+    }
+}
@@ -1 +1,135 @@
-// Theme and color effects - stub for Phase 4
+// Built-in theme presets, all expressed as a CyberPalette
+use ratatui::style::Color;
+
+use super::cyberpunk::CyberPalette;
+
+/// Palette for a built-in preset other than the cyberpunk default
+pub fn preset(name: &str) -> Option<CyberPalette> {
+    match name {
+        "gruvbox" => Some(gruvbox()),
+        "dracula" => Some(dracula()),
+        "solarized-dark" => Some(solarized(true)),
+        "solarized-light" => Some(solarized(false)),
+        "monochrome" => Some(monochrome()),
+        _ => None,
+    }
+}
+
+fn rgb(hex: u32) -> Color {
+    Color::from_u32(hex)
+}
+
+fn gruvbox() -> CyberPalette {
+    CyberPalette {
+        crust: rgb(0x1d2021),
+        surface: rgb(0x282828),
+        text: rgb(0xebdbb2),
+        accent_a: rgb(0xfe8019),
+        accent_b: rgb(0x83a598),
+        accent_c: rgb(0xd3869b),
+        warning: rgb(0xfabd2f),
+        success: rgb(0xb8bb26),
+        error: rgb(0xfb4934),
+        border: rgb(0x83a598),
+        muted: rgb(0x665c54),
+        diff_added: rgb(0xb8bb26),
+        diff_removed: rgb(0xfb4934),
+        diff_context: rgb(0xd5c4a1),
+        diff_hunk: rgb(0x8ec07c),
+        diff_meta: rgb(0xd3869b),
+        user: rgb(0x83a598),
+        agent: rgb(0xb8bb26),
+        tool: rgb(0x458588),
+        system: rgb(0x928374),
+    }
+}
+
+fn dracula() -> CyberPalette {
+    CyberPalette {
+        crust: rgb(0x21222c),
+        surface: rgb(0x282a36),
+        text: rgb(0xf8f8f2),
+        accent_a: rgb(0xff79c6),
+        accent_b: rgb(0x8be9fd),
+        accent_c: rgb(0xbd93f9),
+        warning: rgb(0xf1fa8c),
+        success: rgb(0x50fa7b),
+        error: rgb(0xff5555),
+        border: rgb(0xbd93f9),
+        muted: rgb(0x6272a4),
+        diff_added: rgb(0x50fa7b),
+        diff_removed: rgb(0xff5555),
+        diff_context: rgb(0xf8f8f2),
+        diff_hunk: rgb(0x8be9fd),
+        diff_meta: rgb(0xff79c6),
+        user: rgb(0x8be9fd),
+        agent: rgb(0x50fa7b),
+        tool: rgb(0xbd93f9),
+        system: rgb(0x6272a4),
+    }
+}
+
+fn solarized(dark: bool) -> CyberPalette {
+    // base03..base3 swap roles between the dark and light variants
+    let (bg, bg_highlight, fg, comment) = if dark {
+        (rgb(0x002b36), rgb(0x073642), rgb(0x839496), rgb(0x586e75))
+    } else {
+        (rgb(0xfdf6e3), rgb(0xeee8d5), rgb(0x657b83), rgb(0x93a1a1))
+    };
+    let yellow = rgb(0xb58900);
+    let orange = rgb(0xcb4b16);
+    let red = rgb(0xdc322f);
+    let magenta = rgb(0xd33682);
+    let violet = rgb(0x6c71c4);
+    let blue = rgb(0x268bd2);
+    let cyan = rgb(0x2aa198);
+    let green = rgb(0x859900);
+    CyberPalette {
+        crust: bg,
+        surface: bg_highlight,
+        text: fg,
+        accent_a: magenta,
+        accent_b: blue,
+        accent_c: violet,
+        warning: yellow,
+        success: green,
+        error: red,
+        border: blue,
+        muted: comment,
+        diff_added: green,
+        diff_removed: red,
+        diff_context: fg,
+        diff_hunk: cyan,
+        diff_meta: orange,
+        user: blue,
+        agent: green,
+        tool: cyan,
+        system: comment,
+    }
+}
+
+fn monochrome() -> CyberPalette {
+    let (black, dark, grey, white) = (Color::Black, Color::DarkGray, Color::Gray, Color::White);
+    CyberPalette {
+        crust: black,
+        surface: black,
+        text: grey,
+        accent_a: white,
+        accent_b: white,
+        accent_c: grey,
+        warning: white,
+        success: white,
+        error: white,
+        border: grey,
+        muted: dark,
+        diff_added: white,
+        diff_removed: grey,
+        diff_context: dark,
+        diff_hunk: white,
+        diff_meta: grey,
+        user: white,
+        agent: grey,
+        tool: grey,
+        system: dark,
+    }
+}
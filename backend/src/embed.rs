@@ -0,0 +1,465 @@
+//! `rat --embed`: agent sessions served over msgpack-RPC on stdin/stdout for
+//! an editor frontend, such as a Neovim plugin that starts RAT with
+//! `jobstart({'rat', '--embed'}, {rpc = true})`. It drives the same
+//! `AgentManager` as the TUI, without the ratatui layer.
+//!
+//! Requests are answered as soon as they are taken on: Neovim's
+//! `rpcrequest` blocks the editor until the answer arrives, so a prompt's
+//! output, its permission requests and the end of its turn follow as
+//! events. A job can only notify Neovim by naming an API function, so each
+//! event is an `nvim_exec_lua` notification calling the `--embed-callback`
+//! Lua function with a table shaped like the lines of
+//! `rat exec --output json`.
+
+use agent_client_protocol as acp;
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::io::Write;
+use std::rc::Rc;
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+use tracing::{debug, info, warn};
+
+use crate::acp::store::SessionSummary;
+use crate::acp::{Message, SessionId, SessionRecorder, SessionStore};
+use crate::adapters::{AgentManager, ExternalAgentSpec};
+use crate::config::Config;
+use crate::events::{self, AppMessage, PermissionReply};
+use crate::headless;
+use crate::utils::msgpack::{self, MsgpackFramer};
+
+/// Lua function events go to unless `--embed-callback` names another
+pub const DEFAULT_CALLBACK: &str = "require('rat').on_event";
+
+/// How long `shutdown` waits for requests taken on before it
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
+
+// msgpack-RPC message types
+const REQUEST: u64 = 0;
+const RESPONSE: u64 = 1;
+const NOTIFICATION: u64 = 2;
+
+/// Serve the frontend on stdio until it asks to shut down or closes stdin.
+/// Returns the process exit code.
+pub async fn run(
+    config: Config,
+    external: Option<ExternalAgentSpec>,
+    callback: String,
+) -> Result<i32> {
+    // Adapters spawn non-Send tasks, so everything runs on a LocalSet
+    let local = tokio::task::LocalSet::new();
+    local.run_until(serve(config, external, callback)).await
+}
+
+/// A request, or a notification when it has no id to answer
+struct Request {
+    id: Option<u64>,
+    method: String,
+    params: Vec<Value>,
+}
+
+/// State shared by the request loop and the requests it spawns
+struct Server {
+    config: Config,
+    manager: AgentManager,
+    agents: Vec<String>,
+    default_agent: String,
+    callback: String,
+    /// Agent of each session opened through this server
+    sessions: RefCell<HashMap<SessionId, String>>,
+    /// Permission requests waiting on the frontend, by the id sent with them
+    permissions: RefCell<HashMap<u64, PermissionReply>>,
+    next_permission: Cell<u64>,
+}
+
+async fn serve(
+    config: Config,
+    external: Option<ExternalAgentSpec>,
+    callback: String,
+) -> Result<i32> {
+    let default_agent = external
+        .as_ref()
+        .map(|e| e.name.clone())
+        .unwrap_or_else(|| config.agents.default_agent.clone());
+    let profiles = config.launch_profiles().unwrap_or_else(|e| {
+        warn!("Launch profiles unavailable: {:#}", e);
+        Vec::new()
+    });
+    let mut agents = config.agents.get_enabled_agents();
+    agents.extend(external.as_ref().map(|e| e.name.clone()));
+    agents.extend(profiles.iter().map(|profile| profile.name.clone()));
+
+    let (message_tx, mut message_rx) = events::channel(events::DEFAULT_CAPACITY);
+    let manager = AgentManager::new(config.agents.clone(), message_tx, external)
+        .await
+        .context("Failed to start agent manager")?;
+    manager.set_permission_policy(config.permissions.clone());
+    manager.set_instruction_files(config.project.instruction_files.clone());
+    manager.set_launch_profiles(profiles);
+
+    let (input_tx, mut input_rx) = mpsc::unbounded_channel();
+    tokio::task::spawn_local(read_stdin(input_tx));
+
+    let server = Rc::new(Server {
+        config,
+        manager,
+        agents,
+        default_agent,
+        callback,
+        sessions: RefCell::new(HashMap::new()),
+        permissions: RefCell::new(HashMap::new()),
+        next_permission: Cell::new(1),
+    });
+    let store = SessionStore::from_config(&server.config);
+    let mut recorder = SessionRecorder::default();
+    info!(
+        "Embedded for a frontend (default agent: {})",
+        server.default_agent
+    );
+
+    let mut requests = JoinSet::new();
+    let mut interval = tokio::time::interval(Duration::from_millis(50));
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {
+                if let Err(e) = server.manager.tick().await {
+                    warn!("Agent manager tick error: {}", e);
+                }
+            }
+            Some(message) = message_rx.recv() => {
+                recorder.observe(&message);
+                let turn_ended = matches!(message, AppMessage::TurnCompleted { .. });
+                server.handle_event(message);
+                if turn_ended {
+                    save_sessions(&server.config, &store, &mut recorder).await;
+                }
+            }
+            Some(_) = requests.join_next(), if !requests.is_empty() => {}
+            input = input_rx.recv() => {
+                // stdin closes when the editor exits
+                let Some(value) = input else { break };
+                let Some(request) = parse_request(value) else { continue };
+                if request.method == "shutdown" {
+                    let pending = async { while requests.join_next().await.is_some() {} };
+                    let _ = tokio::time::timeout(SHUTDOWN_GRACE, pending).await;
+                    server.respond(request.id, Ok(Value::Null));
+                    break;
+                }
+                if request.method == "prompt" {
+                    // Recorded here, in order with the session's updates
+                    let result = server.start_prompt(&request.params, &mut recorder);
+                    server.respond(request.id, result.map(|()| Value::Null));
+                } else {
+                    requests.spawn_local(server.clone().call(request));
+                }
+            }
+        }
+    }
+
+    // Unanswered permission requests are denied as their replies drop
+    server.permissions.borrow_mut().clear();
+    if let Err(e) = server.manager.disconnect_all().await {
+        warn!("Failed to disconnect agents: {}", e);
+    }
+    save_sessions(&server.config, &store, &mut recorder).await;
+    info!("Embedded session ended");
+    Ok(0)
+}
+
+async fn save_sessions(config: &Config, store: &SessionStore, recorder: &mut SessionRecorder) {
+    if config.general.auto_save_sessions {
+        if let Err(e) = recorder.flush(store).await {
+            warn!("Failed to save session: {}", e);
+        }
+    }
+}
+
+/// Messages off stdin, until it closes or sends something that isn't
+/// MessagePack
+async fn read_stdin(tx: mpsc::UnboundedSender<Value>) {
+    let mut stdin = tokio::io::stdin();
+    let mut framer = MsgpackFramer::default();
+    let mut buf = vec![0; 64 * 1024];
+    loop {
+        let n = match stdin.read(&mut buf).await {
+            Ok(0) => {
+                if !framer.is_empty() {
+                    debug!("stdin closed in the middle of a message");
+                }
+                break;
+            }
+            Ok(n) => n,
+            Err(e) => {
+                warn!("Failed to read stdin: {}", e);
+                break;
+            }
+        };
+        match framer.push(&buf[..n]) {
+            Ok(values) => {
+                for value in values {
+                    if tx.send(value).is_err() {
+                        return;
+                    }
+                }
+            }
+            Err(e) => {
+                warn!("Ending embedded session: {:#}", e);
+                break;
+            }
+        }
+    }
+}
+
+/// `[0, id, method, params]` or `[2, method, params]`; anything else is
+/// logged and dropped, as RAT never sends requests of its own
+fn parse_request(value: Value) -> Option<Request> {
+    let Value::Array(mut parts) = value else {
+        warn!("Ignoring non-array message from the frontend");
+        return None;
+    };
+    let (id, rest) = match (parts.first().and_then(Value::as_u64), parts.len()) {
+        (Some(REQUEST), 4) => (parts[1].as_u64(), parts.split_off(2)),
+        (Some(NOTIFICATION), 3) => (None, parts.split_off(1)),
+        _ => {
+            debug!("Ignoring frontend message {:?}", parts);
+            return None;
+        }
+    };
+    let [method, params] = <[Value; 2]>::try_from(rest).ok()?;
+    let params = match params {
+        Value::Array(params) => params,
+        Value::Null => Vec::new(),
+        param => vec![param],
+    };
+    Some(Request {
+        id,
+        method: method.as_str()?.to_string(),
+        params,
+    })
+}
+
+impl Server {
+    async fn call(self: Rc<Self>, request: Request) {
+        let result = self.answer(&request.method, &request.params).await;
+        if let Err(e) = &result {
+            debug!("{} failed: {:#}", request.method, e);
+        }
+        self.respond(request.id, result);
+    }
+
+    async fn answer(&self, method: &str, params: &[Value]) -> Result<Value> {
+        match method {
+            "agents" => Ok(self
+                .agents
+                .iter()
+                .map(|name| {
+                    json!({
+                        "name": name,
+                        "connected": self.manager.is_agent_connected(name),
+                        "default": *name == self.default_agent,
+                    })
+                })
+                .collect()),
+            "sessions" => {
+                let sessions = SessionStore::from_config(&self.config).list().await?;
+                let summaries: Vec<SessionSummary> =
+                    sessions.iter().map(SessionSummary::from_session).collect();
+                Ok(serde_json::to_value(summaries)?)
+            }
+            "new_session" => {
+                let agent = match params.first() {
+                    Some(Value::String(agent)) => agent.clone(),
+                    _ => self.default_agent.clone(),
+                };
+                let session_id = self.manager.create_session(&agent).await?;
+                self.opened(session_id, agent)
+            }
+            "resume_session" => {
+                let id = str_param(params, 0, "session id")?;
+                let session = SessionStore::from_config(&self.config).load(id).await?;
+                let agent = session
+                    .agent_name
+                    .clone()
+                    .with_context(|| format!("session {} has no agent", id))?;
+                let session_id = self.manager.resume_session(session).await?;
+                self.opened(session_id, agent)
+            }
+            "cancel" => {
+                let session_id = SessionId(str_param(params, 0, "session id")?.to_string());
+                let agent = self.agent_of(&session_id)?;
+                self.manager.cancel_turn(&agent, &session_id).await?;
+                Ok(Value::Null)
+            }
+            "permission" => {
+                let id = params
+                    .first()
+                    .and_then(Value::as_u64)
+                    .context("permission takes a request id and whether to allow it")?;
+                let allowed = params.get(1).and_then(Value::as_bool).unwrap_or(false);
+                let reply = self.permissions.borrow_mut().remove(&id);
+                // False when the request was answered already or withdrawn
+                Ok(Value::Bool(reply.is_some_and(|reply| {
+                    let waiting = !reply.is_closed();
+                    reply.send(allowed);
+                    waiting
+                })))
+            }
+            method => anyhow::bail!("unknown method {}", method),
+        }
+    }
+
+    fn opened(&self, session_id: SessionId, agent: String) -> Result<Value> {
+        let result = json!({ "session_id": session_id.0, "agent": agent });
+        self.sessions.borrow_mut().insert(session_id, agent);
+        Ok(result)
+    }
+
+    fn agent_of(&self, session_id: &SessionId) -> Result<String> {
+        self.sessions
+            .borrow()
+            .get(session_id)
+            .cloned()
+            .with_context(|| format!("no open session {}", session_id.0))
+    }
+
+    /// Send a prompt as a task of its own; errors are reported as events
+    fn start_prompt(
+        self: &Rc<Self>,
+        params: &[Value],
+        recorder: &mut SessionRecorder,
+    ) -> Result<()> {
+        let session_id = SessionId(str_param(params, 0, "session id")?.to_string());
+        let agent = self.agent_of(&session_id)?;
+        let prompt = match params.get(1) {
+            Some(Value::String(text)) => vec![acp::ContentBlock::Text(acp::TextContent {
+                text: text.clone(),
+                annotations: Default::default(),
+            })],
+            Some(blocks @ Value::Array(_)) => {
+                serde_json::from_value(blocks.clone()).context("prompt content blocks")?
+            }
+            _ => anyhow::bail!("prompt takes a session id and text or ACP content blocks"),
+        };
+        recorder.record(
+            &agent,
+            Message::user_prompt(session_id.clone(), prompt.clone()),
+        );
+        let server = self.clone();
+        tokio::task::spawn_local(async move {
+            if let Err(e) = server
+                .manager
+                .send_prompt(&agent, &session_id, prompt)
+                .await
+            {
+                server.notify(json!({
+                    "type": "error",
+                    "agent": agent,
+                    "session_id": session_id.0,
+                    "message": format!("{:#}", e),
+                }));
+            }
+        });
+        Ok(())
+    }
+
+    /// Pass an agent event on to the frontend
+    fn handle_event(&self, message: AppMessage) {
+        let event = match message {
+            AppMessage::PermissionRequested {
+                agent_name,
+                session_id,
+                title,
+                tool_kind,
+                command,
+                reply,
+            } => {
+                let id = self.next_permission.get();
+                self.next_permission.set(id + 1);
+                let mut permissions = self.permissions.borrow_mut();
+                permissions.retain(|_, reply| !reply.is_closed());
+                permissions.insert(id, reply);
+                json!({
+                    "type": "permission_request",
+                    "id": id,
+                    "agent": agent_name,
+                    "session_id": session_id.0,
+                    "title": title,
+                    "tool_kind": tool_kind,
+                    "command": command,
+                })
+            }
+            AppMessage::SessionReplaced {
+                agent_name,
+                old_session_id,
+                new_session_id,
+            } => {
+                let mut sessions = self.sessions.borrow_mut();
+                sessions.remove(&old_session_id);
+                sessions.insert(new_session_id.clone(), agent_name.clone());
+                json!({
+                    "type": "session_replaced",
+                    "agent": agent_name,
+                    "old_session_id": old_session_id.0,
+                    "session_id": new_session_id.0,
+                })
+            }
+            AppMessage::FileWritten {
+                agent_name,
+                session_id,
+                path,
+            } => json!({
+                "type": "file_written",
+                "agent": agent_name,
+                "session_id": session_id.0,
+                "path": path,
+            }),
+            AppMessage::AgentConnected { agent_name } => {
+                json!({ "type": "agent_connected", "agent": agent_name })
+            }
+            AppMessage::AgentDisconnected { agent_name } => {
+                json!({ "type": "agent_disconnected", "agent": agent_name })
+            }
+            message => match headless::json_event(message) {
+                Some(event) => event,
+                None => return,
+            },
+        };
+        self.notify(event);
+    }
+
+    fn respond(&self, id: Option<u64>, result: Result<Value>) {
+        let Some(id) = id else { return };
+        let (error, result) = match result {
+            Ok(result) => (Value::Null, result),
+            Err(e) => (Value::String(format!("{:#}", e)), Value::Null),
+        };
+        write(&json!([RESPONSE, id, error, result]));
+    }
+
+    fn notify(&self, event: Value) {
+        let lua = format!("return {}(...)", self.callback);
+        write(&json!([NOTIFICATION, "nvim_exec_lua", [lua, [event]]]));
+    }
+}
+
+fn str_param<'a>(params: &'a [Value], index: usize, name: &str) -> Result<&'a str> {
+    params
+        .get(index)
+        .and_then(Value::as_str)
+        .with_context(|| format!("missing {}", name))
+}
+
+/// Write a message to stdout. A frontend that went away closes stdin too,
+/// which ends the session, so failures are only logged.
+fn write(message: &Value) {
+    let mut bytes = Vec::new();
+    msgpack::encode(message, &mut bytes);
+    let mut stdout = std::io::stdout().lock();
+    if let Err(e) = stdout.write_all(&bytes).and_then(|()| stdout.flush()) {
+        debug!("Failed to write to the frontend: {}", e);
+    }
+}
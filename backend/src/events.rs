@@ -0,0 +1,460 @@
+//! Events the agent plumbing reports to whatever hosts it: the TUI, the
+//! headless runner, or an embedding application. Adapters and the ACP client
+//! send them on the [`channel`] they were created with.
+//!
+//! The channel is bounded so a fast-streaming agent cannot outrun its host
+//! without limit. Sending never waits; once the host is `capacity` messages
+//! behind, further messages go through an overflow queue where text chunks
+//! are merged into the chunk queued before them and session status updates
+//! are dropped. Everything else is kept, in order, until the overflow queue
+//! holds [`OVERFLOW_FACTOR`] times `capacity` messages; past that the oldest
+//! agent message is dropped to make room, or the new message when none is
+//! left. Dropped messages are counted in [`Backpressure`].
+
+use agent_client_protocol as acp;
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{mpsc, oneshot};
+use tokio::sync::mpsc::error::{SendError, TryRecvError, TrySendError};
+
+use crate::acp::{Message, MessageContent, SessionId};
+
+/// Messages a host may fall behind by before the overflow policy applies
+pub const DEFAULT_CAPACITY: usize = 1024;
+
+/// How many times the channel's capacity the overflow queue may hold
+pub const OVERFLOW_FACTOR: usize = 8;
+
+#[derive(Debug, Clone)]
+pub enum AppMessage {
+    AgentMessage {
+        agent_name: String,
+        message: Message,
+    },
+    AgentConnected {
+        agent_name: String,
+    },
+    AgentDisconnected {
+        agent_name: String,
+    },
+    SessionCreated {
+        agent_name: String,
+        session_id: SessionId,
+    },
+    Error {
+        error: String,
+    },
+    /// A stored session could not be reloaded and continues as a new session
+    SessionReplaced {
+        agent_name: String,
+        old_session_id: SessionId,
+        new_session_id: SessionId,
+    },
+    /// The agent exited mid-session and was restarted. `reloaded` tells
+    /// whether it loaded the session again or, having had to start a fresh
+    /// one (announced by `SessionReplaced`), lost the earlier context.
+    SessionRecovered {
+        agent_name: String,
+        session_id: SessionId,
+        reloaded: bool,
+    },
+    /// The agent crashed too often to be restarted again and stays stopped
+    /// until the user retries it
+    AgentFailed {
+        agent_name: String,
+        reason: String,
+        /// The agent's last stderr lines, oldest first
+        stderr: Vec<String>,
+    },
+    /// A prompt turn finished; emitted before the send call returns
+    TurnCompleted {
+        agent_name: String,
+        session_id: SessionId,
+        stop_reason: agent_client_protocol::StopReason,
+    },
+    /// Project instructions will be sent with the session's first prompt
+    InstructionsAttached {
+        agent_name: String,
+        session_id: SessionId,
+        path: PathBuf,
+    },
+    /// The policy says to ask: the agent waits until `reply` is answered.
+    /// Dropping every copy of the message unanswered denies the request.
+    PermissionRequested {
+        agent_name: String,
+        session_id: SessionId,
+        title: String,
+        tool_kind: Option<String>,
+        /// Command line for execute requests, when the agent supplied one
+        command: Option<String>,
+        reply: PermissionReply,
+    },
+    /// A permission request was answered by the configured policy, or by
+    /// the user when the policy asked
+    PermissionResolved {
+        agent_name: String,
+        session_id: SessionId,
+        title: String,
+        tool_kind: Option<String>,
+        /// Command line for execute requests, when the agent supplied one
+        command: Option<String>,
+        decision: crate::config::PolicyDecision,
+    },
+    /// An agent wrote a file through the client (`fs/write_text_file`)
+    FileWritten {
+        agent_name: String,
+        session_id: SessionId,
+        path: PathBuf,
+    },
+    /// A session's edits were pushed and a pull request opened for them
+    PullRequestOpened {
+        session_id: SessionId,
+        url: String,
+    },
+    /// Temporarily leave TUI (raw mode + alt screen) so external UI can run
+    SuspendTui,
+    /// Return to TUI after external UI has finished
+    ResumeTui,
+}
+
+/// Answer to a `PermissionRequested`; the first answer wins
+#[derive(Clone)]
+pub struct PermissionReply(Arc<Mutex<Option<oneshot::Sender<bool>>>>);
+
+impl PermissionReply {
+    /// A reply and the receiver the requester waits on; the receiver sees
+    /// an error if the reply is dropped unanswered
+    pub fn new() -> (Self, oneshot::Receiver<bool>) {
+        let (tx, rx) = oneshot::channel();
+        (Self(Arc::new(Mutex::new(Some(tx)))), rx)
+    }
+
+    pub fn send(&self, allowed: bool) {
+        if let Some(tx) = self.0.lock().unwrap().take() {
+            let _ = tx.send(allowed);
+        }
+    }
+
+    /// Answered already, or the requester stopped waiting
+    pub fn is_closed(&self) -> bool {
+        self.0.lock().unwrap().as_ref().is_none_or(|tx| tx.is_closed())
+    }
+}
+
+impl std::fmt::Debug for PermissionReply {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("PermissionReply")
+    }
+}
+
+/// Create a bounded event channel holding up to `capacity` messages
+pub fn channel(capacity: usize) -> (AppSender, AppReceiver) {
+    let capacity = capacity.max(1);
+    let (tx, rx) = mpsc::channel(capacity);
+    let shared = Arc::new(Overflow {
+        queue: Mutex::default(),
+        limit: capacity.saturating_mul(OVERFLOW_FACTOR),
+        coalesced: AtomicU64::default(),
+        dropped: AtomicU64::default(),
+    });
+    (
+        AppSender {
+            tx,
+            overflow: shared.clone(),
+        },
+        AppReceiver {
+            rx,
+            overflow: shared,
+        },
+    )
+}
+
+/// Messages that did not fit in the channel, and what the policy did to
+/// the ones it could not keep since the backlog started
+struct Overflow {
+    queue: Mutex<VecDeque<AppMessage>>,
+    /// Most messages `queue` may hold
+    limit: usize,
+    coalesced: AtomicU64,
+    dropped: AtomicU64,
+}
+
+/// How far the host has fallen behind, for display
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Backpressure {
+    /// Messages waiting beyond the channel's capacity
+    pub queued: usize,
+    /// Text chunks merged into an earlier one since the backlog started
+    pub coalesced: u64,
+    /// Status updates, and agent messages the full overflow queue had no
+    /// room for, dropped since the backlog started
+    pub dropped: u64,
+}
+
+impl Backpressure {
+    pub fn is_active(&self) -> bool {
+        self.queued > 0
+    }
+}
+
+#[derive(Clone)]
+pub struct AppSender {
+    tx: mpsc::Sender<AppMessage>,
+    overflow: Arc<Overflow>,
+}
+
+impl AppSender {
+    /// Queue `message` without waiting; fails only once the receiver is gone
+    pub fn send(&self, message: AppMessage) -> Result<(), Box<SendError<AppMessage>>> {
+        // Held across `try_send` so the receiver sees the channel and the
+        // overflow queue change together
+        let mut queue = self.overflow.queue.lock().unwrap();
+        let message = if queue.is_empty() {
+            match self.tx.try_send(message) {
+                Ok(()) => return Ok(()),
+                Err(TrySendError::Closed(message)) => return Err(Box::new(SendError(message))),
+                Err(TrySendError::Full(message)) => {
+                    self.overflow.coalesced.store(0, Ordering::Relaxed);
+                    self.overflow.dropped.store(0, Ordering::Relaxed);
+                    message
+                }
+            }
+        } else if self.tx.is_closed() {
+            return Err(Box::new(SendError(message)));
+        } else {
+            message
+        };
+
+        if is_status_update(&message) {
+            self.overflow.dropped.fetch_add(1, Ordering::Relaxed);
+        } else if let Some(message) = coalesce(queue.back_mut(), message) {
+            if queue.len() >= self.overflow.limit {
+                self.overflow.dropped.fetch_add(1, Ordering::Relaxed);
+                // Transcript updates only cost the host some output; the
+                // rest steers it, so it goes last
+                match queue.iter().position(is_agent_message) {
+                    Some(oldest) => {
+                        queue.remove(oldest);
+                    }
+                    None => return Ok(()),
+                }
+            }
+            queue.push_back(message);
+        } else {
+            self.overflow.coalesced.fetch_add(1, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+}
+
+pub struct AppReceiver {
+    rx: mpsc::Receiver<AppMessage>,
+    overflow: Arc<Overflow>,
+}
+
+impl AppReceiver {
+    /// Next message; `None` once every sender is gone and nothing is left
+    pub async fn recv(&mut self) -> Option<AppMessage> {
+        match self.try_recv() {
+            Ok(message) => Some(message),
+            Err(TryRecvError::Disconnected) => None,
+            Err(TryRecvError::Empty) => self.rx.recv().await,
+        }
+    }
+
+    pub fn try_recv(&mut self) -> Result<AppMessage, TryRecvError> {
+        // The overflow queue only holds messages newer than the channel's
+        let mut queue = self.overflow.queue.lock().unwrap();
+        match self.rx.try_recv() {
+            Ok(message) => Ok(message),
+            Err(e) => queue.pop_front().ok_or(e),
+        }
+    }
+
+    pub fn backpressure(&self) -> Backpressure {
+        Backpressure {
+            queued: self.overflow.queue.lock().unwrap().len(),
+            coalesced: self.overflow.coalesced.load(Ordering::Relaxed),
+            dropped: self.overflow.dropped.load(Ordering::Relaxed),
+        }
+    }
+}
+
+fn is_agent_message(message: &AppMessage) -> bool {
+    matches!(message, AppMessage::AgentMessage { .. })
+}
+
+/// Status updates only describe the moment; a newer one supersedes them
+fn is_status_update(message: &AppMessage) -> bool {
+    matches!(
+        message,
+        AppMessage::AgentMessage {
+            message: Message {
+                content: MessageContent::SessionStatus { .. },
+                ..
+            },
+            ..
+        }
+    )
+}
+
+/// Append a text chunk to `last` when it is a chunk of the same kind from the
+/// same session; gives `message` back when it can't be merged
+fn coalesce(last: Option<&mut AppMessage>, message: AppMessage) -> Option<AppMessage> {
+    let (
+        Some(AppMessage::AgentMessage {
+            agent_name: last_agent,
+            message: last,
+        }),
+        AppMessage::AgentMessage {
+            agent_name,
+            message: next,
+        },
+    ) = (last, &message)
+    else {
+        return Some(message);
+    };
+    if *last_agent != *agent_name || last.session_id != next.session_id {
+        return Some(message);
+    }
+    match (&mut last.content, &next.content) {
+        (
+            MessageContent::AgentMessageChunk {
+                content: acp::ContentBlock::Text(last),
+            },
+            MessageContent::AgentMessageChunk {
+                content: acp::ContentBlock::Text(next),
+            },
+        )
+        | (
+            MessageContent::AgentResponse {
+                content: acp::ContentBlock::Text(last),
+            },
+            MessageContent::AgentResponse {
+                content: acp::ContentBlock::Text(next),
+            },
+        ) => {
+            last.text.push_str(&next.text);
+            None
+        }
+        _ => Some(message),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(session: &str, text: &str) -> AppMessage {
+        AppMessage::AgentMessage {
+            agent_name: "gemini".to_string(),
+            message: Message::new(
+                SessionId(session.to_string()),
+                MessageContent::AgentMessageChunk {
+                    content: acp::ContentBlock::Text(acp::TextContent {
+                        text: text.to_string(),
+                        annotations: None,
+                    }),
+                },
+            ),
+        }
+    }
+
+    fn status(session: &str) -> AppMessage {
+        AppMessage::AgentMessage {
+            agent_name: "gemini".to_string(),
+            message: Message::new(
+                SessionId(session.to_string()),
+                MessageContent::SessionStatus {
+                    status: "busy".to_string(),
+                },
+            ),
+        }
+    }
+
+    fn text(message: &AppMessage) -> &str {
+        match message {
+            AppMessage::AgentMessage {
+                message:
+                    Message {
+                        content:
+                            MessageContent::AgentMessageChunk {
+                                content: acp::ContentBlock::Text(text),
+                            },
+                        ..
+                    },
+                ..
+            } => &text.text,
+            other => panic!("not a text chunk: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn overflow_merges_chunks_and_drops_status_updates() {
+        let (tx, mut rx) = channel(1);
+        tx.send(chunk("s1", "a")).unwrap();
+        tx.send(chunk("s1", "b")).unwrap();
+        tx.send(status("s1")).unwrap();
+        tx.send(chunk("s1", "c")).unwrap();
+        tx.send(chunk("s2", "d")).unwrap();
+        tx.send(AppMessage::ResumeTui).unwrap();
+
+        let pressure = rx.backpressure();
+        assert!(pressure.is_active());
+        assert_eq!(
+            (pressure.queued, pressure.coalesced, pressure.dropped),
+            (3, 1, 1)
+        );
+
+        assert_eq!(text(&rx.try_recv().unwrap()), "a");
+        assert_eq!(text(&rx.try_recv().unwrap()), "bc");
+        assert_eq!(text(&rx.try_recv().unwrap()), "d");
+        assert!(matches!(rx.try_recv(), Ok(AppMessage::ResumeTui)));
+        assert!(matches!(rx.try_recv(), Err(TryRecvError::Empty)));
+        assert!(!rx.backpressure().is_active());
+    }
+
+    #[test]
+    fn full_overflow_drops_the_oldest_agent_message() {
+        let (tx, mut rx) = channel(1);
+        let limit = OVERFLOW_FACTOR;
+        tx.send(AppMessage::ResumeTui).unwrap();
+        tx.send(AppMessage::SuspendTui).unwrap();
+        // Alternating sessions so no chunk merges into the one before it
+        for i in 0..limit {
+            tx.send(chunk(&format!("s{}", i % 2), &i.to_string())).unwrap();
+        }
+        let pressure = rx.backpressure();
+        assert_eq!((pressure.queued, pressure.dropped), (limit, 1));
+
+        assert!(matches!(rx.try_recv(), Ok(AppMessage::ResumeTui)));
+        assert!(matches!(rx.try_recv(), Ok(AppMessage::SuspendTui)));
+        assert_eq!(text(&rx.try_recv().unwrap()), "1");
+        for _ in 2..limit {
+            rx.try_recv().unwrap();
+        }
+        assert!(matches!(rx.try_recv(), Err(TryRecvError::Empty)));
+
+        // With nothing left to make room, the new message is the one dropped
+        tx.send(AppMessage::ResumeTui).unwrap();
+        for _ in 0..=limit {
+            tx.send(AppMessage::SuspendTui).unwrap();
+        }
+        assert_eq!(rx.backpressure().queued, limit);
+        assert_eq!(rx.backpressure().dropped, 1);
+    }
+
+    #[tokio::test]
+    async fn recv_drains_overflow_after_senders_are_gone() {
+        let (tx, mut rx) = channel(1);
+        tx.send(AppMessage::ResumeTui).unwrap();
+        tx.send(chunk("s1", "a")).unwrap();
+        drop(tx);
+
+        assert!(matches!(rx.recv().await, Some(AppMessage::ResumeTui)));
+        assert_eq!(text(&rx.recv().await.unwrap()), "a");
+        assert!(rx.recv().await.is_none());
+    }
+}
@@ -0,0 +1,917 @@
+//! Non-interactive entry points (`rat exec`, `rat watch`) that drive agents without the TUI.
+
+use anyhow::{Context, Result};
+use tracing::{debug, info, warn};
+use std::collections::BTreeSet;
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+use serde_json::json;
+use tokio::sync::mpsc;
+
+use agent_client_protocol as acp;
+
+use crate::acp::{
+    AgentError, AgentErrorKind, Message, MessageContent, SessionRecorder, SessionStore,
+};
+use crate::adapters::{AgentManager, ExternalAgentSpec};
+use crate::events::{self, AppMessage, AppReceiver};
+use crate::config::{Config, PermissionsConfig, PolicyDecision};
+use crate::utils::audit::AuditLog;
+
+/// How long a timed out turn may take to end after it was cancelled
+const CANCEL_GRACE: Duration = Duration::from_secs(5);
+
+/// How `rat exec` renders agent output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Answer text on stdout, progress on stderr
+    #[default]
+    Text,
+    /// One JSON object per line (NDJSON) for every session update
+    Json,
+}
+
+/// Failure classes for headless runs. Each maps to a stable process exit code
+/// so wrapper scripts can branch on the kind of failure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExitKind {
+    Success,
+    /// Anything not covered below, including turns that ended early
+    Error,
+    /// Bad command-line usage or an invalid config
+    Usage,
+    /// The agent is disabled, unknown or could not be started
+    AgentNotFound,
+    /// The agent needs a login or API key
+    AuthRequired,
+    /// The turn stopped after the permission policy denied a request
+    PermissionDenied,
+    Timeout,
+    /// The agent connection failed or returned an invalid response
+    ProtocolError,
+}
+
+impl ExitKind {
+    pub fn code(self) -> i32 {
+        match self {
+            ExitKind::Success => 0,
+            ExitKind::Error => 1,
+            ExitKind::Usage => 2,
+            ExitKind::AgentNotFound => 3,
+            ExitKind::AuthRequired => 4,
+            ExitKind::PermissionDenied => 5,
+            ExitKind::Timeout => 6,
+            ExitKind::ProtocolError => 7,
+        }
+    }
+
+    /// The exit kind for an agent or manager error, from the cause it carries
+    pub fn of(error: &anyhow::Error) -> Self {
+        match AgentError::kind_of(error) {
+            Some(AgentErrorKind::Unavailable) => ExitKind::AgentNotFound,
+            Some(AgentErrorKind::AuthRequired) => ExitKind::AuthRequired,
+            Some(AgentErrorKind::Timeout) => ExitKind::Timeout,
+            Some(AgentErrorKind::Protocol) => ExitKind::ProtocolError,
+            None => ExitKind::Error,
+        }
+    }
+}
+
+/// Permission flags for unattended runs. Either one replaces the configured
+/// policy, so a run never waits on an interactive prompt.
+#[derive(Debug, Clone, Default, clap::Args)]
+pub struct ApprovalArgs {
+    /// Approve only these request kinds and deny the rest,
+    /// e.g. `reads,exec:safe` (all, reads, edits, exec, exec:safe, or a tool kind)
+    #[arg(long, value_parser = parse_approve_list, conflicts_with = "deny_all")]
+    pub approve: Option<String>,
+
+    /// Deny every permission request
+    #[arg(long)]
+    pub deny_all: bool,
+}
+
+fn parse_approve_list(spec: &str) -> Result<String, String> {
+    PermissionsConfig::from_approve_list(spec)
+        .map(|_| spec.to_string())
+        .map_err(|e| e.to_string())
+}
+
+impl ApprovalArgs {
+    /// Effective policy and where it came from, for the audit log
+    fn resolve(&self, configured: &PermissionsConfig) -> Result<(PermissionsConfig, String)> {
+        if self.deny_all {
+            Ok((PermissionsConfig::deny_all(), "--deny-all".to_string()))
+        } else if let Some(spec) = &self.approve {
+            Ok((
+                PermissionsConfig::from_approve_list(spec)?,
+                format!("--approve {}", spec),
+            ))
+        } else {
+            Ok((configured.non_interactive(), "config".to_string()))
+        }
+    }
+}
+
+/// Options for a single headless prompt run
+#[derive(Debug, Clone)]
+pub struct ExecOptions {
+    pub prompt: String,
+    /// Extra context (e.g. piped stdin) attached as an embedded resource
+    pub context: Option<String>,
+    pub agent: Option<String>,
+    pub timeout: Option<Duration>,
+    pub output: OutputFormat,
+    pub approval: ApprovalArgs,
+    /// Suppress progress and error text on stderr; only the exit code remains
+    pub quiet: bool,
+    /// Report the fatal error as one JSON object on stderr
+    pub json_errors: bool,
+}
+
+/// Options for `rat watch`
+#[derive(Debug, Clone)]
+pub struct WatchOptions {
+    pub exec: ExecOptions,
+    pub paths: Vec<PathBuf>,
+    /// Quiet period after the last change before re-running
+    pub debounce: Duration,
+}
+
+/// Run one prompt against an agent, streaming the answer to stdout.
+/// Returns the process exit code.
+pub async fn run_exec(
+    config: Config,
+    external: Option<ExternalAgentSpec>,
+    opts: ExecOptions,
+) -> Result<i32> {
+    // Adapters spawn non-Send tasks, so everything runs on a LocalSet
+    let local = tokio::task::LocalSet::new();
+    local.run_until(exec_inner(config, external, opts)).await
+}
+
+/// Run a prompt once, then again in a fresh session whenever files under the
+/// watched paths change. Runs until interrupted.
+pub async fn run_watch(
+    config: Config,
+    external: Option<ExternalAgentSpec>,
+    opts: WatchOptions,
+) -> Result<i32> {
+    let local = tokio::task::LocalSet::new();
+    local.run_until(watch_inner(config, external, opts)).await
+}
+
+async fn watch_inner(
+    config: Config,
+    external: Option<ExternalAgentSpec>,
+    opts: WatchOptions,
+) -> Result<i32> {
+    use notify::Watcher;
+
+    let (change_tx, mut change_rx) = mpsc::unbounded_channel::<PathBuf>();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        match res {
+            Ok(event) if !event.kind.is_access() => {
+                for path in event.paths {
+                    let _ = change_tx.send(path);
+                }
+            }
+            Ok(_) => {}
+            Err(e) => warn!("File watcher error: {}", e),
+        }
+    })
+    .context("Failed to create file watcher")?;
+    for path in &opts.paths {
+        watcher
+            .watch(path, notify::RecursiveMode::Recursive)
+            .with_context(|| format!("Failed to watch {}", path.display()))?;
+    }
+
+    let mut printer = HeadlessPrinter::new(&opts.exec, AuditLog::from_config(&config));
+    let (agent_name, manager, mut message_rx) =
+        match start_agent("watch", &config, external, &opts.exec, &mut printer).await {
+            Ok(started) => started,
+            Err(kind) => return Ok(kind.code()),
+        };
+
+    let mut run_opts = opts.exec.clone();
+    loop {
+        run_turn(
+            &manager,
+            &mut message_rx,
+            &agent_name,
+            &run_opts,
+            &mut printer,
+        )
+        .await;
+        save_sessions(&config, &mut printer).await;
+
+        // Ignore changes made during the run (often the agent's own edits)
+        while change_rx.try_recv().is_ok() {}
+        printer.note(&format!(
+            "watching {} for changes",
+            opts.paths
+                .iter()
+                .map(|p| p.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+
+        let mut changed = BTreeSet::new();
+        loop {
+            let Some(path) = change_rx.recv().await else {
+                break;
+            };
+            if !is_ignored(&path) {
+                changed.insert(path);
+                break;
+            }
+        }
+        if changed.is_empty() {
+            break;
+        }
+        // Debounce: keep collecting until the tree has been quiet for a while
+        while let Ok(Some(path)) = tokio::time::timeout(opts.debounce, change_rx.recv()).await {
+            if !is_ignored(&path) {
+                changed.insert(path);
+            }
+        }
+
+        printer.note(&format!("{} file(s) changed, re-running", changed.len()));
+        run_opts.prompt = with_changed_files(&opts.exec.prompt, &changed);
+    }
+
+    if let Err(e) = manager.disconnect_all().await {
+        warn!("Failed to disconnect agents: {}", e);
+    }
+    Ok(0)
+}
+
+/// Skip VCS metadata and build output, which churn without source changes
+fn is_ignored(path: &Path) -> bool {
+    path.components().any(|c| match c {
+        Component::Normal(name) => name == ".git" || name == "target",
+        _ => false,
+    })
+}
+
+fn with_changed_files(prompt: &str, changed: &BTreeSet<PathBuf>) -> String {
+    let mut prompt = format!("{}\n\nFiles changed since the last run:", prompt);
+    for path in changed {
+        prompt.push_str(&format!("\n- {}", path.display()));
+    }
+    prompt
+}
+
+async fn exec_inner(
+    config: Config,
+    external: Option<ExternalAgentSpec>,
+    opts: ExecOptions,
+) -> Result<i32> {
+    let mut printer = HeadlessPrinter::new(&opts, AuditLog::from_config(&config));
+    let (agent_name, manager, mut message_rx) =
+        match start_agent("exec", &config, external, &opts, &mut printer).await {
+            Ok(started) => started,
+            Err(kind) => return Ok(kind.code()),
+        };
+
+    let kind = run_turn(
+        &manager,
+        &mut message_rx,
+        &agent_name,
+        &opts,
+        &mut printer,
+    )
+    .await;
+
+    if let Err(e) = manager.disconnect_all().await {
+        warn!("Failed to disconnect agents: {}", e);
+    }
+    save_sessions(&config, &mut printer).await;
+    Ok(kind.code())
+}
+
+/// Resolve the agent to talk to and start a manager for it. On failure
+/// (reported already) returns how the run should exit.
+async fn start_agent(
+    command: &str,
+    config: &Config,
+    external: Option<ExternalAgentSpec>,
+    opts: &ExecOptions,
+    printer: &mut HeadlessPrinter,
+) -> std::result::Result<(String, AgentManager, AppReceiver), ExitKind> {
+    let agent_name = opts
+        .agent
+        .clone()
+        .or_else(|| external.as_ref().map(|e| e.name.clone()))
+        .unwrap_or_else(|| config.agents.default_agent.clone());
+
+    let is_external = external
+        .as_ref()
+        .map(|e| e.name == agent_name)
+        .unwrap_or(false);
+
+    if !is_external && !config.agents.is_agent_enabled(&agent_name) {
+        printer.fail(
+            ExitKind::AgentNotFound,
+            &format!("agent '{}' is not enabled", agent_name),
+        );
+        return Err(ExitKind::AgentNotFound);
+    }
+
+    let (message_tx, message_rx) = events::channel(events::DEFAULT_CAPACITY);
+    let manager = match AgentManager::new(config.agents.clone(), message_tx, external).await {
+        Ok(manager) => manager,
+        Err(e) => {
+            let error = format!("Failed to start agent manager: {:#}", e);
+            printer.fail(ExitKind::AgentNotFound, &error);
+            return Err(ExitKind::AgentNotFound);
+        }
+    };
+    let (policy, policy_source) = match opts.approval.resolve(&config.permissions) {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            printer.fail(ExitKind::Usage, &format!("{:#}", e));
+            return Err(ExitKind::Usage);
+        }
+    };
+    printer.audit.record(json!({
+        "event": "run_started",
+        "command": command,
+        "agent": agent_name,
+        "policy_source": policy_source,
+        "policy": policy,
+    }));
+    manager.set_permission_policy(policy);
+    manager.set_instruction_files(config.project.instruction_files.clone());
+
+    info!("Headless {} with agent: {} (policy from {})", command, agent_name, policy_source);
+    Ok((agent_name, manager, message_rx))
+}
+
+/// Send one prompt in a fresh session and stream the answer
+async fn run_turn(
+    manager: &AgentManager,
+    message_rx: &mut AppReceiver,
+    agent_name: &str,
+    opts: &ExecOptions,
+    printer: &mut HeadlessPrinter,
+) -> ExitKind {
+    printer.stop_reason = None;
+    printer.denied = false;
+    let session_id = match manager.create_session(agent_name).await {
+        Ok(session_id) => session_id,
+        Err(e) => {
+            let kind = ExitKind::of(&e);
+            printer.fail(kind, &e.to_string());
+            return kind;
+        }
+    };
+
+    // Pick up SessionCreated before recording our own prompt
+    drain(message_rx, printer);
+    let prompt = build_prompt(opts);
+    printer
+        .recorder
+        .record(agent_name, Message::user_prompt(session_id.clone(), prompt.clone()));
+
+    let result = {
+        let send = manager.send_prompt(agent_name, &session_id, prompt);
+        tokio::pin!(send);
+        let deadline = tokio::time::sleep(opts.timeout.unwrap_or(Duration::MAX));
+        tokio::pin!(deadline);
+        let timed_out = || {
+            Err((
+                ExitKind::Timeout,
+                format!(
+                    "timed out after {}s",
+                    opts.timeout.map(|t| t.as_secs()).unwrap_or_default()
+                ),
+            ))
+        };
+        // On timeout the turn is cancelled, and the agent gets a moment to
+        // wind it down so the transcript ends with its Cancelled stop
+        let mut cancelled = false;
+
+        loop {
+            tokio::select! {
+                res = &mut send => {
+                    if cancelled {
+                        break timed_out();
+                    }
+                    break res.map_err(|e| (ExitKind::of(&e), format!("{:#}", e)));
+                }
+                _ = &mut deadline => {
+                    if cancelled {
+                        break timed_out();
+                    }
+                    cancelled = true;
+                    let cancel = manager.cancel_turn(agent_name, &session_id);
+                    match tokio::time::timeout(CANCEL_GRACE, cancel).await {
+                        Ok(Ok(())) => deadline
+                            .as_mut()
+                            .reset(tokio::time::Instant::now() + CANCEL_GRACE),
+                        Ok(Err(e)) => {
+                            debug!("Could not cancel timed out turn: {:#}", e);
+                            break timed_out();
+                        }
+                        Err(_) => break timed_out(),
+                    }
+                }
+                Some(message) = message_rx.recv() => printer.handle(message),
+            }
+        }
+    };
+    drain(message_rx, printer);
+    printer.finish();
+
+    match result {
+        Err((kind, error)) => {
+            printer.fail(kind, &error);
+            kind
+        }
+        Ok(()) => match printer.stop_reason {
+            Some(acp::StopReason::EndTurn) | None => ExitKind::Success,
+            Some(reason) => {
+                let kind = if printer.denied {
+                    ExitKind::PermissionDenied
+                } else {
+                    ExitKind::Error
+                };
+                printer.fail(kind, &format!("turn ended early: {:?}", reason));
+                kind
+            }
+        },
+    }
+}
+
+async fn save_sessions(config: &Config, printer: &mut HeadlessPrinter) {
+    if config.general.auto_save_sessions {
+        let store = SessionStore::from_config(config);
+        if let Err(e) = printer.recorder.flush(&store).await {
+            warn!("Failed to save session: {}", e);
+        }
+    }
+}
+
+fn build_prompt(opts: &ExecOptions) -> Vec<acp::ContentBlock> {
+    let mut prompt = vec![acp::ContentBlock::Text(acp::TextContent {
+        text: opts.prompt.clone(),
+        annotations: Default::default(),
+    })];
+    if let Some(context) = &opts.context {
+        prompt.push(acp::ContentBlock::Resource(acp::EmbeddedResource {
+            resource: acp::EmbeddedResourceResource::TextResourceContents(
+                acp::TextResourceContents {
+                    mime_type: Some("text/plain".to_string()),
+                    text: context.clone(),
+                    uri: "stdin://".to_string(),
+                },
+            ),
+            annotations: Default::default(),
+        }));
+    }
+    prompt
+}
+
+fn drain(rx: &mut AppReceiver, printer: &mut HeadlessPrinter) {
+    while let Ok(message) = rx.try_recv() {
+        printer.handle(message);
+    }
+}
+
+/// Renders agent traffic for a terminal or pipe. In text mode the answer goes
+/// to stdout and progress to stderr; in JSON mode every event is an NDJSON
+/// object on stdout. The transcript is recorded along the way and permission
+/// decisions go to the audit log.
+struct HeadlessPrinter {
+    format: OutputFormat,
+    recorder: SessionRecorder,
+    audit: AuditLog,
+    quiet: bool,
+    json_errors: bool,
+    stop_reason: Option<acp::StopReason>,
+    // Whether the policy denied a request during the current turn
+    denied: bool,
+    // Whether stdout ended without a newline
+    open_line: bool,
+}
+
+impl HeadlessPrinter {
+    fn new(opts: &ExecOptions, audit: AuditLog) -> Self {
+        Self {
+            format: opts.output,
+            recorder: SessionRecorder::default(),
+            audit,
+            quiet: opts.quiet,
+            json_errors: opts.json_errors,
+            stop_reason: None,
+            denied: false,
+            open_line: false,
+        }
+    }
+
+    fn handle(&mut self, message: AppMessage) {
+        self.recorder.observe(&message);
+        if let AppMessage::PermissionResolved {
+            agent_name,
+            session_id,
+            title,
+            tool_kind,
+            command,
+            decision,
+        } = &message
+        {
+            self.denied |= *decision == PolicyDecision::Deny;
+            self.audit.record(json!({
+                "event": "permission",
+                "agent": agent_name,
+                "session_id": session_id.0,
+                "title": title,
+                "tool_kind": tool_kind,
+                "command": command,
+                "decision": decision,
+            }));
+        }
+        if self.format == OutputFormat::Json {
+            self.handle_json(message);
+            return;
+        }
+
+        match message {
+            AppMessage::AgentMessage { message, .. } => self.handle_agent_message(message),
+            AppMessage::PermissionResolved {
+                title,
+                tool_kind,
+                decision,
+                ..
+            } => {
+                let verdict = match decision {
+                    PolicyDecision::Allow => "allowed",
+                    PolicyDecision::Deny | PolicyDecision::Ask => "denied",
+                };
+                self.note(&format!(
+                    "permission {} ({}): {}",
+                    verdict,
+                    tool_kind.unwrap_or_else(|| "unknown".to_string()),
+                    title
+                ));
+            }
+            AppMessage::TurnCompleted { stop_reason, .. } => {
+                self.stop_reason = Some(stop_reason);
+            }
+            AppMessage::Error { error } => self.note(&format!("error: {}", error)),
+            AppMessage::InstructionsAttached { path, .. } => {
+                self.note(&format!("instructions: {}", path.display()));
+            }
+            AppMessage::AgentFailed {
+                agent_name,
+                reason,
+                stderr,
+            } => {
+                self.note(&format!("{} {}", agent_name, reason));
+                for line in stderr {
+                    self.note(&format!("  {}", line));
+                }
+            }
+            AppMessage::SessionRecovered {
+                agent_name,
+                reloaded,
+                ..
+            } => self.note(&format!(
+                "{} restarted; session {}",
+                agent_name,
+                if reloaded { "reloaded" } else { "continues with a fresh context" }
+            )),
+            _ => {}
+        }
+    }
+
+    fn handle_json(&mut self, message: AppMessage) {
+        if let AppMessage::TurnCompleted { stop_reason, .. } = &message {
+            self.stop_reason = Some(*stop_reason);
+        }
+        if let Some(event) = json_event(message) {
+            emit(&event);
+        }
+    }
+
+    fn handle_agent_message(&mut self, message: Message) {
+        match message.content {
+            MessageContent::AgentMessageChunk { content } => {
+                if let Some(text) = block_text(&content) {
+                    let mut stdout = std::io::stdout().lock();
+                    let _ = stdout.write_all(text.as_bytes());
+                    let _ = stdout.flush();
+                    self.open_line = !text.ends_with('\n');
+                }
+            }
+            MessageContent::ToolCall { tool_call } => {
+                self.note(&format!("tool: {}", tool_call.tool_name));
+            }
+            MessageContent::EditProposed { edit } => {
+                self.note(&format!("edit: {}", edit.file_path));
+            }
+            MessageContent::Error { error } => self.note(&format!("error: {}", error)),
+            _ => {}
+        }
+    }
+
+    /// Report a fatal problem for this run
+    fn fail(&mut self, kind: ExitKind, error: &str) {
+        self.finish();
+        report_failure(self.format, self.quiet, self.json_errors, kind, error);
+    }
+
+    fn note(&self, line: &str) {
+        if !self.quiet {
+            eprintln!("[rat] {}", line);
+        }
+    }
+
+    fn finish(&mut self) {
+        if self.open_line {
+            println!();
+            self.open_line = false;
+        }
+    }
+}
+
+/// Report a failure from before a run could start (a bad `--cwd`, an
+/// invalid config) the way the run reports its own. Returns the exit code.
+pub fn setup_failed(
+    format: OutputFormat,
+    quiet: bool,
+    json_errors: bool,
+    kind: ExitKind,
+    error: &anyhow::Error,
+) -> i32 {
+    report_failure(format, quiet, json_errors, kind, &format!("{:#}", error));
+    kind.code()
+}
+
+/// A fatal error as `--output`, `--quiet` and `--json-errors` ask for it
+fn report_failure(
+    format: OutputFormat,
+    quiet: bool,
+    json_errors: bool,
+    kind: ExitKind,
+    error: &str,
+) {
+    if format == OutputFormat::Json {
+        emit(&json!({
+            "type": "error",
+            "message": error,
+            "fatal": true,
+            "kind": kind,
+            "exit_code": kind.code(),
+        }));
+    }
+    if json_errors {
+        let event = json!({ "error": kind, "exit_code": kind.code(), "message": error });
+        eprintln!("{}", event);
+    } else if !quiet && format == OutputFormat::Text {
+        eprintln!("rat: {}", error);
+    }
+}
+
+/// A message as `rat exec --output json` reports it, or `None` for
+/// messages it leaves out
+pub fn json_event(message: AppMessage) -> Option<serde_json::Value> {
+    let event = match message {
+        AppMessage::SessionCreated {
+            agent_name,
+            session_id,
+        } => json!({
+            "type": "session_created",
+            "agent": agent_name,
+            "session_id": session_id.0,
+        }),
+        AppMessage::AgentMessage {
+            agent_name,
+            message,
+        } => json!({
+            "type": "session_update",
+            "agent": agent_name,
+            "session_id": message.session_id.0,
+            "timestamp": message.timestamp,
+            "update": message.content,
+        }),
+        AppMessage::PermissionResolved {
+            agent_name,
+            session_id,
+            title,
+            tool_kind,
+            command,
+            decision,
+        } => json!({
+            "type": "permission",
+            "agent": agent_name,
+            "session_id": session_id.0,
+            "title": title,
+            "tool_kind": tool_kind,
+            "command": command,
+            "decision": decision,
+        }),
+        AppMessage::TurnCompleted {
+            agent_name,
+            session_id,
+            stop_reason,
+        } => json!({
+            "type": "stop",
+            "agent": agent_name,
+            "session_id": session_id.0,
+            "stop_reason": stop_reason,
+        }),
+        AppMessage::Error { error } => json!({ "type": "error", "message": error }),
+        AppMessage::InstructionsAttached {
+            agent_name,
+            session_id,
+            path,
+        } => json!({
+            "type": "instructions",
+            "agent": agent_name,
+            "session_id": session_id.0,
+            "path": path,
+        }),
+        AppMessage::AgentFailed {
+            agent_name,
+            reason,
+            stderr,
+        } => json!({
+            "type": "agent_failed",
+            "agent": agent_name,
+            "reason": reason,
+            "stderr": stderr,
+        }),
+        AppMessage::SessionRecovered {
+            agent_name,
+            session_id,
+            reloaded,
+        } => json!({
+            "type": "recovered",
+            "agent": agent_name,
+            "session_id": session_id.0,
+            "reloaded": reloaded,
+        }),
+        _ => return None,
+    };
+    Some(event)
+}
+
+fn emit(event: &serde_json::Value) {
+    let mut stdout = std::io::stdout().lock();
+    let _ = writeln!(stdout, "{}", event);
+    let _ = stdout.flush();
+}
+
+fn block_text(block: &acp::ContentBlock) -> Option<&str> {
+    match block {
+        acp::ContentBlock::Text(text) => Some(&text.text),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exit_kind_follows_the_error_cause() {
+        let timed_out = Err::<(), _>(AgentError::with_kind(AgentErrorKind::Timeout, "no answer"))
+            .context("Failed to send prompt to agent 'sim'")
+            .unwrap_err();
+        assert_eq!(ExitKind::of(&timed_out), ExitKind::Timeout);
+        let missing = AgentError::with_kind(AgentErrorKind::Unavailable, "Agent 'x' not found");
+        assert_eq!(ExitKind::of(&missing), ExitKind::AgentNotFound);
+        // Messages alone no longer decide the kind
+        let plain = anyhow::anyhow!("acp request timed out: not found");
+        assert_eq!(ExitKind::of(&plain), ExitKind::Error);
+    }
+
+    #[test]
+    fn approval_flags_replace_the_configured_policy() {
+        let configured = PermissionsConfig {
+            default: PolicyDecision::Ask,
+            ..PermissionsConfig::default()
+        };
+
+        let (policy, source) = ApprovalArgs::default().resolve(&configured).unwrap();
+        assert_eq!(source, "config");
+        assert_eq!(policy.decide(Some("edit"), None), PolicyDecision::Deny);
+
+        let deny_all = ApprovalArgs {
+            deny_all: true,
+            ..ApprovalArgs::default()
+        };
+        let (policy, source) = deny_all.resolve(&PermissionsConfig::default()).unwrap();
+        assert_eq!(source, "--deny-all");
+        assert_eq!(policy.decide(Some("read"), None), PolicyDecision::Deny);
+
+        let approve = ApprovalArgs {
+            approve: Some("reads".to_string()),
+            ..ApprovalArgs::default()
+        };
+        let (policy, source) = approve.resolve(&configured).unwrap();
+        assert_eq!(source, "--approve reads");
+        assert_eq!(policy.decide(Some("read"), None), PolicyDecision::Allow);
+        assert_eq!(policy.decide(Some("edit"), None), PolicyDecision::Deny);
+    }
+
+    #[test]
+    fn exit_codes_are_distinct() {
+        let kinds = [
+            ExitKind::Success,
+            ExitKind::Error,
+            ExitKind::Usage,
+            ExitKind::AgentNotFound,
+            ExitKind::AuthRequired,
+            ExitKind::PermissionDenied,
+            ExitKind::Timeout,
+            ExitKind::ProtocolError,
+        ];
+        let codes: BTreeSet<i32> = kinds.iter().map(|kind| kind.code()).collect();
+        assert_eq!(codes.len(), kinds.len());
+        assert_eq!(ExitKind::Success.code(), 0);
+    }
+
+    fn options(prompt: &str) -> ExecOptions {
+        ExecOptions {
+            prompt: prompt.to_string(),
+            context: None,
+            agent: None,
+            timeout: None,
+            output: OutputFormat::Text,
+            approval: ApprovalArgs::default(),
+            quiet: false,
+            json_errors: false,
+        }
+    }
+
+    #[test]
+    fn stdin_context_follows_the_prompt_as_a_resource() {
+        let mut opts = options("Summarize");
+        assert_eq!(build_prompt(&opts).len(), 1);
+
+        opts.context = Some("line one\nline two".to_string());
+        let prompt = build_prompt(&opts);
+        assert_eq!(prompt.len(), 2);
+        assert_eq!(block_text(&prompt[0]), Some("Summarize"));
+        match &prompt[1] {
+            acp::ContentBlock::Resource(embedded) => match &embedded.resource {
+                acp::EmbeddedResourceResource::TextResourceContents(contents) => {
+                    assert_eq!(contents.uri, "stdin://");
+                    assert_eq!(contents.text, "line one\nline two");
+                    assert_eq!(contents.mime_type.as_deref(), Some("text/plain"));
+                }
+                other => panic!("unexpected resource: {:?}", other),
+            },
+            other => panic!("unexpected block: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn json_events_carry_a_type_and_skip_ui_messages() {
+        let session_id = crate::acp::SessionId("s-1".to_string());
+        let created = json_event(AppMessage::SessionCreated {
+            agent_name: "sim".to_string(),
+            session_id: session_id.clone(),
+        })
+        .unwrap();
+        assert_eq!(created["type"], "session_created");
+        assert_eq!(created["agent"], "sim");
+        assert_eq!(created["session_id"], "s-1");
+
+        let stopped = json_event(AppMessage::TurnCompleted {
+            agent_name: "sim".to_string(),
+            session_id,
+            stop_reason: acp::StopReason::EndTurn,
+        })
+        .unwrap();
+        assert_eq!(stopped["type"], "stop");
+        assert_eq!(stopped["stop_reason"], "end_turn");
+
+        assert!(json_event(AppMessage::SuspendTui).is_none());
+        assert!(json_event(AppMessage::AgentConnected {
+            agent_name: "sim".to_string(),
+        })
+        .is_none());
+    }
+
+    #[test]
+    fn watch_skips_vcs_and_build_output_and_lists_changes() {
+        assert!(is_ignored(Path::new("/repo/.git/index")));
+        assert!(is_ignored(Path::new("/repo/target/debug/rat")));
+        assert!(!is_ignored(Path::new("/repo/src/main.rs")));
+        assert!(!is_ignored(Path::new("/repo/src/targets.rs")));
+
+        let changed: BTreeSet<PathBuf> =
+            ["src/b.rs", "src/a.rs"].iter().map(PathBuf::from).collect();
+        assert_eq!(
+            with_changed_files("Run the tests", &changed),
+            "Run the tests\n\nFiles changed since the last run:\n- src/a.rs\n- src/b.rs"
+        );
+    }
+}
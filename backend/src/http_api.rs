@@ -0,0 +1,514 @@
+//! REST and Server-Sent Events API on the bridge port, for integrations that
+//! can't hold a WebSocket open (curl scripts, serverless hooks).
+//!
+//! | Method | Path | Body / result |
+//! |---|---|---|
+//! | `POST` | `/api/v1/sessions` | `{cwd?}` → `{sessionId}` |
+//! | `GET` | `/api/v1/sessions` | open sessions |
+//! | `POST` | `/api/v1/sessions/{id}/prompt` | `{text}` or `{prompt: [...]}`; waits for the turn → `{stopReason}` |
+//! | `GET` | `/api/v1/sessions/{id}/events` | SSE stream of every agent message |
+//! | `GET` | `/api/v1/sessions/{id}/permissions` | permission requests awaiting an answer |
+//! | `POST` | `/api/v1/sessions/{id}/permissions/{requestId}` | `{optionId}` or `{allow}` |
+//! | `DELETE` | `/api/v1/sessions/{id}` | ends the session |
+//!
+//! Each session takes an agent from the pool and drives it as one more
+//! bridge client, so routing, queued prompts and the bridge's own permission
+//! prompts work as they do for WebSocket clients.
+//!
+//! Every request must carry the bridge token as `Authorization: Bearer`, so
+//! the API is off on a bridge without one. Requests from web pages of other
+//! origins and request bodies that aren't JSON are refused, and sessions
+//! only start inside the workspace the bridge serves.
+
+use anyhow::Result;
+use tracing::info;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+use tokio::sync::{broadcast, mpsc, oneshot, Mutex};
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::local_web::HttpRequest;
+use crate::local_ws::{AgentPool, SharedAgent};
+
+/// Requests under this path go to the API instead of the web client
+pub const PREFIX: &str = "/api/v1/";
+
+/// Comment sent on an idle event stream so proxies keep it open
+const SSE_KEEPALIVE: Duration = Duration::from_secs(15);
+
+/// How long `initialize` and `session/new` may take for a new session
+const SETUP_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Agent messages buffered per event stream before a slow reader skips some
+const EVENT_BUFFER: usize = 1024;
+
+type Reply = std::result::Result<Value, (u16, String)>;
+
+/// Sessions created over the API, by ACP session id
+pub struct ApiSessions {
+    sessions: Mutex<HashMap<String, Arc<ApiSession>>>,
+    /// Bearer token every request must carry; without one the API is off
+    token: Option<String>,
+    /// Canonical directory sessions may start in, or below
+    workspace: PathBuf,
+}
+
+/// An agent driven over HTTP: the bridge client it is attached as, and what
+/// came back from the agent that someone may still ask for
+struct ApiSession {
+    agent: Arc<SharedAgent>,
+    client: u64,
+    reply: mpsc::UnboundedSender<Message>,
+    next_id: AtomicU64,
+    /// Our requests waiting for the agent's answer, by id
+    waiting: Mutex<HashMap<u64, oneshot::Sender<Value>>>,
+    /// Permission requests nobody has answered yet, by id
+    permissions: Mutex<BTreeMap<String, Value>>,
+    /// Every message for this client, fanned out to event streams
+    events: broadcast::Sender<String>,
+}
+
+impl ApiSession {
+    async fn start(agent: Arc<SharedAgent>) -> Arc<Self> {
+        let (client, reply, mut outgoing) = agent.join(false).await;
+        let (events, _) = broadcast::channel(EVENT_BUFFER);
+        let session = Arc::new(Self {
+            agent,
+            client,
+            reply,
+            next_id: AtomicU64::new(0),
+            waiting: Mutex::new(HashMap::new()),
+            permissions: Mutex::new(BTreeMap::new()),
+            events,
+        });
+        // Ends once the session is dropped and its reply sender with it
+        let pump: Weak<Self> = Arc::downgrade(&session);
+        tokio::spawn(async move {
+            while let Some(msg) = outgoing.recv().await {
+                let Message::Text(text) = msg else { continue };
+                let Some(session) = pump.upgrade() else { break };
+                session.dispatch(text).await;
+            }
+        });
+        session
+    }
+
+    async fn dispatch(&self, text: String) {
+        if let Ok(msg) = serde_json::from_str::<Value>(&text) {
+            let method = msg.get("method").and_then(|m| m.as_str());
+            match (method, msg.get("id")) {
+                (None, Some(id)) => {
+                    let waiter = match id.as_u64() {
+                        Some(id) => self.waiting.lock().await.remove(&id),
+                        None => None,
+                    };
+                    if let Some(waiter) = waiter {
+                        let _ = waiter.send(msg.clone());
+                    }
+                }
+                (Some(method), Some(id)) if method.ends_with("request_permission") => {
+                    self.permissions
+                        .lock()
+                        .await
+                        .insert(request_key(id), msg.clone());
+                }
+                _ => {}
+            }
+        }
+        let _ = self.events.send(text);
+    }
+
+    /// Send a request to the agent and wait for its result
+    async fn request(&self, method: &str, params: Value) -> Result<Value> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let (tx, rx) = oneshot::channel();
+        self.waiting.lock().await.insert(id, tx);
+        let msg = json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params});
+        self.agent
+            .forward(self.client, msg.to_string(), &self.reply)
+            .await?;
+        let reply = rx
+            .await
+            .map_err(|_| anyhow::anyhow!("agent went away during {}", method))?;
+        match reply.get("error") {
+            Some(error) => Err(anyhow::anyhow!("{} failed: {}", method, error)),
+            None => Ok(reply["result"].clone()),
+        }
+    }
+
+    /// Detach from the agent, handing it back to the pool if nobody else uses it
+    async fn close(&self, pool: &Arc<AgentPool>) {
+        if self.agent.leave(self.client).await == 0 {
+            pool.recycle(self.agent.clone()).await;
+        }
+    }
+}
+
+impl ApiSessions {
+    pub fn new(token: Option<String>, workspace: PathBuf) -> Self {
+        Self {
+            sessions: Mutex::new(HashMap::new()),
+            token,
+            workspace,
+        }
+    }
+
+    /// Refuse requests without the token, from another origin's web page, or
+    /// with a body that isn't JSON
+    fn admit(&self, request: &HttpRequest) -> std::result::Result<(), (u16, String)> {
+        let Some(token) = &self.token else {
+            return Err((401, "the API needs a bridge token (--token or --local-token)".to_string()));
+        };
+        if !crate::local_web::has_token(token, request.header("authorization"), None) {
+            return Err((401, "missing or wrong bearer token".to_string()));
+        }
+        if let Some(origin) = request.header("origin") {
+            let host = request.header("host").unwrap_or_default();
+            let same = ["http://", "https://"]
+                .iter()
+                .any(|scheme| origin.strip_prefix(scheme) == Some(host));
+            if !same {
+                return Err((403, format!("requests from {} are not allowed", origin)));
+            }
+        }
+        let json = request
+            .header("content-type")
+            .and_then(|value| value.split(';').next())
+            .is_some_and(|mime| mime.trim().eq_ignore_ascii_case("application/json"));
+        if !request.body.is_empty() && !json {
+            return Err((415, "request bodies must be application/json".to_string()));
+        }
+        Ok(())
+    }
+
+    /// The directory a new session asked for, which must be in the workspace
+    fn session_dir(&self, cwd: Option<&str>) -> std::result::Result<PathBuf, (u16, String)> {
+        let Some(cwd) = cwd else {
+            return Ok(self.workspace.clone());
+        };
+        let dir = std::fs::canonicalize(cwd).map_err(|e| (400, format!("{}: {}", cwd, e)))?;
+        if !dir.starts_with(&self.workspace) {
+            return Err((
+                403,
+                format!("{} is outside the workspace {}", cwd, self.workspace.display()),
+            ));
+        }
+        Ok(dir)
+    }
+
+    async fn get(&self, id: &str) -> std::result::Result<Arc<ApiSession>, (u16, String)> {
+        self.sessions
+            .lock()
+            .await
+            .get(id)
+            .cloned()
+            .ok_or_else(|| (404, format!("no session {}", id)))
+    }
+
+    async fn list(&self) -> Reply {
+        let sessions = self.sessions.lock().await;
+        let mut list = Vec::new();
+        for (id, session) in sessions.iter() {
+            let pending = session.permissions.lock().await.len();
+            list.push(json!({"sessionId": id, "pendingPermissions": pending}));
+        }
+        Ok(json!({ "sessions": list }))
+    }
+
+    async fn create(&self, request: &HttpRequest, pool: &Arc<AgentPool>) -> Reply {
+        let body = body_json(request, true)?;
+        let cwd = self
+            .session_dir(body.get("cwd").and_then(|c| c.as_str()))?
+            .display()
+            .to_string();
+        let agent = pool.take().await.map_err(|e| (503, e.to_string()))?;
+        let session = ApiSession::start(agent).await;
+        let setup = async {
+            let capabilities =
+                json!({"fs": {"readTextFile": true, "writeTextFile": true}, "terminal": false});
+            session
+                .request(
+                    "initialize",
+                    json!({"protocolVersion": 1, "clientCapabilities": capabilities}),
+                )
+                .await?;
+            session
+                .request("session/new", json!({"cwd": cwd, "mcpServers": []}))
+                .await
+        };
+        let failed = match tokio::time::timeout(SETUP_TIMEOUT, setup).await {
+            Ok(Ok(created)) => match created["sessionId"].as_str() {
+                Some(id) => {
+                    info!("🔧 LOCAL DEV: API session {} started in {}", id, cwd);
+                    self.sessions.lock().await.insert(id.to_string(), session);
+                    return Ok(json!({ "sessionId": id }));
+                }
+                None => (502, "agent returned no sessionId".to_string()),
+            },
+            Ok(Err(e)) => (502, e.to_string()),
+            Err(_) => (504, "agent did not start a session in time".to_string()),
+        };
+        session.close(pool).await;
+        Err(failed)
+    }
+
+    async fn prompt(&self, id: &str, request: &HttpRequest) -> Reply {
+        let session = self.get(id).await?;
+        let body = body_json(request, false)?;
+        let prompt = match (
+            body.get("prompt"),
+            body.get("text").and_then(|t| t.as_str()),
+        ) {
+            (Some(prompt), _) => prompt.clone(),
+            (None, Some(text)) => json!([{ "type": "text", "text": text }]),
+            (None, None) => return Err((400, "give text or prompt".to_string())),
+        };
+        session
+            .request("session/prompt", json!({"sessionId": id, "prompt": prompt}))
+            .await
+            .map_err(|e| (502, e.to_string()))
+    }
+
+    async fn permissions(&self, id: &str) -> Reply {
+        let session = self.get(id).await?;
+        let pending: Vec<Value> = session.permissions.lock().await.values().cloned().collect();
+        Ok(json!({ "permissions": pending }))
+    }
+
+    async fn answer(&self, id: &str, request_id: &str, request: &HttpRequest) -> Reply {
+        let session = self.get(id).await?;
+        let body = body_json(request, false)?;
+        let asked = session
+            .permissions
+            .lock()
+            .await
+            .remove(request_id)
+            .ok_or_else(|| (404, format!("no permission request {}", request_id)))?;
+        let outcome = match (
+            body.get("optionId").and_then(|o| o.as_str()),
+            body.get("allow").and_then(|a| a.as_bool()),
+        ) {
+            (Some(option), _) => json!({"outcome": "selected", "optionId": option}),
+            (None, Some(allow)) => choose_outcome(&asked, allow),
+            (None, None) => {
+                session
+                    .permissions
+                    .lock()
+                    .await
+                    .insert(request_id.to_string(), asked);
+                return Err((400, "give optionId or allow".to_string()));
+            }
+        };
+        let answer = json!({"jsonrpc": "2.0", "id": asked["id"], "result": {"outcome": outcome}});
+        if !session
+            .agent
+            .resolve_bridge_permission(session.client, &answer)
+            .await
+        {
+            session
+                .agent
+                .forward(session.client, answer.to_string(), &session.reply)
+                .await
+                .map_err(|e| (502, e.to_string()))?;
+        }
+        Ok(json!({ "outcome": outcome }))
+    }
+
+    async fn delete(&self, id: &str, pool: &Arc<AgentPool>) -> Reply {
+        let session = self
+            .sessions
+            .lock()
+            .await
+            .remove(id)
+            .ok_or_else(|| (404, format!("no session {}", id)))?;
+        session.close(pool).await;
+        info!("🔧 LOCAL DEV: API session {} closed", id);
+        Ok(json!({ "closed": id }))
+    }
+}
+
+/// Answer one API request; an event stream holds the connection until the
+/// client goes away or the session ends
+pub async fn handle(
+    mut stream: TcpStream,
+    request: HttpRequest,
+    pool: &Arc<AgentPool>,
+    api: &ApiSessions,
+) -> Result<()> {
+    info!("🔧 LOCAL DEV: API {} {}", request.method, request.path);
+    if let Err((status, error)) = api.admit(&request) {
+        respond(&mut stream, status, &json!({ "error": error })).await?;
+        return Ok(());
+    }
+    let path = request.path.strip_prefix(PREFIX).unwrap_or_default();
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    let reply = match (request.method.as_str(), segments.as_slice()) {
+        ("GET", ["sessions"]) => api.list().await,
+        ("POST", ["sessions"]) => api.create(&request, pool).await,
+        ("DELETE", ["sessions", id]) => api.delete(id, pool).await,
+        ("POST", ["sessions", id, "prompt"]) => api.prompt(id, &request).await,
+        ("GET", ["sessions", id, "events"]) => match api.get(id).await {
+            Ok(session) => return stream_events(stream, session).await,
+            Err(e) => Err(e),
+        },
+        ("GET", ["sessions", id, "permissions"]) => api.permissions(id).await,
+        ("POST", ["sessions", id, "permissions", request_id]) => {
+            api.answer(id, request_id, &request).await
+        }
+        _ => Err((404, "not found".to_string())),
+    };
+    match reply {
+        Ok(body) => respond(&mut stream, 200, &body).await?,
+        Err((status, error)) => respond(&mut stream, status, &json!({ "error": error })).await?,
+    }
+    Ok(())
+}
+
+/// Send every agent message as an SSE `data:` line, starting with the
+/// permission requests still waiting so a late subscriber can answer them
+async fn stream_events(mut stream: TcpStream, session: Arc<ApiSession>) -> Result<()> {
+    let mut events = session.events.subscribe();
+    let pending: Vec<Value> = session.permissions.lock().await.values().cloned().collect();
+    // Don't keep a deleted session alive
+    drop(session);
+    stream
+        .write_all(b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n")
+        .await?;
+    for msg in pending {
+        stream
+            .write_all(sse_event(&msg.to_string()).as_bytes())
+            .await?;
+    }
+    loop {
+        let chunk = match tokio::time::timeout(SSE_KEEPALIVE, events.recv()).await {
+            Ok(Ok(event)) => sse_event(&event),
+            Ok(Err(broadcast::error::RecvError::Lagged(skipped))) => {
+                format!(": skipped {} messages\n\n", skipped)
+            }
+            Ok(Err(broadcast::error::RecvError::Closed)) => break,
+            Err(_) => ": keepalive\n\n".to_string(),
+        };
+        stream.write_all(chunk.as_bytes()).await?;
+    }
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// One SSE event; agent messages are single-line JSON, but split just in case
+fn sse_event(data: &str) -> String {
+    let mut event: String = data
+        .lines()
+        .map(|line| format!("data: {}\n", line))
+        .collect();
+    event.push('\n');
+    event
+}
+
+/// Pick the option matching a yes/no answer to a permission request. Agent
+/// requests carry `optionId`s like `allow_once`/`reject_once`, the bridge's
+/// own carry `id`s `allow`/`deny`; without a matching option a no cancels.
+fn choose_outcome(asked: &Value, allow: bool) -> Value {
+    let options = asked["params"]["options"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default();
+    let chosen = options.iter().find_map(|option| {
+        let id = option
+            .get("optionId")
+            .or_else(|| option.get("id"))
+            .and_then(|id| id.as_str())?;
+        let lower = id.to_lowercase();
+        let allows = lower.starts_with("allow");
+        let denies = lower.starts_with("reject") || lower.starts_with("deny");
+        ((allow && allows) || (!allow && denies)).then(|| id.to_string())
+    });
+    match chosen {
+        Some(option) => json!({"outcome": "selected", "optionId": option}),
+        None => json!({"outcome": "cancelled"}),
+    }
+}
+
+/// The request body as JSON; an empty body counts as `{}` where allowed
+fn body_json(request: &HttpRequest, empty_ok: bool) -> Reply {
+    if empty_ok && request.body.iter().all(u8::is_ascii_whitespace) {
+        return Ok(json!({}));
+    }
+    request
+        .json()
+        .map_err(|(status, error)| (status, error.to_string()))
+}
+
+/// JSON-RPC id as a path segment: strings as-is, numbers in decimal
+fn request_key(id: &Value) -> String {
+    match id {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+async fn respond(stream: &mut TcpStream, status: u16, body: &Value) -> std::io::Result<()> {
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        415 => "Unsupported Media Type",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        504 => "Gateway Timeout",
+        _ => "Error",
+    };
+    let body = body.to_string();
+    let challenge = if status == 401 {
+        "WWW-Authenticate: Bearer\r\n"
+    } else {
+        ""
+    };
+    let head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n{}Cache-Control: no-store\r\nConnection: close\r\n\r\n",
+        status,
+        reason,
+        body.len(),
+        challenge
+    );
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(body.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn yes_no_answers_pick_the_matching_option() {
+        let agent = json!({"params": {"options": [
+            {"optionId": "allow_once", "name": "Allow"},
+            {"optionId": "reject_once", "name": "Reject"}
+        ]}});
+        assert_eq!(
+            choose_outcome(&agent, true),
+            json!({"outcome": "selected", "optionId": "allow_once"})
+        );
+        assert_eq!(
+            choose_outcome(&agent, false),
+            json!({"outcome": "selected", "optionId": "reject_once"})
+        );
+        let bridge = json!({"params": {"options": [{"id": "allow"}]}});
+        assert_eq!(
+            choose_outcome(&bridge, false),
+            json!({"outcome": "cancelled"})
+        );
+
+        assert_eq!(sse_event("{\"a\":1}"), "data: {\"a\":1}\n\n");
+        assert_eq!(request_key(&json!(7)), "7");
+        assert_eq!(request_key(&json!("rat-file-1-2")), "rat-file-1-2");
+    }
+}
@@ -0,0 +1,181 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::process::Command;
+
+use crate::acp::{Message, MessageContent};
+use crate::config::integrations::{EditorConfig, EditorKind};
+
+/// Commands handing files and lines over to the user's editor
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Editor {
+    open: Vec<String>,
+    goto: Vec<String>,
+    server: Option<String>,
+}
+
+impl Editor {
+    /// The editor `[integrations.editor]` describes, or `None` without one
+    pub fn from_config(config: &EditorConfig) -> Option<Self> {
+        let (open, goto) = match config.kind {
+            Some(EditorKind::Vscode) => (
+                args(&["code", "--reuse-window", "{files}"]),
+                args(&["code", "--reuse-window", "--goto", "{file}:{line}"]),
+            ),
+            // `--remote` can't take a line in Neovim, so jumps are keys sent
+            // to the server: leave insert or terminal mode, open, go to line
+            Some(EditorKind::Neovim) => (
+                args(&["nvim", "--server", "{server}", "--remote", "{files}"]),
+                args(&[
+                    "nvim",
+                    "--server",
+                    "{server}",
+                    "--remote-send",
+                    "<C-\\><C-N>:drop {file}<CR>{line}G",
+                ]),
+            ),
+            None => (Vec::new(), Vec::new()),
+        };
+        let open = config.open.clone().unwrap_or(open);
+        let goto = config.goto.clone().unwrap_or(goto);
+        if open.is_empty() && goto.is_empty() {
+            return None;
+        }
+        Some(Self {
+            open,
+            goto,
+            server: config.server.clone().or_else(|| std::env::var("NVIM").ok()),
+        })
+    }
+
+    /// Open `files` in the editor
+    pub async fn open(&self, dir: &Path, files: &[PathBuf]) -> Result<()> {
+        let command = self.command(&self.open, "open", files, None)?;
+        run(dir, &command).await
+    }
+
+    /// Show `line` (1-based) of `file` in the editor
+    pub async fn goto(&self, dir: &Path, file: &Path, line: u32) -> Result<()> {
+        let command = self.command(&self.goto, "goto", &[file.to_path_buf()], Some(line))?;
+        run(dir, &command).await
+    }
+
+    /// `template` with its placeholders filled in
+    fn command(
+        &self,
+        template: &[String],
+        name: &str,
+        files: &[PathBuf],
+        line: Option<u32>,
+    ) -> Result<Vec<String>> {
+        if template.is_empty() {
+            anyhow::bail!("set integrations.editor.{} to a command", name);
+        }
+        let files: Vec<String> = files.iter().map(|f| f.display().to_string()).collect();
+        let mut command = Vec::new();
+        for arg in template {
+            if arg == "{files}" {
+                command.extend(files.iter().cloned());
+                continue;
+            }
+            if arg.contains("{server}") && self.server.is_none() {
+                anyhow::bail!(
+                    "no Neovim server: set integrations.editor.server or run RAT in Neovim's terminal"
+                );
+            }
+            command.push(
+                arg.replace("{files}", &files.join(" "))
+                    .replace("{file}", files.first().map_or("", String::as_str))
+                    .replace("{line}", &line.unwrap_or(1).to_string())
+                    .replace("{server}", self.server.as_deref().unwrap_or_default()),
+            );
+        }
+        Ok(command)
+    }
+}
+
+/// The first file and line a message says a tool call works on
+pub fn location(message: &Message) -> Option<(PathBuf, u32)> {
+    let locations = match &message.content {
+        MessageContent::ToolCall { tool_call } => Some(&tool_call.locations),
+        MessageContent::ToolCallUpdate { update } => update.fields.locations.as_ref(),
+        MessageContent::EditProposed { edit } => {
+            return Some((PathBuf::from(&edit.file_path), 1));
+        }
+        _ => None,
+    }?;
+    let location = locations.first()?;
+    Some((location.path.clone(), location.line.unwrap_or(1).max(1)))
+}
+
+async fn run(dir: &Path, command: &[String]) -> Result<()> {
+    // Output would draw over the TUI
+    let output = Command::new(&command[0])
+        .args(&command[1..])
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output()
+        .await
+        .with_context(|| format!("Failed to run {}", command[0]))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!(
+            "{} failed: {}",
+            command[0],
+            stderr
+                .lines()
+                .find(|line| !line.trim().is_empty())
+                .unwrap_or("")
+                .trim()
+        );
+    }
+    Ok(())
+}
+
+fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fills_in_editor_commands() {
+        let files = [PathBuf::from("src/a.rs"), PathBuf::from("src/b.rs")];
+        let config = EditorConfig {
+            kind: Some(EditorKind::Vscode),
+            ..EditorConfig::default()
+        };
+        let code = Editor::from_config(&config).unwrap();
+        assert_eq!(
+            code.command(&code.open, "open", &files, None).unwrap(),
+            ["code", "--reuse-window", "src/a.rs", "src/b.rs"]
+        );
+        assert_eq!(
+            code.command(&code.goto, "goto", &files[..1], Some(12))
+                .unwrap(),
+            ["code", "--reuse-window", "--goto", "src/a.rs:12"]
+        );
+
+        let config = EditorConfig {
+            kind: Some(EditorKind::Neovim),
+            server: Some("/tmp/nvim.sock".to_string()),
+            goto: Some(args(&["nvim-jump", "{file}", "{line}"])),
+            ..EditorConfig::default()
+        };
+        let nvim = Editor::from_config(&config).unwrap();
+        assert_eq!(
+            nvim.command(&nvim.open, "open", &files[..1], None).unwrap(),
+            ["nvim", "--server", "/tmp/nvim.sock", "--remote", "src/a.rs"]
+        );
+        assert_eq!(
+            nvim.command(&nvim.goto, "goto", &files[..1], Some(3))
+                .unwrap(),
+            ["nvim-jump", "src/a.rs", "3"]
+        );
+
+        assert_eq!(Editor::from_config(&EditorConfig::default()), None);
+    }
+}
@@ -0,0 +1,304 @@
+use anyhow::{Context, Result};
+use reqwest::RequestBuilder;
+use serde_json::Value;
+use std::process::Stdio;
+use tokio::process::Command;
+
+use crate::config::integrations::{ForgeConfig, IntegrationsConfig};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForgeKind {
+    GitHub,
+    GitLab,
+}
+
+impl ForgeKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            ForgeKind::GitHub => "GitHub",
+            ForgeKind::GitLab => "GitLab",
+        }
+    }
+
+    /// Section name under `[integrations]`
+    pub fn key(self) -> &'static str {
+        match self {
+            ForgeKind::GitHub => "github",
+            ForgeKind::GitLab => "gitlab",
+        }
+    }
+
+    pub fn config(self, integrations: &IntegrationsConfig) -> &ForgeConfig {
+        match self {
+            ForgeKind::GitHub => &integrations.github,
+            ForgeKind::GitLab => &integrations.gitlab,
+        }
+    }
+
+    fn cli(self) -> &'static str {
+        match self {
+            ForgeKind::GitHub => "gh",
+            ForgeKind::GitLab => "glab",
+        }
+    }
+}
+
+/// A repository on GitHub or GitLab, as named by a git remote
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Repo {
+    pub kind: ForgeKind,
+    pub host: String,
+    /// `owner/name`, or `group/subgroup/name` on GitLab
+    pub path: String,
+}
+
+impl Repo {
+    /// Parse an `https://`, `ssh://` or scp-style (`git@host:owner/repo`)
+    /// remote URL. Hosts other than github.com and gitlab.com are matched
+    /// against `[integrations]` urls, then guessed from their name.
+    pub fn from_remote(url: &str, integrations: &IntegrationsConfig) -> Result<Self> {
+        let (host, path) =
+            split_remote(url).ok_or_else(|| anyhow::anyhow!("can't read remote URL '{}'", url))?;
+        let path = path.trim_matches('/').trim_end_matches(".git").to_string();
+        let configured = |config: &ForgeConfig| config.host().as_deref() == Some(host.as_str());
+        let kind = if host == "github.com" || configured(&integrations.github) {
+            ForgeKind::GitHub
+        } else if host == "gitlab.com"
+            || configured(&integrations.gitlab)
+            || host.contains("gitlab")
+        {
+            ForgeKind::GitLab
+        } else if host.contains("github") {
+            ForgeKind::GitHub
+        } else {
+            anyhow::bail!(
+                "can't tell whether {} is GitHub or GitLab; set [integrations.github] or \
+                 [integrations.gitlab] url",
+                host
+            );
+        };
+        Ok(Self { kind, host, path })
+    }
+
+    /// Web address of the repository
+    pub fn web_url(&self, integrations: &IntegrationsConfig) -> String {
+        format!("{}/{}", self.base_url(integrations), self.path)
+    }
+
+    fn base_url(&self, integrations: &IntegrationsConfig) -> String {
+        match &self.kind.config(integrations).url {
+            Some(url) => url.trim_end_matches('/').to_string(),
+            None => format!("https://{}", self.host),
+        }
+    }
+
+    /// REST API client for the repository's forge: signed in with the
+    /// configured token, or through `gh api` / `glab api` without one
+    pub fn api(&self, integrations: &IntegrationsConfig) -> Result<Api> {
+        let token = self
+            .kind
+            .config(integrations)
+            .token()
+            .with_context(|| format!("integrations.{}", self.kind.key()))?;
+        let auth = match token {
+            Some(token) => {
+                let base = match (self.kind, self.host.as_str()) {
+                    (ForgeKind::GitHub, "github.com") => "https://api.github.com".to_string(),
+                    (ForgeKind::GitHub, _) => format!("{}/api/v3", self.base_url(integrations)),
+                    (ForgeKind::GitLab, _) => format!("{}/api/v4", self.base_url(integrations)),
+                };
+                Auth::Token {
+                    client: reqwest::Client::new(),
+                    base,
+                    token,
+                }
+            }
+            None => Auth::Cli {
+                host: self.host.clone(),
+            },
+        };
+        Ok(Api {
+            kind: self.kind,
+            auth,
+        })
+    }
+
+    /// The path in API URLs: `repos/owner/name` on GitHub, the URL-encoded
+    /// project path on GitLab
+    pub fn api_path(&self) -> String {
+        match self.kind {
+            ForgeKind::GitHub => format!("repos/{}", self.path),
+            ForgeKind::GitLab => format!("projects/{}", self.path.replace('/', "%2F")),
+        }
+    }
+}
+
+fn split_remote(url: &str) -> Option<(String, String)> {
+    if let Ok(parsed) = url::Url::parse(url) {
+        if let Some(host) = parsed.host_str() {
+            return Some((host.to_string(), parsed.path().to_string()));
+        }
+    }
+    // scp-style: [user@]host:path
+    let (host, path) = url.split_once(':')?;
+    let host = host.rsplit('@').next()?;
+    (!host.is_empty() && !path.is_empty()).then(|| (host.to_string(), path.to_string()))
+}
+
+/// Authenticated requests to a forge's REST API
+pub struct Api {
+    kind: ForgeKind,
+    auth: Auth,
+}
+
+enum Auth {
+    Token {
+        client: reqwest::Client,
+        base: String,
+        token: String,
+    },
+    /// Requests go through the forge's CLI and whoever is logged in to it
+    Cli { host: String },
+}
+
+impl Api {
+    /// Whether requests go through `gh` or `glab` rather than a token
+    pub fn uses_cli(&self) -> bool {
+        matches!(self.auth, Auth::Cli { .. })
+    }
+
+    pub async fn get(&self, path: &str) -> Result<Value> {
+        match &self.auth {
+            Auth::Token {
+                client,
+                base,
+                token,
+            } => {
+                self.send(client.get(format!("{}/{}", base, path)), token)
+                    .await
+            }
+            Auth::Cli { host } => self.cli_get(host, path).await,
+        }
+    }
+
+    pub async fn post(&self, path: &str, body: &Value) -> Result<Value> {
+        match &self.auth {
+            Auth::Token {
+                client,
+                base,
+                token,
+            } => {
+                self.send(client.post(format!("{}/{}", base, path)).json(body), token)
+                    .await
+            }
+            Auth::Cli { .. } => anyhow::bail!(
+                "set a token under [integrations.{}] to write through the API",
+                self.kind.key()
+            ),
+        }
+    }
+
+    async fn cli_get(&self, host: &str, path: &str) -> Result<Value> {
+        let program = self.kind.cli();
+        let output = Command::new(program)
+            .args(["api", "--hostname", host, path])
+            .stdin(Stdio::null())
+            .kill_on_drop(true)
+            .output()
+            .await
+            .with_context(|| {
+                format!(
+                    "{} not found; install it or set a token under [integrations.{}]",
+                    program,
+                    self.kind.key()
+                )
+            })?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            anyhow::bail!(
+                "{} api failed: {}",
+                program,
+                stderr
+                    .lines()
+                    .rfind(|line| !line.trim().is_empty())
+                    .unwrap_or("")
+                    .trim()
+            );
+        }
+        serde_json::from_slice(&output.stdout)
+            .with_context(|| format!("{} api printed something other than JSON", program))
+    }
+
+    async fn send(&self, request: RequestBuilder, token: &str) -> Result<Value> {
+        let request = match self.kind {
+            ForgeKind::GitHub => request
+                .bearer_auth(token)
+                .header("Accept", "application/vnd.github+json"),
+            ForgeKind::GitLab => request.header("PRIVATE-TOKEN", token),
+        };
+        let response = request
+            .header("User-Agent", concat!("rat/", env!("CARGO_PKG_VERSION")))
+            .send()
+            .await
+            .with_context(|| format!("{} API unreachable", self.kind.name()))?;
+        let status = response.status();
+        let body: Value = response.json().await.unwrap_or(Value::Null);
+        if !status.is_success() {
+            // GitHub says what went wrong in `message`, GitLab in `message` or `error`
+            let reason = body["message"]
+                .as_str()
+                .or_else(|| body["error"].as_str())
+                .map(String::from)
+                .unwrap_or_else(|| body["message"].to_string());
+            anyhow::bail!("{} API: {} {}", self.kind.name(), status, reason);
+        }
+        Ok(body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_repositories_from_remote_urls() {
+        let mut integrations = IntegrationsConfig::default();
+        let repo = |url: &str, integrations: &IntegrationsConfig| {
+            Repo::from_remote(url, integrations).map(|repo| (repo.kind, repo.host, repo.path))
+        };
+        assert_eq!(
+            repo("git@github.com:G-structure/rat.git", &integrations).unwrap(),
+            (
+                ForgeKind::GitHub,
+                "github.com".to_string(),
+                "G-structure/rat".to_string()
+            )
+        );
+        assert_eq!(
+            repo("https://gitlab.com/group/sub/rat", &integrations).unwrap(),
+            (
+                ForgeKind::GitLab,
+                "gitlab.com".to_string(),
+                "group/sub/rat".to_string()
+            )
+        );
+        assert!(repo(
+            "ssh://git@code.example.com:2222/team/rat.git",
+            &integrations
+        )
+        .is_err());
+
+        integrations.gitlab.url = Some("https://code.example.com".to_string());
+        let repo = Repo::from_remote(
+            "ssh://git@code.example.com:2222/team/rat.git",
+            &integrations,
+        )
+        .unwrap();
+        assert_eq!(repo.kind, ForgeKind::GitLab);
+        assert_eq!(repo.api_path(), "projects/team%2Frat");
+        assert_eq!(
+            repo.web_url(&integrations),
+            "https://code.example.com/team/rat"
+        );
+    }
+}
@@ -0,0 +1,385 @@
+use agent_client_protocol as acp;
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::fmt;
+use std::path::Path;
+
+use super::forge::{ForgeKind, Repo};
+use crate::config::IntegrationsConfig;
+use crate::utils::git;
+
+/// Comments attached with an issue; older ones are left out
+const MAX_COMMENTS: usize = 20;
+
+/// An issue named in a prompt
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IssueRef {
+    /// `123`, `#123` or `owner/repo#123` on GitHub or GitLab; without a
+    /// repository, the one the session's git remote points at
+    Forge { repo: Option<String>, number: u64 },
+    /// `ABC-123`
+    Jira(String),
+}
+
+impl IssueRef {
+    pub fn parse(s: &str) -> Option<Self> {
+        // Allow the reference to end a sentence
+        let s = s.trim_end_matches(['.', ',', ';', ':', '!', '?', ')']);
+        if let Some((repo, number)) = s.rsplit_once('#') {
+            let number = number.parse().ok()?;
+            let repo = match repo {
+                "" => None,
+                repo if repo.contains('/') && !repo.starts_with('/') && !repo.ends_with('/') => {
+                    Some(repo.to_string())
+                }
+                _ => return None,
+            };
+            return Some(IssueRef::Forge { repo, number });
+        }
+        if let Ok(number) = s.parse() {
+            return Some(IssueRef::Forge { repo: None, number });
+        }
+        let (project, number) = s.split_once('-')?;
+        let project_ok = project.starts_with(|c: char| c.is_ascii_uppercase())
+            && project
+                .chars()
+                .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_');
+        let number_ok = !number.is_empty() && number.chars().all(|c| c.is_ascii_digit());
+        (project_ok && number_ok).then(|| IssueRef::Jira(s.to_string()))
+    }
+}
+
+impl fmt::Display for IssueRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IssueRef::Forge { repo, number } => {
+                write!(f, "{}#{}", repo.as_deref().unwrap_or(""), number)
+            }
+            IssueRef::Jira(key) => f.write_str(key),
+        }
+    }
+}
+
+/// Issues a prompt asks for, and the prompt to send with them. A leading
+/// `/issue REF` is taken out of the prompt, leaving what follows it (or a
+/// request to work on the issue when nothing does); `@issue:REF` can appear
+/// anywhere and stays in the text.
+pub fn extract(prompt: &str) -> (String, Vec<IssueRef>) {
+    let mut text = prompt.to_string();
+    let mut refs = Vec::new();
+    if let Some(rest) = prompt.trim_start().strip_prefix("/issue") {
+        if rest.starts_with(char::is_whitespace) {
+            let rest = rest.trim_start();
+            let (word, after) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+            if let Some(reference) = IssueRef::parse(word) {
+                text = match after.trim() {
+                    "" => format!("Work on {}.", reference),
+                    after => after.to_string(),
+                };
+                refs.push(reference);
+            }
+        }
+    }
+    for word in text.split_whitespace() {
+        if let Some(reference) = word.strip_prefix("@issue:").and_then(IssueRef::parse) {
+            if !refs.contains(&reference) {
+                refs.push(reference);
+            }
+        }
+    }
+    (text, refs)
+}
+
+/// An issue's text and discussion, as attached to a prompt
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Issue {
+    /// How the issue was named, e.g. `#12` or `ABC-123`
+    pub reference: String,
+    pub url: String,
+    pub title: String,
+    pub body: String,
+    pub comments: Vec<Comment>,
+    /// Comments left out to stay within `MAX_COMMENTS`
+    pub omitted: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comment {
+    pub author: String,
+    pub body: String,
+}
+
+impl Issue {
+    fn new(
+        reference: &IssueRef,
+        url: String,
+        title: String,
+        body: String,
+        mut comments: Vec<Comment>,
+    ) -> Self {
+        let omitted = comments.len().saturating_sub(MAX_COMMENTS);
+        comments.drain(..omitted);
+        Self {
+            reference: reference.to_string(),
+            url,
+            title,
+            body,
+            comments,
+            omitted,
+        }
+    }
+
+    /// The issue as markdown
+    pub fn to_markdown(&self) -> String {
+        let mut text = format!("# {}: {}\n", self.reference, self.title);
+        if !self.body.trim().is_empty() {
+            text.push_str(&format!("\n{}\n", self.body.trim()));
+        }
+        if !self.comments.is_empty() {
+            text.push_str("\n## Comments\n");
+            if self.omitted > 0 {
+                text.push_str(&format!("\n({} earlier comments left out)\n", self.omitted));
+            }
+            for comment in &self.comments {
+                text.push_str(&format!(
+                    "\n**{}**:\n\n{}\n",
+                    comment.author,
+                    comment.body.trim()
+                ));
+            }
+        }
+        text
+    }
+
+    /// The issue as context for a prompt
+    pub fn to_block(&self) -> acp::ContentBlock {
+        acp::ContentBlock::Resource(acp::EmbeddedResource {
+            resource: acp::EmbeddedResourceResource::TextResourceContents(
+                acp::TextResourceContents {
+                    mime_type: Some("text/markdown".to_string()),
+                    text: self.to_markdown(),
+                    uri: self.url.clone(),
+                },
+            ),
+            annotations: Default::default(),
+        })
+    }
+}
+
+/// Fetch an issue with the credentials in `[integrations]`. GitHub and
+/// GitLab issues without a repository come from the one `remote` of the
+/// checkout at `dir` points at.
+pub async fn fetch(
+    reference: &IssueRef,
+    dir: &Path,
+    remote: &str,
+    integrations: &IntegrationsConfig,
+) -> Result<Issue> {
+    match reference {
+        IssueRef::Forge { repo, number } => {
+            let from_remote =
+                async { Repo::from_remote(&git::remote_url(dir, remote).await?, integrations) };
+            let repo = match (repo, from_remote.await) {
+                (Some(path), Ok(repo)) => Repo {
+                    path: path.clone(),
+                    ..repo
+                },
+                // Outside a checkout, owner/repo is taken to be on github.com
+                (Some(path), Err(_)) => Repo {
+                    kind: ForgeKind::GitHub,
+                    host: "github.com".to_string(),
+                    path: path.clone(),
+                },
+                (None, repo) => repo.context("no repository to look the issue up in")?,
+            };
+            fetch_forge(reference, &repo, *number, integrations).await
+        }
+        IssueRef::Jira(key) => fetch_jira(reference, key, integrations).await,
+    }
+}
+
+async fn fetch_forge(
+    reference: &IssueRef,
+    repo: &Repo,
+    number: u64,
+    integrations: &IntegrationsConfig,
+) -> Result<Issue> {
+    let api = repo.api(integrations)?;
+    let path = format!("{}/issues/{}", repo.api_path(), number);
+    let issue = api.get(&path).await?;
+    let text = |value: &Value| value.as_str().unwrap_or_default().to_string();
+    let (body, url, comments, author) = match repo.kind {
+        ForgeKind::GitHub => (
+            &issue["body"],
+            &issue["html_url"],
+            api.get(&format!("{}/comments?per_page=100", path)).await?,
+            "/user/login",
+        ),
+        ForgeKind::GitLab => (
+            &issue["description"],
+            &issue["web_url"],
+            api.get(&format!("{}/notes?sort=asc&per_page=100", path))
+                .await?,
+            "/author/username",
+        ),
+    };
+    let comments = comments
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+        .iter()
+        // GitLab notes include label changes and the like
+        .filter(|comment| !comment["system"].as_bool().unwrap_or(false))
+        .map(|comment| Comment {
+            author: comment.pointer(author).map(text).unwrap_or_default(),
+            body: text(&comment["body"]),
+        })
+        .collect();
+    Ok(Issue::new(
+        reference,
+        text(url),
+        text(&issue["title"]),
+        text(body),
+        comments,
+    ))
+}
+
+async fn fetch_jira(
+    reference: &IssueRef,
+    key: &str,
+    integrations: &IntegrationsConfig,
+) -> Result<Issue> {
+    let config = &integrations.jira;
+    let base = config
+        .url
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("set [integrations.jira] url to attach Jira issues"))?
+        .trim_end_matches('/');
+    let token = config.token().context("integrations.jira")?;
+    let mut request = reqwest::Client::new()
+        .get(format!(
+            "{}/rest/api/2/issue/{}?fields=summary,description,comment",
+            base, key
+        ))
+        .header("Accept", "application/json")
+        .header("User-Agent", concat!("rat/", env!("CARGO_PKG_VERSION")));
+    request = match (&config.email, token) {
+        (Some(email), Some(token)) => request.basic_auth(email, Some(token)),
+        (None, Some(token)) => request.bearer_auth(token),
+        (_, None) => request,
+    };
+    let response = request.send().await.context("Jira unreachable")?;
+    let status = response.status();
+    let issue: Value = response.json().await.unwrap_or(Value::Null);
+    if !status.is_success() {
+        let reason = issue["errorMessages"][0].as_str().unwrap_or_default();
+        anyhow::bail!("Jira: {} {}", status, reason);
+    }
+    let text = |value: &Value| value.as_str().unwrap_or_default().to_string();
+    let fields = &issue["fields"];
+    let comments = fields["comment"]["comments"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+        .iter()
+        .map(|comment| Comment {
+            author: text(&comment["author"]["displayName"]),
+            body: text(&comment["body"]),
+        })
+        .collect();
+    Ok(Issue::new(
+        reference,
+        format!("{}/browse/{}", base, key),
+        text(&fields["summary"]),
+        text(&fields["description"]),
+        comments,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_issue_references() {
+        let forge = |repo: Option<&str>, number| IssueRef::Forge {
+            repo: repo.map(String::from),
+            number,
+        };
+        assert_eq!(IssueRef::parse("#12"), Some(forge(None, 12)));
+        assert_eq!(IssueRef::parse("12."), Some(forge(None, 12)));
+        assert_eq!(
+            IssueRef::parse("G-structure/rat#7"),
+            Some(forge(Some("G-structure/rat"), 7))
+        );
+        assert_eq!(
+            IssueRef::parse("ABC-123,"),
+            Some(IssueRef::Jira("ABC-123".to_string()))
+        );
+        for not_an_issue in ["abc-123", "ABC-", "rat#7", "#x", "fix"] {
+            assert_eq!(IssueRef::parse(not_an_issue), None, "{}", not_an_issue);
+        }
+    }
+
+    #[test]
+    fn takes_issues_out_of_prompts() {
+        assert_eq!(
+            extract("/issue ABC-123 and add a test"),
+            (
+                "and add a test".to_string(),
+                vec![IssueRef::Jira("ABC-123".to_string())]
+            )
+        );
+        assert_eq!(
+            extract("/issue #4"),
+            (
+                "Work on #4.".to_string(),
+                vec![IssueRef::Forge {
+                    repo: None,
+                    number: 4
+                }]
+            )
+        );
+        assert_eq!(
+            extract("Compare @issue:#4 with @issue:ABC-1."),
+            (
+                "Compare @issue:#4 with @issue:ABC-1.".to_string(),
+                vec![
+                    IssueRef::Forge {
+                        repo: None,
+                        number: 4
+                    },
+                    IssueRef::Jira("ABC-1".to_string())
+                ]
+            )
+        );
+        assert_eq!(
+            extract("/issues are open"),
+            ("/issues are open".to_string(), vec![])
+        );
+    }
+
+    #[test]
+    fn keeps_the_latest_comments() {
+        let comments = (0..25)
+            .map(|i| Comment {
+                author: "ana".to_string(),
+                body: format!("comment {}", i),
+            })
+            .collect();
+        let issue = Issue::new(
+            &IssueRef::Jira("ABC-1".to_string()),
+            "https://example.atlassian.net/browse/ABC-1".to_string(),
+            "Crash on start".to_string(),
+            "It crashes.".to_string(),
+            comments,
+        );
+        assert_eq!(issue.omitted, 5);
+        assert_eq!(issue.comments[0].body, "comment 5");
+        let text = issue.to_markdown();
+        assert!(text.starts_with(
+            "# ABC-1: Crash on start\n\nIt crashes.\n\n## Comments\n\n\
+             (5 earlier comments left out)\n\n**ana**:\n\ncomment 5\n"
+        ));
+    }
+}
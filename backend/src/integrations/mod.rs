@@ -0,0 +1,9 @@
+//! Services and tools sessions work with outside RAT: GitHub and GitLab
+//! for pull requests, those and Jira for issues attached to prompts, tmux
+//! for panes next to RAT's, and the editor for the files agents touch.
+
+pub mod editor;
+pub mod forge;
+pub mod issue;
+pub mod pull_request;
+pub mod tmux;
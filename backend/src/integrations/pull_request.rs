@@ -0,0 +1,238 @@
+use anyhow::{Context, Result};
+use serde_json::json;
+use std::path::Path;
+use std::process::Stdio;
+use tokio::process::Command;
+
+use super::forge::{Api, ForgeKind, Repo};
+use crate::acp::{MessageContent, Session};
+use crate::config::IntegrationsConfig;
+use crate::utils::git;
+
+/// A pull request (merge request on GitLab) to open for a pushed branch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PullRequest {
+    pub branch: String,
+    pub title: String,
+    pub body: String,
+    pub draft: bool,
+}
+
+impl PullRequest {
+    /// Titled after the session's first prompt, described by its prompts
+    /// and the files the agent changed
+    pub fn from_session(session: &Session, files: &[String], branch: String, draft: bool) -> Self {
+        let short_id = session.id.0.get(..8).unwrap_or(&session.id.0);
+        let title = match session.title() {
+            Some(title) if title.chars().count() > 72 => {
+                format!("{}…", title.chars().take(71).collect::<String>())
+            }
+            Some(title) => title,
+            None => format!("Changes from RAT session {}", short_id),
+        };
+
+        let mut body = format!("Changes made with RAT in session `{}`", session.id.0);
+        if let Some(agent) = &session.agent_name {
+            body.push_str(&format!(" ({})", agent));
+        }
+        body.push_str(".\n");
+        let prompts: Vec<String> = session
+            .get_messages()
+            .filter_map(|message| match &message.content {
+                MessageContent::UserPrompt { content } => {
+                    content.iter().find_map(|block| match block {
+                        agent_client_protocol::ContentBlock::Text(text) => {
+                            Some(text.text.trim().to_string())
+                        }
+                        _ => None,
+                    })
+                }
+                _ => None,
+            })
+            .filter(|prompt| !prompt.is_empty())
+            .collect();
+        if !prompts.is_empty() {
+            body.push_str("\n### Prompts\n\n");
+            for (i, prompt) in prompts.iter().enumerate() {
+                // Keep each prompt in its list item
+                body.push_str(&format!("{}. {}\n", i + 1, prompt.replace('\n', "\n   ")));
+            }
+        }
+        if !files.is_empty() {
+            body.push_str("\n### Files\n\n");
+            for file in files {
+                body.push_str(&format!("- `{}`\n", file));
+            }
+        }
+
+        Self {
+            branch,
+            title,
+            body,
+            draft,
+        }
+    }
+}
+
+/// Push `commit` of the repository at `dir` to `pr.branch` on `remote` and
+/// open a pull request for it. Uses the forge's API when `[integrations]`
+/// has a token for it, otherwise `gh` or `glab`. Returns the pull request's
+/// URL.
+pub async fn open(
+    dir: &Path,
+    remote: &str,
+    commit: &str,
+    pr: &PullRequest,
+    integrations: &IntegrationsConfig,
+) -> Result<String> {
+    let repo = Repo::from_remote(&git::remote_url(dir, remote).await?, integrations)?;
+    let api = repo.api(integrations)?;
+    git::push_commit(dir, remote, commit, &pr.branch).await?;
+    if api.uses_cli() {
+        open_with_cli(dir, &repo, integrations, pr).await
+    } else {
+        open_with_api(&repo, &api, pr).await
+    }
+}
+
+async fn open_with_api(repo: &Repo, api: &Api, pr: &PullRequest) -> Result<String> {
+    let project = api.get(&repo.api_path()).await?;
+    let base = project["default_branch"]
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("{} has no default branch", repo.path))?;
+    let (endpoint, body, url_field) = match repo.kind {
+        ForgeKind::GitHub => (
+            "pulls",
+            json!({
+                "title": pr.title,
+                "head": pr.branch,
+                "base": base,
+                "body": pr.body,
+                "draft": pr.draft,
+            }),
+            "html_url",
+        ),
+        ForgeKind::GitLab => (
+            "merge_requests",
+            json!({
+                "title": if pr.draft { format!("Draft: {}", pr.title) } else { pr.title.clone() },
+                "source_branch": pr.branch,
+                "target_branch": base,
+                "description": pr.body,
+            }),
+            "web_url",
+        ),
+    };
+    let created = api
+        .post(&format!("{}/{}", repo.api_path(), endpoint), &body)
+        .await?;
+    created[url_field]
+        .as_str()
+        .map(String::from)
+        .ok_or_else(|| anyhow::anyhow!("{} API returned no URL", repo.kind.name()))
+}
+
+async fn open_with_cli(
+    dir: &Path,
+    repo: &Repo,
+    integrations: &IntegrationsConfig,
+    pr: &PullRequest,
+) -> Result<String> {
+    // gh takes HOST/OWNER/REPO, glab the repository's URL
+    let gh_repo = format!("{}/{}", repo.host, repo.path);
+    let web_url = repo.web_url(integrations);
+    let (program, mut args) = match repo.kind {
+        ForgeKind::GitHub => (
+            "gh",
+            vec![
+                "pr", "create", "--repo", &gh_repo, "--head", &pr.branch, "--title", &pr.title,
+                "--body", &pr.body,
+            ],
+        ),
+        ForgeKind::GitLab => (
+            "glab",
+            vec![
+                "mr",
+                "create",
+                "--repo",
+                &web_url,
+                "--source-branch",
+                &pr.branch,
+                "--title",
+                &pr.title,
+                "--description",
+                &pr.body,
+                "--yes",
+            ],
+        ),
+    };
+    if pr.draft {
+        args.push("--draft");
+    }
+    let output = Command::new(program)
+        .args(&args)
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output()
+        .await
+        .with_context(|| {
+            format!(
+                "{} not found; install it or set a token under [integrations.{}]",
+                program,
+                repo.kind.key()
+            )
+        })?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!(
+            "{} failed: {}",
+            program,
+            stderr
+                .lines()
+                .rfind(|line| !line.trim().is_empty())
+                .unwrap_or("")
+                .trim()
+        );
+    }
+    // Both print the new URL last
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    stdout
+        .lines()
+        .rev()
+        .map(str::trim)
+        .find(|line| line.starts_with("http"))
+        .map(String::from)
+        .ok_or_else(|| anyhow::anyhow!("{} printed no pull request URL", program))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::acp::{Message, SessionId};
+    use agent_client_protocol::{ContentBlock, TextContent};
+
+    #[test]
+    fn describes_the_session() {
+        let id = SessionId("0123456789abcdef".to_string());
+        let mut session = Session::with_agent(id.clone(), "gemini".to_string());
+        for prompt in ["Rename the loader\nand its tests", "Update the docs"] {
+            session.record_message(Message::user_prompt(
+                id.clone(),
+                vec![ContentBlock::Text(TextContent {
+                    text: prompt.to_string(),
+                    annotations: Default::default(),
+                })],
+            ));
+        }
+        let files = ["src/loader.rs".to_string()];
+        let pr = PullRequest::from_session(&session, &files, "rat/01234567".to_string(), false);
+        assert_eq!(pr.title, "Rename the loader");
+        assert_eq!(
+            pr.body,
+            "Changes made with RAT in session `0123456789abcdef` (gemini).\n\
+             \n### Prompts\n\n1. Rename the loader\n   and its tests\n2. Update the docs\n\
+             \n### Files\n\n- `src/loader.rs`\n"
+        );
+    }
+}
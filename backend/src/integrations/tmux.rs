@@ -0,0 +1,159 @@
+use anyhow::{Context, Result};
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+use crate::config::integrations::{TmuxConfig, TmuxSplit};
+
+/// Whether RAT runs inside a tmux session
+pub fn inside_tmux() -> bool {
+    std::env::var_os("TMUX").is_some()
+}
+
+/// Where a new RAT opens
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placement {
+    /// A pane beside (or below) this one
+    Split,
+    Window,
+}
+
+/// Start another RAT in `dir` with `args`, in a split of this RAT's pane
+/// or a new window
+pub async fn open_rat(
+    placement: Placement,
+    dir: &Path,
+    args: &[String],
+    config: &TmuxConfig,
+) -> Result<()> {
+    if !inside_tmux() {
+        anyhow::bail!("RAT isn't running inside tmux");
+    }
+    let exe = std::env::current_exe().context("Can't find the rat executable")?;
+    let mut tmux_args: Vec<OsString> = match placement {
+        Placement::Split => split_args(config),
+        Placement::Window => vec!["new-window".into()],
+    };
+    tmux_args.extend(["-c".into(), dir.as_os_str().to_owned(), exe.into()]);
+    tmux_args.extend(args.iter().map(OsString::from));
+    tokio::task::spawn_blocking(move || tmux(&tmux_args))
+        .await
+        .context("tmux task failed")??;
+    Ok(())
+}
+
+/// `split-window` next to this RAT's pane, as `[integrations.tmux]` sizes it
+fn split_args(config: &TmuxConfig) -> Vec<OsString> {
+    let direction = match config.split {
+        TmuxSplit::Horizontal => "-h",
+        TmuxSplit::Vertical => "-v",
+    };
+    let mut args: Vec<OsString> = vec!["split-window".into(), direction.into()];
+    args.extend(["-l".into(), config.size.clone().into()]);
+    // The focused pane may be another one by now
+    if let Some(pane) = std::env::var_os("TMUX_PANE") {
+        args.extend(["-t".into(), pane]);
+    }
+    args
+}
+
+/// A tmux pane following a log of the commands agents run and their output.
+/// The pane opens with the first write and opens again if it was closed;
+/// it is closed and the log removed when this is dropped.
+pub struct OutputPane {
+    config: TmuxConfig,
+    path: PathBuf,
+    file: Option<File>,
+    pane: Option<String>,
+}
+
+impl OutputPane {
+    /// A pane for `config`, or `None` when it doesn't ask for one or RAT
+    /// isn't inside tmux
+    pub fn from_config(config: &TmuxConfig) -> Option<Self> {
+        (config.output_pane && inside_tmux()).then(|| Self {
+            config: config.clone(),
+            path: std::env::temp_dir().join(format!("rat-output-{}.log", std::process::id())),
+            file: None,
+            pane: None,
+        })
+    }
+
+    pub fn write_lines(&mut self, lines: &[String]) -> Result<()> {
+        if self.file.is_none() {
+            let file = File::create(&self.path)
+                .with_context(|| format!("Can't create {}", self.path.display()))?;
+            self.file = Some(file);
+        }
+        if !self.pane_open() {
+            self.open_pane()?;
+        }
+        if let Some(file) = &mut self.file {
+            for line in lines {
+                writeln!(file, "{}", line)?;
+            }
+            file.flush()?;
+        }
+        Ok(())
+    }
+
+    fn pane_open(&self) -> bool {
+        self.pane.as_ref().is_some_and(|pane| {
+            tmux(&[
+                "display-message".into(),
+                "-p".into(),
+                "-t".into(),
+                pane.into(),
+            ])
+            .is_ok()
+        })
+    }
+
+    fn open_pane(&mut self) -> Result<()> {
+        let mut args = split_args(&self.config);
+        // Keep the keyboard in RAT and print the new pane's id
+        args.extend(["-d", "-P", "-F", "#{pane_id}"].map(OsString::from));
+        args.extend(["tail", "-n", "+1", "-F"].map(OsString::from));
+        args.push(self.path.clone().into_os_string());
+        let output = tmux(&args)?;
+        let pane = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        let _ = tmux(&[
+            "select-pane".into(),
+            "-t".into(),
+            pane.clone().into(),
+            "-T".into(),
+            "RAT agent commands".into(),
+        ]);
+        self.pane = Some(pane);
+        Ok(())
+    }
+}
+
+impl Drop for OutputPane {
+    fn drop(&mut self) {
+        if let Some(pane) = self.pane.take() {
+            let _ = tmux(&["kill-pane".into(), "-t".into(), pane.into()]);
+        }
+        if self.file.take().is_some() {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+fn tmux(args: &[OsString]) -> Result<Output> {
+    let output = Command::new("tmux")
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .context("Failed to run tmux")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "tmux {} failed: {}",
+            args[0].to_string_lossy(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(output)
+}
@@ -1,7 +1,55 @@
+//! RAT's agent plumbing as a library: spawn ACP agents, drive their
+//! sessions and answer their permission requests from your own tool.
+//!
+//! The terminal UI sits behind the default `tui` feature. Embedders that only
+//! want the plumbing can leave it out, along with ratatui and crossterm:
+//!
+//! ```toml
+//! rat = { version = "0.1", default-features = false }
+//! ```
+//!
+//! Agents report back on the channel they were created with, as
+//! [`AppMessage`]s. The channel is bounded; see [`events`] for what happens
+//! when the receiver falls behind:
+//!
+//! ```no_run
+//! use rat::{AgentManager, AppMessage, PermissionsConfig};
+//!
+//! # async fn run() -> anyhow::Result<()> {
+//! let (tx, mut rx) = rat::events::channel(rat::events::DEFAULT_CAPACITY);
+//! let agents = AgentManager::new(Default::default(), tx, None).await?;
+//! agents.set_permission_policy(PermissionsConfig::from_approve_list("reads")?);
+//! agents.connect_agent("claude-code").await?;
+//! let session = agents.create_session("claude-code").await?;
+//! agents
+//!     .send_message("claude-code", &session, "Summarize this repo".into())
+//!     .await?;
+//! while let Some(event) = rx.recv().await {
+//!     if let AppMessage::TurnCompleted { .. } = event {
+//!         break;
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
 pub mod acp;
 pub mod adapters;
+#[cfg(feature = "tui")]
 pub mod app;
 pub mod config;
+#[cfg(feature = "tui")]
 pub mod effects;
+pub mod events;
+pub mod integrations;
+pub mod telemetry;
+#[cfg(feature = "tui")]
 pub mod ui;
 pub mod utils;
+
+pub use acp::permissions::PermissionManager;
+pub use acp::{AcpClient, Message, MessageContent, Session, SessionId};
+pub use adapters::agent_installer::{AgentCommand, AgentInstaller};
+pub use adapters::{AgentAdapter, AgentManager, ExternalAgentSpec};
+pub use config::{PermissionsConfig, PolicyDecision};
+pub use events::AppMessage;
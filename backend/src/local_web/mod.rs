@@ -0,0 +1,290 @@
+use anyhow::Result;
+use tracing::{info, warn};
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// Built-in browser client, served when no `--local-web-root` is given
+const INDEX_HTML: &str = include_str!("index.html");
+
+/// Longest request head we read before giving up on a connection
+const MAX_HEAD: usize = 16 * 1024;
+
+/// Largest request (head and body) the HTTP APIs read
+const MAX_REQUEST: usize = 64 * 1024;
+
+/// Whether the request waiting on `stream` asks for a WebSocket upgrade.
+/// Only peeks, so the WebSocket handshake still sees the whole request.
+pub async fn is_websocket_upgrade(stream: &TcpStream) -> std::io::Result<bool> {
+    let mut buf = vec![0u8; MAX_HEAD];
+    // Headers may arrive in pieces; give them a moment before deciding
+    for _ in 0..200 {
+        let n = stream.peek(&mut buf).await?;
+        if n == 0 {
+            return Ok(false);
+        }
+        let head = &buf[..n];
+        if n == buf.len() || head.windows(4).any(|w| w == b"\r\n\r\n") {
+            let head = String::from_utf8_lossy(head).to_ascii_lowercase();
+            return Ok(head
+                .lines()
+                .any(|line| line.starts_with("upgrade:") && line.contains("websocket")));
+        }
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    Ok(false)
+}
+
+/// Answer a plain HTTP request with the web client: files from `web_root`
+/// (e.g. a built `rat-web/dist`), or the built-in page
+pub async fn serve(
+    mut stream: TcpStream,
+    request: &HttpRequest,
+    web_root: Option<&Path>,
+) -> Result<()> {
+    let method = request.method.as_str();
+    let path = request.path.as_str();
+    info!("🔧 LOCAL DEV: HTTP {} {}", method, path);
+
+    let response = if method != "GET" && method != "HEAD" {
+        Response::text(405, "Method Not Allowed")
+    } else {
+        match web_root {
+            Some(root) => serve_file(root, path).await,
+            None if path == "/" || path == "/index.html" => Response::index(),
+            None => Response::text(404, "Not Found"),
+        }
+    };
+    response.write(&mut stream, method == "HEAD").await?;
+    Ok(())
+}
+
+/// A plain HTTP request, read whole
+pub(crate) struct HttpRequest {
+    pub(crate) method: String,
+    /// Without the query string
+    pub(crate) path: String,
+    /// Header names are lowercased
+    pub(crate) headers: Vec<(String, String)>,
+    pub(crate) body: Vec<u8>,
+}
+
+impl HttpRequest {
+    pub(crate) async fn read(stream: &mut TcpStream) -> Result<Self> {
+        let mut data = Vec::new();
+        let mut buf = [0u8; 4096];
+        let head_end = loop {
+            if let Some(pos) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+                break pos;
+            }
+            let n = stream.read(&mut buf).await?;
+            if n == 0 {
+                return Err(anyhow::anyhow!("connection closed mid-request"));
+            }
+            data.extend_from_slice(&buf[..n]);
+            if data.len() > MAX_REQUEST {
+                return Err(anyhow::anyhow!("HTTP request too large"));
+            }
+        };
+        let head = String::from_utf8_lossy(&data[..head_end]).into_owned();
+        let mut lines = head.lines();
+        let mut parts = lines.next().unwrap_or_default().split_whitespace();
+        let method = parts.next().unwrap_or_default().to_string();
+        let target = parts.next().unwrap_or("/");
+        let path = target.split(['?', '#']).next().unwrap_or("/").to_string();
+        let headers: Vec<(String, String)> = lines
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim().to_string()))
+            .collect();
+
+        let mut request = Self {
+            method,
+            path,
+            headers,
+            body: data[head_end + 4..].to_vec(),
+        };
+        let length: usize = request
+            .header("content-length")
+            .and_then(|n| n.parse().ok())
+            .unwrap_or(0);
+        if length > MAX_REQUEST {
+            return Err(anyhow::anyhow!("HTTP request too large"));
+        }
+        while request.body.len() < length {
+            let n = stream.read(&mut buf).await?;
+            if n == 0 {
+                return Err(anyhow::anyhow!("connection closed mid-request"));
+            }
+            request.body.extend_from_slice(&buf[..n]);
+        }
+        request.body.truncate(length);
+        Ok(request)
+    }
+
+    pub(crate) fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub(crate) fn json(&self) -> std::result::Result<serde_json::Value, (u16, &'static str)> {
+        serde_json::from_slice(&self.body).map_err(|_| (400, "invalid_request"))
+    }
+}
+
+/// Whether a request carries `token`, as `Authorization: Bearer <token>` or,
+/// since browsers can't set headers on a WebSocket, as `?token=<token>`
+pub(crate) fn has_token(token: &str, authorization: Option<&str>, query: Option<&str>) -> bool {
+    let bearer = authorization
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::to_string);
+    let param = query.and_then(|query| {
+        url::form_urlencoded::parse(query.as_bytes())
+            .find(|(key, _)| key == "token")
+            .map(|(_, value)| value.into_owned())
+    });
+    bearer
+        .into_iter()
+        .chain(param)
+        .any(|given| same_bytes(token.as_bytes(), given.trim().as_bytes()))
+}
+
+/// Byte comparison that takes as long wherever the first difference is
+fn same_bytes(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// A file under `root`; unknown paths get `index.html` so client-side routes load
+async fn serve_file(root: &Path, path: &str) -> Response {
+    let Some(relative) = sanitize(path) else {
+        return Response::text(400, "Bad Request");
+    };
+    let mut file = root.join(&relative);
+    if !file.is_file() {
+        file = root.join("index.html");
+    }
+    match tokio::fs::read(&file).await {
+        Ok(body) => Response {
+            status: 200,
+            content_type: content_type(&file),
+            body,
+        },
+        Err(e) => {
+            warn!("🔧 LOCAL DEV: failed to read {}: {}", file.display(), e);
+            Response::text(404, "Not Found")
+        }
+    }
+}
+
+/// Request path as a relative file path, refusing anything that climbs out of the root
+fn sanitize(path: &str) -> Option<PathBuf> {
+    let mut relative = PathBuf::new();
+    for component in Path::new(path.trim_start_matches('/')).components() {
+        match component {
+            Component::Normal(part) => relative.push(part),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    Some(relative)
+}
+
+fn content_type(file: &Path) -> &'static str {
+    match file
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default()
+    {
+        "html" => "text/html; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "json" | "map" => "application/json",
+        "webmanifest" => "application/manifest+json",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "ico" => "image/x-icon",
+        "woff2" => "font/woff2",
+        "wasm" => "application/wasm",
+        "txt" => "text/plain; charset=utf-8",
+        _ => "application/octet-stream",
+    }
+}
+
+struct Response {
+    status: u16,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl Response {
+    /// The built-in page, told which directory new sessions should start in
+    fn index() -> Self {
+        let cwd = std::env::current_dir().unwrap_or_default();
+        let cwd = serde_json::to_string(&cwd.display().to_string()).unwrap_or_default();
+        Self {
+            status: 200,
+            content_type: "text/html; charset=utf-8",
+            body: INDEX_HTML.replace("\"__RAT_CWD__\"", &cwd).into_bytes(),
+        }
+    }
+
+    fn text(status: u16, body: &str) -> Self {
+        Self {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body: body.as_bytes().to_vec(),
+        }
+    }
+
+    async fn write(&self, stream: &mut TcpStream, head_only: bool) -> std::io::Result<()> {
+        let reason = match self.status {
+            200 => "OK",
+            400 => "Bad Request",
+            405 => "Method Not Allowed",
+            _ => "Not Found",
+        };
+        let head = format!(
+            "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n",
+            self.status,
+            reason,
+            self.content_type,
+            self.body.len()
+        );
+        stream.write_all(head.as_bytes()).await?;
+        if !head_only {
+            stream.write_all(&self.body).await?;
+        }
+        stream.shutdown().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_comes_from_the_bearer_header_or_the_query() {
+        assert!(has_token("s3cret", Some("Bearer s3cret"), None));
+        assert!(has_token("s3cret", None, Some("session=work&token=s3cret")));
+        assert!(!has_token("s3cret", Some("Bearer s3cre"), Some("token=other")));
+        assert!(!has_token("s3cret", Some("s3cret"), None));
+        assert!(!has_token("s3cret", None, None));
+    }
+
+    #[test]
+    fn sanitize_keeps_requests_inside_the_root() {
+        assert_eq!(sanitize("/"), Some(PathBuf::new()));
+        assert_eq!(
+            sanitize("/assets/./app.js"),
+            Some(PathBuf::from("assets/app.js"))
+        );
+        assert_eq!(sanitize("/../etc/passwd"), None);
+        assert_eq!(sanitize("/assets/../../secret"), None);
+        assert_eq!(
+            content_type(Path::new("assets/app.js")),
+            "text/javascript; charset=utf-8"
+        );
+    }
+}
@@ -1,102 +1,318 @@
 use anyhow::Result;
+use flate2::{write::GzEncoder, Compression};
 use futures_util::{SinkExt, StreamExt};
-use log::{info, warn};
+use tracing::{info, info_span, warn, Instrument};
 use std::env;
 use std::net::SocketAddr;
-use std::collections::HashMap;
-use std::sync::Arc;
+use std::path::{Component, Path, PathBuf};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::future::Future;
+use std::io::Write as _;
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
 use tokio::io::{AsyncReadExt, AsyncWriteExt, AsyncBufReadExt};
 use tokio::net::{TcpListener, TcpStream};
-use tokio::process::Command;
-use tokio::sync::{Mutex, oneshot};
+use tokio::process::{Child, ChildStdin, Command};
+use tokio::sync::{mpsc, Mutex, oneshot};
 use tokio_tungstenite::{
     accept_hdr_async,
     tungstenite::{
-        handshake::server::{Request, Response},
+        handshake::server::{ErrorResponse, Request, Response},
+        http::StatusCode,
+        protocol::{frame::coding::CloseCode, CloseFrame},
         Message,
     },
 };
 
 use crate::adapters::agent_installer::{AgentCommand, AgentInstaller};
+use crate::bridge_log::{self, BridgeLog};
+use crate::http_api::{self, ApiSessions};
+use crate::local_web::HttpRequest;
+use crate::transfer::{Direction, Transfers};
+use crate::utils::ndjson::{self, gunzip, is_gzip, ws_text_frames, Intercepted, LocalRequest, NdjsonFramer};
+use crate::utils::process::{shutdown_signal, signal_child, ProcessSignal};
+
+/// Warm agent pool for the local WebSocket server
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PoolOptions {
+    /// Initialized agents kept ready for new connections; 0 disables pooling
+    pub size: usize,
+    /// How long a pooled agent may sit unused before it is stopped
+    pub idle_ttl: Duration,
+}
+
+/// Heartbeats, idle handling and compression for bridge WebSocket connections
+#[derive(Debug, Clone, Copy)]
+pub struct ConnectionOptions {
+    /// How often clients are pinged; a client that sends nothing, not even a
+    /// pong, for two intervals is taken for a dead (half-open) connection.
+    /// Zero disables pings.
+    pub ping_interval: Duration,
+    /// Close connections that carry no messages for this long, releasing
+    /// their agent
+    pub idle_timeout: Option<Duration>,
+    /// Messages to clients that asked for compression (`?compress=gzip`) are
+    /// gzipped once they reach this many bytes; `None` never compresses
+    pub compress_threshold: Option<usize>,
+}
+
+impl Default for ConnectionOptions {
+    fn default() -> Self {
+        Self {
+            ping_interval: Duration::from_secs(30),
+            idle_timeout: None,
+            compress_threshold: Some(16 * 1024),
+        }
+    }
+}
+
+/// What the bridge serves, besides its listener
+#[derive(Debug, Clone, Default)]
+pub struct ServeOptions {
+    /// The ACP agent to start for clients; without one, clients get an echo
+    /// of what they send
+    pub agent: Option<AgentCommand>,
+    pub pool: PoolOptions,
+    pub connection: ConnectionOptions,
+    /// Directory of web UI assets served instead of the built-in page
+    pub web_root: Option<PathBuf>,
+    /// Advertise the bridge on the LAN via mDNS
+    pub advertise: bool,
+    /// Secret WebSocket clients must present (see `local_web::has_token`);
+    /// `None` accepts anyone who can reach the port
+    pub token: Option<String>,
+}
 
 /// Start a local WebSocket server for direct connections (no encryption, no pairing)
 /// This is for local development only - WARNING: No security/encryption!
-pub async fn start_local_ws_server(port: u16) -> Result<()> {
-    env_logger::init();
+pub async fn start_local_ws_server(
+    port: u16,
+    pool_options: PoolOptions,
+    connection: ConnectionOptions,
+    web_root: Option<PathBuf>,
+    advertise: bool,
+    token: Option<String>,
+) -> Result<()> {
+    crate::utils::logging::init_stderr();
 
     let addr = format!("0.0.0.0:{}", port);
     let listener = TcpListener::bind(&addr).await?;
     info!("🔧 LOCAL DEV: WebSocket server listening on ws://{}", addr);
-    info!("🔧 LOCAL DEV: WARNING - No encryption, no authentication! Local development only!");
-
-    // Try to automatically resolve an ACP agent (Claude Code preferred), like the TUI does
-    let resolved_agent: Option<AgentCommand> = match env::var("RAT2E_AGENT_CMD") {
-        Ok(cmd_path) => {
-            let args = env::var("RAT2E_AGENT_ARGS").unwrap_or_default();
-            let args_vec: Vec<String> = if args.is_empty() {
-                vec![]
-            } else {
-                args.split_whitespace().map(|s| s.to_string()).collect()
-            };
+    let options = ServeOptions {
+        agent: resolve_agent().await,
+        pool: pool_options,
+        connection,
+        web_root,
+        advertise,
+        token,
+    };
+    serve(listener, options, shutdown_signal()).await
+}
+
+/// The ACP agent for bridge clients: `RAT2E_AGENT_CMD` (with
+/// `RAT2E_AGENT_ARGS`) if set, else Claude Code or Gemini, installed on
+/// first use like the TUI does
+pub async fn resolve_agent() -> Option<AgentCommand> {
+    if let Ok(cmd_path) = env::var("RAT2E_AGENT_CMD") {
+        let args = env::var("RAT2E_AGENT_ARGS").unwrap_or_default();
+        let args_vec: Vec<String> = args.split_whitespace().map(|s| s.to_string()).collect();
+        info!(
+            "🔧 LOCAL DEV: Using ACP agent from env: {} {}",
+            cmd_path,
+            args_vec.join(" ")
+        );
+        return Some(AgentCommand::new(cmd_path.into()).with_args(args_vec));
+    }
+    let installer = match AgentInstaller::new() {
+        Ok(installer) => installer,
+        Err(e) => {
+            warn!("🔧 LOCAL DEV: AgentInstaller init failed: {}", e);
+            return None;
+        }
+    };
+    match installer.get_or_install_claude_code().await {
+        Ok(cmd) => {
             info!(
-                "🔧 LOCAL DEV: Using ACP agent from env: {} {}",
-                cmd_path,
-                args_vec.join(" ")
+                "🔧 LOCAL DEV: Auto-resolved Claude Code ACP at {}",
+                cmd.path.display()
             );
-            Some(AgentCommand::new(cmd_path.into()).with_args(args_vec))
+            Some(cmd)
         }
-        Err(_) => match AgentInstaller::new() {
-            Ok(installer) => match installer.get_or_install_claude_code().await {
+        Err(e1) => {
+            warn!("🔧 LOCAL DEV: Claude Code not available: {}", e1);
+            match installer.get_or_install_gemini().await {
                 Ok(cmd) => {
                     info!(
-                        "🔧 LOCAL DEV: Auto-resolved Claude Code ACP at {}",
+                        "🔧 LOCAL DEV: Auto-resolved Gemini CLI at {}",
                         cmd.path.display()
                     );
                     Some(cmd)
                 }
-                Err(e1) => {
-                    warn!("🔧 LOCAL DEV: Claude Code not available: {}", e1);
-                    match installer.get_or_install_gemini().await {
-                        Ok(cmd) => {
-                            info!(
-                                "🔧 LOCAL DEV: Auto-resolved Gemini CLI at {}",
-                                cmd.path.display()
-                            );
-                            Some(cmd)
-                        }
-                        Err(e2) => {
-                            warn!(
-                                "🔧 LOCAL DEV: No ACP agent resolved automatically: {}; {}",
-                                e1, e2
-                            );
-                            None
-                        }
-                    }
+                Err(e2) => {
+                    warn!(
+                        "🔧 LOCAL DEV: No ACP agent resolved automatically: {}; {}",
+                        e1, e2
+                    );
+                    None
                 }
-            },
-            Err(e) => {
-                warn!("🔧 LOCAL DEV: AgentInstaller init failed: {}", e);
-                None
             }
-        },
+        }
+    }
+}
+
+/// Run the bridge on `listener` until `shutdown` resolves, then stop every
+/// agent it started. `rat serve` passes a socket from systemd here.
+pub async fn serve(
+    listener: TcpListener,
+    mut options: ServeOptions,
+    shutdown: impl Future<Output = ()>,
+) -> Result<()> {
+    let agent = options.agent.take();
+    let pool_options = options.pool;
+    let port = listener.local_addr()?.port();
+    // Let other machines on the LAN find this bridge; withdrawn when we return
+    let _advertisement = if options.advertise {
+        crate::discovery::advertise(port)
+            .map_err(|e| warn!("🔧 LOCAL DEV: mDNS advertisement failed: {}", e))
+            .ok()
+    } else {
+        None
     };
+    if options.token.is_some() {
+        info!("🔧 LOCAL DEV: WebSocket clients must present the bridge token");
+    } else {
+        info!("🔧 LOCAL DEV: WARNING - No encryption, no authentication! Local development only!");
+    }
+    match &options.web_root {
+        Some(root) => info!("🔧 LOCAL DEV: Serving {} at http://localhost:{}/", root.display(), port),
+        None => info!("🔧 LOCAL DEV: Web client at http://localhost:{}/", port),
+    }
+
+    let pool = Arc::new(AgentPool::new(agent, pool_options));
+    if pool.has_agent() && pool_options.size > 0 {
+        info!(
+            "🔧 LOCAL DEV: Keeping {} warm agent(s), idle TTL {:?}",
+            pool_options.size, pool_options.idle_ttl
+        );
+        pool.refill();
+        let reaper = pool.clone();
+        tokio::spawn(async move {
+            let period = (pool_options.idle_ttl / 4).max(Duration::from_secs(1));
+            let mut tick = tokio::time::interval(period);
+            loop {
+                tick.tick().await;
+                reaper.reap().await;
+            }
+        });
+    }
 
-    while let Ok((stream, peer_addr)) = listener.accept().await {
+    let sessions = SharedSessions::default();
+    let workspace = std::fs::canonicalize(env::current_dir()?)?;
+    let api = Arc::new(ApiSessions::new(options.token.clone(), workspace));
+    let options = Arc::new(options);
+    tokio::pin!(shutdown);
+    let mut connections = tokio::task::JoinSet::new();
+    loop {
+        let (stream, peer_addr) = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    warn!("🔧 LOCAL DEV: accept failed: {}", e);
+                    break;
+                }
+            },
+            _ = &mut shutdown => {
+                info!("🔧 LOCAL DEV: Shutting down");
+                break;
+            }
+            Some(_) = connections.join_next(), if !connections.is_empty() => continue,
+        };
         info!("🔧 LOCAL DEV: New connection from {}", peer_addr);
-        let agent_clone = resolved_agent.clone();
-        tokio::spawn(handle_local_connection(stream, peer_addr, agent_clone));
+        connections.spawn(handle_local_connection(
+            stream,
+            peer_addr,
+            pool.clone(),
+            sessions.clone(),
+            api.clone(),
+            options.clone(),
+        ));
     }
 
+    // Cancel running turns and close every client's connection, then give
+    // them a moment to go before the agents are stopped
+    pool.stop_serving().await;
+    let drained = tokio::time::timeout(SHUTDOWN_GRACE, async {
+        while connections.join_next().await.is_some() {}
+    });
+    if drained.await.is_err() {
+        warn!("🔧 LOCAL DEV: {} connection(s) still open at shutdown", connections.len());
+    }
+    pool.shutdown().await;
     Ok(())
 }
 
+/// How long a shutdown waits for clients to close their connections
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(3);
+
 async fn handle_local_connection(
     stream: TcpStream,
     peer_addr: SocketAddr,
-    resolved_agent: Option<AgentCommand>,
+    pool: Arc<AgentPool>,
+    sessions: SharedSessions,
+    api: Arc<ApiSessions>,
+    options: Arc<ServeOptions>,
 ) -> Result<()> {
+    // Plain HTTP requests on the same port get the REST API or the browser client
+    if !crate::local_web::is_websocket_upgrade(&stream).await? {
+        let mut stream = stream;
+        let request = HttpRequest::read(&mut stream).await?;
+        if request.path.starts_with(http_api::PREFIX) {
+            return http_api::handle(stream, request, &pool, &api).await;
+        }
+        if request.path == "/metrics" && crate::metrics::enabled() {
+            if let Some(token) = &options.token {
+                if !crate::local_web::has_token(token, request.header("authorization"), None) {
+                    warn!("🔧 LOCAL DEV: Refused metrics to {}: missing or wrong bridge token", peer_addr);
+                    return crate::metrics::refuse(stream).await;
+                }
+            }
+            return crate::metrics::serve(stream).await;
+        }
+        return crate::local_web::serve(stream, &request, options.web_root.as_deref()).await;
+    }
+    // Clients connecting with the same `?session=<name>` share one agent;
+    // `&role=observer` joins it read-only
+    let mut session = None;
+    let mut observer = false;
+    let mut compress = false;
+    let mut handshake = serde_json::Value::Null;
     // Accept WS and echo subprotocol if client asks for acp.jsonrpc.v1 (browser correctness)
     let ws_stream = accept_hdr_async(stream, |req: &Request, mut resp: Response| {
+        session = session_name(req.uri().query());
+        observer = is_observer(req.uri().query());
+        compress = wants_compression(req.uri().query());
+        let header = |name: &str| req.headers().get(name).and_then(|v| v.to_str().ok()).map(str::to_string);
+        if let Some(token) = &options.token {
+            if !crate::local_web::has_token(token, header("Authorization").as_deref(), req.uri().query()) {
+                warn!("🔧 LOCAL DEV: Refused {}: missing or wrong bridge token", peer_addr);
+                let mut refusal = ErrorResponse::new(Some("Unauthorized".to_string()));
+                *refusal.status_mut() = StatusCode::UNAUTHORIZED;
+                return Err(refusal);
+            }
+        }
+        // Keep the token out of the bridge log
+        let uri = match req.uri().query() {
+            Some(query) if query.contains("token=") => req.uri().path().to_string(),
+            _ => req.uri().to_string(),
+        };
+        handshake = serde_json::json!({
+            "peer": peer_addr.to_string(),
+            "uri": uri,
+            "protocols": header("Sec-WebSocket-Protocol"),
+            "extensions": header("Sec-WebSocket-Extensions"),
+            "origin": header("Origin"),
+            "userAgent": header("User-Agent"),
+        });
         // Look for Sec-WebSocket-Protocol and echo acp.jsonrpc.v1 if requested
         if let Some(values) = req.headers().get("Sec-WebSocket-Protocol") {
             if let Ok(hv) = values.to_str() {
@@ -121,10 +337,27 @@ async fn handle_local_connection(
     info!("🔧 LOCAL DEV: WebSocket connection established with {}", peer_addr);
 
     let (mut ws_write, mut ws_read) = ws_stream.split();
+    let mut connection = options.connection;
+    if !compress {
+        connection.compress_threshold = None;
+    }
 
     // If an ACP agent was resolved (env or auto), run the bridge using direct (unencrypted) transport
-    if resolved_agent.is_some() || std::env::var("RAT2E_AGENT_CMD").is_ok() {
-        run_acp_bridge_local(ws_write, ws_read, resolved_agent).await?;
+    if pool.has_agent() {
+        let log = BridgeLog::open().map(Arc::new);
+        if let Some(log) = &log {
+            handshake["session"] = serde_json::json!(session);
+            handshake["observer"] = serde_json::json!(observer);
+            handshake["compress"] = serde_json::json!(compress);
+            log.record("handshake", handshake);
+            info!("🔧 LOCAL DEV: logging connection from {} as bridge connection {}", peer_addr, log.connection());
+        }
+        // Traced as one span per connection when telemetry is exported
+        let span = info_span!("connection", peer = %peer_addr, session = session.as_deref(), observer);
+        let hello = ClientHello { session, observer, log };
+        run_acp_bridge_local(ws_write, ws_read, pool, hello, sessions, connection)
+            .instrument(span)
+            .await?;
         info!("🔧 LOCAL DEV: ACP bridge session ended for {}", peer_addr);
         return Ok(());
     }
@@ -166,61 +399,1252 @@ async fn handle_local_connection(
                     } else {
                         ws_write.send(Message::Binary(data)).await?;
                     }
-                } else {
-                    ws_write.send(Message::Binary(data)).await?;
+                } else {
+                    ws_write.send(Message::Binary(data)).await?;
+                }
+            }
+            Message::Close(_) => {
+                info!("🔧 LOCAL DEV: Connection closed by {}", peer_addr);
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Agents started for named sessions, so later clients can join them
+type SharedSessions = Arc<Mutex<HashMap<String, Arc<SharedAgent>>>>;
+
+/// The `session` query parameter of a connection URL, e.g. `ws://localhost:8081/?session=work`
+fn session_name(query: Option<&str>) -> Option<String> {
+    query?
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("session="))
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+}
+
+/// Whether a connection URL asks for a read-only observer, e.g. `?session=work&role=observer`
+fn is_observer(query: Option<&str>) -> bool {
+    query.is_some_and(|query| query.split('&').any(|pair| pair == "role=observer"))
+}
+
+/// Whether a client takes gzipped binary frames for large messages, e.g. `?compress=gzip`
+fn wants_compression(query: Option<&str>) -> bool {
+    query.is_some_and(|query| query.split('&').any(|pair| pair == "compress=gzip"))
+}
+
+/// JSON-RPC error code for requests an observer isn't allowed to make
+const READ_ONLY_ERROR: i64 = -32003;
+
+/// One agent process and the WebSocket clients attached to it
+pub(crate) struct SharedAgent {
+    child: Mutex<Child>,
+    stdin: Arc<Mutex<ChildStdin>>,
+    clients: Arc<Mutex<ClientSet>>,
+    router: Arc<Mutex<RequestRouter>>,
+    // Permission prompts awaiting a browser decision
+    pending_perms: Arc<Mutex<HashMap<String, oneshot::Sender<bool>>>>,
+    grants: Arc<Mutex<PermissionGrants>>,
+    // Running terminal/execute children by request id so the browser can signal them
+    running_terms: Arc<Mutex<HashMap<String, mpsc::UnboundedSender<ProcessSignal>>>>,
+}
+
+impl SharedAgent {
+    /// Pass a client's message on to the agent, or answer it directly when
+    /// the router says so
+    pub(crate) async fn forward(
+        &self,
+        client: u64,
+        frame: String,
+        reply: &mpsc::UnboundedSender<Message>,
+    ) -> std::io::Result<()> {
+        let Ok(msg) = serde_json::from_str::<serde_json::Value>(&frame) else {
+            // Not JSON-RPC; pass it through untouched
+            return write_frame(&self.stdin, frame.as_bytes()).await;
+        };
+        // What the other clients are told this client did, if it gets through
+        let done = match msg.get("method").and_then(|m| m.as_str()) {
+            Some("session/prompt") => Some(("prompt", serde_json::json!({"prompt": msg["params"]["prompt"]}))),
+            Some("session/cancel") => Some(("cancel", serde_json::json!({}))),
+            None if msg.get("id").is_some() => Some((
+                "permission",
+                serde_json::json!({"requestId": msg["id"], "outcome": msg["result"]["outcome"]}),
+            )),
+            _ => None,
+        };
+        let route = self.router.lock().await.route_client(client, msg);
+        match route {
+            ClientRoute::Agent(msg) => {
+                write_frame(&self.stdin, msg.to_string().as_bytes()).await?;
+                if let Some((kind, params)) = done {
+                    if kind == "permission" && !params["outcome"].is_null() {
+                        crate::metrics::permission(crate::metrics::permission_outcome(&params["outcome"]));
+                    }
+                    let _ = self.clients.lock().await.send(activity(client, kind, params)).await;
+                }
+                Ok(())
+            }
+            ClientRoute::Reply(msg) => {
+                let _ = reply.send(Message::Text(msg.to_string()));
+                Ok(())
+            }
+            ClientRoute::Queued => {
+                if let Some((kind, mut params)) = done {
+                    params["queued"] = serde_json::json!(true);
+                    let _ = self.clients.lock().await.send(activity(client, kind, params)).await;
+                }
+                Ok(())
+            }
+            ClientRoute::Drop => Ok(()),
+        }
+    }
+
+    /// Attach a client, telling everyone already here
+    pub(crate) async fn join(
+        &self,
+        observer: bool,
+    ) -> (u64, mpsc::UnboundedSender<Message>, mpsc::UnboundedReceiver<Message>) {
+        let mut clients = self.clients.lock().await;
+        let attached = clients.attach(observer);
+        clients.announce_presence();
+        attached
+    }
+
+    /// Detach a client, returning how many are still attached
+    pub(crate) async fn leave(&self, client: u64) -> usize {
+        self.router.lock().await.detach(client);
+        self.grants.lock().await.revoke(client);
+        let mut clients = self.clients.lock().await;
+        let remaining = clients.detach(client);
+        clients.announce_presence();
+        remaining
+    }
+
+    /// Settle one of the bridge's own permission prompts (file writes,
+    /// terminal commands, transfers) if `v` answers one; returns whether it did
+    pub(crate) async fn resolve_bridge_permission(&self, client: u64, v: &serde_json::Value) -> bool {
+        if v.get("method").is_some() || v.get("id").is_none() {
+            return false;
+        }
+        let id_str = id_key(&v["id"]).unwrap_or_default();
+        let Some(tx) = self.pending_perms.lock().await.remove(&id_str) else {
+            return false;
+        };
+        warn!("🔧 LOCAL DEV: Intercepted permission response for id {}", id_str);
+        let mut allowed = false;
+        let mut always = false;
+        if let Some(res) = v.get("result") {
+            if res.get("outcome").and_then(|o| o.get("cancelled")).and_then(|b| b.as_bool()) == Some(true) {
+                allowed = false;
+            } else if let Some(outcome) = res.get("outcome") {
+                if let Some(outcome_type) = outcome.get("outcome").and_then(|o| o.as_str()) {
+                    if outcome_type == "selected" {
+                        let opt = outcome.get("optionId").and_then(|s| s.as_str()).unwrap_or("").to_lowercase();
+                        warn!("🔧 LOCAL DEV: Parsed optionId: {}", opt);
+                        // Accept common allow variants (ACP may use allow/allow_once/allow_always)
+                        allowed = opt == "allow"
+                            || opt.starts_with("allow")
+                            || opt == "yes"
+                            || opt == "ok"
+                            || opt == "approve";
+                        always = opt == "allow_always";
+                        if !allowed {
+                            warn!("🔧 LOCAL DEV: permission response optionId='{}' not recognized as allow; treating as deny", opt);
+                        }
+                    }
+                }
+            }
+        }
+        warn!("🔧 LOCAL DEV: Sending allowed={} for id {}", allowed, id_str);
+        let _ = tx.send(allowed);
+        self.grants.lock().await.settle(&id_str, always.then_some(client));
+        crate::metrics::permission(if allowed { "allowed" } else { "denied" });
+        let decided = activity(client, "permission", serde_json::json!({"requestId": v["id"], "allowed": allowed}));
+        let mut clients = self.clients.lock().await;
+        clients.record(
+            "permission_answered",
+            serde_json::json!({"requestId": v["id"], "clientId": client, "allowed": allowed, "always": always}),
+        );
+        let _ = clients.send(decided).await;
+        true
+    }
+
+    /// Handle a message from a read-only observer; nothing it sends reaches the agent
+    async fn observe(&self, client: u64, frame: &str, reply: &mpsc::UnboundedSender<Message>) {
+        let Ok(msg) = serde_json::from_str::<serde_json::Value>(frame) else {
+            return;
+        };
+        let route = self.router.lock().await.route_observer(client, &msg);
+        if let ClientRoute::Reply(msg) = route {
+            let _ = reply.send(Message::Text(msg.to_string()));
+        }
+    }
+
+    /// Run `initialize` before any client connects; the router replays the
+    /// result to the first one
+    async fn warm_up(&self) -> Result<()> {
+        let (client, reply, mut outgoing) = self.clients.lock().await.attach(false);
+        let init = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 0,
+            "method": "initialize",
+            "params": {
+                "protocolVersion": 1,
+                "clientCapabilities": {"fs": {"readTextFile": true, "writeTextFile": true}, "terminal": false}
+            }
+        });
+        let outcome = tokio::time::timeout(WARM_UP_TIMEOUT, async {
+            self.forward(client, init.to_string(), &reply).await?;
+            while let Some(msg) = outgoing.recv().await {
+                let Message::Text(text) = msg else { continue };
+                let Ok(v) = serde_json::from_str::<serde_json::Value>(&text) else { continue };
+                if v.get("method").is_none() && v["id"] == 0 {
+                    return match v.get("error") {
+                        Some(error) => Err(anyhow::anyhow!("initialize failed: {}", error)),
+                        None => Ok(()),
+                    };
+                }
+            }
+            Err(anyhow::anyhow!("agent closed before initializing"))
+        })
+        .await
+        .unwrap_or_else(|_| Err(anyhow::anyhow!("agent did not initialize within {:?}", WARM_UP_TIMEOUT)));
+        self.clients.lock().await.detach(client);
+        self.router.lock().await.detach(client);
+        outcome
+    }
+
+    async fn is_running(&self) -> bool {
+        matches!(self.child.lock().await.try_wait(), Ok(None))
+    }
+
+    /// Cancel the running turn, if any, and close every client's connection
+    async fn stop_serving(&self) {
+        let session = self.router.lock().await.prompt_session.clone();
+        if let Some(session) = session {
+            let cancel = serde_json::json!({"jsonrpc": "2.0", "method": "session/cancel", "params": {"sessionId": session}});
+            let _ = write_frame(&self.stdin, cancel.to_string().as_bytes()).await;
+        }
+        self.clients.lock().await.close_all("bridge shutting down");
+    }
+
+    /// Kill the agent process and reap it
+    async fn shutdown(&self) {
+        if let Err(e) = self.child.lock().await.kill().await {
+            warn!("🔧 LOCAL DEV: failed to stop agent: {}", e);
+        }
+    }
+}
+
+/// Give up on a warm agent that hasn't answered `initialize` by then
+const WARM_UP_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Initialized agents waiting for connections, so a client doesn't wait for
+/// a node-based agent to boot. An agent whose clients all left returns here
+/// and starts a fresh session for the next one.
+pub(crate) struct AgentPool {
+    resolved_agent: Option<AgentCommand>,
+    options: PoolOptions,
+    idle: Mutex<Vec<(Instant, Arc<SharedAgent>)>>,
+    /// Every agent started, pooled or in use, so they can all be stopped on shutdown
+    started: std::sync::Mutex<Vec<Weak<SharedAgent>>>,
+}
+
+impl AgentPool {
+    fn new(resolved_agent: Option<AgentCommand>, options: PoolOptions) -> Self {
+        Self {
+            resolved_agent,
+            options,
+            idle: Mutex::new(Vec::new()),
+            started: std::sync::Mutex::new(Vec::new()),
+        }
+    }
+
+    fn start(&self) -> Result<Arc<SharedAgent>> {
+        let agent = start_agent(self.resolved_agent.clone())?;
+        let mut started = self.started.lock().unwrap();
+        started.retain(|agent| agent.strong_count() > 0);
+        started.push(Arc::downgrade(&agent));
+        Ok(agent)
+    }
+
+    /// Wind down every agent in use ahead of [`Self::shutdown`]
+    async fn stop_serving(&self) {
+        let started: Vec<_> = self.started.lock().unwrap().iter().filter_map(Weak::upgrade).collect();
+        for agent in started {
+            agent.stop_serving().await;
+        }
+    }
+
+    /// Stop every agent still running, in use or not
+    async fn shutdown(&self) {
+        self.idle.lock().await.clear();
+        let started = std::mem::take(&mut *self.started.lock().unwrap());
+        for agent in started.iter().filter_map(Weak::upgrade) {
+            agent.shutdown().await;
+        }
+    }
+
+    /// Whether there is an agent to run at all
+    fn has_agent(&self) -> bool {
+        self.resolved_agent.is_some()
+    }
+
+    /// A pooled agent if one is ready, else a freshly started one
+    pub(crate) async fn take(self: &Arc<Self>) -> Result<Arc<SharedAgent>> {
+        let pooled = loop {
+            let next = self.idle.lock().await.pop();
+            match next {
+                Some((_, agent)) if agent.is_running().await => break Some(agent),
+                Some(_) => {
+                    crate::metrics::agents_restarted(1);
+                    continue;
+                }
+                None => break None,
+            }
+        };
+        let agent = match pooled {
+            Some(agent) => {
+                info!("🔧 LOCAL DEV: Using a warm agent from the pool");
+                agent
+            }
+            None => self.start()?,
+        };
+        self.refill();
+        Ok(agent)
+    }
+
+    /// Top the pool up to its size in the background
+    fn refill(self: &Arc<Self>) {
+        if self.options.size == 0 {
+            return;
+        }
+        let pool = self.clone();
+        tokio::spawn(async move {
+            while pool.idle.lock().await.len() < pool.options.size {
+                let agent = match pool.start() {
+                    Ok(agent) => agent,
+                    Err(e) => {
+                        warn!("🔧 LOCAL DEV: failed to start a pooled agent: {}", e);
+                        break;
+                    }
+                };
+                if let Err(e) = agent.warm_up().await {
+                    warn!("🔧 LOCAL DEV: pooled agent failed to warm up: {}", e);
+                    agent.shutdown().await;
+                    break;
+                }
+                pool.park(agent).await;
+            }
+        });
+    }
+
+    /// Take back an agent whose last client left
+    pub(crate) async fn recycle(&self, agent: Arc<SharedAgent>) {
+        if self.options.size == 0 || !agent.is_running().await {
+            agent.shutdown().await;
+            return;
+        }
+        agent.router.lock().await.reset();
+        self.park(agent).await;
+    }
+
+    async fn park(&self, agent: Arc<SharedAgent>) {
+        let mut idle = self.idle.lock().await;
+        if idle.len() < self.options.size {
+            idle.push((Instant::now(), agent));
+        } else {
+            drop(idle);
+            agent.shutdown().await;
+        }
+    }
+
+    /// Stop agents that sat unused for longer than the idle TTL, and replace
+    /// any that exited on their own
+    async fn reap(self: &Arc<Self>) {
+        let mut expired = Vec::new();
+        let mut exited = 0;
+        {
+            let mut idle = self.idle.lock().await;
+            let mut fresh = Vec::new();
+            for (since, agent) in idle.drain(..) {
+                if !agent.is_running().await {
+                    exited += 1;
+                } else if since.elapsed() >= self.options.idle_ttl {
+                    expired.push(agent);
+                } else {
+                    fresh.push((since, agent));
+                }
+            }
+            *idle = fresh;
+        }
+        for agent in expired {
+            info!("🔧 LOCAL DEV: Stopping an idle pooled agent");
+            agent.shutdown().await;
+        }
+        if exited > 0 {
+            warn!("🔧 LOCAL DEV: {} pooled agent(s) exited; starting replacements", exited);
+            crate::metrics::agents_restarted(exited);
+            self.refill();
+        }
+    }
+}
+
+/// Tool calls forgotten if the agent never reports them finished
+const TOOL_CALL_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
+/// When each of an agent's running tool calls started, so the time it took
+/// can be reported once it completes or fails
+#[derive(Default)]
+struct ToolCallTimes {
+    running: HashMap<String, (Instant, String)>,
+}
+
+impl ToolCallTimes {
+    /// Follow one `session/update`; returns the kind, final status and
+    /// duration of a tool call it finished
+    fn observe(&mut self, update: &serde_json::Value) -> Option<(String, &'static str, Duration)> {
+        let id = update["toolCallId"].as_str()?;
+        let status = match update["status"].as_str() {
+            Some("completed") => Some("completed"),
+            Some("failed") => Some("failed"),
+            _ => None,
+        };
+        if update["sessionUpdate"] == "tool_call" && status.is_none() {
+            self.running.retain(|_, (started, _)| started.elapsed() < TOOL_CALL_TIMEOUT);
+            let kind = update["kind"].as_str().unwrap_or("other").to_string();
+            self.running.insert(id.to_string(), (Instant::now(), kind));
+            return None;
+        }
+        let status = status?;
+        let (started, kind) = self.running.remove(id)?;
+        Some((kind, status, started.elapsed()))
+    }
+}
+
+/// Outgoing queues of the clients attached to one agent
+#[derive(Default)]
+struct ClientSet {
+    next_id: u64,
+    clients: HashMap<u64, mpsc::UnboundedSender<Message>>,
+    /// Clients that joined with `role=observer`
+    observers: HashSet<u64>,
+    /// Bridge event logs of the clients that have one, see `bridge_log`
+    logs: HashMap<u64, Arc<BridgeLog>>,
+}
+
+impl ClientSet {
+    fn attach(&mut self, observer: bool) -> (u64, mpsc::UnboundedSender<Message>, mpsc::UnboundedReceiver<Message>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.next_id += 1;
+        self.clients.insert(self.next_id, tx.clone());
+        if observer {
+            self.observers.insert(self.next_id);
+        }
+        (self.next_id, tx, rx)
+    }
+
+    /// Remove a client, returning how many are still attached
+    fn detach(&mut self, client: u64) -> usize {
+        self.clients.remove(&client);
+        self.observers.remove(&client);
+        self.logs.remove(&client);
+        self.clients.len()
+    }
+
+    /// The `rat/presence` notification for `client`: everyone attached, and
+    /// which of them is the receiver
+    fn presence(&self, client: u64) -> Message {
+        let mut ids: Vec<u64> = self.clients.keys().copied().collect();
+        ids.sort_unstable();
+        let clients: Vec<serde_json::Value> = ids
+            .into_iter()
+            .map(|id| {
+                let role = if self.observers.contains(&id) { "observer" } else { "participant" };
+                serde_json::json!({"clientId": id, "role": role})
+            })
+            .collect();
+        let msg = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "rat/presence",
+            "params": {"clientId": client, "clients": clients}
+        });
+        Message::Text(msg.to_string())
+    }
+
+    /// Tell every client who is attached, after someone joins or leaves
+    fn announce_presence(&self) {
+        for (id, tx) in &self.clients {
+            let _ = tx.send(self.presence(*id));
+        }
+    }
+
+    fn send_to(&self, client: u64, msg: Message) {
+        if let Some(tx) = self.clients.get(&client) {
+            let _ = tx.send(msg);
+        }
+    }
+
+    /// Broadcast to every attached client. A client that went away doesn't
+    /// stop delivery to the others, so this never fails.
+    async fn send(&mut self, msg: Message) -> Result<()> {
+        for tx in self.clients.values() {
+            let _ = tx.send(msg.clone());
+        }
+        Ok(())
+    }
+
+    /// Record an event that concerns the agent rather than one connection in
+    /// every attached client's bridge log
+    fn record(&self, event: &str, fields: serde_json::Value) {
+        for log in self.logs.values() {
+            log.record(event, fields.clone());
+        }
+    }
+
+    /// Send every client a close frame; each detaches once its connection ends
+    fn close_all(&self, reason: &str) {
+        for tx in self.clients.values() {
+            let frame = CloseFrame { code: CloseCode::Away, reason: reason.to_string().into() };
+            let _ = tx.send(Message::Close(Some(frame)));
+        }
+    }
+}
+
+/// `rat/activity` notification attributing a prompt, cancel or permission
+/// decision to the client that made it; `params` is an object of details
+fn activity(client: u64, kind: &str, mut params: serde_json::Value) -> Message {
+    params["clientId"] = serde_json::json!(client);
+    params["kind"] = serde_json::json!(kind);
+    let msg = serde_json::json!({"jsonrpc": "2.0", "method": "rat/activity", "params": params});
+    Message::Text(msg.to_string())
+}
+
+/// Ask the clients to allow one of the bridge's own operations, unless a
+/// client already chose "always allow" for it. The answer arrives on the
+/// returned channel either way.
+async fn ask_bridge_permission(
+    clients: &Mutex<ClientSet>,
+    pending: &Mutex<HashMap<String, oneshot::Sender<bool>>>,
+    grants: &Mutex<PermissionGrants>,
+    id: &str,
+    tool: &str,
+    subject: &str,
+    reason: String,
+) -> oneshot::Receiver<bool> {
+    let (tx, rx) = oneshot::channel::<bool>();
+    let covering = grants.lock().await.covering(tool, subject);
+    if let Some(grantor) = covering {
+        // Still logged and announced, just not asked
+        info!("🔧 LOCAL DEV: {} {} allowed by client {}'s standing grant", tool, subject, grantor);
+        let _ = tx.send(true);
+        crate::metrics::permission("allowed");
+        let params = serde_json::json!({"requestId": id, "allowed": true, "remembered": true, "tool": tool, "subject": subject});
+        let mut clients = clients.lock().await;
+        clients.record("permission_remembered", params.clone());
+        let _ = clients.send(activity(grantor, "permission", params)).await;
+        return rx;
+    }
+    pending.lock().await.insert(id.to_string(), tx);
+    grants.lock().await.ask(id, tool, subject);
+    clients.lock().await.record("permission_asked", serde_json::json!({"requestId": id, "tool": tool, "subject": subject}));
+    let perm_req = serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "method": "session/request_permission",
+        "params": {
+            "tool": tool,
+            "reason": reason,
+            "options": [
+                {"id": "allow", "label": "Allow"},
+                {"id": "allow_always", "label": "Always allow"},
+                {"id": "deny", "label": "Deny"}
+            ]
+        }
+    });
+    if let Err(e) = clients.lock().await.send(Message::Text(perm_req.to_string())).await {
+        warn!("🔧 LOCAL DEV: ws send perm req error: {}", e);
+    }
+    rx
+}
+
+/// How long an "always allow" answer to a bridge permission prompt lasts
+const PERMISSION_GRANT_TTL: Duration = Duration::from_secs(30 * 60);
+
+/// Tools whose grants cover everything under the granted path; other grants
+/// (commands, renames) cover only the exact same request
+const PATH_TOOLS: [&str; 3] = ["write_text_file", "mkdir", "delete_file"];
+
+/// A client's "always allow" for one tool on one path or command
+#[derive(Debug)]
+struct PermissionGrant {
+    client: u64,
+    tool: String,
+    subject: String,
+    expires: Instant,
+}
+
+impl PermissionGrant {
+    fn covers(&self, tool: &str, subject: &str) -> bool {
+        if self.tool != tool {
+            return false;
+        }
+        if PATH_TOOLS.contains(&tool) {
+            // `..` could climb back out of the granted directory, so paths
+            // with it always ask
+            let path = Path::new(subject);
+            !path.components().any(|part| part == Component::ParentDir)
+                && path.starts_with(&self.subject)
+        } else {
+            subject == self.subject
+        }
+    }
+}
+
+/// "Always allow" decisions on the bridge's own permission prompts, so
+/// repeated writes to the same file don't ask again. Grants expire after
+/// [`PERMISSION_GRANT_TTL`] and go away with the client that made them.
+#[derive(Debug, Default)]
+struct PermissionGrants {
+    /// Tool and subject of each prompt still waiting for an answer
+    asked: HashMap<String, (String, String)>,
+    grants: Vec<PermissionGrant>,
+}
+
+impl PermissionGrants {
+    /// The client whose grant allows `tool` on `subject`, if any
+    fn covering(&mut self, tool: &str, subject: &str) -> Option<u64> {
+        let now = Instant::now();
+        self.grants.retain(|grant| grant.expires > now);
+        self.grants
+            .iter()
+            .find(|grant| grant.covers(tool, subject))
+            .map(|grant| grant.client)
+    }
+
+    fn ask(&mut self, id: &str, tool: &str, subject: &str) {
+        self.asked.insert(id.to_string(), (tool.to_string(), subject.to_string()));
+    }
+
+    /// A prompt was answered; `always` is the client that chose "always allow"
+    fn settle(&mut self, id: &str, always: Option<u64>) {
+        let Some((tool, subject)) = self.asked.remove(id) else {
+            return;
+        };
+        if let Some(client) = always {
+            self.grants.push(PermissionGrant {
+                client,
+                tool,
+                subject,
+                expires: Instant::now() + PERMISSION_GRANT_TTL,
+            });
+        }
+    }
+
+    fn revoke(&mut self, client: u64) {
+        self.grants.retain(|grant| grant.client != client);
+    }
+}
+
+/// Where a client message goes
+#[derive(Debug, PartialEq)]
+enum ClientRoute {
+    /// Write to the agent
+    Agent(serde_json::Value),
+    /// Answer the client without involving the agent
+    Reply(serde_json::Value),
+    /// Held until the running prompt finishes
+    Queued,
+    /// Not for the agent, e.g. a second answer to the same agent request
+    Drop,
+}
+
+/// Where an agent message goes
+#[derive(Debug, PartialEq)]
+enum AgentRoute {
+    /// Unchanged to every client
+    All,
+    /// A reply to one client's request, with its own id restored
+    Client(u64, serde_json::Value),
+    /// A reply to no request in flight, e.g. one the agent sent twice
+    Drop,
+}
+
+/// Methods whose result every client of an agent shares
+const SHARED_SETUP: [&str; 2] = ["initialize", "session/new"];
+
+/// Result of a setup call and the clients that have already received it
+#[derive(Debug)]
+struct SharedSetup {
+    clients: HashSet<u64>,
+    result: serde_json::Value,
+}
+
+/// A client request awaiting the agent's reply
+#[derive(Debug)]
+struct InFlight {
+    client: u64,
+    id: serde_json::Value,
+    method: String,
+}
+
+/// JSON-RPC routing between one agent and the clients sharing it. Client
+/// request ids are rewritten so replies reach only the sender, a client
+/// joining late is handed the existing `initialize`/`session/new` results
+/// so it attaches to the running session, and prompts run one at a time.
+#[derive(Debug, Default)]
+struct RequestRouter {
+    next_id: u64,
+    /// Client requests by the id the agent saw
+    in_flight: HashMap<String, InFlight>,
+    /// Agent requests still waiting for a client's answer
+    agent_requests: HashSet<String>,
+    setup: HashMap<String, SharedSetup>,
+    queued_prompts: VecDeque<(u64, serde_json::Value)>,
+    prompt_running: bool,
+    /// When the running prompt reached the agent
+    prompt_started: Option<Instant>,
+    /// Session of the running prompt, to cancel it on shutdown
+    prompt_session: Option<String>,
+}
+
+impl RequestRouter {
+    fn route_client(&mut self, client: u64, mut msg: serde_json::Value) -> ClientRoute {
+        let Some(id) = msg.get("id").cloned() else {
+            // Notifications such as session/cancel go straight through
+            return ClientRoute::Agent(msg);
+        };
+        let Some(method) = msg.get("method").and_then(|m| m.as_str()).map(str::to_string) else {
+            // An answer to an agent request; only the first one counts
+            return match id_key(&id) {
+                Some(key) if self.agent_requests.remove(&key) => ClientRoute::Agent(msg),
+                _ => ClientRoute::Drop,
+            };
+        };
+        if let Some(setup) = self.setup.get_mut(&method) {
+            // A client repeating a setup call it already got (e.g. a new chat) reaches the agent
+            if setup.clients.insert(client) {
+                return ClientRoute::Reply(
+                    serde_json::json!({"jsonrpc": "2.0", "id": id, "result": setup.result}),
+                );
+            }
+        }
+        self.next_id += 1;
+        msg["id"] = serde_json::json!(self.next_id);
+        self.in_flight
+            .insert(self.next_id.to_string(), InFlight { client, id, method: method.clone() });
+        if method == "session/prompt" {
+            if self.prompt_running {
+                self.queued_prompts.push_back((client, msg));
+                return ClientRoute::Queued;
+            }
+            self.start_prompt(&msg);
+        }
+        ClientRoute::Agent(msg)
+    }
+
+    /// An observer may fetch the shared setup results so it can follow the
+    /// session; any other request is refused, and its notifications and
+    /// answers to agent requests (permission decisions) are dropped
+    fn route_observer(&mut self, client: u64, msg: &serde_json::Value) -> ClientRoute {
+        let (Some(id), Some(method)) = (msg.get("id"), msg.get("method").and_then(|m| m.as_str())) else {
+            return ClientRoute::Drop;
+        };
+        if let Some(setup) = self.setup.get_mut(method) {
+            setup.clients.insert(client);
+            return ClientRoute::Reply(serde_json::json!({"jsonrpc": "2.0", "id": id, "result": setup.result}));
+        }
+        ClientRoute::Reply(serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": {"code": READ_ONLY_ERROR, "message": format!("{} is not allowed on a read-only observer connection", method)}
+        }))
+    }
+
+    fn route_agent(&mut self, msg: &serde_json::Value) -> AgentRoute {
+        let Some(key) = msg.get("id").and_then(id_key) else {
+            return AgentRoute::All;
+        };
+        if msg.get("method").is_some() {
+            // A request for the clients; remember it so only one answer goes back
+            self.agent_requests.insert(key);
+            return AgentRoute::All;
+        }
+        let Some(request) = self.in_flight.remove(&key) else {
+            // Client ids are rewritten, so passing it on could complete some
+            // other request of the same number
+            return AgentRoute::Drop;
+        };
+        if request.method == "session/prompt" {
+            self.prompt_running = false;
+            self.prompt_session = None;
+            if let Some(started) = self.prompt_started.take() {
+                crate::metrics::turn_finished(started.elapsed());
+            }
+        }
+        if let Some(result) = msg.get("result") {
+            if SHARED_SETUP.contains(&request.method.as_str()) {
+                let setup = SharedSetup {
+                    clients: HashSet::from([request.client]),
+                    result: result.clone(),
+                };
+                self.setup.insert(request.method.clone(), setup);
+            }
+        }
+        let mut reply = msg.clone();
+        reply["id"] = request.id;
+        AgentRoute::Client(request.client, reply)
+    }
+
+    /// The next queued prompt and the client that sent it, once none is running
+    fn take_next_prompt(&mut self) -> Option<(u64, serde_json::Value)> {
+        if self.prompt_running {
+            return None;
+        }
+        let next = self.queued_prompts.pop_front()?;
+        self.start_prompt(&next.1);
+        Some(next)
+    }
+
+    fn start_prompt(&mut self, prompt: &serde_json::Value) {
+        self.prompt_running = true;
+        self.prompt_started = Some(Instant::now());
+        self.prompt_session = prompt["params"]["sessionId"].as_str().map(str::to_string);
+        crate::metrics::prompt_sent();
+    }
+
+    /// Forget everything about the previous clients so the next one starts a
+    /// fresh session on the still-initialized agent
+    fn reset(&mut self) {
+        self.in_flight.clear();
+        self.agent_requests.clear();
+        self.queued_prompts.clear();
+        self.prompt_running = false;
+        self.prompt_started = None;
+        self.prompt_session = None;
+        self.setup.retain(|method, _| method == "initialize");
+        for setup in self.setup.values_mut() {
+            setup.clients.clear();
+        }
+    }
+
+    fn detach(&mut self, client: u64) {
+        let in_flight = &mut self.in_flight;
+        self.queued_prompts.retain(|(owner, prompt)| {
+            if *owner != client {
+                return true;
+            }
+            if let Some(key) = prompt.get("id").and_then(id_key) {
+                in_flight.remove(&key);
+            }
+            false
+        });
+        for setup in self.setup.values_mut() {
+            setup.clients.remove(&client);
+        }
+    }
+}
+
+/// JSON-RPC id as a map key; `null` ids are treated as absent
+fn id_key(v: &serde_json::Value) -> Option<String> {
+    match v {
+        serde_json::Value::String(s) => Some(s.clone()),
+        serde_json::Value::Number(n) => Some(n.to_string()),
+        serde_json::Value::Null => None,
+        other => Some(other.to_string()),
+    }
+}
+
+/// What a WebSocket client asked for in its connection URL, and where its
+/// traffic is logged
+struct ClientHello {
+    session: Option<String>,
+    observer: bool,
+    log: Option<Arc<BridgeLog>>,
+}
+
+async fn run_acp_bridge_local<WS, WR>(
+    mut ws_write: WS,
+    mut ws_read: WR,
+    pool: Arc<AgentPool>,
+    hello: ClientHello,
+    sessions: SharedSessions,
+    connection: ConnectionOptions,
+) -> Result<()>
+where
+    WS: SinkExt<Message, Error = tokio_tungstenite::tungstenite::Error> + Unpin + Send + 'static,
+    WR: StreamExt<Item = Result<Message, tokio_tungstenite::tungstenite::Error>> + Unpin + Send + 'static,
+{
+    let ClientHello { session, observer, log } = hello;
+    // Join the named session's agent if it is running; unnamed connections
+    // always start an agent of their own
+    let (agent, client_id, reply_tx, mut outgoing) = {
+        let mut shared = sessions.lock().await;
+        let existing = session.as_ref().and_then(|name| shared.get(name).cloned());
+        let agent = match existing {
+            Some(agent) => agent,
+            // Observers only watch; they never start an agent
+            None if observer => {
+                drop(shared);
+                let frame = CloseFrame {
+                    code: CloseCode::Policy,
+                    reason: "no running session to observe".into(),
+                };
+                if let Some(log) = &log {
+                    log.record("closed", serde_json::json!({"reason": frame.reason}));
+                }
+                let _ = ws_write.send(Message::Close(Some(frame))).await;
+                return Ok(());
+            }
+            None => {
+                let agent = pool.take().await?;
+                if let Some(name) = &session {
+                    shared.insert(name.clone(), agent.clone());
+                }
+                agent
+            }
+        };
+        let (client_id, reply_tx, outgoing) = agent.join(observer).await;
+        if let Some(log) = &log {
+            log.record("joined", serde_json::json!({"clientId": client_id}));
+            agent.clients.lock().await.logs.insert(client_id, log.clone());
+        }
+        (agent, client_id, reply_tx, outgoing)
+    };
+    let _connection = crate::metrics::connection_opened();
+    let liveness = Arc::new(Liveness::new());
+    if let Some(name) = &session {
+        let role = if observer { "an observer" } else { "a participant" };
+        info!("🔧 LOCAL DEV: client {} joined session '{}' as {}", client_id, name, role);
+    }
+
+    // Task: messages for this client (agent output and bridge replies) -> WS
+    let liveness_out = liveness.clone();
+    let log_out = log.clone();
+    let agent_to_ws = tokio::spawn(async move {
+        while let Some(msg) = outgoing.recv().await {
+            if msg.is_text() || msg.is_binary() {
+                liveness_out.active();
+            }
+            if let Some(log) = &log_out {
+                match &msg {
+                    Message::Text(text) => log.frame("out", text),
+                    Message::Close(frame) => log.record("close_sent", serde_json::json!({"reason": frame.as_ref().map(|f| f.reason.to_string())})),
+                    _ => {}
+                }
+            }
+            let msg = match (msg, connection.compress_threshold) {
+                (Message::Text(text), Some(threshold)) if text.len() >= threshold => {
+                    Message::Binary(gzip(text.as_bytes()))
+                }
+                (msg, _) => msg,
+            };
+            crate::metrics::bytes_out(msg.len());
+            if let Err(e) = ws_write.send(msg).await {
+                warn!("🔧 LOCAL DEV: ws send error: {}", e);
+                break;
+            }
+        }
+    });
+
+    // Task: WS -> agent stdin (direct pass-through, no encryption)
+    let agent_for_ws = agent.clone();
+    let running_for_ws = agent.running_terms.clone();
+    let liveness_in = liveness.clone();
+    let log_in = log.clone();
+    let ping_tx = reply_tx.clone();
+    let mut ws_to_agent = tokio::spawn(async move {
+        let mut stdin_frames = NdjsonFramer::default();
+        let root = std::env::current_dir().unwrap_or_default();
+        let transfers = Arc::new(Mutex::new(Transfers::new(root)));
+        'ws: while let Some(msg) = ws_read.next().await {
+            if let Ok(msg) = &msg {
+                crate::metrics::bytes_in(msg.len());
+                liveness_in.heard(msg.is_text() || msg.is_binary());
+            }
+            // A gzipped binary frame is a large message sent whole, like a text frame
+            let msg = match msg {
+                Ok(Message::Binary(data)) if is_gzip(&data) => match gunzip(&data) {
+                    Ok(text) => Ok(Message::Text(text)),
+                    Err(e) => {
+                        warn!("🔧 LOCAL DEV: dropping compressed frame: {}", e);
+                        continue;
+                    }
+                },
+                msg => msg,
+            };
+            match msg {
+                Ok(Message::Text(text)) => {
+                    // A text frame normally holds one message, but may batch several lines
+                    for text in ws_text_frames(&text) {
+                         warn!("🔧 LOCAL DEV: WS received: {}", text);
+                         if let Some(log) = &log_in {
+                             log.frame("in", &text);
+                         }
+                          if observer {
+                              agent_for_ws.observe(client_id, &text, &reply_tx).await;
+                              continue;
+                          }
+                          // Intercept permission responses addressed to local bridge
+                          let mut intercepted = false;
+                          if let Ok(v) = serde_json::from_str::<serde_json::Value>(&text) {
+                              // Bridge-local file transfers, see `transfer`
+                              if v.get("method").and_then(|m| m.as_str()).is_some_and(|m| m.starts_with("rat/file/")) {
+                                  handle_file_request(&agent_for_ws, client_id, &transfers, v, &reply_tx).await;
+                                  continue;
+                              }
+                              // Bridge-local control message: interrupt or kill a terminal/execute child
+                              if v.get("method").and_then(|m| m.as_str()) == Some("terminal/kill") {
+                                  let target = id_key(&v["params"]["id"]).unwrap_or_default();
+                                  let signal = v["params"]["signal"]
+                                      .as_str()
+                                      .map(ProcessSignal::from_name)
+                                      .unwrap_or(Some(ProcessSignal::Interrupt));
+                                  let delivered = match signal {
+                                      Some(sig) => running_for_ws
+                                          .lock()
+                                          .await
+                                          .get(&target)
+                                          .map(|tx| tx.send(sig).is_ok())
+                                          .unwrap_or(false),
+                                      None => false,
+                                  };
+                                  warn!("🔧 LOCAL DEV: terminal/kill for {} delivered={}", target, delivered);
+                                  if let Some(req_id) = v.get("id") {
+                                      let reply = match signal {
+                                          Some(_) => serde_json::json!({"jsonrpc":"2.0","id": req_id, "result": {"delivered": delivered}}),
+                                          None => serde_json::json!({"jsonrpc":"2.0","id": req_id, "error": {"code": -32602, "message": "unknown signal"}}),
+                                      };
+                                      let _ = reply_tx.send(Message::Text(reply.to_string()));
+                                  }
+                                  continue;
+                              }
+                              // Only intercept responses to permission requests that WE sent
+                              intercepted = agent_for_ws.resolve_bridge_permission(client_id, &v).await;
+                          }
+                         if intercepted { continue; }
+                        if let Err(e) = agent_for_ws.forward(client_id, text, &reply_tx).await {
+                            warn!("🔧 LOCAL DEV: stdin write error: {}", e);
+                            break 'ws;
+                        }
+                    }
                 }
+                Ok(Message::Binary(data)) => {
+                    // Binary frames are a raw byte stream; forward only whole lines
+                    for frame in stdin_frames.push(&data) {
+                        let frame = match frame {
+                            Ok(frame) => String::from_utf8_lossy(&frame).into_owned(),
+                            Err(e) => {
+                                warn!("🔧 LOCAL DEV: closing connection of client {}: {}", client_id, e);
+                                let frame = CloseFrame { code: CloseCode::Size, reason: "message too big".into() };
+                                let _ = reply_tx.send(Message::Close(Some(frame)));
+                                break 'ws;
+                            }
+                        };
+                        if let Some(log) = &log_in {
+                            log.frame("in", &frame);
+                        }
+                        if observer {
+                            agent_for_ws.observe(client_id, &frame, &reply_tx).await;
+                            continue;
+                        }
+                        if let Err(e) = agent_for_ws.forward(client_id, frame, &reply_tx).await {
+                            warn!("🔧 LOCAL DEV: stdin write error: {}", e);
+                            break 'ws;
+                        }
+                    }
+                }
+                Ok(Message::Close(_)) => break,
+                _ => {}
             }
-            Message::Close(_) => {
-                info!("🔧 LOCAL DEV: Connection closed by {}", peer_addr);
-                break;
-            }
-            _ => {}
+        }
+    });
+    tokio::select! {
+        _ = &mut ws_to_agent => {}
+        reason = keep_alive(connection, &liveness, &ping_tx) => {
+            info!("🔧 LOCAL DEV: closing connection of client {}: {}", client_id, reason);
+            ws_to_agent.abort();
+            let frame = CloseFrame { code: CloseCode::Away, reason: reason.into() };
+            let _ = ping_tx.send(Message::Close(Some(frame)));
         }
     }
+    drop(ping_tx);
 
+    // The last client to leave hands the agent back to the pool
+    let mut shared = sessions.lock().await;
+    if agent.leave(client_id).await == 0 {
+        if let Some(name) = &session {
+            shared.remove(name);
+        }
+        pool.recycle(agent).await;
+    }
+    drop(shared);
+    let _ = agent_to_ws.await;
+    if let Some(log) = &log {
+        log.record("left", serde_json::json!({"clientId": client_id}));
+    }
     Ok(())
 }
 
-async fn run_acp_bridge_local<WS, WR>(
-    mut ws_write: WS,
-    mut ws_read: WR,
-    resolved_agent: Option<AgentCommand>,
-) -> Result<()>
-where
-    WS: SinkExt<Message, Error = tokio_tungstenite::tungstenite::Error> + Unpin + Send + 'static,
-    WR: StreamExt<Item = Result<Message, tokio_tungstenite::tungstenite::Error>> + Unpin + Send + 'static,
-{
-    fn id_key(v: &serde_json::Value) -> Option<String> {
-        match v {
-            serde_json::Value::String(s) => Some(s.clone()),
-            serde_json::Value::Number(n) => Some(n.to_string()),
-            serde_json::Value::Null => None,
-            other => Some(other.to_string()),
+/// When a connection last heard from its client, and last carried messages
+/// either way
+#[derive(Debug)]
+struct Liveness {
+    heard: std::sync::Mutex<Instant>,
+    active: std::sync::Mutex<Instant>,
+}
+
+impl Liveness {
+    fn new() -> Self {
+        Self {
+            heard: std::sync::Mutex::new(Instant::now()),
+            active: std::sync::Mutex::new(Instant::now()),
         }
     }
-    // Track permission prompts awaiting a browser decision
-    let pending_perms: Arc<Mutex<HashMap<String, oneshot::Sender<bool>>>> = Arc::new(Mutex::new(HashMap::new()));
-    // Determine agent command: prefer resolved_agent; fallback to env variables
-    let (path, args_vec, env_map): (
-        std::path::PathBuf,
-        Vec<String>,
-        Option<std::collections::HashMap<String, String>>,
-    ) = if let Some(cmd) = resolved_agent {
-        (cmd.path, cmd.args, cmd.env)
-    } else {
-        let cmd = env::var("RAT2E_AGENT_CMD").map_err(|_| {
-            anyhow::anyhow!(
-                "No ACP agent resolved. Set RAT2E_AGENT_CMD or install @zed-industries/claude-code-acp"
-            )
-        })?;
-        let args = env::var("RAT2E_AGENT_ARGS").unwrap_or_default();
-        let args_vec: Vec<String> = if args.is_empty() {
-            vec![]
-        } else {
-            args.split_whitespace().map(|s| s.to_string()).collect()
+
+    /// Any frame from the client, pongs included, shows it is still there
+    fn heard(&self, message: bool) {
+        *self.heard.lock().unwrap() = Instant::now();
+        if message {
+            self.active();
+        }
+    }
+
+    fn active(&self) {
+        *self.active.lock().unwrap() = Instant::now();
+    }
+}
+
+/// Ping the client until it stops answering or the connection sits idle
+/// past the timeout; resolves with the reason to close it
+async fn keep_alive(
+    options: ConnectionOptions,
+    liveness: &Liveness,
+    outgoing: &mpsc::UnboundedSender<Message>,
+) -> &'static str {
+    let period = match (options.ping_interval.is_zero(), options.idle_timeout) {
+        (false, _) => options.ping_interval,
+        (true, Some(idle)) => (idle / 4).max(Duration::from_secs(1)),
+        (true, None) => std::future::pending().await,
+    };
+    let mut tick = tokio::time::interval(period);
+    tick.tick().await;
+    loop {
+        tick.tick().await;
+        if let Some(idle) = options.idle_timeout {
+            if liveness.active.lock().unwrap().elapsed() >= idle {
+                return "idle timeout";
+            }
+        }
+        if options.ping_interval.is_zero() {
+            continue;
+        }
+        if liveness.heard.lock().unwrap().elapsed() >= options.ping_interval * 2 {
+            return "no answer to pings";
+        }
+        let _ = outgoing.send(Message::Ping(Vec::new()));
+    }
+}
+
+/// Serve a client's `rat/file/*` request. Opening a transfer asks every
+/// client for permission first, without holding up the connection; chunks
+/// and closes are answered in order.
+async fn handle_file_request(
+    agent: &Arc<SharedAgent>,
+    client: u64,
+    transfers: &Arc<Mutex<Transfers>>,
+    msg: serde_json::Value,
+    reply: &mpsc::UnboundedSender<Message>,
+) {
+    let id = msg.get("id").cloned().unwrap_or(serde_json::json!(null));
+    let method = msg["method"].as_str().unwrap_or_default().to_string();
+    // Our own permission request id, distinct from the agent's
+    let perm_id = format!("rat-file-{}-{}", client, id_key(&id).unwrap_or_default());
+    let answer = move |result: Result<serde_json::Value>| {
+        let resp = match result {
+            Ok(result) => serde_json::json!({"jsonrpc":"2.0","id": id, "result": result}),
+            Err(e) => serde_json::json!({"jsonrpc":"2.0","id": id, "error": {"code": -32000, "message": e.to_string()}}),
         };
-        (cmd.into(), args_vec, None)
+        Message::Text(resp.to_string())
+    };
+    if method != "rat/file/open" {
+        let result = transfers.lock().await.handle(&method, &msg["params"]).await;
+        let _ = reply.send(answer(result));
+        return;
+    }
+
+    let params = &msg["params"];
+    let direction = params["mode"].as_str().and_then(Direction::parse);
+    let path = params["path"].as_str().unwrap_or_default();
+    let resolved = transfers.lock().await.resolve(path);
+    let (direction, path) = match (direction, resolved) {
+        (Some(direction), Ok(path)) => (direction, path),
+        (None, _) => {
+            let _ = reply.send(answer(Err(anyhow::anyhow!("mode must be read or write"))));
+            return;
+        }
+        (_, Err(e)) => {
+            let _ = reply.send(answer(Err(e)));
+            return;
+        }
+    };
+    let size = params["size"].as_u64();
+    let reason = match direction {
+        Direction::Download => format!("Client {} wants to download {}", client, path.display()),
+        Direction::Upload => format!(
+            "Client {} wants to upload {} ({} bytes)",
+            client,
+            path.display(),
+            size.unwrap_or_default()
+        ),
     };
+    let tool = match direction {
+        Direction::Download => "download_file",
+        Direction::Upload => "upload_file",
+    };
+    let (tx, rx) = oneshot::channel::<bool>();
+    agent.pending_perms.lock().await.insert(perm_id.clone(), tx);
+    let perm_req = serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": perm_id,
+        "method": "session/request_permission",
+        "params": {"tool": tool, "reason": reason, "options":[{"id":"allow"},{"id":"deny"}]}
+    });
+    let _ = agent.clients.lock().await.send(Message::Text(perm_req.to_string())).await;
+
+    let transfers = transfers.clone();
+    let reply = reply.clone();
+    tokio::spawn(async move {
+        let result = if rx.await.unwrap_or(false) {
+            transfers.lock().await.open(path, direction, size).await
+        } else {
+            Err(anyhow::anyhow!("permission denied"))
+        };
+        let _ = reply.send(answer(result));
+    });
+}
+
+/// Spawn an agent process and the task relaying its output to the clients
+/// attached to it
+fn start_agent(resolved_agent: Option<AgentCommand>) -> Result<Arc<SharedAgent>> {
+    // Track permission prompts awaiting a browser decision
+    let pending_perms: Arc<Mutex<HashMap<String, oneshot::Sender<bool>>>> = Arc::new(Mutex::new(HashMap::new()));
+    // "Always allow" answers to those prompts
+    let grants = Arc::new(Mutex::new(PermissionGrants::default()));
+    // Track running terminal/execute children by request id so the browser can signal them
+    let running_terms: Arc<Mutex<HashMap<String, mpsc::UnboundedSender<ProcessSignal>>>> = Arc::new(Mutex::new(HashMap::new()));
+    let AgentCommand {
+        path,
+        args: args_vec,
+        env: env_map,
+    } = resolved_agent.ok_or_else(|| {
+        anyhow::anyhow!(
+            "No ACP agent resolved. Set RAT2E_AGENT_CMD or install @zed-industries/claude-code-acp"
+        )
+    })?;
 
     // Build additional args to ensure Claude Code has edit/tools enabled when used
     // via the WS bridge. Only applies when we detect Claude Code entrypoints.
@@ -281,6 +1705,7 @@ where
         command.envs(envs);
     }
     let mut child = command.spawn()?;
+    crate::metrics::agent_started();
     let child_stdin = child
         .stdin
         .take()
@@ -291,117 +1716,66 @@ where
         .ok_or_else(|| anyhow::anyhow!("Failed to get agent stdout"))?;
 
     // Share stdin between tasks for local handling of fs/* RPCs
-    let child_stdin = std::sync::Arc::new(tokio::sync::Mutex::new(child_stdin));
-    // Share WS writer across tasks
-    let ws_writer = std::sync::Arc::new(tokio::sync::Mutex::new(ws_write));
-
-    // Task: WS -> agent stdin (direct pass-through, no encryption)
-    let stdin_for_ws = child_stdin.clone();
-    let perms_for_ws = pending_perms.clone();
-    let ws_to_agent = tokio::spawn(async move {
-        while let Some(msg) = ws_read.next().await {
-            match msg {
-                 Ok(Message::Text(text)) => {
-                     warn!("🔧 LOCAL DEV: WS received: {}", text);
-                      // Intercept permission responses addressed to local bridge
-                      let mut intercepted = false;
-                      if let Ok(v) = serde_json::from_str::<serde_json::Value>(&text) {
-                          let is_response = v.get("method").is_none() && v.get("id").is_some();
-                          if is_response {
-                              let id_str = id_key(&v["id"]).unwrap_or_default();
-                              // Only intercept responses to permission requests that WE sent
-                              if let Some(tx) = perms_for_ws.lock().await.remove(&id_str) {
-                                  warn!("🔧 LOCAL DEV: Intercepted permission response for id {}", id_str);
-                                  let mut allowed = false;
-                                  if let Some(res) = v.get("result") {
-                                      if res.get("outcome").and_then(|o| o.get("cancelled")).and_then(|b| b.as_bool()) == Some(true) {
-                                          allowed = false;
-                                      } else if let Some(outcome) = res.get("outcome") {
-                                          if let Some(outcome_type) = outcome.get("outcome").and_then(|o| o.as_str()) {
-                                              if outcome_type == "selected" {
-                                                  let opt = outcome.get("optionId").and_then(|s| s.as_str()).unwrap_or("").to_lowercase();
-                                                  warn!("🔧 LOCAL DEV: Parsed optionId: {}", opt);
-                                                  // Accept common allow variants (ACP may use allow/allow_once/allow_always)
-                                                  allowed = opt == "allow"
-                                                      || opt.starts_with("allow")
-                                                      || opt == "yes"
-                                                      || opt == "ok"
-                                                      || opt == "approve";
-                                                  if !allowed {
-                                                      warn!("🔧 LOCAL DEV: permission response optionId='{}' not recognized as allow; treating as deny", opt);
-                                                  }
-                                              }
-                                          }
-                                      }
-                                  }
-                                  warn!("🔧 LOCAL DEV: Sending allowed={} for id {}", allowed, id_str);
-                                  let _ = tx.send(allowed);
-                                  intercepted = true;
-                              }
-                              // If it's not a response to our permission request, don't intercept it
-                          }
-                      }
-                     if intercepted { continue; }
-                    if let Err(e) = stdin_for_ws.lock().await.write_all(text.as_bytes()).await {
-                        warn!("🔧 LOCAL DEV: stdin write error: {}", e);
-                        break;
-                    }
-                    if let Err(e) = stdin_for_ws.lock().await.write_all(b"\n").await {
-                        warn!("🔧 LOCAL DEV: stdin newline write error: {}", e);
-                        break;
-                    }
-                }
-                Ok(Message::Binary(data)) => {
-                    if let Err(e) = stdin_for_ws.lock().await.write_all(&data).await {
-                        warn!("🔧 LOCAL DEV: stdin write error: {}", e);
-                        break;
-                    }
-                }
-                Ok(Message::Close(_)) => break,
-                _ => {}
-            }
-        }
-    });
+    let child_stdin = Arc::new(Mutex::new(child_stdin));
+    let clients = Arc::new(Mutex::new(ClientSet::default()));
+    let router = Arc::new(Mutex::new(RequestRouter::default()));
 
     // Task: agent stdout -> WS (direct pass-through, no encryption)
     let stdin_for_agent = child_stdin.clone();
     let perms_for_agent = pending_perms.clone();
-    let agent_to_ws = tokio::spawn(async move {
+    let grants_for_agent = grants.clone();
+    let running_for_agent = running_terms.clone();
+    let ws_writer = clients.clone();
+    let router_for_agent = router.clone();
+    tokio::spawn(async move {
         let mut buf = vec![0u8; 8192];
+        let mut stdout_frames = NdjsonFramer::default();
+        let mut tool_calls = ToolCallTimes::default();
         loop {
             match child_stdout.read(&mut buf).await {
-                Ok(0) => break,
+                Ok(0) => {
+                    // Flush a final message the agent didn't newline-terminate
+                    if let Some(rest) = stdout_frames.finish() {
+                        let text = String::from_utf8_lossy(&rest).into_owned();
+                        let _ = ws_writer.lock().await.send(Message::Text(text)).await;
+                    }
+                    break;
+                }
                 Ok(n) => {
-                    let data = &buf[..n];
-                    // Try to treat output as NDJSON and intercept fs/* requests locally
-                    if let Ok(text) = std::str::from_utf8(data) {
-                        for line in text.split('\n').filter(|l| !l.trim().is_empty()) {
-                            let maybe_json: Result<serde_json::Value, _> = serde_json::from_str(line);
-                            if let Ok(v) = maybe_json {
-                                if let Some(m) = v.get("method").and_then(|x| x.as_str()) {
-                                    if m == "fs/write_text_file" {
-                                        let id = v.get("id").cloned().unwrap_or(serde_json::json!(null));
-                                        let id_str = id_key(&id).unwrap_or_else(|| "".into());
-                                        let path = v["params"]["path"].as_str().unwrap_or("").to_string();
-                                        let content = v["params"]["content"].as_str().unwrap_or("").to_string();
-
+                    // Treat output as NDJSON and intercept fs/* requests locally; a message
+                    // may span several reads or share one with others
+                    for frame in stdout_frames.push(&buf[..n]) {
+                        let frame = match frame {
+                            Ok(frame) => frame,
+                            Err(e) => {
+                                warn!("🔧 LOCAL DEV: dropping agent output: {}", e);
+                                continue;
+                            }
+                        };
+                        let line = match String::from_utf8(frame) {
+                            Ok(line) => line,
+                            Err(e) => {
+                                if let Err(e) = ws_writer.lock().await.send(Message::Binary(e.into_bytes())).await {
+                                    warn!("🔧 LOCAL DEV: ws send error: {}", e);
+                                    break;
+                                }
+                                continue;
+                            }
+                        };
+                        let maybe_json: Result<serde_json::Value, _> = serde_json::from_str(&line);
+                        if let Ok(v) = maybe_json {
+                            if let Some(m) = v.get("method").and_then(|x| x.as_str()) {
+                                if m.starts_with("fs/") || m.starts_with("terminal/") {
+                                    ws_writer.lock().await.record("intercepted", serde_json::json!({"request": bridge_log::loggable(&line)}));
+                                }
+                            }
+                            if let Some(Intercepted { id, request }) = ndjson::intercept(&v) {
+                                let id_str = id_key(&id).unwrap_or_default();
+                                match request {
+                                    LocalRequest::WriteTextFile { path, content } => {
                                         // Prompt the browser for permission before writing
-                                        let (tx, rx) = oneshot::channel::<bool>();
-                                        perms_for_agent.lock().await.insert(id_str.clone(), tx);
-                                        let perm_req = serde_json::json!({
-                                            "jsonrpc": "2.0",
-                                            "id": id_str,
-                                            "method": "session/request_permission",
-                                            "params": {
-                                                "tool": "write_text_file",
-                                                "reason": format!("Agent requested to write {}", path),
-                                                "options": [
-                                                    {"id": "allow", "label": "Allow"},
-                                                    {"id": "deny", "label": "Deny"}
-                                                ]
-                                            }
-                                        });
-                                        if let Err(e) = ws_writer.lock().await.send(Message::Text(perm_req.to_string())).await { warn!("🔧 LOCAL DEV: ws send perm req error: {}", e); }
+                                        let reason = format!("Agent requested to write {}", path);
+                                        let rx = ask_bridge_permission(&ws_writer, &perms_for_agent, &grants_for_agent, &id_str, "write_text_file", &path, reason).await;
 
                                         // Spawn a task to wait for decision and then perform the write + reply to agent
                                         let stdin_for_agent2 = stdin_for_agent.clone();
@@ -422,9 +1796,8 @@ where
                                         });
                                         // Do not forward this request to the browser
                                         continue;
-                                    } else if m == "fs/read_text_file" {
-                                        let id = v.get("id").cloned().unwrap_or(serde_json::json!(null));
-                                        let path = v["params"]["path"].as_str().unwrap_or("").to_string();
+                                    }
+                                    LocalRequest::ReadTextFile { path } => {
                                         let result = tokio::fs::read_to_string(&path).await;
                                         let resp = match result {
                                             Ok(content) => serde_json::json!({"jsonrpc":"2.0","id": id, "result": {"content": content}}),
@@ -435,19 +1808,10 @@ where
                                             warn!("🔧 LOCAL DEV: reply write error: {}", e);
                                         }
                                         continue;
-                                    } else if m == "fs/mkdir" || m == "fs/create_dir" {
-                                        let id = v.get("id").cloned().unwrap_or(serde_json::json!(null));
-                                        let id_str = id_key(&id).unwrap_or_else(|| "".into());
-                                        let path = v["params"]["path"].as_str().unwrap_or("").to_string();
-                                        let (tx, rx) = oneshot::channel::<bool>();
-                                        perms_for_agent.lock().await.insert(id_str.clone(), tx);
-                                        let perm_req = serde_json::json!({
-                                            "jsonrpc": "2.0",
-                                            "id": id_str,
-                                            "method": "session/request_permission",
-                                            "params": {"tool":"mkdir","reason": format!("Agent requested to create directory {}", path), "options":[{"id":"allow"},{"id":"deny"}]}
-                                        });
-                                        let _ = ws_writer.lock().await.send(Message::Text(perm_req.to_string())).await;
+                                    }
+                                    LocalRequest::CreateDir { path } => {
+                                        let reason = format!("Agent requested to create directory {}", path);
+                                        let rx = ask_bridge_permission(&ws_writer, &perms_for_agent, &grants_for_agent, &id_str, "mkdir", &path, reason).await;
                                         let stdin_for_agent2 = stdin_for_agent.clone();
                                         tokio::spawn(async move {
                                             let allowed = rx.await.unwrap_or(false);
@@ -461,19 +1825,10 @@ where
                                             let _ = stdin_for_agent2.lock().await.write_all(s.as_bytes()).await;
                                         });
                                         continue;
-                                    } else if m == "fs/delete_file" || m == "fs/remove_file" {
-                                        let id = v.get("id").cloned().unwrap_or(serde_json::json!(null));
-                                        let id_str = id_key(&id).unwrap_or_else(|| "".into());
-                                        let path = v["params"]["path"].as_str().unwrap_or("").to_string();
-                                        let (tx, rx) = oneshot::channel::<bool>();
-                                        perms_for_agent.lock().await.insert(id_str.clone(), tx);
-                                        let perm_req = serde_json::json!({
-                                            "jsonrpc": "2.0",
-                                            "id": id_str,
-                                            "method": "session/request_permission",
-                                            "params": {"tool":"delete_file","reason": format!("Agent requested to delete {}", path), "options":[{"id":"allow"},{"id":"deny"}]}
-                                        });
-                                        let _ = ws_writer.lock().await.send(Message::Text(perm_req.to_string())).await;
+                                    }
+                                    LocalRequest::DeleteFile { path } => {
+                                        let reason = format!("Agent requested to delete {}", path);
+                                        let rx = ask_bridge_permission(&ws_writer, &perms_for_agent, &grants_for_agent, &id_str, "delete_file", &path, reason).await;
                                         let stdin_for_agent2 = stdin_for_agent.clone();
                                         tokio::spawn(async move {
                                             let allowed = rx.await.unwrap_or(false);
@@ -487,20 +1842,11 @@ where
                                             let _ = stdin_for_agent2.lock().await.write_all(s.as_bytes()).await;
                                         });
                                         continue;
-                                    } else if m == "fs/rename" || m == "fs/move" {
-                                        let id = v.get("id").cloned().unwrap_or(serde_json::json!(null));
-                                        let id_str = id_key(&id).unwrap_or_else(|| "".into());
-                                        let from = v["params"]["from"].as_str().unwrap_or("").to_string();
-                                        let to = v["params"]["to"].as_str().unwrap_or("").to_string();
-                                        let (tx, rx) = oneshot::channel::<bool>();
-                                        perms_for_agent.lock().await.insert(id_str.clone(), tx);
-                                        let perm_req = serde_json::json!({
-                                            "jsonrpc": "2.0",
-                                            "id": id_str,
-                                            "method": "session/request_permission",
-                                            "params": {"tool":"rename","reason": format!("Agent requested to rename {} -> {}", from, to), "options":[{"id":"allow"},{"id":"deny"}]}
-                                        });
-                                        let _ = ws_writer.lock().await.send(Message::Text(perm_req.to_string())).await;
+                                    }
+                                    LocalRequest::Rename { from, to } => {
+                                        let subject = format!("{} -> {}", from, to);
+                                        let reason = format!("Agent requested to rename {}", subject);
+                                        let rx = ask_bridge_permission(&ws_writer, &perms_for_agent, &grants_for_agent, &id_str, "rename", &subject, reason).await;
                                         let stdin_for_agent2 = stdin_for_agent.clone();
                                         tokio::spawn(async move {
                                             let allowed = rx.await.unwrap_or(false);
@@ -514,32 +1860,21 @@ where
                                         let _ = stdin_for_agent2.lock().await.write_all(s.as_bytes()).await;
                                         });
                                         continue;
-                                    } else if m == "terminal/execute" {
+                                    }
+                                    LocalRequest::Execute { cmd, args, cwd } => {
                                         // Prompt and execute command locally, stream output to browser, send result to agent
-                                        let id = v.get("id").cloned().unwrap_or(serde_json::json!(null));
-                                        let id_str = id_key(&id).unwrap_or_else(|| "".into());
-                                        let cmd = v["params"]["cmd"].as_str().unwrap_or("").to_string();
-                                        let args: Vec<String> = v["params"]["args"].as_array()
-                                            .map(|a| a.iter().filter_map(|x| x.as_str().map(|s| s.to_string())).collect())
-                                            .unwrap_or_else(|| vec![]);
-                                        let cwd = v["params"]["cwd"].as_str().map(|s| s.to_string());
                                         if cmd.is_empty() {
                                             let resp = serde_json::json!({"jsonrpc":"2.0","id": id, "error": {"code": -32602, "message": "terminal/execute missing cmd"}});
                                             let _ = stdin_for_agent.lock().await.write_all((resp.to_string()+"\n").as_bytes()).await;
                                             continue;
                                         }
-                                        let (tx, rx) = oneshot::channel::<bool>();
-                                        perms_for_agent.lock().await.insert(id_str.clone(), tx);
-                                        let perm_req = serde_json::json!({
-                                            "jsonrpc": "2.0",
-                                            "id": id_str,
-                                            "method": "session/request_permission",
-                                            "params": {"tool":"terminal_execute","reason": format!("Agent requested to run: {} {}", cmd, args.join(" ")), "options":[{"id":"allow"},{"id":"deny"}]}
-                                        });
-                                        let _ = ws_writer.lock().await.send(Message::Text(perm_req.to_string())).await;
+                                        let command_line = format!("{} {}", cmd, args.join(" "));
+                                        let reason = format!("Agent requested to run: {}", command_line);
+                                        let rx = ask_bridge_permission(&ws_writer, &perms_for_agent, &grants_for_agent, &id_str, "terminal_execute", &command_line, reason).await;
 
                                         let stdin_for_agent2 = stdin_for_agent.clone();
                                         let ws_write2 = ws_writer.clone();
+                                        let running = running_for_agent.clone();
                                         tokio::spawn(async move {
                                             let allowed = rx.await.unwrap_or(false);
                                             if !allowed {
@@ -551,43 +1886,71 @@ where
                                             c.args(&args)
                                                 .stdin(std::process::Stdio::null())
                                                 .stdout(std::process::Stdio::piped())
-                                                .stderr(std::process::Stdio::piped());
+                                                .stderr(std::process::Stdio::piped())
+                                                .kill_on_drop(true);
                                             if let Some(ref d) = cwd { c.current_dir(d); }
                                             match c.spawn() {
                                                 Ok(mut child) => {
-                                                    // Stream stdout
-                                                    if let Some(mut out) = child.stdout.take() {
-                                                        let mut rdr = tokio::io::BufReader::new(out);
-                                                        loop {
-                                                            let mut line = String::new();
-                                                            match rdr.read_line(&mut line).await {
-                                                                Ok(0) => break,
-                                                                Ok(_) => {
-                                                                    let term = serde_json::json!({"jsonrpc":"2.0","method":"terminal/output","params": {"stream":"stdout","line": line.trim_end()}});
-                                                                    let _ = ws_write2.lock().await.send(Message::Text(term.to_string())).await;
+                                                    // Register so the browser can interrupt/kill via terminal/kill
+                                                    let (kill_tx, mut kill_rx) = mpsc::unbounded_channel::<ProcessSignal>();
+                                                    running.lock().await.insert(id_str.clone(), kill_tx);
+
+                                                    // Stream stdout and stderr concurrently so a kill is observed promptly
+                                                    let mut streams = Vec::new();
+                                                    for (name, reader) in [
+                                                        ("stdout", child.stdout.take().map(|o| Box::new(o) as Box<dyn tokio::io::AsyncRead + Unpin + Send>)),
+                                                        ("stderr", child.stderr.take().map(|e| Box::new(e) as Box<dyn tokio::io::AsyncRead + Unpin + Send>)),
+                                                    ] {
+                                                        let Some(reader) = reader else { continue };
+                                                        let ws_out = ws_write2.clone();
+                                                        streams.push(tokio::spawn(async move {
+                                                            let mut rdr = tokio::io::BufReader::new(reader);
+                                                            loop {
+                                                                let mut line = String::new();
+                                                                match rdr.read_line(&mut line).await {
+                                                                    Ok(0) | Err(_) => break,
+                                                                    Ok(_) => {
+                                                                        let term = serde_json::json!({"jsonrpc":"2.0","method":"terminal/output","params": {"stream": name,"line": line.trim_end()}});
+                                                                        let _ = ws_out.lock().await.send(Message::Text(term.to_string())).await;
+                                                                    }
                                                                 }
-                                                                Err(_) => break,
                                                             }
-                                                        }
+                                                        }));
                                                     }
-                                                    // Stream stderr
-                                                    if let Some(mut err) = child.stderr.take() {
-                                                        let mut rdr = tokio::io::BufReader::new(err);
-                                                        loop {
-                                                            let mut line = String::new();
-                                                            match rdr.read_line(&mut line).await {
-                                                                Ok(0) => break,
-                                                                Ok(_) => {
-                                                                    let term = serde_json::json!({"jsonrpc":"2.0","method":"terminal/output","params": {"stream":"stderr","line": line.trim_end()}});
-                                                                    let _ = ws_write2.lock().await.send(Message::Text(term.to_string())).await;
+
+                                                    let mut terminated_by: Option<ProcessSignal> = None;
+                                                    let status = loop {
+                                                        tokio::select! {
+                                                            status = child.wait() => break status,
+                                                            Some(sig) = kill_rx.recv() => {
+                                                                warn!("🔧 LOCAL DEV: Sending {} to '{}' (request {})", sig, cmd, id_str);
+                                                                if let Err(e) = signal_child(&mut child, sig) {
+                                                                    warn!("🔧 LOCAL DEV: failed to signal '{}': {}", cmd, e);
+                                                                } else {
+                                                                    terminated_by = Some(sig);
                                                                 }
-                                                                Err(_) => break,
                                                             }
                                                         }
-                                                    }
-                                                    let status = child.wait().await;
+                                                    };
+                                                    running.lock().await.remove(&id_str);
+                                                    for s in streams { let _ = s.await; }
+
                                                     let code = status.ok().and_then(|s| s.code()).unwrap_or(-1);
-                                                    let resp = serde_json::json!({"jsonrpc":"2.0","id": id, "result": {"exitCode": code }});
+                                                    let resp = match terminated_by {
+                                                        // Report user termination to the agent as a failed tool call
+                                                        Some(sig) => serde_json::json!({"jsonrpc":"2.0","id": id, "error": {
+                                                            "code": -32000,
+                                                            "message": format!("command '{}' terminated by user ({})", cmd, sig),
+                                                            "data": {"exitCode": code, "signal": sig.name()}
+                                                        }}),
+                                                        None => serde_json::json!({"jsonrpc":"2.0","id": id, "result": {"exitCode": code }}),
+                                                    };
+                                                    let exited = serde_json::json!({"jsonrpc":"2.0","method":"terminal/exited","params": {
+                                                        "id": id_str,
+                                                        "exitCode": code,
+                                                        "signal": terminated_by.map(|s| s.name()),
+                                                    }});
+                                                    let _ = ws_write2.lock().await.send(Message::Text(exited.to_string())).await;
                                                     let _ = stdin_for_agent2.lock().await.write_all((resp.to_string()+"\n").as_bytes()).await;
                                                 }
                                                 Err(e) => {
@@ -600,16 +1963,40 @@ where
                                     }
                                 }
                             }
-                            // Forward non-intercepted lines to the browser
-                            if let Err(e) = ws_writer.lock().await.send(Message::Text(line.to_string())).await {
-                                warn!("🔧 LOCAL DEV: ws send error: {}", e);
-                                break;
+                        }
+                        // Forward non-intercepted lines: replies go back to the client that
+                        // asked, everything else to every attached client
+                        let route = match serde_json::from_str::<serde_json::Value>(&line) {
+                            Ok(v) => {
+                                if v["params"]["update"]["sessionUpdate"] == "tool_call" {
+                                    crate::metrics::tool_call(v["params"]["update"]["kind"].as_str().unwrap_or("other"));
+                                }
+                                if let Some((kind, status, elapsed)) = tool_calls.observe(&v["params"]["update"]) {
+                                    crate::telemetry::tool_call_finished(&kind, status, elapsed);
+                                }
+                                router_for_agent.lock().await.route_agent(&v)
+                            }
+                            Err(_) => AgentRoute::All,
+                        };
+                        match route {
+                            AgentRoute::All => {
+                                let _ = ws_writer.lock().await.send(Message::Text(line)).await;
+                            }
+                            AgentRoute::Client(client, reply) => {
+                                ws_writer.lock().await.send_to(client, Message::Text(reply.to_string()));
+                            }
+                            AgentRoute::Drop => {
+                                warn!("🔧 LOCAL DEV: dropping agent reply to no pending request: {}", bridge_log::loggable(&line));
                             }
                         }
-                    } else {
-                        if let Err(e) = ws_writer.lock().await.send(Message::Binary(data.to_vec())).await {
-                            warn!("🔧 LOCAL DEV: ws send error: {}", e);
-                            break;
+                        // A finished prompt lets the next queued one through
+                        let next_prompt = router_for_agent.lock().await.take_next_prompt();
+                        if let Some((client, prompt)) = next_prompt {
+                            if let Err(e) = write_frame(&stdin_for_agent, prompt.to_string().as_bytes()).await {
+                                warn!("🔧 LOCAL DEV: stdin write error: {}", e);
+                            }
+                            let started = activity(client, "prompt", serde_json::json!({"prompt": prompt["params"]["prompt"]}));
+                            let _ = ws_writer.lock().await.send(started).await;
                         }
                     }
                 }
@@ -619,37 +2006,595 @@ where
                 }
             }
         }
+        crate::metrics::agent_exited();
     });
 
-    let _ = tokio::join!(ws_to_agent, agent_to_ws);
-    Ok(())
+    Ok(Arc::new(SharedAgent {
+        child: Mutex::new(child),
+        stdin: child_stdin,
+        clients,
+        router,
+        pending_perms,
+        grants,
+        running_terms,
+    }))
+}
+
+/// Compress a message for a client that asked for it
+fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    // Writing to a Vec can't fail
+    let _ = encoder.write_all(data);
+    encoder.finish().unwrap_or_default()
+}
+
+/// Write one frame plus its newline under a single lock so concurrent replies
+/// to the agent can't interleave with it
+async fn write_frame(
+    stdin: &Mutex<tokio::process::ChildStdin>,
+    frame: &[u8],
+) -> std::io::Result<()> {
+    let mut line = Vec::with_capacity(frame.len() + 1);
+    line.extend_from_slice(frame);
+    line.push(b'\n');
+    stdin.lock().await.write_all(&line).await
 }
 
 // --- Tests ---
 #[cfg(test)]
 mod tests {
     use super::*;
-    use tokio::time::{sleep, Duration};
+    use crate::utils::ndjson::{MAX_FRAME_BYTES, MAX_INFLATED_BYTES};
+    use serde_json::json;
+    use tokio::time::Duration;
     use tokio_tungstenite::tungstenite::client::IntoClientRequest;
     use tokio_tungstenite::tungstenite::http::header::{HeaderName, HeaderValue};
     use tokio_tungstenite::{connect_async, connect_async_with_config, tungstenite::Message};
 
-    async fn find_free_port(start: u16) -> u16 {
-        for p in start..(start + 50) {
-            if tokio::net::TcpListener::bind(("127.0.0.1", p)).await.is_ok() {
-                return p;
+    /// Frames a push completed, none of them oversized
+    fn lines(frames: Vec<Result<Vec<u8>>>) -> Vec<Vec<u8>> {
+        frames.into_iter().map(|frame| frame.expect("frame in bounds")).collect()
+    }
+
+    #[test]
+    fn framer_joins_frames_split_across_reads() {
+        let mut framer = NdjsonFramer::default();
+        assert!(framer.push(br#"{"jsonrpc":"2.0","id":1,"#).is_empty());
+        assert!(framer.push(br#""result":{}}"#).is_empty());
+        let frames = lines(framer.push(b"\r\n"));
+        assert_eq!(frames, vec![br#"{"jsonrpc":"2.0","id":1,"result":{}}"#.to_vec()]);
+
+        // A multi-byte character split between reads survives intact
+        let text = "{\"text\":\"héllo\"}\n".as_bytes();
+        let split = text.iter().position(|&b| b >= 0x80).unwrap() + 1;
+        assert!(framer.push(&text[..split]).is_empty());
+        let frames = lines(framer.push(&text[split..]));
+        assert_eq!(String::from_utf8(frames[0].clone()).unwrap(), "{\"text\":\"héllo\"}");
+        assert_eq!(framer.finish(), None);
+    }
+
+    #[test]
+    fn framer_splits_concatenated_frames() {
+        let mut framer = NdjsonFramer::default();
+        let frames = lines(framer.push(b"{\"id\":1}\n\n{\"id\":2}\n{\"id\":"));
+        assert_eq!(frames, vec![b"{\"id\":1}".to_vec(), b"{\"id\":2}".to_vec()]);
+        assert_eq!(lines(framer.push(b"3}\n")), vec![b"{\"id\":3}".to_vec()]);
+        assert!(framer.push(b"{\"id\":4}").is_empty());
+        assert_eq!(framer.finish(), Some(b"{\"id\":4}".to_vec()));
+
+        assert_eq!(ws_text_frames("{\"id\":1}\n{\"id\":2}\n"), vec!["{\"id\":1}", "{\"id\":2}"]);
+        assert_eq!(ws_text_frames("{\n  \"id\": 1\n}"), vec!["{\"id\":1}"]);
+        assert!(ws_text_frames("  \n").is_empty());
+    }
+
+    #[test]
+    fn framer_drops_lines_over_the_limit() {
+        let mut framer = NdjsonFramer::default();
+        let half = vec![b'x'; MAX_FRAME_BYTES / 2 + 1];
+        assert!(framer.push(&half).is_empty());
+        let frames = framer.push(&half);
+        assert_eq!(frames.len(), 1);
+        assert!(frames[0].is_err());
+
+        // The rest of the long line is skipped, not buffered
+        assert!(framer.push(&half).is_empty());
+        let frames = lines(framer.push(b"tail\n{\"id\":1}\n"));
+        assert_eq!(frames, vec![b"{\"id\":1}".to_vec()]);
+
+        let mut long = vec![b'x'; MAX_FRAME_BYTES + 1];
+        long.extend_from_slice(b"\n{\"id\":2}\n");
+        let frames = framer.push(&long);
+        assert!(frames[0].is_err());
+        assert_eq!(frames[1].as_ref().unwrap(), b"{\"id\":2}");
+    }
+
+    #[test]
+    fn router_keeps_replies_private_and_serializes_prompts() {
+        use serde_json::json;
+        let mut router = RequestRouter::default();
+
+        // Both clients use id 1; the agent sees distinct ids and each reply
+        // goes back to its sender with the original id
+        let init = json!({"jsonrpc":"2.0","id":1,"method":"initialize","params":{}});
+        let ClientRoute::Agent(sent) = router.route_client(1, init.clone()) else { panic!() };
+        let reply = json!({"jsonrpc":"2.0","id":sent["id"],"result":{"protocolVersion":1}});
+        assert_eq!(
+            router.route_agent(&reply),
+            AgentRoute::Client(1, json!({"jsonrpc":"2.0","id":1,"result":{"protocolVersion":1}}))
+        );
+        // A second client gets the setup result without reaching the agent
+        assert_eq!(
+            router.route_client(2, init),
+            ClientRoute::Reply(json!({"jsonrpc":"2.0","id":1,"result":{"protocolVersion":1}}))
+        );
+
+        let prompt = |id: u64| json!({"jsonrpc":"2.0","id":id,"method":"session/prompt","params":{}});
+        let ClientRoute::Agent(first) = router.route_client(1, prompt(2)) else { panic!() };
+        assert_eq!(router.route_client(2, prompt(2)), ClientRoute::Queued);
+        assert_eq!(router.take_next_prompt(), None);
+        let done = json!({"jsonrpc":"2.0","id":first["id"],"result":{"stopReason":"end_turn"}});
+        assert!(matches!(router.route_agent(&done), AgentRoute::Client(1, _)));
+        // A repeated reply reaches nobody
+        assert_eq!(router.route_agent(&done), AgentRoute::Drop);
+        let (owner, second) = router.take_next_prompt().expect("queued prompt released");
+        assert_eq!(owner, 2);
+        assert_ne!(second["id"], first["id"]);
+
+        // The running prompt's session is known, so shutdown can cancel it
+        let busy = json!({"jsonrpc":"2.0","id":4,"method":"session/prompt","params":{"sessionId":"s9"}});
+        let second_done = json!({"jsonrpc":"2.0","id":second["id"],"result":{"stopReason":"end_turn"}});
+        router.route_agent(&second_done);
+        assert_eq!(router.prompt_session, None);
+        assert!(matches!(router.route_client(1, busy), ClientRoute::Agent(_)));
+        assert_eq!(router.prompt_session.as_deref(), Some("s9"));
+        router.reset();
+        assert_eq!(router.prompt_session, None);
+
+        // Updates are broadcast, and an agent request takes only the first answer
+        let update = json!({"jsonrpc":"2.0","method":"session/update","params":{}});
+        assert_eq!(router.route_agent(&update), AgentRoute::All);
+        let ask = json!({"jsonrpc":"2.0","id":7,"method":"session/request_permission","params":{}});
+        assert_eq!(router.route_agent(&ask), AgentRoute::All);
+        let answer = json!({"jsonrpc":"2.0","id":7,"result":{}});
+        assert_eq!(router.route_client(2, answer.clone()), ClientRoute::Agent(answer.clone()));
+        assert_eq!(router.route_client(1, answer), ClientRoute::Drop);
+
+        // A recycled agent keeps its initialize result but not the session
+        let new_session = json!({"jsonrpc":"2.0","id":3,"method":"session/new","params":{}});
+        let ClientRoute::Agent(sent) = router.route_client(1, new_session.clone()) else { panic!() };
+        let created = json!({"jsonrpc":"2.0","id":sent["id"],"result":{"sessionId":"s1"}});
+        assert!(matches!(router.route_agent(&created), AgentRoute::Client(1, _)));
+        assert!(matches!(router.route_client(2, new_session.clone()), ClientRoute::Reply(_)));
+        router.reset();
+        assert!(matches!(router.route_client(3, new_session), ClientRoute::Agent(_)));
+        let init = json!({"jsonrpc":"2.0","id":1,"method":"initialize","params":{}});
+        assert!(matches!(router.route_client(3, init), ClientRoute::Reply(_)));
+
+        assert_eq!(session_name(Some("x=1&session=work")), Some("work".to_string()));
+        assert_eq!(session_name(Some("session=")), None);
+        assert_eq!(session_name(None), None);
+    }
+
+    #[test]
+    fn presence_lists_clients_and_activity_names_the_sender() {
+        let text = |msg: Message| match msg {
+            Message::Text(text) => serde_json::from_str::<serde_json::Value>(&text).unwrap(),
+            other => panic!("unexpected {:?}", other),
+        };
+        let mut clients = ClientSet::default();
+        let (first, _, mut first_rx) = clients.attach(false);
+        let (watcher, _, _watcher_rx) = clients.attach(true);
+        clients.announce_presence();
+        let presence = text(first_rx.try_recv().unwrap());
+        assert_eq!(presence["method"], "rat/presence");
+        assert_eq!(presence["params"]["clientId"], first);
+        assert_eq!(
+            presence["params"]["clients"],
+            serde_json::json!([
+                {"clientId": first, "role": "participant"},
+                {"clientId": watcher, "role": "observer"}
+            ])
+        );
+        assert_eq!(clients.detach(watcher), 1);
+        clients.announce_presence();
+        assert_eq!(text(first_rx.try_recv().unwrap())["params"]["clients"].as_array().unwrap().len(), 1);
+
+        let prompted = text(activity(first, "prompt", serde_json::json!({"prompt": []})));
+        assert_eq!(prompted["method"], "rat/activity");
+        assert_eq!(prompted["params"], serde_json::json!({"clientId": first, "kind": "prompt", "prompt": []}));
+    }
+
+    #[test]
+    fn observers_follow_the_session_but_cannot_drive_it() {
+        use serde_json::json;
+        let mut router = RequestRouter::default();
+        let init = json!({"jsonrpc":"2.0","id":1,"method":"initialize","params":{}});
+        let ClientRoute::Agent(sent) = router.route_client(1, init.clone()) else { panic!() };
+        router.route_agent(&json!({"jsonrpc":"2.0","id":sent["id"],"result":{"protocolVersion":1}}));
+
+        // Setup results are replayed; a setup call nobody made yet is refused
+        assert_eq!(
+            router.route_observer(2, &init),
+            ClientRoute::Reply(json!({"jsonrpc":"2.0","id":1,"result":{"protocolVersion":1}}))
+        );
+        let new_session = json!({"jsonrpc":"2.0","id":2,"method":"session/new","params":{}});
+        let ClientRoute::Reply(refused) = router.route_observer(2, &new_session) else { panic!() };
+        assert_eq!(refused["error"]["code"], READ_ONLY_ERROR);
+
+        let prompt = json!({"jsonrpc":"2.0","id":3,"method":"session/prompt","params":{}});
+        assert!(matches!(router.route_observer(2, &prompt), ClientRoute::Reply(v) if v.get("error").is_some()));
+        assert!(!router.prompt_running);
+
+        // Observers can't answer permission prompts or cancel
+        router.route_agent(&json!({"jsonrpc":"2.0","id":7,"method":"session/request_permission","params":{}}));
+        let answer = json!({"jsonrpc":"2.0","id":7,"result":{}});
+        assert_eq!(router.route_observer(2, &answer), ClientRoute::Drop);
+        assert_eq!(router.route_client(1, answer.clone()), ClientRoute::Agent(answer));
+        let cancel = json!({"jsonrpc":"2.0","method":"session/cancel","params":{}});
+        assert_eq!(router.route_observer(2, &cancel), ClientRoute::Drop);
+
+        assert!(is_observer(Some("session=work&role=observer")));
+        assert!(!is_observer(Some("session=work")));
+        assert!(!is_observer(None));
+    }
+
+    #[test]
+    fn always_allow_covers_the_path_until_its_client_leaves() {
+        let mut grants = PermissionGrants::default();
+        grants.ask("7", "write_text_file", "/work/src");
+        grants.ask("8", "terminal_execute", "cargo test");
+        assert_eq!(grants.covering("write_text_file", "/work/src/main.rs"), None);
+
+        grants.settle("7", Some(2));
+        grants.settle("8", None);
+        assert_eq!(grants.covering("write_text_file", "/work/src/main.rs"), Some(2));
+        assert_eq!(grants.covering("write_text_file", "/work/srcs/main.rs"), None);
+        assert_eq!(
+            grants.covering("write_text_file", "/work/src/../../home/u/.ssh/authorized_keys"),
+            None
+        );
+        assert_eq!(grants.covering("write_text_file", "/work/src/gen/../main.rs"), None);
+        assert_eq!(grants.covering("delete_file", "/work/src/main.rs"), None);
+        // Only "always" answers leave a grant behind
+        assert_eq!(grants.covering("terminal_execute", "cargo test"), None);
+        assert!(grants.asked.is_empty());
+
+        grants.ask("9", "terminal_execute", "cargo test");
+        grants.settle("9", Some(3));
+        assert_eq!(grants.covering("terminal_execute", "cargo test"), Some(3));
+        assert_eq!(grants.covering("terminal_execute", "cargo test --release"), None);
+
+        grants.revoke(2);
+        assert_eq!(grants.covering("write_text_file", "/work/src/main.rs"), None);
+        grants.grants[0].expires = Instant::now();
+        assert_eq!(grants.covering("terminal_execute", "cargo test"), None);
+    }
+
+    #[test]
+    fn compressed_frames_round_trip_and_stay_bounded() {
+        let update = json!({"jsonrpc":"2.0","method":"session/update","params":{"diff":"x".repeat(64 * 1024)}}).to_string();
+        let packed = gzip(update.as_bytes());
+        assert!(is_gzip(&packed));
+        assert!(packed.len() < update.len() / 10);
+        assert_eq!(gunzip(&packed).unwrap(), update);
+        // NDJSON never looks compressed
+        assert!(!is_gzip(b"{\"jsonrpc\":\"2.0\"}\n"));
+
+        let bomb = gzip(&vec![b' '; MAX_INFLATED_BYTES as usize + 1]);
+        assert!(gunzip(&bomb).is_err());
+
+        assert!(wants_compression(Some("session=work&compress=gzip")));
+        assert!(!wants_compression(Some("session=work")));
+    }
+
+    #[test]
+    fn tool_call_times_report_calls_once_finished() {
+        let mut times = ToolCallTimes::default();
+        let started = serde_json::json!({"sessionUpdate": "tool_call", "toolCallId": "t1", "kind": "edit", "status": "pending"});
+        assert!(times.observe(&started).is_none());
+        let progress = serde_json::json!({"sessionUpdate": "tool_call_update", "toolCallId": "t1", "status": "in_progress"});
+        assert!(times.observe(&progress).is_none());
+
+        let done = serde_json::json!({"sessionUpdate": "tool_call_update", "toolCallId": "t1", "status": "failed"});
+        let (kind, status, _) = times.observe(&done).unwrap();
+        assert_eq!((kind.as_str(), status), ("edit", "failed"));
+        // Reported once, and never for calls it didn't see start
+        assert!(times.observe(&done).is_none());
+        assert!(times.running.is_empty());
+    }
+
+    #[tokio::test]
+    async fn keep_alive_pings_and_gives_up_on_silent_clients() {
+        let options = ConnectionOptions {
+            ping_interval: Duration::from_millis(20),
+            idle_timeout: None,
+            ..Default::default()
+        };
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let liveness = Liveness::new();
+        assert_eq!(keep_alive(options, &liveness, &tx).await, "no answer to pings");
+        assert!(matches!(rx.try_recv(), Ok(Message::Ping(_))));
+
+        // A client that answers stays connected until the idle timeout
+        let options = ConnectionOptions {
+            ping_interval: Duration::from_millis(20),
+            idle_timeout: Some(Duration::from_millis(100)),
+            ..Default::default()
+        };
+        let liveness = Arc::new(Liveness::new());
+        let answering = liveness.clone();
+        let pongs = tokio::spawn(async move {
+            while let Some(Message::Ping(_)) = rx.recv().await {
+                answering.heard(false);
+            }
+        });
+        let started = Instant::now();
+        assert_eq!(keep_alive(options, &liveness, &tx).await, "idle timeout");
+        assert!(started.elapsed() >= Duration::from_millis(100));
+        pongs.abort();
+    }
+
+    /// An agent that answers every request with an empty result
+    #[cfg(unix)]
+    fn answering_agent() -> AgentCommand {
+        let script = r#"while IFS= read -r line; do id=${line#*\"id\":}; id=${id%%[,\}]*}; printf '{"jsonrpc":"2.0","id":%s,"result":{}}\n' "$id"; done"#;
+        AgentCommand::new(PathBuf::from("sh")).with_args(vec!["-c".to_string(), script.to_string()])
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn pool_hands_out_warm_agents_and_stops_idle_ones() {
+        let options = PoolOptions {
+            size: 1,
+            idle_ttl: Duration::ZERO,
+        };
+        let pool = Arc::new(AgentPool::new(Some(answering_agent()), options));
+
+        // Wait for the pool to warm up a spare in the background
+        let spare = || async {
+            tokio::time::timeout(Duration::from_secs(10), async {
+                loop {
+                    if let Some((_, agent)) = pool.idle.lock().await.first() {
+                        break agent.clone();
+                    }
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                }
+            })
+            .await
+            .expect("a warm agent in the pool")
+        };
+
+        // The first connection starts an agent; the next one gets the spare
+        let first = pool.take().await.unwrap();
+        let warm = spare().await;
+        assert!(!Arc::ptr_eq(&first, &warm));
+        let second = pool.take().await.unwrap();
+        assert!(Arc::ptr_eq(&second, &warm));
+
+        // With the pool full again, a recycled agent is stopped
+        let refilled = spare().await;
+        assert!(!Arc::ptr_eq(&refilled, &second));
+        pool.recycle(first.clone()).await;
+        assert!(!first.is_running().await);
+
+        // Agents idle past the TTL are stopped too
+        pool.reap().await;
+        assert!(pool.idle.lock().await.is_empty());
+        assert!(!refilled.is_running().await);
+
+        pool.shutdown().await;
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn shutdown_closes_connections_and_returns() {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await.expect("bind");
+        let port = listener.local_addr().expect("local addr").port();
+        let (stop, stopped) = oneshot::channel::<()>();
+        let options = ServeOptions {
+            agent: Some(answering_agent()),
+            ..Default::default()
+        };
+        let server = tokio::spawn(serve(listener, options, async {
+            let _ = stopped.await;
+        }));
+
+        let (mut ws, _resp) = connect_async(format!("ws://127.0.0.1:{}/", port))
+            .await
+            .expect("connect ok");
+        let init = json!({"jsonrpc":"2.0","id":1,"method":"initialize","params":{}});
+        ws.send(Message::Text(init.to_string())).await.expect("send");
+        loop {
+            let Message::Text(text) = ws.next().await.expect("reply").expect("ok") else { continue };
+            let reply: serde_json::Value = serde_json::from_str(&text).expect("JSON");
+            if reply["id"] == 1 {
+                break;
             }
         }
-        start
+
+        stop.send(()).unwrap();
+        let closed = loop {
+            match ws.next().await {
+                Some(Ok(Message::Close(frame))) => break frame,
+                Some(Ok(_)) => continue,
+                other => panic!("expected a close frame, got {:?}", other),
+            }
+        };
+        assert_eq!(closed.expect("close frame").reason, "bridge shutting down");
+        // Answering the close lets the bridge finish without waiting out its grace period
+        while let Some(Ok(_)) = ws.next().await {}
+        tokio::time::timeout(Duration::from_secs(2), server)
+            .await
+            .expect("serve returns before the grace period is up")
+            .unwrap()
+            .unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn oversized_binary_message_closes_the_connection() {
+        let port = spawn_bridge_with(ServeOptions {
+            agent: Some(answering_agent()),
+            ..Default::default()
+        })
+        .await;
+        let (mut ws, _resp) = connect_async(format!("ws://127.0.0.1:{}/", port))
+            .await
+            .expect("connect ok");
+
+        // Two frames of one unterminated line, together over the limit
+        let half = vec![b'x'; MAX_FRAME_BYTES / 2 + 1];
+        ws.send(Message::Binary(half.clone())).await.expect("send");
+        ws.send(Message::Binary(half)).await.expect("send");
+        let closed = loop {
+            match ws.next().await {
+                Some(Ok(Message::Close(frame))) => break frame,
+                Some(Ok(_)) => continue,
+                other => panic!("expected a close frame, got {:?}", other),
+            }
+        };
+        assert_eq!(closed.expect("close frame").code, CloseCode::Size);
+    }
+
+    #[tokio::test]
+    async fn plain_http_gets_the_web_client() {
+        let port = spawn_bridge().await;
+
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).await.expect("connect ok");
+        stream
+            .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .await
+            .expect("send");
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.expect("read");
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("<title>RAT</title>"));
+        assert!(!response.contains("__RAT_CWD__"));
+    }
+
+    #[tokio::test]
+    async fn http_api_needs_the_token_and_stays_in_the_workspace() {
+        let port = spawn_bridge_with(ServeOptions {
+            token: Some("s3cret".to_string()),
+            ..Default::default()
+        })
+        .await;
+
+        let request = |raw: String| async move {
+            let mut stream = TcpStream::connect(("127.0.0.1", port)).await.expect("connect ok");
+            stream.write_all(raw.as_bytes()).await.expect("send");
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.expect("read");
+            response
+        };
+        let host = format!("Host: 127.0.0.1:{}\r\n", port);
+        let auth = "Authorization: Bearer s3cret\r\n";
+        let anonymous = request(format!("GET /api/v1/sessions HTTP/1.1\r\n{}\r\n", host)).await;
+        assert!(anonymous.starts_with("HTTP/1.1 401 Unauthorized"));
+        let foreign = request(format!(
+            "GET /api/v1/sessions HTTP/1.1\r\n{}{}Origin: http://evil.example\r\n\r\n",
+            host, auth
+        ))
+        .await;
+        assert!(foreign.starts_with("HTTP/1.1 403 Forbidden"));
+        let form = request(format!(
+            "POST /api/v1/sessions/nope/prompt HTTP/1.1\r\n{}{}Content-Type: text/plain\r\nContent-Length: 13\r\n\r\n{{\"text\":\"hi\"}}",
+            host, auth
+        ))
+        .await;
+        assert!(form.starts_with("HTTP/1.1 415 Unsupported Media Type"));
+
+        let listed = request(format!(
+            "GET /api/v1/sessions HTTP/1.1\r\n{}{}Origin: http://127.0.0.1:{}\r\n\r\n",
+            host, auth, port
+        ))
+        .await;
+        assert!(listed.starts_with("HTTP/1.1 200 OK"));
+        assert!(listed.ends_with(r#"{"sessions":[]}"#));
+        let json = "Content-Type: application/json\r\n";
+        let missing = request(format!(
+            "POST /api/v1/sessions/nope/prompt HTTP/1.1\r\n{}{}{}Content-Length: 13\r\n\r\n{{\"text\":\"hi\"}}",
+            host, auth, json
+        ))
+        .await;
+        assert!(missing.starts_with("HTTP/1.1 404 Not Found"));
+        let outside = request(format!(
+            "POST /api/v1/sessions HTTP/1.1\r\n{}{}{}Content-Length: 11\r\n\r\n{{\"cwd\":\"/\"}}",
+            host, auth, json
+        ))
+        .await;
+        assert!(outside.starts_with("HTTP/1.1 403 Forbidden"));
+    }
+
+    #[tokio::test]
+    async fn metrics_need_the_token_when_one_is_set() {
+        crate::metrics::enable();
+        let port = spawn_bridge_with(ServeOptions {
+            token: Some("s3cret".to_string()),
+            ..Default::default()
+        })
+        .await;
+
+        let scrape = |auth: &'static str| async move {
+            let mut stream = TcpStream::connect(("127.0.0.1", port)).await.expect("connect ok");
+            let raw = format!("GET /metrics HTTP/1.1\r\nHost: localhost\r\n{}\r\n", auth);
+            stream.write_all(raw.as_bytes()).await.expect("send");
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.expect("read");
+            response
+        };
+        let anonymous = scrape("").await;
+        assert!(anonymous.starts_with("HTTP/1.1 401 Unauthorized"));
+        assert!(!anonymous.contains("rat_active_connections"));
+        let wrong = scrape("Authorization: Bearer guess\r\n").await;
+        assert!(wrong.starts_with("HTTP/1.1 401 Unauthorized"));
+
+        let scraped = scrape("Authorization: Bearer s3cret\r\n").await;
+        assert!(scraped.starts_with("HTTP/1.1 200 OK"));
+        assert!(scraped.contains("rat_active_connections"));
+    }
+
+    /// A bridge on a free loopback port with no agent, so clients get the
+    /// echo fallback
+    async fn spawn_bridge() -> u16 {
+        spawn_bridge_with(ServeOptions::default()).await
+    }
+
+    async fn spawn_bridge_with(options: ServeOptions) -> u16 {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await.expect("bind");
+        let port = listener.local_addr().expect("local addr").port();
+        tokio::spawn(serve(listener, options, std::future::pending()));
+        port
+    }
+
+    #[tokio::test]
+    async fn ws_requires_the_token_when_one_is_set() {
+        let port = spawn_bridge_with(ServeOptions {
+            token: Some("s3cret".to_string()),
+            ..Default::default()
+        })
+        .await;
+
+        let refused = connect_async(format!("ws://127.0.0.1:{}/?session=work", port)).await;
+        assert!(matches!(
+            refused,
+            Err(tokio_tungstenite::tungstenite::Error::Http(response)) if response.status() == 401
+        ));
+
+        let url = format!("ws://127.0.0.1:{}/?session=work&token=s3cret", port);
+        let (mut ws, _resp) = connect_async(url).await.expect("connect with token");
+        ws.close(None).await.ok();
+
+        let mut req = format!("ws://127.0.0.1:{}/", port).into_client_request().expect("valid request");
+        req.headers_mut()
+            .insert("authorization", HeaderValue::from_static("Bearer s3cret"));
+        let (mut ws, _resp) = connect_async(req).await.expect("connect with bearer");
+        ws.close(None).await.ok();
     }
 
     #[tokio::test]
     async fn ws_handshake_echoes_acp_subprotocol() {
-        let port = find_free_port(8950).await;
-        tokio::spawn(async move {
-            let _ = start_local_ws_server(port).await;
-        });
-        sleep(Duration::from_millis(100)).await;
+        let port = spawn_bridge().await;
 
         let url = format!("ws://127.0.0.1:{}/", port);
         let mut req = url.into_client_request().expect("valid request");
@@ -671,14 +2616,7 @@ mod tests {
 
     #[tokio::test]
     async fn ws_accepts_acp_text_frames_in_echo_mode() {
-        // Ensure no agent is auto-resolved by clearing env
-        std::env::remove_var("RAT2E_AGENT_CMD");
-
-        let port = find_free_port(8960).await;
-        tokio::spawn(async move {
-            let _ = start_local_ws_server(port).await;
-        });
-        sleep(Duration::from_millis(100)).await;
+        let port = spawn_bridge().await;
 
         let url = format!("ws://127.0.0.1:{}/", port);
         let (mut ws, _resp) = connect_async(url).await.expect("connect ok");
@@ -692,11 +2630,12 @@ mod tests {
         let init = r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":1,"clientCapabilities":{"fs":{"readTextFile":true,"writeTextFile":true},"terminal":false}}}"#;
         ws.send(Message::Text(init.to_string())).await.expect("send");
 
-        // Expect echo wrapper containing our JSON
+        // Expect echo wrapper carrying our frame as it was sent
         let msg = ws.next().await.expect("echo").expect("ok");
         let echoed = match msg { Message::Text(t) => t, _ => String::new() };
-        assert!(echoed.contains("\"jsonrpc\":\"2.0\""));
-        assert!(echoed.contains("\"method\":\"initialize\""));
+        let echoed: serde_json::Value = serde_json::from_str(&echoed).expect("echo is JSON");
+        assert_eq!(echoed["type"], "echo");
+        assert_eq!(echoed["original"], init);
 
         ws.close(None).await.ok();
     }
@@ -1,50 +1,90 @@
-use anyhow::Result;
-use clap::Parser;
-use log::{info, warn};
-use std::fs::OpenOptions;
-use std::io::Write;
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
+use tracing::info;
 
 mod acp;
 mod adapters;
 mod app;
+mod commands;
 mod config;
 mod effects;
+mod embed;
+mod events;
+mod headless;
+mod integrations;
 mod pairing;
 mod ui;
 mod utils;
 mod local_ws;
+mod local_web;
+mod discovery;
+mod relay;
+mod http_api;
+mod transfer;
+mod metrics;
+mod telemetry;
+mod bridge_log;
 
 use app::App;
-use config::Config;
+use config::{Config, LogFormat};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer};
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Commands>,
+
     /// Configuration file path
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     config: Option<String>,
 
+    /// Config profile to apply (from `[profiles.<name>]` or `profiles/<name>.toml`)
+    #[arg(long, global = true)]
+    profile: Option<String>,
+
+    /// Workspace directory for sessions, agent processes and file access
+    /// (defaults to the current directory)
+    #[arg(long, global = true)]
+    cwd: Option<std::path::PathBuf>,
+
     /// Verbose logging
-    #[arg(short, long, action = clap::ArgAction::Count)]
+    #[arg(short, long, action = clap::ArgAction::Count, global = true)]
     verbose: u8,
 
-    /// Agent to start with (claude-code, gemini)
-    #[arg(short, long)]
-    agent: Option<String>,
+    /// Agent to start with (claude-code, gemini); repeat to open one tab per agent
+    #[arg(short, long, global = true)]
+    agent: Vec<String>,
 
     /// Override agent command (path or program). When set, RAT registers
     /// an external agent with this command and optional args.
-    #[arg(long)]
+    #[arg(long, global = true)]
     agent_cmd: Option<String>,
 
     /// Arguments for --agent-cmd; can be repeated. Hyphenated values allowed.
-    #[arg(long = "agent-arg", allow_hyphen_values = true)]
+    #[arg(long = "agent-arg", allow_hyphen_values = true, global = true)]
     agent_args: Vec<String>,
 
+    /// Login command for --agent-cmd, run when the agent reports that
+    /// authentication is required; repeat for its arguments.
+    #[arg(long = "agent-login", allow_hyphen_values = true, global = true)]
+    agent_login: Vec<String>,
+
     /// Name to register the external agent under (default: "sim").
-    #[arg(long)]
+    #[arg(long, global = true)]
     agent_name: Option<String>,
 
+    /// Headless runs: print nothing on stderr; rely on the exit code
+    #[arg(short, long, global = true)]
+    quiet: bool,
+
+    /// Headless runs: report the fatal error as a JSON object on stderr
+    #[arg(long, global = true)]
+    json_errors: bool,
+
     /// Disable all effects (theme animations, chat sweeps, etc.)
     #[arg(long)]
     no_effects: bool,
@@ -57,6 +97,15 @@ struct Cli {
     #[arg(long)]
     pair: bool,
 
+    /// Serve agent sessions over msgpack-RPC on stdin/stdout instead of
+    /// the TUI, for an editor plugin such as a Neovim frontend
+    #[arg(long, conflicts_with = "pair")]
+    embed: bool,
+
+    /// Lua function `--embed` passes events to in Neovim
+    #[arg(long, value_name = "LUA", default_value = embed::DEFAULT_CALLBACK, requires = "embed")]
+    embed_callback: String,
+
     /// Start local WebSocket server for direct connections (development mode)
     #[arg(long)]
     local_ws: bool,
@@ -64,93 +113,252 @@ struct Cli {
     /// Port for local WebSocket server (default: 8081)
     #[arg(long, default_value = "8081")]
     local_port: u16,
+
+    /// Initialized agents the local WebSocket server keeps ready (0 disables pooling)
+    #[arg(long, default_value_t = 1)]
+    local_pool_size: usize,
+
+    /// Seconds a pooled agent may sit unused before it is stopped
+    #[arg(long, default_value_t = 600)]
+    local_pool_idle_secs: u64,
+
+    /// Seconds between WebSocket pings to local clients (0 disables them)
+    #[arg(long, default_value_t = 30)]
+    local_ping_secs: u64,
+
+    /// Close local connections after this many minutes without messages (0 never does)
+    #[arg(long, default_value_t = 0)]
+    local_idle_timeout_mins: u64,
+
+    /// Gzip messages of at least this many bytes for local clients that ask for it (0 never does)
+    #[arg(long, default_value_t = 16384)]
+    local_compress_threshold: usize,
+
+    /// Directory of web UI assets (e.g. a built rat-web/dist) to serve instead of the built-in page
+    #[arg(long)]
+    local_web_root: Option<std::path::PathBuf>,
+
+    /// Secret local clients must present, as `Authorization: Bearer <token>` or `?token=<token>`
+    #[arg(long, env = "RAT_BRIDGE_TOKEN", hide_env_values = true)]
+    local_token: Option<String>,
+
+    /// Advertise the local WebSocket server on the LAN via mDNS; needs --local-token
+    #[arg(long)]
+    mdns: bool,
+
+    /// Serve Prometheus metrics at /metrics on the local WebSocket port
+    #[arg(long)]
+    local_metrics: bool,
+
+    /// Write a JSONL event log per local connection (handshake, frames, permissions) into this directory
+    #[arg(long, value_name = "DIR")]
+    local_bridge_log: Option<std::path::PathBuf>,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// Send a single prompt without the TUI and stream the answer to stdout
+    Exec {
+        /// Prompt to send (optional with --stdin, which then becomes the prompt)
+        prompt: Option<String>,
+
+        /// Attach standard input as context, e.g. `git diff | rat exec --stdin "review this"`
+        #[arg(long)]
+        stdin: bool,
+
+        /// Give up after this many seconds
+        #[arg(long)]
+        timeout: Option<u64>,
+
+        /// Output format: plain text, or NDJSON with one object per session update
+        #[arg(long, value_enum, default_value_t = headless::OutputFormat::Text)]
+        output: headless::OutputFormat,
+
+        #[command(flatten)]
+        approval: headless::ApprovalArgs,
+    },
+    /// Re-send a prompt whenever watched files change
+    Watch {
+        /// Prompt to send on every change
+        #[arg(long)]
+        prompt: String,
+
+        /// Files or directories to watch
+        #[arg(long, num_args = 1.., default_value = ".")]
+        paths: Vec<std::path::PathBuf>,
+
+        /// Quiet period in milliseconds before re-running
+        #[arg(long, default_value_t = 500)]
+        debounce_ms: u64,
+
+        /// Give up on a single run after this many seconds
+        #[arg(long)]
+        timeout: Option<u64>,
+
+        /// Output format: plain text, or NDJSON with one object per session update
+        #[arg(long, value_enum, default_value_t = headless::OutputFormat::Text)]
+        output: headless::OutputFormat,
+
+        #[command(flatten)]
+        approval: headless::ApprovalArgs,
+    },
+    /// Manage agent installations
+    Agents {
+        #[command(subcommand)]
+        action: commands::agents::AgentsAction,
+    },
+    /// Read, change and validate the config file
+    Config {
+        #[command(subcommand)]
+        action: commands::config::ConfigAction,
+    },
+    /// List, inspect, export and resume saved sessions
+    Sessions {
+        #[command(subcommand)]
+        action: commands::sessions::SessionsAction,
+    },
+    /// Run the local WebSocket bridge as a service, e.g. under systemd socket activation
+    Serve(commands::serve::ServeArgs),
+    /// List, revoke and re-key browsers paired through `rat --pair`
+    Pairing {
+        #[command(subcommand)]
+        action: commands::pairing::PairingAction,
+    },
+    /// Run a self-hosted pairing relay for `rat --pair`
+    Relay(commands::relay::RelayArgs),
+    /// Run ACP conformance checks against --agent-cmd and print a report
+    CheckAgent(commands::check_agent::CheckAgentArgs),
+    /// List RAT bridges advertised on the local network
+    Discover {
+        /// Seconds to listen for answers
+        #[arg(long, default_value_t = 3)]
+        timeout: u64,
+
+        /// Print the peers as a JSON array
+        #[arg(long)]
+        json: bool,
+    },
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
 
-    if cli.pair {
-        crate::pairing::start_pairing().await?;
+    if cli.local_ws {
+        if cli.mdns && cli.local_token.is_none() {
+            anyhow::bail!("--mdns tells the whole network where the bridge is; it needs --local-token (or RAT_BRIDGE_TOKEN)");
+        }
+        if cli.local_metrics {
+            crate::metrics::enable();
+        }
+        if let Some(dir) = cli.local_bridge_log.clone() {
+            crate::bridge_log::enable(dir)?;
+        }
+        let pool = crate::local_ws::PoolOptions {
+            size: cli.local_pool_size,
+            idle_ttl: std::time::Duration::from_secs(cli.local_pool_idle_secs),
+        };
+        let connection = crate::local_ws::ConnectionOptions {
+            ping_interval: std::time::Duration::from_secs(cli.local_ping_secs),
+            idle_timeout: (cli.local_idle_timeout_mins > 0)
+                .then_some(std::time::Duration::from_secs(cli.local_idle_timeout_mins * 60)),
+            compress_threshold: (cli.local_compress_threshold > 0).then_some(cli.local_compress_threshold),
+        };
+        crate::local_ws::start_local_ws_server(
+            cli.local_port,
+            pool,
+            connection,
+            cli.local_web_root.clone(),
+            cli.mdns,
+            cli.local_token.clone(),
+        )
+        .await?;
         return Ok(());
     }
 
-    if cli.local_ws {
-        crate::local_ws::start_local_ws_server(cli.local_port).await?;
-        return Ok(());
+    // `rat config` works on the file itself, so it runs even if loading would fail
+    if let Some(Commands::Config { action }) = &cli.command {
+        let path = commands::config::config_path(cli.config.as_deref())?;
+        let code = commands::config::run(action, &path).await?;
+        std::process::exit(code);
     }
 
-    // Initialize logging
-    let log_level = match cli.verbose {
-        0 => log::LevelFilter::Warn,
-        1 => log::LevelFilter::Info,
-        2 => log::LevelFilter::Debug,
-        _ => log::LevelFilter::Trace,
+    // `rat exec` and `rat watch` report setup failures like their own, so
+    // --quiet, --json-errors and the exit codes hold for them too
+    let headless_output = match &cli.command {
+        Some(Commands::Exec { output, .. }) | Some(Commands::Watch { output, .. }) => Some(*output),
+        _ => None,
+    };
+    let (quiet, json_errors) = (cli.quiet, cli.json_errors);
+    let setup_failed = |kind: headless::ExitKind, error: anyhow::Error| -> anyhow::Error {
+        if let Some(output) = headless_output {
+            exit(headless::setup_failed(output, quiet, json_errors, kind, &error));
+        }
+        error
     };
 
-    // Create logs directory if it doesn't exist
-    std::fs::create_dir_all("logs").unwrap_or_else(|e| {
-        eprintln!("Failed to create logs directory: {}", e);
-    });
-
-    // Set up file logging
-    let log_file = OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open("logs/rat.log")
-        .expect("Failed to create log file");
-
-    // Allow environment variable override for log level
-    let mut builder = env_logger::Builder::from_default_env();
+    // Resolve before --cwd changes what a relative path points at
+    let config_file = cli
+        .config
+        .as_ref()
+        .map(|path| {
+            std::fs::canonicalize(path).unwrap_or_else(|_| std::path::PathBuf::from(path))
+        });
 
-    // If RUST_LOG is not set, use the CLI verbose level
-    if std::env::var("RUST_LOG").is_err() {
-        builder.filter_level(log_level);
+    // Switch workspace before loading config, so the project `.rat.toml` is
+    // found from the workspace; everything else (session cwd, spawned agents,
+    // relative file access) follows the process working directory too.
+    if let Some(dir) = &cli.cwd {
+        let dir = std::fs::canonicalize(dir)
+            .with_context(|| format!("Invalid --cwd: {}", dir.display()))
+            .map_err(|e| setup_failed(headless::ExitKind::Usage, e))?;
+        std::env::set_current_dir(&dir)
+            .with_context(|| format!("Failed to change directory to {}", dir.display()))
+            .map_err(|e| setup_failed(headless::ExitKind::Usage, e))?;
+        info!("Using workspace: {}", dir.display());
     }
 
-    builder
-        .target(env_logger::Target::Pipe(Box::new(log_file)))
-        .format_timestamp_secs()
-        .format_module_path(true)
-        .format(|buf, record| {
-            writeln!(
-                buf,
-                "{} [{}] [{}:{}] [{}] - {}",
-                buf.timestamp(),
-                record.level(),
-                record.file().unwrap_or("unknown"),
-                record.line().unwrap_or(0),
-                record.module_path().unwrap_or("unknown"),
-                record.args()
-            )
-        })
-        .init();
+    // User config, project `.rat.toml` and RAT_SECTION__KEY environment
+    // variables; the flags below override all of them
+    let mut config = Config::load(config_file.as_deref())
+        .await
+        .map_err(|e| setup_failed(headless::ExitKind::Usage, e))?;
 
-    info!(
-        "Starting RAT (Rust Agent Terminal) v{}",
-        env!("CARGO_PKG_VERSION")
-    );
+    if let Some(name) = &cli.profile {
+        info!("Using profile: {}", name);
+        config = config
+            .with_profile(name)
+            .map_err(|e| setup_failed(headless::ExitKind::Usage, e))?;
+    }
 
-    // Load configuration
-    let mut config = match cli.config {
-        Some(path) => {
-            info!("Loading configuration from: {}", path);
-            Config::from_file(&path).await?
+    // CLI overrides for effects; reapplied when the config file is reloaded
+    let (no_effects, no_intro) = (cli.no_effects, cli.no_intro);
+    let cli_overrides = move |config: &mut Config| {
+        if no_effects {
+            config.ui.effects.enabled = false;
         }
-        None => {
-            info!("Using default configuration");
-            Config::default()
+        if no_intro {
+            config.ui.effects.startup.enabled = false;
         }
     };
+    cli_overrides(&mut config);
 
-    // CLI overrides for effects
-    if cli.no_effects {
-        config.ui.effects.enabled = false;
-    }
-    if cli.no_intro {
-        config.ui.effects.startup.enabled = false;
+    // Pairing sets up its own logging
+    if cli.pair {
+        crate::pairing::start_pairing(&config).await?;
+        return Ok(());
     }
 
+    // Initialize logging once the config says where the log goes; `rat serve`
+    // under systemd logs to the journal instead
+    let to_journal = matches!(&cli.command, Some(Commands::Serve(args)) if args.logs_to_journal());
+    init_logging(cli.verbose, &config, to_journal)?;
+
+    info!(
+        "Starting RAT (Rust Agent Terminal) v{}",
+        env!("CARGO_PKG_VERSION")
+    );
+
     // Initialize and run the application
     // Build optional external agent spec from CLI
     let external = if let Some(cmd) = cli.agent_cmd.clone() {
@@ -160,21 +368,218 @@ async fn main() -> Result<()> {
             path: cmd,
             args: cli.agent_args.clone(),
             env: None,
+            login_command: (!cli.agent_login.is_empty()).then(|| cli.agent_login.clone()),
         })
     } else {
         None
     };
 
+    if cli.embed {
+        let code = embed::run(config, external, cli.embed_callback).await?;
+        exit(code);
+    }
+
+    let mut resume = None;
+    match cli.command {
+        Some(Commands::Sessions {
+            action: commands::sessions::SessionsAction::Resume { id },
+        }) => {
+            let store = acp::SessionStore::from_config(&config);
+            resume = Some(store.load(&id).await?);
+        }
+        Some(Commands::Sessions { action }) => {
+            let code = commands::sessions::run(action, &config).await?;
+            exit(code);
+        }
+        Some(Commands::Pairing { action }) => {
+            let code = commands::pairing::run(action, &config).await?;
+            exit(code);
+        }
+        Some(Commands::Agents { action }) => {
+            let code = commands::agents::run(action, &config).await?;
+            exit(code);
+        }
+        Some(Commands::Exec {
+            prompt,
+            stdin,
+            timeout,
+            output,
+            approval,
+        }) => {
+            let piped = if stdin {
+                let mut input = String::new();
+                tokio::io::AsyncReadExt::read_to_string(&mut tokio::io::stdin(), &mut input)
+                    .await
+                    .context("Failed to read prompt context from stdin")
+                    .map_err(|e| setup_failed(headless::ExitKind::Usage, e))?;
+                Some(input)
+            } else {
+                None
+            };
+            // Without an explicit prompt, piped input is the prompt itself
+            let (prompt, context) = match (prompt, piped) {
+                (Some(prompt), context) => (prompt, context),
+                (None, Some(input)) => (input, None),
+                (None, None) => {
+                    let error = anyhow::anyhow!("exec needs a prompt or --stdin");
+                    return Err(setup_failed(headless::ExitKind::Usage, error));
+                }
+            };
+            let agent =
+                single_agent(cli.agent).map_err(|e| setup_failed(headless::ExitKind::Usage, e))?;
+            let opts = headless::ExecOptions {
+                prompt,
+                context,
+                agent,
+                timeout: timeout.map(std::time::Duration::from_secs),
+                output,
+                approval,
+                quiet: cli.quiet,
+                json_errors: cli.json_errors,
+            };
+            let code = headless::run_exec(config, external, opts).await?;
+            info!("Headless exec finished with exit code {}", code);
+            exit(code);
+        }
+        Some(Commands::Watch {
+            prompt,
+            paths,
+            debounce_ms,
+            timeout,
+            output,
+            approval,
+        }) => {
+            let agent =
+                single_agent(cli.agent).map_err(|e| setup_failed(headless::ExitKind::Usage, e))?;
+            let opts = headless::WatchOptions {
+                exec: headless::ExecOptions {
+                    prompt,
+                    context: None,
+                    agent,
+                    timeout: timeout.map(std::time::Duration::from_secs),
+                    output,
+                    approval,
+                    quiet: cli.quiet,
+                    json_errors: cli.json_errors,
+                },
+                paths,
+                debounce: std::time::Duration::from_millis(debounce_ms),
+            };
+            let code = headless::run_watch(config, external, opts).await?;
+            exit(code);
+        }
+        Some(Commands::CheckAgent(args)) => {
+            let Some(spec) = &external else {
+                eprintln!("rat check-agent needs --agent-cmd (and --agent-arg for its arguments)");
+                exit(headless::ExitKind::Usage.code());
+            };
+            let code = commands::check_agent::run(spec, &args).await?;
+            exit(code);
+        }
+        Some(Commands::Discover { timeout, json }) => {
+            let timeout = std::time::Duration::from_secs(timeout);
+            let code = commands::discover::run(timeout, json).await?;
+            exit(code);
+        }
+        Some(Commands::Serve(args)) => {
+            let code = commands::serve::run(&args).await?;
+            exit(code);
+        }
+        Some(Commands::Relay(args)) => {
+            let code = commands::relay::run(&args).await?;
+            exit(code);
+        }
+        Some(Commands::Config { .. }) | None => {}
+    }
+
     let mut app = App::new(config, external.clone()).await?;
+    app.watch_config(config_file, cli_overrides);
 
-    if let Some(agent_name) = cli.agent.or_else(|| external.as_ref().map(|e| e.name.clone())) {
-        info!("Starting with agent: {}", agent_name);
-        app.connect_agent(&agent_name).await?;
+    if let Some(session) = resume {
+        info!("Resuming session: {}", session.id);
+        app.resume_session(session);
+    } else {
+        let mut agents = cli.agent;
+        if agents.is_empty() {
+            agents.extend(external.as_ref().map(|e| e.name.clone()));
+        }
+        for agent_name in agents {
+            info!("Starting with agent: {}", agent_name);
+            app.open_agent(&agent_name)?;
+        }
     }
 
     // Run the TUI
     app.run().await?;
 
     info!("RAT terminated successfully");
+    telemetry::shutdown();
     Ok(())
 }
+
+/// Headless commands talk to one agent; reject repeated `--agent`
+fn single_agent(mut agents: Vec<String>) -> Result<Option<String>> {
+    if agents.len() > 1 {
+        return Err(anyhow::anyhow!("this command takes a single --agent"));
+    }
+    Ok(agents.pop())
+}
+
+fn init_logging(verbose: u8, config: &Config, to_journal: bool) -> Result<()> {
+    let log_level = match verbose {
+        0 => "warn",
+        1 => "info",
+        2 => "debug",
+        _ => "trace",
+    };
+
+    // RUST_LOG, when set, overrides the CLI verbose level
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(log_level));
+
+    // Spans reach the collector at info level whatever the log shows
+    let otel = telemetry::init(&config.telemetry)?.with_filter(LevelFilter::INFO);
+
+    let (output, log_path) = if to_journal {
+        let journal = tracing_subscriber::fmt::layer()
+            .with_writer(std::io::stderr)
+            .with_ansi(false)
+            .event_format(utils::logging::JournalFormat);
+        (journal.with_filter(filter).boxed(), None)
+    } else {
+        let log_file =
+            utils::log_file::RotatingFile::open(config.get_effective_log_file(), &config.logging)?;
+        let log_path = log_file.path().to_path_buf();
+        let file = tracing_subscriber::fmt::layer()
+            .with_writer(std::sync::Mutex::new(log_file))
+            .with_ansi(false)
+            .with_file(true)
+            .with_line_number(true);
+        let output = match config.logging.format {
+            LogFormat::Text => file.with_filter(filter).boxed(),
+            // Each event carries its spans, so a permission decision can be
+            // matched to the session and turn it came from
+            LogFormat::Json => file
+                .json()
+                .with_current_span(true)
+                .with_span_list(true)
+                .with_filter(filter)
+                .boxed(),
+        };
+        (output, Some(log_path))
+    };
+    tracing_subscriber::registry().with(otel).with(output).init();
+
+    if let Some(log_path) = log_path {
+        info!("Logging to {:?}", log_path);
+    }
+    if config.telemetry.enabled {
+        info!("Exporting telemetry to {}", config.telemetry.endpoint());
+    }
+    Ok(())
+}
+
+/// Exit with `code` once pending telemetry has been flushed
+fn exit(code: i32) -> ! {
+    telemetry::shutdown();
+    std::process::exit(code)
+}
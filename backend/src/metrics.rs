@@ -0,0 +1,320 @@
+//! Prometheus metrics for the bridge. Counting is always on and cheap; the
+//! `/metrics` endpoint on the bridge port is only served once enabled with
+//! `--local-metrics` or `rat serve --metrics`.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+
+/// Upper bounds, in seconds, of the turn latency histogram buckets
+const TURN_BUCKETS: [f64; 10] = [0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0, 600.0];
+
+/// Distinct tool kinds tracked before the rest are counted as `other`
+const MAX_TOOL_KINDS: usize = 32;
+
+struct Metrics {
+    enabled: AtomicBool,
+    connections: AtomicI64,
+    agents: AtomicI64,
+    restarts: AtomicU64,
+    prompts: AtomicU64,
+    /// Finished turns per bucket (not cumulative), plus one for `+Inf`
+    turn_buckets: [AtomicU64; TURN_BUCKETS.len() + 1],
+    turn_micros: AtomicU64,
+    tool_calls: Mutex<BTreeMap<String, u64>>,
+    permissions: Mutex<BTreeMap<&'static str, u64>>,
+    bytes_in: AtomicU64,
+    bytes_out: AtomicU64,
+}
+
+#[allow(clippy::declare_interior_mutable_const)]
+const ZERO: AtomicU64 = AtomicU64::new(0);
+
+static METRICS: Metrics = Metrics {
+    enabled: AtomicBool::new(false),
+    connections: AtomicI64::new(0),
+    agents: AtomicI64::new(0),
+    restarts: AtomicU64::new(0),
+    prompts: AtomicU64::new(0),
+    turn_buckets: [ZERO; TURN_BUCKETS.len() + 1],
+    turn_micros: AtomicU64::new(0),
+    tool_calls: Mutex::new(BTreeMap::new()),
+    permissions: Mutex::new(BTreeMap::new()),
+    bytes_in: AtomicU64::new(0),
+    bytes_out: AtomicU64::new(0),
+};
+
+/// Serve `/metrics` from now on
+pub fn enable() {
+    METRICS.enabled.store(true, Ordering::Relaxed);
+}
+
+pub fn enabled() -> bool {
+    METRICS.enabled.load(Ordering::Relaxed)
+}
+
+/// Counts a client connection as active until dropped
+pub struct ConnectionGuard(());
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        METRICS.connections.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+pub fn connection_opened() -> ConnectionGuard {
+    METRICS.connections.fetch_add(1, Ordering::Relaxed);
+    ConnectionGuard(())
+}
+
+pub fn agent_started() {
+    METRICS.agents.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn agent_exited() {
+    METRICS.agents.fetch_sub(1, Ordering::Relaxed);
+}
+
+/// `count` agents exited on their own and are being replaced
+pub fn agents_restarted(count: u64) {
+    METRICS.restarts.fetch_add(count, Ordering::Relaxed);
+    crate::telemetry::agents_restarted(count);
+}
+
+pub fn prompt_sent() {
+    METRICS.prompts.fetch_add(1, Ordering::Relaxed);
+}
+
+/// A prompt turn ended `elapsed` after the prompt reached the agent
+pub fn turn_finished(elapsed: Duration) {
+    let secs = elapsed.as_secs_f64();
+    let bucket = TURN_BUCKETS
+        .iter()
+        .position(|bound| secs <= *bound)
+        .unwrap_or(TURN_BUCKETS.len());
+    METRICS.turn_buckets[bucket].fetch_add(1, Ordering::Relaxed);
+    METRICS
+        .turn_micros
+        .fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+    crate::telemetry::turn_finished(elapsed);
+}
+
+pub fn tool_call(kind: &str) {
+    let mut calls = METRICS.tool_calls.lock().unwrap();
+    let kind = if calls.contains_key(kind) || calls.len() < MAX_TOOL_KINDS {
+        kind
+    } else {
+        "other"
+    };
+    *calls.entry(kind.to_string()).or_default() += 1;
+}
+
+/// A permission request was settled: `allowed`, `denied` or `cancelled`
+pub fn permission(outcome: &'static str) {
+    *METRICS
+        .permissions
+        .lock()
+        .unwrap()
+        .entry(outcome)
+        .or_default() += 1;
+}
+
+/// How a permission answer from a client settles, from its `outcome`
+pub fn permission_outcome(outcome: &serde_json::Value) -> &'static str {
+    let option = outcome["optionId"].as_str().unwrap_or_default();
+    match outcome["outcome"].as_str() {
+        Some("selected") if option.to_lowercase().starts_with("allow") => "allowed",
+        Some("selected") => "denied",
+        _ => "cancelled",
+    }
+}
+
+pub fn bytes_in(n: usize) {
+    METRICS.bytes_in.fetch_add(n as u64, Ordering::Relaxed);
+}
+
+pub fn bytes_out(n: usize) {
+    METRICS.bytes_out.fetch_add(n as u64, Ordering::Relaxed);
+}
+
+/// Everything in the Prometheus text exposition format
+pub fn render() -> String {
+    let m = &METRICS;
+    let mut out = String::new();
+    gauge(
+        &mut out,
+        "rat_active_connections",
+        "WebSocket clients connected to the bridge",
+        m.connections.load(Ordering::Relaxed),
+    );
+    gauge(
+        &mut out,
+        "rat_agent_processes",
+        "Agent processes running, pooled or in use",
+        m.agents.load(Ordering::Relaxed),
+    );
+    counter(
+        &mut out,
+        "rat_agent_restarts_total",
+        "Agent processes that exited on their own and were replaced",
+        m.restarts.load(Ordering::Relaxed),
+    );
+    counter(
+        &mut out,
+        "rat_prompts_sent_total",
+        "Prompts sent to agents",
+        m.prompts.load(Ordering::Relaxed),
+    );
+
+    let _ = writeln!(
+        out,
+        "# HELP rat_turn_duration_seconds Time from a prompt reaching the agent to its result"
+    );
+    let _ = writeln!(out, "# TYPE rat_turn_duration_seconds histogram");
+    let mut cumulative = 0;
+    for (bound, count) in TURN_BUCKETS.iter().zip(&m.turn_buckets) {
+        cumulative += count.load(Ordering::Relaxed);
+        let _ = writeln!(
+            out,
+            "rat_turn_duration_seconds_bucket{{le=\"{}\"}} {}",
+            bound, cumulative
+        );
+    }
+    cumulative += m.turn_buckets[TURN_BUCKETS.len()].load(Ordering::Relaxed);
+    let _ = writeln!(
+        out,
+        "rat_turn_duration_seconds_bucket{{le=\"+Inf\"}} {}",
+        cumulative
+    );
+    let sum = m.turn_micros.load(Ordering::Relaxed) as f64 / 1e6;
+    let _ = writeln!(out, "rat_turn_duration_seconds_sum {}", sum);
+    let _ = writeln!(out, "rat_turn_duration_seconds_count {}", cumulative);
+
+    let _ = writeln!(
+        out,
+        "# HELP rat_tool_calls_total Tool calls reported by agents, by kind"
+    );
+    let _ = writeln!(out, "# TYPE rat_tool_calls_total counter");
+    for (kind, count) in m.tool_calls.lock().unwrap().iter() {
+        let _ = writeln!(
+            out,
+            "rat_tool_calls_total{{kind=\"{}\"}} {}",
+            escape(kind),
+            count
+        );
+    }
+    let _ = writeln!(
+        out,
+        "# HELP rat_permission_outcomes_total Permission requests by how they were settled"
+    );
+    let _ = writeln!(out, "# TYPE rat_permission_outcomes_total counter");
+    for (outcome, count) in m.permissions.lock().unwrap().iter() {
+        let _ = writeln!(
+            out,
+            "rat_permission_outcomes_total{{outcome=\"{}\"}} {}",
+            outcome, count
+        );
+    }
+
+    counter(
+        &mut out,
+        "rat_bridge_received_bytes_total",
+        "Bytes received from bridge clients",
+        m.bytes_in.load(Ordering::Relaxed),
+    );
+    counter(
+        &mut out,
+        "rat_bridge_sent_bytes_total",
+        "Bytes sent to bridge clients",
+        m.bytes_out.load(Ordering::Relaxed),
+    );
+    out
+}
+
+/// Answer a scrape on a plain HTTP connection
+pub async fn serve(mut stream: TcpStream) -> anyhow::Result<()> {
+    let body = render();
+    let head = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n",
+        body.len()
+    );
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(body.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// Turn away a scrape without the bridge token
+pub async fn refuse(mut stream: TcpStream) -> anyhow::Result<()> {
+    let body = "missing or wrong bearer token\n";
+    let head = format!(
+        "HTTP/1.1 401 Unauthorized\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\nWWW-Authenticate: Bearer\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n",
+        body.len()
+    );
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(body.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+fn gauge(out: &mut String, name: &str, help: &str, value: i64) {
+    let _ = writeln!(
+        out,
+        "# HELP {} {}\n# TYPE {} gauge\n{} {}",
+        name, help, name, name, value
+    );
+}
+
+fn counter(out: &mut String, name: &str, help: &str, value: u64) {
+    let _ = writeln!(
+        out,
+        "# HELP {} {}\n# TYPE {} counter\n{} {}",
+        name, help, name, name, value
+    );
+}
+
+/// Label values may not contain raw quotes, backslashes or newlines
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_counters_and_a_cumulative_turn_histogram() {
+        turn_finished(Duration::from_millis(300));
+        turn_finished(Duration::from_secs(7));
+        tool_call("edit");
+        permission(permission_outcome(
+            &serde_json::json!({"outcome": "selected", "optionId": "allow_once"}),
+        ));
+        assert_eq!(
+            permission_outcome(&serde_json::json!({"outcome": "cancelled"})),
+            "cancelled"
+        );
+
+        let text = render();
+        assert!(text.contains("# TYPE rat_turn_duration_seconds histogram"));
+        // Tests share the registry, so only check what this one recorded
+        let bucket = |le: &str| -> u64 {
+            let prefix = format!("rat_turn_duration_seconds_bucket{{le=\"{}\"}} ", le);
+            let line = text.lines().find(|l| l.starts_with(&prefix)).unwrap();
+            line[prefix.len()..].parse().unwrap()
+        };
+        assert!(bucket("0.5") >= 1);
+        assert!(bucket("10") > bucket("5"));
+        assert_eq!(bucket("+Inf"), bucket("600"));
+        assert!(text.contains("rat_tool_calls_total{kind=\"edit\"}"));
+        assert!(text.contains("rat_permission_outcomes_total{outcome=\"allowed\"}"));
+        assert_eq!(escape("a\"b"), "a\\\"b");
+    }
+}
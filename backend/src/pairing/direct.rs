@@ -0,0 +1,247 @@
+//! Direct WebRTC data-channel links to a paired browser.
+//!
+//! The browser sends a `rat/webrtc/offer` request through the encrypted relay
+//! tunnel and gets our answer SDP back. Once the browser's `acp` data channel
+//! opens, the ACP JSON-RPC frames that went through the relay go over the
+//! data channel instead; the relay stays up for signaling and as the
+//! fallback. DTLS
+//! protects the channel, and its fingerprint travels inside the Noise tunnel,
+//! so the peer is the browser we paired with.
+//!
+//! Built with the `webrtc` feature; without it offers are refused and the
+//! relay carries everything.
+
+use anyhow::Result;
+
+/// Label of the data channel the browser opens for ACP traffic
+pub const CHANNEL_LABEL: &str = "acp";
+
+/// Something that happened on a direct link
+#[derive(Debug)]
+// Only the WebRTC link produces these; other builds just match on them
+#[cfg_attr(not(feature = "webrtc"), allow(dead_code))]
+pub enum DirectEvent {
+    /// The browser's data channel is open; output can go direct
+    Open,
+    /// A message from the browser
+    Message(Vec<u8>),
+    /// The channel or peer connection went away
+    Closed,
+}
+
+#[cfg(feature = "webrtc")]
+mod imp {
+    use super::{DirectEvent, CHANNEL_LABEL};
+    use anyhow::{Context, Result};
+    use bytes::Bytes;
+    use tracing::{info, warn};
+    use std::sync::Arc;
+    use tokio::sync::{mpsc, Mutex};
+    use webrtc::api::APIBuilder;
+    use webrtc::data_channel::data_channel_message::DataChannelMessage;
+    use webrtc::data_channel::RTCDataChannel;
+    use webrtc::ice_transport::ice_server::RTCIceServer;
+    use webrtc::peer_connection::configuration::RTCConfiguration;
+    use webrtc::peer_connection::peer_connection_state::RTCPeerConnectionState;
+    use webrtc::peer_connection::sdp::session_description::RTCSessionDescription;
+    use webrtc::peer_connection::RTCPeerConnection;
+
+    /// A WebRTC peer connection answering one browser offer
+    pub struct DirectLink {
+        peer: Arc<RTCPeerConnection>,
+        channel: Arc<Mutex<Option<Arc<RTCDataChannel>>>>,
+        events: mpsc::UnboundedReceiver<DirectEvent>,
+    }
+
+    impl DirectLink {
+        /// Accept a browser's offer; returns our answer SDP and the link.
+        /// ICE candidates are gathered up front, so no trickling is needed.
+        pub async fn answer(offer_sdp: String, ice_servers: &[String]) -> Result<(String, Self)> {
+            let api = APIBuilder::new().build();
+            let config = RTCConfiguration {
+                ice_servers: vec![RTCIceServer {
+                    urls: ice_servers.to_vec(),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            };
+            let peer = Arc::new(api.new_peer_connection(config).await?);
+            let (tx, events) = mpsc::unbounded_channel();
+            let channel = Arc::new(Mutex::new(None));
+
+            let opened = channel.clone();
+            let channel_tx = tx.clone();
+            peer.on_data_channel(Box::new(move |dc: Arc<RTCDataChannel>| {
+                let opened = opened.clone();
+                let tx = channel_tx.clone();
+                Box::pin(async move {
+                    if dc.label() != CHANNEL_LABEL {
+                        warn!("Ignoring data channel '{}'", dc.label());
+                        return;
+                    }
+                    let open_tx = tx.clone();
+                    let open_dc = dc.clone();
+                    dc.on_open(Box::new(move || {
+                        Box::pin(async move {
+                            *opened.lock().await = Some(open_dc);
+                            let _ = open_tx.send(DirectEvent::Open);
+                        })
+                    }));
+                    let message_tx = tx.clone();
+                    dc.on_message(Box::new(move |msg: DataChannelMessage| {
+                        let _ = message_tx.send(DirectEvent::Message(msg.data.to_vec()));
+                        Box::pin(async {})
+                    }));
+                    dc.on_close(Box::new(move || {
+                        let _ = tx.send(DirectEvent::Closed);
+                        Box::pin(async {})
+                    }));
+                })
+            }));
+            peer.on_peer_connection_state_change(Box::new(move |state: RTCPeerConnectionState| {
+                info!("Direct link {}", state);
+                if matches!(
+                    state,
+                    RTCPeerConnectionState::Failed | RTCPeerConnectionState::Closed
+                ) {
+                    let _ = tx.send(DirectEvent::Closed);
+                }
+                Box::pin(async {})
+            }));
+
+            let offer = RTCSessionDescription::offer(offer_sdp).context("Invalid offer")?;
+            peer.set_remote_description(offer).await?;
+            let answer = peer.create_answer(None).await?;
+            let mut gathered = peer.gathering_complete_promise().await;
+            peer.set_local_description(answer).await?;
+            let _ = gathered.recv().await;
+            let sdp = peer
+                .local_description()
+                .await
+                .ok_or_else(|| anyhow::anyhow!("No local description"))?
+                .sdp;
+            Ok((
+                sdp,
+                Self {
+                    peer,
+                    channel,
+                    events,
+                },
+            ))
+        }
+
+        pub async fn recv(&mut self) -> DirectEvent {
+            self.events.recv().await.unwrap_or(DirectEvent::Closed)
+        }
+
+        /// Send agent output to the browser over the open data channel
+        pub async fn send(&self, data: &[u8]) -> Result<()> {
+            let channel = self.channel.lock().await.clone();
+            let channel = channel.ok_or_else(|| anyhow::anyhow!("Data channel is not open"))?;
+            channel.send(&Bytes::copy_from_slice(data)).await?;
+            Ok(())
+        }
+    }
+
+    impl Drop for DirectLink {
+        fn drop(&mut self) {
+            let peer = self.peer.clone();
+            tokio::spawn(async move {
+                let _ = peer.close().await;
+            });
+        }
+    }
+}
+
+#[cfg(not(feature = "webrtc"))]
+mod imp {
+    use super::DirectEvent;
+    use anyhow::Result;
+
+    /// Stand-in for builds without WebRTC; never constructed
+    pub enum DirectLink {}
+
+    impl DirectLink {
+        pub async fn answer(_offer_sdp: String, _ice_servers: &[String]) -> Result<(String, Self)> {
+            Err(anyhow::anyhow!(
+                "This build of rat has no WebRTC support (enable the `webrtc` feature)"
+            ))
+        }
+
+        pub async fn recv(&mut self) -> DirectEvent {
+            match *self {}
+        }
+
+        pub async fn send(&self, _data: &[u8]) -> Result<()> {
+            match *self {}
+        }
+    }
+}
+
+pub use imp::DirectLink;
+
+/// The next event on an optional link; never resolves without one
+pub async fn next_event(link: &mut Option<DirectLink>) -> DirectEvent {
+    match link {
+        Some(link) => link.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// The offer SDP if `plain` is a `rat/webrtc/offer` request, with its id
+pub fn offer(plain: &[u8]) -> Option<(serde_json::Value, String)> {
+    let msg: serde_json::Value = serde_json::from_slice(plain).ok()?;
+    if msg.get("method")?.as_str()? != "rat/webrtc/offer" {
+        return None;
+    }
+    let sdp = msg.get("params")?.get("sdp")?.as_str()?.to_string();
+    Some((
+        msg.get("id").cloned().unwrap_or(serde_json::Value::Null),
+        sdp,
+    ))
+}
+
+/// The reply to a `rat/webrtc/offer` request, as an NDJSON line
+pub fn answer_reply(id: serde_json::Value, answer: Result<&str>) -> Vec<u8> {
+    let msg = match answer {
+        Ok(sdp) => serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "result": {"type": "answer", "sdp": sdp, "label": CHANNEL_LABEL}
+        }),
+        Err(e) => serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": {"code": -32000, "message": e.to_string()}
+        }),
+    };
+    let mut line = msg.to_string().into_bytes();
+    line.push(b'\n');
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offers_are_picked_out_of_the_tunnel() {
+        let plain =
+            br#"{"jsonrpc":"2.0","id":4,"method":"rat/webrtc/offer","params":{"sdp":"v=0"}}"#;
+        assert_eq!(
+            offer(plain),
+            Some((serde_json::json!(4), "v=0".to_string()))
+        );
+        assert_eq!(
+            offer(br#"{"jsonrpc":"2.0","id":5,"method":"session/new"}"#),
+            None
+        );
+
+        let reply: serde_json::Value =
+            serde_json::from_slice(&answer_reply(serde_json::json!(4), Ok("v=0"))).unwrap();
+        assert_eq!(reply["result"]["sdp"], "v=0");
+        assert_eq!(reply["result"]["label"], CHANNEL_LABEL);
+        let refused = answer_reply(serde_json::json!(4), Err(anyhow::anyhow!("no")));
+        assert!(refused.ends_with(b"\n"));
+    }
+}
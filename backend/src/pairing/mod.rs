@@ -0,0 +1,709 @@
+pub mod direct;
+pub mod store;
+
+use anyhow::{Context, Result};
+use futures_util::stream::{SplitSink, SplitStream};
+use futures_util::{SinkExt, StreamExt};
+use tracing::{info, warn};
+use rand::Rng;
+use reqwest::Client;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use snow::{params::NoiseParams, Builder};
+use std::collections::{BTreeSet, VecDeque};
+use std::env;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::process::{Child, ChildStdin, Command};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::header::{HeaderName, HeaderValue, AUTHORIZATION};
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::protocol::{CloseFrame, WebSocketConfig};
+use tokio_tungstenite::{connect_async_with_config, tungstenite::Message};
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+use url::Url;
+
+use self::direct::{DirectEvent, DirectLink};
+use self::store::PairingStore;
+use crate::config::Config;
+use crate::utils::ndjson::NdjsonFramer;
+use crate::utils::process::shutdown_signal;
+
+pub async fn start_pairing(config: &Config) -> Result<()> {
+    crate::utils::logging::init_stderr();
+    let relay_url = config.relay.base_url();
+    let token = config.relay.token()?;
+    info!("Pairing through relay {}", relay_url);
+
+    // Noise static keypair, kept until `rat pairing rotate`
+    let store = PairingStore::from_config(config);
+    let identity = store.load_or_create().await?;
+    let private_key = identity.private_key()?;
+    let pubkey_b64 = identity.public_key.clone();
+
+    info!("RAT pubkey: {}", pubkey_b64);
+
+    // POST /v1/pair/start
+    let client = Client::new();
+    let url = Url::parse(&format!("{}/v1/pair/start", relay_url))?;
+    let mut req = client.post(url);
+    if let Some(token) = &token {
+        req = req.bearer_auth(token);
+    }
+    let res = req
+        .json(&json!({
+            "rat_pubkey": pubkey_b64,
+            "caps": ["acp", "fs", "tools"],
+            "rat_version": env!("CARGO_PKG_VERSION")
+        }))
+        .send()
+        .await?;
+
+    if !res.status().is_success() {
+        if res.status() == reqwest::StatusCode::UNAUTHORIZED {
+            return Err(anyhow::anyhow!(
+                "Relay {} rejected the pairing request; check [relay] token",
+                relay_url
+            ));
+        }
+        return Err(anyhow::anyhow!("Pair start failed: {}", res.status()));
+    }
+
+    let pair_info: serde_json::Value = res.json().await?;
+    let user_code = pair_info["user_code"].as_str().ok_or(anyhow::anyhow!("No user_code"))?;
+    let device_code = pair_info["device_code"].as_str().ok_or(anyhow::anyhow!("No device_code"))?;
+    let relay_ws_url = pair_info["relay_ws_url"].as_str().ok_or(anyhow::anyhow!("No relay_ws_url"))?;
+
+    info!("Pairing started. User code: {}. Enter on hosted UI.", user_code);
+    // Printed rather than logged so it shows without RUST_LOG
+    let pair_link = pairing_link(&pair_info, user_code);
+    match render_qr(&pair_link) {
+        Ok(qr) => println!("{}", qr),
+        Err(e) => warn!("Could not render pairing QR code: {}", e),
+    }
+    println!("Scan to pair, or enter code {} on the hosted UI", user_code);
+    println!("Key fingerprint: {}", identity.fingerprint());
+    info!("Device code (internal): {}", device_code);
+
+    // The agent outlives relay connections so a reconnect resumes its sessions
+    let mut agent = if env::var("RAT2E_AGENT_CMD").is_ok() {
+        Some(RelayAgent::spawn(config.relay.ice_servers())?)
+    } else {
+        None
+    };
+
+    let ws_url = format!("{}?device_code={}", relay_ws_url, device_code);
+    let mut backoff = Backoff::default();
+    let mut reconnects = 0u32;
+    let mut paired = false;
+    // SIGTERM or Ctrl-C cancels the running turn and closes the tunnel first
+    let shutdown = shutdown_signal();
+    tokio::pin!(shutdown);
+    loop {
+        let connected = tokio::select! {
+            connected = connect_relay(&ws_url, token.as_deref(), &private_key) => connected,
+            _ = &mut shutdown => break,
+        };
+        let link = match connected {
+            Ok(link) => link,
+            Err(e) => {
+                let Some(delay) = backoff.next_delay() else {
+                    return Err(e.context("Giving up on the relay"));
+                };
+                warn!("Relay connection failed ({}); retrying in {:?}", e, delay);
+                tokio::select! {
+                    _ = tokio::time::sleep(delay) => continue,
+                    _ = &mut shutdown => break,
+                }
+            }
+        };
+        info!("Noise XX key established");
+
+        // The browser that uses this run's code pairs; reconnects must come
+        // from a device that is still paired
+        let device = store
+            .admit(&identity.public_key, &link.remote_key, !paired, &relay_url)
+            .await
+            .context("Refusing browser")?;
+        paired = true;
+        info!("Browser device {} connected", device.id);
+        let pin = DevicePin {
+            store: &store,
+            our_key: &identity.public_key,
+            device_id: &device.id,
+        };
+
+        // If ACP is configured, run the bridge using Noise transport
+        let Some(agent) = agent.as_mut() else {
+            return Ok(());
+        };
+        let connected_at = Instant::now();
+        match agent.bridge(link, reconnects, &pin, &mut shutdown).await {
+            BridgeEnd::AgentExited => {
+                info!("ACP bridge session ended");
+                return Ok(());
+            }
+            BridgeEnd::Shutdown => break,
+            BridgeEnd::Revoked => {
+                return Err(anyhow::anyhow!(
+                    "This browser was unpaired; run `rat --pair` to pair again"
+                ));
+            }
+            BridgeEnd::Dropped(reason) => {
+                if connected_at.elapsed() >= RECONNECT_STABLE {
+                    backoff.reset();
+                }
+                reconnects += 1;
+                let Some(delay) = backoff.next_delay() else {
+                    return Err(anyhow::anyhow!(
+                        "Relay connection lost ({}); giving up",
+                        reason
+                    ));
+                };
+                warn!(
+                    "Relay connection lost ({}); reconnecting in {:?}",
+                    reason, delay
+                );
+                tokio::select! {
+                    _ = tokio::time::sleep(delay) => {}
+                    _ = &mut shutdown => break,
+                }
+            }
+        }
+    }
+    info!("Shutting down");
+    if let Some(agent) = agent.as_mut() {
+        agent.stop().await;
+    }
+    Ok(())
+}
+
+type RelayStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// One authenticated relay connection: the WebSocket plus its Noise session
+struct RelayLink {
+    write: SplitSink<RelayStream, Message>,
+    read: SplitStream<RelayStream>,
+    transport: snow::TransportState,
+    /// The browser's Noise static key
+    remote_key: Vec<u8>,
+}
+
+impl RelayLink {
+    async fn send(&mut self, plain: &[u8]) -> Result<()> {
+        let mut ct = vec![0u8; plain.len() + 16];
+        let n = self.transport.write_message(plain, &mut ct)?;
+        ct.truncate(n);
+        self.write.send(Message::Binary(ct)).await?;
+        Ok(())
+    }
+
+    fn decrypt(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        let mut out = vec![0u8; data.len()];
+        let n = self.transport.read_message(data, &mut out)?;
+        out.truncate(n);
+        Ok(out)
+    }
+}
+
+/// Connect to the relay, wait for the browser to bind, and run the Noise XX
+/// handshake. Each connection gets fresh transport keys.
+async fn connect_relay(ws_url: &str, token: Option<&str>, private_key: &[u8]) -> Result<RelayLink> {
+    // Connect WS to relay with device_code and explicit subprotocol per spec
+    let mut request = ws_url.into_client_request()?;
+    let hname = HeaderName::from_static("sec-websocket-protocol");
+    // RAT side does not need attach token; advertise baseline subprotocol
+    request
+        .headers_mut()
+        .insert(hname, HeaderValue::from_static("acp.jsonrpc.v1"));
+    if let Some(token) = token {
+        request.headers_mut().insert(
+            AUTHORIZATION,
+            HeaderValue::from_str(&format!("Bearer {}", token))?,
+        );
+    }
+    let ws_cfg = WebSocketConfig::default();
+    let (ws_stream, _) = connect_async_with_config(request, Some(ws_cfg), false).await?;
+    let (mut ws_write, mut ws_read) = ws_stream.split();
+
+    // Wait for browser to send binding context
+    let (session_id, stk_sha256_b64u, protocol) = wait_for_noise_bind(&mut ws_read).await?;
+    let prologue = derive_prologue(&session_id, &stk_sha256_b64u, &protocol);
+
+    // Build Noise XX initiator with prologue and static key
+    let params = "Noise_XX_25519_ChaChaPoly_BLAKE2s".parse::<NoiseParams>()?;
+    let mut hs = Builder::new(params)
+        .prologue(&prologue)
+        .local_private_key(private_key)
+        .build_initiator()?;
+
+    // Act 1 ->
+    let mut buf = vec![0u8; 1024];
+    let n = hs.write_message(&[], &mut buf)?;
+    buf.truncate(n);
+    ws_write.send(Message::Binary(buf)).await?;
+    // Act 2 <-
+    let m2 = read_binary(&mut ws_read).await?;
+    let mut tmp = vec![0u8; 1024];
+    let _ = hs.read_message(&m2, &mut tmp)?;
+    // Act 3 ->
+    let mut out = vec![0u8; 1024];
+    let n3 = hs.write_message(&[], &mut out)?;
+    out.truncate(n3);
+    ws_write.send(Message::Binary(out)).await?;
+
+    let remote_key = hs
+        .get_remote_static()
+        .ok_or_else(|| anyhow::anyhow!("Browser sent no static key"))?
+        .to_vec();
+    // Enter transport mode
+    Ok(RelayLink {
+        write: ws_write,
+        read: ws_read,
+        transport: hs.into_transport_mode()?,
+        remote_key,
+    })
+}
+
+/// What the QR code carries: the relay's complete verification link if it
+/// sent one, else `RAT_PAIR_URL` with the code appended, else the bare code
+fn pairing_link(pair_info: &serde_json::Value, user_code: &str) -> String {
+    if let Some(link) = pair_info["verification_uri_complete"].as_str() {
+        return link.to_string();
+    }
+    match env::var("RAT_PAIR_URL") {
+        Ok(base) if !base.is_empty() => match Url::parse(&base) {
+            Ok(mut url) => {
+                url.query_pairs_mut().append_pair("code", user_code);
+                url.to_string()
+            }
+            Err(_) => user_code.to_string(),
+        },
+        _ => user_code.to_string(),
+    }
+}
+
+/// `data` as a QR code drawn with half-block characters, two modules per
+/// character cell, light-on-dark so it scans from a dark terminal
+fn render_qr(data: &str) -> Result<String> {
+    use qrcode::render::unicode::Dense1x2;
+    let code = qrcode::QrCode::new(data.as_bytes())?;
+    Ok(code
+        .render::<Dense1x2>()
+        .dark_color(Dense1x2::Light)
+        .light_color(Dense1x2::Dark)
+        .quiet_zone(true)
+        .build())
+}
+
+fn derive_prologue(session_id: &str, stk_sha256_b64u: &str, protocol: &str) -> Vec<u8> {
+    let canon = format!("RAT2E/v1|sid:{}|stk:{}|proto:{}", session_id, stk_sha256_b64u, protocol);
+    let mut h = Sha256::new();
+    h.update(canon.as_bytes());
+    h.finalize().to_vec()
+}
+
+async fn wait_for_noise_bind<WR>(
+    ws_read: &mut WR,
+) -> Result<(String, String, String)>
+where
+    WR: StreamExt<Item = Result<Message, tokio_tungstenite::tungstenite::Error>> + Unpin,
+{
+    while let Some(msg) = ws_read.next().await {
+        match msg? {
+            Message::Binary(data) => {
+                // Allow JSON in binary frame
+                if let Ok(text) = String::from_utf8(data) {
+                    if let Ok(v) = serde_json::from_str::<serde_json::Value>(&text) {
+                        if v.get("type").and_then(|x| x.as_str()) == Some("noise_bind") {
+                            let sid = v["session_id"].as_str().unwrap_or("").to_string();
+                            let stk = v["attach_token_sha256"].as_str().unwrap_or("").to_string();
+                            let proto = v["protocol"].as_str().unwrap_or("acp.jsonrpc.v1").to_string();
+                            return Ok((sid, stk, proto));
+                        }
+                    }
+                }
+            }
+            Message::Text(text) => {
+                if let Ok(v) = serde_json::from_str::<serde_json::Value>(&text) {
+                    if v.get("type").and_then(|x| x.as_str()) == Some("noise_bind") {
+                        let sid = v["session_id"].as_str().unwrap_or("").to_string();
+                        let stk = v["attach_token_sha256"].as_str().unwrap_or("").to_string();
+                        let proto = v["protocol"].as_str().unwrap_or("acp.jsonrpc.v1").to_string();
+                        return Ok((sid, stk, proto));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Err(anyhow::anyhow!("noise_bind not received"))
+}
+
+async fn read_binary<WR>(ws_read: &mut WR) -> Result<Vec<u8>>
+where
+    WR: StreamExt<Item = Result<Message, tokio_tungstenite::tungstenite::Error>> + Unpin,
+{
+    while let Some(msg) = ws_read.next().await {
+        if let Message::Binary(data) = msg? {
+            return Ok(data);
+        }
+    }
+    Err(anyhow::anyhow!("eof waiting for binary"))
+}
+
+// Relay backoff per the RAT2E spec: base 250 ms, factor 2, cap 30 s, reset
+// after 60 s stable
+const RECONNECT_BASE: Duration = Duration::from_millis(250);
+const RECONNECT_MAX: Duration = Duration::from_secs(30);
+const RECONNECT_STABLE: Duration = Duration::from_secs(60);
+/// Consecutive failed attempts before pairing mode gives up (about ten minutes)
+const RECONNECT_ATTEMPTS: u32 = 24;
+
+/// Exponential backoff with jitter between relay connection attempts
+#[derive(Debug, Default)]
+struct Backoff {
+    attempt: u32,
+}
+
+impl Backoff {
+    /// Delay before the next attempt, or `None` once we should give up
+    fn next_delay(&mut self) -> Option<Duration> {
+        if self.attempt >= RECONNECT_ATTEMPTS {
+            return None;
+        }
+        let delay = RECONNECT_BASE
+            .saturating_mul(1 << self.attempt.min(16))
+            .min(RECONNECT_MAX);
+        self.attempt += 1;
+        // Shave off up to a quarter so bridges dropped together don't retry in lockstep
+        let jitter = rand::thread_rng().gen_range(0..=delay.as_millis() as u64 / 4);
+        Some(delay - Duration::from_millis(jitter))
+    }
+
+    fn reset(&mut self) {
+        self.attempt = 0;
+    }
+}
+
+enum BridgeEnd {
+    AgentExited,
+    Dropped(String),
+    /// The device was revoked or the keys rotated while it was connected
+    Revoked,
+    /// We were asked to exit; the browser has been told
+    Shutdown,
+}
+
+/// How often a connected device's pairing is re-checked, so revocation and
+/// rotation cut off live tunnels too
+const PAIRING_RECHECK: Duration = Duration::from_secs(5);
+
+/// The paired device a connection belongs to
+struct DevicePin<'a> {
+    store: &'a PairingStore,
+    our_key: &'a str,
+    device_id: &'a str,
+}
+
+/// The local ACP agent behind the relay. It keeps running while the relay is
+/// away; its output queues up and is delivered once a connection is back.
+struct RelayAgent {
+    child: Child,
+    stdin: ChildStdin,
+    output: mpsc::Receiver<Vec<u8>>,
+    /// Output that was read from the agent but not delivered before a drop
+    unsent: VecDeque<Vec<u8>>,
+    /// Sessions the agent has created, reported to clients on reconnect
+    sessions: Arc<std::sync::Mutex<BTreeSet<String>>>,
+    /// STUN/TURN servers offered for direct links
+    ice_servers: Vec<String>,
+}
+
+impl RelayAgent {
+    fn spawn(ice_servers: Vec<String>) -> Result<Self> {
+        // Spawn agent process from env
+        let cmd = env::var("RAT2E_AGENT_CMD")
+            .map_err(|_| anyhow::anyhow!("Set RAT2E_AGENT_CMD to the ACP agent command"))?;
+        let args = env::var("RAT2E_AGENT_ARGS").unwrap_or_default();
+        let args_vec: Vec<String> = if args.is_empty() {
+            vec![]
+        } else {
+            args.split_whitespace().map(|s| s.to_string()).collect()
+        };
+        info!("Starting local ACP agent: {} {}", cmd, args_vec.join(" "));
+        let mut child = Command::new(cmd)
+            .args(args_vec)
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .spawn()?;
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| anyhow::anyhow!("Failed to get agent stdin"))?;
+        let mut stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| anyhow::anyhow!("Failed to get agent stdout"))?;
+
+        // Task: agent stdout -> queue. Bounded, so a long outage pauses the
+        // agent instead of growing without limit.
+        let (tx, output) = mpsc::channel::<Vec<u8>>(256);
+        let sessions = Arc::new(std::sync::Mutex::new(BTreeSet::new()));
+        let seen = sessions.clone();
+        tokio::spawn(async move {
+            let mut frames = NdjsonFramer::default();
+            let mut buf = vec![0u8; 8192];
+            loop {
+                match stdout.read(&mut buf).await {
+                    Ok(0) => break,
+                    Ok(n) => {
+                        for frame in frames.push(&buf[..n]).into_iter().flatten() {
+                            if let Some(id) = created_session(&frame) {
+                                seen.lock().unwrap().insert(id);
+                            }
+                        }
+                        if tx.send(buf[..n].to_vec()).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(e) => {
+                        warn!("stdout read error: {}", e);
+                        break;
+                    }
+                }
+            }
+        });
+
+        Ok(Self {
+            child,
+            stdin,
+            output,
+            unsent: VecDeque::new(),
+            sessions,
+            ice_servers,
+        })
+    }
+
+    /// Ask the agent to cancel whatever its sessions are running
+    async fn cancel_turns(&mut self) {
+        let sessions: Vec<String> = self.sessions.lock().unwrap().iter().cloned().collect();
+        for session in sessions {
+            let cancel = json!({"jsonrpc": "2.0", "method": "session/cancel", "params": {"sessionId": session}});
+            let _ = self.stdin.write_all(format!("{}\n", cancel).as_bytes()).await;
+        }
+    }
+
+    /// Kill the agent process and reap it
+    async fn stop(&mut self) {
+        if let Err(e) = self.child.kill().await {
+            warn!("Failed to stop the agent: {}", e);
+        }
+    }
+
+    /// Relay traffic between the agent and one connection until either side
+    /// goes away. `reconnects` counts earlier connections that dropped. If
+    /// the browser sets up a direct WebRTC link, agent output moves onto it
+    /// while the relay connection stays up for signaling and fallback.
+    async fn bridge(
+        &mut self,
+        mut link: RelayLink,
+        reconnects: u32,
+        pin: &DevicePin<'_>,
+        shutdown: &mut (impl Future<Output = ()> + Unpin),
+    ) -> BridgeEnd {
+        let event = if reconnects == 0 {
+            connection_event("connected", json!({}))
+        } else {
+            let sessions: Vec<String> = self.sessions.lock().unwrap().iter().cloned().collect();
+            connection_event(
+                "reconnected",
+                json!({ "reconnects": reconnects, "sessionIds": sessions }),
+            )
+        };
+        if let Err(e) = link.send(&event).await {
+            return BridgeEnd::Dropped(e.to_string());
+        }
+        while let Some(chunk) = self.unsent.pop_front() {
+            if let Err(e) = link.send(&chunk).await {
+                self.unsent.push_front(chunk);
+                return BridgeEnd::Dropped(e.to_string());
+            }
+        }
+
+        let start = tokio::time::Instant::now() + PAIRING_RECHECK;
+        let mut recheck = tokio::time::interval_at(start, PAIRING_RECHECK);
+        let mut direct: Option<DirectLink> = None;
+        let mut direct_open = false;
+        loop {
+            tokio::select! {
+                _ = &mut *shutdown => {
+                    self.cancel_turns().await;
+                    let closed = connection_event("closed", json!({ "reason": "shutting down" }));
+                    let _ = link.send(&closed).await;
+                    let frame = CloseFrame { code: CloseCode::Away, reason: "rat shutting down".into() };
+                    let _ = link.write.send(Message::Close(Some(frame))).await;
+                    return BridgeEnd::Shutdown;
+                }
+                _ = recheck.tick() => {
+                    if !pin.store.is_paired(pin.our_key, pin.device_id).await {
+                        let revoked = connection_event("closed", json!({ "reason": "unpaired" }));
+                        let _ = link.send(&revoked).await;
+                        let _ = link.write.close().await;
+                        return BridgeEnd::Revoked;
+                    }
+                }
+                // WS -> agent stdin (decrypt then write)
+                msg = link.read.next() => match msg {
+                    Some(Ok(Message::Binary(data))) => match link.decrypt(&data) {
+                        Ok(plain) => {
+                            if let Some((id, sdp)) = direct::offer(&plain) {
+                                // A new offer replaces any earlier link; answering waits
+                                // for ICE gathering, and agent output queues meanwhile
+                                direct = None;
+                                direct_open = false;
+                                let reply = match DirectLink::answer(sdp, &self.ice_servers).await {
+                                    Ok((answer, new_link)) => {
+                                        direct = Some(new_link);
+                                        direct::answer_reply(id, Ok(&answer))
+                                    }
+                                    Err(e) => direct::answer_reply(id, Err(e)),
+                                };
+                                if let Err(e) = link.send(&reply).await {
+                                    return BridgeEnd::Dropped(e.to_string());
+                                }
+                                continue;
+                            }
+                            if let Err(e) = self.stdin.write_all(&plain).await {
+                                warn!("stdin write error: {}", e);
+                                return BridgeEnd::AgentExited;
+                            }
+                        }
+                        Err(e) => warn!("decrypt error: {}", e),
+                    },
+                    Some(Ok(Message::Close(_))) => {
+                        return BridgeEnd::Dropped("closed by relay".to_string())
+                    }
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) => return BridgeEnd::Dropped(e.to_string()),
+                    None => return BridgeEnd::Dropped("connection ended".to_string()),
+                },
+                // Direct link -> agent stdin, and switching output between paths
+                event = direct::next_event(&mut direct) => match event {
+                    DirectEvent::Open => {
+                        info!("Direct link open; ACP traffic bypasses the relay");
+                        direct_open = true;
+                        let _ = link.send(&connection_event("direct", json!({}))).await;
+                    }
+                    DirectEvent::Message(data) => {
+                        if let Err(e) = self.stdin.write_all(&data).await {
+                            warn!("stdin write error: {}", e);
+                            return BridgeEnd::AgentExited;
+                        }
+                    }
+                    DirectEvent::Closed => {
+                        direct = None;
+                        if std::mem::take(&mut direct_open) {
+                            info!("Direct link closed; back on the relay");
+                            let _ = link.send(&connection_event("relayed", json!({}))).await;
+                        }
+                    }
+                },
+                // agent stdout -> direct link or WS (encrypt and send)
+                chunk = self.output.recv() => match chunk {
+                    Some(chunk) => {
+                        if direct_open {
+                            let sent = match &direct {
+                                Some(direct) => direct.send(&chunk).await,
+                                None => Err(anyhow::anyhow!("direct link closed")),
+                            };
+                            match sent {
+                                Ok(()) => continue,
+                                Err(e) => {
+                                    warn!("Direct link failed ({}); back on the relay", e);
+                                    direct = None;
+                                    direct_open = false;
+                                    let _ = link.send(&connection_event("relayed", json!({}))).await;
+                                }
+                            }
+                        }
+                        if let Err(e) = link.send(&chunk).await {
+                            self.unsent.push_back(chunk);
+                            return BridgeEnd::Dropped(e.to_string());
+                        }
+                    }
+                    None => {
+                        let closed = connection_event("closed", json!({ "reason": "agent exited" }));
+                        let _ = link.send(&closed).await;
+                        return BridgeEnd::AgentExited;
+                    }
+                },
+            }
+        }
+    }
+}
+
+/// A `rat/connection` notification telling the client about the relay link
+fn connection_event(state: &str, mut params: serde_json::Value) -> Vec<u8> {
+    params["state"] = json!(state);
+    let mut line = json!({
+        "jsonrpc": "2.0",
+        "method": "rat/connection",
+        "params": params,
+    })
+    .to_string()
+    .into_bytes();
+    line.push(b'\n');
+    line
+}
+
+/// The session id in an agent response to `session/new`, if `frame` is one
+fn created_session(frame: &[u8]) -> Option<String> {
+    let msg: serde_json::Value = serde_json::from_slice(frame).ok()?;
+    msg.get("id")?;
+    msg.get("result")?
+        .get("sessionId")?
+        .as_str()
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pairing_qr_uses_half_blocks_and_the_relay_link() {
+        let qr = render_qr("https://example.com/pair?code=ABCD-1234").unwrap();
+        let widths: Vec<usize> = qr.lines().map(|line| line.chars().count()).collect();
+        assert!(widths.len() > 10);
+        assert!(widths.iter().all(|w| *w == widths[0]));
+        assert!(qr.chars().all(|c| " \u{2580}\u{2584}\u{2588}\n".contains(c)));
+
+        let info = serde_json::json!({"verification_uri_complete": "https://example.com/pair?code=X"});
+        assert_eq!(pairing_link(&info, "X"), "https://example.com/pair?code=X");
+    }
+
+    #[test]
+    fn reconnect_backoff_doubles_up_to_the_cap_then_gives_up() {
+        let mut backoff = Backoff::default();
+        let delays: Vec<Duration> = std::iter::from_fn(|| backoff.next_delay()).collect();
+        assert_eq!(delays.len(), RECONNECT_ATTEMPTS as usize);
+        assert!(delays[0] >= RECONNECT_BASE * 3 / 4 && delays[0] <= RECONNECT_BASE);
+        assert!(delays[3] >= RECONNECT_BASE * 6 && delays[3] <= RECONNECT_BASE * 8);
+        assert!(delays.iter().all(|d| *d <= RECONNECT_MAX));
+        assert!(delays.last().unwrap() >= &(RECONNECT_MAX * 3 / 4));
+
+        backoff.reset();
+        assert!(backoff.next_delay().unwrap() <= RECONNECT_BASE);
+
+        let created = br#"{"jsonrpc":"2.0","id":2,"result":{"sessionId":"sess-1"}}"#;
+        assert_eq!(created_session(created).as_deref(), Some("sess-1"));
+        let update = br#"{"jsonrpc":"2.0","method":"session/update","params":{"sessionId":"sess-1"}}"#;
+        assert_eq!(created_session(update), None);
+    }
+}
@@ -0,0 +1,272 @@
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose, Engine as _};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use snow::{params::NoiseParams, Builder};
+use std::path::PathBuf;
+
+use crate::config::Config;
+
+/// This machine's pairing identity and the browsers paired with it, kept in
+/// `pairing.json` under the data dir
+pub struct PairingStore {
+    path: PathBuf,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PairingState {
+    /// Noise static private key, base64; browsers pin the matching public key
+    private_key: String,
+    pub public_key: String,
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub devices: Vec<PairedDevice>,
+}
+
+/// A browser that completed pairing, identified by its Noise static key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PairedDevice {
+    pub id: String,
+    pub public_key: String,
+    pub paired_at: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    pub relay: String,
+}
+
+impl PairingState {
+    fn generate() -> Result<Self> {
+        let params = "Noise_XX_25519_ChaChaPoly_BLAKE2s".parse::<NoiseParams>()?;
+        let keypair = Builder::new(params).generate_keypair()?;
+        Ok(Self {
+            private_key: general_purpose::STANDARD.encode(&keypair.private),
+            public_key: general_purpose::STANDARD.encode(&keypair.public),
+            created_at: Utc::now(),
+            devices: Vec::new(),
+        })
+    }
+
+    pub fn private_key(&self) -> Result<Vec<u8>> {
+        general_purpose::STANDARD
+            .decode(&self.private_key)
+            .context("Corrupt pairing key; run `rat pairing rotate`")
+    }
+
+    /// Short fingerprint of our public key, for comparing with what browsers show
+    pub fn fingerprint(&self) -> String {
+        fingerprint(&self.public_key)
+    }
+
+    /// The paired device whose id starts with `prefix`
+    pub fn find(&self, prefix: &str) -> Result<&PairedDevice> {
+        let matches: Vec<&PairedDevice> = self
+            .devices
+            .iter()
+            .filter(|device| device.id.starts_with(prefix))
+            .collect();
+        match matches.as_slice() {
+            [device] => Ok(device),
+            [] => Err(anyhow::anyhow!("No paired device matches '{}'", prefix)),
+            _ => Err(anyhow::anyhow!(
+                "'{}' matches {} devices; use more of the id",
+                prefix,
+                matches.len()
+            )),
+        }
+    }
+}
+
+/// First 8 bytes of SHA-256 over a base64 key, as hex
+pub fn fingerprint(public_key: &str) -> String {
+    Sha256::digest(public_key.as_bytes())[..8]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+impl PairingStore {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(config.get_effective_data_dir().join("pairing.json"))
+    }
+
+    pub async fn load(&self) -> Result<Option<PairingState>> {
+        match tokio::fs::read(&self.path).await {
+            Ok(data) => Ok(Some(serde_json::from_slice(&data).with_context(|| {
+                format!("Failed to parse pairing state: {:?}", self.path)
+            })?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => {
+                Err(e).with_context(|| format!("Failed to read pairing state: {:?}", self.path))
+            }
+        }
+    }
+
+    /// The stored identity, generating one the first time
+    pub async fn load_or_create(&self) -> Result<PairingState> {
+        if let Some(state) = self.load().await? {
+            return Ok(state);
+        }
+        let state = PairingState::generate()?;
+        self.save(&state).await?;
+        Ok(state)
+    }
+
+    /// Replace the keypair and forget every paired device; browsers pinned
+    /// to the old key have to pair again
+    pub async fn rotate(&self) -> Result<(PairingState, usize)> {
+        let dropped = self.load().await?.map_or(0, |old| old.devices.len());
+        let state = PairingState::generate()?;
+        self.save(&state).await?;
+        Ok((state, dropped))
+    }
+
+    /// Record a browser that just completed a handshake. A fresh pairing adds
+    /// it; a reconnect must come from a device that is still paired.
+    pub async fn admit(
+        &self,
+        our_key: &str,
+        device_key: &[u8],
+        fresh_pairing: bool,
+        relay: &str,
+    ) -> Result<PairedDevice> {
+        let mut state = self
+            .load()
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Pairing state was removed"))?;
+        if state.public_key != our_key {
+            return Err(anyhow::anyhow!(
+                "Pairing keys were rotated; run `rat --pair` again"
+            ));
+        }
+        let device_key = general_purpose::STANDARD.encode(device_key);
+        let now = Utc::now();
+        let index = match state
+            .devices
+            .iter()
+            .position(|d| d.public_key == device_key)
+        {
+            Some(index) => index,
+            None if fresh_pairing => {
+                state.devices.push(PairedDevice {
+                    id: fingerprint(&device_key),
+                    public_key: device_key,
+                    paired_at: now,
+                    last_seen: now,
+                    relay: relay.to_string(),
+                });
+                state.devices.len() - 1
+            }
+            None => {
+                return Err(anyhow::anyhow!(
+                    "Device {} is not paired (revoked?)",
+                    fingerprint(&device_key)
+                ))
+            }
+        };
+        state.devices[index].last_seen = now;
+        let device = state.devices[index].clone();
+        self.save(&state).await?;
+        Ok(device)
+    }
+
+    /// Whether `device_id` may still use the identity `our_key`
+    pub async fn is_paired(&self, our_key: &str, device_id: &str) -> bool {
+        match self.load().await {
+            Ok(Some(state)) => {
+                state.public_key == our_key && state.devices.iter().any(|d| d.id == device_id)
+            }
+            _ => false,
+        }
+    }
+
+    /// Forget the device whose id starts with `prefix`
+    pub async fn revoke(&self, prefix: &str) -> Result<PairedDevice> {
+        let mut state = self
+            .load()
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("No devices are paired"))?;
+        let id = state.find(prefix)?.id.clone();
+        let index = state.devices.iter().position(|d| d.id == id).unwrap();
+        let device = state.devices.remove(index);
+        self.save(&state).await?;
+        Ok(device)
+    }
+
+    async fn save(&self, state: &PairingState) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            tokio::fs::create_dir_all(dir)
+                .await
+                .with_context(|| format!("Failed to create data directory: {:?}", dir))?;
+        }
+        let tmp = self.path.with_extension("json.tmp");
+        let content =
+            serde_json::to_vec_pretty(state).context("Failed to serialize pairing state")?;
+        tokio::fs::write(&tmp, content)
+            .await
+            .with_context(|| format!("Failed to write pairing state: {:?}", tmp))?;
+        // Holds a private key
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            tokio::fs::set_permissions(&tmp, std::fs::Permissions::from_mode(0o600)).await?;
+        }
+        tokio::fs::rename(&tmp, &self.path)
+            .await
+            .with_context(|| format!("Failed to write pairing state: {:?}", self.path))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn rotation_and_revocation_unpair_devices() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = PairingStore::new(dir.path().join("pairing.json"));
+        let state = store.load_or_create().await.unwrap();
+        assert_eq!(
+            store.load_or_create().await.unwrap().public_key,
+            state.public_key
+        );
+
+        // Reconnects are only accepted from devices that paired
+        assert!(store
+            .admit(&state.public_key, b"browser", false, "r")
+            .await
+            .is_err());
+        let device = store
+            .admit(&state.public_key, b"browser", true, "r")
+            .await
+            .unwrap();
+        assert!(store
+            .admit(&state.public_key, b"browser", false, "r")
+            .await
+            .is_ok());
+        assert!(store.is_paired(&state.public_key, &device.id).await);
+
+        store.revoke(&device.id[..4]).await.unwrap();
+        assert!(!store.is_paired(&state.public_key, &device.id).await);
+        assert!(store
+            .admit(&state.public_key, b"browser", false, "r")
+            .await
+            .is_err());
+
+        store
+            .admit(&state.public_key, b"browser", true, "r")
+            .await
+            .unwrap();
+        let (rotated, dropped) = store.rotate().await.unwrap();
+        assert_eq!(dropped, 1);
+        assert_ne!(rotated.public_key, state.public_key);
+        assert!(store
+            .admit(&state.public_key, b"browser", true, "r")
+            .await
+            .is_err());
+    }
+}
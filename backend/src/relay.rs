@@ -0,0 +1,820 @@
+//! Minimal self-hostable pairing relay (`rat relay`).
+//!
+//! Implements the part of the RAT2E relay API (see `docs/spec_rat2e.org`)
+//! that `rat --pair` and a browser client need: pairing codes, attach
+//! tickets and the `/v1/connect` WebSocket splice. After the Noise handshake
+//! every frame is ciphertext, so the relay never sees ACP traffic. State
+//! lives in memory and the relay speaks plain HTTP; run it behind a
+//! TLS-terminating proxy.
+
+use anyhow::Result;
+use base64::{engine::general_purpose, Engine as _};
+use futures_util::{SinkExt, StreamExt};
+use tracing::{info, warn};
+use rand::{Rng, RngCore};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::future::Future;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::oneshot;
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::http;
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::protocol::CloseFrame;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{accept_hdr_async, WebSocketStream};
+
+use crate::local_web::{self, HttpRequest};
+
+/// Subprotocol `rat --pair` connects with; browsers add `.stksha256.<hash>`
+const BASE_SUBPROTOCOL: &str = "acp.jsonrpc.v1";
+/// How long a pairing code stays valid
+const CODE_TTL: Duration = Duration::from_secs(600);
+/// How long an attach token stays valid
+const ATTACH_TTL: Duration = Duration::from_secs(300);
+/// Seconds clients are asked to wait between `/v1/pair/poll` calls
+const POLL_INTERVAL_SECS: u64 = 5;
+/// Wrong pairing codes one address may send before it is locked out
+const MAX_BAD_CODES: u32 = 5;
+const LOCKOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Default)]
+pub struct RelayOptions {
+    /// Token `rat --pair` must send as `Authorization: Bearer`; `None` lets anyone pair
+    pub token: Option<String>,
+    /// Base URL clients reach the relay on, e.g. `https://relay.example.com`;
+    /// defaults to the request's `Host`
+    pub public_url: Option<String>,
+    /// Origins browsers may attach and call the API from; empty allows any
+    pub allowed_origins: Vec<String>,
+    /// Take client addresses from `X-Forwarded-For`, for use behind a proxy
+    pub trust_proxy: bool,
+}
+
+struct Device {
+    rat_pubkey: String,
+    /// Unpaired devices are forgotten after this
+    expires: Instant,
+    session_id: Option<String>,
+    /// The device's open connection, parked until a browser attaches
+    waiting: Option<oneshot::Sender<Attach>>,
+}
+
+struct Session {
+    device_code: String,
+    /// base64url(SHA-256(attach_token)); the raw token is never stored
+    stk: String,
+    attach_nonce: String,
+    expires: Instant,
+    used: bool,
+}
+
+impl Session {
+    /// Replace the attach ticket, returning the new raw token
+    fn issue_ticket(&mut self) -> String {
+        let token = random_token(32);
+        self.stk = b64u(&Sha256::digest(token.as_bytes()));
+        self.attach_nonce = random_token(16);
+        self.expires = Instant::now() + ATTACH_TTL;
+        self.used = false;
+        token
+    }
+
+    fn subprotocol(&self) -> String {
+        format!("{}.stksha256.{}", BASE_SUBPROTOCOL, self.stk)
+    }
+}
+
+/// A browser connection handed over to the device it attached to
+struct Attach {
+    browser: WebSocketStream<TcpStream>,
+    session_id: String,
+    stk: String,
+    protocol: String,
+}
+
+#[derive(Default)]
+struct RelayState {
+    devices: HashMap<String, Device>,
+    /// Pending pairing codes: user_code -> device_code
+    user_codes: HashMap<String, (String, Instant)>,
+    sessions: HashMap<String, Session>,
+    bad_codes: HashMap<IpAddr, (u32, Instant)>,
+}
+
+impl RelayState {
+    /// Forget expired pairing codes, unpaired devices and old lockouts
+    fn sweep(&mut self) {
+        let now = Instant::now();
+        self.user_codes.retain(|_, (_, expires)| *expires > now);
+        self.devices
+            .retain(|_, device| device.session_id.is_some() || device.expires > now);
+        self.bad_codes
+            .retain(|_, (_, since)| now.duration_since(*since) < LOCKOUT);
+    }
+
+    fn locked_out(&self, ip: IpAddr) -> bool {
+        self.bad_codes
+            .get(&ip)
+            .is_some_and(|(count, since)| *count >= MAX_BAD_CODES && since.elapsed() < LOCKOUT)
+    }
+
+    fn note_bad_code(&mut self, ip: IpAddr) {
+        let entry = self.bad_codes.entry(ip).or_insert((0, Instant::now()));
+        entry.0 += 1;
+        entry.1 = Instant::now();
+    }
+}
+
+type SharedState = Arc<Mutex<RelayState>>;
+
+/// Serve the relay on `listener` until `shutdown` completes
+pub async fn serve(
+    listener: TcpListener,
+    options: RelayOptions,
+    shutdown: impl Future<Output = ()>,
+) -> Result<()> {
+    if options.token.is_none() {
+        warn!("Relay has no token; anyone who can reach it can start pairing");
+    }
+    let options = Arc::new(options);
+    let state = SharedState::default();
+    tokio::pin!(shutdown);
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                let (stream, peer) = match accepted {
+                    Ok(accepted) => accepted,
+                    Err(e) => {
+                        warn!("Relay accept failed: {}", e);
+                        continue;
+                    }
+                };
+                let state = state.clone();
+                let options = options.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = handle_connection(stream, peer, state, options).await {
+                        warn!("Relay connection from {} failed: {}", peer, e);
+                    }
+                });
+            }
+            _ = &mut shutdown => break,
+        }
+    }
+    Ok(())
+}
+
+async fn handle_connection(
+    mut stream: TcpStream,
+    peer: SocketAddr,
+    state: SharedState,
+    options: Arc<RelayOptions>,
+) -> Result<()> {
+    if local_web::is_websocket_upgrade(&stream).await? {
+        return handle_connect(stream, state, &options).await;
+    }
+    let request = HttpRequest::read(&mut stream).await?;
+    let ip = request.client_ip(peer.ip(), &options);
+    let response = route(&request, ip, &state, &options);
+    response
+        .write(&mut stream, request.cors_origin(&options))
+        .await?;
+    Ok(())
+}
+
+type Reply = std::result::Result<Value, (u16, &'static str)>;
+
+fn route(
+    request: &HttpRequest,
+    ip: IpAddr,
+    state: &SharedState,
+    options: &RelayOptions,
+) -> HttpResponse {
+    let reply = match (request.method.as_str(), request.path.as_str()) {
+        // CORS preflight for browser calls
+        ("OPTIONS", _) => {
+            return HttpResponse {
+                status: 204,
+                body: None,
+            }
+        }
+        ("GET", "/health") => Ok(json!({ "status": "ok" })),
+        ("GET", "/version") => Ok(json!({ "version": env!("CARGO_PKG_VERSION") })),
+        ("POST", "/v1/pair/start") => pair_start(request, state, options),
+        ("POST", "/v1/pair/poll") => pair_poll(request, state),
+        ("POST", "/v1/pair/complete") => pair_complete(request, ip, state, options),
+        ("POST", "/v1/session/attach-ticket") => attach_ticket(request, state),
+        _ => Err((404, "not_found")),
+    };
+    match reply {
+        Ok(body) => HttpResponse {
+            status: 200,
+            body: Some(body),
+        },
+        Err((status, error)) => HttpResponse {
+            status,
+            body: Some(json!({ "error": error })),
+        },
+    }
+}
+
+/// `POST /v1/pair/start { rat_pubkey, caps, rat_version }` from `rat --pair`
+fn pair_start(request: &HttpRequest, state: &SharedState, options: &RelayOptions) -> Reply {
+    if !bearer_matches(request.header("authorization"), options) {
+        return Err((401, "unauthorized"));
+    }
+    let body = request.json()?;
+    let rat_pubkey = body["rat_pubkey"]
+        .as_str()
+        .filter(|key| !key.is_empty())
+        .ok_or((400, "invalid_request"))?;
+
+    let mut state = state.lock().unwrap();
+    state.sweep();
+    let user_code = loop {
+        let code = user_code();
+        if !state.user_codes.contains_key(&code) {
+            break code;
+        }
+    };
+    let device_code = random_token(32);
+    let expires = Instant::now() + CODE_TTL;
+    state
+        .user_codes
+        .insert(user_code.clone(), (device_code.clone(), expires));
+    state.devices.insert(
+        device_code.clone(),
+        Device {
+            rat_pubkey: rat_pubkey.to_string(),
+            expires,
+            session_id: None,
+            waiting: None,
+        },
+    );
+    info!("Pairing started; code expires in {:?}", CODE_TTL);
+    Ok(json!({
+        "user_code": user_code,
+        "device_code": device_code,
+        "relay_ws_url": request.ws_url(options),
+        "expires_in": CODE_TTL.as_secs(),
+        "interval": POLL_INTERVAL_SECS,
+    }))
+}
+
+/// `POST /v1/pair/poll { device_code }`: whether a browser has completed pairing
+fn pair_poll(request: &HttpRequest, state: &SharedState) -> Reply {
+    let body = request.json()?;
+    let device_code = body["device_code"]
+        .as_str()
+        .ok_or((400, "invalid_request"))?;
+    let mut state = state.lock().unwrap();
+    state.sweep();
+    let device = state
+        .devices
+        .get(device_code)
+        .ok_or((400, "expired_token"))?;
+    let expires_in = device
+        .expires
+        .saturating_duration_since(Instant::now())
+        .as_secs();
+    let session = device
+        .session_id
+        .as_ref()
+        .and_then(|id| Some((id, state.sessions.get(id)?)));
+    Ok(match session {
+        Some((session_id, session)) => json!({
+            "status": "ready",
+            "session_id": session_id,
+            "attach_nonce": session.attach_nonce,
+            "effective_subprotocol": session.subprotocol(),
+            "interval": POLL_INTERVAL_SECS,
+            "expires_in": expires_in,
+        }),
+        None => json!({
+            "status": "pending",
+            "interval": POLL_INTERVAL_SECS,
+            "expires_in": expires_in,
+        }),
+    })
+}
+
+/// `POST /v1/pair/complete { user_code }` from the browser; the code is single-use
+fn pair_complete(
+    request: &HttpRequest,
+    ip: IpAddr,
+    state: &SharedState,
+    options: &RelayOptions,
+) -> Reply {
+    let body = request.json()?;
+    let code = normalize_code(body["user_code"].as_str().unwrap_or_default());
+
+    let mut state = state.lock().unwrap();
+    state.sweep();
+    if state.locked_out(ip) {
+        return Err((429, "slow_down"));
+    }
+    let Some((device_code, _)) = state.user_codes.remove(&code) else {
+        state.note_bad_code(ip);
+        return Err((400, "invalid_code"));
+    };
+    let Some(device) = state.devices.get_mut(&device_code) else {
+        return Err((400, "expired_token"));
+    };
+    let session_id = random_token(16);
+    let replaced = device.session_id.replace(session_id.clone());
+    let rat_pubkey = device.rat_pubkey.clone();
+    if let Some(old) = replaced {
+        state.sessions.remove(&old);
+    }
+
+    let mut session = Session {
+        device_code,
+        stk: String::new(),
+        attach_nonce: String::new(),
+        expires: Instant::now(),
+        used: false,
+    };
+    let attach_token = session.issue_ticket();
+    let reply = json!({
+        "session_id": session_id,
+        "attach_token": attach_token,
+        "attach_nonce": session.attach_nonce,
+        "relay_ws_url": request.ws_url(options),
+        "effective_subprotocol": session.subprotocol(),
+        "rat_pubkey": rat_pubkey,
+    });
+    state.sessions.insert(session_id, session);
+    info!("Pairing completed");
+    Ok(reply)
+}
+
+/// `POST /v1/session/attach-ticket { session_id }`: a fresh ticket for reconnecting
+fn attach_ticket(request: &HttpRequest, state: &SharedState) -> Reply {
+    let body = request.json()?;
+    let session_id = body["session_id"]
+        .as_str()
+        .ok_or((400, "invalid_request"))?;
+    let mut state = state.lock().unwrap();
+    let session = state
+        .sessions
+        .get_mut(session_id)
+        .ok_or((404, "unknown_session"))?;
+    let attach_token = session.issue_ticket();
+    Ok(json!({
+        "attach_token": attach_token,
+        "attach_nonce": session.attach_nonce,
+        "effective_subprotocol": session.subprotocol(),
+    }))
+}
+
+/// Who is on the other end of a `/v1/connect` WebSocket
+enum Peer {
+    Device(String),
+    Browser {
+        session_id: String,
+        device_code: String,
+        stk: String,
+        protocol: String,
+    },
+}
+
+/// `GET /v1/connect`: `?device_code=` from `rat --pair`, which waits there
+/// for a browser; `?session_id=` from a browser holding an attach ticket
+// The handshake callback's error type is tungstenite's, too big for clippy
+#[allow(clippy::result_large_err)]
+async fn handle_connect(
+    stream: TcpStream,
+    state: SharedState,
+    options: &RelayOptions,
+) -> Result<()> {
+    let mut peer = None;
+    let ws = accept_hdr_async(stream, |req: &Request, mut resp: Response| {
+        let admitted = admit(req, &state, options);
+        match admitted {
+            Ok((admitted, protocol)) => {
+                let protocol = http::HeaderValue::from_str(&protocol)
+                    .map_err(|_| reject(400, "invalid subprotocol"))?;
+                resp.headers_mut()
+                    .insert("Sec-WebSocket-Protocol", protocol);
+                peer = Some(admitted);
+                Ok(resp)
+            }
+            Err((status, reason)) => Err(reject(status, reason)),
+        }
+    })
+    .await
+    .map_err(|e| anyhow::anyhow!("WebSocket handshake failed: {}", e))?;
+
+    match peer {
+        Some(Peer::Device(device_code)) => run_device(ws, device_code, state).await,
+        Some(Peer::Browser {
+            session_id,
+            device_code,
+            stk,
+            protocol,
+        }) => {
+            let attach = Attach {
+                browser: ws,
+                session_id,
+                stk,
+                protocol,
+            };
+            let waiting = state
+                .lock()
+                .unwrap()
+                .devices
+                .get_mut(&device_code)
+                .and_then(|device| device.waiting.take());
+            let unclaimed = match waiting {
+                Some(device) => device.send(attach).err(),
+                None => Some(attach),
+            };
+            if let Some(mut attach) = unclaimed {
+                info!("Browser attached while its device is offline");
+                let frame = CloseFrame {
+                    code: CloseCode::Again,
+                    reason: "device offline".into(),
+                };
+                let _ = attach.browser.close(Some(frame)).await;
+            }
+            Ok(())
+        }
+        None => Ok(()),
+    }
+}
+
+/// Check a `/v1/connect` handshake, returning the peer and the subprotocol to echo
+fn admit(
+    req: &Request,
+    state: &SharedState,
+    options: &RelayOptions,
+) -> std::result::Result<(Peer, String), (u16, &'static str)> {
+    let query = req.uri().query().unwrap_or_default();
+    let params: HashMap<String, String> = url::form_urlencoded::parse(query.as_bytes())
+        .into_owned()
+        .collect();
+    let offered: Vec<&str> = req
+        .headers()
+        .get_all("Sec-WebSocket-Protocol")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .collect();
+    let header = |name: &str| req.headers().get(name).and_then(|v| v.to_str().ok());
+
+    let mut state = state.lock().unwrap();
+    state.sweep();
+    if let Some(device_code) = params.get("device_code") {
+        if !bearer_matches(header("authorization"), options) {
+            return Err((401, "unauthorized"));
+        }
+        if !offered.contains(&BASE_SUBPROTOCOL) {
+            return Err((400, "subprotocol mismatch"));
+        }
+        if !state.devices.contains_key(device_code) {
+            return Err((404, "unknown device"));
+        }
+        return Ok((
+            Peer::Device(device_code.clone()),
+            BASE_SUBPROTOCOL.to_string(),
+        ));
+    }
+
+    let session_id = params
+        .get("session_id")
+        .ok_or((400, "missing device_code or session_id"))?;
+    if !origin_allowed(header("origin"), options) {
+        return Err((403, "origin not allowed"));
+    }
+    let session = state
+        .sessions
+        .get_mut(session_id)
+        .ok_or((404, "unknown session"))?;
+    let protocol = session.subprotocol();
+    if !offered.contains(&protocol.as_str()) {
+        return Err((403, "invalid attach token"));
+    }
+    if session.used || session.expires <= Instant::now() {
+        return Err((403, "attach token expired or already used"));
+    }
+    session.used = true;
+    Ok((
+        Peer::Browser {
+            session_id: session_id.clone(),
+            device_code: session.device_code.clone(),
+            stk: session.stk.clone(),
+            protocol: protocol.clone(),
+        },
+        protocol,
+    ))
+}
+
+/// Park the device's connection until a browser attaches, then splice the two
+async fn run_device(
+    mut device: WebSocketStream<TcpStream>,
+    device_code: String,
+    state: SharedState,
+) -> Result<()> {
+    let (attached, mut attach) = oneshot::channel();
+    match state.lock().unwrap().devices.get_mut(&device_code) {
+        // A newer connection from the same device replaces this one
+        Some(entry) => entry.waiting = Some(attached),
+        None => return Ok(()),
+    }
+    let attach = loop {
+        tokio::select! {
+            attach = &mut attach => match attach {
+                Ok(attach) => break attach,
+                Err(_) => return Ok(()),
+            },
+            msg = device.next() => match msg {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return Ok(()),
+                Some(Ok(_)) => {}
+            },
+        }
+    };
+
+    let bind = json!({
+        "type": "noise_bind",
+        "session_id": attach.session_id,
+        "attach_token_sha256": attach.stk,
+        "protocol": attach.protocol,
+    });
+    device.send(Message::Text(bind.to_string())).await?;
+    info!("Browser attached; relaying");
+
+    let (mut device_tx, mut device_rx) = device.split();
+    let (mut browser_tx, mut browser_rx) = attach.browser.split();
+    loop {
+        tokio::select! {
+            msg = device_rx.next() => match msg {
+                Some(Ok(msg @ (Message::Binary(_) | Message::Text(_)))) => {
+                    if browser_tx.send(msg).await.is_err() {
+                        break;
+                    }
+                }
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+            msg = browser_rx.next() => match msg {
+                Some(Ok(msg @ (Message::Binary(_) | Message::Text(_)))) => {
+                    if device_tx.send(msg).await.is_err() {
+                        break;
+                    }
+                }
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+    // Either side leaving ends the tunnel; the device reconnects to wait for the next attach
+    let _ = browser_tx.close().await;
+    let _ = device_tx.close().await;
+    info!("Tunnel closed");
+    Ok(())
+}
+
+fn reject(status: u16, reason: &str) -> ErrorResponse {
+    let mut response = ErrorResponse::new(Some(reason.to_string()));
+    *response.status_mut() =
+        http::StatusCode::from_u16(status).unwrap_or(http::StatusCode::BAD_REQUEST);
+    response
+}
+
+/// Whether an `Authorization` header carries the relay token (always, if none is set)
+fn bearer_matches(header: Option<&str>, options: &RelayOptions) -> bool {
+    let Some(expected) = &options.token else {
+        return true;
+    };
+    let Some(given) = header.and_then(|value| value.strip_prefix("Bearer ")) else {
+        return false;
+    };
+    // Compare digests so the time taken doesn't depend on where the token differs
+    Sha256::digest(given.as_bytes()) == Sha256::digest(expected.as_bytes())
+}
+
+fn origin_allowed(origin: Option<&str>, options: &RelayOptions) -> bool {
+    options.allowed_origins.is_empty()
+        || origin.is_some_and(|origin| options.allowed_origins.iter().any(|o| o == origin))
+}
+
+/// Eight characters from an alphabet without look-alikes (0/O, 1/I)
+fn user_code() -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+    let mut rng = rand::thread_rng();
+    (0..8)
+        .map(|_| ALPHABET[rng.gen_range(0..ALPHABET.len())] as char)
+        .collect()
+}
+
+/// Codes as typed: case, spaces and dashes don't matter
+fn normalize_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+fn random_token(bytes: usize) -> String {
+    let mut buf = vec![0u8; bytes];
+    rand::thread_rng().fill_bytes(&mut buf);
+    b64u(&buf)
+}
+
+fn b64u(data: &[u8]) -> String {
+    general_purpose::URL_SAFE_NO_PAD.encode(data)
+}
+
+impl HttpRequest {
+    fn client_ip(&self, peer: IpAddr, options: &RelayOptions) -> IpAddr {
+        if !options.trust_proxy {
+            return peer;
+        }
+        self.header("x-forwarded-for")
+            .and_then(|list| list.split(',').next())
+            .and_then(|ip| ip.trim().parse().ok())
+            .unwrap_or(peer)
+    }
+
+    /// `ws(s)://<host>/v1/connect`, from the public URL or the `Host` header
+    fn ws_url(&self, options: &RelayOptions) -> String {
+        let base = match &options.public_url {
+            Some(url) => url.trim_end_matches('/').to_string(),
+            None => format!("http://{}", self.header("host").unwrap_or("localhost")),
+        };
+        let base = match base.split_once("://") {
+            Some(("https", rest)) => format!("wss://{}", rest),
+            Some((_, rest)) => format!("ws://{}", rest),
+            None => format!("ws://{}", base),
+        };
+        format!("{}/v1/connect", base)
+    }
+
+    /// The `Access-Control-Allow-Origin` value for this request, if any
+    fn cors_origin(&self, options: &RelayOptions) -> Option<String> {
+        let origin = self.header("origin")?;
+        origin_allowed(Some(origin), options).then(|| origin.to_string())
+    }
+}
+
+struct HttpResponse {
+    status: u16,
+    body: Option<Value>,
+}
+
+impl HttpResponse {
+    async fn write(
+        &self,
+        stream: &mut TcpStream,
+        cors_origin: Option<String>,
+    ) -> std::io::Result<()> {
+        let reason = match self.status {
+            200 => "OK",
+            204 => "No Content",
+            400 => "Bad Request",
+            401 => "Unauthorized",
+            404 => "Not Found",
+            429 => "Too Many Requests",
+            _ => "Error",
+        };
+        let body = self
+            .body
+            .as_ref()
+            .map(|body| body.to_string())
+            .unwrap_or_default();
+        let mut head = format!(
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n",
+            self.status,
+            reason,
+            body.len()
+        );
+        if let Some(origin) = cors_origin {
+            head.push_str(&format!(
+                "Access-Control-Allow-Origin: {}\r\nAccess-Control-Allow-Methods: GET, POST, OPTIONS\r\nAccess-Control-Allow-Headers: Authorization, Content-Type\r\nVary: Origin\r\n",
+                origin
+            ));
+        }
+        head.push_str("\r\n");
+        stream.write_all(head.as_bytes()).await?;
+        stream.write_all(body.as_bytes()).await?;
+        stream.shutdown().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+
+    async fn post(port: u16, path: &str, token: Option<&str>, body: Value) -> (u16, Value) {
+        let client = reqwest::Client::new();
+        let mut req = client.post(format!("http://127.0.0.1:{}{}", port, path));
+        if let Some(token) = token {
+            req = req.bearer_auth(token);
+        }
+        let res = req.json(&body).send().await.unwrap();
+        (res.status().as_u16(), res.json().await.unwrap())
+    }
+
+    async fn connect(
+        url: String,
+        protocol: &str,
+        token: Option<&str>,
+    ) -> tokio_tungstenite::tungstenite::Result<
+        WebSocketStream<tokio_tungstenite::MaybeTlsStream<TcpStream>>,
+    > {
+        let mut request = url.into_client_request()?;
+        request
+            .headers_mut()
+            .insert("Sec-WebSocket-Protocol", protocol.parse().unwrap());
+        if let Some(token) = token {
+            let bearer = format!("Bearer {}", token);
+            request
+                .headers_mut()
+                .insert("Authorization", bearer.parse().unwrap());
+        }
+        Ok(tokio_tungstenite::connect_async(request).await?.0)
+    }
+
+    #[tokio::test]
+    async fn relay_pairs_a_device_and_splices_the_browser() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let options = RelayOptions {
+            token: Some("secret".to_string()),
+            ..RelayOptions::default()
+        };
+        tokio::spawn(serve(listener, options, std::future::pending()));
+
+        let start = json!({ "rat_pubkey": "cGs=", "caps": ["acp"] });
+        let (status, _) = post(port, "/v1/pair/start", None, start.clone()).await;
+        assert_eq!(status, 401);
+        let (status, pair) = post(port, "/v1/pair/start", Some("secret"), start).await;
+        assert_eq!(status, 200);
+        let ws_url = pair["relay_ws_url"].as_str().unwrap().to_string();
+        assert_eq!(ws_url, format!("ws://127.0.0.1:{}/v1/connect", port));
+
+        let device_url = format!(
+            "{}?device_code={}",
+            ws_url,
+            pair["device_code"].as_str().unwrap()
+        );
+        let mut device = connect(device_url, BASE_SUBPROTOCOL, Some("secret"))
+            .await
+            .unwrap();
+
+        // Codes are single-use and forgiving about formatting
+        let code = pair["user_code"].as_str().unwrap().to_lowercase();
+        let (status, done) = post(
+            port,
+            "/v1/pair/complete",
+            None,
+            json!({ "user_code": code }),
+        )
+        .await;
+        assert_eq!(status, 200);
+        let (status, _) = post(
+            port,
+            "/v1/pair/complete",
+            None,
+            json!({ "user_code": code }),
+        )
+        .await;
+        assert_eq!(status, 400);
+
+        let protocol = done["effective_subprotocol"].as_str().unwrap();
+        let browser_url = format!(
+            "{}?session_id={}",
+            ws_url,
+            done["session_id"].as_str().unwrap()
+        );
+        let mut browser = connect(browser_url.clone(), protocol, None).await.unwrap();
+
+        let Some(Ok(Message::Text(bind))) = device.next().await else {
+            panic!("device got no noise_bind");
+        };
+        let bind: Value = serde_json::from_str(&bind).unwrap();
+        assert_eq!(bind["session_id"], done["session_id"]);
+        assert_eq!(bind["protocol"], protocol);
+
+        browser.send(Message::Binary(vec![1, 2, 3])).await.unwrap();
+        assert_eq!(
+            device.next().await.unwrap().unwrap(),
+            Message::Binary(vec![1, 2, 3])
+        );
+        device.send(Message::Binary(vec![4])).await.unwrap();
+        assert_eq!(
+            browser.next().await.unwrap().unwrap(),
+            Message::Binary(vec![4])
+        );
+
+        // Attach tickets are single-use
+        assert!(connect(browser_url, protocol, None).await.is_err());
+    }
+}
@@ -0,0 +1,200 @@
+//! OpenTelemetry export (`[telemetry]`, feature `otel`). Spans leave as OTLP
+//! traces through a layer on the tracing subscriber; turn latency, tool call
+//! duration and agent restarts are exported as OTLP metrics. Recording is a
+//! no-op until [`init`] has run with telemetry enabled, and always without
+//! the feature.
+
+use anyhow::Result;
+use std::time::Duration;
+#[cfg(feature = "otel")]
+use tracing::Subscriber;
+#[cfg(feature = "otel")]
+use tracing_subscriber::registry::LookupSpan;
+
+use crate::config::TelemetryConfig;
+
+#[cfg(feature = "otel")]
+mod otlp {
+    use anyhow::Result;
+    use opentelemetry::metrics::{Counter, Histogram};
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry::{global, KeyValue};
+    use opentelemetry_otlp::WithExportConfig;
+    use opentelemetry_sdk::metrics::{PeriodicReader, SdkMeterProvider};
+    use opentelemetry_sdk::trace::{Tracer, TracerProvider};
+    use opentelemetry_sdk::{runtime, Resource};
+    use std::sync::{Mutex, OnceLock};
+    use std::time::Duration;
+
+    use crate::config::{telemetry::OtlpProtocol, TelemetryConfig};
+
+    pub(super) struct Instruments {
+        pub turn_duration: Histogram<f64>,
+        pub tool_call_duration: Histogram<f64>,
+        pub agent_restarts: Counter<u64>,
+    }
+
+    pub(super) static INSTRUMENTS: OnceLock<Instruments> = OnceLock::new();
+
+    /// Kept to flush pending exports on shutdown
+    static PROVIDERS: Mutex<(Option<TracerProvider>, Option<SdkMeterProvider>)> =
+        Mutex::new((None, None));
+
+    /// Start the exporters; returns the tracer for the tracing layer when
+    /// traces are on
+    pub(super) fn start(config: &TelemetryConfig) -> Result<Option<Tracer>> {
+        let resource = Resource::new(vec![KeyValue::new(
+            "service.name",
+            config.service_name.clone(),
+        )]);
+        let endpoint = config.endpoint();
+        // The HTTP exporters post to the signal's path under the endpoint
+        let signal_url = |signal: &str| match config.protocol {
+            OtlpProtocol::Grpc => endpoint.clone(),
+            OtlpProtocol::Http => format!("{}/v1/{}", endpoint.trim_end_matches('/'), signal),
+        };
+        let mut providers = PROVIDERS.lock().unwrap();
+
+        let tracer = if config.traces {
+            let exporter = match config.protocol {
+                OtlpProtocol::Grpc => opentelemetry_otlp::SpanExporter::builder()
+                    .with_tonic()
+                    .with_endpoint(signal_url("traces"))
+                    .build()?,
+                OtlpProtocol::Http => opentelemetry_otlp::SpanExporter::builder()
+                    .with_http()
+                    .with_endpoint(signal_url("traces"))
+                    .build()?,
+            };
+            let provider = TracerProvider::builder()
+                .with_batch_exporter(exporter, runtime::Tokio)
+                .with_resource(resource.clone())
+                .build();
+            let tracer = provider.tracer("rat");
+            global::set_tracer_provider(provider.clone());
+            providers.0 = Some(provider);
+            Some(tracer)
+        } else {
+            None
+        };
+
+        if config.metrics {
+            let exporter = match config.protocol {
+                OtlpProtocol::Grpc => opentelemetry_otlp::MetricExporter::builder()
+                    .with_tonic()
+                    .with_endpoint(signal_url("metrics"))
+                    .build()?,
+                OtlpProtocol::Http => opentelemetry_otlp::MetricExporter::builder()
+                    .with_http()
+                    .with_endpoint(signal_url("metrics"))
+                    .build()?,
+            };
+            let reader = PeriodicReader::builder(exporter, runtime::Tokio)
+                .with_interval(Duration::from_secs(config.export_interval_secs))
+                .build();
+            let provider = SdkMeterProvider::builder()
+                .with_reader(reader)
+                .with_resource(resource)
+                .build();
+            global::set_meter_provider(provider.clone());
+            let meter = global::meter("rat");
+            let _ = INSTRUMENTS.set(Instruments {
+                turn_duration: meter
+                    .f64_histogram("rat.turn.duration")
+                    .with_unit("s")
+                    .with_description("Time from a prompt reaching the agent to its result")
+                    .build(),
+                tool_call_duration: meter
+                    .f64_histogram("rat.tool_call.duration")
+                    .with_unit("s")
+                    .with_description("Time from a tool call starting to it completing or failing")
+                    .build(),
+                agent_restarts: meter
+                    .u64_counter("rat.agent.restarts")
+                    .with_description("Agent processes that exited on their own and were replaced")
+                    .build(),
+            });
+            providers.1 = Some(provider);
+        }
+        Ok(tracer)
+    }
+
+    pub(super) fn shutdown() {
+        let (traces, metrics) = std::mem::take(&mut *PROVIDERS.lock().unwrap());
+        if let Some(provider) = traces {
+            if let Err(e) = provider.shutdown() {
+                tracing::warn!("Failed to flush traces: {}", e);
+            }
+        }
+        if let Some(provider) = metrics {
+            if let Err(e) = provider.shutdown() {
+                tracing::warn!("Failed to flush metrics: {}", e);
+            }
+        }
+    }
+}
+
+/// Start exporting if `config` enables it. The returned layer sends spans
+/// to the collector and belongs on the tracing subscriber.
+#[cfg(feature = "otel")]
+pub fn init<S>(
+    config: &TelemetryConfig,
+) -> Result<Option<tracing_opentelemetry::OpenTelemetryLayer<S, opentelemetry_sdk::trace::Tracer>>>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    if !config.enabled {
+        return Ok(None);
+    }
+    let tracer = otlp::start(config)?;
+    Ok(tracer.map(|tracer| tracing_opentelemetry::layer().with_tracer(tracer)))
+}
+
+/// Without the `otel` feature there is nothing to export; config validation
+/// already rejects `enabled = true`
+#[cfg(not(feature = "otel"))]
+pub fn init(_config: &TelemetryConfig) -> Result<Option<tracing_subscriber::layer::Identity>> {
+    Ok(None)
+}
+
+/// Flush and stop the exporters before exiting
+pub fn shutdown() {
+    #[cfg(feature = "otel")]
+    otlp::shutdown();
+}
+
+/// A prompt turn ended `elapsed` after the prompt reached the agent
+pub fn turn_finished(elapsed: Duration) {
+    #[cfg(feature = "otel")]
+    if let Some(instruments) = otlp::INSTRUMENTS.get() {
+        instruments.turn_duration.record(elapsed.as_secs_f64(), &[]);
+    }
+    #[cfg(not(feature = "otel"))]
+    let _ = elapsed;
+}
+
+/// A tool call of `kind` ended with `status` (`completed` or `failed`)
+pub fn tool_call_finished(kind: &str, status: &str, elapsed: Duration) {
+    #[cfg(feature = "otel")]
+    if let Some(instruments) = otlp::INSTRUMENTS.get() {
+        let attributes = [
+            opentelemetry::KeyValue::new("kind", kind.to_string()),
+            opentelemetry::KeyValue::new("status", status.to_string()),
+        ];
+        instruments
+            .tool_call_duration
+            .record(elapsed.as_secs_f64(), &attributes);
+    }
+    #[cfg(not(feature = "otel"))]
+    let _ = (kind, status, elapsed);
+}
+
+/// `count` agent processes exited on their own and are being replaced
+pub fn agents_restarted(count: u64) {
+    #[cfg(feature = "otel")]
+    if let Some(instruments) = otlp::INSTRUMENTS.get() {
+        instruments.agent_restarts.add(count, &[]);
+    }
+    #[cfg(not(feature = "otel"))]
+    let _ = count;
+}
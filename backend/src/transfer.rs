@@ -0,0 +1,411 @@
+//! Chunked file transfers between bridge clients and the workspace, so a
+//! browser can fetch a generated file or push an attachment without going
+//! through the agent's text-only `fs/*` calls.
+//!
+//! A transfer is opened with `rat/file/open` (after the user allows it), then
+//! moved in base64 chunks with `rat/file/read` or `rat/file/write`, and
+//! finished with `rat/file/close`. Uploads land in a temporary file that is
+//! renamed into place only once every announced byte has arrived.
+
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose, Engine as _};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+
+/// Largest chunk a single read or write carries, before base64
+pub const MAX_CHUNK_BYTES: usize = 256 * 1024;
+
+/// Largest file that can be uploaded or downloaded
+pub const MAX_FILE_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Transfers one client may have open at once
+pub const MAX_OPEN_TRANSFERS: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Workspace to client
+    Download,
+    /// Client to workspace
+    Upload,
+}
+
+impl Direction {
+    pub fn parse(mode: &str) -> Option<Self> {
+        match mode {
+            "read" | "download" => Some(Self::Download),
+            "write" | "upload" => Some(Self::Upload),
+            _ => None,
+        }
+    }
+}
+
+struct Transfer {
+    path: PathBuf,
+    direction: Direction,
+    size: u64,
+    written: u64,
+    file: tokio::fs::File,
+    /// Where an upload is staged until it completes
+    staging: Option<PathBuf>,
+}
+
+impl Drop for Transfer {
+    fn drop(&mut self) {
+        // An upload that never completed leaves nothing behind
+        if let Some(staging) = &self.staging {
+            let _ = std::fs::remove_file(staging);
+        }
+    }
+}
+
+/// Open transfers of one client connection, confined to a workspace root
+pub struct Transfers {
+    root: PathBuf,
+    next_id: u64,
+    open: HashMap<String, Transfer>,
+}
+
+impl Transfers {
+    pub fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            next_id: 0,
+            open: HashMap::new(),
+        }
+    }
+
+    /// The workspace path a client asked for; `..` and paths outside the
+    /// root are refused
+    pub fn resolve(&self, path: &str) -> Result<PathBuf> {
+        let path = Path::new(path);
+        if path.components().any(|c| c == Component::ParentDir) {
+            return Err(anyhow::anyhow!("{} may not contain ..", path.display()));
+        }
+        let path = if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            self.root.join(path)
+        };
+        if !path.starts_with(&self.root) || path == self.root {
+            return Err(anyhow::anyhow!(
+                "{} is outside the workspace {}",
+                path.display(),
+                self.root.display()
+            ));
+        }
+        Ok(path)
+    }
+
+    /// Start a transfer of an already resolved path. Uploads must announce
+    /// their size up front.
+    pub async fn open(
+        &mut self,
+        path: PathBuf,
+        direction: Direction,
+        size: Option<u64>,
+    ) -> Result<Value> {
+        if self.open.len() >= MAX_OPEN_TRANSFERS {
+            return Err(anyhow::anyhow!(
+                "{} transfers are already open; close one first",
+                MAX_OPEN_TRANSFERS
+            ));
+        }
+        let transfer = match direction {
+            Direction::Download => {
+                // Follow symlinks before trusting the path
+                let real = tokio::fs::canonicalize(&path)
+                    .await
+                    .with_context(|| format!("Failed to open {}", path.display()))?;
+                let root = tokio::fs::canonicalize(&self.root).await?;
+                if !real.starts_with(&root) {
+                    return Err(anyhow::anyhow!(
+                        "{} is outside the workspace",
+                        path.display()
+                    ));
+                }
+                let file = tokio::fs::File::open(&real)
+                    .await
+                    .with_context(|| format!("Failed to open {}", path.display()))?;
+                let meta = file.metadata().await?;
+                if !meta.is_file() {
+                    return Err(anyhow::anyhow!("{} is not a file", path.display()));
+                }
+                if meta.len() > MAX_FILE_BYTES {
+                    return Err(too_large(meta.len()));
+                }
+                Transfer {
+                    path,
+                    direction,
+                    size: meta.len(),
+                    written: 0,
+                    file,
+                    staging: None,
+                }
+            }
+            Direction::Upload => {
+                let size = size.ok_or_else(|| anyhow::anyhow!("uploads must give their size"))?;
+                if size > MAX_FILE_BYTES {
+                    return Err(too_large(size));
+                }
+                let path = self.upload_target(&path).await?;
+                let mut staging = path.clone().into_os_string();
+                staging.push(".rat-upload");
+                let staging = PathBuf::from(staging);
+                // Never through a file (or symlink) already there
+                let file = tokio::fs::OpenOptions::new()
+                    .write(true)
+                    .create_new(true)
+                    .open(&staging)
+                    .await
+                    .with_context(|| format!("Failed to create {}", staging.display()))?;
+                Transfer {
+                    path,
+                    direction,
+                    size,
+                    written: 0,
+                    file,
+                    staging: Some(staging),
+                }
+            }
+        };
+        self.next_id += 1;
+        let id = self.next_id.to_string();
+        let size = transfer.size;
+        self.open.insert(id.clone(), transfer);
+        Ok(json!({"transferId": id, "size": size, "chunkSize": MAX_CHUNK_BYTES}))
+    }
+
+    /// Where an upload to `path` really goes: its directory with symlinks
+    /// followed, which must stay inside the workspace. Missing directories
+    /// are created only once the part that exists is known to be inside.
+    async fn upload_target(&self, path: &Path) -> Result<PathBuf> {
+        let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+            return Err(anyhow::anyhow!("{} is not a file path", path.display()));
+        };
+        let root = tokio::fs::canonicalize(&self.root).await?;
+        let inside = |real: &Path| {
+            if real.starts_with(&root) {
+                Ok(())
+            } else {
+                Err(anyhow::anyhow!("{} is outside the workspace", path.display()))
+            }
+        };
+        let existing = parent
+            .ancestors()
+            .find(|dir| dir.exists())
+            .unwrap_or(&self.root);
+        inside(&tokio::fs::canonicalize(existing).await?)?;
+        tokio::fs::create_dir_all(parent)
+            .await
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+        let parent = tokio::fs::canonicalize(parent).await?;
+        inside(&parent)?;
+        Ok(parent.join(name))
+    }
+
+    /// Handle `rat/file/read`, `rat/file/write` or `rat/file/close`
+    pub async fn handle(&mut self, method: &str, params: &Value) -> Result<Value> {
+        let id = params["transferId"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("missing transferId"))?;
+        match method {
+            "rat/file/read" => self.read(id, params["offset"].as_u64().unwrap_or(0)).await,
+            "rat/file/write" => {
+                let data = params["data"]
+                    .as_str()
+                    .ok_or_else(|| anyhow::anyhow!("missing data"))?;
+                self.write(id, data).await
+            }
+            "rat/file/close" => self.close(id).await,
+            other => Err(anyhow::anyhow!("unknown method {}", other)),
+        }
+    }
+
+    async fn read(&mut self, id: &str, offset: u64) -> Result<Value> {
+        let transfer = self.get(id, Direction::Download)?;
+        if offset > transfer.size {
+            return Err(anyhow::anyhow!("offset {} is past the end", offset));
+        }
+        let want = (transfer.size - offset).min(MAX_CHUNK_BYTES as u64) as usize;
+        let mut buf = vec![0u8; want];
+        transfer.file.seek(std::io::SeekFrom::Start(offset)).await?;
+        transfer.file.read_exact(&mut buf).await?;
+        let next = offset + want as u64;
+        Ok(json!({
+            "data": general_purpose::STANDARD.encode(&buf),
+            "offset": offset,
+            "eof": next >= transfer.size
+        }))
+    }
+
+    async fn write(&mut self, id: &str, data: &str) -> Result<Value> {
+        let transfer = self.get(id, Direction::Upload)?;
+        let bytes = general_purpose::STANDARD
+            .decode(data)
+            .context("data is not base64")?;
+        if bytes.len() > MAX_CHUNK_BYTES {
+            return Err(anyhow::anyhow!(
+                "chunks are limited to {} bytes",
+                MAX_CHUNK_BYTES
+            ));
+        }
+        if transfer.written + bytes.len() as u64 > transfer.size {
+            return Err(anyhow::anyhow!(
+                "upload is larger than the announced {} bytes",
+                transfer.size
+            ));
+        }
+        transfer.file.write_all(&bytes).await?;
+        transfer.written += bytes.len() as u64;
+        Ok(json!({"written": transfer.written}))
+    }
+
+    /// Finish a transfer; an upload is moved into place only if complete
+    async fn close(&mut self, id: &str) -> Result<Value> {
+        let mut transfer = self
+            .open
+            .remove(id)
+            .ok_or_else(|| anyhow::anyhow!("no open transfer {}", id))?;
+        if transfer.direction == Direction::Upload {
+            if transfer.written != transfer.size {
+                return Err(anyhow::anyhow!(
+                    "upload incomplete: {} of {} bytes",
+                    transfer.written,
+                    transfer.size
+                ));
+            }
+            transfer.file.flush().await?;
+            if let Some(staging) = transfer.staging.take() {
+                tokio::fs::rename(&staging, &transfer.path)
+                    .await
+                    .with_context(|| format!("Failed to write {}", transfer.path.display()))?;
+            }
+        }
+        Ok(json!({"path": transfer.path, "size": transfer.size}))
+    }
+
+    fn get(&mut self, id: &str, direction: Direction) -> Result<&mut Transfer> {
+        match self.open.get_mut(id) {
+            Some(transfer) if transfer.direction == direction => Ok(transfer),
+            Some(_) => Err(anyhow::anyhow!("transfer {} goes the other way", id)),
+            None => Err(anyhow::anyhow!("no open transfer {}", id)),
+        }
+    }
+}
+
+fn too_large(size: u64) -> anyhow::Error {
+    anyhow::anyhow!(
+        "{} bytes is over the {} byte transfer limit",
+        size,
+        MAX_FILE_BYTES
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn uploads_and_downloads_in_chunks_inside_the_workspace() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut transfers = Transfers::new(dir.path().to_path_buf());
+        assert!(transfers.resolve("../escape").is_err());
+        assert!(transfers.resolve("/etc/passwd").is_err());
+
+        // An upload only appears once every announced byte arrived
+        let data = vec![7u8; MAX_CHUNK_BYTES + 10];
+        let path = transfers.resolve("in/attachment.bin").unwrap();
+        let opened = transfers
+            .open(path.clone(), Direction::Upload, Some(data.len() as u64))
+            .await
+            .unwrap();
+        let id = opened["transferId"].clone();
+        for chunk in data.chunks(MAX_CHUNK_BYTES) {
+            let params = json!({"transferId": id, "data": general_purpose::STANDARD.encode(chunk)});
+            transfers.handle("rat/file/write", &params).await.unwrap();
+        }
+        assert!(!path.exists());
+        transfers
+            .handle("rat/file/close", &json!({"transferId": id}))
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), data);
+
+        // Downloads come back in chunks until eof
+        let opened = transfers
+            .open(path, Direction::Download, None)
+            .await
+            .unwrap();
+        let id = opened["transferId"].clone();
+        let mut fetched = Vec::new();
+        loop {
+            let params = json!({"transferId": id, "offset": fetched.len()});
+            let chunk = transfers.handle("rat/file/read", &params).await.unwrap();
+            fetched.extend(
+                general_purpose::STANDARD
+                    .decode(chunk["data"].as_str().unwrap())
+                    .unwrap(),
+            );
+            if chunk["eof"] == true {
+                break;
+            }
+        }
+        assert_eq!(fetched, data);
+
+        // Oversized and short uploads are refused and leave no file
+        let big = transfers.resolve("big.bin").unwrap();
+        assert!(transfers
+            .open(big, Direction::Upload, Some(MAX_FILE_BYTES + 1))
+            .await
+            .is_err());
+        let short = transfers.resolve("short.bin").unwrap();
+        let opened = transfers
+            .open(short.clone(), Direction::Upload, Some(4))
+            .await
+            .unwrap();
+        let close = json!({"transferId": opened["transferId"]});
+        assert!(transfers.handle("rat/file/close", &close).await.is_err());
+        assert!(!short.exists());
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn uploads_cannot_follow_a_symlink_out_of_the_workspace() {
+        let dir = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        std::os::unix::fs::symlink(outside.path(), dir.path().join("link")).unwrap();
+        let mut transfers = Transfers::new(dir.path().to_path_buf());
+
+        for target in ["link/escape.bin", "link/new/dir/escape.bin"] {
+            let path = transfers.resolve(target).unwrap();
+            let opened = transfers.open(path, Direction::Upload, Some(1)).await;
+            assert!(opened.is_err(), "{} was accepted", target);
+        }
+        assert_eq!(std::fs::read_dir(outside.path()).unwrap().count(), 0);
+    }
+
+    #[tokio::test]
+    async fn a_client_can_only_hold_so_many_transfers_open() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut transfers = Transfers::new(dir.path().to_path_buf());
+        let mut ids = Vec::new();
+        for n in 0..MAX_OPEN_TRANSFERS {
+            let path = transfers.resolve(&format!("{}.bin", n)).unwrap();
+            let opened = transfers.open(path, Direction::Upload, Some(1)).await.unwrap();
+            ids.push(opened["transferId"].clone());
+        }
+        let path = transfers.resolve("one-more.bin").unwrap();
+        assert!(transfers
+            .open(path.clone(), Direction::Upload, Some(1))
+            .await
+            .is_err());
+
+        // Closing one, even unfinished, frees its slot
+        let close = json!({"transferId": ids[0]});
+        assert!(transfers.handle("rat/file/close", &close).await.is_err());
+        assert!(transfers.open(path, Direction::Upload, Some(1)).await.is_ok());
+    }
+}
@@ -1,43 +1,144 @@
 use anyhow::Result;
-use crossterm::event::{KeyCode, KeyEvent};
-use log::info;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use tracing::{debug, info, warn};
 use ratatui::{
     prelude::*,
-    widgets::{Block, Borders, Clear, Paragraph, Tabs, BorderType},
+    widgets::{Block, Borders, Paragraph, Tabs},
 };
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
 use tokio::sync::{mpsc, oneshot};
 use std::time::Instant;
 
 use tachyonfx::{fx, Duration as FxDuration, EffectManager as FxManager, Interpolation};
-use crate::effects::cyberpunk::{CyberTheme, neon_pulse_border, subtle_hsl_drift, sweep_in_attention, glitch_burst};
-use crate::effects::startup::matrix_rain_morph_with_duration;
+use crate::effects::cyberpunk::{
+    glitch_burst, neon_pulse_border, permission_flash, permission_pulse, subtle_hsl_drift,
+    sweep_in_attention, CyberTheme,
+};
+use crate::effects::pipeline::{self, CustomEffect, Trigger};
+use crate::effects::startup::StartupStyle;
 use tachyonfx::RefRect;
 use tachyonfx::{ref_count, BufferRenderer};
+use agent_client_protocol::ToolCallStatus;
 
 use crate::acp::{Message, MessageContent, SessionId};
 use crate::app::UiToApp;
-use crate::config::UiConfig;
-use crate::ui::{chat::ChatView, components::AgentSelector, statusbar::StatusBar};
+use crate::config::{ui::EffectsConfig, UiConfig};
+use crate::events::{Backpressure, PermissionReply};
+use crate::ui::{
+    ascii,
+    chat::ChatView,
+    color::{self, ColorDepth},
+    frame_budget::{FrameBudget, LoadChange},
+    components::{
+        agent_selector::{AgentInfo, AgentStatus},
+        AgentSelector, CommandLine, CommandPalette, GalleryItem, GalleryResult, HelpOverlay, Modal,
+        ModalResult, ThemeGallery, ToastLevel, Toasts,
+    },
+    keymap::{Action, KeyContext, KeyResolution, Keymap, KeymapPreset},
+    layout::{Pane, PaneKind, PaneLayout},
+    log_viewer::LogViewer,
+    plan::PlanView,
+    statusbar::{Segment, SessionStats, StatusBar},
+    terminal::{AgentCommands, TerminalLineLevel, TerminalView},
+    traffic::TrafficView,
+};
+use crate::integrations::tmux::{OutputPane, Placement};
+
+/// Upper bound on the chat sweep (see `sweep_in_attention`)
+const ATTENTION_FX_DURATION: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// Percent of the main area a grow or shrink moves the split by
+const PANE_RESIZE_STEP: i16 = 5;
+
+/// What a confirmation dialog can carry out once answered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Confirmed {
+    Quit,
+}
+
+/// A permission request the agent is blocked on until its dialog is answered
+#[derive(Debug)]
+struct WaitingPermission {
+    modal: Modal<()>,
+    reply: PermissionReply,
+}
+
+/// Theme and effects from before the gallery opened, put back if it is
+/// cancelled, and the effects preset previewed since
+#[derive(Debug, Clone)]
+struct GalleryBefore {
+    theme: String,
+    effects: EffectsConfig,
+    preset: Option<&'static str>,
+}
 
 pub struct TuiManager {
     config: UiConfig,
     active_tab: usize,
     tabs: Vec<Tab>,
     agent_selector: AgentSelector,
+    command_palette: CommandPalette,
+    /// Vim mode's `:` prompt
+    command_line: CommandLine,
+    toasts: Toasts,
     status_bar: StatusBar,
-    error_message: Option<String>,
-    show_help: bool,
+    terminal: TerminalView,
+    /// Side panes beside the active tab's chat
+    panes: PaneLayout,
+    plan_view: PlanView,
+    log_viewer: LogViewer,
+    traffic: TrafficView,
+    /// Open dialogs, the last on top and taking every key: errors the user
+    /// must acknowledge, informational popups and confirmations
+    modals: Vec<Modal<Confirmed>>,
+    /// Permission requests waiting for the user, oldest first; only the
+    /// first one's dialog is shown, above every other
+    permissions: VecDeque<WaitingPermission>,
+    help: HelpOverlay,
+    gallery: ThemeGallery,
+    gallery_before: Option<GalleryBefore>,
+    keymap: Keymap,
     ui_tx: mpsc::UnboundedSender<UiToApp>,
     default_agent: String,
     theme: CyberTheme,
+    theme_name: String,
+    /// What the terminal can show; frames are mapped down to it
+    color_depth: ColorDepth,
+    /// No truecolor, or a console `TERM`: effects stay off unless forced
+    basic_terminal: bool,
     fx: FxManager<&'static str>,
     last_fx_tick: Instant,
     ambient_fx_initialized: bool,
+    /// `[ui.effects.custom]`, compiled against the current theme
+    custom_fx: Vec<CustomEffect>,
+    /// Where the active chat and the status bar were last drawn, for
+    /// custom effects aimed at them
+    chat_area_ref: RefRect,
+    status_area_ref: RefRect,
+    /// Where the tab bar and the side panes were last drawn, for the hue
+    /// drift when it is limited to some components
+    tabs_area_ref: RefRect,
+    panes_area_ref: RefRect,
+    /// Where the waiting permission's dialog and status bar segment were
+    /// last drawn, and whether their alert effect is registered
+    permission_area_ref: RefRect,
+    permission_segment_ref: RefRect,
+    permission_alert_running: bool,
+    /// Frame draw times; ambient effects pause while it is degraded
+    frame_budget: FrameBudget,
     // Startup animation state
     startup_effect: Option<tachyonfx::Effect>,
     startup_running: bool,
     startup_duration_ms: u64,
+    /// When the last chat attention effect finishes
+    attention_until: Option<Instant>,
+    /// Working directories of launch profiles; other agents run in RAT's
+    agent_dirs: HashMap<String, PathBuf>,
+    /// Commands agents run, shown in the terminal panel or `output_pane`
+    agent_commands: AgentCommands,
+    /// tmux pane showing agents' commands instead of the terminal panel
+    output_pane: Option<OutputPane>,
 }
 
 #[derive(Debug, Clone)]
@@ -48,33 +149,90 @@ pub struct Tab {
     pub chat_view: ChatView,
     pub active: bool,
     pub chat_area_ref: RefRect,
+    /// Project instructions file sent with this session's first prompt
+    pub instructions: Option<String>,
+    /// The agent crashed and was restarted under this session
+    pub recovered: bool,
+    /// Token estimate, turn timing and permission counts for the status bar
+    pub stats: SessionStats,
 }
 
 impl TuiManager {
     pub fn new(
-        config: UiConfig,
+        mut config: UiConfig,
         ui_tx: mpsc::UnboundedSender<UiToApp>,
         default_agent: String,
     ) -> Result<Self> {
+        let color_depth = ColorDepth::from_config(&config.colors)?;
+        let basic_terminal = !color::supports_effects(color_depth, term().as_deref());
+        if basic_terminal && config.effects.enabled && !config.effects.force {
+            info!("Effects are off: the terminal lacks truecolor or is a console (ui.effects.force runs them anyway)");
+        }
+        fit_effects(&mut config.effects, config.accessibility, basic_terminal);
         let startup_duration_ms = config.effects.startup.duration_ms;
-        let startup_running = config.effects.enabled && config.effects.startup.enabled;
+        let startup_running = config.effects.startup_active();
+        let keymap = Keymap::from_ui_config(&config)?;
+        let panes = PaneLayout::from_config(&config.layout)?;
+        let mut terminal = TerminalView::new(1000);
+        if panes.has(PaneKind::Terminal) {
+            terminal.show();
+        }
+        let mut theme = CyberTheme::from_config(&config.theme, &config.theme.name)?;
+        if config.accessibility {
+            theme.palette = theme.palette.high_contrast();
+        }
+        let theme_name = config.theme.name.clone();
+        let frame_budget = FrameBudget::new(config.fps);
+        let custom_fx = pipeline::compile(&config.effects.custom, &theme.palette)?;
+        let mut status_bar = StatusBar::new();
+        status_bar.set_theme(theme);
+        status_bar.set_segments(Segment::from_names(&config.status_bar.segments));
+        status_bar.set_accessible(config.accessibility);
         Ok(Self {
             config,
             active_tab: 0,
             tabs: Vec::new(),
             agent_selector: AgentSelector::new(),
-            status_bar: StatusBar::new(),
-            error_message: None,
-            show_help: false,
+            command_palette: CommandPalette::default(),
+            command_line: CommandLine::default(),
+            toasts: Toasts::default(),
+            status_bar,
+            terminal,
+            panes,
+            plan_view: PlanView::new(),
+            log_viewer: LogViewer::new(5000),
+            traffic: TrafficView::new(2000),
+            modals: Vec::new(),
+            permissions: VecDeque::new(),
+            help: HelpOverlay::default(),
+            gallery: ThemeGallery::default(),
+            gallery_before: None,
+            keymap,
             ui_tx,
             default_agent,
-            theme: CyberTheme::default(),
+            theme,
+            theme_name,
+            color_depth,
+            basic_terminal,
             fx: FxManager::default(),
             last_fx_tick: Instant::now(),
             ambient_fx_initialized: false,
+            custom_fx,
+            chat_area_ref: RefRect::default(),
+            status_area_ref: RefRect::default(),
+            tabs_area_ref: RefRect::default(),
+            panes_area_ref: RefRect::default(),
+            permission_area_ref: RefRect::default(),
+            permission_segment_ref: RefRect::default(),
+            permission_alert_running: false,
+            frame_budget,
             startup_effect: None,
             startup_running,
             startup_duration_ms,
+            attention_until: None,
+            agent_dirs: HashMap::new(),
+            agent_commands: AgentCommands::default(),
+            output_pane: None,
         })
     }
 
@@ -105,7 +263,7 @@ impl TuiManager {
             .direction(Direction::Vertical)
             .constraints(
                 [
-                    Constraint::Length(3), // Tab bar
+                    Constraint::Length(self.config.layout.tab_bar_height), // Tab bar
                     Constraint::Min(1),    // Main content
                     Constraint::Length(1), // Status bar
                 ]
@@ -116,11 +274,60 @@ impl TuiManager {
         // Render tab bar if we have tabs
         if !self.tabs.is_empty() {
             self.render_tabs(frame, chunks[0]);
+            self.tabs_area_ref.set(chunks[0]);
+
+            // Split off the terminal panel below the chat when it is open,
+            // unless it has a pane of its own
+            let terminal_below = self.terminal.is_visible() && !self.panes.has(PaneKind::Terminal);
+            let (content_area, terminal_area) = if terminal_below {
+                let split = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([
+                        Constraint::Min(6),
+                        Constraint::Length(self.config.layout.terminal_height),
+                    ])
+                    .split(chunks[1]);
+                (split[0], Some(split[1]))
+            } else {
+                (chunks[1], None)
+            };
+
+            // Stacked panes keep the reading order top to bottom
+            let (chat_area, pane_areas) = if self.config.accessibility {
+                self.panes.stacked_areas(content_area)
+            } else {
+                self.panes.areas(content_area)
+            };
 
             // Render active tab content
             if let Some(active_tab) = self.tabs.get_mut(self.active_tab) {
-                active_tab.chat_area_ref.set(chunks[1]);
-                active_tab.chat_view.render(frame, chunks[1])?;
+                active_tab.chat_area_ref.set(chat_area);
+                self.chat_area_ref.set(chat_area);
+                active_tab.chat_view.render(frame, chat_area)?;
+            }
+            let panes = self.panes.panes().to_vec();
+            for (pane, area) in panes.into_iter().zip(pane_areas.iter().copied()) {
+                self.render_pane(frame, pane, area)?;
+            }
+            // Side panes sit together beside or below the chat
+            self.panes_area_ref.set(
+                pane_areas
+                    .iter()
+                    .copied()
+                    .reduce(|a, b| a.union(b))
+                    .unwrap_or_default(),
+            );
+            if let Some(area) = terminal_area {
+                self.terminal.render(frame, area)?;
+            }
+
+            // Only worth marking focus once there is more than one pane
+            if !pane_areas.is_empty() {
+                let focused = match self.panes.focused() {
+                    0 => chat_area,
+                    i => pane_areas[i - 1],
+                };
+                highlight_border(frame, focused, self.theme.palette.accent_a);
             }
         } else {
             // Show welcome screen
@@ -128,20 +335,58 @@ impl TuiManager {
         }
 
         // Render status bar
+        let active = self
+            .tabs
+            .get(self.active_tab)
+            .map(|tab| (tab.agent_name.clone(), tab.stats.clone()));
+        let git_dir = active
+            .as_ref()
+            .and_then(|(agent_name, _)| self.agent_dirs.get(agent_name).cloned());
+        self.status_bar.set_git_dir(git_dir);
+        self.status_bar.set_active(active);
+        self.status_bar.set_waiting_permissions(self.permissions.len());
         self.status_bar.render(frame, chunks[2])?;
-
-        // Render error popup if present
-        if let Some(ref error) = self.error_message {
-            self.render_error_popup(frame, error);
-        }
+        self.status_area_ref.set(chunks[2]);
+        // Flash the whole bar when the permissions segment is hidden
+        self.permission_segment_ref.set(
+            self.status_bar
+                .permissions_area(chunks[2])
+                .unwrap_or(chunks[2]),
+        );
+        self.command_line.render(frame, chunks[2]);
+
+        // Toasts sit above the status bar, under any popup
+        self.toasts.render(frame, chunks[1]);
+
+        // Agent selector popup (no-op while hidden)
+        self.agent_selector.render(frame, area)?;
+        self.command_palette
+            .render(frame, area, self.theme.palette.accent_b);
+        self.gallery.render(frame, area, self.theme.palette.accent_b);
 
         // Render help if requested
-        if self.show_help {
+        if self.help.is_visible() {
             self.render_help_popup(frame);
         }
 
+        // Dialogs go over everything else
+        for modal in &self.modals {
+            let accent = match modal.level() {
+                ToastLevel::Error => self.theme.palette.accent_a,
+                _ => self.theme.palette.accent_b,
+            };
+            modal.render(frame, area, accent, self.theme.palette.error);
+        }
+        // The agent is blocked on this one, so it goes on top
+        if let Some(waiting) = self.permissions.front() {
+            self.permission_area_ref.set(waiting.modal.area(area));
+            waiting
+                .modal
+                .render(frame, area, self.theme.palette.warning, self.theme.palette.error);
+        }
+
         // Apply startup/ambient effects depending on config
-        if self.config.effects.enabled {
+        if self.config.effects.animate() {
             if self.startup_running {
                 self.apply_startup_fx(frame);
             } else {
@@ -150,14 +395,83 @@ impl TuiManager {
             }
         }
 
+        if self.config.accessibility {
+            ascii::ascii_buffer(frame.buffer_mut());
+        }
+        color::downsample_buffer(frame.buffer_mut(), self.color_depth);
+
+        Ok(())
+    }
+
+    fn render_pane(&mut self, frame: &mut Frame, pane: Pane, area: Rect) -> Result<()> {
+        match pane.kind {
+            PaneKind::Plan => {
+                self.plan_view.plan = self
+                    .tabs
+                    .get(self.active_tab)
+                    .and_then(|tab| tab.chat_view.latest_plan().cloned());
+                self.plan_view.accessible = self.config.accessibility;
+                self.plan_view.render(area, frame);
+            }
+            PaneKind::Terminal if self.terminal.is_visible() => self.terminal.render(frame, area)?,
+            PaneKind::Terminal => {
+                let hint = format!("Hidden - press {} to show", self.keymap.label(Action::ToggleTerminal));
+                let block = Block::default().title("Terminal").borders(Borders::ALL);
+                frame.render_widget(Paragraph::new(hint).dark_gray().block(block), area);
+            }
+            PaneKind::Diff => {
+                if let Some(tab) = self.tabs.get(self.active_tab) {
+                    tab.chat_view.render_edits(frame, area);
+                }
+            }
+            PaneKind::Logs => self.log_viewer.render(frame, area),
+            PaneKind::Traffic => {
+                let tab = self.tabs.get(self.active_tab);
+                self.traffic.render(
+                    frame,
+                    area,
+                    tab.map(|tab| tab.agent_name.as_str()),
+                    tab.and_then(|tab| tab.session_id.as_ref()).map(|id| id.0.as_str()),
+                );
+            }
+            PaneKind::Chat => match self.tabs.get_mut(pane.tab) {
+                Some(tab) => tab.chat_view.render_transcript(frame, area),
+                None => {
+                    let block = Block::default().title("Chat").borders(Borders::ALL);
+                    frame.render_widget(block, area);
+                }
+            },
+        }
         Ok(())
     }
 
     fn render_tabs(&self, frame: &mut Frame, area: Rect) {
-        let tab_names: Vec<&str> = self.tabs.iter().map(|tab| tab.name.as_str()).collect();
+        let tab_names: Vec<String> = self
+            .tabs
+            .iter()
+            .map(|tab| {
+                let mut name = tab.name.clone();
+                if tab.instructions.is_some() {
+                    name.push_str(" ◆");
+                }
+                if tab.recovered {
+                    name.push_str(" ↻");
+                }
+                if self.config.accessibility {
+                    name = ascii::ascii(&name);
+                }
+                name
+            })
+            .collect();
 
+        // Too short for the underline, just the names
+        let borders = if area.height > 1 {
+            Borders::BOTTOM
+        } else {
+            Borders::NONE
+        };
         let tabs = Tabs::new(tab_names)
-            .block(Block::default().borders(Borders::BOTTOM))
+            .block(Block::default().borders(borders))
             .style(self.theme.title_inactive())
             .highlight_style(self.theme.title_active())
             .select(self.active_tab);
@@ -166,16 +480,21 @@ impl TuiManager {
     }
 
     fn render_welcome(&self, frame: &mut Frame, area: Rect) {
+        let key = |action| self.keymap.label(action);
         let welcome_text = vec![
             Line::from("Welcome to RAT (Rust Agent Terminal)!"),
             Line::from(""),
             Line::from("Commands:"),
-            Line::from("  n - Create new session with default agent"),
-            Line::from("  a - Select agent"),
-            Line::from("  ? - Show help"),
-            Line::from("  q - Quit"),
+            Line::from(format!("  {} - Create new session with default agent", key(Action::NewSession))),
+            Line::from(format!("  {} - Select agent", key(Action::SwitchAgent))),
+            Line::from(format!("  {} - Command palette", key(Action::CommandPalette))),
+            Line::from(format!("  {} - Show help", key(Action::ToggleHelp))),
+            Line::from(format!("  {} - Quit", key(Action::Quit))),
             Line::from(""),
-            Line::from("No active sessions. Press 'n' to start!"),
+            Line::from(format!(
+                "No active sessions. Press '{}' to start!",
+                key(Action::NewSession)
+            )),
         ];
 
         let welcome = Paragraph::new(welcome_text)
@@ -186,235 +505,1176 @@ impl TuiManager {
         frame.render_widget(welcome, area);
     }
 
-    fn render_error_popup(&self, frame: &mut Frame, error: &str) {
-        let area = centered_rect(60, 20, frame.area());
+    fn render_help_popup(&mut self, frame: &mut Frame) {
+        let area = centered_rect(80, 60, frame.area());
+        let sections = self.keymap.help_sections();
+        self.help
+            .render(frame, area, &sections, self.theme.palette.accent_b);
+    }
 
-        frame.render_widget(Clear, area);
+    pub async fn handle_key_event(&mut self, key: KeyEvent) -> Result<()> {
+        // A waiting permission request is answered before anything else
+        if let Some(waiting) = self.permissions.front_mut() {
+            if let ModalResult::Closed(allowed) = waiting.modal.handle_key_event(key) {
+                waiting.reply.send(allowed.is_some());
+                self.permissions.pop_front();
+            }
+            return Ok(());
+        }
 
-        let error_text = vec![
-            Line::from("Error"),
-            Line::from(""),
-            Line::from(error),
-            Line::from(""),
-            Line::from("Press any key to dismiss"),
-        ];
+        // An open dialog traps focus until it is answered
+        if let Some(modal) = self.modals.last_mut() {
+            if let ModalResult::Closed(confirmed) = modal.handle_key_event(key) {
+                self.modals.pop();
+                if let Some(confirmed) = confirmed {
+                    self.run_confirmed(confirmed);
+                }
+            }
+            return Ok(());
+        }
 
-        let popup = Paragraph::new(error_text)
-            .block(
-                Block::default()
-                    .title("Error")
-                    .borders(Borders::ALL)
-                    .border_type(BorderType::Double)
-                    .border_style(Style::default().fg(self.theme.palette.accent_a)),
-            )
-            .alignment(Alignment::Center)
-            .wrap(ratatui::widgets::Wrap { trim: true });
+        // The terminal search prompt owns the keyboard while typing
+        if self.terminal.is_visible() && self.terminal.handle_key_event(key)? {
+            return Ok(());
+        }
 
-        frame.render_widget(popup, area);
-    }
+        // As does the log filter prompt
+        if self.log_viewer.is_searching() && self.log_viewer.handle_key_event(key) {
+            return Ok(());
+        }
 
-    fn render_help_popup(&self, frame: &mut Frame) {
-        let area = centered_rect(80, 60, frame.area());
+        // And the chat search prompt
+        let chat = self.focused_chat_index();
+        if let Some(tab) = self.tabs.get_mut(chat) {
+            if tab.chat_view.handle_search_key(key) {
+                return Ok(());
+            }
+        }
 
-        frame.render_widget(Clear, area);
+        // Vim's `:` prompt runs the command typed once Enter is pressed
+        if self.command_line.is_open() {
+            if let Some(command) = self.command_line.handle_key_event(key) {
+                self.run_command(&command).await?;
+            }
+            return Ok(());
+        }
 
-        let help_text = vec![
-            Line::from("RAT - Rust Agent Terminal Help"),
-            Line::from(""),
-            Line::from("Global Commands:"),
-            Line::from("  q       - Quit application"),
-            Line::from("  ?       - Toggle this help"),
-            Line::from("  Ctrl+C  - Force quit"),
-            Line::from(""),
-            Line::from("Session Management:"),
-            Line::from("  n       - New session with default agent"),
-            Line::from("  a       - Switch agent"),
-            Line::from("  Tab     - Next tab"),
-            Line::from("  Shift+Tab - Previous tab"),
-            Line::from(""),
-            Line::from("Chat:"),
-            Line::from("  Enter   - Send message"),
-            Line::from("  Esc     - Cancel input"),
-            Line::from(""),
-            Line::from("Edit Review:"),
-            Line::from("  y       - Accept edit"),
-            Line::from("  n       - Reject edit"),
-            Line::from("  d       - Show diff"),
-            Line::from(""),
-            Line::from("Press any key to close help"),
-        ];
+        // The help overlay searches as you type
+        if self.help.is_visible() {
+            self.help.handle_key_event(key);
+            return Ok(());
+        }
 
-        let popup = Paragraph::new(help_text)
-            .block(
-                Block::default()
-                    .title("Help")
-                    .borders(Borders::ALL)
-                    .border_type(BorderType::Double)
-                    .border_style(Style::default().fg(self.theme.palette.accent_b)),
-            )
-            .wrap(ratatui::widgets::Wrap { trim: true });
+        if self.gallery.is_visible() {
+            self.handle_gallery_key(key);
+            return Ok(());
+        }
 
-        frame.render_widget(popup, area);
-    }
+        if self.command_palette.is_visible() {
+            return self.handle_palette_key(key).await;
+        }
 
-    pub async fn handle_key_event(&mut self, key: KeyEvent) -> Result<()> {
-        // Intercept Enter to send a chat message bound to the active session
-        if let KeyCode::Enter = key.code {
-            if let Some(active_tab) = self.tabs.get_mut(self.active_tab) {
-                if active_tab.chat_view.is_input_mode() {
-                    let content = active_tab.chat_view.get_input_buffer().trim().to_string();
-                    if !content.is_empty() {
-                        if let Some(session_id) = active_tab.session_id.clone() {
-                            // Create and add user message to chat history immediately
-                            let user_message = Message::new(
-                                session_id.clone(),
-                                MessageContent::UserPrompt {
-                                    content: vec![agent_client_protocol::ContentBlock::Text(
-                                        agent_client_protocol::TextContent {
-                                            text: content.clone(),
-                                            annotations: Default::default(),
-                                        },
-                                    )],
-                                },
-                            );
-
-                            // Add to current tab's chat view
-                            if let Err(e) = active_tab.chat_view.add_message(user_message).await {
-                                self.error_message = Some(format!("Failed to add message: {}", e));
-                            }
+        if self.agent_selector.is_visible() {
+            return self.handle_selector_key(key).await;
+        }
+
+        let chat_input_active = self
+            .tabs
+            .get(self.active_tab)
+            .map(|tab| tab.chat_view.is_input_mode())
+            .unwrap_or(false);
+
+        if chat_input_active {
+            // Typing goes to the input; only a few bindings (tab switching) apply
+            if let Some(action) = self
+                .keymap
+                .lookup(key, &[KeyContext::Global])
+                .filter(|a| a.allowed_while_typing())
+            {
+                return self.run_action(action).await;
+            }
 
-                            let (tx, _rx) = oneshot::channel();
-                            let _ = self.ui_tx.send(UiToApp::SendMessage {
-                                agent_name: active_tab.agent_name.clone(),
-                                session_id,
-                                content,
-                                respond_to: tx,
-                            });
-                        } else {
-                            self.error_message = Some("No active session for this tab".to_string());
+            // Intercept Enter to send a chat message bound to the active session
+            if let KeyCode::Enter = key.code {
+                if let Some(active_tab) = self.tabs.get_mut(self.active_tab) {
+                    if active_tab.chat_view.is_input_mode() {
+                        let content = active_tab.chat_view.get_input_buffer().trim().to_string();
+                        if !content.is_empty() {
+                            if let Some(session_id) = active_tab.session_id.clone() {
+                                // Create and add user message to chat history immediately
+                                let user_message = Message::new(
+                                    session_id.clone(),
+                                    MessageContent::UserPrompt {
+                                        content: vec![agent_client_protocol::ContentBlock::Text(
+                                            agent_client_protocol::TextContent {
+                                                text: content.clone(),
+                                                annotations: Default::default(),
+                                            },
+                                        )],
+                                    },
+                                );
+
+                                active_tab.stats.record(&user_message);
+                                active_tab.stats.start_turn();
+
+                                // Add to current tab's chat view
+                                if let Err(e) = active_tab.chat_view.add_message(user_message).await {
+                                    self.toasts
+                                        .push(ToastLevel::Error, format!("Failed to add message: {}", e));
+                                }
+
+                                let (tx, _rx) = oneshot::channel();
+                                let _ = self.ui_tx.send(UiToApp::SendMessage {
+                                    agent_name: active_tab.agent_name.clone(),
+                                    session_id,
+                                    content,
+                                    respond_to: tx,
+                                });
+                            } else {
+                                self.toasts
+                                    .push(ToastLevel::Warning, "No active session for this tab".to_string());
+                            }
                         }
                     }
                 }
             }
+
+            if let Some(active_tab) = self.tabs.get_mut(self.active_tab) {
+                active_tab.chat_view.handle_key_event(key).await?;
+            }
+            return Ok(());
+        }
+
+        let contexts = self.active_contexts();
+        match self.keymap.resolve(key, &contexts) {
+            KeyResolution::Action(action) => self.run_action(action).await,
+            KeyResolution::Pending | KeyResolution::Unbound => Ok(()),
+        }
+    }
+
+    /// Key contexts in priority order for the current layout
+    fn active_contexts(&self) -> Vec<KeyContext> {
+        let mut contexts = Vec::with_capacity(4);
+        let chat_search = self
+            .tabs
+            .get(self.focused_chat_index())
+            .is_some_and(|tab| tab.chat_view.has_search());
+        if (self.terminal.is_visible() && self.terminal.has_search()) || chat_search {
+            contexts.push(KeyContext::Search);
+        }
+        contexts.push(KeyContext::Global);
+        if self
+            .panes
+            .focused_pane()
+            .is_some_and(|pane| pane.kind == PaneKind::Logs)
+        {
+            contexts.push(KeyContext::Logs);
+        }
+        if self
+            .panes
+            .focused_pane()
+            .is_some_and(|pane| pane.kind == PaneKind::Traffic)
+        {
+            contexts.push(KeyContext::Traffic);
         }
+        if self.terminal.is_visible() {
+            contexts.push(KeyContext::Terminal);
+        }
+        contexts.push(KeyContext::Chat);
+        contexts
+    }
 
-        // Handle global keys first
+    async fn handle_palette_key(&mut self, key: KeyEvent) -> Result<()> {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
         match key.code {
-            KeyCode::Char('?') => {
-                self.show_help = !self.show_help;
-                return Ok(());
+            KeyCode::Up => self.command_palette.previous(),
+            KeyCode::Down | KeyCode::Tab => self.command_palette.next(),
+            KeyCode::Char('p') if ctrl => self.command_palette.previous(),
+            KeyCode::Char('n') if ctrl => self.command_palette.next(),
+            KeyCode::Char(c) if !ctrl => self.command_palette.push_char(c),
+            KeyCode::Backspace => self.command_palette.pop_char(),
+            KeyCode::Enter => {
+                let action = self.command_palette.selected();
+                self.command_palette.hide();
+                if let Some(action) = action {
+                    return self.run_action(action).await;
+                }
             }
+            // Esc clears the filter first, then closes
             KeyCode::Esc => {
-                self.error_message = None;
-                self.show_help = false;
-                return Ok(());
+                if self.command_palette.query().is_empty() {
+                    self.command_palette.hide();
+                } else {
+                    self.command_palette.set_query(String::new());
+                }
             }
             _ => {}
         }
+        Ok(())
+    }
 
-        // If help or error is showing, consume any key to dismiss
-        if self.show_help || self.error_message.is_some() {
-            self.show_help = false;
-            self.error_message = None;
-            return Ok(());
-        }
-
-        // Check if chat input is active before processing global keys
-        let chat_input_active = if let Some(active_tab) = self.tabs.get(self.active_tab) {
-            active_tab.chat_view.is_input_mode()
-        } else {
-            false
-        };
-
-        // Tab navigation (always allowed)
+    async fn handle_selector_key(&mut self, key: KeyEvent) -> Result<()> {
         match key.code {
-            KeyCode::Tab => {
-                self.next_tab();
-                return Ok(());
+            KeyCode::Up => self.agent_selector.previous(),
+            KeyCode::Down | KeyCode::Tab => self.agent_selector.next(),
+            KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.agent_selector.previous()
             }
-            KeyCode::BackTab => {
-                self.prev_tab();
-                return Ok(());
+            KeyCode::Char('n') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.agent_selector.next()
+            }
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.agent_selector.push_char(c)
+            }
+            KeyCode::Backspace => {
+                self.agent_selector.pop_char();
+            }
+            KeyCode::Enter => {
+                let selected = self
+                    .agent_selector
+                    .get_selected_agent()
+                    .map(|a| (a.name.clone(), a.status.clone()));
+                self.agent_selector.hide();
+                if let Some((agent_name, status)) = selected {
+                    let failed = matches!(status, AgentStatus::Failed { .. });
+                    // Selecting a failed agent is the explicit retry it waits for
+                    if failed {
+                        let _ = self.ui_tx.send(UiToApp::RetryAgent {
+                            agent_name: agent_name.clone(),
+                        });
+                    }
+                    if status != AgentStatus::Connected {
+                        self.agent_selector
+                            .update_agent_status(&agent_name, AgentStatus::Connecting);
+                    }
+                    self.create_session_for(agent_name).await?;
+                }
+            }
+            // Esc clears the filter first, then closes
+            KeyCode::Esc => {
+                if self.agent_selector.query().is_empty() {
+                    self.agent_selector.hide();
+                } else {
+                    self.agent_selector.set_query(String::new());
+                }
             }
             _ => {}
         }
+        Ok(())
+    }
 
-        // Only process these global keys if chat input is NOT active
-        if !chat_input_active {
-            match key.code {
-                KeyCode::Char('n') => {
-                    // Create new session with default agent
-                    self.create_new_session().await?;
-                    return Ok(());
+    async fn run_action(&mut self, action: Action) -> Result<()> {
+        match action {
+            Action::Quit => {
+                let running = self
+                    .tabs
+                    .iter()
+                    .filter(|tab| tab.stats.turn_started.is_some())
+                    .count();
+                if running == 0 {
+                    let _ = self.ui_tx.send(UiToApp::Quit);
+                } else {
+                    self.modals.push(Modal::confirm(
+                        "Quit RAT?",
+                        vec![format!(
+                            "{} session{} still waiting on {} agent. Quitting stops {}.",
+                            running,
+                            if running == 1 { " is" } else { "s are" },
+                            if running == 1 { "its" } else { "their" },
+                            if running == 1 { "it" } else { "them" },
+                        )],
+                        "Quit",
+                        Confirmed::Quit,
+                    ));
+                }
+            }
+            Action::ToggleHelp => self.help.show(),
+            Action::CommandLine => self.command_line.open(),
+            Action::CommandPalette => {
+                let entries = self.keymap.palette_entries(&self.active_contexts());
+                self.command_palette.show(entries);
+            }
+            Action::NewSession => self.create_new_session().await?,
+            Action::SwitchAgent => self.agent_selector.toggle_visibility(),
+            Action::SwitchProfile => {
+                let _ = self.ui_tx.send(UiToApp::CycleProfile);
+            }
+            Action::SwitchTheme => self.cycle_theme(),
+            Action::ThemeGallery => self.open_gallery(),
+            Action::NextTab => self.next_tab(),
+            Action::PrevTab => self.prev_tab(),
+            Action::ToggleTerminal => self.terminal.toggle(),
+            Action::ExportSession => {
+                match self.tabs.get(self.active_tab).and_then(|t| t.session_id.clone()) {
+                    Some(session_id) => {
+                        let _ = self.ui_tx.send(UiToApp::ExportSession { session_id });
+                    }
+                    None => self
+                        .toasts
+                        .push(ToastLevel::Warning, "No session to export".to_string()),
+                }
+            }
+            Action::PullRequest => match self.tabs.get(self.active_tab) {
+                Some(Tab {
+                    agent_name,
+                    session_id: Some(session_id),
+                    ..
+                }) => {
+                    let _ = self.ui_tx.send(UiToApp::CreatePullRequest {
+                        agent_name: agent_name.clone(),
+                        session_id: session_id.clone(),
+                    });
+                }
+                _ => self
+                    .toasts
+                    .push(ToastLevel::Warning, "No session to open a pull request for".to_string()),
+            },
+            Action::InterruptTurn => match self.tabs.get(self.active_tab) {
+                Some(Tab {
+                    agent_name,
+                    session_id: Some(session_id),
+                    stats,
+                    ..
+                }) if stats.turn_started.is_some() => {
+                    let _ = self.ui_tx.send(UiToApp::CancelTurn {
+                        agent_name: agent_name.clone(),
+                        session_id: session_id.clone(),
+                    });
+                }
+                _ => self
+                    .toasts
+                    .push(ToastLevel::Warning, "No running turn to stop".to_string()),
+            },
+            Action::OpenChangedFiles => match self.tabs.get(self.active_tab) {
+                Some(Tab {
+                    agent_name,
+                    session_id: Some(session_id),
+                    ..
+                }) => {
+                    let _ = self.ui_tx.send(UiToApp::OpenChangedFiles {
+                        agent_name: agent_name.clone(),
+                        session_id: session_id.clone(),
+                    });
+                }
+                _ => self
+                    .toasts
+                    .push(ToastLevel::Warning, "No session to open files from".to_string()),
+            },
+            Action::TmuxSplit | Action::TmuxWindow => {
+                let agent_name = match self.tabs.get(self.active_tab) {
+                    Some(tab) => tab.agent_name.clone(),
+                    None => self.default_agent.clone(),
+                };
+                let placement = if action == Action::TmuxSplit { Placement::Split } else { Placement::Window };
+                let _ = self.ui_tx.send(UiToApp::OpenInTmux { agent_name, placement });
+            }
+            Action::PermissionRules => {
+                let _ = self.ui_tx.send(UiToApp::ShowPermissionRules);
+            }
+            Action::SplitPane => match self.panes.split() {
+                Some(kind) => {
+                    self.sync_terminal_pane(None, kind);
+                    self.status_bar.set_message(format!("Opened {} pane", kind.name()));
+                }
+                None => self.toasts.push(
+                    ToastLevel::Info,
+                    format!("At most {} panes fit beside the chat", crate::ui::layout::MAX_PANES),
+                ),
+            },
+            Action::ClosePane => {
+                if let Some(kind) = self.panes.close_focused() {
+                    self.sync_terminal_pane(Some(kind), PaneKind::Chat);
+                }
+            }
+            Action::CyclePane => {
+                if let Some((before, after)) = self.panes.cycle_focused(self.tabs.len()) {
+                    self.sync_terminal_pane(Some(before), after);
+                }
+            }
+            Action::ToggleLogs => {
+                if self.panes.has(PaneKind::Logs) {
+                    self.panes.close(PaneKind::Logs);
+                } else if !self.panes.open(PaneKind::Logs) {
+                    self.toasts.push(
+                        ToastLevel::Info,
+                        "Close a pane to make room for the logs".to_string(),
+                    );
+                }
+            }
+            Action::ToggleTraffic => {
+                if self.panes.has(PaneKind::Traffic) {
+                    self.panes.close(PaneKind::Traffic);
+                } else if !self.panes.open(PaneKind::Traffic) {
+                    self.toasts.push(
+                        ToastLevel::Info,
+                        "Close a pane to make room for the traffic view".to_string(),
+                    );
+                }
+            }
+            Action::TrafficPause
+            | Action::TrafficPretty
+            | Action::TrafficClear
+            | Action::TrafficScrollUp
+            | Action::TrafficScrollDown => {
+                self.traffic.handle_action(action);
+            }
+            Action::LogsSearch
+            | Action::LogsLevel
+            | Action::LogsClear
+            | Action::LogsScrollUp
+            | Action::LogsScrollDown => {
+                self.log_viewer.handle_action(action);
+            }
+            Action::FocusPane => self.panes.focus_next(),
+            Action::RotatePanes => self.panes.rotate(),
+            Action::GrowPane => {
+                self.panes.resize_focused(PANE_RESIZE_STEP);
+            }
+            Action::ShrinkPane => {
+                self.panes.resize_focused(-PANE_RESIZE_STEP);
+            }
+            Action::TerminalTaller | Action::TerminalShorter => {
+                let height = &mut self.config.layout.terminal_height;
+                *height = if action == Action::TerminalTaller {
+                    (*height + 2).min(50)
+                } else {
+                    height.saturating_sub(2).max(3)
+                };
+            }
+            Action::SaveLayout => {
+                let panes = self.panes.pane_names();
+                let split = self.panes.direction().name().to_string();
+                let main_percent = self.panes.main_percent();
+                // The reload the write triggers then leaves the panes alone
+                self.config.layout.panes = panes.clone();
+                self.config.layout.split = split.clone();
+                self.config.layout.main_percent = main_percent;
+                let _ = self.ui_tx.send(UiToApp::SaveLayout {
+                    panes,
+                    split,
+                    main_percent,
+                    terminal_height: self.config.layout.terminal_height,
+                });
+            }
+            Action::ChatStartInput => {
+                if let Some(active_tab) = self.tabs.get_mut(self.active_tab) {
+                    active_tab.chat_view.handle_action(action);
+                }
+            }
+            // Scrolling and search follow focus onto a chat pane
+            Action::ChatScrollUp
+            | Action::ChatScrollDown
+            | Action::ChatScrollTop
+            | Action::ChatScrollBottom
+            | Action::ChatSearch => {
+                let index = self.focused_chat_index();
+                if let Some(tab) = self.tabs.get_mut(index) {
+                    tab.chat_view.handle_action(action);
                 }
-                KeyCode::Char('a') => {
-                    // Show agent selector (toggle visibility)
-                    self.agent_selector.toggle_visibility();
-                    return Ok(());
+            }
+            // A terminal search takes these first, as its context is checked first
+            Action::SearchNext | Action::SearchPrev | Action::SearchClear
+                if !(self.terminal.is_visible() && self.terminal.has_search()) =>
+            {
+                let index = self.focused_chat_index();
+                if let Some(tab) = self.tabs.get_mut(index) {
+                    match action {
+                        Action::SearchNext => tab.chat_view.jump_match(true),
+                        Action::SearchPrev => tab.chat_view.jump_match(false),
+                        _ => tab.chat_view.clear_search(),
+                    }
                 }
-                KeyCode::Char('q') => {
-                    // TODO: Implement quit functionality
-                    return Ok(());
+            }
+            // Terminal and search actions
+            _ => {
+                if let Err(e) = self.terminal.handle_action(action) {
+                    self.status_bar.set_message(e.to_string());
                 }
-                _ => {}
             }
         }
+        Ok(())
+    }
 
-        // Pass to active tab
-        if let Some(active_tab) = self.tabs.get_mut(self.active_tab) {
-            active_tab.chat_view.handle_key_event(key).await?;
+    /// Tab whose chat has focus: a focused chat pane's, else the active one
+    fn focused_chat_index(&self) -> usize {
+        match self.panes.focused_pane() {
+            Some(Pane {
+                kind: PaneKind::Chat,
+                tab,
+            }) => tab,
+            _ => self.active_tab,
         }
+    }
 
-        Ok(())
+    /// Run a `:` command: a tab number, or an action by name
+    async fn run_command(&mut self, command: &str) -> Result<()> {
+        if let Ok(number) = command.trim().parse::<usize>() {
+            if (1..=self.tabs.len()).contains(&number) {
+                self.active_tab = number - 1;
+            } else {
+                self.toasts
+                    .push(ToastLevel::Warning, format!("No tab {}", number));
+            }
+            return Ok(());
+        }
+        match Action::from_command(command) {
+            Some(action) => self.run_action(action).await,
+            None => {
+                if !command.trim().is_empty() {
+                    self.toasts.push(
+                        ToastLevel::Warning,
+                        format!("Not a command: {}", command.trim()),
+                    );
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Show the terminal when a pane starts showing it, hide it when the last
+    /// terminal pane goes away
+    fn sync_terminal_pane(&mut self, before: Option<PaneKind>, after: PaneKind) {
+        if after == PaneKind::Terminal {
+            self.terminal.show();
+        } else if before == Some(PaneKind::Terminal) && !self.panes.has(PaneKind::Terminal) {
+            self.terminal.hide();
+        }
     }
 
-    pub async fn tick(&mut self) -> Result<()> {
-        // Update chat views
+    /// Periodic housekeeping; returns whether anything on screen changed
+    pub async fn tick(&mut self) -> Result<bool> {
+        // Update chat views (tool call spinners)
+        let mut changed = false;
         for tab in &mut self.tabs {
-            tab.chat_view.tick().await?;
+            changed |= tab.chat_view.tick().await?;
         }
 
-        // Update status bar
-        self.status_bar.tick().await?;
+        // Update terminal panel (collect output, reap exited processes)
+        changed |= self.terminal.tick().await?;
+
+        // Tail the log while its pane is open
+        if self.panes.has(PaneKind::Logs) {
+            match self.log_viewer.tick() {
+                Ok(read) => changed |= read,
+                Err(e) => debug!("Reading the log failed: {}", e),
+            }
+        }
 
-        // Ensure long-running ambience is registered (if enabled)
-        if self.config.effects.enabled && !self.ambient_fx_initialized {
+        // Capture ACP frames only while their pane is open
+        self.traffic.attach(self.panes.has(PaneKind::Traffic));
+        changed |= self.traffic.tick();
+
+        // Update status bar
+        changed |= self.status_bar.tick().await?;
+        changed |= self.toasts.tick();
+
+        // Drop requests the agent stopped waiting on, e.g. when it exited
+        let waiting = self.permissions.len();
+        self.permissions.retain(|p| !p.reply.is_closed());
+        changed |= self.permissions.len() != waiting;
+        self.sync_permission_alert();
+
+        // Ensure long-running ambience is registered (if enabled and the
+        // terminal keeps up)
+        if self.ambient_active() && !self.ambient_fx_initialized {
             // Subtle global hue drift
-            self.fx.add_unique_effect("global_drift", subtle_hsl_drift());
+            if self.config.effects.hue_drift_active() {
+                self.fx.add_unique_effect("global_drift", self.scoped_drift());
+            }
             // Neon border pulse
-            self.fx.add_unique_effect("neon_border", neon_pulse_border(&self.theme));
+            if self.config.effects.border_pulse_active() {
+                self.fx.add_unique_effect("neon_border", neon_pulse_border(&self.theme));
+            }
+            let effects = &self.config.effects;
+            let custom: Vec<_> = self
+                .custom_fx
+                .iter()
+                .filter(|effect| effect.trigger == Trigger::Ambient)
+                .filter(|effect| effect.target.component().is_none_or(|c| effects.on(c)))
+                .map(|effect| effect.instance(&self.chat_area_ref, &self.status_area_ref))
+                .collect();
+            if !custom.is_empty() {
+                self.fx.add_unique_effect("custom_ambient", fx::parallel(&custom));
+            }
             self.ambient_fx_initialized = true;
         }
 
-        Ok(())
+        Ok(changed)
+    }
+
+    /// Run the permission alert while a request waits, and stop it once
+    /// none does
+    fn sync_permission_alert(&mut self) {
+        let effects = &self.config.effects;
+        let alert = !self.permissions.is_empty() && effects.permission_alert_active();
+        if alert && !self.permission_alert_running {
+            let palette = &self.theme.palette;
+            let mut alerts = vec![fx::dynamic_area(
+                self.permission_area_ref.clone(),
+                permission_pulse(palette.warning),
+            )];
+            if effects.on("statusbar") {
+                alerts.push(fx::dynamic_area(
+                    self.permission_segment_ref.clone(),
+                    permission_flash(palette.crust, palette.warning),
+                ));
+            }
+            self.fx.add_unique_effect("permission_alert", fx::parallel(&alerts));
+            self.permission_alert_running = true;
+        } else if !alert && self.permission_alert_running {
+            // Replacing a unique effect cancels the one running under its key
+            self.fx
+                .add_unique_effect("permission_alert", fx::sleep(0));
+            self.permission_alert_running = false;
+        }
+    }
+
+    /// Hue drift over the components in `ui.effects.components`; the whole
+    /// screen when none is left out
+    fn scoped_drift(&self) -> tachyonfx::Effect {
+        let areas = [
+            ("chat", &self.chat_area_ref),
+            ("tabs", &self.tabs_area_ref),
+            ("panes", &self.panes_area_ref),
+            ("statusbar", &self.status_area_ref),
+        ];
+        if areas.iter().all(|(component, _)| self.config.effects.on(component)) {
+            return subtle_hsl_drift();
+        }
+        let scoped: Vec<_> = areas
+            .into_iter()
+            .filter(|(component, _)| self.config.effects.on(component))
+            .map(|(_, area)| fx::dynamic_area(area.clone(), subtle_hsl_drift()))
+            .collect();
+        fx::parallel(&scoped)
+    }
+
+    /// Whether ambient effects run: effects animate and frames keep up
+    fn ambient_active(&self) -> bool {
+        self.config.effects.animate() && !self.frame_budget.is_degraded()
+    }
+
+    /// Whether an effect is running, so frames change without any event
+    pub fn is_animating(&self) -> bool {
+        let effects = &self.config.effects;
+        let ambient = self.ambient_active()
+            && (effects.hue_drift_active()
+                || effects.border_pulse_active()
+                || self.custom_fx.iter().any(|e| e.trigger == Trigger::Ambient));
+        self.startup_running
+            || ambient
+            || (effects.permission_alert_active() && !self.permissions.is_empty())
+            // The glitch burst never finishes, so it keeps the attention
+            // effect running once a message has started it; it is shed
+            // with the ambient effects
+            || (effects.glitch_active() && self.attention_until.is_some() && self.ambient_active())
+            || self.attention_until.is_some_and(|until| Instant::now() < until)
     }
 
     pub async fn add_message(&mut self, agent_name: &str, message: Message) -> Result<()> {
+        // A finished tool call may have edited, moved or deleted files
+        if let MessageContent::ToolCallUpdate { update } = &message.content {
+            if update.fields.status == Some(ToolCallStatus::Completed) {
+                self.status_bar.refresh_git();
+            }
+        }
+        let commands = self.agent_commands.lines(agent_name, &message);
+        if !commands.is_empty() {
+            self.show_agent_commands(commands);
+        }
         // Find the appropriate tab for this agent/session
         if let Some(tab) = self.tabs.iter_mut().find(|t| {
             t.agent_name == agent_name && t.session_id.as_ref() == Some(&message.session_id)
         }) {
+            tab.stats.record(&message);
             tab.chat_view.add_message(message).await?;
             // Attention effect over the chat area when a message lands
-            let accent = self.theme.palette.accent_b;
+            let effects = &self.config.effects;
             let area_ref = tab.chat_area_ref.clone();
-            let attn = fx::dynamic_area(area_ref.clone(), sweep_in_attention(accent));
-            let glitch = fx::dynamic_area(area_ref, glitch_burst());
-            self.fx.add_unique_effect("chat-attn", fx::parallel(&[attn, glitch]));
+            let mut attention = Vec::new();
+            if effects.chat_sweep_active() {
+                let accent = self.theme.palette.accent_b;
+                attention.push(fx::dynamic_area(area_ref.clone(), sweep_in_attention(accent)));
+            }
+            if effects.glitch_active() && !self.frame_budget.is_degraded() {
+                attention.push(fx::dynamic_area(area_ref.clone(), glitch_burst()));
+            }
+            let mut duration = ATTENTION_FX_DURATION;
+            if effects.animate() {
+                let custom = self.custom_fx.iter().filter(|e| {
+                    e.trigger == Trigger::Message
+                        && e.target.component().is_none_or(|c| effects.on(c))
+                });
+                for custom in custom {
+                    let effect = custom.instance(&area_ref, &self.status_area_ref);
+                    if let Some(timer) = effect.timer() {
+                        duration = duration.max(timer.duration().into());
+                    }
+                    attention.push(effect);
+                }
+            }
+            if !attention.is_empty() {
+                // Effects were not processed while nothing animated; don't
+                // count that gap against the new one
+                if !self.is_animating() {
+                    self.last_fx_tick = Instant::now();
+                }
+                self.fx.add_unique_effect("chat-attn", fx::parallel(&attention));
+                self.attention_until = Some(Instant::now() + duration);
+            }
         }
         Ok(())
     }
 
     pub fn set_agent_status(&mut self, agent_name: &str, status: String) {
+        match status.as_str() {
+            "Connected" => self
+                .agent_selector
+                .update_agent_status(agent_name, AgentStatus::Connected),
+            "Disconnected" => {
+                self.agent_selector
+                    .update_agent_status(agent_name, AgentStatus::Disconnected);
+                self.abandon_turns(agent_name);
+            }
+            _ => {}
+        }
         self.status_bar
             .set_agent_status(agent_name.to_string(), status);
     }
 
+    /// Stop the turn timer of a session's tab
+    pub fn turn_completed(&mut self, agent_name: &str, session_id: &SessionId) {
+        if let Some(tab) = self.tab_for(agent_name, session_id) {
+            tab.stats.finish_turn();
+        }
+        self.status_bar.refresh_git();
+    }
+
+    /// An agent wrote to its working tree; update the git status
+    pub fn files_changed(&mut self) {
+        self.status_bar.refresh_git();
+    }
+
+    /// Ask the user about a request the policy left to them; `reply` gets
+    /// the answer. Requests queue up and are asked one at a time.
+    pub fn permission_requested(
+        &mut self,
+        agent_name: &str,
+        session_id: &SessionId,
+        title: String,
+        command: Option<String>,
+        reply: PermissionReply,
+    ) {
+        let tab = self
+            .tabs
+            .iter()
+            .find(|t| t.agent_name == agent_name && t.session_id.as_ref() == Some(session_id))
+            .map_or_else(|| agent_name.to_string(), |tab| tab.name.clone());
+        let mut lines = vec![format!("{} wants to: {}", tab, title)];
+        lines.extend(command.map(|command| format!("$ {}", command)));
+        let modal = Modal::confirm("Permission requested", lines, "Allow", ())
+            .with_cancel_label("Deny");
+        if self.permissions.is_empty() && !self.is_animating() {
+            self.last_fx_tick = Instant::now();
+        }
+        self.permissions.push_back(WaitingPermission { modal, reply });
+        self.sync_permission_alert();
+    }
+
+    /// Count a permission decision against a session's tab
+    pub fn permission_resolved(&mut self, agent_name: &str, session_id: &SessionId, allowed: bool) {
+        if let Some(tab) = self.tab_for(agent_name, session_id) {
+            if allowed {
+                tab.stats.permissions_allowed += 1;
+            } else {
+                tab.stats.permissions_denied += 1;
+            }
+        }
+    }
+
+    /// Log file the logs pane tails
+    pub fn set_log_file(&mut self, path: std::path::PathBuf) {
+        self.log_viewer.set_path(path);
+    }
+
+    /// Working directories of agents that don't run in RAT's own, for the
+    /// git status of their sessions
+    pub fn set_agent_dirs(&mut self, dirs: HashMap<String, PathBuf>) {
+        self.agent_dirs = dirs;
+    }
+
+    /// Show agents' commands in a tmux pane instead of the terminal panel
+    pub fn set_output_pane(&mut self, pane: Option<OutputPane>) {
+        self.output_pane = pane;
+    }
+
+    fn show_agent_commands(&mut self, lines: Vec<(String, TerminalLineLevel)>) {
+        if let Some(pane) = &mut self.output_pane {
+            let text: Vec<String> = lines.iter().map(|(line, _)| line.clone()).collect();
+            match pane.write_lines(&text) {
+                Ok(()) => return,
+                Err(e) => {
+                    // Fall back to the panel rather than fail every command
+                    warn!("tmux output pane unavailable: {:#}", e);
+                    self.toasts.push(
+                        ToastLevel::Warning,
+                        format!("Showing agent commands in the terminal panel: {:#}", e),
+                    );
+                    self.output_pane = None;
+                }
+            }
+        }
+        for (line, level) in lines {
+            self.terminal.add_line(line, level);
+        }
+    }
+
+    /// Config profile shown in the status bar
+    pub fn set_profile(&mut self, profile: Option<String>) {
+        self.status_bar.set_mode(profile);
+    }
+
+    /// Stop the turn timers of an agent that went away mid-turn
+    fn abandon_turns(&mut self, agent_name: &str) {
+        for tab in self.tabs.iter_mut().filter(|t| t.agent_name == agent_name) {
+            tab.stats.turn_started = None;
+        }
+    }
+
+    fn tab_for(&mut self, agent_name: &str, session_id: &SessionId) -> Option<&mut Tab> {
+        self.tabs
+            .iter_mut()
+            .find(|t| t.agent_name == agent_name && t.session_id.as_ref() == Some(session_id))
+    }
+
+    /// Mark a session's tab as carrying project instructions
+    pub fn set_instructions(&mut self, agent_name: &str, session_id: &SessionId, file_name: String) {
+        if let Some(tab) = self
+            .tabs
+            .iter_mut()
+            .find(|t| t.agent_name == agent_name && t.session_id.as_ref() == Some(session_id))
+        {
+            tab.instructions = Some(file_name.clone());
+        }
+        self.status_bar
+            .set_message(format!("Project instructions: {}", file_name));
+    }
+
+    /// Mark an agent that crashed too often, with its last stderr lines
+    pub fn set_agent_failed(&mut self, agent_name: &str, reason: String, stderr: Vec<String>) {
+        let message = match stderr.last() {
+            Some(line) => format!("{} {}: {}", agent_name, reason, line),
+            None => format!("{} {}", agent_name, reason),
+        };
+        self.agent_selector
+            .update_agent_status(agent_name, AgentStatus::Failed { reason, stderr });
+        self.abandon_turns(agent_name);
+        self.status_bar
+            .set_agent_status(agent_name.to_string(), "Failed".to_string());
+        self.status_bar.set_message(message.clone());
+        // The agent stays down until retried, so this one is not a toast
+        self.show_error(format!(
+            "{}\n\nSelect it in the agent selector ({}) to retry.",
+            message,
+            self.keymap.label(Action::SwitchAgent)
+        ));
+    }
+
+    /// Mark a session's tab as running on a restarted agent
+    pub fn set_recovered(&mut self, agent_name: &str, session_id: &SessionId, reloaded: bool) {
+        if let Some(tab) = self
+            .tabs
+            .iter_mut()
+            .find(|t| t.agent_name == agent_name && t.session_id.as_ref() == Some(session_id))
+        {
+            tab.recovered = true;
+        }
+        self.status_bar.set_message(if reloaded {
+            format!("{} restarted; session recovered", agent_name)
+        } else {
+            format!("{} restarted; session recovered with a fresh context", agent_name)
+        });
+    }
+
+    /// Entries for the agent selector: agents and launch profiles
+    pub fn set_agents(&mut self, agents: Vec<AgentInfo>) {
+        // Keep known connection states across config reloads
+        let agents = agents
+            .into_iter()
+            .map(|agent| {
+                match self
+                    .agent_selector
+                    .get_agents()
+                    .iter()
+                    .find(|a| a.name == agent.name)
+                {
+                    Some(existing) => agent.with_status(existing.status.clone()),
+                    None => agent,
+                }
+            })
+            .collect();
+        self.agent_selector.update_agents(agents);
+    }
+
+    /// Show a modal error; reserved for failures the user must acknowledge
     pub fn show_error(&mut self, error: String) {
-        self.error_message = Some(error);
+        let lines = error.lines().map(str::to_string).collect();
+        self.modals
+            .push(Modal::message(ToastLevel::Error, "Error", lines));
+    }
+
+    /// Carry out what a confirmation dialog was answered with
+    fn run_confirmed(&mut self, confirmed: Confirmed) {
+        match confirmed {
+            Confirmed::Quit => {
+                let _ = self.ui_tx.send(UiToApp::Quit);
+            }
+        }
+    }
+
+    /// Show a transient notification in the corner
+    pub fn notify(&mut self, level: ToastLevel, message: String) {
+        self.toasts.push(level, message);
+    }
+
+    /// A non-fatal error: a toast, and an agent it names stops showing as
+    /// connecting in the selector
+    pub fn report_error(&mut self, error: String) {
+        let failed: Vec<String> = self
+            .agent_selector
+            .get_agents()
+            .iter()
+            .filter(|a| a.status == AgentStatus::Connecting)
+            .filter(|a| error.contains(&format!("agent '{}'", a.name)))
+            .map(|a| a.name.clone())
+            .collect();
+        for name in failed {
+            self.agent_selector
+                .update_agent_status(&name, AgentStatus::Error(error.clone()));
+        }
+        self.toasts.push(ToastLevel::Error, error);
+    }
+
+    /// Show an informational popup until the next key press
+    pub fn show_info(&mut self, title: String, lines: Vec<String>) {
+        self.modals
+            .push(Modal::message(ToastLevel::Info, title, lines));
+    }
+
+    pub fn show_status(&mut self, message: String) {
+        self.status_bar.set_message(message);
+    }
+
+    pub fn set_backpressure(&mut self, backpressure: Backpressure) {
+        self.status_bar.set_backpressure(backpressure);
+    }
+
+    /// Apply UI settings and the default agent from a newly selected profile
+    pub fn apply_config(&mut self, mut config: UiConfig, default_agent: String) {
+        if config.colors != self.config.colors {
+            match ColorDepth::from_config(&config.colors) {
+                Ok(depth) => {
+                    self.color_depth = depth;
+                    self.basic_terminal = !color::supports_effects(depth, term().as_deref());
+                }
+                Err(e) => warn!("Keeping current colors: {}", e),
+            }
+        }
+        fit_effects(&mut config.effects, config.accessibility, self.basic_terminal);
+        // A running intro can be cut short, but never restarted
+        self.startup_running = self.startup_running && config.effects.startup_active();
+        // Drop running effects; ambient ones are re-registered per the new toggles
+        self.reset_fx();
+        self.frame_budget.set_fps(config.fps);
+        match Keymap::from_ui_config(&config) {
+            Ok(keymap) => self.keymap = keymap,
+            Err(e) => warn!("Keeping current key bindings: {}", e),
+        }
+        let layout_changed = config.layout.panes != self.config.layout.panes
+            || config.layout.split != self.config.layout.split
+            || config.layout.main_percent != self.config.layout.main_percent;
+        if layout_changed {
+            match PaneLayout::from_config(&config.layout) {
+                Ok(panes) => {
+                    let had_terminal = self.panes.has(PaneKind::Terminal);
+                    self.panes = panes;
+                    if self.panes.has(PaneKind::Terminal) {
+                        self.terminal.show();
+                    } else if had_terminal {
+                        self.terminal.hide();
+                    }
+                }
+                Err(e) => warn!("Keeping current pane layout: {}", e),
+            }
+        }
+        self.status_bar
+            .set_segments(Segment::from_names(&config.status_bar.segments));
+        self.status_bar.set_accessible(config.accessibility);
+        for tab in &mut self.tabs {
+            tab.chat_view.set_input_height(config.layout.input_height);
+            tab.chat_view
+                .set_vim(KeymapPreset::parse(&config.keymap) == Some(KeymapPreset::Vim));
+        }
+        let theme_name = config.theme.name.clone();
+        self.config = config;
+        self.default_agent = default_agent;
+        if let Err(e) = self.set_theme(&theme_name) {
+            warn!("Keeping current theme: {}", e);
+        }
+    }
+
+    /// Switch to a built-in or configured theme by name
+    pub fn set_theme(&mut self, name: &str) -> Result<()> {
+        self.theme = CyberTheme::from_config(&self.config.theme, name)?;
+        if self.config.accessibility {
+            self.theme.palette = self.theme.palette.high_contrast();
+        }
+        self.theme_name = name.to_string();
+        for tab in &mut self.tabs {
+            tab.chat_view.set_theme(self.theme);
+            tab.chat_view.set_accessible(self.config.accessibility);
+        }
+        self.status_bar.set_theme(self.theme);
+        match pipeline::compile(&self.config.effects.custom, &self.theme.palette) {
+            Ok(custom) => self.custom_fx = custom,
+            Err(e) => warn!("Keeping current custom effects: {:#}", e),
+        }
+        // Re-register ambient effects so they pick up the new accents
+        self.ambient_fx_initialized = false;
+        Ok(())
+    }
+
+    /// Move to the next theme in the switcher order
+    fn cycle_theme(&mut self) {
+        let names = CyberTheme::names(&self.config.theme);
+        let current = if self.theme_name == "default" {
+            "cyberpunk"
+        } else {
+            self.theme_name.as_str()
+        };
+        let next = names
+            .iter()
+            .position(|name| name == current)
+            .map(|i| (i + 1) % names.len())
+            .unwrap_or(0);
+        let name = names[next].clone();
+        match self.set_theme(&name) {
+            Ok(()) => self.status_bar.set_message(format!("Theme: {}", name)),
+            Err(e) => self.toasts.push(ToastLevel::Error, e.to_string()),
+        }
+    }
+
+    /// Open the theme gallery on the current theme
+    fn open_gallery(&mut self) {
+        let current = if self.theme_name == "default" {
+            "cyberpunk"
+        } else {
+            self.theme_name.as_str()
+        };
+        self.gallery
+            .show(CyberTheme::names(&self.config.theme), current);
+        self.gallery_before = Some(GalleryBefore {
+            theme: self.theme_name.clone(),
+            effects: self.config.effects.clone(),
+            preset: None,
+        });
+    }
+
+    /// Preview the selected entry live; Enter keeps and saves it, Esc puts
+    /// the old theme and effects back
+    fn handle_gallery_key(&mut self, key: KeyEvent) {
+        match self.gallery.handle_key_event(key) {
+            GalleryResult::None => {}
+            GalleryResult::Preview(GalleryItem::Theme(name)) => {
+                if let Err(e) = self.set_theme(&name) {
+                    self.toasts.push(ToastLevel::Error, e.to_string());
+                }
+            }
+            GalleryResult::Preview(GalleryItem::Effects(preset)) => {
+                if let Some(before) = &mut self.gallery_before {
+                    before.preset = Some(preset);
+                }
+                let mut effects = self.config.effects.clone();
+                if effects.apply_preset(preset).is_ok() {
+                    self.set_effects(effects);
+                }
+            }
+            GalleryResult::Apply => {
+                let preset = self.gallery_before.take().and_then(|before| before.preset);
+                // The reload the write triggers then keeps the theme
+                self.config.theme.name = self.theme_name.clone();
+                self.status_bar
+                    .set_message(format!("Theme: {}", self.theme_name));
+                let _ = self.ui_tx.send(UiToApp::SaveAppearance {
+                    theme: self.theme_name.clone(),
+                    effects: preset.map(str::to_string),
+                });
+            }
+            GalleryResult::Cancel => {
+                if let Some(before) = self.gallery_before.take() {
+                    self.set_effects(before.effects);
+                    if let Err(e) = self.set_theme(&before.theme) {
+                        warn!("Keeping previewed theme: {}", e);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Swap the effect toggles, restarting ambient effects under the new ones
+    fn set_effects(&mut self, mut effects: EffectsConfig) {
+        fit_effects(&mut effects, self.config.accessibility, self.basic_terminal);
+        self.startup_running = self.startup_running && effects.startup_active();
+        self.config.effects = effects;
+        self.reset_fx();
+    }
+
+    /// Drop every running effect; ambient ones and the permission alert are
+    /// registered again on the next tick
+    fn reset_fx(&mut self) {
+        self.fx = FxManager::default();
+        self.ambient_fx_initialized = false;
+        self.permission_alert_running = false;
+    }
+
+    /// Count a frame that took `elapsed` to draw and flush. Ambient effects
+    /// pause while frames keep missing the `fps` budget.
+    pub fn record_frame(&mut self, elapsed: std::time::Duration) {
+        let effects = &self.config.effects;
+        if !effects.animate() || !effects.auto_degrade {
+            return;
+        }
+        match self.frame_budget.record(elapsed, Instant::now()) {
+            Some(LoadChange::Degraded) => {
+                warn!("Frames are taking over {:?}; pausing ambient effects", elapsed);
+                self.reset_fx();
+                self.toasts.push(
+                    ToastLevel::Warning,
+                    "Screen updates are slow; ambient effects paused".to_string(),
+                );
+            }
+            Some(LoadChange::Restored) => {
+                info!("Frames are back within budget; resuming ambient effects");
+                self.ambient_fx_initialized = false;
+                self.toasts
+                    .push(ToastLevel::Info, "Ambient effects resumed".to_string());
+            }
+            None => {}
+        }
+    }
+
+    fn new_chat_view(&self) -> ChatView {
+        let mut chat_view = ChatView::new(self.config.layout.chat_history_limit);
+        chat_view.set_theme(self.theme);
+        chat_view.set_accessible(self.config.accessibility);
+        chat_view.set_input_height(self.config.layout.input_height);
+        chat_view.set_vim(KeymapPreset::parse(&self.config.keymap) == Some(KeymapPreset::Vim));
+        chat_view
     }
 
     pub fn add_session(&mut self, agent_name: &str, session_id: SessionId) -> Result<()> {
+        self.agent_selector
+            .update_agent_status(agent_name, AgentStatus::Connected);
         let session_prefix = &session_id.0[..session_id.0.len().min(8)];
         let tab_name = format!("{} ({})", agent_name, session_prefix);
 
@@ -438,9 +1698,12 @@ impl TuiManager {
                 name: tab_name,
                 agent_name: agent_name.to_string(),
                 session_id: Some(session_id),
-                chat_view: ChatView::new(self.config.layout.chat_history_limit),
+                chat_view: self.new_chat_view(),
                 active: true,
                 chat_area_ref: RefRect::default(),
+                instructions: None,
+                recovered: false,
+                stats: SessionStats::default(),
             };
 
             // Deactivate other tabs
@@ -455,6 +1718,20 @@ impl TuiManager {
         Ok(())
     }
 
+    /// Point the tab bound to `old` at a replacement session
+    pub fn replace_session(&mut self, agent_name: &str, old: &SessionId, new: SessionId) {
+        if let Some(tab) = self
+            .tabs
+            .iter_mut()
+            .find(|t| t.agent_name == agent_name && t.session_id.as_ref() == Some(old))
+        {
+            tab.name = format!("{} ({})", agent_name, &new.0[..new.0.len().min(8)]);
+            tab.session_id = Some(new);
+        } else {
+            let _ = self.add_session(agent_name, new);
+        }
+    }
+
     pub fn remove_tab(&mut self, index: usize) {
         if index < self.tabs.len() {
             self.tabs.remove(index);
@@ -490,12 +1767,16 @@ impl TuiManager {
     }
 
     pub async fn create_new_session(&mut self) -> Result<()> {
+        self.create_session_for(self.default_agent.clone()).await
+    }
+
+    /// Open a tab for a new session with `agent_name` (an agent or launch profile)
+    async fn create_session_for(&mut self, agent_name: String) -> Result<()> {
         // Request a real session from the App layer without blocking the UI.
-        let agent_name = self.default_agent.clone();
         let (tx, rx) = oneshot::channel();
         // Best-effort send; errors surface through AppMessage::Error handling
         let _ = self.ui_tx.send(UiToApp::CreateSession {
-            agent_name,
+            agent_name: agent_name.clone(),
             respond_to: tx,
         });
 
@@ -503,7 +1784,7 @@ impl TuiManager {
         if let Some(existing_idx) = self
             .tabs
             .iter()
-            .position(|t| t.agent_name == self.default_agent && t.session_id.is_none())
+            .position(|t| t.agent_name == agent_name && t.session_id.is_none())
         {
             // Focus the existing pending tab
             for (i, t) in self.tabs.iter_mut().enumerate() {
@@ -512,12 +1793,15 @@ impl TuiManager {
             self.active_tab = existing_idx;
         } else {
             let tab = Tab {
-                name: format!("{} (creating)", self.default_agent),
-                agent_name: self.default_agent.clone(),
+                name: format!("{} (creating)", agent_name),
+                agent_name: agent_name.clone(),
                 session_id: None,
-                chat_view: ChatView::new(self.config.layout.chat_history_limit),
+                chat_view: self.new_chat_view(),
                 active: true,
                 chat_area_ref: RefRect::default(),
+                instructions: None,
+                recovered: false,
+                stats: SessionStats::default(),
             };
             for t in &mut self.tabs {
                 t.active = false;
@@ -528,7 +1812,7 @@ impl TuiManager {
 
         // Provide immediate, non-blocking UI feedback
         self.status_bar
-            .set_agent_status(self.default_agent.clone(), "Creating session...".to_string());
+            .set_agent_status(agent_name, "Creating session...".to_string());
 
         // Optionally, we could await rx and use the SessionId to name the tab,
         // but to keep the UI responsive we rely on AppMessage::SessionCreated
@@ -538,6 +1822,34 @@ impl TuiManager {
     }
 }
 
+/// Adjust effects to where they run: no motion in accessibility mode, and
+/// none at all on a basic terminal unless `force` is set
+fn fit_effects(effects: &mut EffectsConfig, accessibility: bool, basic_terminal: bool) {
+    if accessibility {
+        effects.reduce_motion = true;
+    }
+    if basic_terminal && !effects.force {
+        effects.enabled = false;
+    }
+}
+
+fn term() -> Option<String> {
+    std::env::var("TERM").ok()
+}
+
+/// Recolor the outer edge of `area`, e.g. the border of the focused pane
+fn highlight_border(frame: &mut Frame, area: Rect, color: Color) {
+    if area.width < 2 || area.height < 2 {
+        return;
+    }
+    let style = Style::default().fg(color);
+    let buf = frame.buffer_mut();
+    buf.set_style(Rect { height: 1, ..area }, style);
+    buf.set_style(Rect { y: area.bottom() - 1, height: 1, ..area }, style);
+    buf.set_style(Rect { width: 1, ..area }, style);
+    buf.set_style(Rect { x: area.right() - 1, width: 1, ..area }, style);
+}
+
 fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
     let popup_layout = Layout::default()
         .direction(Direction::Vertical)
@@ -593,8 +1905,11 @@ impl TuiManager {
                 let mut dst = target.borrow_mut();
                 src.render_buffer(ratatui::layout::Offset::default(), &mut dst);
             }
-            // Effect morphs rain into target UI
-            self.startup_effect = Some(matrix_rain_morph_with_duration(target, self.startup_duration_ms));
+            // The intro morphs into the target UI
+            let style = StartupStyle::parse(&self.config.effects.startup.style)
+                .unwrap_or(StartupStyle::Matrix);
+            self.startup_effect = style.effect(target, self.startup_duration_ms);
+            self.startup_running = self.startup_effect.is_some();
             self.last_fx_tick = Instant::now();
         }
 
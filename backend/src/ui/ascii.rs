@@ -0,0 +1,147 @@
+//! Plain ASCII for accessibility mode: [`ascii`] turns the TUI's emoji and
+//! symbols into labels before text is laid out, [`linear`] unboxes chat
+//! cards, and [`ascii_buffer`] catches borders and markers left in the
+//! rendered frame.
+
+use ratatui::buffer::Buffer;
+
+/// Labels for the emoji and symbols the TUI writes into its text. Priority
+/// dots are dropped since the priority is spelled out next to them.
+const LABELS: &[(&str, &str)] = &[
+    ("✅", "[done]"),
+    ("❌", "[failed]"),
+    ("⏳", "[pending]"),
+    ("⚡", "[active]"),
+    ("🔄", "[running]"),
+    ("❓", "[unknown]"),
+    ("🔴 ", ""),
+    ("🟡 ", ""),
+    ("🟢 ", ""),
+    ("🔧", "Tool:"),
+    ("📋", "Params:"),
+    ("🔒", "[locked]"),
+    ("📄", "Result:"),
+    ("📊", "Stats:"),
+    ("📁", "File:"),
+    ("💬", "Note:"),
+    ("🤖", "Agent"),
+    ("🔗", "Link"),
+    ("⌚", "Time"),
+    ("⚠️", "Warning:"),
+    ("⚠", "Warning:"),
+    ("◆", "[instructions]"),
+    ("↻", "[recovered]"),
+    ("⎇", "branch"),
+    ("✓", "+"),
+    ("✗", "-"),
+    ("…", "..."),
+    ("·", "-"),
+    ("↑↓", "Up/Down"),
+    ("↑/↓", "Up/Down"),
+    ("→", "->"),
+    ("←", "<-"),
+];
+
+/// `text` with the TUI's emoji and symbols replaced by ASCII labels. Other
+/// characters, such as accented letters in a message, are left alone.
+pub fn ascii(text: &str) -> String {
+    if text.is_ascii() {
+        return text.to_string();
+    }
+    LABELS
+        .iter()
+        .fold(text.to_string(), |text, (symbol, label)| {
+            text.replace(symbol, label)
+        })
+}
+
+/// Chat card text read top to bottom: the box's title line (the message
+/// header already names the card) and lines that only drew the box are
+/// dropped, box edges are removed from the rest, symbols become labels
+pub fn linear(text: &str) -> String {
+    text.lines()
+        .filter_map(|line| {
+            if !line.chars().any(is_box_drawing) {
+                return Some(ascii(line));
+            }
+            if line.starts_with('┌') {
+                return None;
+            }
+            let stripped: String = line.chars().filter(|c| !is_box_drawing(*c)).collect();
+            let stripped = stripped.trim();
+            (!stripped.is_empty()).then(|| ascii(stripped))
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn is_box_drawing(c: char) -> bool {
+    ('\u{2500}'..='\u{257F}').contains(&c)
+}
+
+/// One-cell ASCII stand-in for a symbol drawn by a widget (borders,
+/// spinners, markers), if it is one
+fn ascii_cell(symbol: &str) -> Option<&'static str> {
+    let mut chars = symbol.chars();
+    let c = chars.next()?;
+    if chars.next().is_some() {
+        return None;
+    }
+    let replacement = match c {
+        '─' | '━' | '═' | '┄' | '┈' | '╌' => "-",
+        '│' | '┃' | '║' | '┆' | '┊' | '╎' | '▏' | '▕' => "|",
+        c if is_box_drawing(c) => "+",
+        '\u{2800}'..='\u{28FF}' => "*",
+        '•' | '●' | '◆' | '■' => "*",
+        '○' | '◯' => "o",
+        '◐' | '◑' => "~",
+        '▶' | '›' | '→' | '⟧' => ">",
+        '‹' | '←' | '⟦' => "<",
+        '↑' | '▲' => "^",
+        '↓' | '▼' => "v",
+        '…' => ".",
+        '·' => "-",
+        '✓' => "+",
+        '✗' => "x",
+        '█' | '▓' | '▒' | '░' => "#",
+        _ => return None,
+    };
+    Some(replacement)
+}
+
+/// Replace the box drawing and symbols left in a rendered frame
+pub fn ascii_buffer(buffer: &mut Buffer) {
+    for cell in buffer.content.iter_mut() {
+        if let Some(replacement) = ascii_cell(cell.symbol()) {
+            cell.set_symbol(replacement);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cards_read_as_plain_lines() {
+        let card = "┌─ Tool Call ────┐\n│ 🔧 read_file │\n│ ✅ Auto-approved │\n└────────────────┘";
+        assert_eq!(linear(card), "Tool: read_file\n[done] Auto-approved");
+        assert_eq!(ascii("✅ 🔴 High: ship it"), "[done] High: ship it");
+        assert_eq!(ascii("héllo"), "héllo");
+    }
+
+    #[test]
+    fn buffer_borders_become_ascii() {
+        use ratatui::{
+            layout::Rect,
+            widgets::{Block, Borders, Widget},
+        };
+        let area = Rect::new(0, 0, 4, 3);
+        let mut buffer = Buffer::empty(area);
+        Block::default()
+            .borders(Borders::ALL)
+            .render(area, &mut buffer);
+        ascii_buffer(&mut buffer);
+        assert_eq!(buffer, Buffer::with_lines(["+--+", "|  |", "+--+"]));
+    }
+}
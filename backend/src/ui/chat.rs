@@ -4,12 +4,84 @@ use ratatui::{
     prelude::*,
     widgets::{Block, Borders, Paragraph, Wrap, BorderType},
 };
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
 
-use crate::acp::{Message, MessageContent, message::{ToolCallRequest, EditProposal}};
+use crate::acp::{Message, MessageContent, Plan, message::{ToolCallRequest, EditProposal}};
+use crate::effects::cyberpunk::CyberTheme;
+use crate::ui::ascii;
+use crate::ui::keymap::Action;
 use crate::utils::diff::{DiffGenerator, DiffLineType};
 use agent_client_protocol::{ToolCallUpdate, ToolCallStatus, ToolCallContent, ContentBlock};
 
+const SPINNER: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// Live state of a tool call: one row under its card, updated in place as
+/// `ToolCallUpdate`s arrive instead of adding a message per update
+#[derive(Debug, Clone)]
+struct ToolProgress {
+    status: ToolCallStatus,
+    started: Instant,
+    /// How long it ran, once completed or failed
+    elapsed: Option<Duration>,
+    /// Last non-empty line of the content the tool streamed
+    last_line: Option<String>,
+}
+
+impl ToolProgress {
+    fn new() -> Self {
+        Self {
+            status: ToolCallStatus::Pending,
+            started: Instant::now(),
+            elapsed: None,
+            last_line: None,
+        }
+    }
+
+    fn apply(&mut self, update: &ToolCallUpdate) {
+        if let Some(status) = &update.fields.status {
+            self.status = *status;
+            let finished = matches!(status, ToolCallStatus::Completed | ToolCallStatus::Failed);
+            if finished && self.elapsed.is_none() {
+                self.elapsed = Some(self.started.elapsed());
+            }
+        }
+        let streamed = update.fields.content.iter().flatten().rev().find_map(|content| match content {
+            ToolCallContent::Content {
+                content: ContentBlock::Text(text),
+            } => text
+                .text
+                .lines()
+                .rev()
+                .map(str::trim)
+                .find(|line| !line.is_empty())
+                .map(str::to_string),
+            _ => None,
+        });
+        if streamed.is_some() {
+            self.last_line = streamed;
+        }
+    }
+
+    fn running(&self) -> bool {
+        self.elapsed.is_none()
+    }
+}
+
+/// `/` search over the conversation, matched against the rendered lines
+#[derive(Debug, Clone, Default)]
+struct ChatSearch {
+    query: String,
+    /// True while the query is being typed
+    editing: bool,
+    /// Visual lines that match, oldest first, as of the last render
+    matches: Vec<usize>,
+    /// Index into `matches` of the focused match; `None` is the newest
+    current: Option<usize>,
+    /// Scroll the focused match into view on the next render
+    follow: bool,
+}
+
 #[derive(Debug, Clone)]
 pub struct ChatView {
     messages: VecDeque<Message>,
@@ -22,6 +94,17 @@ pub struct ChatView {
     last_total_lines: usize,
     last_visible_lines: usize,
     last_inner_width: usize,
+    theme: CyberTheme,
+    /// Progress of the tool calls shown, by tool call id
+    tool_progress: HashMap<String, ToolProgress>,
+    /// Accessibility mode: cards as plain lines, symbols as ASCII labels
+    accessible: bool,
+    /// Rows of the input box, borders included
+    input_height: u16,
+    search: Option<ChatSearch>,
+    /// Vim mode: Esc leaves a draft in the box (normal mode) instead of
+    /// discarding it
+    vim: bool,
 }
 
 impl ChatView {
@@ -35,7 +118,92 @@ impl ChatView {
             last_total_lines: 0,
             last_visible_lines: 0,
             last_inner_width: 0,
+            theme: CyberTheme::default(),
+            tool_progress: HashMap::new(),
+            accessible: false,
+            input_height: 3,
+            search: None,
+            vim: false,
+        }
+    }
+
+    pub fn set_theme(&mut self, theme: CyberTheme) {
+        self.theme = theme;
+    }
+
+    pub fn set_accessible(&mut self, accessible: bool) {
+        self.accessible = accessible;
+    }
+
+    pub fn set_input_height(&mut self, height: u16) {
+        self.input_height = height;
+    }
+
+    pub fn set_vim(&mut self, vim: bool) {
+        self.vim = vim;
+    }
+
+    pub fn start_search(&mut self) {
+        self.search = Some(ChatSearch {
+            editing: true,
+            ..Default::default()
+        });
+    }
+
+    /// True when a search query is active (typed or confirmed)
+    pub fn has_search(&self) -> bool {
+        self.search.is_some()
+    }
+
+    pub fn clear_search(&mut self) {
+        self.search = None;
+    }
+
+    /// Keys while the search prompt is open: Enter keeps the search for
+    /// `n`/`N`, Esc drops it. Returns false when not searching.
+    pub fn handle_search_key(&mut self, key: KeyEvent) -> bool {
+        let Some(search) = self.search.as_mut().filter(|s| s.editing) else {
+            return false;
+        };
+        match key.code {
+            KeyCode::Enter => {
+                search.editing = false;
+                if search.query.is_empty() {
+                    self.search = None;
+                }
+                return true;
+            }
+            KeyCode::Esc => {
+                self.search = None;
+                return true;
+            }
+            KeyCode::Backspace => {
+                search.query.pop();
+            }
+            KeyCode::Char(c) => search.query.push(c),
+            _ => return true,
         }
+        search.current = None;
+        search.follow = true;
+        true
+    }
+
+    /// Move to the next (newer) or previous (older) match, wrapping around
+    pub fn jump_match(&mut self, forward: bool) {
+        let Some(search) = self.search.as_mut() else {
+            return;
+        };
+        let count = search.matches.len();
+        if count == 0 {
+            return;
+        }
+        let current = search.current.unwrap_or(count - 1).min(count - 1);
+        search.current = Some(if forward {
+            (current + 1) % count
+        } else {
+            (current + count - 1) % count
+        });
+        search.follow = true;
     }
 
 pub fn render(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
@@ -51,7 +219,7 @@ pub fn render(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Min(1),
-            Constraint::Length(3),
+            Constraint::Length(self.input_height),
         ])
         .split(area);
 
@@ -79,6 +247,7 @@ pub fn render(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
         }
 
         let total_lines = lines.len();
+        let search_note = self.mark_search_matches(&mut lines, visible_lines);
 
         // Persist last layout for scroll logic elsewhere
         self.last_total_lines = total_lines;
@@ -93,7 +262,10 @@ pub fn render(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
             base_from_top.saturating_sub(self.scroll_offset)
         };
 
-        let title = if total_lines <= visible_lines {
+        // A search replaces the scroll hint
+        let title = if let Some(note) = search_note {
+            format!("Conversation ({} messages) - {}", self.messages.len(), note)
+        } else if total_lines <= visible_lines {
             format!("Conversation ({} messages)", self.messages.len())
         } else if self.scroll_offset == 0 {
             format!("Conversation ({} messages) - Latest", self.messages.len())
@@ -106,7 +278,7 @@ pub fn render(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
                 Block::default()
                     .title(title)
                     .borders(Borders::ALL)
-                    .border_style(Style::default().fg(Color::from_u32(0x18e5ff)))
+                    .border_style(Style::default().fg(self.theme.palette.border))
                     .border_type(BorderType::Double),
             )
             .wrap(Wrap { trim: false })
@@ -115,20 +287,150 @@ pub fn render(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
         frame.render_widget(para, area);
     }
 
+    /// Find the search query in the rendered lines, underline the matches
+    /// and reverse the focused one, scrolling to it when asked. Returns the
+    /// search's part of the title.
+    fn mark_search_matches(&mut self, lines: &mut [Line], visible_lines: usize) -> Option<String> {
+        let search = self.search.as_mut()?;
+        let query = search.query.to_lowercase();
+        search.matches = if query.is_empty() {
+            Vec::new()
+        } else {
+            lines
+                .iter()
+                .enumerate()
+                .filter(|(_, line)| line.to_string().to_lowercase().contains(&query))
+                .map(|(i, _)| i)
+                .collect()
+        };
+        let count = search.matches.len();
+        let current = search.current.unwrap_or(count.saturating_sub(1));
+        for (n, &i) in search.matches.iter().enumerate() {
+            let modifier = if n == current {
+                Modifier::REVERSED
+            } else {
+                Modifier::UNDERLINED
+            };
+            let line = std::mem::take(&mut lines[i]);
+            lines[i] = line.patch_style(Style::default().add_modifier(modifier));
+        }
+        if search.follow {
+            if let Some(&target) = search.matches.get(current) {
+                // The match goes on the bottom row; the offset counts from the bottom
+                let max_from_bottom = lines.len().saturating_sub(visible_lines);
+                self.scroll_offset = (lines.len() - 1 - target).min(max_from_bottom);
+            }
+            search.follow = false;
+        }
+
+        let prompt = if search.editing { "/" } else { "Search: " };
+        Some(if count == 0 {
+            format!("{}{} (no matches)", prompt, search.query)
+        } else {
+            format!("{}{} ({}/{})", prompt, search.query, current + 1, count)
+        })
+    }
+
+    /// Conversation without the input box, for a chat in a side pane
+    pub fn render_transcript(&mut self, frame: &mut Frame, area: Rect) {
+        if area.width < 10 || area.height < 3 {
+            return;
+        }
+        self.render_messages(frame, area);
+    }
+
+    /// Latest plan the agent reported in this session
+    pub fn latest_plan(&self) -> Option<&Plan> {
+        self.messages.iter().rev().find_map(|m| match &m.content {
+            MessageContent::Plan(plan) => Some(plan),
+            _ => None,
+        })
+    }
+
+    /// Every edit proposed in this session as a diff, newest at the bottom
+    pub fn render_edits(&self, frame: &mut Frame, area: Rect) {
+        let inner_width = area.width.saturating_sub(2) as usize;
+        let visible_lines = area.height.saturating_sub(2) as usize;
+
+        let mut count = 0;
+        let mut lines: Vec<Line> = Vec::new();
+        for msg in &self.messages {
+            match &msg.content {
+                MessageContent::EditProposed { edit } => {
+                    count += 1;
+                    if !lines.is_empty() {
+                        lines.push(Line::from(""));
+                    }
+                    if let Some(description) = &edit.description {
+                        lines.extend(self.wrap_styled(
+                            description.clone(),
+                            Style::default().fg(self.theme.palette.warning),
+                            inner_width,
+                        ));
+                    }
+                    lines.extend(self.format_edit_content_styled(edit, inner_width));
+                }
+                MessageContent::EditAccepted { edit_id } => lines.push(Line::from(Span::styled(
+                    format!("Accepted {}", edit_id),
+                    Style::default().fg(self.theme.palette.success),
+                ))),
+                MessageContent::EditRejected { edit_id } => lines.push(Line::from(Span::styled(
+                    format!("Rejected {}", edit_id),
+                    Style::default().fg(self.theme.palette.error),
+                ))),
+                _ => {}
+            }
+        }
+        if lines.is_empty() {
+            lines.push(Line::from(Span::styled(
+                "No edits proposed in this session",
+                Style::default().fg(self.theme.palette.muted),
+            )));
+        }
+
+        let scroll = lines.len().saturating_sub(visible_lines) as u16;
+        let para = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .title(format!("Edits ({})", count))
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(self.theme.palette.border))
+                    .border_type(BorderType::Double),
+            )
+            .scroll((scroll, 0));
+        frame.render_widget(para, area);
+    }
+
     fn render_input(&self, frame: &mut Frame, area: Rect) {
         let input_style = if self.input_mode {
-            Style::default().fg(Color::from_u32(0xff2e88))
+            Style::default().fg(self.theme.palette.accent_a)
         } else {
-            Style::default().fg(Color::DarkGray)
+            Style::default().fg(self.theme.palette.muted)
         };
 
-        let input_title = if self.input_mode {
-            "Message (Enter: send, Esc: cancel)"
-        } else {
-            "Press Enter to start typing"
+        let input_title = match (self.vim, self.input_mode) {
+            (false, true) => "Message (Enter: send, Esc: cancel)",
+            (false, false) => "Press Enter to start typing",
+            (true, true) => "-- INSERT -- (Enter: send, Esc: normal mode)",
+            (true, false) => "-- NORMAL -- (i: insert, /: search, :: command)",
         };
 
-        let input = Paragraph::new(self.input_buffer.as_str()).block(
+        // Broken at the box width so a taller box shows the end of a long
+        // message, with the cursor after it
+        let inner_width = area.width.saturating_sub(2).max(1) as usize;
+        let rows = area.height.saturating_sub(2).max(1) as usize;
+        let chars: Vec<char> = self.input_buffer.chars().collect();
+        let mut lines: Vec<Line> = chars
+            .chunks(inner_width)
+            .map(|chunk| Line::from(chunk.iter().collect::<String>()))
+            .collect();
+        if chars.len().is_multiple_of(inner_width) {
+            lines.push(Line::from(""));
+        }
+        let shown = lines.split_off(lines.len().saturating_sub(rows));
+        let cursor_row = shown.len() as u16;
+
+        let input = Paragraph::new(shown).block(
             Block::default()
                 .title(input_title)
                 .borders(Borders::ALL)
@@ -140,8 +442,8 @@ pub fn render(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
 
         // Show cursor if in input mode
         if self.input_mode {
-            let cursor_x = area.x + 1 + self.input_buffer.len() as u16;
-            let cursor_y = area.y + 1;
+            let cursor_x = area.x + 1 + (chars.len() % inner_width) as u16;
+            let cursor_y = area.y + cursor_row;
             // Ensure cursor position is within bounds
             if cursor_x < frame.area().width && cursor_y < frame.area().height {
                 frame.set_cursor_position(Position {
@@ -152,7 +454,10 @@ pub fn render(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
         }
     }
 
-    fn format_message_lines(&self, message: &Message, max_width: usize) -> Vec<Line<'static>> {
+    /// Lay out one message as styled lines at most `max_width` wide. Public
+    /// for the benchmarks in `benches/`.
+    #[doc(hidden)]
+    pub fn format_message_lines(&self, message: &Message, max_width: usize) -> Vec<Line<'static>> {
         let timestamp = message.timestamp.format("%H:%M:%S");
 
         match &message.content {
@@ -163,13 +468,14 @@ pub fn render(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
                 // Add timestamp header with clean styling
                 lines.push(Line::from(Span::styled(
                     format!("[{}] Code Edit", timestamp),
-                    Style::default().fg(Color::Yellow),
+                    Style::default().fg(self.theme.palette.warning),
                 )));
 
                 // Add separator
+                let rule = if self.accessible { "-" } else { "─" };
                 lines.push(Line::from(Span::styled(
-                    "─".repeat(max_width.min(50)),
-                    Style::default().fg(Color::DarkGray),
+                    rule.repeat(max_width.min(50)),
+                    Style::default().fg(self.theme.palette.muted),
                 )));
 
                 // Add the neovim-style diff content
@@ -183,62 +489,76 @@ pub fn render(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
                     MessageContent::UserPrompt { .. } => (
                         format!("[{}] You: ", timestamp),
                         self.extract_text_content(message),
-                        Style::default().cyan(),
+                        Style::default().fg(self.theme.palette.user),
                     ),
                     MessageContent::AgentResponse { content } => (
                         format!("[{}] Agent: ", timestamp),
                         self.content_to_string(content),
-                        Style::default().green(),
+                        Style::default().fg(self.theme.palette.agent),
                     ),
                     MessageContent::AgentMessageChunk { content } => (
                         format!("[{}] Agent: ", timestamp),
                         self.content_to_string(content),
-                        Style::default().green(),
+                        Style::default().fg(self.theme.palette.agent),
                     ),
                     MessageContent::EditAccepted { edit_id } => (
                         format!("[{}] Edit accepted: ", timestamp),
                         edit_id.clone(),
-                        Style::default().green(),
+                        Style::default().fg(self.theme.palette.success),
                     ),
                     MessageContent::EditRejected { edit_id } => (
                         format!("[{}] Edit rejected: ", timestamp),
                         edit_id.clone(),
-                        Style::default().red(),
+                        Style::default().fg(self.theme.palette.error),
                     ),
                     MessageContent::ToolCall { tool_call } => (
                         format!("[{}] Tool Call", timestamp),
                         self.format_tool_call_content(tool_call),
-                        Style::default().blue(),
+                        Style::default().fg(self.theme.palette.tool),
                     ),
                     MessageContent::ToolResult { result, .. } => (
                         format!("[{}] Tool Result", timestamp),
                         self.format_tool_result_content(result),
-                        Style::default().blue(),
+                        Style::default().fg(self.theme.palette.tool),
                     ),
                     MessageContent::ToolCallUpdate { update } => (
                         format!("[{}] Tool Update", timestamp),
                         self.format_tool_call_update_content(update),
-                        Style::default().cyan(),
+                        Style::default().fg(self.theme.palette.tool),
                     ),
                     MessageContent::SessionStatus { status } => (
                         format!("[{}] Status: ", timestamp),
                         status.clone(),
-                        Style::default().gray(),
+                        Style::default().fg(self.theme.palette.system),
                     ),
                     MessageContent::Error { error } => (
                         format!("[{}] Error: ", timestamp),
                         error.clone(),
-                        Style::default().red(),
+                        Style::default().fg(self.theme.palette.error),
                     ),
                     MessageContent::Plan(plan) => (
                         format!("[{}] Agent Plan: ", timestamp),
                         self.format_plan_content(plan),
-                        Style::default().fg(Color::Cyan),
+                        Style::default().fg(self.theme.palette.accent_b),
                     ),
                     MessageContent::EditProposed { .. } => unreachable!("Handled above"),
                 };
 
-                self.wrap_styled(format!("{}{}", prefix, body), style, max_width)
+                // Cards start on the line below their header when unboxed
+                let body = if !self.accessible {
+                    body
+                } else if prefix.ends_with(' ') {
+                    ascii::linear(&body)
+                } else {
+                    format!("\n{}", ascii::linear(&body))
+                };
+                let mut lines = self.wrap_styled(format!("{}{}", prefix, body), style, max_width);
+                if let MessageContent::ToolCall { tool_call } = &message.content {
+                    if let Some(progress) = self.tool_progress.get(&tool_call.id) {
+                        lines.push(self.format_progress_line(progress, max_width));
+                    }
+                }
+                lines
             }
         }
     }
@@ -339,6 +659,40 @@ pub fn render(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
         lines.join("\n")
     }
 
+    /// `⠹ Running 4.2s · <last output line>`, cut to one line
+    fn format_progress_line(&self, progress: &ToolProgress, max_width: usize) -> Line<'static> {
+        let elapsed = progress.elapsed.unwrap_or_else(|| progress.started.elapsed());
+        let (icon, label, color) = match progress.status {
+            ToolCallStatus::Completed => ("✅", "Done", self.theme.palette.success),
+            ToolCallStatus::Failed => ("❌", "Failed", self.theme.palette.error),
+            ToolCallStatus::InProgress => (self.spinner_frame(elapsed), "Running", self.theme.palette.tool),
+            ToolCallStatus::Pending => (self.spinner_frame(elapsed), "Pending", self.theme.palette.tool),
+        };
+        let mut text = format!("  {} {} {:.1}s", icon, label, elapsed.as_secs_f64());
+        if let Some(line) = &progress.last_line {
+            text.push_str(" · ");
+            text.push_str(line);
+        }
+        if self.accessible {
+            text = ascii::ascii(&text);
+        }
+        // The status icon is two columns wide
+        let limit = max_width.saturating_sub(1);
+        if text.chars().count() > limit {
+            text = text.chars().take(limit.saturating_sub(1)).collect();
+            text.push('…');
+        }
+        Line::from(Span::styled(text, Style::default().fg(color)))
+    }
+
+    fn spinner_frame(&self, elapsed: Duration) -> &'static str {
+        // No animation in accessibility mode; the label says it's running
+        if self.accessible {
+            return "*";
+        }
+        SPINNER[(elapsed.as_millis() / 100) as usize % SPINNER.len()]
+    }
+
     fn format_tool_result_content(&self, result: &str) -> String {
         let mut lines = Vec::new();
         lines.push("┌─ Tool Result ────────────────────────────┐".to_string());
@@ -388,23 +742,23 @@ pub fn render(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
         // File header (like git diff)
         lines.push(Line::from(Span::styled(
             format!("diff --git a/{} b/{}", edit.file_path, edit.file_path),
-            Style::default().fg(Color::Magenta).bold(),
+            Style::default().fg(self.theme.palette.diff_meta).bold(),
         )));
 
         // Index line (simulated)
         lines.push(Line::from(Span::styled(
             "index 0000000..1111111 100644",
-            Style::default().fg(Color::Magenta),
+            Style::default().fg(self.theme.palette.diff_meta),
         )));
 
         // File path header
         lines.push(Line::from(Span::styled(
             format!("--- a/{}", edit.file_path),
-            Style::default().fg(Color::Red),
+            Style::default().fg(self.theme.palette.diff_removed),
         )));
         lines.push(Line::from(Span::styled(
             format!("+++ b/{}", edit.file_path),
-            Style::default().fg(Color::Green),
+            Style::default().fg(self.theme.palette.diff_added),
         )));
 
         // Add description if available
@@ -412,7 +766,7 @@ pub fn render(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
             lines.push(Line::from(""));
             lines.push(Line::from(Span::styled(
                 format!("// {}", desc),
-                Style::default().fg(Color::Blue).italic(),
+                Style::default().fg(self.theme.palette.tool).italic(),
             )));
         }
 
@@ -433,7 +787,7 @@ pub fn render(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
                 // Hunk header with line numbers
                 lines.push(Line::from(Span::styled(
                     hunk.header.clone(),
-                    Style::default().fg(Color::Cyan).bold(),
+                    Style::default().fg(self.theme.palette.diff_hunk).bold(),
                 )));
 
                 // Display diff lines with proper colors and prefixes
@@ -442,13 +796,13 @@ pub fn render(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
                     let (prefix, style) = match line.line_type {
                         DiffLineType::Added => {
                             total_additions += 1;
-                            ("+", Style::default().fg(Color::Green))
+                            ("+", Style::default().fg(self.theme.palette.diff_added))
                         },
                         DiffLineType::Removed => {
                             total_deletions += 1;
-                            ("-", Style::default().fg(Color::Red))
+                            ("-", Style::default().fg(self.theme.palette.diff_removed))
                         },
-                        DiffLineType::Context => (" ", Style::default().fg(Color::White)),
+                        DiffLineType::Context => (" ", Style::default().fg(self.theme.palette.diff_context)),
                     };
 
                     // Handle long lines by truncating
@@ -469,7 +823,7 @@ pub fn render(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
                 if hunk.lines.len() > 10 {
                     lines.push(Line::from(Span::styled(
                         format!("... ({} more lines)", hunk.lines.len() - 10),
-                        Style::default().fg(Color::DarkGray),
+                        Style::default().fg(self.theme.palette.muted),
                     )));
                 }
             }
@@ -478,25 +832,25 @@ pub fn render(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
             lines.push(Line::from(""));
             lines.push(Line::from(Span::styled(
                 format!("{} insertions(+), {} deletions(-)", total_additions, total_deletions),
-                Style::default().fg(Color::Yellow),
+                Style::default().fg(self.theme.palette.warning),
             )));
         } else {
             // Fallback: display raw diff if parsing fails
             lines.push(Line::from(Span::styled(
                 "Failed to parse diff, showing raw:",
-                Style::default().fg(Color::Red),
+                Style::default().fg(self.theme.palette.error),
             )));
             lines.push(Line::from(""));
 
             for line in edit.diff.lines().take(15) {
                 let (style, prefix) = if line.starts_with('+') {
-                    (Style::default().fg(Color::Green), "+")
+                    (Style::default().fg(self.theme.palette.diff_added), "+")
                 } else if line.starts_with('-') {
-                    (Style::default().fg(Color::Red), "-")
+                    (Style::default().fg(self.theme.palette.diff_removed), "-")
                 } else if line.starts_with("@@") {
-                    (Style::default().fg(Color::Cyan).bold(), "")
+                    (Style::default().fg(self.theme.palette.diff_hunk).bold(), "")
                 } else {
-                    (Style::default().fg(Color::White), "")
+                    (Style::default().fg(self.theme.palette.diff_context), "")
                 };
 
                 let content = if line.len() > max_width {
@@ -604,60 +958,73 @@ pub fn render(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
         lines.join("\n")
     }
 
+    /// Keys typed into the message input; ignored outside input mode
     pub async fn handle_key_event(&mut self, key: KeyEvent) -> Result<()> {
+        if !self.input_mode {
+            return Ok(());
+        }
         match key.code {
             KeyCode::Enter => {
-                if self.input_mode {
-                    if !self.input_buffer.trim().is_empty() {
-                        // Send message - would need to communicate with app layer
-                        self.input_buffer.clear();
-                    }
-                    self.input_mode = false;
-                } else {
-                    self.input_mode = true;
-                }
+                // Sending happens in TuiManager; leaving input mode clears the buffer
+                self.input_buffer.clear();
+                self.input_mode = false;
             }
+            // Vim's normal mode keeps the draft for the next `i`
+            KeyCode::Esc if self.vim => self.input_mode = false,
             KeyCode::Esc => {
-                if self.input_mode {
-                    self.input_buffer.clear();
-                    self.input_mode = false;
-                }
-            }
-            KeyCode::Char(c) => {
-                if self.input_mode {
-                    self.input_buffer.push(c);
-                }
+                self.input_buffer.clear();
+                self.input_mode = false;
             }
+            KeyCode::Char(c) => self.input_buffer.push(c),
             KeyCode::Backspace => {
-                if self.input_mode {
-                    self.input_buffer.pop();
-                }
+                self.input_buffer.pop();
             }
-            KeyCode::Up => {
-                if !self.input_mode {
-                    // Scroll up by one visual line (older content)
-                    let max_from_bottom = self
-                        .last_total_lines
-                        .saturating_sub(self.last_visible_lines);
-                    if self.scroll_offset < max_from_bottom {
-                        self.scroll_offset += 1;
-                    }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Run a chat action (scrolling, focusing the input). Returns true if it applied.
+    pub fn handle_action(&mut self, action: Action) -> bool {
+        if self.input_mode {
+            return false;
+        }
+        let max_from_bottom = self
+            .last_total_lines
+            .saturating_sub(self.last_visible_lines);
+        match action {
+            Action::ChatStartInput => self.input_mode = true,
+            Action::ChatSearch => self.start_search(),
+            // Scroll by one visual line; the offset counts from the bottom
+            Action::ChatScrollUp => {
+                if self.scroll_offset < max_from_bottom {
+                    self.scroll_offset += 1;
                 }
             }
-            KeyCode::Down => {
-                if !self.input_mode {
-                    // Scroll down by one visual line (toward latest)
-                    if self.scroll_offset > 0 {
-                        self.scroll_offset -= 1;
-                    }
+            Action::ChatScrollDown => self.scroll_offset = self.scroll_offset.saturating_sub(1),
+            Action::ChatScrollTop => self.scroll_offset = max_from_bottom,
+            Action::ChatScrollBottom => self.scroll_offset = 0,
+            _ => return false,
+        }
+        true
+    }
+
+    pub async fn add_message(&mut self, message: Message) -> Result<()> {
+        match &message.content {
+            // Updates to a call on screen change its progress row in place
+            MessageContent::ToolCallUpdate { update } => {
+                if let Some(progress) = self.tool_progress.get_mut(&*update.id.0) {
+                    progress.apply(update);
+                    return Ok(());
                 }
             }
+            MessageContent::ToolCall { tool_call } => {
+                self.tool_progress
+                    .insert(tool_call.id.clone(), ToolProgress::new());
+            }
             _ => {}
         }
-        Ok(())
-    }
 
-    pub async fn add_message(&mut self, message: Message) -> Result<()> {
         // If the user has scrolled up, keep their viewport anchored by
         // increasing the offset by the number of visual lines added.
         let mut added_lines = 1usize;
@@ -670,7 +1037,13 @@ pub fn render(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
 
         // Keep only the max number of messages
         while self.messages.len() > self.max_messages {
-            self.messages.pop_front();
+            if let Some(Message {
+                content: MessageContent::ToolCall { tool_call },
+                ..
+            }) = self.messages.pop_front()
+            {
+                self.tool_progress.remove(&tool_call.id);
+            }
         }
 
         // Stick to bottom only if already at bottom; otherwise preserve position
@@ -683,9 +1056,9 @@ pub fn render(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
         Ok(())
     }
 
-    pub async fn tick(&mut self) -> Result<()> {
-        // Handle any periodic updates
-        Ok(())
+    /// Returns whether anything animates (a running tool call's spinner)
+    pub async fn tick(&mut self) -> Result<bool> {
+        Ok(self.tool_progress.values().any(ToolProgress::running))
     }
 
     pub fn get_input_buffer(&self) -> &str {
@@ -709,7 +1082,9 @@ pub fn render(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
 }
 
 impl ChatView {
-    fn wrap_styled(&self, text: String, style: Style, max_width: usize) -> Vec<Line<'static>> {
+    /// Word-wrap `text` into lines of `style`. Public for the benchmarks.
+    #[doc(hidden)]
+    pub fn wrap_styled(&self, text: String, style: Style, max_width: usize) -> Vec<Line<'static>> {
         if max_width == 0 {
             return vec![Line::from(Span::styled(text, style))];
         }
@@ -784,4 +1159,51 @@ impl ChatView {
 
         lines
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(lines: &[Line<'static>]) -> Vec<String> {
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn wrapping_keeps_words_whole_and_splits_only_long_tokens() {
+        let view = ChatView::new(10);
+        let lines = view.wrap_styled(
+            "the agent refactored https://example.com/long/path it".to_string(),
+            Style::default(),
+            12,
+        );
+        let expected = [
+            "the agent",
+            "refactored",
+            "https://exam",
+            "ple.com/long",
+            "/path it",
+        ];
+        assert_eq!(texts(&lines), expected);
+
+        let empty = view.wrap_styled(String::new(), Style::default(), 12);
+        assert_eq!(texts(&empty), [""]);
+    }
+
+    #[test]
+    fn message_lines_fit_the_width() {
+        let view = ChatView::new(10);
+        let session_id = crate::acp::SessionId("s1".to_string());
+        let long = "word ".repeat(200);
+        let message = Message::agent_response(
+            session_id,
+            ContentBlock::Text(agent_client_protocol::TextContent {
+                text: long,
+                annotations: Default::default(),
+            }),
+        );
+        let lines = view.format_message_lines(&message, 40);
+        assert!(lines.len() > 20);
+        assert!(lines.iter().all(|line| line.width() <= 40));
+    }
+}
@@ -0,0 +1,252 @@
+//! Terminal color depth. Themes are written in truecolor; on terminals with
+//! fewer colors [`downsample_buffer`] maps every cell of the rendered frame
+//! to the nearest color the terminal can show.
+
+use anyhow::{anyhow, Result};
+use ratatui::{buffer::Buffer, style::Color};
+use std::process::Command;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorDepth {
+    TrueColor,
+    /// The xterm 256-color palette
+    Ansi256,
+    /// The 16 named ANSI colors, whose exact shades the terminal picks
+    Ansi16,
+}
+
+/// xterm's default shades for the 16 ANSI colors, used to pick the nearest
+const ANSI16: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+/// Channel levels of the 6x6x6 cube in the 256-color palette
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+impl ColorDepth {
+    /// `truecolor`, `256` or `16`
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "truecolor" | "24bit" => Some(ColorDepth::TrueColor),
+            "256" => Some(ColorDepth::Ansi256),
+            "16" => Some(ColorDepth::Ansi16),
+            _ => None,
+        }
+    }
+
+    /// The `ui.colors` setting; `auto` detects the terminal's depth
+    pub fn from_config(name: &str) -> Result<Self> {
+        if name == "auto" {
+            return Ok(Self::detect());
+        }
+        Self::parse(name).ok_or_else(|| {
+            anyhow!(
+                "colors must be 'auto', 'truecolor', '256' or '16', got '{}'",
+                name
+            )
+        })
+    }
+
+    /// From `COLORTERM`, then the terminfo entry for `TERM`
+    pub fn detect() -> Self {
+        let colorterm = std::env::var("COLORTERM").ok();
+        let term = std::env::var("TERM").ok();
+        // The Windows console has no TERM but handles truecolor
+        if cfg!(windows) && term.is_none() {
+            return ColorDepth::TrueColor;
+        }
+        let terminfo_colors = Command::new("tput")
+            .arg("colors")
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .and_then(|output| String::from_utf8_lossy(&output.stdout).trim().parse().ok());
+        Self::from_env(colorterm.as_deref(), term.as_deref(), terminfo_colors)
+    }
+
+    fn from_env(colorterm: Option<&str>, term: Option<&str>, terminfo_colors: Option<u32>) -> Self {
+        if matches!(colorterm, Some("truecolor" | "24bit")) {
+            return ColorDepth::TrueColor;
+        }
+        match terminfo_colors {
+            // Direct-color entries such as xterm-direct report 2^24
+            Some(colors) if colors >= 1 << 24 => ColorDepth::TrueColor,
+            Some(colors) if colors >= 256 => ColorDepth::Ansi256,
+            Some(_) => ColorDepth::Ansi16,
+            // No tput: go by the name
+            None => match term {
+                Some(term) if term.ends_with("-direct") => ColorDepth::TrueColor,
+                Some(term) if term.contains("256color") => ColorDepth::Ansi256,
+                _ => ColorDepth::Ansi16,
+            },
+        }
+    }
+
+    /// `color` as this depth can show it; named colors and `Reset` are kept
+    pub fn map(self, color: Color) -> Color {
+        match (self, color) {
+            (ColorDepth::TrueColor, _) => color,
+            (ColorDepth::Ansi256, Color::Rgb(r, g, b)) => Color::Indexed(nearest_256((r, g, b))),
+            (ColorDepth::Ansi16, Color::Rgb(r, g, b)) => nearest_16((r, g, b)),
+            (ColorDepth::Ansi16, Color::Indexed(index)) if index < 16 => ANSI16[index as usize].0,
+            (ColorDepth::Ansi16, Color::Indexed(index)) => nearest_16(indexed_rgb(index)),
+            _ => color,
+        }
+    }
+}
+
+/// `TERM` names of text consoles and hardware terminal emulations, where
+/// effects only smear colors around
+const BASIC_TERMS: &[&str] = &["dumb", "linux", "cons25", "ansi", "sun", "wsvt25"];
+
+/// Whether a terminal can show effects: truecolor, and a `TERM` that is not
+/// a console or VT emulation
+pub fn supports_effects(depth: ColorDepth, term: Option<&str>) -> bool {
+    let basic = term.is_some_and(|term| BASIC_TERMS.contains(&term) || term.starts_with("vt"));
+    depth == ColorDepth::TrueColor && !basic
+}
+
+/// Map every cell of a rendered frame down to `depth`
+pub fn downsample_buffer(buf: &mut Buffer, depth: ColorDepth) {
+    if depth == ColorDepth::TrueColor {
+        return;
+    }
+    for cell in buf.content.iter_mut() {
+        cell.fg = depth.map(cell.fg);
+        cell.bg = depth.map(cell.bg);
+    }
+}
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let d = |x: u8, y: u8| (x as i32 - y as i32).pow(2) as u32;
+    d(a.0, b.0) + d(a.1, b.1) + d(a.2, b.2)
+}
+
+fn nearest_16(rgb: (u8, u8, u8)) -> Color {
+    ANSI16
+        .iter()
+        .min_by_key(|(_, shade)| distance(rgb, *shade))
+        .map(|(color, _)| *color)
+        .unwrap_or(Color::Reset)
+}
+
+/// The nearest of the color cube and the gray ramp; the first 16 entries
+/// are left out since terminals theme them
+fn nearest_256(rgb: (u8, u8, u8)) -> u8 {
+    let level = |channel: u8| {
+        (0..CUBE_LEVELS.len())
+            .min_by_key(|&i| (CUBE_LEVELS[i] as i32 - channel as i32).abs())
+            .unwrap_or(0)
+    };
+    let (r, g, b) = (level(rgb.0), level(rgb.1), level(rgb.2));
+    let cube = 16 + 36 * r + 6 * g + b;
+
+    let average = (rgb.0 as u32 + rgb.1 as u32 + rgb.2 as u32) / 3;
+    let gray = 232 + ((average.saturating_sub(8) + 5) / 10).min(23) as usize;
+
+    if distance(rgb, indexed_rgb(gray as u8)) < distance(rgb, indexed_rgb(cube as u8)) {
+        gray as u8
+    } else {
+        cube as u8
+    }
+}
+
+/// The shade of a 256-color palette entry
+fn indexed_rgb(index: u8) -> (u8, u8, u8) {
+    match index {
+        0..=15 => ANSI16[index as usize].1,
+        16..=231 => {
+            let i = (index - 16) as usize;
+            (
+                CUBE_LEVELS[i / 36],
+                CUBE_LEVELS[(i / 6) % 6],
+                CUBE_LEVELS[i % 6],
+            )
+        }
+        _ => {
+            let level = 8 + 10 * (index - 232);
+            (level, level, level)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_depth_from_colorterm_and_terminfo() {
+        use ColorDepth::*;
+        assert_eq!(
+            ColorDepth::from_env(Some("truecolor"), Some("xterm"), Some(8)),
+            TrueColor
+        );
+        assert_eq!(
+            ColorDepth::from_env(None, Some("xterm-256color"), Some(256)),
+            Ansi256
+        );
+        assert_eq!(ColorDepth::from_env(None, Some("linux"), Some(8)), Ansi16);
+        assert_eq!(
+            ColorDepth::from_env(None, Some("screen-256color"), None),
+            Ansi256
+        );
+        assert_eq!(ColorDepth::from_env(None, None, None), Ansi16);
+    }
+
+    #[test]
+    fn effects_need_truecolor_and_a_modern_term() {
+        use ColorDepth::*;
+        assert!(supports_effects(TrueColor, Some("xterm-kitty")));
+        assert!(supports_effects(TrueColor, None));
+        assert!(!supports_effects(Ansi256, Some("xterm-256color")));
+        assert!(!supports_effects(TrueColor, Some("linux")));
+        assert!(!supports_effects(TrueColor, Some("vt220")));
+    }
+
+    #[test]
+    fn maps_theme_colors_to_the_nearest_ansi_color() {
+        let ansi16 = ColorDepth::Ansi16;
+        // Cyberpunk crust, text and accents
+        assert_eq!(ansi16.map(Color::from_u32(0x0b0f14)), Color::Black);
+        assert_eq!(ansi16.map(Color::from_u32(0xd6dde7)), Color::Gray);
+        assert_eq!(ansi16.map(Color::from_u32(0x18e5ff)), Color::LightCyan);
+        assert_eq!(ansi16.map(Color::from_u32(0xff2e88)), Color::Magenta);
+        assert_eq!(ansi16.map(Color::Indexed(196)), Color::LightRed);
+        assert_eq!(ansi16.map(Color::Green), Color::Green);
+        assert_eq!(ansi16.map(Color::Reset), Color::Reset);
+
+        let ansi256 = ColorDepth::Ansi256;
+        assert_eq!(ansi256.map(Color::Rgb(255, 0, 0)), Color::Indexed(196));
+        assert_eq!(ansi256.map(Color::Rgb(128, 128, 128)), Color::Indexed(244));
+        assert_eq!(ansi256.map(Color::Rgb(0, 0, 0)), Color::Indexed(16));
+        assert_eq!(
+            ColorDepth::TrueColor.map(Color::Rgb(1, 2, 3)),
+            Color::Rgb(1, 2, 3)
+        );
+    }
+
+    #[test]
+    fn downsamples_every_cell() {
+        let mut buf = Buffer::empty(ratatui::layout::Rect::new(0, 0, 2, 1));
+        buf.content[0].fg = Color::Rgb(0, 255, 0);
+        buf.content[1].bg = Color::Rgb(250, 250, 250);
+        downsample_buffer(&mut buf, ColorDepth::Ansi16);
+        assert_eq!(buf.content[0].fg, Color::LightGreen);
+        assert_eq!(buf.content[1].bg, Color::White);
+    }
+}
@@ -3,11 +3,17 @@ use ratatui::{
     prelude::*,
     widgets::{Block, Borders, Clear, List, ListItem, ListState},
 };
-use std::collections::HashMap;
+
+use crate::ui::fuzzy::fuzzy_score;
 
 #[derive(Debug)]
 pub struct AgentSelector {
     agents: Vec<AgentInfo>,
+    /// Filter typed while the popup is open
+    query: String,
+    /// Indexes into `agents` that match the query, best first
+    matches: Vec<usize>,
+    /// Selection within `matches`
     state: ListState,
     visible: bool,
 }
@@ -19,6 +25,8 @@ pub struct AgentInfo {
     pub status: AgentStatus,
     pub capabilities: Vec<String>,
     pub enabled: bool,
+    /// How the agent authenticates, when that can be told without starting it
+    pub auth: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -27,6 +35,11 @@ pub enum AgentStatus {
     Disconnected,
     Connecting,
     Error(String),
+    /// Crashed too often; stays stopped until selected again
+    Failed {
+        reason: String,
+        stderr: Vec<String>,
+    },
 }
 
 impl AgentSelector {
@@ -36,6 +49,8 @@ impl AgentSelector {
 
         Self {
             agents: Vec::new(),
+            query: String::new(),
+            matches: Vec::new(),
             state,
             visible: false,
         }
@@ -55,95 +70,113 @@ impl AgentSelector {
         let popup_area = centered_rect(60, 70, area);
         frame.render_widget(Clear, popup_area);
 
-        let items: Vec<ListItem> = self
-            .agents
-            .iter()
-            .map(|agent| {
-                let status_text = match agent.status {
-                    AgentStatus::Connected => "🟢 Connected".to_string(),
-                    AgentStatus::Connecting => "🟡 Connecting...".to_string(),
-                    AgentStatus::Disconnected => "🔴 Disconnected".to_string(),
-                    AgentStatus::Error(ref e) => format!("❌ Error: {}", e),
-                };
-
-                let text = format!("{} - {}", agent.name, status_text);
-                ListItem::new(text).style(Style::default().white())
-            })
-            .collect();
-
-        let list = List::new(items)
-            .block(
-                Block::default()
-                    .title("Select Agent")
-                    .borders(Borders::ALL)
-                    .border_style(Style::default().blue()),
-            )
-            .highlight_style(Style::default().reversed())
-            .highlight_symbol(">> ");
-
-        frame.render_stateful_widget(list, popup_area, &mut self.state);
-
-        // Show help text at bottom
-        let help_area = Rect {
-            x: popup_area.x,
-            y: popup_area.y + popup_area.height - 3,
-            width: popup_area.width,
-            height: 3,
+        let block = Block::default()
+            .title("Select Agent")
+            .borders(Borders::ALL)
+            .border_style(Style::default().blue());
+        let inner = block.inner(popup_area);
+        frame.render_widget(block, popup_area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(2), // Filter
+                Constraint::Min(1),    // Agents
+                Constraint::Length(2), // Help
+            ])
+            .split(inner);
+
+        let filter = if self.query.is_empty() {
+            Line::from("Type to filter").dark_gray()
+        } else {
+            Line::from(vec![Span::raw("/ "), Span::raw(self.query.as_str()).bold()])
         };
+        frame.render_widget(
+            ratatui::widgets::Paragraph::new(filter).block(Block::default().borders(Borders::BOTTOM)),
+            chunks[0],
+        );
 
-        let help_text = vec![Line::from("↑/↓: Navigate, Enter: Select, Esc: Cancel")];
+        if self.matches.is_empty() {
+            let empty = if self.agents.is_empty() {
+                "No agents configured"
+            } else {
+                "No matching agents"
+            };
+            frame.render_widget(
+                ratatui::widgets::Paragraph::new(Line::from(empty).dark_gray()),
+                chunks[1],
+            );
+        } else {
+            let items: Vec<ListItem> = self
+                .matches
+                .iter()
+                .map(|&i| self.format_agent_item(&self.agents[i]))
+                .collect();
+            let list = List::new(items)
+                .highlight_style(Style::default().reversed())
+                .highlight_symbol(">> ");
+            frame.render_stateful_widget(list, chunks[1], &mut self.state);
+        }
 
+        let help_text = vec![Line::from("↑/↓: Navigate, Enter: Connect, Esc: Clear/Cancel")];
         let help = ratatui::widgets::Paragraph::new(help_text)
             .block(Block::default().borders(Borders::TOP))
             .style(Style::default().gray());
-
-        frame.render_widget(help, help_area);
+        frame.render_widget(help, chunks[2]);
 
         Ok(())
     }
 
-    fn format_agent_item(&self, agent: &AgentInfo) -> ListItem {
-        let status_char = match agent.status {
-            AgentStatus::Connected => "●",
-            AgentStatus::Disconnected => "○",
-            AgentStatus::Connecting => "◐",
-            AgentStatus::Error(_) => "✗",
+    fn format_agent_item(&self, agent: &AgentInfo) -> ListItem<'static> {
+        let (status_char, status_color) = match agent.status {
+            AgentStatus::Connected => ("●", Color::Green),
+            AgentStatus::Disconnected => ("○", Color::Gray),
+            AgentStatus::Connecting => ("◐", Color::Yellow),
+            AgentStatus::Error(_) | AgentStatus::Failed { .. } => ("✗", Color::Red),
         };
 
-        let status_color = match agent.status {
-            AgentStatus::Connected => Color::Green,
-            AgentStatus::Disconnected => Color::Gray,
-            AgentStatus::Connecting => Color::Yellow,
-            AgentStatus::Error(_) => Color::Red,
-        };
-
-        let capabilities_text = if agent.capabilities.is_empty() {
-            String::new()
-        } else {
-            format!(" ({})", agent.capabilities.join(", "))
-        };
+        let mut header = vec![
+            Span::styled(format!("{} ", status_char), Style::default().fg(status_color)),
+            Span::raw(agent.display_name.clone()).bold(),
+            Span::styled(
+                format!("  {}", agent.status.as_str().to_lowercase()),
+                Style::default().fg(status_color),
+            ),
+        ];
+        if let Some(auth) = &agent.auth {
+            header.push(Span::raw(format!(" · {}", auth)).dark_gray());
+        }
+        if !agent.capabilities.is_empty() {
+            header.push(Span::raw(format!(" ({})", agent.capabilities.join(", "))).dark_gray());
+        }
 
-        let item_text = format!(
-            "{} {}{}",
-            status_char, agent.display_name, capabilities_text
-        );
+        let mut lines = vec![Line::from(header)];
+        match &agent.status {
+            AgentStatus::Error(error) => {
+                lines.push(Line::from(format!("    {}", error)).red());
+            }
+            AgentStatus::Failed { reason, stderr } => {
+                lines.push(Line::from(format!("    {} (Enter to retry)", reason)).red());
+                lines.extend(
+                    stderr
+                        .iter()
+                        .map(|line| Line::from(format!("    {}", line)).dark_gray()),
+                );
+            }
+            _ => {}
+        }
 
         let style = if agent.enabled {
             Style::default().fg(Color::White)
         } else {
             Style::default().fg(Color::DarkGray)
         };
-
-        ListItem::new(item_text)
-            .style(style)
-            .add_modifier(Modifier::BOLD)
+        ListItem::new(lines).style(style)
     }
 
     pub fn show(&mut self) {
         self.visible = true;
-        if !self.agents.is_empty() && self.state.selected().is_none() {
-            self.state.select(Some(0));
-        }
+        self.set_query(String::new());
     }
 
     pub fn hide(&mut self) {
@@ -151,62 +184,105 @@ impl AgentSelector {
     }
 
     pub fn toggle_visibility(&mut self) {
-        self.visible = !self.visible;
+        if self.visible {
+            self.hide();
+        } else {
+            self.show();
+        }
     }
 
     pub fn is_visible(&self) -> bool {
         self.visible
     }
 
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// Add a character to the filter
+    pub fn push_char(&mut self, c: char) {
+        let mut query = std::mem::take(&mut self.query);
+        query.push(c);
+        self.set_query(query);
+    }
+
+    /// Remove the filter's last character; returns false if it was empty
+    pub fn pop_char(&mut self) -> bool {
+        let mut query = std::mem::take(&mut self.query);
+        let popped = query.pop().is_some();
+        self.set_query(query);
+        popped
+    }
+
+    /// Filter the agents by `query`, best match first, and select the top one
+    pub fn set_query(&mut self, query: String) {
+        self.query = query;
+        self.refilter();
+        self.state
+            .select(if self.matches.is_empty() { None } else { Some(0) });
+    }
+
+    fn refilter(&mut self) {
+        let mut scored: Vec<(i64, usize)> = self
+            .agents
+            .iter()
+            .enumerate()
+            .filter_map(|(i, agent)| {
+                let score = fuzzy_score(&self.query, &agent.name)
+                    .max(fuzzy_score(&self.query, &agent.display_name))?;
+                Some((score, i))
+            })
+            .collect();
+        // Stable, so equal scores keep the configured order
+        scored.sort_by_key(|&(score, _)| std::cmp::Reverse(score));
+        self.matches = scored.into_iter().map(|(_, i)| i).collect();
+    }
+
     pub fn next(&mut self) {
-        if self.agents.is_empty() {
+        if self.matches.is_empty() {
             return;
         }
 
         let i = match self.state.selected() {
-            Some(i) => (i + 1) % self.agents.len(),
+            Some(i) => (i + 1) % self.matches.len(),
             None => 0,
         };
         self.state.select(Some(i));
     }
 
     pub fn previous(&mut self) {
-        if self.agents.is_empty() {
+        if self.matches.is_empty() {
             return;
         }
 
         let i = match self.state.selected() {
-            Some(i) => {
-                if i == 0 {
-                    self.agents.len() - 1
-                } else {
-                    i - 1
-                }
-            }
-            None => 0,
+            Some(0) | None => self.matches.len() - 1,
+            Some(i) => i - 1,
         };
         self.state.select(Some(i));
     }
 
     pub fn get_selected_agent(&self) -> Option<&AgentInfo> {
-        self.state.selected().and_then(|i| self.agents.get(i))
+        self.state
+            .selected()
+            .and_then(|i| self.matches.get(i))
+            .and_then(|&i| self.agents.get(i))
     }
 
     pub fn update_agents(&mut self, agents: Vec<AgentInfo>) {
         let current_selection = self.get_selected_agent().map(|a| a.name.clone());
 
         self.agents = agents;
+        self.refilter();
 
         // Try to maintain selection
-        if let Some(selected_name) = current_selection {
-            if let Some(pos) = self.agents.iter().position(|a| a.name == selected_name) {
-                self.state.select(Some(pos));
-            } else if !self.agents.is_empty() {
-                self.state.select(Some(0));
-            }
-        } else if !self.agents.is_empty() {
-            self.state.select(Some(0));
-        }
+        let pos = current_selection
+            .and_then(|name| self.matches.iter().position(|&i| self.agents[i].name == name));
+        self.state.select(match pos {
+            Some(pos) => Some(pos),
+            None if self.matches.is_empty() => None,
+            None => Some(0),
+        });
     }
 
     pub fn update_agent_status(&mut self, agent_name: &str, status: AgentStatus) {
@@ -238,6 +314,7 @@ impl AgentStatus {
             AgentStatus::Disconnected => "Disconnected",
             AgentStatus::Connecting => "Connecting",
             AgentStatus::Error(_) => "Error",
+            AgentStatus::Failed { .. } => "Failed",
         }
     }
 }
@@ -250,6 +327,7 @@ impl AgentInfo {
             status: AgentStatus::Disconnected,
             capabilities: Vec::new(),
             enabled: true,
+            auth: None,
         }
     }
 
@@ -267,6 +345,11 @@ impl AgentInfo {
         self.enabled = enabled;
         self
     }
+
+    pub fn with_auth(mut self, auth: Option<String>) -> Self {
+        self.auth = auth;
+        self
+    }
 }
 
 fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
@@ -288,3 +371,56 @@ fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
         ])
         .split(popup_layout[1])[1]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn selector(names: &[&str]) -> AgentSelector {
+        let mut selector = AgentSelector::new();
+        selector.update_agents(
+            names
+                .iter()
+                .map(|name| AgentInfo::new(name.to_string(), name.to_string()))
+                .collect(),
+        );
+        selector
+    }
+
+    fn selected(selector: &AgentSelector) -> Option<&str> {
+        selector.get_selected_agent().map(|a| a.name.as_str())
+    }
+
+    #[test]
+    fn fuzzy_filter_ranks_and_selects_the_best_match() {
+        let mut selector = selector(&["claude-code", "gemini", "codex"]);
+        assert_eq!(selected(&selector), Some("claude-code"));
+
+        // A tight prefix match beats a scattered one
+        selector.set_query("co".to_string());
+        assert_eq!(selected(&selector), Some("codex"));
+        selector.next();
+        assert_eq!(selected(&selector), Some("claude-code"));
+        selector.next();
+        assert_eq!(selected(&selector), Some("codex"));
+
+        selector.set_query("GMI".to_string());
+        assert_eq!(selected(&selector), Some("gemini"));
+
+        selector.push_char('z');
+        assert_eq!(selected(&selector), None);
+        assert!(selector.pop_char());
+        assert_eq!(selected(&selector), Some("gemini"));
+    }
+
+    #[test]
+    fn updates_keep_the_selected_agent() {
+        let mut selector = selector(&["claude-code", "gemini"]);
+        selector.next();
+        selector.update_agents(vec![
+            AgentInfo::new("codex".to_string(), "codex".to_string()),
+            AgentInfo::new("gemini".to_string(), "gemini".to_string()),
+        ]);
+        assert_eq!(selected(&selector), Some("gemini"));
+    }
+}
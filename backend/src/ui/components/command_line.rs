@@ -0,0 +1,73 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{prelude::*, widgets::Paragraph};
+
+/// Vim-style `:` prompt drawn over the status bar. What is typed is handed
+/// back on Enter for the caller to run.
+#[derive(Debug, Default)]
+pub struct CommandLine {
+    input: Option<String>,
+}
+
+impl CommandLine {
+    pub fn open(&mut self) {
+        self.input = Some(String::new());
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.input.is_some()
+    }
+
+    /// Keys while open. Returns the command on Enter; Esc, or Backspace on
+    /// an empty line, closes without one.
+    pub fn handle_key_event(&mut self, key: KeyEvent) -> Option<String> {
+        let input = self.input.as_mut()?;
+        match key.code {
+            KeyCode::Enter => return self.input.take(),
+            KeyCode::Esc => self.input = None,
+            KeyCode::Backspace if input.is_empty() => self.input = None,
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Char(c) => input.push(c),
+            _ => {}
+        }
+        None
+    }
+
+    pub fn render(&self, frame: &mut Frame, area: Rect) {
+        let Some(input) = &self.input else {
+            return;
+        };
+        frame.render_widget(Paragraph::new(format!(":{}", input)), area);
+        frame.set_cursor_position(Position {
+            x: (area.x + 1 + input.chars().count() as u16).min(area.right().saturating_sub(1)),
+            y: area.y,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn returns_the_typed_command_on_enter() {
+        let mut line = CommandLine::default();
+        line.open();
+        for c in "split".chars() {
+            assert_eq!(
+                line.handle_key_event(KeyEvent::from(KeyCode::Char(c))),
+                None
+            );
+        }
+        assert_eq!(
+            line.handle_key_event(KeyEvent::from(KeyCode::Enter)),
+            Some("split".to_string())
+        );
+        assert!(!line.is_open());
+
+        line.open();
+        line.handle_key_event(KeyEvent::from(KeyCode::Backspace));
+        assert!(!line.is_open());
+    }
+}
@@ -0,0 +1,197 @@
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph},
+};
+
+use crate::ui::fuzzy::fuzzy_score;
+use crate::ui::keymap::Action;
+
+/// Popup listing every action in the keymap registry, filtered as you type
+#[derive(Debug, Default)]
+pub struct CommandPalette {
+    /// (action, key hint) for the contexts active when it was opened
+    entries: Vec<(Action, String)>,
+    query: String,
+    /// Indexes into `entries` that match the query, best first
+    matches: Vec<usize>,
+    state: ListState,
+    visible: bool,
+}
+
+impl CommandPalette {
+    /// Open with the given entries and an empty query
+    pub fn show(&mut self, entries: Vec<(Action, String)>) {
+        self.entries = entries;
+        self.visible = true;
+        self.set_query(String::new());
+    }
+
+    pub fn hide(&mut self) {
+        self.visible = false;
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    pub fn push_char(&mut self, c: char) {
+        let mut query = std::mem::take(&mut self.query);
+        query.push(c);
+        self.set_query(query);
+    }
+
+    pub fn pop_char(&mut self) {
+        let mut query = std::mem::take(&mut self.query);
+        query.pop();
+        self.set_query(query);
+    }
+
+    /// Filter by description or config name, best match first
+    pub fn set_query(&mut self, query: String) {
+        self.query = query;
+        let mut scored: Vec<(i64, usize)> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(i, (action, _))| {
+                let score = fuzzy_score(&self.query, action.description())
+                    .max(fuzzy_score(&self.query, action.name()))?;
+                Some((score, i))
+            })
+            .collect();
+        scored.sort_by_key(|&(score, _)| std::cmp::Reverse(score));
+        self.matches = scored.into_iter().map(|(_, i)| i).collect();
+        self.state.select(if self.matches.is_empty() {
+            None
+        } else {
+            Some(0)
+        });
+    }
+
+    pub fn next(&mut self) {
+        if self.matches.is_empty() {
+            return;
+        }
+        let i = self
+            .state
+            .selected()
+            .map_or(0, |i| (i + 1) % self.matches.len());
+        self.state.select(Some(i));
+    }
+
+    pub fn previous(&mut self) {
+        if self.matches.is_empty() {
+            return;
+        }
+        let i = match self.state.selected() {
+            Some(0) | None => self.matches.len() - 1,
+            Some(i) => i - 1,
+        };
+        self.state.select(Some(i));
+    }
+
+    pub fn selected(&self) -> Option<Action> {
+        self.state
+            .selected()
+            .and_then(|i| self.matches.get(i))
+            .map(|&i| self.entries[i].0)
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, area: Rect, accent: Color) {
+        if !self.visible || area.width < 20 || area.height < 10 {
+            return;
+        }
+
+        let popup_area = palette_rect(area);
+        frame.render_widget(Clear, popup_area);
+        let block = Block::default()
+            .title("Commands")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(accent));
+        let inner = block.inner(popup_area);
+        frame.render_widget(block, popup_area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(2), Constraint::Min(1)])
+            .split(inner);
+
+        let prompt = Line::from(vec![Span::raw("> "), Span::raw(self.query.as_str()).bold()]);
+        frame.render_widget(
+            Paragraph::new(prompt).block(Block::default().borders(Borders::BOTTOM)),
+            chunks[0],
+        );
+
+        if self.matches.is_empty() {
+            frame.render_widget(
+                Paragraph::new(Line::from("No matching commands").dark_gray()),
+                chunks[1],
+            );
+            return;
+        }
+
+        // Key hints are right-aligned in the space the highlight symbol leaves
+        let width = chunks[1].width.saturating_sub(3) as usize;
+        let items: Vec<ListItem> = self
+            .matches
+            .iter()
+            .map(|&i| {
+                let (action, keys) = &self.entries[i];
+                let description = action.description();
+                let pad = width.saturating_sub(description.chars().count() + keys.chars().count());
+                ListItem::new(Line::from(vec![
+                    Span::raw(description),
+                    Span::raw(" ".repeat(pad)),
+                    Span::raw(keys.clone()).dark_gray(),
+                ]))
+            })
+            .collect();
+        let list = List::new(items)
+            .highlight_style(Style::default().reversed())
+            .highlight_symbol("> ");
+        frame.render_stateful_widget(list, chunks[1], &mut self.state);
+    }
+}
+
+/// Top-anchored like most palettes: 60% wide, up to 16 rows tall
+fn palette_rect(area: Rect) -> Rect {
+    let width = area.width * 3 / 5;
+    let height = area.height.saturating_sub(4).min(16);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + 2,
+        width,
+        height,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filters_by_description_and_name() {
+        let mut palette = CommandPalette::default();
+        palette.show(vec![
+            (Action::NewSession, "n".to_string()),
+            (Action::SwitchTheme, "T".to_string()),
+            (Action::ExportSession, String::new()),
+        ]);
+        assert_eq!(palette.selected(), Some(Action::NewSession));
+
+        for c in "theme".chars() {
+            palette.push_char(c);
+        }
+        assert_eq!(palette.selected(), Some(Action::SwitchTheme));
+
+        palette.set_query("export_s".to_string());
+        assert_eq!(palette.selected(), Some(Action::ExportSession));
+
+        palette.push_char('!');
+        assert_eq!(palette.selected(), None);
+    }
+}
@@ -0,0 +1,169 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Clear, List, ListItem, ListState},
+};
+
+use crate::config::ui::EFFECT_PRESETS;
+
+/// A row of the gallery that can be previewed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GalleryItem {
+    Theme(String),
+    /// Name from `EFFECT_PRESETS`
+    Effects(&'static str),
+}
+
+/// What a key did to the open gallery
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GalleryResult {
+    /// Nothing to do
+    None,
+    /// Selection moved onto an item; show it on screen
+    Preview(GalleryItem),
+    /// Keep what is previewed and save it
+    Apply,
+    /// Closed without applying; restore what was there before
+    Cancel,
+}
+
+/// Side panel listing themes and effects presets. The rest of the screen
+/// stays visible so each entry can be previewed on it as it is selected.
+#[derive(Debug, Default)]
+pub struct ThemeGallery {
+    items: Vec<GalleryItem>,
+    state: ListState,
+    visible: bool,
+}
+
+impl ThemeGallery {
+    /// Open on `current_theme`, listing `themes` then the effects presets
+    pub fn show(&mut self, themes: Vec<String>, current_theme: &str) {
+        self.items = themes
+            .into_iter()
+            .map(GalleryItem::Theme)
+            .chain(EFFECT_PRESETS.iter().map(|(name, _)| GalleryItem::Effects(name)))
+            .collect();
+        let current = self
+            .items
+            .iter()
+            .position(|item| matches!(item, GalleryItem::Theme(name) if name == current_theme));
+        self.state.select(Some(current.unwrap_or(0)));
+        self.visible = true;
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn handle_key_event(&mut self, key: KeyEvent) -> GalleryResult {
+        let count = self.items.len();
+        if count == 0 {
+            self.visible = false;
+            return GalleryResult::Cancel;
+        }
+        let current = self.state.selected().unwrap_or(0);
+        let next = match key.code {
+            KeyCode::Down | KeyCode::Tab | KeyCode::Char('j') => (current + 1) % count,
+            KeyCode::Up | KeyCode::BackTab | KeyCode::Char('k') => (current + count - 1) % count,
+            KeyCode::Enter => {
+                self.visible = false;
+                return GalleryResult::Apply;
+            }
+            KeyCode::Esc | KeyCode::Char('q') => {
+                self.visible = false;
+                return GalleryResult::Cancel;
+            }
+            _ => return GalleryResult::None,
+        };
+        self.state.select(Some(next));
+        GalleryResult::Preview(self.items[next].clone())
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, area: Rect, accent: Color) {
+        if !self.visible || area.width < 30 || area.height < 10 {
+            return;
+        }
+
+        // Section headings sit between the rows, so the selection is offset
+        let mut rows = vec![ListItem::new(Line::from("Themes").bold())];
+        let mut effects_heading = true;
+        for item in &self.items {
+            match item {
+                GalleryItem::Theme(name) => rows.push(ListItem::new(format!("  {}", name))),
+                GalleryItem::Effects(name) => {
+                    if effects_heading {
+                        rows.push(ListItem::new(Line::from("Effects").bold()));
+                        effects_heading = false;
+                    }
+                    let description = EFFECT_PRESETS
+                        .iter()
+                        .find(|(preset, _)| preset == name)
+                        .map_or("", |(_, description)| description);
+                    rows.push(ListItem::new(Line::from(vec![
+                        Span::raw(format!("  {:<8}", name)),
+                        Span::raw(description).dark_gray(),
+                    ])));
+                }
+            }
+        }
+        let row = self.state.selected().map(|i| match self.items.get(i) {
+            Some(GalleryItem::Effects(_)) => i + 2,
+            _ => i + 1,
+        });
+        let mut state = ListState::default().with_selected(row);
+
+        let popup_area = gallery_rect(area, rows.len() as u16 + 2);
+        frame.render_widget(Clear, popup_area);
+        let list = List::new(rows)
+            .block(
+                Block::default()
+                    .title("Gallery (Enter: apply, Esc: cancel)")
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(accent)),
+            )
+            .highlight_style(Style::default().reversed())
+            .highlight_symbol("> ");
+        frame.render_stateful_widget(list, popup_area, &mut state);
+    }
+}
+
+/// Right-hand column below the tab bar, so the chat shows the preview
+fn gallery_rect(area: Rect, rows: u16) -> Rect {
+    let width = (area.width / 3).clamp(30, 44).min(area.width);
+    let height = rows.min(area.height.saturating_sub(4));
+    Rect {
+        x: area.right() - width,
+        y: area.y + 2,
+        width,
+        height,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(gallery: &mut ThemeGallery, code: KeyCode) -> GalleryResult {
+        gallery.handle_key_event(KeyEvent::from(code))
+    }
+
+    #[test]
+    fn previews_as_the_selection_moves() {
+        let mut gallery = ThemeGallery::default();
+        gallery.show(vec!["cyberpunk".to_string(), "dracula".to_string()], "dracula");
+
+        // Opens on the current theme, the last before the effects presets
+        assert_eq!(
+            press(&mut gallery, KeyCode::Down),
+            GalleryResult::Preview(GalleryItem::Effects("full"))
+        );
+        assert_eq!(
+            press(&mut gallery, KeyCode::Up),
+            GalleryResult::Preview(GalleryItem::Theme("dracula".to_string()))
+        );
+        assert_eq!(press(&mut gallery, KeyCode::Char('x')), GalleryResult::None);
+        assert_eq!(press(&mut gallery, KeyCode::Esc), GalleryResult::Cancel);
+        assert!(!gallery.is_visible());
+    }
+}
@@ -0,0 +1,192 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    prelude::*,
+    widgets::{Block, BorderType, Borders, Clear, Paragraph},
+};
+
+use crate::ui::keymap::HelpEntry;
+
+/// Key bindings overlay built from the keymap registry; typing narrows it
+/// down to the matching entries
+#[derive(Debug, Default)]
+pub struct HelpOverlay {
+    query: String,
+    /// Lines scrolled down from the top
+    scroll: usize,
+    visible: bool,
+}
+
+impl HelpOverlay {
+    pub fn show(&mut self) {
+        self.visible = true;
+        self.query.clear();
+        self.scroll = 0;
+    }
+
+    pub fn hide(&mut self) {
+        self.visible = false;
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Keys while the overlay is open. Printable keys search; `Esc` clears
+    /// the search, then closes, as do `Enter` and `?` with no search.
+    pub fn handle_key_event(&mut self, key: KeyEvent) {
+        let plain = !key
+            .modifiers
+            .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+        match key.code {
+            KeyCode::Esc if !self.query.is_empty() => self.query.clear(),
+            KeyCode::Esc | KeyCode::Enter => self.hide(),
+            KeyCode::Char('?') if self.query.is_empty() => self.hide(),
+            KeyCode::Backspace => {
+                self.query.pop();
+            }
+            KeyCode::Up => self.scroll = self.scroll.saturating_sub(1),
+            KeyCode::Down => self.scroll += 1,
+            KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(10),
+            KeyCode::PageDown => self.scroll += 10,
+            KeyCode::Char(c) if plain => self.query.push(c),
+            _ => return,
+        }
+        if !matches!(
+            key.code,
+            KeyCode::Up | KeyCode::Down | KeyCode::PageUp | KeyCode::PageDown
+        ) {
+            self.scroll = 0;
+        }
+    }
+
+    /// Section lines for the current search; sections with no match are left out
+    fn lines(&self, sections: &[(&'static str, Vec<HelpEntry>)]) -> Vec<Line<'static>> {
+        let mut lines = Vec::new();
+        for (title, entries) in sections {
+            let shown: Vec<&HelpEntry> = entries
+                .iter()
+                .filter(|entry| entry.matches(&self.query))
+                .collect();
+            if shown.is_empty() {
+                continue;
+            }
+            lines.push(Line::from(format!("{}:", title)).bold());
+            for entry in shown {
+                let line = if entry.keys.is_empty() {
+                    Line::from(vec![
+                        Span::raw(format!("  {:<9} - {} ", "-", entry.description)),
+                        Span::styled(format!("({})", entry.name), Style::default().dark_gray()),
+                    ])
+                } else {
+                    Line::from(format!("  {:<9} - {}", entry.keys, entry.description))
+                };
+                lines.push(line);
+            }
+            lines.push(Line::from(""));
+        }
+        lines
+    }
+
+    pub fn render(
+        &mut self,
+        frame: &mut Frame,
+        area: Rect,
+        sections: &[(&'static str, Vec<HelpEntry>)],
+        accent: Color,
+    ) {
+        frame.render_widget(Clear, area);
+
+        let block = Block::default()
+            .title("Help")
+            .borders(Borders::ALL)
+            .border_type(BorderType::Double)
+            .border_style(Style::default().fg(accent));
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let [header, body, footer] = Layout::vertical([
+            Constraint::Length(2),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .areas(inner);
+
+        let search = if self.query.is_empty() {
+            Span::styled("Type to search", Style::default().dark_gray())
+        } else {
+            Span::raw(format!("Search: {}▏", self.query))
+        };
+        frame.render_widget(
+            Paragraph::new(vec![
+                Line::from("RAT - Rust Agent Terminal Help"),
+                Line::from(search),
+            ]),
+            header,
+        );
+
+        let mut lines = self.lines(sections);
+        if lines.is_empty() {
+            lines.push(Line::from("No bindings match").dark_gray());
+        }
+        self.scroll = self
+            .scroll
+            .min(lines.len().saturating_sub(body.height as usize));
+        frame.render_widget(Paragraph::new(lines).scroll((self.scroll as u16, 0)), body);
+
+        frame.render_widget(
+            Paragraph::new("Esc closes · ↑/↓ scroll · rebind under [ui.keybindings]").dark_gray(),
+            footer,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ui::UiConfig;
+    use crate::ui::keymap::Keymap;
+
+    fn type_str(help: &mut HelpOverlay, text: &str) {
+        for c in text.chars() {
+            help.handle_key_event(KeyEvent::from(KeyCode::Char(c)));
+        }
+    }
+
+    #[test]
+    fn search_narrows_sections_and_esc_clears_before_closing() {
+        let keymap = Keymap::from_ui_config(&UiConfig::default()).unwrap();
+        let sections = keymap.help_sections();
+        let mut help = HelpOverlay::default();
+        help.show();
+        let all = help.lines(&sections).len();
+
+        type_str(&mut help, "scroll logs");
+        let texts: Vec<String> = help
+            .lines(&sections)
+            .iter()
+            .map(|l| l.to_string())
+            .collect();
+        assert_eq!(
+            texts,
+            [
+                "Logs (when focused):",
+                "  PgUp      - Scroll logs up",
+                "  PgDn      - Scroll logs down",
+                ""
+            ]
+        );
+        assert!(help.lines(&sections).len() < all);
+
+        // Unbound actions are listed with their config name
+        help.handle_key_event(KeyEvent::from(KeyCode::Esc));
+        assert!(help.is_visible());
+        type_str(&mut help, "export");
+        assert!(help.lines(&sections)[1]
+            .to_string()
+            .contains("(export_session)"));
+
+        help.handle_key_event(KeyEvent::from(KeyCode::Esc));
+        help.handle_key_event(KeyEvent::from(KeyCode::Esc));
+        assert!(!help.is_visible());
+    }
+}
@@ -1,3 +1,15 @@
 pub mod agent_selector;
+pub mod command_line;
+pub mod command_palette;
+pub mod gallery;
+pub mod help;
+pub mod modal;
+pub mod toast;
 
 pub use agent_selector::AgentSelector;
+pub use command_line::CommandLine;
+pub use command_palette::CommandPalette;
+pub use gallery::{GalleryItem, GalleryResult, ThemeGallery};
+pub use help::HelpOverlay;
+pub use modal::{Modal, ModalResult};
+pub use toast::{ToastLevel, Toasts};
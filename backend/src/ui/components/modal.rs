@@ -0,0 +1,235 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    prelude::*,
+    widgets::{Block, BorderType, Borders, Clear, Paragraph, Wrap},
+};
+
+use super::ToastLevel;
+
+#[derive(Debug, Clone)]
+struct Button<A> {
+    label: String,
+    /// Activates the button without moving focus to it
+    hotkey: Option<char>,
+    /// Handed back when the button is chosen; `None` just closes
+    result: Option<A>,
+    /// Drawn in the error color, for destructive choices
+    danger: bool,
+}
+
+/// What a key did to an open modal
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModalResult<A> {
+    /// Still open
+    Open,
+    /// Closed with the chosen button's result; `None` when cancelled
+    Closed(Option<A>),
+}
+
+/// Centered dialog that takes every key until a button is chosen. `A` is
+/// what the caller wants done, e.g. an enum of confirmable actions.
+#[derive(Debug, Clone)]
+pub struct Modal<A> {
+    level: ToastLevel,
+    title: String,
+    lines: Vec<String>,
+    buttons: Vec<Button<A>>,
+    focused: usize,
+    /// Messages close on any key rather than only on their button
+    any_key_closes: bool,
+}
+
+impl<A: Clone> Modal<A> {
+    /// A message to acknowledge; any key closes it
+    pub fn message(level: ToastLevel, title: impl Into<String>, lines: Vec<String>) -> Self {
+        Self {
+            level,
+            title: title.into(),
+            lines,
+            buttons: vec![Button {
+                label: "OK".to_string(),
+                hotkey: None,
+                result: None,
+                danger: false,
+            }],
+            focused: 0,
+            any_key_closes: true,
+        }
+    }
+
+    /// A yes/no question. `y` or the confirm button returns `action`; `n`,
+    /// `Esc` or Cancel returns nothing. Focus starts on Cancel so a stray
+    /// Enter is harmless.
+    pub fn confirm(
+        title: impl Into<String>,
+        lines: Vec<String>,
+        confirm_label: impl Into<String>,
+        action: A,
+    ) -> Self {
+        Self {
+            level: ToastLevel::Warning,
+            title: title.into(),
+            lines,
+            buttons: vec![
+                Button {
+                    label: confirm_label.into(),
+                    hotkey: Some('y'),
+                    result: Some(action),
+                    danger: true,
+                },
+                Button {
+                    label: "Cancel".to_string(),
+                    hotkey: Some('n'),
+                    result: None,
+                    danger: false,
+                },
+            ],
+            focused: 1,
+            any_key_closes: false,
+        }
+    }
+
+    /// Rename the Cancel button of a `confirm`, e.g. to "Deny"
+    pub fn with_cancel_label(mut self, label: impl Into<String>) -> Self {
+        if let Some(cancel) = self.buttons.iter_mut().find(|b| b.result.is_none()) {
+            cancel.label = label.into();
+        }
+        self
+    }
+
+    pub fn level(&self) -> ToastLevel {
+        self.level
+    }
+
+    pub fn handle_key_event(&mut self, key: KeyEvent) -> ModalResult<A> {
+        if self.any_key_closes {
+            return self.choose(self.focused);
+        }
+        let count = self.buttons.len();
+        match key.code {
+            KeyCode::Esc => return ModalResult::Closed(None),
+            KeyCode::Enter | KeyCode::Char(' ') => return self.choose(self.focused),
+            KeyCode::Right | KeyCode::Tab | KeyCode::Char('l') => {
+                self.focused = (self.focused + 1) % count;
+            }
+            KeyCode::Left | KeyCode::BackTab | KeyCode::Char('h') => {
+                self.focused = (self.focused + count - 1) % count;
+            }
+            KeyCode::Char(c) => {
+                let c = c.to_ascii_lowercase();
+                if let Some(i) = self.buttons.iter().position(|b| b.hotkey == Some(c)) {
+                    return self.choose(i);
+                }
+            }
+            _ => {}
+        }
+        ModalResult::Open
+    }
+
+    fn choose(&self, index: usize) -> ModalResult<A> {
+        ModalResult::Closed(self.buttons.get(index).and_then(|b| b.result.clone()))
+    }
+
+    fn button_line(&self, palette_error: Color) -> Line<'static> {
+        let mut spans = Vec::new();
+        for (i, button) in self.buttons.iter().enumerate() {
+            if i > 0 {
+                spans.push(Span::raw("   "));
+            }
+            let label = match button.hotkey {
+                Some(key) => format!("[ {} ({}) ]", button.label, key),
+                None => format!("[ {} ]", button.label),
+            };
+            let mut style = Style::default();
+            if button.danger {
+                style = style.fg(palette_error);
+            }
+            if i == self.focused {
+                style = style.add_modifier(Modifier::REVERSED | Modifier::BOLD);
+            }
+            spans.push(Span::styled(label, style));
+        }
+        Line::from(spans).alignment(Alignment::Center)
+    }
+
+    /// Where `render` draws it: centered in `area`, sized to the text
+    pub fn area(&self, area: Rect) -> Rect {
+        let width = (area.width * 3 / 5).max(40).min(area.width);
+        let inner_width = width.saturating_sub(2).max(1) as usize;
+        let text_rows: usize = self
+            .lines
+            .iter()
+            .map(|line| line.chars().count().div_ceil(inner_width).max(1))
+            .sum();
+        // Text, a blank row, the buttons and the borders
+        let height = ((text_rows + 4) as u16).min(area.height);
+        Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        }
+    }
+
+    /// Draw centered in `area`, sized to the text
+    pub fn render(&self, frame: &mut Frame, area: Rect, accent: Color, error: Color) {
+        let rect = self.area(area);
+        frame.render_widget(Clear, rect);
+
+        let block = Block::default()
+            .title(self.title.clone())
+            .borders(Borders::ALL)
+            .border_type(BorderType::Double)
+            .border_style(Style::default().fg(accent));
+        let inner = block.inner(rect);
+        frame.render_widget(block, rect);
+
+        let [body, buttons] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(inner);
+        let text: Vec<Line> = self
+            .lines
+            .iter()
+            .map(|line| Line::from(line.clone()))
+            .collect();
+        frame.render_widget(Paragraph::new(text).wrap(Wrap { trim: true }), body);
+        frame.render_widget(Paragraph::new(self.button_line(error)), buttons);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(modal: &mut Modal<&'static str>, code: KeyCode) -> ModalResult<&'static str> {
+        modal.handle_key_event(KeyEvent::from(code))
+    }
+
+    #[test]
+    fn confirm_defaults_to_cancel_and_traps_keys() {
+        let mut modal = Modal::confirm("Quit?", vec![], "Quit", "quit");
+        assert_eq!(press(&mut modal, KeyCode::Char('q')), ModalResult::Open);
+        assert_eq!(press(&mut modal, KeyCode::Enter), ModalResult::Closed(None));
+
+        let mut modal = Modal::confirm("Quit?", vec![], "Quit", "quit");
+        assert_eq!(press(&mut modal, KeyCode::Left), ModalResult::Open);
+        assert_eq!(
+            press(&mut modal, KeyCode::Enter),
+            ModalResult::Closed(Some("quit"))
+        );
+        assert_eq!(
+            press(&mut modal, KeyCode::Char('Y')),
+            ModalResult::Closed(Some("quit"))
+        );
+        assert_eq!(press(&mut modal, KeyCode::Esc), ModalResult::Closed(None));
+    }
+
+    #[test]
+    fn messages_close_on_any_key() {
+        let mut modal: Modal<&'static str> =
+            Modal::message(ToastLevel::Error, "Error", vec!["boom".to_string()]);
+        assert_eq!(
+            press(&mut modal, KeyCode::Char('x')),
+            ModalResult::Closed(None)
+        );
+    }
+}
@@ -0,0 +1,149 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use ratatui::{
+    prelude::*,
+    widgets::{Block, BorderType, Borders, Clear, Paragraph, Wrap},
+};
+
+/// Most toasts shown at once; older ones are dropped first
+const MAX_TOASTS: usize = 4;
+const TOAST_WIDTH: u16 = 40;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToastLevel {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+impl ToastLevel {
+    /// Errors stay up longer so they can be read
+    fn duration(self) -> Duration {
+        match self {
+            ToastLevel::Info | ToastLevel::Success => Duration::from_secs(3),
+            ToastLevel::Warning => Duration::from_secs(5),
+            ToastLevel::Error => Duration::from_secs(8),
+        }
+    }
+
+    fn color(self) -> Color {
+        match self {
+            ToastLevel::Info => Color::Cyan,
+            ToastLevel::Success => Color::Green,
+            ToastLevel::Warning => Color::Yellow,
+            ToastLevel::Error => Color::Red,
+        }
+    }
+
+    fn title(self) -> &'static str {
+        match self {
+            ToastLevel::Info => "Info",
+            ToastLevel::Success => "Done",
+            ToastLevel::Warning => "Warning",
+            ToastLevel::Error => "Error",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Toast {
+    level: ToastLevel,
+    message: String,
+    expires: Instant,
+}
+
+/// Non-blocking notifications stacked in the bottom-right corner, newest
+/// at the bottom, each dismissed after a delay that depends on its level
+#[derive(Debug, Default)]
+pub struct Toasts {
+    toasts: VecDeque<Toast>,
+}
+
+impl Toasts {
+    pub fn push(&mut self, level: ToastLevel, message: String) {
+        // Repeats (e.g. an agent failing to reconnect) refresh the existing toast
+        self.toasts
+            .retain(|t| !(t.level == level && t.message == message));
+        self.toasts.push_back(Toast {
+            level,
+            message,
+            expires: Instant::now() + level.duration(),
+        });
+        while self.toasts.len() > MAX_TOASTS {
+            self.toasts.pop_front();
+        }
+    }
+
+    /// Drop expired toasts; returns whether any were removed
+    pub fn tick(&mut self) -> bool {
+        let now = Instant::now();
+        let before = self.toasts.len();
+        self.toasts.retain(|t| t.expires > now);
+        self.toasts.len() != before
+    }
+
+    /// Render above the bottom edge of `area` (the space above the status bar)
+    pub fn render(&self, frame: &mut Frame, area: Rect) {
+        let width = TOAST_WIDTH.min(area.width);
+        if width < 10 {
+            return;
+        }
+        let inner_width = width.saturating_sub(2).max(1) as usize;
+
+        let mut bottom = area.y + area.height;
+        for toast in self.toasts.iter().rev() {
+            // Wrapped lines plus the border
+            let lines = toast.message.chars().count().div_ceil(inner_width).max(1) as u16;
+            let height = (lines + 2).min(6);
+            if bottom < area.y + height {
+                break;
+            }
+            bottom -= height;
+            let rect = Rect {
+                x: area.x + area.width - width,
+                y: bottom,
+                width,
+                height,
+            };
+
+            let color = toast.level.color();
+            let popup = Paragraph::new(toast.message.as_str())
+                .block(
+                    Block::default()
+                        .title(toast.level.title())
+                        .borders(Borders::ALL)
+                        .border_type(BorderType::Rounded)
+                        .border_style(Style::default().fg(color)),
+                )
+                .wrap(Wrap { trim: true });
+            frame.render_widget(Clear, rect);
+            frame.render_widget(popup, rect);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeats_refresh_and_the_stack_is_capped() {
+        let mut toasts = Toasts::default();
+        for i in 0..6 {
+            toasts.push(ToastLevel::Info, format!("event {}", i));
+        }
+        toasts.push(ToastLevel::Info, "event 5".to_string());
+        let messages: Vec<&str> = toasts.toasts.iter().map(|t| t.message.as_str()).collect();
+        assert_eq!(messages, ["event 2", "event 3", "event 4", "event 5"]);
+
+        assert!(!toasts.tick());
+        toasts
+            .toasts
+            .iter_mut()
+            .for_each(|t| t.expires = Instant::now());
+        assert!(toasts.tick());
+        assert!(toasts.toasts.is_empty());
+    }
+}
@@ -0,0 +1,122 @@
+//! Watches how long frames take to draw, so ambient effects can be shed
+//! when the terminal can't keep up (e.g. over a slow SSH connection) and
+//! brought back once it can.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Frames judged together
+const WINDOW: usize = 30;
+/// Slow frames in a full window that count as falling behind
+const SLOW_TO_DEGRADE: usize = 20;
+/// Slow frames a window may still hold when effects come back
+const SLOW_TO_RESTORE: usize = 3;
+/// How long effects stay off the first time; each further time doubles it
+const FIRST_PAUSE: Duration = Duration::from_secs(30);
+const MAX_PAUSE: Duration = Duration::from_secs(300);
+
+/// A switch `record` asks for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadChange {
+    /// Frames keep running over budget; turn ambient effects off
+    Degraded,
+    /// Effects were off long enough and frames are fast; turn them back on
+    Restored,
+}
+
+#[derive(Debug)]
+pub struct FrameBudget {
+    budget: Duration,
+    /// Whether each recent frame ran over budget, oldest first
+    recent: VecDeque<bool>,
+    degraded_at: Option<Instant>,
+    pause: Duration,
+}
+
+impl FrameBudget {
+    /// Budget of one frame at `fps`
+    pub fn new(fps: u32) -> Self {
+        Self {
+            budget: Duration::from_secs(1) / fps.max(1),
+            recent: VecDeque::with_capacity(WINDOW),
+            degraded_at: None,
+            pause: FIRST_PAUSE,
+        }
+    }
+
+    pub fn set_fps(&mut self, fps: u32) {
+        self.budget = Duration::from_secs(1) / fps.max(1);
+    }
+
+    pub fn is_degraded(&self) -> bool {
+        self.degraded_at.is_some()
+    }
+
+    /// Count a frame that took `elapsed` to draw. Frames without ambient
+    /// effects are cheaper, so effects only come back after a pause that
+    /// grows each time they have to be turned off again.
+    pub fn record(&mut self, elapsed: Duration, now: Instant) -> Option<LoadChange> {
+        if self.recent.len() == WINDOW {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(elapsed > self.budget);
+        let slow = self.recent.iter().filter(|slow| **slow).count();
+
+        match self.degraded_at {
+            None if self.recent.len() == WINDOW && slow >= SLOW_TO_DEGRADE => {
+                self.degraded_at = Some(now);
+                self.recent.clear();
+                Some(LoadChange::Degraded)
+            }
+            Some(at) if now.duration_since(at) >= self.pause && slow <= SLOW_TO_RESTORE => {
+                self.degraded_at = None;
+                self.pause = (self.pause * 2).min(MAX_PAUSE);
+                self.recent.clear();
+                Some(LoadChange::Restored)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn degrades_on_slow_frames_and_restores_after_a_growing_pause() {
+        let mut budget = FrameBudget::new(50); // 20ms a frame
+        let start = Instant::now();
+        let slow = Duration::from_millis(45);
+        let fast = Duration::from_millis(5);
+
+        // A few slow frames among fast ones are fine
+        for i in 0..WINDOW {
+            let elapsed = if i % 3 == 0 { slow } else { fast };
+            assert_eq!(budget.record(elapsed, start), None);
+        }
+        let degraded = (0..WINDOW).find_map(|_| budget.record(slow, start));
+        assert_eq!(degraded, Some(LoadChange::Degraded));
+        assert!(budget.is_degraded());
+
+        // Fast frames alone don't bring effects back before the pause is over
+        assert_eq!(budget.record(fast, start + FIRST_PAUSE / 2), None);
+        assert_eq!(
+            budget.record(fast, start + FIRST_PAUSE),
+            Some(LoadChange::Restored)
+        );
+
+        // The next pause is twice as long
+        let again = start + FIRST_PAUSE * 2;
+        for _ in 0..SLOW_TO_DEGRADE - 1 {
+            budget.record(fast, again);
+        }
+        let degraded = (0..WINDOW).find_map(|_| budget.record(slow, again));
+        assert_eq!(degraded, Some(LoadChange::Degraded));
+        assert_eq!(budget.record(fast, again + FIRST_PAUSE), None);
+        assert_eq!(
+            budget.record(fast, again + FIRST_PAUSE * 2),
+            Some(LoadChange::Restored)
+        );
+    }
+}
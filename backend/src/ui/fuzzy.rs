@@ -0,0 +1,39 @@
+//! Fuzzy matching for the filterable popups (agent selector, command palette)
+
+/// How well `query` matches `text` as a case-insensitive subsequence, or
+/// `None` if it doesn't. Consecutive characters and matches at the start of
+/// a word score higher; gaps cost a little.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+    let text: Vec<char> = text.chars().flat_map(char::to_lowercase).collect();
+    let mut score = 0;
+    let mut pos = 0;
+    let mut last_match: Option<usize> = None;
+    for q in query.chars().flat_map(char::to_lowercase) {
+        let found = pos + text[pos..].iter().position(|&c| c == q)?;
+        score += 1;
+        if last_match == Some(found.wrapping_sub(1)) {
+            score += 5;
+        } else if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        if let Some(last) = last_match {
+            score -= (found - last - 1).min(3) as i64;
+        }
+        last_match = Some(found);
+        pos = found + 1;
+    }
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tight_and_word_start_matches_score_higher() {
+        assert_eq!(fuzzy_score("", "anything"), Some(0));
+        assert_eq!(fuzzy_score("xyz", "codex"), None);
+        assert!(fuzzy_score("co", "codex") > fuzzy_score("co", "claude-code"));
+        assert!(fuzzy_score("tt", "Toggle terminal") > fuzzy_score("tt", "Next tab"));
+    }
+}
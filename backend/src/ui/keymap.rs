@@ -0,0 +1,650 @@
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::config::ui::{KeybindingConfig, UiConfig};
+
+/// Where an action applies. Contexts are checked in priority order, so the
+/// same key can mean different things (e.g. `n` while a search is active).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyContext {
+    Global,
+    /// Terminal panel is open
+    Terminal,
+    /// A terminal scrollback search is active
+    Search,
+    /// Chat view of the active tab (when not typing)
+    Chat,
+    /// The logs pane has focus
+    Logs,
+    /// The traffic pane has focus
+    Traffic,
+}
+
+impl KeyContext {
+    /// Order of the help overlay's sections
+    const ALL: [KeyContext; 6] = [
+        KeyContext::Global,
+        KeyContext::Chat,
+        KeyContext::Terminal,
+        KeyContext::Search,
+        KeyContext::Logs,
+        KeyContext::Traffic,
+    ];
+
+    fn title(self) -> &'static str {
+        match self {
+            KeyContext::Global => "Global",
+            KeyContext::Terminal => "Terminal (when open)",
+            KeyContext::Search => "Search (terminal or chat)",
+            KeyContext::Chat => "Chat",
+            KeyContext::Logs => "Logs (when focused)",
+            KeyContext::Traffic => "ACP traffic (when focused)",
+        }
+    }
+}
+
+/// Everything a key can be bound to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Quit,
+    ToggleHelp,
+    CommandPalette,
+    CommandLine,
+    NewSession,
+    SwitchAgent,
+    SwitchProfile,
+    SwitchTheme,
+    ThemeGallery,
+    NextTab,
+    PrevTab,
+    ToggleTerminal,
+    InterruptTurn,
+    ExportSession,
+    PullRequest,
+    OpenChangedFiles,
+    TmuxSplit,
+    TmuxWindow,
+    PermissionRules,
+    SplitPane,
+    ClosePane,
+    CyclePane,
+    FocusPane,
+    RotatePanes,
+    GrowPane,
+    ShrinkPane,
+    SaveLayout,
+    ToggleLogs,
+    ToggleTraffic,
+    TerminalSearch,
+    TerminalFilter,
+    TerminalScrollUp,
+    TerminalScrollDown,
+    TerminalTaller,
+    TerminalShorter,
+    SearchNext,
+    SearchPrev,
+    SearchClear,
+    ChatStartInput,
+    ChatSearch,
+    ChatScrollUp,
+    ChatScrollDown,
+    ChatScrollTop,
+    ChatScrollBottom,
+    LogsSearch,
+    LogsLevel,
+    LogsClear,
+    LogsScrollUp,
+    LogsScrollDown,
+    TrafficPause,
+    TrafficPretty,
+    TrafficClear,
+    TrafficScrollUp,
+    TrafficScrollDown,
+}
+
+/// (action, config name, context, help text, default keys). Actions with
+/// no default keys are only reachable from the command palette until bound.
+const ACTIONS: &[(Action, &str, KeyContext, &str, &str)] = &[
+    (Action::Quit, "quit", KeyContext::Global, "Quit application", "q"),
+    (Action::ToggleHelp, "toggle_help", KeyContext::Global, "Toggle this help", "?"),
+    (Action::CommandPalette, "command_palette", KeyContext::Global, "Command palette", "Ctrl+p"),
+    (Action::CommandLine, "command_line", KeyContext::Global, "Run an action by name, e.g. :split_pane or :q", ""),
+    (Action::NewSession, "new_session", KeyContext::Global, "New session with default agent", "n"),
+    (Action::SwitchAgent, "switch_agent", KeyContext::Global, "Switch agent", "a"),
+    (Action::SwitchProfile, "switch_profile", KeyContext::Global, "Switch config profile", "p"),
+    (Action::SwitchTheme, "switch_theme", KeyContext::Global, "Switch theme", "T"),
+    (Action::ThemeGallery, "theme_gallery", KeyContext::Global, "Preview and pick a theme or effects preset", ""),
+    (Action::NextTab, "next_tab", KeyContext::Global, "Next tab", "Tab"),
+    (Action::PrevTab, "prev_tab", KeyContext::Global, "Previous tab", "BackTab"),
+    (Action::ToggleTerminal, "toggle_terminal", KeyContext::Global, "Toggle terminal panel", "t"),
+    (Action::InterruptTurn, "interrupt_turn", KeyContext::Global, "Stop the running turn and the command its agent runs", "Ctrl+x"),
+    (Action::ExportSession, "export_session", KeyContext::Global, "Export session transcript", ""),
+    (Action::PullRequest, "pull_request", KeyContext::Global, "Push the session's edits and open a pull request", ""),
+    (Action::OpenChangedFiles, "open_changed_files", KeyContext::Global, "Open the files the last turn changed in the editor", ""),
+    (Action::TmuxSplit, "tmux_split", KeyContext::Global, "Open another RAT in a tmux split", ""),
+    (Action::TmuxWindow, "tmux_window", KeyContext::Global, "Open another RAT in a tmux window", ""),
+    (Action::PermissionRules, "permission_rules", KeyContext::Global, "Show permission rules", ""),
+    (Action::SplitPane, "split_pane", KeyContext::Global, "Split: open a pane beside the chat", "Ctrl+w s"),
+    (Action::ClosePane, "close_pane", KeyContext::Global, "Close focused pane", "Ctrl+w c"),
+    (Action::CyclePane, "cycle_pane", KeyContext::Global, "Change what the focused pane shows", "Ctrl+w m"),
+    (Action::FocusPane, "focus_pane", KeyContext::Global, "Focus next pane", "Ctrl+w w"),
+    (Action::RotatePanes, "rotate_panes", KeyContext::Global, "Stack panes beside/below the chat", "Ctrl+w r"),
+    (Action::GrowPane, "grow_pane", KeyContext::Global, "Grow the focused pane", "Ctrl+Right"),
+    (Action::ShrinkPane, "shrink_pane", KeyContext::Global, "Shrink the focused pane", "Ctrl+Left"),
+    (Action::SaveLayout, "save_layout", KeyContext::Global, "Save pane layout and sizes to config", ""),
+    (Action::ToggleLogs, "toggle_logs", KeyContext::Global, "Toggle log viewer pane", "L"),
+    (Action::ToggleTraffic, "toggle_traffic", KeyContext::Global, "Toggle raw ACP traffic pane", "D"),
+    (Action::TerminalSearch, "terminal_search", KeyContext::Terminal, "Search scrollback", "/"),
+    (Action::TerminalFilter, "terminal_filter", KeyContext::Terminal, "Filter stream (all/stdout/stderr)", "f"),
+    (Action::TerminalScrollUp, "terminal_scroll_up", KeyContext::Terminal, "Scroll output up", "PageUp"),
+    (Action::TerminalScrollDown, "terminal_scroll_down", KeyContext::Terminal, "Scroll output down", "PageDown"),
+    (Action::TerminalTaller, "terminal_taller", KeyContext::Terminal, "Make the terminal panel taller", "Ctrl+Up"),
+    (Action::TerminalShorter, "terminal_shorter", KeyContext::Terminal, "Make the terminal panel shorter", "Ctrl+Down"),
+    (Action::SearchNext, "search_next", KeyContext::Search, "Next match", "n"),
+    (Action::SearchPrev, "search_prev", KeyContext::Search, "Previous match", "N"),
+    (Action::SearchClear, "search_clear", KeyContext::Search, "Clear search", "Esc"),
+    (Action::ChatStartInput, "chat_input", KeyContext::Chat, "Start typing a message", "Enter"),
+    (Action::ChatSearch, "chat_search", KeyContext::Chat, "Search the conversation", "/"),
+    (Action::ChatScrollUp, "chat_scroll_up", KeyContext::Chat, "Scroll up", "Up"),
+    (Action::ChatScrollDown, "chat_scroll_down", KeyContext::Chat, "Scroll down", "Down"),
+    (Action::ChatScrollTop, "chat_scroll_top", KeyContext::Chat, "Jump to oldest message", "g g"),
+    (Action::ChatScrollBottom, "chat_scroll_bottom", KeyContext::Chat, "Jump to latest message", "G"),
+    (Action::LogsSearch, "logs_search", KeyContext::Logs, "Filter log lines", "/"),
+    (Action::LogsLevel, "logs_level", KeyContext::Logs, "Cycle minimum log level", "l"),
+    (Action::LogsClear, "logs_clear", KeyContext::Logs, "Clear log filter", "Esc"),
+    (Action::LogsScrollUp, "logs_scroll_up", KeyContext::Logs, "Scroll logs up", "PageUp"),
+    (Action::LogsScrollDown, "logs_scroll_down", KeyContext::Logs, "Scroll logs down", "PageDown"),
+    (Action::TrafficPause, "traffic_pause", KeyContext::Traffic, "Pause/resume the frame view", "Space"),
+    (Action::TrafficPretty, "traffic_pretty", KeyContext::Traffic, "Pretty-print or compact frames", "e"),
+    (Action::TrafficClear, "traffic_clear", KeyContext::Traffic, "Clear captured frames", "c"),
+    (Action::TrafficScrollUp, "traffic_scroll_up", KeyContext::Traffic, "Scroll frames up", "PageUp"),
+    (Action::TrafficScrollDown, "traffic_scroll_down", KeyContext::Traffic, "Scroll frames down", "PageDown"),
+];
+
+/// Bindings `ui.keymap = "vim"` adds beside the defaults, unless the action
+/// is rebound in `[ui.keybindings]`
+const VIM_KEYS: &[(Action, &str)] = &[
+    (Action::ChatScrollDown, "j"),
+    (Action::ChatScrollUp, "k"),
+    (Action::ChatStartInput, "i"),
+    (Action::CommandLine, ":"),
+];
+
+/// Key scheme picked with `ui.keymap`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KeymapPreset {
+    #[default]
+    Default,
+    /// Normal and insert modes in the input box, `j`/`k` scrolling and a
+    /// `:` command line
+    Vim,
+}
+
+impl KeymapPreset {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "default" => Some(KeymapPreset::Default),
+            "vim" => Some(KeymapPreset::Vim),
+            _ => None,
+        }
+    }
+}
+
+/// Keys handled before the keymap, so they can't be rebound: (keys, help text)
+const FIXED_KEYS: &[(&str, &str)] = &[
+    ("Enter", "Send message (while typing)"),
+    ("Esc", "Cancel input (while typing)"),
+    ("Ctrl+c", "Force quit"),
+    ("Ctrl+z", "Suspend to the shell (Unix)"),
+];
+
+/// One line of the help overlay
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HelpEntry {
+    /// Current binding; empty when the action is only in the palette
+    pub keys: String,
+    /// Config name, empty for fixed keys
+    pub name: &'static str,
+    pub description: &'static str,
+}
+
+impl HelpEntry {
+    /// Whether every word of `query` appears in the keys, name or description
+    pub fn matches(&self, query: &str) -> bool {
+        let haystack = format!("{} {} {}", self.keys, self.name, self.description).to_lowercase();
+        query
+            .to_lowercase()
+            .split_whitespace()
+            .all(|word| haystack.contains(word))
+    }
+}
+
+impl Action {
+    fn entry(self) -> &'static (Action, &'static str, KeyContext, &'static str, &'static str) {
+        ACTIONS
+            .iter()
+            .find(|entry| entry.0 == self)
+            .expect("every action has an ACTIONS entry")
+    }
+
+    /// Name used in `[ui.keybindings]`
+    pub fn name(self) -> &'static str {
+        self.entry().1
+    }
+
+    pub fn context(self) -> KeyContext {
+        self.entry().2
+    }
+
+    pub fn description(self) -> &'static str {
+        self.entry().3
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        ACTIONS.iter().find(|entry| entry.1 == name).map(|entry| entry.0)
+    }
+
+    /// The action a `:` command names: vim's `q`, `w` and `h`, a config
+    /// name such as `split_pane` (with dashes or spaces if preferred), or a
+    /// prefix only one config name starts with
+    pub fn from_command(command: &str) -> Option<Action> {
+        let name = command.trim().to_lowercase().replace(['-', ' '], "_");
+        match name.as_str() {
+            "" => return None,
+            "q" | "qa" | "quit" => return Some(Action::Quit),
+            "w" => return Some(Action::SaveLayout),
+            "h" | "help" => return Some(Action::ToggleHelp),
+            _ => {}
+        }
+        if let Some(action) = Self::from_name(&name) {
+            return Some(action);
+        }
+        let mut candidates = ACTIONS.iter().filter(|entry| entry.1.starts_with(&name));
+        match (candidates.next(), candidates.next()) {
+            (Some(entry), None) => Some(entry.0),
+            _ => None,
+        }
+    }
+
+    /// Actions that still work while the chat input is capturing text
+    pub fn allowed_while_typing(self) -> bool {
+        matches!(self, Action::NextTab | Action::PrevTab | Action::CommandPalette)
+    }
+}
+
+/// A single key press, normalized for matching
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyPress {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyPress {
+    fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        // Shift is already part of the character (`X`, `?`) and terminals
+        // disagree on whether they report it, so only Ctrl/Alt are significant
+        let modifiers = modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT);
+        Self { code, modifiers }
+    }
+
+    /// Parse one key like `q`, `Ctrl+c`, `PageUp` or `Space`
+    pub fn parse(spec: &str) -> Result<Self> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = spec;
+        loop {
+            let lower = rest.to_ascii_lowercase();
+            if rest.len() > 1 && (lower.starts_with("ctrl+") || lower.starts_with("ctrl-")) {
+                modifiers |= KeyModifiers::CONTROL;
+                rest = &rest[5..];
+            } else if rest.len() > 1 && (lower.starts_with("alt+") || lower.starts_with("alt-")) {
+                modifiers |= KeyModifiers::ALT;
+                rest = &rest[4..];
+            } else {
+                break;
+            }
+        }
+
+        let code = match rest {
+            "Tab" => KeyCode::Tab,
+            "BackTab" | "Shift+Tab" => KeyCode::BackTab,
+            "Enter" => KeyCode::Enter,
+            "Esc" => KeyCode::Esc,
+            "Backspace" => KeyCode::Backspace,
+            "Delete" => KeyCode::Delete,
+            "Up" => KeyCode::Up,
+            "Down" => KeyCode::Down,
+            "Left" => KeyCode::Left,
+            "Right" => KeyCode::Right,
+            "Home" => KeyCode::Home,
+            "End" => KeyCode::End,
+            "PageUp" => KeyCode::PageUp,
+            "PageDown" => KeyCode::PageDown,
+            "Space" => KeyCode::Char(' '),
+            f if f.len() > 1 && f.starts_with('F') => {
+                let n: u8 = f[1..]
+                    .parse()
+                    .map_err(|_| anyhow::anyhow!("Unknown key: {}", spec))?;
+                KeyCode::F(n)
+            }
+            c if c.chars().count() == 1 => KeyCode::Char(c.chars().next().unwrap_or_default()),
+            _ => return Err(anyhow::anyhow!("Unknown key: {}", spec)),
+        };
+        Ok(Self::new(code, modifiers))
+    }
+
+    fn label(&self) -> String {
+        let key = match self.code {
+            KeyCode::Char(' ') => "Space".to_string(),
+            KeyCode::Char(c) => c.to_string(),
+            KeyCode::BackTab => "Shift+Tab".to_string(),
+            KeyCode::PageUp => "PgUp".to_string(),
+            KeyCode::PageDown => "PgDn".to_string(),
+            KeyCode::F(n) => format!("F{}", n),
+            other => format!("{:?}", other),
+        };
+        let mut label = String::new();
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            label.push_str("Ctrl+");
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            label.push_str("Alt+");
+        }
+        label.push_str(&key);
+        label
+    }
+}
+
+impl From<KeyEvent> for KeyPress {
+    fn from(key: KeyEvent) -> Self {
+        Self::new(key.code, key.modifiers)
+    }
+}
+
+/// Parse a binding: one key, or a space-separated chord such as `g g`
+pub fn parse_keys(spec: &str) -> Result<Vec<KeyPress>> {
+    let keys = spec
+        .split_whitespace()
+        .map(KeyPress::parse)
+        .collect::<Result<Vec<_>>>()?;
+    if keys.is_empty() {
+        return Err(anyhow::anyhow!("Empty key binding"));
+    }
+    Ok(keys)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyResolution {
+    Action(Action),
+    /// The key started a chord; wait for the next one
+    Pending,
+    Unbound,
+}
+
+/// Key bindings for every [`Action`], built from defaults plus
+/// `[ui.keybindings]`, with chord state.
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: Vec<(Vec<KeyPress>, Action)>,
+    pending: Vec<KeyPress>,
+}
+
+impl Keymap {
+    /// Bindings for `ui.keymap` with `[ui.keybindings]` on top
+    pub fn from_ui_config(config: &UiConfig) -> Result<Self> {
+        let preset = KeymapPreset::parse(&config.keymap).ok_or_else(|| {
+            anyhow::anyhow!("keymap must be 'default' or 'vim', got '{}'", config.keymap)
+        })?;
+        Self::with_preset(&config.keybindings, preset)
+    }
+
+    pub fn with_preset(config: &KeybindingConfig, preset: KeymapPreset) -> Result<Self> {
+        let mut overrides: Vec<(Action, &str)> = vec![
+            (Action::Quit, config.quit.as_str()),
+            (Action::NewSession, config.new_session.as_str()),
+            (Action::SwitchAgent, config.switch_agent.as_str()),
+            (Action::ToggleTerminal, config.toggle_terminal.as_str()),
+            (Action::NextTab, config.next_tab.as_str()),
+            (Action::PrevTab, config.prev_tab.as_str()),
+        ];
+        for (name, keys) in &config.custom_bindings {
+            let action = Action::from_name(name)
+                .ok_or_else(|| anyhow::anyhow!("Unknown keybinding action: {}", name))?;
+            overrides.push((action, keys.as_str()));
+        }
+
+        let mut bindings = Vec::new();
+        for (action, _, _, _, default_keys) in ACTIONS {
+            let spec = overrides
+                .iter()
+                .rev()
+                .find(|(a, _)| a == action)
+                .map(|(_, keys)| *keys)
+                .unwrap_or(default_keys);
+            // An empty binding leaves the action to the command palette
+            if spec.trim().is_empty() {
+                continue;
+            }
+            let keys = parse_keys(spec)
+                .map_err(|e| anyhow::anyhow!("Invalid binding for {}: {}", action.name(), e))?;
+            bindings.push((keys, *action));
+        }
+        if preset == KeymapPreset::Vim {
+            for (action, spec) in VIM_KEYS {
+                if !config.custom_bindings.contains_key(action.name()) {
+                    bindings.push((parse_keys(spec)?, *action));
+                }
+            }
+        }
+        Ok(Self {
+            bindings,
+            pending: Vec::new(),
+        })
+    }
+
+    /// Resolve a key press against the active contexts, highest priority first
+    pub fn resolve(&mut self, key: KeyEvent, contexts: &[KeyContext]) -> KeyResolution {
+        let mut sequence = self.pending.clone();
+        sequence.push(KeyPress::from(key));
+
+        for context in contexts {
+            let candidates = self
+                .bindings
+                .iter()
+                .filter(|(_, action)| action.context() == *context);
+            let mut is_prefix = false;
+            for (keys, action) in candidates {
+                if *keys == sequence {
+                    self.pending.clear();
+                    return KeyResolution::Action(*action);
+                }
+                is_prefix |= keys.len() > sequence.len() && keys.starts_with(&sequence);
+            }
+            if is_prefix {
+                self.pending = sequence;
+                return KeyResolution::Pending;
+            }
+        }
+
+        // A broken chord: start over with just this key
+        if !self.pending.is_empty() {
+            self.pending.clear();
+            return self.resolve(key, contexts);
+        }
+        KeyResolution::Unbound
+    }
+
+    /// Single-key lookup without chord state
+    pub fn lookup(&self, key: KeyEvent, contexts: &[KeyContext]) -> Option<Action> {
+        let press = KeyPress::from(key);
+        contexts.iter().find_map(|context| {
+            self.bindings
+                .iter()
+                .find(|(keys, action)| action.context() == *context && keys.as_slice() == [press])
+                .map(|(_, action)| *action)
+        })
+    }
+
+    /// Display label for an action's bindings, e.g. `g g` or `Enter, i`
+    pub fn label(&self, action: Action) -> String {
+        self.bindings
+            .iter()
+            .filter(|(_, a)| *a == action)
+            .map(|(keys, _)| keys.iter().map(KeyPress::label).collect::<Vec<_>>().join(" "))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Command palette entries for the active contexts: (action, keys),
+    /// in registry order. `keys` is empty for unbound actions.
+    pub fn palette_entries(&self, contexts: &[KeyContext]) -> Vec<(Action, String)> {
+        ACTIONS
+            .iter()
+            .map(|entry| entry.0)
+            .filter(|action| *action != Action::CommandPalette)
+            .filter(|action| contexts.contains(&action.context()))
+            .map(|action| (action, self.label(action)))
+            .collect()
+    }
+
+    /// Help overlay sections: every registered action grouped by context,
+    /// with its current binding, then the keys that can't be rebound
+    pub fn help_sections(&self) -> Vec<(&'static str, Vec<HelpEntry>)> {
+        let mut sections: Vec<(&'static str, Vec<HelpEntry>)> = KeyContext::ALL
+            .iter()
+            .map(|context| {
+                let entries = ACTIONS
+                    .iter()
+                    .filter(|entry| entry.2 == *context)
+                    .map(|entry| HelpEntry {
+                        keys: self.label(entry.0),
+                        name: entry.1,
+                        description: entry.3,
+                    })
+                    .collect();
+                (context.title(), entries)
+            })
+            .collect();
+        sections.push((
+            "Fixed",
+            FIXED_KEYS
+                .iter()
+                .map(|(keys, description)| HelpEntry {
+                    keys: keys.to_string(),
+                    name: "",
+                    description,
+                })
+                .collect(),
+        ));
+        sections
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(c: char) -> KeyEvent {
+        KeyEvent::from(KeyCode::Char(c))
+    }
+
+    #[test]
+    fn config_overrides_and_chords() {
+        let mut config = KeybindingConfig {
+            quit: "Ctrl+q".to_string(),
+            ..Default::default()
+        };
+        config
+            .custom_bindings
+            .insert("chat_scroll_top".to_string(), "Space g".to_string());
+        let mut keymap = Keymap::with_preset(&config, KeymapPreset::Default).unwrap();
+        let contexts = [KeyContext::Global, KeyContext::Chat];
+
+        assert_eq!(keymap.resolve(key('q'), &contexts), KeyResolution::Unbound);
+        let ctrl_q = KeyEvent::new(KeyCode::Char('q'), KeyModifiers::CONTROL);
+        assert_eq!(keymap.resolve(ctrl_q, &contexts), KeyResolution::Action(Action::Quit));
+
+        assert_eq!(keymap.resolve(key(' '), &contexts), KeyResolution::Pending);
+        assert_eq!(
+            keymap.resolve(key('g'), &contexts),
+            KeyResolution::Action(Action::ChatScrollTop)
+        );
+        // A broken chord falls back to the new key on its own
+        assert_eq!(keymap.resolve(key(' '), &contexts), KeyResolution::Pending);
+        assert_eq!(
+            keymap.resolve(key('n'), &contexts),
+            KeyResolution::Action(Action::NewSession)
+        );
+        assert_eq!(keymap.label(Action::ChatScrollTop), "Space g");
+    }
+
+    #[test]
+    fn context_priority_and_bad_config() {
+        let mut keymap =
+            Keymap::with_preset(&KeybindingConfig::default(), KeymapPreset::Default).unwrap();
+        let contexts = [KeyContext::Search, KeyContext::Global];
+        assert_eq!(
+            keymap.resolve(key('n'), &contexts),
+            KeyResolution::Action(Action::SearchNext)
+        );
+
+        let mut config = KeybindingConfig::default();
+        config
+            .custom_bindings
+            .insert("launch_rockets".to_string(), "r".to_string());
+        assert!(Keymap::with_preset(&config, KeymapPreset::Default).is_err());
+    }
+
+    #[test]
+    fn palette_lists_unbound_actions_for_active_contexts() {
+        let mut config = KeybindingConfig::default();
+        config
+            .custom_bindings
+            .insert("toggle_help".to_string(), "".to_string());
+        let keymap = Keymap::with_preset(&config, KeymapPreset::Default).unwrap();
+
+        let entries = keymap.palette_entries(&[KeyContext::Global]);
+        assert!(entries.contains(&(Action::ExportSession, String::new())));
+        assert!(entries.contains(&(Action::ToggleHelp, String::new())));
+        assert!(entries.contains(&(Action::NewSession, "n".to_string())));
+        assert!(!entries.iter().any(|(action, _)| *action == Action::CommandPalette));
+        assert!(!entries.iter().any(|(action, _)| *action == Action::TerminalFilter));
+        assert_eq!(keymap.label(Action::CommandPalette), "Ctrl+p");
+    }
+
+    #[test]
+    fn vim_preset_adds_keys_beside_the_defaults() {
+        let mut config = KeybindingConfig::default();
+        config
+            .custom_bindings
+            .insert("chat_scroll_up".to_string(), "Ctrl+y".to_string());
+        let mut keymap = Keymap::with_preset(&config, KeymapPreset::Vim).unwrap();
+        let contexts = [KeyContext::Global, KeyContext::Chat];
+
+        assert_eq!(
+            keymap.resolve(key('j'), &contexts),
+            KeyResolution::Action(Action::ChatScrollDown)
+        );
+        assert_eq!(
+            keymap.resolve(KeyEvent::from(KeyCode::Down), &contexts),
+            KeyResolution::Action(Action::ChatScrollDown)
+        );
+        assert_eq!(keymap.label(Action::ChatStartInput), "Enter, i");
+        assert_eq!(keymap.label(Action::CommandLine), ":");
+        // A rebound action keeps only its configured keys
+        assert_eq!(keymap.resolve(key('k'), &contexts), KeyResolution::Unbound);
+
+        let default = Keymap::with_preset(&config, KeymapPreset::Default).unwrap();
+        assert_eq!(default.label(Action::CommandLine), "");
+    }
+
+    #[test]
+    fn commands_name_actions() {
+        assert_eq!(Action::from_command("q"), Some(Action::Quit));
+        assert_eq!(Action::from_command(" split_pane "), Some(Action::SplitPane));
+        assert_eq!(Action::from_command("toggle-logs"), Some(Action::ToggleLogs));
+        assert_eq!(Action::from_command("rotate"), Some(Action::RotatePanes));
+        // Ambiguous prefix
+        assert_eq!(Action::from_command("toggle"), None);
+        assert_eq!(Action::from_command("launch_rockets"), None);
+    }
+}
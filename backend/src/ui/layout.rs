@@ -0,0 +1,442 @@
+//! Split-pane layout of the main area: the active tab's chat plus up to
+//! `MAX_PANES` side panes showing the plan, terminal, edit review, another
+//! session's chat, RAT's log or the raw ACP traffic.
+
+use anyhow::Result;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use std::ops::RangeInclusive;
+
+use crate::config::LayoutConfig;
+
+/// Side panes next to the main chat
+pub const MAX_PANES: usize = 3;
+
+/// Share of the main area, in percent, the chat may keep when split
+pub const MAIN_PERCENT: RangeInclusive<u16> = 20..=80;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaneKind {
+    Plan,
+    Terminal,
+    Diff,
+    Chat,
+    Logs,
+    Traffic,
+}
+
+impl PaneKind {
+    /// Order used when splitting and cycling
+    pub const ALL: [PaneKind; 6] = [
+        PaneKind::Plan,
+        PaneKind::Terminal,
+        PaneKind::Diff,
+        PaneKind::Chat,
+        PaneKind::Logs,
+        PaneKind::Traffic,
+    ];
+
+    /// Name used in `[ui.layout] panes`
+    pub fn name(self) -> &'static str {
+        match self {
+            PaneKind::Plan => "plan",
+            PaneKind::Terminal => "terminal",
+            PaneKind::Diff => "diff",
+            PaneKind::Chat => "chat",
+            PaneKind::Logs => "logs",
+            PaneKind::Traffic => "traffic",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.name().eq_ignore_ascii_case(name.trim()))
+    }
+
+    fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|k| *k == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pane {
+    pub kind: PaneKind,
+    /// Tab shown by a `Chat` pane; ignored by the others
+    pub tab: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitDirection {
+    /// Panes sit beside the chat
+    Horizontal,
+    /// Panes sit below the chat
+    Vertical,
+}
+
+impl SplitDirection {
+    pub fn name(self) -> &'static str {
+        match self {
+            SplitDirection::Horizontal => "horizontal",
+            SplitDirection::Vertical => "vertical",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "horizontal" => Some(SplitDirection::Horizontal),
+            "vertical" => Some(SplitDirection::Vertical),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaneLayout {
+    panes: Vec<Pane>,
+    /// 0 is the main chat, 1.. the side panes
+    focused: usize,
+    direction: SplitDirection,
+    main_percent: u16,
+}
+
+impl Default for PaneLayout {
+    fn default() -> Self {
+        Self {
+            panes: Vec::new(),
+            focused: 0,
+            direction: SplitDirection::Horizontal,
+            main_percent: 60,
+        }
+    }
+}
+
+impl PaneLayout {
+    pub fn from_config(config: &LayoutConfig) -> Result<Self> {
+        if config.panes.len() > MAX_PANES {
+            return Err(anyhow::anyhow!("at most {} panes are supported", MAX_PANES));
+        }
+        let panes = config
+            .panes
+            .iter()
+            .map(|name| {
+                PaneKind::parse(name)
+                    .map(|kind| Pane { kind, tab: 0 })
+                    .ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "unknown pane '{}' (expected plan, terminal, diff, chat, logs or traffic)",
+                            name
+                        )
+                    })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let direction = SplitDirection::parse(&config.split).ok_or_else(|| {
+            anyhow::anyhow!(
+                "split must be 'horizontal' or 'vertical', got '{}'",
+                config.split
+            )
+        })?;
+        if !MAIN_PERCENT.contains(&config.main_percent) {
+            return Err(anyhow::anyhow!(
+                "main_percent must be between {} and {}",
+                MAIN_PERCENT.start(),
+                MAIN_PERCENT.end()
+            ));
+        }
+        Ok(Self {
+            panes,
+            focused: 0,
+            direction,
+            main_percent: config.main_percent,
+        })
+    }
+
+    pub fn panes(&self) -> &[Pane] {
+        &self.panes
+    }
+
+    pub fn direction(&self) -> SplitDirection {
+        self.direction
+    }
+
+    pub fn main_percent(&self) -> u16 {
+        self.main_percent
+    }
+
+    pub fn has(&self, kind: PaneKind) -> bool {
+        self.panes.iter().any(|p| p.kind == kind)
+    }
+
+    /// Index of the focused pane, 0 being the main chat
+    pub fn focused(&self) -> usize {
+        self.focused
+    }
+
+    /// The focused side pane, if focus isn't on the main chat
+    pub fn focused_pane(&self) -> Option<Pane> {
+        self.focused
+            .checked_sub(1)
+            .and_then(|i| self.panes.get(i))
+            .copied()
+    }
+
+    /// Pane names as written to `[ui.layout] panes`
+    pub fn pane_names(&self) -> Vec<String> {
+        self.panes
+            .iter()
+            .map(|p| p.kind.name().to_string())
+            .collect()
+    }
+
+    /// Open a pane with the first kind not already shown (a second chat once
+    /// all are) and focus it. Returns the new pane's kind, or `None` when full.
+    pub fn split(&mut self) -> Option<PaneKind> {
+        if self.panes.len() >= MAX_PANES {
+            return None;
+        }
+        let kind = PaneKind::ALL
+            .into_iter()
+            .find(|kind| !self.has(*kind))
+            .unwrap_or(PaneKind::Chat);
+        self.panes.push(Pane { kind, tab: 0 });
+        self.focused = self.panes.len();
+        Some(kind)
+    }
+
+    /// Open a pane of `kind` without moving focus; false when full
+    pub fn open(&mut self, kind: PaneKind) -> bool {
+        if self.panes.len() >= MAX_PANES {
+            return false;
+        }
+        self.panes.push(Pane { kind, tab: 0 });
+        true
+    }
+
+    /// Close every pane of `kind`, keeping focus on the same pane if it stays
+    pub fn close(&mut self, kind: PaneKind) {
+        let focused = self.focused_pane();
+        self.panes.retain(|p| p.kind != kind);
+        self.focused = match focused {
+            Some(pane) if pane.kind != kind => self
+                .panes
+                .iter()
+                .position(|p| *p == pane)
+                .map_or(0, |i| i + 1),
+            _ => 0,
+        };
+    }
+
+    /// Close the focused side pane; the main chat can't be closed
+    pub fn close_focused(&mut self) -> Option<PaneKind> {
+        let index = self.focused.checked_sub(1)?;
+        let pane = self.panes.remove(index);
+        self.focused = index.min(self.panes.len());
+        Some(pane.kind)
+    }
+
+    /// Change what the focused pane shows. A chat pane steps through the
+    /// session tabs before moving on to the next kind. Returns the kinds
+    /// before and after.
+    pub fn cycle_focused(&mut self, tab_count: usize) -> Option<(PaneKind, PaneKind)> {
+        let index = self.focused.checked_sub(1)?;
+        let pane = self.panes.get_mut(index)?;
+        let before = pane.kind;
+        if pane.kind == PaneKind::Chat && pane.tab + 1 < tab_count {
+            pane.tab += 1;
+        } else {
+            pane.kind = pane.kind.next();
+            pane.tab = 0;
+        }
+        Some((before, pane.kind))
+    }
+
+    /// Move focus to the next pane, wrapping back to the main chat
+    pub fn focus_next(&mut self) {
+        self.focused = (self.focused + 1) % (self.panes.len() + 1);
+    }
+
+    /// Switch between side-by-side and stacked panes
+    pub fn rotate(&mut self) {
+        self.direction = match self.direction {
+            SplitDirection::Horizontal => SplitDirection::Vertical,
+            SplitDirection::Vertical => SplitDirection::Horizontal,
+        };
+    }
+
+    /// Grow the focused pane by `step` percent of the main area (shrink when
+    /// negative) by moving the split. Side panes grow and shrink together.
+    /// Returns false when there is nothing to resize or it is at its limit.
+    pub fn resize_focused(&mut self, step: i16) -> bool {
+        if self.panes.is_empty() {
+            return false;
+        }
+        let step = if self.focused == 0 { step } else { -step };
+        let resized = (self.main_percent as i16 + step)
+            .clamp(*MAIN_PERCENT.start() as i16, *MAIN_PERCENT.end() as i16)
+            as u16;
+        let changed = resized != self.main_percent;
+        self.main_percent = resized;
+        changed
+    }
+
+    /// Split `area` into the main chat and one rect per side pane
+    pub fn areas(&self, area: Rect) -> (Rect, Vec<Rect>) {
+        self.areas_in(area, self.direction)
+    }
+
+    /// As `areas`, but with the panes below the chat whatever the direction
+    pub fn stacked_areas(&self, area: Rect) -> (Rect, Vec<Rect>) {
+        self.areas_in(area, SplitDirection::Vertical)
+    }
+
+    fn areas_in(&self, area: Rect, direction: SplitDirection) -> (Rect, Vec<Rect>) {
+        if self.panes.is_empty() {
+            return (area, Vec::new());
+        }
+        let direction = match direction {
+            SplitDirection::Horizontal => Direction::Horizontal,
+            SplitDirection::Vertical => Direction::Vertical,
+        };
+        let outer = Layout::default()
+            .direction(direction)
+            .constraints([
+                Constraint::Percentage(self.main_percent),
+                Constraint::Percentage(100 - self.main_percent),
+            ])
+            .split(area);
+        // Side panes share the rest across the split, e.g. stacked beside the chat
+        let cross = match direction {
+            Direction::Horizontal => Direction::Vertical,
+            Direction::Vertical => Direction::Horizontal,
+        };
+        let count = self.panes.len() as u32;
+        let panes = Layout::default()
+            .direction(cross)
+            .constraints(vec![Constraint::Ratio(1, count); self.panes.len()])
+            .split(outer[1]);
+        (outer[0], panes.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(panes: &[&str]) -> LayoutConfig {
+        LayoutConfig {
+            panes: panes.iter().map(|p| p.to_string()).collect(),
+            ..LayoutConfig::default()
+        }
+    }
+
+    #[test]
+    fn parses_config_and_rejects_unknown_panes() {
+        let layout = PaneLayout::from_config(&config(&["plan", "Terminal"])).unwrap();
+        assert_eq!(layout.pane_names(), ["plan", "terminal"]);
+        assert_eq!(layout.direction(), SplitDirection::Horizontal);
+
+        assert!(PaneLayout::from_config(&config(&["sidebar"])).is_err());
+        assert!(PaneLayout::from_config(&config(&["plan", "plan", "plan", "plan"])).is_err());
+        let mut bad_split = config(&[]);
+        bad_split.split = "diagonal".to_string();
+        assert!(PaneLayout::from_config(&bad_split).is_err());
+    }
+
+    #[test]
+    fn split_close_and_cycle() {
+        let mut layout = PaneLayout::default();
+        assert_eq!(layout.close_focused(), None);
+        assert_eq!(layout.split(), Some(PaneKind::Plan));
+        assert_eq!(layout.split(), Some(PaneKind::Terminal));
+        assert_eq!(layout.split(), Some(PaneKind::Diff));
+        assert_eq!(layout.split(), None);
+        assert_eq!(layout.focused(), 3);
+
+        // Diff -> Chat, then through the other tabs, then on to Logs and Traffic
+        assert_eq!(
+            layout.cycle_focused(2),
+            Some((PaneKind::Diff, PaneKind::Chat))
+        );
+        assert_eq!(
+            layout.cycle_focused(2),
+            Some((PaneKind::Chat, PaneKind::Chat))
+        );
+        assert_eq!(layout.focused_pane().map(|p| p.tab), Some(1));
+        assert_eq!(
+            layout.cycle_focused(2),
+            Some((PaneKind::Chat, PaneKind::Logs))
+        );
+        assert_eq!(
+            layout.cycle_focused(2),
+            Some((PaneKind::Logs, PaneKind::Traffic))
+        );
+        assert_eq!(
+            layout.cycle_focused(2),
+            Some((PaneKind::Traffic, PaneKind::Plan))
+        );
+
+        layout.focus_next();
+        assert_eq!(layout.focused(), 0);
+        assert_eq!(layout.cycle_focused(2), None);
+        layout.focus_next();
+        layout.focus_next();
+        assert_eq!(layout.close_focused(), Some(PaneKind::Terminal));
+        assert_eq!(layout.pane_names(), ["plan", "plan"]);
+        assert_eq!(layout.focused(), 1);
+    }
+
+    #[test]
+    fn open_and_close_by_kind_keep_focus() {
+        let mut layout = PaneLayout::default();
+        layout.split();
+        assert!(layout.open(PaneKind::Logs));
+        assert_eq!(layout.focused_pane().map(|p| p.kind), Some(PaneKind::Plan));
+
+        layout.close(PaneKind::Logs);
+        assert_eq!(layout.focused_pane().map(|p| p.kind), Some(PaneKind::Plan));
+        layout.close(PaneKind::Plan);
+        assert_eq!(layout.focused(), 0);
+        assert!(layout.panes().is_empty());
+    }
+
+    #[test]
+    fn areas_split_the_main_area() {
+        let mut layout = PaneLayout::default();
+        let area = Rect::new(0, 0, 100, 40);
+        assert_eq!(layout.areas(area), (area, Vec::new()));
+
+        layout.split();
+        layout.split();
+        let (main, panes) = layout.areas(area);
+        assert_eq!(main.width, 60);
+        assert_eq!(panes.len(), 2);
+        assert!(panes.iter().all(|p| p.x == 60 && p.height == 20));
+
+        layout.rotate();
+        let (main, panes) = layout.areas(area);
+        assert_eq!((main.width, main.height), (100, 24));
+        assert!(panes.iter().all(|p| p.y == 24 && p.width == 50));
+
+        layout.rotate();
+        assert_eq!(layout.stacked_areas(area), (main, panes));
+    }
+
+    #[test]
+    fn resizing_moves_the_split_toward_the_unfocused_side() {
+        let mut layout = PaneLayout::default();
+        assert!(!layout.resize_focused(5));
+
+        layout.split();
+        // Focus is on the new side pane: growing it shrinks the chat
+        assert!(layout.resize_focused(5));
+        assert_eq!(layout.main_percent(), 55);
+        layout.focus_next();
+        assert!(layout.resize_focused(10));
+        assert_eq!(layout.main_percent(), 65);
+        for _ in 0..5 {
+            layout.resize_focused(5);
+        }
+        assert_eq!(layout.main_percent(), 80);
+        assert!(!layout.resize_focused(5));
+    }
+}
@@ -0,0 +1,405 @@
+//! Tail of RAT's own log file, so agent problems can be debugged without
+//! leaving the TUI for `tail -f`. Shown as the `logs` pane.
+
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Paragraph},
+};
+use std::collections::VecDeque;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::PathBuf;
+
+use crate::ui::keymap::Action;
+
+/// How much of an existing log is read when the viewer first opens it
+const TAIL_BYTES: u64 = 256 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    pub fn name(self) -> &'static str {
+        match self {
+            LogLevel::Error => "error",
+            LogLevel::Warn => "warn",
+            LogLevel::Info => "info",
+            LogLevel::Debug => "debug",
+            LogLevel::Trace => "trace",
+        }
+    }
+
+    fn parse(word: &str) -> Option<Self> {
+        match word {
+            "ERROR" => Some(LogLevel::Error),
+            "WARN" => Some(LogLevel::Warn),
+            "INFO" => Some(LogLevel::Info),
+            "DEBUG" => Some(LogLevel::Debug),
+            "TRACE" => Some(LogLevel::Trace),
+            _ => None,
+        }
+    }
+
+    /// Level of a `text` (`<time> LEVEL target: ...`) or `json` log line
+    fn of_line(line: &str) -> Option<Self> {
+        if line.starts_with('{') {
+            let rest = &line[line.find("\"level\":\"")? + 9..];
+            return Self::parse(&rest[..rest.find('"')?]);
+        }
+        Self::parse(line.split_whitespace().nth(1)?)
+    }
+
+    /// Next filter, from showing everything down to errors only
+    fn stricter(self) -> Self {
+        match self {
+            LogLevel::Trace => LogLevel::Debug,
+            LogLevel::Debug => LogLevel::Info,
+            LogLevel::Info => LogLevel::Warn,
+            LogLevel::Warn => LogLevel::Error,
+            LogLevel::Error => LogLevel::Trace,
+        }
+    }
+
+    fn color(self) -> Color {
+        match self {
+            LogLevel::Error => Color::Red,
+            LogLevel::Warn => Color::Yellow,
+            LogLevel::Info => Color::Green,
+            LogLevel::Debug => Color::Cyan,
+            LogLevel::Trace => Color::DarkGray,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct LogLine {
+    /// Continuation lines (multi-line messages) take the level of the
+    /// line they continue
+    level: LogLevel,
+    text: String,
+}
+
+#[derive(Debug)]
+pub struct LogViewer {
+    path: Option<PathBuf>,
+    /// Bytes of the file read so far
+    offset: u64,
+    /// Trailing text not yet ended by a newline
+    partial: String,
+    lines: VecDeque<LogLine>,
+    max_lines: usize,
+    /// Most verbose level shown
+    level: LogLevel,
+    /// Case-insensitive filter on the line text
+    search: Option<String>,
+    editing: bool,
+    /// Lines scrolled up from the newest
+    scroll_offset: usize,
+}
+
+impl LogViewer {
+    pub fn new(max_lines: usize) -> Self {
+        Self {
+            path: None,
+            offset: 0,
+            partial: String::new(),
+            lines: VecDeque::new(),
+            max_lines,
+            level: LogLevel::Trace,
+            search: None,
+            editing: false,
+            scroll_offset: 0,
+        }
+    }
+
+    /// Follow `path` from now on, starting over with its tail
+    pub fn set_path(&mut self, path: PathBuf) {
+        if self.path.as_ref() == Some(&path) {
+            return;
+        }
+        self.path = Some(path);
+        self.offset = 0;
+        self.partial.clear();
+        self.lines.clear();
+        self.scroll_offset = 0;
+    }
+
+    /// Read whatever was appended since the last call; returns whether any
+    /// lines arrived. A file that shrank was rotated and is read from the top.
+    pub fn tick(&mut self) -> Result<bool> {
+        let Some(path) = &self.path else {
+            return Ok(false);
+        };
+        let Ok(mut file) = std::fs::File::open(path) else {
+            return Ok(false);
+        };
+        let len = file.metadata()?.len();
+        if len < self.offset {
+            self.offset = 0;
+            self.partial.clear();
+        }
+        if len == self.offset {
+            return Ok(false);
+        }
+
+        // On first read, skip to the tail and drop the line cut in half
+        let skip_partial = self.offset == 0 && len > TAIL_BYTES;
+        if skip_partial {
+            self.offset = len - TAIL_BYTES;
+        }
+        file.seek(SeekFrom::Start(self.offset))?;
+        let mut bytes = Vec::new();
+        file.take(len - self.offset).read_to_end(&mut bytes)?;
+        self.offset += bytes.len() as u64;
+
+        let mut text = String::from_utf8_lossy(&bytes).into_owned();
+        if skip_partial {
+            text = text
+                .split_once('\n')
+                .map(|(_, rest)| rest.to_string())
+                .unwrap_or_default();
+        }
+        self.push_text(&text);
+        Ok(true)
+    }
+
+    fn push_text(&mut self, text: &str) {
+        let mut buffer = std::mem::take(&mut self.partial);
+        buffer.push_str(text);
+        let complete = buffer.ends_with('\n');
+        let mut lines: Vec<&str> = buffer.lines().collect();
+        if !complete {
+            self.partial = lines.pop().unwrap_or_default().to_string();
+        }
+        for line in lines.into_iter().filter(|l| !l.trim().is_empty()) {
+            let level = LogLevel::of_line(line)
+                .or_else(|| self.lines.back().map(|l| l.level))
+                .unwrap_or(LogLevel::Info);
+            self.lines.push_back(LogLine {
+                level,
+                text: line.to_string(),
+            });
+            // Keep the view where it is while scrolled up
+            if self.scroll_offset > 0 && self.shows(self.lines.back()) {
+                self.scroll_offset += 1;
+            }
+        }
+        while self.lines.len() > self.max_lines {
+            self.lines.pop_front();
+        }
+    }
+
+    fn shows(&self, line: Option<&LogLine>) -> bool {
+        let Some(line) = line else {
+            return false;
+        };
+        line.level <= self.level
+            && self.search.as_ref().is_none_or(|query| {
+                query.is_empty() || line.text.to_lowercase().contains(&query.to_lowercase())
+            })
+    }
+
+    fn visible_lines(&self) -> Vec<&LogLine> {
+        self.lines.iter().filter(|l| self.shows(Some(l))).collect()
+    }
+
+    /// True while the search prompt is capturing keystrokes
+    pub fn is_searching(&self) -> bool {
+        self.editing
+    }
+
+    /// Keys typed into the search prompt. Returns true if the key was consumed.
+    pub fn handle_key_event(&mut self, key: KeyEvent) -> bool {
+        if !self.editing {
+            return false;
+        }
+        match key.code {
+            KeyCode::Enter => {
+                self.editing = false;
+                if self.search.as_ref().is_some_and(|q| q.is_empty()) {
+                    self.search = None;
+                }
+            }
+            KeyCode::Esc => {
+                self.editing = false;
+                self.search = None;
+            }
+            KeyCode::Backspace => {
+                if let Some(query) = self.search.as_mut() {
+                    query.pop();
+                }
+            }
+            KeyCode::Char(c) => self.search.get_or_insert_with(String::new).push(c),
+            _ => {}
+        }
+        self.scroll_offset = 0;
+        true
+    }
+
+    /// Run a logs action. Returns true if it applied here.
+    pub fn handle_action(&mut self, action: Action) -> bool {
+        match action {
+            Action::LogsSearch => {
+                self.search = Some(String::new());
+                self.editing = true;
+            }
+            Action::LogsLevel => self.level = self.level.stricter(),
+            Action::LogsClear => self.search = None,
+            Action::LogsScrollUp => {
+                let max = self.visible_lines().len().saturating_sub(1);
+                self.scroll_offset = (self.scroll_offset + 10).min(max);
+                return true;
+            }
+            Action::LogsScrollDown => {
+                self.scroll_offset = self.scroll_offset.saturating_sub(10);
+                return true;
+            }
+            _ => return false,
+        }
+        self.scroll_offset = 0;
+        true
+    }
+
+    pub fn render(&self, frame: &mut Frame, area: Rect) {
+        let mut title = format!("Logs [{}+]", self.level.name());
+        if let Some(query) = &self.search {
+            title.push_str(&format!(" /{}", query));
+            if self.editing {
+                title.push('▏');
+            }
+        }
+        let block = Block::default().title(title).borders(Borders::ALL);
+
+        let visible = self.visible_lines();
+        if visible.is_empty() {
+            let note = match &self.path {
+                None => "Logging to a file is off".to_string(),
+                Some(path) if self.lines.is_empty() => {
+                    format!("Nothing logged yet in {}", path.display())
+                }
+                Some(_) => "No lines match".to_string(),
+            };
+            frame.render_widget(Paragraph::new(note).dark_gray().block(block), area);
+            return;
+        }
+
+        let height = area.height.saturating_sub(2) as usize;
+        let end = visible.len().saturating_sub(self.scroll_offset);
+        let start = end.saturating_sub(height);
+        let query = self.search.as_deref().filter(|q| !q.is_empty());
+        let lines: Vec<Line> = visible[start..end]
+            .iter()
+            .map(|line| highlight(&line.text, query, line.level.color()))
+            .collect();
+        frame.render_widget(Paragraph::new(lines).block(block), area);
+    }
+}
+
+/// A log line in its level's color, with search matches reversed
+fn highlight(text: &str, query: Option<&str>, color: Color) -> Line<'static> {
+    let style = Style::default().fg(color);
+    let Some(query) = query else {
+        return Line::from(Span::styled(text.to_string(), style));
+    };
+    // Lowercasing can change byte lengths outside ASCII; don't highlight then
+    let lower = text.to_lowercase();
+    let query = query.to_lowercase();
+    if lower.len() != text.len() {
+        return Line::from(Span::styled(text.to_string(), style));
+    }
+    let mut spans = Vec::new();
+    let mut rest = 0;
+    for (start, found) in lower.match_indices(&query) {
+        spans.push(Span::styled(text[rest..start].to_string(), style));
+        spans.push(Span::styled(
+            text[start..start + found.len()].to_string(),
+            style.add_modifier(Modifier::REVERSED),
+        ));
+        rest = start + found.len();
+    }
+    spans.push(Span::styled(text[rest..].to_string(), style));
+    Line::from(spans)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_text_and_json_levels() {
+        assert_eq!(
+            LogLevel::of_line("2025-01-02T03:04:05.000001Z  WARN rat::app: src/app.rs:10: slow"),
+            Some(LogLevel::Warn)
+        );
+        assert_eq!(
+            LogLevel::of_line(r#"{"timestamp":"2025-01-02","level":"DEBUG","fields":{}}"#),
+            Some(LogLevel::Debug)
+        );
+        assert_eq!(LogLevel::of_line("   at src/main.rs"), None);
+    }
+
+    #[test]
+    fn filters_by_level_and_search_across_partial_writes() {
+        let mut viewer = LogViewer::new(100);
+        viewer.push_text("t  INFO rat: agent started\nt ERROR rat: agent crashed\n  caused by: EOF\nt DEBUG rat: po");
+        viewer.push_text("lling\n");
+        assert_eq!(viewer.visible_lines().len(), 4);
+
+        viewer.handle_action(Action::LogsLevel);
+        viewer.handle_action(Action::LogsLevel);
+        let texts: Vec<&str> = viewer
+            .visible_lines()
+            .iter()
+            .map(|l| l.text.as_str())
+            .collect();
+        assert_eq!(
+            texts,
+            [
+                "t  INFO rat: agent started",
+                "t ERROR rat: agent crashed",
+                "  caused by: EOF"
+            ]
+        );
+
+        viewer.handle_action(Action::LogsSearch);
+        for c in "CRASH".chars() {
+            viewer.handle_key_event(KeyEvent::from(KeyCode::Char(c)));
+        }
+        viewer.handle_key_event(KeyEvent::from(KeyCode::Enter));
+        assert!(!viewer.is_searching());
+        assert_eq!(viewer.visible_lines().len(), 1);
+
+        viewer.handle_action(Action::LogsClear);
+        assert_eq!(viewer.visible_lines().len(), 3);
+    }
+
+    #[test]
+    fn tails_a_growing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rat.log");
+        let mut viewer = LogViewer::new(100);
+        viewer.set_path(path.clone());
+        assert!(!viewer.tick().unwrap());
+
+        std::fs::write(&path, "t  INFO rat: one\n").unwrap();
+        assert!(viewer.tick().unwrap());
+        assert!(!viewer.tick().unwrap());
+
+        // Rotated: the new file is shorter and read from the start
+        std::fs::write(&path, "t  WARN r\n").unwrap();
+        assert!(viewer.tick().unwrap());
+        let texts: Vec<&str> = viewer
+            .visible_lines()
+            .iter()
+            .map(|l| l.text.as_str())
+            .collect();
+        assert_eq!(texts, ["t  INFO rat: one", "t  WARN r"]);
+    }
+}
@@ -1,10 +1,19 @@
 pub mod app;
+pub mod ascii;
 pub mod chat;
+pub mod color;
 pub mod components;
 pub mod diff;
+pub mod frame_budget;
+pub mod fuzzy;
+pub mod keymap;
+pub mod layout;
+pub mod log_viewer;
+pub mod notifications;
 pub mod plan;
 pub mod statusbar;
 pub mod terminal;
+pub mod traffic;
 
 pub use app::TuiManager;
 pub use plan::PlanView;
@@ -0,0 +1,107 @@
+//! Desktop notifications for events that happen while the terminal is in the
+//! background. Terminals that don't report focus changes count as focused,
+//! so they never get one. The terminal bell, when enabled, rings either way.
+
+use crate::config::NotificationsConfig;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DesktopEvent {
+    TurnComplete,
+    Error,
+    Permission,
+}
+
+#[derive(Debug)]
+pub struct DesktopNotifier {
+    config: NotificationsConfig,
+    focused: bool,
+}
+
+impl DesktopNotifier {
+    pub fn new(config: NotificationsConfig) -> Self {
+        Self {
+            config,
+            focused: true,
+        }
+    }
+
+    pub fn set_config(&mut self, config: NotificationsConfig) {
+        self.config = config;
+    }
+
+    /// Track terminal focus (crossterm `FocusGained` / `FocusLost`)
+    pub fn set_focused(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+
+    /// Whether `event` would be shown right now
+    pub fn wants(&self, event: DesktopEvent) -> bool {
+        let enabled = match event {
+            DesktopEvent::TurnComplete => self.config.turn_complete,
+            DesktopEvent::Error => self.config.errors,
+            DesktopEvent::Permission => self.config.permissions,
+        };
+        self.config.desktop && !self.focused && enabled
+    }
+
+    pub fn notify(&self, event: DesktopEvent, summary: String, body: String) {
+        if self.wants(event) {
+            show(summary, body);
+        }
+    }
+
+    /// Ring the terminal bell when `bell` is on
+    pub fn bell(&self) {
+        if self.config.bell {
+            use std::io::Write;
+            let mut stdout = std::io::stdout();
+            let _ = stdout.write_all(b"\x07").and_then(|_| stdout.flush());
+        }
+    }
+}
+
+/// Hand the notification to the desktop off the UI thread; the D-Bus round
+/// trip on Linux can take a while
+#[cfg(feature = "desktop-notifications")]
+fn show(summary: String, body: String) {
+    std::thread::spawn(move || {
+        if let Err(e) = notify_rust::Notification::new()
+            .appname("rat")
+            .summary(&summary)
+            .body(&body)
+            .show()
+        {
+            tracing::debug!("Desktop notification failed: {}", e);
+        }
+    });
+}
+
+#[cfg(not(feature = "desktop-notifications"))]
+fn show(summary: String, _body: String) {
+    tracing::debug!("Desktop notifications not built in; dropped '{}'", summary);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_enabled_events_while_unfocused() {
+        let mut notifier = DesktopNotifier::new(NotificationsConfig {
+            permissions: false,
+            ..NotificationsConfig::default()
+        });
+        assert!(!notifier.wants(DesktopEvent::TurnComplete));
+
+        notifier.set_focused(false);
+        assert!(notifier.wants(DesktopEvent::TurnComplete));
+        assert!(notifier.wants(DesktopEvent::Error));
+        assert!(!notifier.wants(DesktopEvent::Permission));
+
+        notifier.set_config(NotificationsConfig {
+            desktop: false,
+            ..NotificationsConfig::default()
+        });
+        assert!(!notifier.wants(DesktopEvent::Error));
+    }
+}
@@ -4,10 +4,13 @@ use ratatui::{
 };
 
 use crate::acp::{Plan, PlanEntry, PlanEntryPriority, PlanEntryStatus};
+use crate::ui::ascii;
 
 #[derive(Clone, Debug)]
 pub struct PlanView {
     pub plan: Option<Plan>,
+    /// Status labels instead of emoji (accessibility mode)
+    pub accessible: bool,
     state: ListState,
 }
 
@@ -15,6 +18,7 @@ impl PlanView {
     pub fn new() -> Self {
         Self {
             plan: None,
+            accessible: false,
             state: ListState::default(),
         }
     }
@@ -84,6 +88,11 @@ impl PlanView {
                     PlanEntryStatus::InProgress => "⚡",
                     PlanEntryStatus::Completed => "✅",
                 };
+                let status_icon = if self.accessible {
+                    ascii::ascii(status_icon)
+                } else {
+                    status_icon.to_string()
+                };
 
                 let line = Line::from(vec![
                     Span::styled(status_icon, status_style),
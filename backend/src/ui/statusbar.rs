@@ -4,24 +4,181 @@ use ratatui::{
     widgets::{Block, Paragraph},
 };
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+
+use agent_client_protocol::ContentBlock;
+
+use crate::acp::{Message, MessageContent};
+use crate::effects::cyberpunk::CyberTheme;
+use crate::events::Backpressure;
+use crate::ui::ascii;
+use crate::utils::git::GitStatus;
+
+/// How often the git status is re-read
+const GIT_REFRESH: Duration = Duration::from_secs(5);
+
+/// One piece of the status bar, named as in `[ui.status_bar] segments`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Segment {
+    /// Last status message
+    Message,
+    /// Active tab's agent and its connection status
+    Agent,
+    /// Active config profile
+    Mode,
+    /// Git branch, change counts and upstream tracking of the active
+    /// session's working directory
+    Git,
+    /// Estimated tokens exchanged in the active session
+    Tokens,
+    /// Running time of the current turn, or how long the last one took
+    Latency,
+    /// Permission requests waiting for an answer, and the decisions made
+    /// in the active session
+    Permissions,
+    /// Agent events the UI has fallen behind on
+    Backpressure,
+    Memory,
+    Clock,
+}
+
+impl Segment {
+    pub const ALL: [Segment; 10] = [
+        Segment::Message,
+        Segment::Agent,
+        Segment::Mode,
+        Segment::Git,
+        Segment::Tokens,
+        Segment::Latency,
+        Segment::Permissions,
+        Segment::Backpressure,
+        Segment::Memory,
+        Segment::Clock,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Segment::Message => "message",
+            Segment::Agent => "agent",
+            Segment::Mode => "mode",
+            Segment::Git => "git",
+            Segment::Tokens => "tokens",
+            Segment::Latency => "latency",
+            Segment::Permissions => "permissions",
+            Segment::Backpressure => "backpressure",
+            Segment::Memory => "memory",
+            Segment::Clock => "clock",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|s| s.name() == name.trim())
+    }
+
+    /// Segments from config, skipping unknown names (rejected by validation)
+    pub fn from_names(names: &[String]) -> Vec<Self> {
+        names.iter().filter_map(|name| Self::parse(name)).collect()
+    }
+}
+
+/// Per-session counters shown by the tokens, latency and permissions segments
+#[derive(Debug, Clone, Default)]
+pub struct SessionStats {
+    /// Rough token count (a token per four characters of prompt and reply);
+    /// agents don't report usage over ACP
+    pub tokens: usize,
+    pub turn_started: Option<Instant>,
+    pub last_turn: Option<Duration>,
+    pub permissions_allowed: usize,
+    pub permissions_denied: usize,
+}
+
+impl SessionStats {
+    /// Count the text of a prompt or reply towards the token estimate
+    pub fn record(&mut self, message: &Message) {
+        let text_len = |block: &ContentBlock| match block {
+            ContentBlock::Text(text) => text.text.chars().count(),
+            _ => 0,
+        };
+        let chars: usize = match &message.content {
+            MessageContent::UserPrompt { content } => content.iter().map(text_len).sum(),
+            MessageContent::AgentResponse { content }
+            | MessageContent::AgentMessageChunk { content } => text_len(content),
+            _ => 0,
+        };
+        self.tokens += chars.div_ceil(4);
+    }
+
+    pub fn start_turn(&mut self) {
+        self.turn_started = Some(Instant::now());
+    }
+
+    pub fn finish_turn(&mut self) {
+        if let Some(started) = self.turn_started.take() {
+            self.last_turn = Some(started.elapsed());
+        }
+    }
+}
 
 pub struct StatusBar {
+    segments: Vec<Segment>,
     agent_statuses: HashMap<String, String>,
     current_message: String,
     memory_usage: Option<u64>,
-    connection_count: usize,
+    backpressure: Backpressure,
+    theme: CyberTheme,
+    /// Unix second the clock last showed, to tell when it needs a redraw
+    clock_second: i64,
+    mode: Option<String>,
+    git: Option<GitStatus>,
+    /// Working directory of the active session; RAT's own when unset
+    git_dir: Option<PathBuf>,
+    git_checked: Option<Instant>,
+    /// A `git status` running in the background
+    git_pending: Option<oneshot::Receiver<Option<GitStatus>>>,
+    /// Agent and stats of the active tab
+    active: Option<(String, SessionStats)>,
+    /// ASCII labels instead of symbols (accessibility mode)
+    accessible: bool,
+    /// Permission requests from any session waiting for the user
+    waiting_permissions: usize,
 }
 
 impl StatusBar {
     pub fn new() -> Self {
         Self {
+            segments: Segment::ALL.to_vec(),
             agent_statuses: HashMap::new(),
             current_message: "Ready".to_string(),
             memory_usage: None,
-            connection_count: 0,
+            backpressure: Backpressure::default(),
+            theme: CyberTheme::default(),
+            clock_second: 0,
+            mode: None,
+            git: None,
+            git_dir: None,
+            git_checked: None,
+            git_pending: None,
+            active: None,
+            accessible: false,
+            waiting_permissions: 0,
         }
     }
 
+    pub fn set_theme(&mut self, theme: CyberTheme) {
+        self.theme = theme;
+    }
+
+    pub fn set_segments(&mut self, segments: Vec<Segment>) {
+        self.segments = segments;
+    }
+
+    pub fn set_accessible(&mut self, accessible: bool) {
+        self.accessible = accessible;
+    }
+
     pub fn render(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
         // Check for minimum area size
         if area.width < 5 || area.height < 1 {
@@ -32,8 +189,8 @@ impl StatusBar {
 
         let paragraph = Paragraph::new(status_text).style(
             Style::default()
-                .bg(Color::from_u32(0x121821))
-                .fg(Color::from_u32(0x18e5ff)),
+                .bg(self.theme.palette.surface)
+                .fg(self.theme.palette.accent_b),
         );
 
         frame.render_widget(paragraph, area);
@@ -41,42 +198,121 @@ impl StatusBar {
     }
 
     fn build_status_text(&self) -> String {
-        let mut parts = Vec::new();
-
-        // Current message
-        parts.push(self.current_message.clone());
-
-        // Agent statuses
-        if !self.agent_statuses.is_empty() {
-            let agent_info: Vec<String> = self
-                .agent_statuses
-                .iter()
-                .map(|(name, status)| format!("{}:{}", name, status))
-                .collect();
-            parts.push(format!("Agents[{}]", agent_info.join(", ")));
-        }
+        let parts: Vec<String> = self.parts().into_iter().map(|(_, text)| text).collect();
+        format!(" {} ", parts.join(" | "))
+    }
 
-        // Connection count
-        if self.connection_count > 0 {
-            parts.push(format!("Connections: {}", self.connection_count));
-        }
+    /// Text of each segment that has something to show, in order
+    fn parts(&self) -> Vec<(Segment, String)> {
+        self.segments
+            .iter()
+            .filter_map(|segment| {
+                let text = self.segment_text(*segment)?;
+                Some((*segment, if self.accessible { ascii::ascii(&text) } else { text }))
+            })
+            .collect()
+    }
 
-        // Memory usage
-        if let Some(memory) = self.memory_usage {
-            parts.push(format!("Mem: {}MB", memory / 1024 / 1024));
+    /// Where the permissions segment is drawn within `area`, when it shows
+    pub fn permissions_area(&self, area: Rect) -> Option<Rect> {
+        // One leading space, then each part followed by " | "
+        let mut x = 1;
+        for (segment, text) in self.parts() {
+            let width = text.chars().count() as u16;
+            if segment == Segment::Permissions {
+                let x = area.x.saturating_add(x).min(area.right());
+                return Some(Rect {
+                    x,
+                    width: width.min(area.right() - x),
+                    ..area
+                });
+            }
+            x = x.saturating_add(width + 3);
         }
+        None
+    }
 
-        // Current time
-        let now = chrono::Local::now();
-        parts.push(now.format("%H:%M:%S").to_string());
-
-        format!(" {} ", parts.join(" | "))
+    fn segment_text(&self, segment: Segment) -> Option<String> {
+        let stats = self.active.as_ref().map(|(_, stats)| stats);
+        match segment {
+            Segment::Message => Some(self.current_message.clone()),
+            Segment::Agent => {
+                let (agent, _) = self.active.as_ref()?;
+                Some(match self.agent_statuses.get(agent) {
+                    Some(status) => format!("{}: {}", agent, status),
+                    None => agent.clone(),
+                })
+            }
+            Segment::Mode => self.mode.as_ref().map(|mode| format!("Profile: {}", mode)),
+            Segment::Git => self
+                .git
+                .as_ref()
+                .map(|git| format!("⎇ {}", git.summary())),
+            Segment::Tokens => {
+                let tokens = stats?.tokens;
+                if tokens == 0 {
+                    None
+                } else if tokens < 1000 {
+                    Some(format!("~{} tok", tokens))
+                } else {
+                    Some(format!("~{:.1}k tok", tokens as f64 / 1000.0))
+                }
+            }
+            Segment::Latency => {
+                let stats = stats?;
+                match (stats.turn_started, stats.last_turn) {
+                    (Some(started), _) => Some(format!("Turn {}s…", started.elapsed().as_secs())),
+                    (None, Some(last)) => Some(format!("Turn {:.1}s", last.as_secs_f64())),
+                    (None, None) => None,
+                }
+            }
+            Segment::Permissions => {
+                let (allowed, denied) = stats
+                    .map_or((0, 0), |stats| (stats.permissions_allowed, stats.permissions_denied));
+                match (self.waiting_permissions, allowed + denied) {
+                    (0, 0) => None,
+                    (0, _) => Some(format!("Perms ✓{} ✗{}", allowed, denied)),
+                    (waiting, 0) => Some(format!("Perms {} waiting", waiting)),
+                    (waiting, _) => Some(format!("Perms {} waiting ✓{} ✗{}", waiting, allowed, denied)),
+                }
+            }
+            Segment::Backpressure if self.backpressure.is_active() => Some(format!(
+                "Behind: {} queued, {} merged, {} dropped",
+                self.backpressure.queued, self.backpressure.coalesced, self.backpressure.dropped
+            )),
+            Segment::Backpressure => None,
+            Segment::Memory => self
+                .memory_usage
+                .map(|memory| format!("Mem: {}MB", memory / 1024 / 1024)),
+            Segment::Clock => Some(chrono::Local::now().format("%H:%M:%S").to_string()),
+        }
     }
 
-    pub async fn tick(&mut self) -> Result<()> {
-        // Update memory usage periodically
-        self.update_memory_usage();
-        Ok(())
+    /// Returns whether the text changed (memory usage, git status, the clock
+    /// or a running turn's timer)
+    pub async fn tick(&mut self) -> Result<bool> {
+        let mut changed = false;
+        if self.segments.contains(&Segment::Memory) {
+            // Update memory usage periodically
+            let shown_mb = |memory: Option<u64>| memory.map(|m| m / 1024 / 1024);
+            let before = shown_mb(self.memory_usage);
+            self.update_memory_usage();
+            changed |= shown_mb(self.memory_usage) != before;
+        }
+        if self.segments.contains(&Segment::Git) {
+            changed |= self.poll_git();
+        }
+
+        let second = chrono::Local::now().timestamp();
+        let turn_running = self
+            .active
+            .as_ref()
+            .is_some_and(|(_, stats)| stats.turn_started.is_some());
+        let timed = self.segments.contains(&Segment::Clock)
+            || (turn_running && self.segments.contains(&Segment::Latency));
+        changed |= timed && second != self.clock_second;
+        self.clock_second = second;
+        Ok(changed)
     }
 
     pub fn set_agent_status(&mut self, agent_name: String, status: String) {
@@ -91,8 +327,76 @@ impl StatusBar {
         self.current_message = message;
     }
 
-    pub fn set_connection_count(&mut self, count: usize) {
-        self.connection_count = count;
+    pub fn set_backpressure(&mut self, backpressure: Backpressure) {
+        self.backpressure = backpressure;
+    }
+
+    /// Config profile shown by the mode segment
+    pub fn set_mode(&mut self, mode: Option<String>) {
+        self.mode = mode;
+    }
+
+    /// Permission requests waiting for the user, across every session
+    pub fn set_waiting_permissions(&mut self, waiting: usize) {
+        self.waiting_permissions = waiting;
+    }
+
+    /// Agent and stats of the active tab, refreshed before each render
+    pub fn set_active(&mut self, active: Option<(String, SessionStats)>) {
+        self.active = active;
+    }
+
+    /// Show the git status of `dir` (the active session's working
+    /// directory), re-reading it if the directory changed
+    pub fn set_git_dir(&mut self, dir: Option<PathBuf>) {
+        if dir != self.git_dir {
+            self.git_dir = dir;
+            self.git = None;
+            self.git_pending = None;
+            self.git_checked = None;
+        }
+    }
+
+    /// Re-read the git status on the next tick, e.g. after an agent wrote
+    /// to the tree
+    pub fn refresh_git(&mut self) {
+        self.git_checked = None;
+    }
+
+    /// Pick up a finished `git status` and start the next one when due, so
+    /// a slow repository never holds up a frame. Returns whether the
+    /// status changed.
+    fn poll_git(&mut self) -> bool {
+        let mut changed = false;
+        if let Some(pending) = &mut self.git_pending {
+            match pending.try_recv() {
+                Ok(git) => {
+                    changed = git != self.git;
+                    self.git = git;
+                    self.git_pending = None;
+                }
+                Err(oneshot::error::TryRecvError::Empty) => {}
+                Err(oneshot::error::TryRecvError::Closed) => self.git_pending = None,
+            }
+        }
+        if self.git_pending.is_none()
+            && self
+                .git_checked
+                .is_none_or(|at| at.elapsed() >= GIT_REFRESH)
+        {
+            self.git_checked = Some(Instant::now());
+            let dir = self.git_dir.clone().or_else(|| std::env::current_dir().ok());
+            let (tx, rx) = oneshot::channel();
+            tokio::spawn(async move {
+                let git = match dir {
+                    Some(dir) => read_git(&dir).await,
+                    None => None,
+                };
+                let _ = tx.send(git);
+            });
+            self.git_pending = Some(rx);
+        }
+        changed
     }
 
     fn update_memory_usage(&mut self) {
@@ -102,6 +406,45 @@ impl StatusBar {
     }
 }
 
+/// Git status of `dir`, or just its branch when `git` can't be run
+async fn read_git(dir: &Path) -> Option<GitStatus> {
+    match GitStatus::read(dir).await {
+        Some(git) => Some(git),
+        None => git_branch(dir).map(|branch| GitStatus {
+            branch,
+            ..GitStatus::default()
+        }),
+    }
+}
+
+/// Branch checked out in the repository containing `dir`, or the short
+/// commit hash when the HEAD is detached
+fn git_branch(dir: &Path) -> Option<String> {
+    let git = dir
+        .ancestors()
+        .map(|d| d.join(".git"))
+        .find(|p| p.exists())?;
+    // Worktrees and submodules have a `.git` file pointing at the real one
+    let git_dir = if git.is_file() {
+        let content = std::fs::read_to_string(&git).ok()?;
+        let target = content.strip_prefix("gitdir:")?.trim();
+        git.parent()?.join(target)
+    } else {
+        git
+    };
+    let head = std::fs::read_to_string(git_dir.join("HEAD")).ok()?;
+    let head = head.trim();
+    match head.strip_prefix("ref: ") {
+        Some(reference) => Some(
+            reference
+                .strip_prefix("refs/heads/")
+                .unwrap_or(reference)
+                .to_string(),
+        ),
+        None => Some(head.chars().take(7).collect()),
+    }
+}
+
 #[cfg(unix)]
 fn get_process_memory() -> Option<u64> {
     use std::fs;
@@ -124,3 +467,77 @@ fn get_process_memory() -> Option<u64> {
     // Fallback for non-Unix systems
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn segments_render_in_configured_order() {
+        let mut bar = StatusBar::new();
+        bar.set_segments(Segment::from_names(&[
+            "latency".to_string(),
+            "agent".to_string(),
+            "tokens".to_string(),
+            "mode".to_string(),
+        ]));
+        // Nothing to show yet for any of them
+        assert_eq!(bar.build_status_text(), "  ");
+
+        let stats = SessionStats {
+            tokens: 1234,
+            last_turn: Some(Duration::from_millis(2500)),
+            ..SessionStats::default()
+        };
+        bar.set_active(Some(("gemini".to_string(), stats)));
+        bar.set_agent_status("gemini".to_string(), "Connected".to_string());
+        assert_eq!(
+            bar.build_status_text(),
+            " Turn 2.5s | gemini: Connected | ~1.2k tok "
+        );
+    }
+
+    #[test]
+    fn locates_the_waiting_permissions_segment() {
+        let mut bar = StatusBar::new();
+        bar.set_segments(Segment::from_names(&["mode".to_string(), "permissions".to_string()]));
+        bar.set_mode(Some("work".to_string()));
+        let area = Rect::new(0, 9, 60, 1);
+        assert_eq!(bar.permissions_area(area), None);
+
+        bar.set_waiting_permissions(2);
+        assert_eq!(bar.build_status_text(), " Profile: work | Perms 2 waiting ");
+        assert_eq!(bar.permissions_area(area), Some(Rect::new(17, 9, 15, 1)));
+    }
+
+    #[test]
+    fn reads_branch_and_detached_head() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("src");
+        std::fs::create_dir_all(dir.path().join(".git")).unwrap();
+        std::fs::create_dir_all(&nested).unwrap();
+        let head = dir.path().join(".git").join("HEAD");
+
+        std::fs::write(&head, "ref: refs/heads/feature/panes\n").unwrap();
+        assert_eq!(git_branch(&nested).as_deref(), Some("feature/panes"));
+
+        std::fs::write(&head, "0123456789abcdef\n").unwrap();
+        assert_eq!(git_branch(&nested).as_deref(), Some("0123456"));
+    }
+
+    #[tokio::test]
+    async fn tick_reports_a_change_only_when_the_text_would_change() {
+        let mut bar = StatusBar::new();
+        bar.set_segments(Segment::from_names(&["agent".to_string()]));
+        assert!(!bar.tick().await.unwrap());
+        assert!(!bar.tick().await.unwrap());
+
+        let mut bar = StatusBar::new();
+        bar.set_segments(Segment::from_names(&["clock".to_string()]));
+        assert!(bar.tick().await.unwrap());
+        // Again only once the clock has moved on to another second
+        let shown = bar.clock_second;
+        let changed = bar.tick().await.unwrap();
+        assert_eq!(changed, bar.clock_second != shown);
+    }
+}
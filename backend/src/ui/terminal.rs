@@ -1,20 +1,77 @@
 use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent};
 use ratatui::{
     prelude::*,
     widgets::{Block, Borders, List, ListItem, Paragraph},
 };
-use std::collections::VecDeque;
+use agent_client_protocol::{ContentBlock, ToolCallContent, ToolCallStatus, ToolKind};
+use std::collections::{HashSet, VecDeque};
 use std::process::Stdio;
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::{Child, Command};
 use tokio::sync::mpsc;
 
+use crate::acp::{Message, MessageContent};
+use crate::ui::keymap::Action;
+
 pub struct TerminalView {
     output_lines: VecDeque<TerminalLine>,
     max_lines: usize,
+    // Scroll offset measured in lines from the bottom (0 = follow output)
     scroll_offset: usize,
     processes: Vec<TerminalProcess>,
     visible: bool,
+    stream_filter: StreamFilter,
+    search: Option<TerminalSearch>,
+}
+
+/// Which output stream the terminal panel shows. Command echoes and system
+/// notices are always kept for context.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamFilter {
+    All,
+    Stdout,
+    Stderr,
+}
+
+impl StreamFilter {
+    fn next(self) -> Self {
+        match self {
+            StreamFilter::All => StreamFilter::Stdout,
+            StreamFilter::Stdout => StreamFilter::Stderr,
+            StreamFilter::Stderr => StreamFilter::All,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            StreamFilter::All => "all",
+            StreamFilter::Stdout => "stdout",
+            StreamFilter::Stderr => "stderr",
+        }
+    }
+
+    fn accepts(self, level: &TerminalLineLevel) -> bool {
+        matches!(
+            (self, level),
+            (StreamFilter::All, _)
+                | (_, TerminalLineLevel::Command | TerminalLineLevel::System)
+                | (StreamFilter::Stdout, TerminalLineLevel::Output)
+                | (StreamFilter::Stderr, TerminalLineLevel::Error)
+        )
+    }
+}
+
+/// Incremental `/` search over the scrollback
+#[derive(Debug, Clone, Default)]
+struct TerminalSearch {
+    query: String,
+    // True while the query is being typed
+    editing: bool,
+    // Indices into output_lines of matching lines, oldest first
+    matches: Vec<usize>,
+    // Index into `matches` of the focused match
+    current: usize,
 }
 
 #[derive(Debug, Clone)]
@@ -34,11 +91,10 @@ pub enum TerminalLineLevel {
 
 #[derive(Debug)]
 pub struct TerminalProcess {
-    pub id: String,
     pub command: String,
     pub child: Child,
     pub status: ProcessStatus,
-    pub output_rx: Option<mpsc::UnboundedReceiver<String>>,
+    pub output_rx: Option<mpsc::Receiver<String>>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -56,6 +112,8 @@ impl TerminalView {
             scroll_offset: 0,
             processes: Vec::new(),
             visible: false,
+            stream_filter: StreamFilter::All,
+            search: None,
         }
     }
 
@@ -88,7 +146,25 @@ impl TerminalView {
             .filter(|p| p.status == ProcessStatus::Running)
             .count();
 
-        let header_text = format!("Terminal | Active processes: {}", active_count);
+        let mut header_text = format!("Terminal | Active processes: {}", active_count);
+        header_text.push_str(&format!(" | Stream: {} (f)", self.stream_filter.label()));
+        match &self.search {
+            Some(search) if search.editing => {
+                header_text.push_str(&format!(" | /{}_", search.query));
+            }
+            Some(search) if search.matches.is_empty() => {
+                header_text.push_str(&format!(" | /{} (no matches)", search.query));
+            }
+            Some(search) => {
+                header_text.push_str(&format!(
+                    " | /{} ({}/{}, n/N)",
+                    search.query,
+                    search.current + 1,
+                    search.matches.len()
+                ));
+            }
+            None => header_text.push_str(" | /: search"),
+        }
 
         let header = Paragraph::new(header_text).block(
             Block::default()
@@ -101,12 +177,14 @@ impl TerminalView {
     }
 
     fn render_output(&self, frame: &mut Frame, area: Rect) {
-        let visible_lines: Vec<ListItem> = self
-            .output_lines
+        let height = area.height.saturating_sub(2) as usize;
+        let filtered = self.filtered_indices();
+        let end = filtered.len().saturating_sub(self.scroll_offset);
+        let start = end.saturating_sub(height);
+
+        let visible_lines: Vec<ListItem> = filtered[start..end]
             .iter()
-            .skip(self.scroll_offset)
-            .take(area.height.saturating_sub(2) as usize)
-            .map(|line| self.format_terminal_line(line))
+            .map(|&idx| self.format_terminal_line(idx, &self.output_lines[idx]))
             .collect();
 
         let output_list = List::new(visible_lines).block(Block::default().borders(Borders::ALL));
@@ -114,9 +192,8 @@ impl TerminalView {
         frame.render_widget(output_list, area);
     }
 
-    fn format_terminal_line(&self, line: &TerminalLine) -> ListItem {
+    fn format_terminal_line(&self, idx: usize, line: &TerminalLine) -> ListItem<'static> {
         let timestamp = line.timestamp.format("%H:%M:%S");
-        let formatted = format!("[{}] {}", timestamp, line.content);
 
         let style = match line.level {
             TerminalLineLevel::Output => Style::default().white(),
@@ -125,7 +202,115 @@ impl TerminalView {
             TerminalLineLevel::System => Style::default().blue(),
         };
 
-        ListItem::new(formatted).style(style)
+        let mut spans = vec![Span::styled(format!("[{}] ", timestamp), style)];
+        match &self.search {
+            Some(search) if !search.query.is_empty() && search.matches.binary_search(&idx).is_ok() => {
+                let focused = search.matches.get(search.current) == Some(&idx);
+                let highlight = if focused {
+                    Style::default().black().on_magenta().bold()
+                } else {
+                    Style::default().black().on_yellow()
+                };
+                let mut last = 0;
+                for (range_start, range_end) in match_ranges(&line.content, &search.query) {
+                    spans.push(Span::styled(line.content[last..range_start].to_string(), style));
+                    spans.push(Span::styled(
+                        line.content[range_start..range_end].to_string(),
+                        highlight,
+                    ));
+                    last = range_end;
+                }
+                spans.push(Span::styled(line.content[last..].to_string(), style));
+            }
+            _ => spans.push(Span::styled(line.content.clone(), style)),
+        }
+
+        ListItem::new(Line::from(spans))
+    }
+
+    /// Indices of lines that pass the current stream filter, oldest first
+    fn filtered_indices(&self) -> Vec<usize> {
+        self.output_lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| self.stream_filter.accepts(&line.level))
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
+    /// Begin typing a new `/` search query
+    pub fn start_search(&mut self) {
+        self.search = Some(TerminalSearch {
+            editing: true,
+            ..Default::default()
+        });
+    }
+
+    /// True while the search prompt is capturing keystrokes
+    pub fn is_searching(&self) -> bool {
+        self.search.as_ref().map(|s| s.editing).unwrap_or(false)
+    }
+
+    /// True when a search query is active (typed or confirmed)
+    pub fn has_search(&self) -> bool {
+        self.search.is_some()
+    }
+
+    pub fn clear_search(&mut self) {
+        self.search = None;
+    }
+
+    pub fn set_stream_filter(&mut self, filter: StreamFilter) {
+        self.stream_filter = filter;
+        self.scroll_offset = 0;
+        self.refresh_search();
+    }
+
+    /// Recompute matches for the current query and focus the newest one
+    fn refresh_search(&mut self) {
+        let filtered = self.filtered_indices();
+        if let Some(search) = self.search.as_mut() {
+            search.matches = if search.query.is_empty() {
+                Vec::new()
+            } else {
+                filtered
+                    .into_iter()
+                    .filter(|&idx| !match_ranges(&self.output_lines[idx].content, &search.query).is_empty())
+                    .collect()
+            };
+            search.current = search.matches.len().saturating_sub(1);
+        }
+        self.scroll_to_current_match();
+    }
+
+    /// Move focus to the next (newer) or previous (older) match, wrapping around
+    pub fn jump_match(&mut self, forward: bool) {
+        if let Some(search) = self.search.as_mut() {
+            let count = search.matches.len();
+            if count == 0 {
+                return;
+            }
+            search.current = if forward {
+                (search.current + 1) % count
+            } else {
+                (search.current + count - 1) % count
+            };
+        }
+        self.scroll_to_current_match();
+    }
+
+    fn scroll_to_current_match(&mut self) {
+        let target = self
+            .search
+            .as_ref()
+            .and_then(|s| s.matches.get(s.current).copied());
+        if let Some(target) = target {
+            let filtered = self.filtered_indices();
+            if let Some(pos) = filtered.iter().position(|&idx| idx == target) {
+                // Place the match on the bottom row of the viewport
+                self.scroll_offset = filtered.len() - 1 - pos;
+            }
+        }
     }
 
     pub async fn execute_command(&mut self, command: &str, args: Vec<&str>) -> Result<String> {
@@ -157,7 +342,9 @@ impl TerminalView {
             .take()
             .ok_or_else(|| anyhow::anyhow!("Failed to get stderr"))?;
 
-        let (output_tx, output_rx) = mpsc::unbounded_channel();
+        // Bounded: a process that outputs faster than the panel reads waits
+        // on its pipe instead of piling lines up in memory
+        let (output_tx, output_rx) = mpsc::channel(1024);
 
         // Spawn task to read stdout
         let output_tx_clone = output_tx.clone();
@@ -165,7 +352,9 @@ impl TerminalView {
             let mut reader = BufReader::new(stdout);
             let mut line = String::new();
             while reader.read_line(&mut line).await.unwrap_or(0) > 0 {
-                let _ = output_tx_clone.send(line.trim_end().to_string());
+                if output_tx_clone.send(line.trim_end().to_string()).await.is_err() {
+                    break;
+                }
                 line.clear();
             }
         });
@@ -175,13 +364,14 @@ impl TerminalView {
             let mut reader = BufReader::new(stderr);
             let mut line = String::new();
             while reader.read_line(&mut line).await.unwrap_or(0) > 0 {
-                let _ = output_tx.send(format!("ERROR: {}", line.trim_end()));
+                if output_tx.send(format!("ERROR: {}", line.trim_end())).await.is_err() {
+                    break;
+                }
                 line.clear();
             }
         });
 
         let process = TerminalProcess {
-            id: process_id.clone(),
             command: format!("{} {}", command, args.join(" ")),
             child,
             status: ProcessStatus::Running,
@@ -192,7 +382,8 @@ impl TerminalView {
         Ok(process_id)
     }
 
-    pub async fn tick(&mut self) -> Result<()> {
+    /// Returns whether any output or process status arrived
+    pub async fn tick(&mut self) -> Result<bool> {
         let mut lines_to_add = Vec::new();
 
         // Collect output from active processes
@@ -245,6 +436,7 @@ impl TerminalView {
         }
 
         // Add all collected lines
+        let changed = !lines_to_add.is_empty();
         for (content, level) in lines_to_add {
             self.add_line(content, level);
         }
@@ -253,7 +445,7 @@ impl TerminalView {
         self.processes
             .retain(|p| p.status == ProcessStatus::Running);
 
-        Ok(())
+        Ok(changed)
     }
 
     pub fn add_line(&mut self, content: String, level: TerminalLineLevel) {
@@ -263,15 +455,31 @@ impl TerminalView {
             level,
         };
 
+        let counts = self.stream_filter.accepts(&line.level);
         self.output_lines.push_back(line);
 
         // Keep only max_lines
+        let mut evicted = false;
         while self.output_lines.len() > self.max_lines {
             self.output_lines.pop_front();
+            evicted = true;
+        }
+
+        // Follow output at the bottom; otherwise keep the viewport anchored
+        if self.scroll_offset > 0 && counts {
+            self.scroll_offset += 1;
         }
 
-        // Auto-scroll to bottom
-        self.scroll_offset = self.output_lines.len().saturating_sub(1);
+        if self.search.is_some() && (evicted || !self.is_searching()) {
+            // Match indices shift on eviction and new lines may match
+            let current = self.search.as_ref().map(|s| s.current).unwrap_or(0);
+            let offset = self.scroll_offset;
+            self.refresh_search();
+            if let Some(search) = self.search.as_mut() {
+                search.current = current.min(search.matches.len().saturating_sub(1));
+            }
+            self.scroll_offset = offset;
+        }
     }
 
     pub fn show(&mut self) {
@@ -291,20 +499,21 @@ impl TerminalView {
     }
 
     pub fn scroll_up(&mut self) {
-        if self.scroll_offset > 0 {
-            self.scroll_offset -= 1;
+        if self.scroll_offset < self.filtered_indices().len().saturating_sub(1) {
+            self.scroll_offset += 1;
         }
     }
 
     pub fn scroll_down(&mut self) {
-        if self.scroll_offset < self.output_lines.len().saturating_sub(1) {
-            self.scroll_offset += 1;
+        if self.scroll_offset > 0 {
+            self.scroll_offset -= 1;
         }
     }
 
     pub fn clear(&mut self) {
         self.output_lines.clear();
         self.scroll_offset = 0;
+        self.search = None;
     }
 
     pub fn get_active_process_count(&self) -> usize {
@@ -314,6 +523,62 @@ impl TerminalView {
             .count()
     }
 
+    /// Keys typed into the search prompt. Returns true if the key was consumed.
+    pub fn handle_key_event(&mut self, key: KeyEvent) -> Result<bool> {
+        // The search prompt captures all typing until Enter/Esc
+        if !self.is_searching() {
+            return Ok(false);
+        }
+        match key.code {
+            KeyCode::Enter => {
+                if let Some(search) = self.search.as_mut() {
+                    search.editing = false;
+                }
+                if self.search.as_ref().map(|s| s.query.is_empty()).unwrap_or(true) {
+                    self.search = None;
+                }
+            }
+            KeyCode::Esc => self.search = None,
+            KeyCode::Backspace => {
+                if let Some(search) = self.search.as_mut() {
+                    search.query.pop();
+                }
+                self.refresh_search();
+            }
+            KeyCode::Char(c) => {
+                if let Some(search) = self.search.as_mut() {
+                    search.query.push(c);
+                }
+                self.refresh_search();
+            }
+            _ => {}
+        }
+        Ok(true)
+    }
+
+    /// Run a terminal or search action. Returns true if it applied here.
+    pub fn handle_action(&mut self, action: Action) -> Result<bool> {
+        match action {
+            Action::TerminalSearch => self.start_search(),
+            Action::SearchNext if self.search.is_some() => self.jump_match(true),
+            Action::SearchPrev if self.search.is_some() => self.jump_match(false),
+            Action::SearchClear if self.search.is_some() => self.clear_search(),
+            Action::TerminalFilter => self.set_stream_filter(self.stream_filter.next()),
+            Action::TerminalScrollUp => {
+                for _ in 0..10 {
+                    self.scroll_up();
+                }
+            }
+            Action::TerminalScrollDown => {
+                for _ in 0..10 {
+                    self.scroll_down();
+                }
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
     pub async fn kill_all_processes(&mut self) -> Result<()> {
         let mut messages = Vec::new();
         for process in &mut self.processes {
@@ -425,3 +690,178 @@ impl TerminalView {
         self.execute_command(command, args_str).await
     }
 }
+
+/// Follows the commands agents run (execute tool calls), to show them and
+/// their output in the terminal panel or a tmux pane
+#[derive(Debug, Default)]
+pub struct AgentCommands {
+    /// Ids of commands that haven't finished
+    running: HashSet<String>,
+}
+
+impl AgentCommands {
+    /// Terminal lines for what `message` says about an agent's command: the
+    /// command when it starts, its output and result when it finishes
+    pub fn lines(&mut self, agent_name: &str, message: &Message) -> Vec<(String, TerminalLineLevel)> {
+        let mut lines = Vec::new();
+        match &message.content {
+            MessageContent::ToolCall { tool_call } if tool_call.tool_name == format!("{:?}", ToolKind::Execute) => {
+                let command = match tool_call.parameters.get("command") {
+                    Some(serde_json::Value::String(command)) => command.clone(),
+                    _ => tool_call.parameters.to_string(),
+                };
+                self.running.insert(tool_call.id.clone());
+                lines.push((format!("{}$ {}", agent_name, command), TerminalLineLevel::Command));
+            }
+            MessageContent::ToolCallUpdate { update } => {
+                let id = update.id.0.to_string();
+                let fields = &update.fields;
+                if fields.kind == Some(ToolKind::Execute) && self.running.insert(id.clone()) {
+                    let title = fields.title.as_deref().unwrap_or("command");
+                    lines.push((format!("{}$ {}", agent_name, title), TerminalLineLevel::Command));
+                }
+                let failed = match fields.status {
+                    Some(ToolCallStatus::Completed) => false,
+                    Some(ToolCallStatus::Failed) => true,
+                    _ => return lines,
+                };
+                if !self.running.remove(&id) {
+                    return lines;
+                }
+                let level = if failed { TerminalLineLevel::Error } else { TerminalLineLevel::Output };
+                for content in fields.content.iter().flatten() {
+                    if let ToolCallContent::Content { content: ContentBlock::Text(text) } = content {
+                        lines.extend(text.text.lines().map(|line| (line.to_string(), level.clone())));
+                    }
+                }
+                let result = if failed { "failed" } else { "finished" };
+                lines.push((format!("{}: command {}", agent_name, result), TerminalLineLevel::System));
+            }
+            _ => {}
+        }
+        lines
+    }
+}
+
+/// Byte ranges of case-insensitive (ASCII) occurrences of `query` in `text`
+fn match_ranges(text: &str, query: &str) -> Vec<(usize, usize)> {
+    if query.is_empty() {
+        return Vec::new();
+    }
+    // ASCII lowercasing keeps byte offsets aligned with the original text
+    let haystack = text.to_ascii_lowercase();
+    let needle = query.to_ascii_lowercase();
+    haystack
+        .match_indices(&needle)
+        .map(|(start, m)| (start, start + m.len()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn view_with(lines: &[(&str, TerminalLineLevel)]) -> TerminalView {
+        let mut view = TerminalView::new(100);
+        for (content, level) in lines {
+            view.add_line(content.to_string(), level.clone());
+        }
+        view
+    }
+
+    #[tokio::test]
+    async fn tick_reports_new_output_and_exits_only() {
+        let mut view = TerminalView::new(100);
+        assert!(!view.tick().await.unwrap());
+
+        view.execute_command("echo", vec!["hello"]).await.unwrap();
+        let mut changed = false;
+        for _ in 0..200 {
+            changed |= view.tick().await.unwrap();
+            if view.get_active_process_count() == 0 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert!(changed);
+        assert!(!view.tick().await.unwrap());
+    }
+
+    #[test]
+    fn search_finds_matches_case_insensitively_and_wraps() {
+        let mut view = view_with(&[
+            ("$ cargo build", TerminalLineLevel::Command),
+            ("Compiling rat", TerminalLineLevel::Output),
+            ("ERROR: warning: unused", TerminalLineLevel::Error),
+            ("compiling done", TerminalLineLevel::Output),
+        ]);
+        view.start_search();
+        for c in "COMPILING".chars() {
+            view.handle_key_event(KeyEvent::from(KeyCode::Char(c))).unwrap();
+        }
+        view.handle_key_event(KeyEvent::from(KeyCode::Enter)).unwrap();
+
+        let search = view.search.as_ref().unwrap();
+        assert_eq!(search.matches, vec![1, 3]);
+        // Newest match is focused first
+        assert_eq!(search.current, 1);
+
+        view.jump_match(true);
+        assert_eq!(view.search.as_ref().unwrap().current, 0);
+        assert_eq!(view.scroll_offset, 2);
+    }
+
+    #[test]
+    fn stream_filter_limits_lines_and_matches() {
+        let mut view = view_with(&[
+            ("$ make", TerminalLineLevel::Command),
+            ("ok line", TerminalLineLevel::Output),
+            ("ERROR: bad line", TerminalLineLevel::Error),
+        ]);
+        view.set_stream_filter(StreamFilter::Stderr);
+        assert_eq!(view.filtered_indices(), vec![0, 2]);
+
+        view.start_search();
+        view.handle_key_event(KeyEvent::from(KeyCode::Char('l'))).unwrap();
+        assert_eq!(view.search.as_ref().unwrap().matches, vec![2]);
+    }
+
+    #[test]
+    fn agent_commands_show_the_command_then_its_output() {
+        let session_id = crate::acp::SessionId("s1".to_string());
+        let message = |update: serde_json::Value| {
+            Message::from_session_update(session_id.clone(), serde_json::from_value(update).unwrap())
+        };
+        let mut commands = AgentCommands::default();
+        let started = message(serde_json::json!({
+            "sessionUpdate": "tool_call", "toolCallId": "t1", "title": "Run tests",
+            "kind": "execute", "rawInput": {"command": "cargo test"},
+        }));
+        let lines: Vec<String> = commands.lines("gemini", &started).into_iter().map(|(line, _)| line).collect();
+        assert_eq!(lines, vec!["gemini$ cargo test"]);
+
+        let progress = message(serde_json::json!({
+            "sessionUpdate": "tool_call_update", "toolCallId": "t1", "status": "in_progress",
+        }));
+        assert!(commands.lines("gemini", &progress).is_empty());
+
+        let finished = message(serde_json::json!({
+            "sessionUpdate": "tool_call_update", "toolCallId": "t1", "status": "failed",
+            "content": [{"type": "content", "content": {"type": "text", "text": "1 failed\nerror: test"}}],
+        }));
+        let lines: Vec<String> = commands.lines("gemini", &finished).into_iter().map(|(line, _)| line).collect();
+        assert_eq!(lines, vec!["1 failed", "error: test", "gemini: command failed"]);
+        // Other tool calls and repeated updates are left to the chat
+        assert!(commands.lines("gemini", &finished).is_empty());
+        let read = message(serde_json::json!({
+            "sessionUpdate": "tool_call", "toolCallId": "t2", "title": "Read", "kind": "read",
+        }));
+        assert!(commands.lines("gemini", &read).is_empty());
+    }
+
+    #[test]
+    fn match_ranges_preserve_byte_offsets() {
+        assert_eq!(match_ranges("héllo HELLO", "hello"), vec![(7, 12)]);
+        assert!(match_ranges("abc", "").is_empty());
+    }
+}
@@ -0,0 +1,246 @@
+//! Live JSON-RPC frames exchanged with the active session's agent, for
+//! working out why an agent misbehaves. Shown as the `traffic` pane; frames
+//! are only captured while the pane is open.
+
+use chrono::{DateTime, Local};
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Paragraph},
+};
+use std::collections::VecDeque;
+use tokio::sync::mpsc;
+
+use crate::acp::traffic::{self, Direction, Frame};
+use crate::ui::keymap::Action;
+
+#[derive(Debug)]
+struct Captured {
+    frame: Frame,
+    /// Parsed once on arrival rather than on every redraw
+    session_id: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct TrafficView {
+    frames: mpsc::UnboundedReceiver<Frame>,
+    /// Receiver is only subscribed while the pane is open
+    subscribed: bool,
+    captured: VecDeque<Captured>,
+    max_frames: usize,
+    /// Frames that arrived since pausing, hidden until resumed
+    held: Option<usize>,
+    /// Pretty-print the JSON rather than one line per frame
+    pretty: bool,
+    /// Lines scrolled up from the newest
+    scroll_offset: usize,
+}
+
+impl TrafficView {
+    pub fn new(max_frames: usize) -> Self {
+        Self {
+            // Closed until `attach` subscribes
+            frames: mpsc::unbounded_channel().1,
+            subscribed: false,
+            captured: VecDeque::new(),
+            max_frames,
+            held: None,
+            pretty: true,
+            scroll_offset: 0,
+        }
+    }
+
+    /// Start or stop capturing; stopping keeps what was captured
+    pub fn attach(&mut self, open: bool) {
+        if open && !self.subscribed {
+            self.frames = traffic::subscribe();
+        } else if !open && self.subscribed {
+            self.frames.close();
+        }
+        self.subscribed = open;
+    }
+
+    /// Take the frames sent since the last call; returns whether any arrived
+    pub fn tick(&mut self) -> bool {
+        let mut received = false;
+        while let Ok(frame) = self.frames.try_recv() {
+            self.push(frame);
+            received = true;
+        }
+        received
+    }
+
+    fn push(&mut self, frame: Frame) {
+        let session_id = frame.session_id();
+        self.captured.push_back(Captured { frame, session_id });
+        if let Some(held) = self.held.as_mut() {
+            *held += 1;
+        }
+        while self.captured.len() > self.max_frames {
+            self.captured.pop_front();
+        }
+    }
+
+    /// Frames of `agent_name` for `session_id`, and those not tied to a
+    /// session (initialize, responses), oldest first, as of the pause
+    fn visible(&self, agent_name: &str, session_id: Option<&str>) -> Vec<&Frame> {
+        let shown = self.captured.len() - self.held.unwrap_or(0).min(self.captured.len());
+        self.captured
+            .iter()
+            .take(shown)
+            .filter(|c| c.frame.agent_name == agent_name)
+            .filter(|c| match (&c.session_id, session_id) {
+                (Some(frame_session), Some(session)) => frame_session == session,
+                _ => true,
+            })
+            .map(|c| &c.frame)
+            .collect()
+    }
+
+    /// Run a traffic action. Returns true if it applied here.
+    pub fn handle_action(&mut self, action: Action) -> bool {
+        match action {
+            Action::TrafficPause => {
+                self.held = match self.held {
+                    Some(_) => None,
+                    None => Some(0),
+                };
+            }
+            Action::TrafficPretty => self.pretty = !self.pretty,
+            Action::TrafficClear => {
+                self.captured.clear();
+                self.held = self.held.map(|_| 0);
+            }
+            Action::TrafficScrollUp => {
+                self.scroll_offset += 10;
+                return true;
+            }
+            Action::TrafficScrollDown => {
+                self.scroll_offset = self.scroll_offset.saturating_sub(10);
+                return true;
+            }
+            _ => return false,
+        }
+        self.scroll_offset = 0;
+        true
+    }
+
+    /// Render the frames of the active tab's session
+    pub fn render(
+        &mut self,
+        frame: &mut ratatui::Frame,
+        area: Rect,
+        agent_name: Option<&str>,
+        session_id: Option<&str>,
+    ) {
+        let mut title = format!("ACP traffic - {}", agent_name.unwrap_or("no session"));
+        if let Some(held) = self.held {
+            title.push_str(&format!(" [paused, {} new]", held));
+        }
+        let block = Block::default().title(title).borders(Borders::ALL);
+
+        let frames = agent_name
+            .map(|agent| self.visible(agent, session_id))
+            .unwrap_or_default();
+        if frames.is_empty() {
+            let note = if agent_name.is_none() {
+                "Open a session to watch its traffic"
+            } else {
+                "No frames yet"
+            };
+            frame.render_widget(Paragraph::new(note).dark_gray().block(block), area);
+            return;
+        }
+
+        // Format from the newest back, only as far as the view reaches
+        let height = area.height.saturating_sub(2) as usize;
+        let mut lines: Vec<Line> = Vec::new();
+        for f in frames.iter().rev() {
+            if lines.len() >= height + self.scroll_offset {
+                break;
+            }
+            let mut formatted = format_frame(f, self.pretty);
+            formatted.append(&mut lines);
+            lines = formatted;
+        }
+        self.scroll_offset = self.scroll_offset.min(lines.len().saturating_sub(height));
+        let end = lines.len() - self.scroll_offset;
+        let start = end.saturating_sub(height);
+        lines.truncate(end);
+        frame.render_widget(Paragraph::new(lines.split_off(start)).block(block), area);
+    }
+}
+
+/// Header line with time, direction and method, then the JSON
+fn format_frame(frame: &Frame, pretty: bool) -> Vec<Line<'static>> {
+    let color = match frame.direction {
+        Direction::Outgoing => Color::Cyan,
+        Direction::Incoming => Color::Green,
+    };
+    let time: DateTime<Local> = frame.at.into();
+    let header = Line::from(vec![
+        Span::styled(
+            time.format("%H:%M:%S%.3f ").to_string(),
+            Style::default().dark_gray(),
+        ),
+        Span::styled(
+            format!("{} {}", frame.direction.arrow(), frame.summary()),
+            Style::default().fg(color).add_modifier(Modifier::BOLD),
+        ),
+    ]);
+    let body = match frame.json() {
+        Some(json) if pretty => serde_json::to_string_pretty(&json).unwrap_or_default(),
+        _ => frame.text.clone(),
+    };
+    std::iter::once(header)
+        .chain(body.lines().map(|line| Line::from(format!("  {}", line))))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::SystemTime;
+
+    fn frame(direction: Direction, text: &str) -> Frame {
+        Frame {
+            agent_name: "claude-code".to_string(),
+            direction,
+            at: SystemTime::now(),
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn shows_the_active_session_and_holds_frames_while_paused() {
+        let mut view = TrafficView::new(100);
+        view.push(frame(
+            Direction::Outgoing,
+            r#"{"id":0,"method":"initialize"}"#,
+        ));
+        view.push(frame(
+            Direction::Incoming,
+            r#"{"method":"session/update","params":{"sessionId":"a"}}"#,
+        ));
+        view.push(frame(
+            Direction::Incoming,
+            r#"{"method":"session/update","params":{"sessionId":"b"}}"#,
+        ));
+        assert_eq!(view.visible("claude-code", Some("a")).len(), 2);
+        assert_eq!(view.visible("gemini", Some("a")).len(), 0);
+
+        view.handle_action(Action::TrafficPause);
+        view.push(frame(Direction::Incoming, r#"{"id":0,"result":{}}"#));
+        assert_eq!(view.visible("claude-code", Some("a")).len(), 2);
+        view.handle_action(Action::TrafficPause);
+        assert_eq!(view.visible("claude-code", Some("a")).len(), 3);
+    }
+
+    #[test]
+    fn formats_pretty_and_compact() {
+        let f = frame(Direction::Outgoing, r#"{"id":1,"method":"session/new"}"#);
+        let pretty = format_frame(&f, true);
+        assert!(pretty[0].to_string().ends_with("→ session/new #1"));
+        assert_eq!(pretty.len(), 5);
+        assert_eq!(format_frame(&f, false).len(), 2);
+    }
+}
@@ -0,0 +1,46 @@
+use anyhow::{Context, Result};
+use tracing::warn;
+use serde_json::json;
+use std::io::Write;
+use std::path::PathBuf;
+
+use crate::config::Config;
+
+/// Append-only JSONL record of unattended runs: the permission policy each
+/// run used and every permission decision made under it.
+pub struct AuditLog {
+    path: PathBuf,
+}
+
+impl AuditLog {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(config.get_effective_data_dir().join("audit.log"))
+    }
+
+    /// Append an event; `event` should be a JSON object and gets a timestamp
+    pub fn record(&self, mut event: serde_json::Value) {
+        if let Some(fields) = event.as_object_mut() {
+            fields.insert("timestamp".to_string(), json!(chrono::Utc::now()));
+        }
+        if let Err(e) = self.append(&event) {
+            warn!("Failed to write audit log {:?}: {}", self.path, e);
+        }
+    }
+
+    fn append(&self, event: &serde_json::Value) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("Failed to open audit log: {:?}", self.path))?;
+        writeln!(file, "{}", event)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,369 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::{Output, Stdio};
+use tokio::process::Command;
+
+/// Branch and working tree state of a git checkout, as shown in the status
+/// bar and the session list
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct GitStatus {
+    /// Checked out branch, or the short commit hash when the HEAD is detached
+    pub branch: String,
+    /// Files with changes in the index
+    pub staged: usize,
+    /// Tracked files with unstaged changes, conflicts included
+    pub dirty: usize,
+    pub untracked: usize,
+    /// Commits not yet on the upstream branch, and upstream commits not yet
+    /// merged; both zero without an upstream
+    pub ahead: usize,
+    pub behind: usize,
+}
+
+impl GitStatus {
+    /// Status of the checkout containing `dir`, or `None` outside a
+    /// repository or without a `git` executable
+    pub async fn read(dir: &Path) -> Option<Self> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["status", "--porcelain=v2", "--branch"])
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .output()
+            .await
+            .ok()
+            .filter(|output| output.status.success())?;
+        Self::parse(&String::from_utf8_lossy(&output.stdout))
+    }
+
+    /// Parse `git status --porcelain=v2 --branch` output
+    pub fn parse(output: &str) -> Option<Self> {
+        let mut status = GitStatus::default();
+        let mut oid = None;
+        for line in output.lines() {
+            if let Some(header) = line.strip_prefix("# ") {
+                let (key, value) = header.split_once(' ')?;
+                match key {
+                    "branch.oid" => oid = Some(value),
+                    "branch.head" => status.branch = value.to_string(),
+                    "branch.ab" => {
+                        let (ahead, behind) = value.split_once(' ')?;
+                        status.ahead = ahead.trim_start_matches('+').parse().ok()?;
+                        status.behind = behind.trim_start_matches('-').parse().ok()?;
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+            let mut fields = line.split(' ');
+            match fields.next() {
+                // Ordinary and renamed entries carry index and worktree states
+                Some("1" | "2") => {
+                    let xy = fields.next()?.as_bytes();
+                    if xy.first().is_some_and(|x| *x != b'.') {
+                        status.staged += 1;
+                    }
+                    if xy.get(1).is_some_and(|y| *y != b'.') {
+                        status.dirty += 1;
+                    }
+                }
+                Some("u") => status.dirty += 1,
+                Some("?") => status.untracked += 1,
+                _ => {}
+            }
+        }
+        if status.branch == "(detached)" {
+            status.branch = oid?.chars().take(7).collect();
+        }
+        (!status.branch.is_empty()).then_some(status)
+    }
+
+    /// The branch followed by whatever is non-zero, e.g. `main ↑1 +2 ~3 ?1`
+    pub fn summary(&self) -> String {
+        let mut summary = self.branch.clone();
+        for (symbol, count) in [
+            ("↑", self.ahead),
+            ("↓", self.behind),
+            ("+", self.staged),
+            ("~", self.dirty),
+            ("?", self.untracked),
+        ] {
+            if count > 0 {
+                summary.push_str(&format!(" {}{}", symbol, count));
+            }
+        }
+        summary
+    }
+}
+
+/// Commit `paths` in the repository containing `dir` with `message`,
+/// leaving anything else in the index alone. Returns the short hash of the
+/// new commit, or `None` when the files match what is committed.
+pub async fn commit_paths(dir: &Path, paths: &[PathBuf], message: &str) -> Result<Option<String>> {
+    let with_paths = |args: &[&str]| -> Vec<OsString> {
+        args.iter()
+            .map(OsString::from)
+            .chain(std::iter::once("--".into()))
+            .chain(paths.iter().map(|path| path.clone().into_os_string()))
+            .collect()
+    };
+    git(dir, &with_paths(&["add"])).await?;
+    if run(dir, None, &with_paths(&["diff", "--cached", "--quiet"]))
+        .await?
+        .status
+        .success()
+    {
+        return Ok(None);
+    }
+    git(dir, &with_paths(&["commit", "--only", "-m", message])).await?;
+    let output = git(dir, &["rev-parse".into(), "--short".into(), "HEAD".into()]).await?;
+    Ok(Some(
+        String::from_utf8_lossy(&output.stdout).trim().to_string(),
+    ))
+}
+
+/// Commit `paths` as they are in the working tree on top of `base`, in a
+/// temporary index, so the checked out branch, HEAD and the real index stay
+/// as they are. Returns the new commit's hash, or `None` when the files
+/// match what `base` has.
+pub async fn commit_onto(
+    dir: &Path,
+    base: &str,
+    paths: &[PathBuf],
+    message: &str,
+) -> Result<Option<String>> {
+    let scratch = tempfile::tempdir().context("Failed to create a temporary index")?;
+    let index = scratch.path().join("index");
+    let index = Some(index.as_path());
+    git_with_index(dir, index, &["read-tree".into(), base.into()]).await?;
+    let add: Vec<OsString> = ["add", "--"]
+        .iter()
+        .map(OsString::from)
+        .chain(paths.iter().map(|path| path.clone().into_os_string()))
+        .collect();
+    git_with_index(dir, index, &add).await?;
+    let tree = stdout(git_with_index(dir, index, &["write-tree".into()]).await?);
+    let base_tree = stdout(
+        git(
+            dir,
+            &["rev-parse".into(), format!("{}^{{tree}}", base).into()],
+        )
+        .await?,
+    );
+    if tree == base_tree {
+        return Ok(None);
+    }
+    let args: Vec<OsString> = ["commit-tree", &tree, "-p", base, "-m", message]
+        .iter()
+        .map(OsString::from)
+        .collect();
+    Ok(Some(stdout(git(dir, &args).await?)))
+}
+
+/// The commit a pull request branch builds on: the branch as last pushed to
+/// `remote`, so another push fast-forwards it, else the remote's default
+/// branch, else HEAD
+pub async fn pull_request_base(dir: &Path, remote: &str, branch: &str) -> Result<String> {
+    for candidate in [
+        format!("refs/remotes/{}/{}", remote, branch),
+        format!("refs/remotes/{}/HEAD", remote),
+        "HEAD".to_string(),
+    ] {
+        let args = [
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("{}^{{commit}}", candidate),
+        ]
+        .map(OsString::from);
+        let output = run(dir, None, &args).await?;
+        if output.status.success() {
+            return Ok(stdout(output));
+        }
+    }
+    anyhow::bail!(
+        "{} has no commits to build a pull request on",
+        dir.display()
+    )
+}
+
+/// Fetch URL of `remote` in the repository containing `dir`
+pub async fn remote_url(dir: &Path, remote: &str) -> Result<String> {
+    let output = git(dir, &["remote".into(), "get-url".into(), remote.into()]).await?;
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Push `commit` to `branch` on `remote`, creating or fast-forwarding it
+pub async fn push_commit(dir: &Path, remote: &str, commit: &str, branch: &str) -> Result<()> {
+    let refspec = format!("{}:refs/heads/{}", commit, branch);
+    git(dir, &["push".into(), remote.into(), refspec.into()]).await?;
+    Ok(())
+}
+
+/// Run git in `dir`, failing with its error output when it exits non-zero
+async fn git(dir: &Path, args: &[OsString]) -> Result<Output> {
+    git_with_index(dir, None, args).await
+}
+
+/// `git`, with `index` in place of the repository's index when given
+async fn git_with_index(dir: &Path, index: Option<&Path>, args: &[OsString]) -> Result<Output> {
+    let output = run(dir, index, args).await?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        // Progress and hints come first; the reason is on an error line
+        let reason = stderr
+            .lines()
+            .find(|line| line.starts_with("fatal:") || line.starts_with("error:"))
+            .or_else(|| stderr.lines().next())
+            .unwrap_or("");
+        anyhow::bail!(
+            "git {} failed: {}",
+            args[0].to_string_lossy(),
+            reason.trim()
+        );
+    }
+    Ok(output)
+}
+
+async fn run(dir: &Path, index: Option<&Path>, args: &[OsString]) -> Result<Output> {
+    let mut command = Command::new("git");
+    if let Some(index) = index {
+        command.env("GIT_INDEX_FILE", index);
+    }
+    command
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("Failed to run git")
+}
+
+fn stdout(output: Output) -> String {
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_changes_and_tracking_from_porcelain_output() {
+        let status = GitStatus::parse(
+            "# branch.oid 0123456789abcdef\n\
+             # branch.head feature/git\n\
+             # branch.upstream origin/feature/git\n\
+             # branch.ab +2 -1\n\
+             1 M. N... 100644 100644 100644 aaa bbb src/lib.rs\n\
+             1 MM N... 100644 100644 100644 aaa bbb src/main.rs\n\
+             1 .M N... 100644 100644 100644 aaa bbb README.md\n\
+             2 R. N... 100644 100644 100644 aaa bbb R100 new.rs\told.rs\n\
+             u UU N... 100644 100644 100644 100644 aaa bbb ccc conflict.rs\n\
+             ? notes.txt\n",
+        )
+        .unwrap();
+        assert_eq!(
+            status,
+            GitStatus {
+                branch: "feature/git".to_string(),
+                staged: 3,
+                dirty: 3,
+                untracked: 1,
+                ahead: 2,
+                behind: 1,
+            }
+        );
+        assert_eq!(status.summary(), "feature/git ↑2 ↓1 +3 ~3 ?1");
+
+        let detached =
+            GitStatus::parse("# branch.oid 0123456789abcdef\n# branch.head (detached)\n").unwrap();
+        assert_eq!(detached.summary(), "0123456");
+        assert_eq!(GitStatus::parse(""), None);
+    }
+
+    #[tokio::test]
+    async fn commits_only_the_given_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path();
+        for args in [
+            &["init", "-q"][..],
+            &["config", "user.name", "RAT"],
+            &["config", "user.email", "rat@example.com"],
+        ] {
+            let args: Vec<OsString> = args.iter().map(OsString::from).collect();
+            git(repo, &args).await.unwrap();
+        }
+        std::fs::write(repo.join("edited.rs"), "fn main() {}\n").unwrap();
+        std::fs::write(repo.join("staged.rs"), "// mine\n").unwrap();
+        git(repo, &["add".into(), "staged.rs".into()])
+            .await
+            .unwrap();
+
+        let edited = [repo.join("edited.rs")];
+        let hash = commit_paths(repo, &edited, "gemini: add main")
+            .await
+            .unwrap();
+        assert!(hash.is_some());
+        // Unchanged since the last commit
+        assert_eq!(commit_paths(repo, &edited, "again").await.unwrap(), None);
+
+        let status = GitStatus::read(repo).await.unwrap();
+        assert_eq!((status.staged, status.untracked), (1, 0));
+    }
+
+    #[tokio::test]
+    async fn commits_onto_a_base_without_touching_the_checkout() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path();
+        let git_in = |args: &[&str]| {
+            let args: Vec<OsString> = args.iter().map(OsString::from).collect();
+            async move { stdout(git(repo, &args).await.unwrap()) }
+        };
+        git_in(&["init", "-q", "-b", "main"]).await;
+        git_in(&["config", "user.name", "RAT"]).await;
+        git_in(&["config", "user.email", "rat@example.com"]).await;
+        std::fs::write(repo.join("README.md"), "base\n").unwrap();
+        git_in(&["add", "README.md"]).await;
+        git_in(&["commit", "-q", "-m", "base"]).await;
+        let base = pull_request_base(repo, "origin", "rat/work").await.unwrap();
+
+        // Local work the pull request must not pick up
+        git_in(&["checkout", "-q", "-b", "feature"]).await;
+        std::fs::write(repo.join("local.rs"), "// local\n").unwrap();
+        git_in(&["add", "local.rs"]).await;
+        git_in(&["commit", "-q", "-m", "local"]).await;
+        std::fs::write(repo.join("staged.rs"), "// staged\n").unwrap();
+        git_in(&["add", "staged.rs"]).await;
+        std::fs::write(repo.join("edited.rs"), "fn main() {}\n").unwrap();
+        let head = git_in(&["rev-parse", "HEAD"]).await;
+
+        let edited = [repo.join("edited.rs")];
+        let commit = commit_onto(repo, &base, &edited, "gemini: add main")
+            .await
+            .unwrap()
+            .unwrap();
+        let files = git_in(&["ls-tree", "--name-only", &commit]).await;
+        assert_eq!(files, "README.md\nedited.rs");
+        assert_eq!(git_in(&["rev-parse", &format!("{}^", commit)]).await, base);
+        // Branch, HEAD and index as they were
+        assert_eq!(git_in(&["branch", "--show-current"]).await, "feature");
+        assert_eq!(git_in(&["rev-parse", "HEAD"]).await, head);
+        assert_eq!(
+            git_in(&["diff", "--cached", "--name-only"]).await,
+            "staged.rs"
+        );
+
+        // Nothing to commit when the files match the base
+        let unchanged = [repo.join("README.md")];
+        assert_eq!(
+            commit_onto(repo, &base, &unchanged, "again").await.unwrap(),
+            None
+        );
+    }
+}
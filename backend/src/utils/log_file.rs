@@ -0,0 +1,135 @@
+use anyhow::{Context, Result};
+use std::ffi::OsString;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::config::LoggingConfig;
+
+/// Append-only log file that rotates by size and/or age. Rotated files are
+/// `<name>.1` (newest) through `<name>.<keep>`; older ones are deleted.
+pub struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    started: SystemTime,
+    max_size: Option<u64>,
+    max_age: Option<Duration>,
+    keep: usize,
+}
+
+impl RotatingFile {
+    pub fn open(path: PathBuf, config: &LoggingConfig) -> Result<Self> {
+        let max_size = (config.max_size_mb > 0).then(|| config.max_size_mb * 1024 * 1024);
+        let max_age =
+            (config.max_age_hours > 0).then(|| Duration::from_secs(config.max_age_hours * 3600));
+        Self::with_limits(path, max_size, max_age, config.keep)
+    }
+
+    fn with_limits(
+        path: PathBuf,
+        max_size: Option<u64>,
+        max_age: Option<Duration>,
+        keep: usize,
+    ) -> Result<Self> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create log directory: {:?}", parent))?;
+        }
+        let file = open_append(&path)
+            .with_context(|| format!("Failed to open log file: {:?}", path))?;
+        let metadata = file.metadata()?;
+        let started = metadata
+            .created()
+            .or_else(|_| metadata.modified())
+            .unwrap_or_else(|_| SystemTime::now());
+        Ok(Self {
+            path,
+            file,
+            size: metadata.len(),
+            started,
+            max_size,
+            max_age,
+            keep,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn due(&self) -> bool {
+        let too_big = self.max_size.is_some_and(|max| self.size >= max);
+        let too_old = self
+            .max_age
+            .is_some_and(|age| self.started.elapsed().map(|e| e >= age).unwrap_or(false));
+        self.size > 0 && (too_big || too_old)
+    }
+
+    fn rotated(&self, n: usize) -> PathBuf {
+        let mut name = OsString::from(self.path.as_os_str());
+        name.push(format!(".{}", n));
+        PathBuf::from(name)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        if self.keep == 0 {
+            std::fs::remove_file(&self.path)?;
+        } else {
+            // Drop the oldest and shift the rest up by one
+            let _ = std::fs::remove_file(self.rotated(self.keep));
+            for n in (1..self.keep).rev() {
+                let _ = std::fs::rename(self.rotated(n), self.rotated(n + 1));
+            }
+            std::fs::rename(&self.path, self.rotated(1))?;
+        }
+        self.file = open_append(&self.path)?;
+        self.size = 0;
+        self.started = SystemTime::now();
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.due() {
+            // Keep logging to the current file if rotation fails
+            let _ = self.rotate();
+        }
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotates_by_size_and_keeps_limited_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("logs").join("rat.log");
+        let mut log = RotatingFile::with_limits(path.clone(), Some(10), None, 2).unwrap();
+
+        for line in ["first line\n", "second line\n", "third line\n", "fourth line\n"] {
+            log.write_all(line.as_bytes()).unwrap();
+        }
+        log.flush().unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "fourth line\n");
+        assert_eq!(std::fs::read_to_string(log.rotated(1)).unwrap(), "third line\n");
+        assert_eq!(std::fs::read_to_string(log.rotated(2)).unwrap(), "second line\n");
+        assert!(!log.rotated(3).exists());
+    }
+}
@@ -1,3 +1,12 @@
+pub mod audit;
 pub mod diff;
+pub mod git;
+pub mod log_file;
+pub mod logging;
+pub mod msgpack;
+pub mod ndjson;
+pub mod process;
+#[cfg(feature = "tui")]
 pub mod syntax;
+#[cfg(feature = "tui")]
 pub mod terminal;
@@ -0,0 +1,418 @@
+//! MessagePack encoding and framing for `rat --embed`, which speaks
+//! msgpack-RPC on stdio. Values map to and from `serde_json::Value`: binary
+//! strings are read as (lossy) UTF-8, map keys that aren't strings are
+//! written out as JSON, and extension types such as Neovim's buffer and
+//! window handles become their integer handle, or null when they hold
+//! anything else. The input is whatever the frontend sends, so malformed
+//! data is reported, never unwrapped.
+
+use anyhow::Result;
+use serde_json::{Map, Number, Value};
+
+/// Largest message held while waiting for the rest of it
+pub const MAX_MESSAGE_BYTES: usize = 64 * 1024 * 1024;
+
+/// Arrays and maps nested deeper than this are rejected
+const MAX_DEPTH: usize = 128;
+
+/// Append the MessagePack encoding of `value` to `out`
+pub fn encode(value: &Value, out: &mut Vec<u8>) {
+    match value {
+        Value::Null => out.push(0xc0),
+        Value::Bool(false) => out.push(0xc2),
+        Value::Bool(true) => out.push(0xc3),
+        Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                encode_int(i, out);
+            } else if let Some(u) = n.as_u64() {
+                out.push(0xcf);
+                out.extend_from_slice(&u.to_be_bytes());
+            } else {
+                out.push(0xcb);
+                out.extend_from_slice(&n.as_f64().unwrap_or_default().to_be_bytes());
+            }
+        }
+        Value::String(s) => encode_str(s, out),
+        Value::Array(items) => {
+            encode_len(items.len(), 0x90, 0xdc, out);
+            for item in items {
+                encode(item, out);
+            }
+        }
+        Value::Object(map) => {
+            encode_len(map.len(), 0x80, 0xde, out);
+            for (key, item) in map {
+                encode_str(key, out);
+                encode(item, out);
+            }
+        }
+    }
+}
+
+fn encode_int(i: i64, out: &mut Vec<u8>) {
+    match i {
+        0..=0x7f => out.push(i as u8),
+        -32..=-1 => out.push(i as i8 as u8),
+        0x80..=0xff => out.extend_from_slice(&[0xcc, i as u8]),
+        0x100..=0xffff => {
+            out.push(0xcd);
+            out.extend_from_slice(&(i as u16).to_be_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            out.push(0xce);
+            out.extend_from_slice(&(i as u32).to_be_bytes());
+        }
+        0x1_0000_0000.. => {
+            out.push(0xcf);
+            out.extend_from_slice(&(i as u64).to_be_bytes());
+        }
+        -0x80..=-33 => out.extend_from_slice(&[0xd0, i as i8 as u8]),
+        -0x8000..=-0x81 => {
+            out.push(0xd1);
+            out.extend_from_slice(&(i as i16).to_be_bytes());
+        }
+        -0x8000_0000..=-0x8001 => {
+            out.push(0xd2);
+            out.extend_from_slice(&(i as i32).to_be_bytes());
+        }
+        _ => {
+            out.push(0xd3);
+            out.extend_from_slice(&i.to_be_bytes());
+        }
+    }
+}
+
+fn encode_str(s: &str, out: &mut Vec<u8>) {
+    match s.len() {
+        len @ 0..=31 => out.push(0xa0 | len as u8),
+        len @ 32..=0xff => out.extend_from_slice(&[0xd9, len as u8]),
+        len @ 0x100..=0xffff => {
+            out.push(0xda);
+            out.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            out.push(0xdb);
+            out.extend_from_slice(&(len as u32).to_be_bytes());
+        }
+    }
+    out.extend_from_slice(s.as_bytes());
+}
+
+/// Header of an array (`fix` for up to 15 items, then 16 and 32 bit lengths
+/// from `wide`) or a map
+fn encode_len(len: usize, fix: u8, wide: u8, out: &mut Vec<u8>) {
+    if len <= 15 {
+        out.push(fix | len as u8);
+    } else if len <= 0xffff {
+        out.push(wide);
+        out.extend_from_slice(&(len as u16).to_be_bytes());
+    } else {
+        out.push(wide + 1);
+        out.extend_from_slice(&(len as u32).to_be_bytes());
+    }
+}
+
+/// Why a buffer couldn't be decoded
+#[derive(Debug)]
+enum Malformed {
+    /// The value continues past the end of the buffer
+    Incomplete,
+    Invalid(String),
+}
+
+/// Decodes one value from the front of a buffer
+struct Decoder<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Malformed> {
+        if self.data.len() - self.pos < len {
+            return Err(Malformed::Incomplete);
+        }
+        let bytes = &self.data[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn be(&mut self, len: usize) -> Result<u64, Malformed> {
+        Ok(self
+            .take(len)?
+            .iter()
+            .fold(0, |n, &b| (n << 8) | u64::from(b)))
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Value, Malformed> {
+        if depth > MAX_DEPTH {
+            return Err(Malformed::Invalid("values nested too deeply".to_string()));
+        }
+        let marker = self.take(1)?[0];
+        let value = match marker {
+            0x00..=0x7f => Value::from(marker),
+            0x80..=0x8f => self.map(usize::from(marker & 0x0f), depth)?,
+            0x90..=0x9f => self.array(usize::from(marker & 0x0f), depth)?,
+            0xa0..=0xbf => self.string(usize::from(marker & 0x1f))?,
+            0xc0 => Value::Null,
+            0xc2 => Value::Bool(false),
+            0xc3 => Value::Bool(true),
+            0xc4 => {
+                let len = self.be(1)? as usize;
+                self.string(len)?
+            }
+            0xc5 => {
+                let len = self.be(2)? as usize;
+                self.string(len)?
+            }
+            0xc6 => {
+                let len = self.be(4)? as usize;
+                self.string(len)?
+            }
+            0xc7 => {
+                let len = self.be(1)? as usize;
+                self.ext(len)?
+            }
+            0xc8 => {
+                let len = self.be(2)? as usize;
+                self.ext(len)?
+            }
+            0xc9 => {
+                let len = self.be(4)? as usize;
+                self.ext(len)?
+            }
+            0xca => {
+                let f = f32::from_bits(self.be(4)? as u32);
+                float(f64::from(f))
+            }
+            0xcb => float(f64::from_bits(self.be(8)?)),
+            0xcc => Value::from(self.be(1)?),
+            0xcd => Value::from(self.be(2)?),
+            0xce => Value::from(self.be(4)?),
+            0xcf => Value::from(self.be(8)?),
+            0xd0 => Value::from(self.be(1)? as u8 as i8),
+            0xd1 => Value::from(self.be(2)? as u16 as i16),
+            0xd2 => Value::from(self.be(4)? as u32 as i32),
+            0xd3 => Value::from(self.be(8)? as i64),
+            0xd4 => self.ext(1)?,
+            0xd5 => self.ext(2)?,
+            0xd6 => self.ext(4)?,
+            0xd7 => self.ext(8)?,
+            0xd8 => self.ext(16)?,
+            0xd9 => {
+                let len = self.be(1)? as usize;
+                self.string(len)?
+            }
+            0xda => {
+                let len = self.be(2)? as usize;
+                self.string(len)?
+            }
+            0xdb => {
+                let len = self.be(4)? as usize;
+                self.string(len)?
+            }
+            0xdc => {
+                let len = self.be(2)? as usize;
+                self.array(len, depth)?
+            }
+            0xdd => {
+                let len = self.be(4)? as usize;
+                self.array(len, depth)?
+            }
+            0xde => {
+                let len = self.be(2)? as usize;
+                self.map(len, depth)?
+            }
+            0xdf => {
+                let len = self.be(4)? as usize;
+                self.map(len, depth)?
+            }
+            0xe0..=0xff => Value::from(marker as i8),
+            0xc1 => return Err(Malformed::Invalid("reserved byte 0xc1".to_string())),
+        };
+        Ok(value)
+    }
+
+    fn string(&mut self, len: usize) -> Result<Value, Malformed> {
+        Ok(Value::String(
+            String::from_utf8_lossy(self.take(len)?).into_owned(),
+        ))
+    }
+
+    /// An extension's type byte and data; Neovim handles are an integer
+    fn ext(&mut self, len: usize) -> Result<Value, Malformed> {
+        self.take(1)?;
+        let data = self.take(len)?;
+        let mut handle = Decoder { data, pos: 0 };
+        match handle.value(MAX_DEPTH) {
+            Ok(value @ Value::Number(_)) if handle.pos == data.len() => Ok(value),
+            _ => Ok(Value::Null),
+        }
+    }
+
+    fn array(&mut self, len: usize, depth: usize) -> Result<Value, Malformed> {
+        // Every item takes at least a byte, so a bogus length can't reserve
+        // more than the buffer holds
+        let mut items = Vec::with_capacity(len.min(self.data.len() - self.pos));
+        for _ in 0..len {
+            items.push(self.value(depth + 1)?);
+        }
+        Ok(Value::Array(items))
+    }
+
+    fn map(&mut self, len: usize, depth: usize) -> Result<Value, Malformed> {
+        let mut map = Map::new();
+        for _ in 0..len {
+            let key = match self.value(depth + 1)? {
+                Value::String(key) => key,
+                key => key.to_string(),
+            };
+            map.insert(key, self.value(depth + 1)?);
+        }
+        Ok(Value::Object(map))
+    }
+}
+
+/// JSON has no NaN or infinity
+fn float(f: f64) -> Value {
+    Number::from_f64(f).map_or(Value::Null, Value::Number)
+}
+
+/// Buffering for a MessagePack stream. Values carry no delimiter, so a read
+/// may end mid-value or hold several; bytes are kept until a value is whole.
+#[derive(Debug, Default)]
+pub struct MsgpackFramer {
+    buf: Vec<u8>,
+}
+
+impl MsgpackFramer {
+    /// Append `data` and return the values it completes. Malformed input is
+    /// an error the stream can't recover from: values have no boundaries to
+    /// resynchronise on.
+    pub fn push(&mut self, data: &[u8]) -> Result<Vec<Value>> {
+        self.buf.extend_from_slice(data);
+        let mut values = Vec::new();
+        let mut start = 0;
+        while start < self.buf.len() {
+            let mut decoder = Decoder {
+                data: &self.buf[start..],
+                pos: 0,
+            };
+            match decoder.value(0) {
+                Ok(value) => {
+                    values.push(value);
+                    start += decoder.pos;
+                }
+                Err(Malformed::Incomplete) => break,
+                Err(Malformed::Invalid(reason)) => {
+                    anyhow::bail!("malformed MessagePack: {}", reason)
+                }
+            }
+        }
+        self.buf.drain(..start);
+        if self.buf.len() > MAX_MESSAGE_BYTES {
+            anyhow::bail!(
+                "MessagePack message larger than {} bytes",
+                MAX_MESSAGE_BYTES
+            );
+        }
+        Ok(values)
+    }
+
+    /// Whether bytes of an unfinished value are waiting
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn round_trip(value: Value) {
+        let mut bytes = Vec::new();
+        encode(&value, &mut bytes);
+        let mut framer = MsgpackFramer::default();
+        assert_eq!(framer.push(&bytes).unwrap(), vec![value]);
+        assert!(framer.is_empty());
+    }
+
+    #[test]
+    fn round_trips_values() {
+        round_trip(json!(null));
+        round_trip(json!([true, false]));
+        for n in [
+            0,
+            127,
+            128,
+            255,
+            256,
+            65536,
+            i64::MAX,
+            -1,
+            -32,
+            -33,
+            -129,
+            -40000,
+            i64::MIN,
+        ] {
+            round_trip(json!(n));
+        }
+        round_trip(json!(u64::MAX));
+        round_trip(json!(1.5));
+        round_trip(json!("x".repeat(31)));
+        round_trip(json!("é".repeat(200)));
+        round_trip(json!("y".repeat(70000)));
+        round_trip(json!((0..20).collect::<Vec<_>>()));
+        round_trip(json!({"method": "prompt", "params": [{"a": {}}, []]}));
+    }
+
+    #[test]
+    fn encodes_compactly() {
+        let mut bytes = Vec::new();
+        encode(&json!([0, 1, "nvim_exec_lua", [-1, 200]]), &mut bytes);
+        assert_eq!(
+            bytes,
+            [
+                0x94, 0x00, 0x01, 0xad, b'n', b'v', b'i', b'm', b'_', b'e', b'x', b'e', b'c', b'_',
+                b'l', b'u', b'a', 0x92, 0xff, 0xcc, 200
+            ]
+        );
+    }
+
+    #[test]
+    fn frames_split_and_joined_reads() {
+        let mut bytes = Vec::new();
+        encode(&json!([0, 1, "agents", []]), &mut bytes);
+        encode(&json!([2, "ping", []]), &mut bytes);
+        let mut framer = MsgpackFramer::default();
+        assert!(framer.push(&bytes[..3]).unwrap().is_empty());
+        assert!(!framer.is_empty());
+        assert_eq!(
+            framer.push(&bytes[3..]).unwrap(),
+            vec![json!([0, 1, "agents", []]), json!([2, "ping", []])]
+        );
+    }
+
+    #[test]
+    fn reads_what_neovim_sends() {
+        // bin 8 "ab", an ext 0 buffer handle, a float 32 and an integer key
+        let bytes = [
+            0x84, 0xa1, b'b', 0xc4, 0x02, b'a', b'b', 0xa1, b'h', 0xd4, 0x00, 0x07, 0xa1, b'f',
+            0xca, 0x3f, 0xc0, 0x00, 0x00, 0x01, 0xc0,
+        ];
+        let mut framer = MsgpackFramer::default();
+        assert_eq!(
+            framer.push(&bytes).unwrap(),
+            vec![json!({"b": "ab", "h": 7, "f": 1.5, "1": null})]
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        let mut framer = MsgpackFramer::default();
+        assert!(framer.push(&[0x91, 0xc1]).is_err());
+        let mut framer = MsgpackFramer::default();
+        assert!(framer.push(&[0x91; MAX_DEPTH + 2]).is_err());
+    }
+}
@@ -0,0 +1,262 @@
+//! NDJSON framing for the ACP bridge, and parsing of the agent requests it
+//! answers itself. Everything here takes untrusted bytes from agents and
+//! WebSocket clients, so malformed input is reported or passed through,
+//! never unwrapped.
+
+use anyhow::Result;
+use flate2::read::GzDecoder;
+use serde_json::Value;
+use std::io::Read as _;
+
+/// Largest message a compressed frame may expand to
+pub const MAX_INFLATED_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Longest line the framer holds on to
+pub const MAX_FRAME_BYTES: usize = 16 * 1024 * 1024;
+
+/// Line-buffered NDJSON framing for the agent's stdio. A single read may end
+/// mid-message or carry several messages, so bytes are held until a newline
+/// completes each frame, up to [`MAX_FRAME_BYTES`].
+#[derive(Debug, Default)]
+pub struct NdjsonFramer {
+    buf: Vec<u8>,
+    /// Inside a line that grew too long; the rest of it is thrown away
+    skipping: bool,
+}
+
+impl NdjsonFramer {
+    /// Append `data` and return the non-blank lines it completes, without
+    /// newlines. A line over [`MAX_FRAME_BYTES`] is dropped and reported as
+    /// an error in its place; framing picks up again after its newline.
+    pub fn push(&mut self, data: &[u8]) -> Vec<Result<Vec<u8>>> {
+        let mut data = data;
+        if self.skipping {
+            let Some(end) = data.iter().position(|&b| b == b'\n') else {
+                return Vec::new();
+            };
+            self.skipping = false;
+            data = &data[end + 1..];
+        }
+        self.buf.extend_from_slice(data);
+        let mut frames = Vec::new();
+        let mut start = 0;
+        while let Some(len) = self.buf[start..].iter().position(|&b| b == b'\n') {
+            let line = trim_frame(&self.buf[start..start + len]);
+            if len > MAX_FRAME_BYTES {
+                frames.push(Err(frame_too_large()));
+            } else if !line.is_empty() {
+                frames.push(Ok(line.to_vec()));
+            }
+            start += len + 1;
+        }
+        self.buf.drain(..start);
+        if self.buf.len() > MAX_FRAME_BYTES {
+            self.buf = Vec::new();
+            self.skipping = true;
+            frames.push(Err(frame_too_large()));
+        }
+        frames
+    }
+
+    /// Whatever follows the last newline, e.g. an unterminated message at EOF
+    pub fn finish(&mut self) -> Option<Vec<u8>> {
+        self.skipping = false;
+        let rest = std::mem::take(&mut self.buf);
+        let line = trim_frame(&rest);
+        (!line.is_empty()).then(|| line.to_vec())
+    }
+}
+
+fn frame_too_large() -> anyhow::Error {
+    anyhow::anyhow!("message is over {} bytes", MAX_FRAME_BYTES)
+}
+
+fn trim_frame(line: &[u8]) -> &[u8] {
+    let end = line
+        .iter()
+        .rposition(|b| !b.is_ascii_whitespace())
+        .map_or(0, |i| i + 1);
+    let start = line[..end]
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(end);
+    &line[start..end]
+}
+
+/// Messages in a WebSocket text frame, each to be written as one NDJSON line.
+/// Pretty-printed JSON is compacted; otherwise every non-blank line is a message.
+pub fn ws_text_frames(text: &str) -> Vec<String> {
+    let text = text.trim();
+    if !text.contains('\n') {
+        return if text.is_empty() {
+            Vec::new()
+        } else {
+            vec![text.to_string()]
+        };
+    }
+    if let Ok(v) = serde_json::from_str::<Value>(text) {
+        return vec![v.to_string()];
+    }
+    text.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Whether a binary frame is a gzip stream rather than raw NDJSON, which can
+/// never start with these bytes
+pub fn is_gzip(data: &[u8]) -> bool {
+    data.starts_with(&[0x1f, 0x8b])
+}
+
+/// Inflate a compressed frame, refusing ones over [`MAX_INFLATED_BYTES`]
+pub fn gunzip(data: &[u8]) -> Result<String> {
+    let mut text = String::new();
+    GzDecoder::new(data)
+        .take(MAX_INFLATED_BYTES + 1)
+        .read_to_string(&mut text)?;
+    if text.len() as u64 > MAX_INFLATED_BYTES {
+        return Err(anyhow::anyhow!(
+            "message is over {} bytes",
+            MAX_INFLATED_BYTES
+        ));
+    }
+    Ok(text)
+}
+
+/// An agent request the bridge carries out locally instead of forwarding
+#[derive(Debug, Clone, PartialEq)]
+pub enum LocalRequest {
+    WriteTextFile {
+        path: String,
+        content: String,
+    },
+    ReadTextFile {
+        path: String,
+    },
+    CreateDir {
+        path: String,
+    },
+    DeleteFile {
+        path: String,
+    },
+    Rename {
+        from: String,
+        to: String,
+    },
+    /// `cmd` may be empty, which the bridge answers with an error
+    Execute {
+        cmd: String,
+        args: Vec<String>,
+        cwd: Option<String>,
+    },
+}
+
+/// A [`LocalRequest`] and the JSON-RPC id to answer it with (`null` if the
+/// agent sent none)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Intercepted {
+    pub id: Value,
+    pub request: LocalRequest,
+}
+
+/// The local request in an agent message, if it is one. Missing or
+/// mistyped parameters read as empty strings.
+pub fn intercept(message: &Value) -> Option<Intercepted> {
+    let method = message.get("method")?.as_str()?;
+    let params = message.get("params");
+    let string = |key: &str| {
+        params
+            .and_then(|p| p.get(key))
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+    let request = match method {
+        "fs/write_text_file" => LocalRequest::WriteTextFile {
+            path: string("path"),
+            content: string("content"),
+        },
+        "fs/read_text_file" => LocalRequest::ReadTextFile {
+            path: string("path"),
+        },
+        "fs/mkdir" | "fs/create_dir" => LocalRequest::CreateDir {
+            path: string("path"),
+        },
+        "fs/delete_file" | "fs/remove_file" => LocalRequest::DeleteFile {
+            path: string("path"),
+        },
+        "fs/rename" | "fs/move" => LocalRequest::Rename {
+            from: string("from"),
+            to: string("to"),
+        },
+        "terminal/execute" => LocalRequest::Execute {
+            cmd: string("cmd"),
+            args: params
+                .and_then(|p| p.get("args"))
+                .and_then(Value::as_array)
+                .map(|args| {
+                    args.iter()
+                        .filter_map(|arg| arg.as_str().map(str::to_string))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            cwd: params
+                .and_then(|p| p.get("cwd"))
+                .and_then(Value::as_str)
+                .map(str::to_string),
+        },
+        _ => return None,
+    };
+    Some(Intercepted {
+        id: message.get("id").cloned().unwrap_or(Value::Null),
+        request,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn intercepts_local_requests_and_tolerates_bad_params() {
+        let write = json!({"id": 7, "method": "fs/write_text_file", "params": {"path": "a.txt", "content": "hi"}});
+        assert_eq!(
+            intercept(&write),
+            Some(Intercepted {
+                id: json!(7),
+                request: LocalRequest::WriteTextFile {
+                    path: "a.txt".into(),
+                    content: "hi".into()
+                },
+            })
+        );
+
+        let execute = json!({"method": "terminal/execute", "params": {"cmd": "ls", "args": ["-l", 3], "cwd": null}});
+        assert_eq!(
+            intercept(&execute).map(|i| (i.id, i.request)),
+            Some((
+                Value::Null,
+                LocalRequest::Execute {
+                    cmd: "ls".into(),
+                    args: vec!["-l".into()],
+                    cwd: None
+                }
+            ))
+        );
+
+        // Parameters of the wrong shape don't panic
+        let odd = json!({"id": 1, "method": "fs/rename", "params": [1, 2]});
+        assert_eq!(
+            intercept(&odd).map(|i| i.request),
+            Some(LocalRequest::Rename {
+                from: String::new(),
+                to: String::new()
+            })
+        );
+        assert_eq!(intercept(&json!({"method": "session/update"})), None);
+        assert_eq!(intercept(&json!([1, 2, 3])), None);
+    }
+}
@@ -0,0 +1,156 @@
+use std::io;
+use tokio::process::Child;
+
+/// Signals RAT can deliver to a child process it spawned on an agent's behalf.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcessSignal {
+    /// Polite interrupt (SIGINT on Unix), lets the command clean up
+    Interrupt,
+    /// Hard kill (SIGKILL on Unix)
+    Kill,
+}
+
+impl ProcessSignal {
+    /// Parse a signal name as sent by bridge clients ("SIGINT", "int", "kill", ...)
+    pub fn from_name(name: &str) -> Option<Self> {
+        let upper = name.trim().to_ascii_uppercase();
+        match upper.strip_prefix("SIG").unwrap_or(&upper) {
+            "INT" | "INTERRUPT" => Some(ProcessSignal::Interrupt),
+            "KILL" => Some(ProcessSignal::Kill),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProcessSignal::Interrupt => "SIGINT",
+            ProcessSignal::Kill => "SIGKILL",
+        }
+    }
+}
+
+impl std::fmt::Display for ProcessSignal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Deliver `signal` to `child` without waiting for it to exit.
+///
+/// On non-Unix platforms there is no SIGINT equivalent, so an interrupt
+/// falls back to a hard kill.
+pub fn signal_child(child: &mut Child, signal: ProcessSignal) -> io::Result<()> {
+    match signal {
+        ProcessSignal::Kill => child.start_kill(),
+        ProcessSignal::Interrupt => {
+            #[cfg(unix)]
+            {
+                let Some(pid) = child.id() else {
+                    // Already reaped; nothing left to interrupt
+                    return Ok(());
+                };
+                let rc = unsafe { libc::kill(pid as libc::pid_t, libc::SIGINT) };
+                if rc == 0 {
+                    Ok(())
+                } else {
+                    Err(io::Error::last_os_error())
+                }
+            }
+            #[cfg(not(unix))]
+            {
+                child.start_kill()
+            }
+        }
+    }
+}
+
+/// Resolves on SIGTERM or Ctrl-C (SIGINT), whichever comes first.
+pub async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        if let Ok(mut terminate) = signal(SignalKind::terminate()) {
+            tokio::select! {
+                _ = terminate.recv() => {}
+                _ = tokio::signal::ctrl_c() => {}
+            }
+            return;
+        }
+    }
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+/// Job-control suspend requests sent to the process (SIGTSTP, e.g. from
+/// `kill -TSTP`); none ever arrive where there is no job control
+pub struct SuspendRequests {
+    #[cfg(unix)]
+    signal: Option<tokio::signal::unix::Signal>,
+}
+
+impl SuspendRequests {
+    /// Start catching SIGTSTP; from now on it no longer stops the process
+    /// by itself
+    pub fn new() -> Self {
+        #[cfg(unix)]
+        {
+            use tokio::signal::unix::{signal, SignalKind};
+            Self {
+                signal: signal(SignalKind::from_raw(libc::SIGTSTP)).ok(),
+            }
+        }
+        #[cfg(not(unix))]
+        Self {}
+    }
+
+    pub async fn recv(&mut self) {
+        #[cfg(unix)]
+        if let Some(signal) = &mut self.signal {
+            if signal.recv().await.is_some() {
+                return;
+            }
+        }
+        std::future::pending::<()>().await
+    }
+}
+
+impl Default for SuspendRequests {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Stop the process the way the shell's job control does, returning once it
+/// is continued (`fg`, SIGCONT). Does nothing without job control.
+pub fn suspend_self() {
+    #[cfg(unix)]
+    // SIGSTOP can't be caught, so this stops even with SIGTSTP handled
+    unsafe {
+        libc::raise(libc::SIGSTOP);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_signal_names() {
+        assert_eq!(ProcessSignal::from_name("SIGINT"), Some(ProcessSignal::Interrupt));
+        assert_eq!(ProcessSignal::from_name("int"), Some(ProcessSignal::Interrupt));
+        assert_eq!(ProcessSignal::from_name(" sigkill "), Some(ProcessSignal::Kill));
+        assert_eq!(ProcessSignal::from_name("TERM"), None);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn caught_sigtstp_becomes_a_suspend_request() {
+        let mut requests = SuspendRequests::new();
+        // Handled now, so this does not stop the test process
+        unsafe {
+            libc::raise(libc::SIGTSTP);
+        }
+        tokio::time::timeout(std::time::Duration::from_secs(5), requests.recv())
+            .await
+            .expect("suspend request");
+    }
+}
@@ -8,6 +8,62 @@ use crossterm::{
 };
 use std::io::{self, Write};
 
+/// Raw mode, alternate screen, mouse capture and focus reporting for the TUI, undone when
+/// dropped, so an early return or unwinding panic still gives the user
+/// their terminal back
+pub struct TerminalGuard;
+
+impl TerminalGuard {
+    pub fn enter() -> Result<Self> {
+        install_panic_hook();
+        terminal::enable_raw_mode()?;
+        // From here on, failing to finish setup still restores via Drop
+        let guard = TerminalGuard;
+        execute!(
+            io::stdout(),
+            terminal::EnterAlternateScreen,
+            event::EnableMouseCapture,
+            // Desktop notifications only go out while the terminal is unfocused
+            event::EnableFocusChange
+        )?;
+        Ok(guard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore_terminal();
+    }
+}
+
+/// Leave raw mode and the alternate screen, best effort; harmless when the
+/// terminal is already restored
+pub fn restore_terminal() {
+    let _ = terminal::disable_raw_mode();
+    let _ = execute!(
+        io::stdout(),
+        event::DisableMouseCapture,
+        event::DisableFocusChange,
+        terminal::LeaveAlternateScreen,
+        cursor::Show
+    );
+}
+
+/// Restore the terminal before a panic message is printed, so it shows on
+/// the normal screen with proper line breaks. Release builds abort on panic
+/// without unwinding, which leaves this hook as the only cleanup that runs.
+pub fn install_panic_hook() {
+    static INSTALL: std::sync::Once = std::sync::Once::new();
+    INSTALL.call_once(|| {
+        let default_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            restore_terminal();
+            tracing::error!("{}", info);
+            default_hook(info);
+        }));
+    });
+}
+
 /// Terminal utilities for cross-platform terminal operations
 pub struct TerminalUtils;
 
@@ -1,12 +1,10 @@
 use std::collections::HashMap;
 
 use anyhow::Result;
-use tokio::sync::mpsc;
 
 use rat::acp::AcpClient;
 use rat::adapters::agent_installer::AgentInstaller;
 use rat::adapters::manager::AgentManager;
-use rat::app::AppMessage;
 use rat::config::agent::AgentConfig;
 
 fn should_skip() -> bool {
@@ -61,7 +59,7 @@ async fn start_claude_adapter_and_attempt_session() -> Result<()> {
     }
 
     // Message channel to capture app-level events
-    let (tx, mut rx) = mpsc::unbounded_channel::<AppMessage>();
+    let (tx, mut rx) = rat::events::channel(rat::events::DEFAULT_CAPACITY);
 
     // Configure only claude-code to avoid other agents
     let mut agents = AgentConfig::default();
@@ -70,7 +68,7 @@ async fn start_claude_adapter_and_attempt_session() -> Result<()> {
     agents.claude_code.enabled = true;
 
     // Build manager; it will initialize adapters using installer
-    let mut manager = AgentManager::new(agents.clone(), tx.clone(), None).await?;
+    let manager = AgentManager::new(agents.clone(), tx.clone(), None).await?;
 
     // Ensure adapter exists and connect
     manager.connect_agent("claude-code").await?;
@@ -110,7 +108,7 @@ async fn acp_client_start_and_create_session() -> Result<()> {
     let installer = AgentInstaller::new()?;
     let cmd = installer.get_or_install_claude_code().await?;
 
-    let (tx, mut rx) = mpsc::unbounded_channel::<AppMessage>();
+    let (tx, mut rx) = rat::events::channel(rat::events::DEFAULT_CAPACITY);
     let mut client = AcpClient::new(
         "claude-code",
         cmd.path.to_str().unwrap(),
@@ -0,0 +1,78 @@
+use std::path::Path;
+use std::process::{Command, Output};
+
+// Run the rat binary with its config and data dirs inside `home`, so the
+// user's own config never leaks into a test
+fn rat(home: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_rat"))
+        .args(args)
+        .env("HOME", home)
+        .env("XDG_CONFIG_HOME", home.join(".config"))
+        .env("XDG_DATA_HOME", home.join(".local/share"))
+        .env_remove("RAT_BRIDGE_TOKEN")
+        .output()
+        .expect("run rat")
+}
+
+#[test]
+fn cwd_that_does_not_exist_is_a_usage_error() {
+    let home = tempfile::tempdir().unwrap();
+    let output = rat(
+        home.path(),
+        &["--cwd", "/does/not/exist", "--json-errors", "exec", "hi"],
+    );
+
+    assert_eq!(output.status.code(), Some(2));
+    let error: serde_json::Value =
+        serde_json::from_slice(&output.stderr).expect("one JSON error on stderr");
+    assert_eq!(error["error"], "usage");
+    assert!(error["message"]
+        .as_str()
+        .unwrap()
+        .contains("Invalid --cwd: /does/not/exist"));
+}
+
+#[test]
+fn cwd_loads_the_project_config_of_the_workspace() {
+    let home = tempfile::tempdir().unwrap();
+    let workspace = tempfile::tempdir().unwrap();
+    std::fs::write(workspace.path().join(".rat.toml"), "[ui\n").unwrap();
+
+    let output = rat(
+        home.path(),
+        &[
+            "--cwd",
+            workspace.path().to_str().unwrap(),
+            "--json-errors",
+            "exec",
+            "hi",
+        ],
+    );
+
+    assert_eq!(output.status.code(), Some(2));
+    let error: serde_json::Value =
+        serde_json::from_slice(&output.stderr).expect("one JSON error on stderr");
+    assert!(error["message"].as_str().unwrap().contains(".rat.toml"));
+}
+
+#[test]
+fn exec_usage_errors_follow_json_errors_and_quiet() {
+    let home = tempfile::tempdir().unwrap();
+    let output = rat(home.path(), &["--json-errors", "exec"]);
+
+    assert_eq!(output.status.code(), Some(2));
+    let error: serde_json::Value =
+        serde_json::from_slice(&output.stderr).expect("one JSON error on stderr");
+    assert_eq!(error["error"], "usage");
+    assert!(error["message"]
+        .as_str()
+        .unwrap()
+        .contains("exec needs a prompt or --stdin"));
+
+    let output = rat(
+        home.path(),
+        &["--quiet", "--agent", "a", "--agent", "b", "exec", "hi"],
+    );
+    assert_eq!(output.status.code(), Some(2));
+    assert!(output.stderr.is_empty());
+}
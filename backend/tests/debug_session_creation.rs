@@ -1,18 +1,16 @@
 use anyhow::Result;
-use log::info;
+use tracing::info;
 use std::path::PathBuf;
 
 use rat::adapters::agent_installer::AgentInstaller;
 use rat::adapters::claude_code::ClaudeCodeAdapter;
 use rat::adapters::traits::AgentAdapter;
-use rat::app::AppMessage;
 use rat::config::agent::ClaudeCodeConfig;
-use tokio::sync::mpsc;
 
 #[ignore]
 #[tokio::test]
 async fn debug_claude_code_session_creation() -> Result<()> {
-    env_logger::init();
+    tracing_subscriber::fmt::init();
 
     info!("=== Starting debug test for claude-code session creation ===");
 
@@ -41,7 +39,7 @@ async fn debug_claude_code_session_creation() -> Result<()> {
             // Use default config for current struct layout
             let config = ClaudeCodeConfig::default();
 
-            let (message_tx, mut message_rx) = mpsc::unbounded_channel::<AppMessage>();
+            let (message_tx, mut message_rx) = rat::events::channel(rat::events::DEFAULT_CAPACITY);
             let mut adapter = ClaudeCodeAdapter::new(config, message_tx).await?;
             info!("✅ ClaudeCodeAdapter created successfully");
 
@@ -173,7 +171,7 @@ async fn debug_claude_code_session_creation() -> Result<()> {
 #[ignore]
 #[tokio::test]
 async fn test_manual_acp_connection() -> Result<()> {
-    env_logger::init();
+    tracing_subscriber::fmt::init();
 
     info!("=== Testing manual ACP connection ===");
 
@@ -1,11 +1,9 @@
 use rat::acp::AcpClient;
-use rat::app::AppMessage;
-use tokio::sync::mpsc;
 
 #[tokio::test]
 async fn acp_client_can_talk_to_sim_agent_via_cargo() {
     // Build a client that runs the example via cargo
-    let (tx, mut _rx) = mpsc::unbounded_channel::<AppMessage>();
+    let (tx, mut _rx) = rat::events::channel(rat::events::DEFAULT_CAPACITY);
     let mut client = AcpClient::new(
         "sim",
         "cargo",
@@ -45,14 +45,14 @@ impl AgentAdapter for MockAdapter {
 #[tokio::test(flavor = "current_thread")]
 async fn manager_worker_connect_and_create_session() -> Result<()> {
     // Capture app messages
-    let (app_tx, mut app_rx) = mpsc::unbounded_channel::<AppMessage>();
+    let (app_tx, mut app_rx) = rat::events::channel(rat::events::DEFAULT_CAPACITY);
 
     // Start with disabled agents to avoid external processes
     let mut config = AgentConfig::default();
     config.claude_code.enabled = false;
     config.gemini.enabled = false;
 
-    let mut manager = AgentManager::new(config, app_tx, None).await?;
+    let manager = AgentManager::new(config, app_tx, None).await?;
     manager.register_agent(
         "mock".to_string(),
         Box::new(MockAdapter { name: "mock".to_string(), connected: false, sessions: HashMap::new() }),
@@ -80,7 +80,7 @@ async fn test_ui_to_app_message_flow() {
 /// Test manager command processing
 #[tokio::test]
 async fn test_manager_command_processing() {
-    let (message_tx, mut message_rx) = mpsc::unbounded_channel::<AppMessage>();
+    let (message_tx, mut message_rx) = rat::events::channel(rat::events::DEFAULT_CAPACITY);
     let (manager_tx, manager_rx) = mpsc::unbounded_channel::<ManagerCmd>();
 
     let mut config = AgentConfig::default();
@@ -271,7 +271,7 @@ async fn test_error_scenarios() {
 async fn test_adapter_health_monitoring() {
     let config = ClaudeCodeConfig::default();
 
-    let (message_tx, mut message_rx) = mpsc::unbounded_channel::<AppMessage>();
+    let (message_tx, mut message_rx) = rat::events::channel(rat::events::DEFAULT_CAPACITY);
 
     let mut adapter = ClaudeCodeAdapter::new(config, message_tx)
         .await
@@ -0,0 +1,36 @@
+//! The library API as an embedder sees it; builds without the `tui` feature
+
+use rat::config::agent::AgentConfig;
+use rat::{AgentManager, AppMessage, PermissionsConfig, PolicyDecision, SessionId};
+
+#[tokio::test(flavor = "current_thread")]
+async fn agents_report_on_the_channel_they_were_created_with() {
+    let (tx, mut rx) = rat::events::channel(rat::events::DEFAULT_CAPACITY);
+
+    // No agent is enabled, so nothing is spawned
+    let mut config = AgentConfig::default();
+    config.claude_code.enabled = false;
+    config.gemini.enabled = false;
+    let agents = AgentManager::new(config, tx.clone(), None).await.unwrap();
+
+    let policy = PermissionsConfig::from_approve_list("reads").unwrap();
+    assert_eq!(policy.decide(Some("edit"), None), PolicyDecision::Deny);
+    agents.set_permission_policy(policy);
+    assert!(agents.connect_agent("claude-code").await.is_err());
+
+    tx.send(AppMessage::TurnCompleted {
+        agent_name: "claude-code".to_string(),
+        session_id: SessionId("s-1".to_string()),
+        stop_reason: agent_client_protocol::StopReason::EndTurn,
+    })
+    .unwrap();
+    loop {
+        match rx.recv().await.expect("the manager keeps the channel open") {
+            AppMessage::TurnCompleted { session_id, .. } => {
+                assert_eq!(session_id, SessionId("s-1".to_string()));
+                break;
+            }
+            _ => continue,
+        }
+    }
+}
@@ -0,0 +1,25 @@
+//! The panic hook is process-wide, so it is checked in a test binary of its
+//! own where it can't swallow or reroute other tests' panics.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use rat::utils::terminal::install_panic_hook;
+
+#[test]
+fn panic_hook_is_installed_once_and_keeps_the_previous_hook() {
+    static SEEN: AtomicUsize = AtomicUsize::new(0);
+
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if info.payload().downcast_ref::<&str>() == Some(&"terminal guard test") {
+            SEEN.fetch_add(1, Ordering::SeqCst);
+        } else {
+            previous(info);
+        }
+    }));
+    install_panic_hook();
+    install_panic_hook();
+
+    assert!(std::panic::catch_unwind(|| panic!("terminal guard test")).is_err());
+    assert_eq!(SEEN.load(Ordering::SeqCst), 1);
+}
@@ -0,0 +1,471 @@
+//! End-to-end runs of the `rat` binary against `examples/sim_agent`.
+//!
+//! Each test runs `rat exec --output json` with the simulator as its
+//! `--agent-cmd`, so the whole path is covered: process spawn, the ACP
+//! thread, the manager and the headless printer. The NDJSON events are
+//! checked for order, so changes to how the ACP thread forwards updates show
+//! up here.
+
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
+
+/// Exit code of `rat exec` when the agent needs a login
+const EXIT_AUTH_REQUIRED: i32 = 4;
+/// Exit code of `rat exec` when the turn timed out
+const EXIT_TIMEOUT: i32 = 6;
+/// Exit code of `rat exec` when the agent connection failed
+const EXIT_PROTOCOL: i32 = 7;
+
+/// Build the simulator once, next to the `rat` binary under test
+fn sim_agent() -> &'static Path {
+    static SIM_AGENT: OnceLock<PathBuf> = OnceLock::new();
+    SIM_AGENT.get_or_init(|| {
+        let rat = Path::new(env!("CARGO_BIN_EXE_rat"));
+        let profile_dir = rat.parent().expect("binary has a parent directory");
+        let mut build = Command::new(env!("CARGO"));
+        build
+            .args(["build", "--quiet", "--example", "sim_agent"])
+            .current_dir(env!("CARGO_MANIFEST_DIR"));
+        if profile_dir
+            .file_name()
+            .is_some_and(|name| name == "release")
+        {
+            build.arg("--release");
+        }
+        let status = build.status().expect("failed to run cargo build");
+        assert!(status.success(), "building sim_agent failed");
+        profile_dir
+            .join("examples")
+            .join(format!("sim_agent{}", std::env::consts::EXE_SUFFIX))
+    })
+}
+
+struct Run {
+    code: i32,
+    events: Vec<Value>,
+    /// Home and working directory of the run
+    home: tempfile::TempDir,
+}
+
+impl Run {
+    /// Variant names of the session updates, e.g. `Plan` or `EditProposed`
+    fn update_kinds(&self) -> Vec<&str> {
+        self.updates()
+            .filter_map(|update| update.as_object()?.keys().next().map(String::as_str))
+            .collect()
+    }
+
+    fn updates(&self) -> impl Iterator<Item = &Value> {
+        self.events
+            .iter()
+            .filter(|event| event["type"] == "session_update")
+            .map(|event| &event["update"])
+    }
+
+    fn event(&self, kind: &str) -> Option<&Value> {
+        self.events.iter().find(|event| event["type"] == kind)
+    }
+
+    /// Text of the agent's message chunks, concatenated
+    fn answer(&self) -> String {
+        self.updates()
+            .filter_map(|update| update["AgentMessageChunk"]["content"]["text"].as_str())
+            .collect()
+    }
+}
+
+/// Run one prompt against the simulator playing `scenario`, with extra
+/// `rat exec` flags
+fn exec(scenario: &str, speed: &str, timeout_secs: u64, flags: &[&str]) -> Run {
+    exec_with(
+        &["--scenario", scenario, "--speed", speed],
+        timeout_secs,
+        flags,
+    )
+}
+
+/// Run one prompt against the simulator started with `agent_args`
+fn exec_with(agent_args: &[&str], timeout_secs: u64, flags: &[&str]) -> Run {
+    let home = tempfile::tempdir().expect("temp home");
+    let output = Command::new(env!("CARGO_BIN_EXE_rat"))
+        .arg("--agent-cmd")
+        .arg(sim_agent())
+        .args(agent_args.iter().map(|arg| format!("--agent-arg={arg}")))
+        .arg("--cwd")
+        .arg(home.path())
+        .args(["exec", "--output", "json", "--timeout"])
+        .arg(timeout_secs.to_string())
+        .args(flags)
+        .arg("refactor main.rs")
+        // Keep the user's config, sessions and log level out of the run
+        .env("HOME", home.path())
+        .env("XDG_CONFIG_HOME", home.path().join("config"))
+        .env("XDG_DATA_HOME", home.path().join("data"))
+        .env_remove("RUST_LOG")
+        .output()
+        .expect("failed to run rat");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let events = stdout
+        .lines()
+        .map(|line| {
+            serde_json::from_str(line)
+                .unwrap_or_else(|e| panic!("stdout line is not JSON ({e}): {line}"))
+        })
+        .collect();
+    Run {
+        code: output.status.code().expect("rat was killed by a signal"),
+        events,
+        home,
+    }
+}
+
+#[test]
+fn happy_path_streams_plan_edit_and_answer_in_order() {
+    let run = exec("happy-path-edit", "max", 30, &[]);
+
+    assert_eq!(run.code, 0, "events: {:#?}", run.events);
+    assert_eq!(run.events.first().unwrap()["type"], "session_created");
+    assert_eq!(
+        run.update_kinds(),
+        [
+            "Plan",
+            "EditProposed",
+            "ToolCallUpdate",
+            "AgentMessageChunk",
+            "AgentMessageChunk"
+        ]
+    );
+
+    let updates: Vec<_> = run.updates().collect();
+    let plan = &updates[0]["Plan"]["entries"];
+    assert_eq!(plan.as_array().unwrap().len(), 2);
+    assert_eq!(plan[0]["content"], "Open file src/lib.rs");
+
+    let edit = &updates[1]["EditProposed"]["edit"];
+    assert_eq!(edit["id"], "call_edit_1");
+    assert_eq!(edit["file_path"], "/workspace/src/main.rs");
+    assert!(edit["proposed_content"]
+        .as_str()
+        .unwrap()
+        .contains("env_logger::init();"));
+
+    assert_eq!(
+        updates[2]["ToolCallUpdate"]["update"]["status"],
+        "completed"
+    );
+    assert_eq!(
+        run.answer(),
+        "Applied the change to src/lib.rs. Anything else?"
+    );
+
+    let stop = run.events.last().unwrap();
+    assert_eq!(stop["type"], "stop");
+    assert_eq!(stop["stop_reason"], "end_turn");
+}
+
+#[test]
+fn failed_tool_call_is_reported_before_the_answer() {
+    let run = exec("failure-path", "max", 30, &[]);
+
+    assert_eq!(run.code, 0, "events: {:#?}", run.events);
+    assert_eq!(
+        run.update_kinds(),
+        ["ToolCall", "ToolCallUpdate", "AgentMessageChunk"]
+    );
+    let updates: Vec<_> = run.updates().collect();
+    assert_eq!(updates[0]["ToolCall"]["tool_call"]["id"], "call_fail_1");
+    assert_eq!(updates[1]["ToolCallUpdate"]["update"]["status"], "failed");
+    assert_eq!(run.answer(), "Search failed; please refine your query.");
+    assert_eq!(run.event("stop").unwrap()["stop_reason"], "end_turn");
+}
+
+#[test]
+fn thoughts_and_images_arrive_before_text() {
+    let run = exec("images-and-thoughts", "max", 30, &[]);
+
+    assert_eq!(run.code, 0, "events: {:#?}", run.events);
+    assert_eq!(
+        run.update_kinds(),
+        ["AgentResponse", "AgentMessageChunk", "AgentMessageChunk"]
+    );
+    let updates: Vec<_> = run.updates().collect();
+    assert_eq!(updates[1]["AgentMessageChunk"]["content"]["type"], "image");
+    assert_eq!(run.answer(), "Here\u{2019}s a quick sketch.");
+}
+
+#[test]
+fn timeout_cancels_the_running_turn() {
+    // At this speed the simulator pauses for over two seconds after the
+    // plan, so the one second timeout lands mid-turn
+    let run = exec("happy-path-edit", "0.05", 1, &[]);
+
+    assert_eq!(run.code, EXIT_TIMEOUT, "events: {:#?}", run.events);
+    // Nothing is streamed after the cancel, and the agent confirms it
+    assert_eq!(run.update_kinds(), ["Plan"]);
+    assert_eq!(run.event("stop").unwrap()["stop_reason"], "cancelled");
+
+    let error = run.events.last().unwrap();
+    assert_eq!(error["type"], "error");
+    assert_eq!(error["exit_code"], EXIT_TIMEOUT);
+    assert_eq!(error["message"], "timed out after 1s");
+}
+
+#[test]
+fn allowed_permission_request_writes_through_the_client() {
+    let run = exec("permission-request", "max", 30, &["--approve", "edits"]);
+
+    assert_eq!(run.code, 0, "events: {:#?}", run.events);
+    let permission = run.event("permission").expect("permission event");
+    assert_eq!(permission["tool_kind"], "edit");
+    assert_eq!(permission["decision"], "allow");
+    assert_eq!(
+        run.update_kinds(),
+        ["EditProposed", "ToolCallUpdate", "AgentMessageChunk"]
+    );
+    let updates: Vec<_> = run.updates().collect();
+    assert_eq!(
+        updates[1]["ToolCallUpdate"]["update"]["status"],
+        "completed"
+    );
+
+    let notes = std::fs::read_to_string(run.home.path().join("sim-notes.md")).unwrap();
+    assert!(notes.starts_with("# Notes"));
+}
+
+#[test]
+fn denied_permission_request_writes_nothing() {
+    let run = exec("permission-request", "max", 30, &["--deny-all"]);
+
+    assert_eq!(run.code, 0, "events: {:#?}", run.events);
+    assert_eq!(run.event("permission").unwrap()["decision"], "deny");
+    let updates: Vec<_> = run.updates().collect();
+    assert_eq!(updates[1]["ToolCallUpdate"]["update"]["status"], "failed");
+    assert_eq!(run.answer(), "Permission denied; nothing was written.");
+    assert!(!run.home.path().join("sim-notes.md").exists());
+}
+
+#[test]
+fn stress_run_delivers_every_update() {
+    let run = exec("stress", "max", 120, &[]);
+
+    assert_eq!(run.code, 0, "stop: {:?}", run.event("stop"));
+    // Chunks may be merged when the UI side falls behind, but none are lost
+    let expected: String = (0..5_000).map(|i| format!("chunk {i} ")).collect();
+    let answer = run.answer();
+    assert!(answer.starts_with(&expected));
+    assert!(answer.contains(&"x".repeat(50_000)));
+    assert!(answer.ends_with("漢字"));
+
+    let edit = run
+        .updates()
+        .find_map(|update| update["EditProposed"]["edit"]["diff"].as_str())
+        .expect("diff");
+    assert_eq!(
+        edit.lines().filter(|l| l.starts_with("+pub const")).count(),
+        20_000
+    );
+
+    let nested: Vec<_> = run
+        .updates()
+        .map(|update| &update["ToolCallUpdate"]["update"])
+        .filter(|update| update["toolCallId"] == "call_stress_nested")
+        .collect();
+    assert_eq!(nested.len(), 500);
+    assert_eq!(nested[499]["status"], "completed");
+    assert_eq!(nested[499]["rawOutput"]["level"], 0);
+}
+
+#[test]
+fn script_replays_its_updates() {
+    let script = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/sim_scripts/fix-typo.yaml");
+    let run = exec_with(
+        &["--script", script.to_str().unwrap(), "--speed", "max"],
+        30,
+        &[],
+    );
+
+    assert_eq!(run.code, 0, "events: {:#?}", run.events);
+    assert_eq!(
+        run.update_kinds(),
+        [
+            "Plan",
+            "EditProposed",
+            "ToolCallUpdate",
+            "AgentMessageChunk"
+        ]
+    );
+    let updates: Vec<_> = run.updates().collect();
+    assert_eq!(
+        updates[1]["EditProposed"]["edit"]["file_path"],
+        "/workspace/README.md"
+    );
+    assert_eq!(run.answer(), "Fixed the typo in README.md.");
+    assert_eq!(run.event("stop").unwrap()["stop_reason"], "end_turn");
+}
+
+#[test]
+fn json_script_sets_the_stop_reason() {
+    let dir = tempfile::tempdir().unwrap();
+    let script = dir.path().join("refusal.json");
+    std::fs::write(
+        &script,
+        r#"{
+            "steps": [
+                { "delay_ms": 10, "update": { "sessionUpdate": "agent_message_chunk", "content": { "type": "text", "text": "No." } } }
+            ],
+            "stop_reason": "cancelled"
+        }"#,
+    )
+    .unwrap();
+    let run = exec_with(
+        &["--script", script.to_str().unwrap(), "--speed", "max"],
+        30,
+        &[],
+    );
+
+    assert_eq!(run.update_kinds(), ["AgentMessageChunk"]);
+    assert_eq!(run.answer(), "No.");
+    assert_eq!(run.event("stop").unwrap()["stop_reason"], "cancelled");
+}
+
+/// Run the happy path with `fault` injected after the first update
+fn exec_fault(fault: &str, timeout_secs: u64) -> Run {
+    exec_with(
+        &[
+            "--scenario",
+            "happy-path-edit",
+            "--speed",
+            "max",
+            "--fault",
+            fault,
+        ],
+        timeout_secs,
+        &[],
+    )
+}
+
+#[test]
+fn malformed_lines_are_skipped() {
+    let run = exec_fault("malformed-json", 30);
+
+    assert_eq!(run.code, 0, "events: {:#?}", run.events);
+    assert_eq!(
+        run.answer(),
+        "Applied the change to src/lib.rs. Anything else?"
+    );
+}
+
+#[test]
+fn duplicate_responses_are_ignored() {
+    let run = exec_fault("duplicate-ids", 30);
+
+    assert_eq!(run.code, 0, "events: {:#?}", run.events);
+    let stops = run
+        .events
+        .iter()
+        .filter(|event| event["type"] == "stop")
+        .count();
+    assert_eq!(stops, 1);
+}
+
+#[test]
+fn agent_exiting_mid_turn_fails_the_turn() {
+    let run = exec_fault("close-stdout", 30);
+
+    assert_eq!(run.code, EXIT_PROTOCOL, "events: {:#?}", run.events);
+    assert!(run.event("stop").is_none());
+    let error = run.events.last().unwrap();
+    assert_eq!(error["type"], "error");
+    assert!(error["message"]
+        .as_str()
+        .unwrap()
+        .contains("agent closed the connection mid-turn"));
+}
+
+#[test]
+fn stalled_agent_times_out() {
+    let run = exec_fault("stall", 1);
+
+    // The cancel goes unanswered, so the run ends after the grace period
+    assert_eq!(run.code, EXIT_TIMEOUT, "events: {:#?}", run.events);
+    assert_eq!(run.update_kinds(), ["Plan"]);
+    assert!(run.event("stop").is_none());
+}
+
+#[test]
+fn login_command_runs_when_the_agent_requires_auth() {
+    let login = tempfile::tempdir().unwrap();
+    let marker = login.path().join("logged-in");
+    let marker = marker.to_str().unwrap();
+    let agent = [
+        "--scenario",
+        "happy-path-edit",
+        "--speed",
+        "max",
+        "--require-auth",
+        marker,
+    ];
+
+    // Without a login command the session is refused
+    let run = exec_with(&agent, 30, &[]);
+    assert_eq!(run.code, EXIT_AUTH_REQUIRED, "events: {:#?}", run.events);
+
+    // With one, it runs and session creation is retried
+    let run = exec_with(
+        &agent,
+        30,
+        &["--agent-login", "touch", "--agent-login", marker],
+    );
+    assert_eq!(run.code, 0, "events: {:#?}", run.events);
+    assert!(Path::new(marker).exists());
+    assert_eq!(
+        run.answer(),
+        "Applied the change to src/lib.rs. Anything else?"
+    );
+}
+
+#[test]
+fn check_agent_passes_the_simulator() {
+    let home = tempfile::tempdir().expect("temp home");
+    let output = Command::new(env!("CARGO_BIN_EXE_rat"))
+        .arg("--agent-cmd")
+        .arg(sim_agent())
+        // Slow enough that the cancel lands mid-turn
+        .args(["--agent-arg=--speed", "--agent-arg=slomo"])
+        .args(["check-agent", "--json", "--timeout", "30"])
+        .env("HOME", home.path())
+        .env("XDG_CONFIG_HOME", home.path().join("config"))
+        .env("XDG_DATA_HOME", home.path().join("data"))
+        .env_remove("RUST_LOG")
+        .output()
+        .expect("failed to run rat");
+
+    let report: Value = serde_json::from_slice(&output.stdout).expect("JSON report");
+    assert_eq!(output.status.code(), Some(0), "report: {report:#}");
+    let status = |name: &str| {
+        report["checks"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|check| check["name"] == name)
+            .map(|check| check["status"].as_str().unwrap().to_string())
+            .unwrap_or_else(|| panic!("no {name} check"))
+    };
+    for name in [
+        "initialize",
+        "unknown method",
+        "session/new",
+        "invalid params",
+        "prompt turn",
+        "cancellation",
+        "session/load",
+        "stdout",
+    ] {
+        assert_eq!(status(name), "pass", "{name}: {report:#}");
+    }
+    // The simulator never reads files through the client
+    assert_eq!(status("fs callbacks"), "warn");
+}
@@ -119,3 +119,87 @@ async fn sim_agent_happy_path_jsonrpc() {
     let _ = child.kill().await;
 }
 
+
+/// Write one JSON-RPC message to the agent
+async fn send(stdin: &mut tokio::process::ChildStdin, message: Value) {
+    stdin.write_all(format!("{}\n", message).as_bytes()).await.unwrap();
+    stdin.flush().await.unwrap();
+}
+
+#[tokio::test]
+async fn sim_agent_terminal_execution_jsonrpc() {
+    let mut child = tokio::process::Command::new("cargo")
+        .args([
+            "run",
+            "--quiet",
+            "--example",
+            "sim_agent",
+            "--",
+            "--scenario",
+            "terminal-execution",
+            "--speed",
+            "max",
+        ])
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .expect("failed to spawn sim_agent");
+    let mut stdin = child.stdin.take().unwrap();
+    let mut lines = BufReader::new(child.stdout.take().unwrap()).lines();
+
+    send(&mut stdin, json!({"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":1,"clientCapabilities":{"fs":{"readTextFile":true,"writeTextFile":true}}}})).await;
+    send(&mut stdin, json!({"jsonrpc":"2.0","id":2,"method":"session/new","params":{"cwd":"/tmp","mcpServers":[]}})).await;
+
+    let mut commands = Vec::new();
+    let mut statuses = Vec::new();
+    let mut answer = String::new();
+    let run = async {
+        while let Some(line) = lines.next_line().await.unwrap() {
+            let Ok(v) = serde_json::from_str::<Value>(line.trim()) else { continue };
+            match (v.get("method").and_then(|m| m.as_str()), v.get("id")) {
+                (Some("terminal/execute"), Some(id)) => {
+                    // Answer as the bridge would: the build fails, the watcher gets killed
+                    assert!(id.as_str().unwrap().starts_with("sim-raw-"));
+                    let params = &v["params"];
+                    commands.push(params["cmd"].as_str().unwrap().to_string());
+                    assert_eq!(params["cwd"], "/tmp");
+                    let reply = if commands.len() == 1 {
+                        json!({"jsonrpc":"2.0","id":id,"result":{"exitCode":3}})
+                    } else {
+                        json!({"jsonrpc":"2.0","id":id,"error":{"code":-32000,"message":"command 'sleep' terminated by user (SIGINT)","data":{"exitCode":-1,"signal":"SIGINT"}}})
+                    };
+                    send(&mut stdin, reply).await;
+                }
+                (Some("session/update"), _) => {
+                    let u = &v["params"]["update"];
+                    match u["sessionUpdate"].as_str() {
+                        Some("tool_call_update") => statuses.push(u["status"].as_str().unwrap().to_string()),
+                        Some("agent_message_chunk") => answer.push_str(u["content"]["text"].as_str().unwrap()),
+                        _ => {}
+                    }
+                }
+                (None, Some(id)) if id == 2 => {
+                    let sid = v["result"]["sessionId"].as_str().unwrap().to_string();
+                    send(&mut stdin, json!({"jsonrpc":"2.0","id":3,"method":"session/prompt","params":{"sessionId":sid,"prompt":[{"type":"text","text":"build it"}]}})).await;
+                }
+                (None, Some(id)) if id == 3 => {
+                    assert_eq!(v["result"]["stopReason"], "end_turn");
+                    return;
+                }
+                _ => {}
+            }
+        }
+        panic!("sim_agent exited before ending the turn");
+    };
+    tokio::time::timeout(std::time::Duration::from_secs(120), run)
+        .await
+        .expect("terminal scenario timed out");
+
+    assert_eq!(commands, ["sh", "sleep"]);
+    assert_eq!(statuses, ["failed", "failed"]);
+    assert_eq!(
+        answer,
+        "The build exited with code 3; the watcher was stopped by the user (SIGINT)."
+    );
+}
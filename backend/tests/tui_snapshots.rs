@@ -0,0 +1,416 @@
+//! Rendering snapshots of the chat view and the full TUI on a `TestBackend`.
+//!
+//! Messages carry a fixed timestamp and effects are off, so the buffers are
+//! deterministic; the status bar clock is filtered out. Review changes with
+//! `cargo insta review`.
+
+use agent_client_protocol as acp;
+use chrono::TimeZone;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{backend::TestBackend, Terminal};
+use tokio::sync::mpsc;
+
+use rat::acp::message::{EditProposal, ToolCallRequest};
+use rat::app::UiToApp;
+use rat::config::UiConfig;
+use rat::events::PermissionReply;
+use rat::ui::chat::ChatView;
+use rat::ui::keymap::Action;
+use rat::ui::TuiManager;
+use rat::utils::diff::DiffGenerator;
+use rat::{Message, MessageContent, SessionId};
+
+/// Terminal sizes every layout is checked at: cramped, classic and roomy
+const SIZES: [(u16, u16); 3] = [(40, 12), (80, 24), (120, 40)];
+
+fn session() -> SessionId {
+    SessionId("snapshot-session".to_string())
+}
+
+fn message(content: MessageContent) -> Message {
+    let mut message = Message::new(session(), content);
+    message.timestamp = chrono::Utc.with_ymd_and_hms(2025, 1, 2, 3, 4, 5).unwrap();
+    message
+}
+
+fn text(text: &str) -> acp::ContentBlock {
+    acp::ContentBlock::Text(acp::TextContent {
+        text: text.to_string(),
+        annotations: Default::default(),
+    })
+}
+
+fn prompt(body: &str) -> Message {
+    message(MessageContent::UserPrompt {
+        content: vec![text(body)],
+    })
+}
+
+fn reply(body: &str) -> Message {
+    message(MessageContent::AgentMessageChunk {
+        content: text(body),
+    })
+}
+
+fn edit() -> Message {
+    let original = "fn main() {\n    println!(\"hello\");\n}\n";
+    let proposed = "fn main() {\n    let name = \"rat\";\n    println!(\"hello, {name}\");\n}\n";
+    message(MessageContent::EditProposed {
+        edit: EditProposal {
+            id: "edit-1".to_string(),
+            file_path: "src/main.rs".to_string(),
+            original_content: original.to_string(),
+            proposed_content: proposed.to_string(),
+            diff: DiffGenerator::generate_diff(original, proposed),
+            description: Some("Greet by name".to_string()),
+        },
+    })
+}
+
+fn tool_calls() -> Vec<Message> {
+    vec![
+        message(MessageContent::ToolCall {
+            tool_call: ToolCallRequest {
+                id: "call-1".to_string(),
+                tool_name: "Run cargo test".to_string(),
+                parameters: serde_json::json!({ "command": "cargo test --quiet" }),
+                requires_permission: true,
+                locations: Vec::new(),
+            },
+        }),
+        message(MessageContent::ToolCallUpdate {
+            update: acp::ToolCallUpdate {
+                id: acp::ToolCallId("call-1".into()),
+                fields: acp::ToolCallUpdateFields {
+                    status: Some(acp::ToolCallStatus::Completed),
+                    content: Some(vec![acp::ToolCallContent::Content {
+                        content: text("test result: ok. 42 passed"),
+                    }]),
+                    ..Default::default()
+                },
+            },
+        }),
+        message(MessageContent::ToolCallUpdate {
+            update: acp::ToolCallUpdate {
+                id: acp::ToolCallId("call-2".into()),
+                fields: acp::ToolCallUpdateFields {
+                    status: Some(acp::ToolCallStatus::Failed),
+                    ..Default::default()
+                },
+            },
+        }),
+    ]
+}
+
+/// A short conversation with a long, wrapping answer
+fn conversation() -> Vec<Message> {
+    vec![
+        prompt("Why does the chat wrap long lines instead of truncating them?"),
+        reply(
+            "Truncating would hide the end of commands and paths, which is usually the part \
+             that matters. Wrapping keeps every character on screen, breaks at spaces where it \
+             can and splits words only when a single word is wider than the view.",
+        ),
+        reply("averyveryveryverylongidentifierwithoutanyspacesthatmustbesplitsomewhere"),
+    ]
+}
+
+async fn chat_with(messages: Vec<Message>) -> ChatView {
+    let mut view = ChatView::new(1000);
+    for message in messages {
+        view.add_message(message).await.unwrap();
+    }
+    view
+}
+
+fn draw_chat(view: &mut ChatView, (width, height): (u16, u16)) -> Terminal<TestBackend> {
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+    terminal
+        .draw(|frame| view.render(frame, frame.area()).unwrap())
+        .unwrap();
+    terminal
+}
+
+fn tui() -> TuiManager {
+    let mut config = UiConfig::default();
+    config.effects.enabled = false;
+    let (ui_tx, _ui_rx) = mpsc::unbounded_channel();
+    TuiManager::new(config, ui_tx, "sim".to_string()).unwrap()
+}
+
+fn draw_tui(tui: &mut TuiManager, (width, height): (u16, u16)) -> Terminal<TestBackend> {
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+    terminal.draw(|frame| tui.render(frame).unwrap()).unwrap();
+    terminal
+}
+
+/// Snapshot a buffer, with the status bar clock masked
+macro_rules! assert_screen {
+    ($name:expr, $terminal:expr) => {
+        insta::with_settings!({ filters => vec![(r"\d{2}:\d{2}:\d{2}", "[clock]")] }, {
+            insta::assert_snapshot!($name, $terminal.backend());
+        })
+    };
+}
+
+fn size_name(prefix: &str, (width, height): (u16, u16)) -> String {
+    format!("{}_{}x{}", prefix, width, height)
+}
+
+#[tokio::test]
+async fn chat_wraps_long_messages() {
+    for size in SIZES {
+        let mut view = chat_with(conversation()).await;
+        assert_screen!(size_name("chat_wrapping", size), draw_chat(&mut view, size));
+    }
+}
+
+#[tokio::test]
+async fn chat_renders_diffs() {
+    for size in SIZES {
+        let mut view = chat_with(vec![prompt("Greet by name"), edit()]).await;
+        assert_screen!(size_name("chat_diff", size), draw_chat(&mut view, size));
+    }
+}
+
+#[tokio::test]
+async fn chat_renders_tool_cards() {
+    for size in SIZES {
+        let mut view = chat_with(tool_calls()).await;
+        assert_screen!(
+            size_name("chat_tool_cards", size),
+            draw_chat(&mut view, size)
+        );
+    }
+}
+
+#[tokio::test]
+async fn chat_updates_tool_progress_in_place() {
+    let mut view = chat_with(tool_calls()).await;
+    let screen = format!("{}", draw_chat(&mut view, (80, 24)).backend());
+    // call-1's update lands on its card; call-2 was never shown, so its
+    // update stays a message of its own
+    assert!(screen.contains("Conversation (2 messages)"));
+    assert!(screen.contains("Done 0.0s · test result: ok. 42 passed"));
+}
+
+#[tokio::test]
+async fn chat_scrolls_from_the_bottom() {
+    let messages = (1..=30)
+        .map(|i| reply(&format!("Message number {}", i)))
+        .collect();
+    let mut view = chat_with(messages).await;
+    let size = (60, 16);
+
+    assert_screen!("chat_scroll_bottom", draw_chat(&mut view, size));
+    for _ in 0..5 {
+        assert!(view.handle_action(Action::ChatScrollUp));
+    }
+    assert_screen!("chat_scroll_up_5", draw_chat(&mut view, size));
+    assert!(view.handle_action(Action::ChatScrollTop));
+    assert_screen!("chat_scroll_top", draw_chat(&mut view, size));
+}
+
+#[tokio::test]
+async fn chat_search_jumps_between_matches() {
+    let messages = (1..=30)
+        .map(|i| reply(&format!("Message number {}", i)))
+        .collect();
+    let mut view = chat_with(messages).await;
+    let size = (60, 16);
+
+    assert!(view.handle_action(Action::ChatSearch));
+    for c in "number 1".chars() {
+        assert!(view.handle_search_key(KeyEvent::from(KeyCode::Char(c))));
+    }
+    // The newest of "number 1", "number 10".."number 19" is focused
+    let screen = format!("{}", draw_chat(&mut view, size).backend());
+    assert!(screen.contains("/number 1 (11/11)"));
+    assert!(screen.contains("Message number 19"));
+    assert!(!screen.contains("Message number 30"));
+
+    assert!(view.handle_search_key(KeyEvent::from(KeyCode::Enter)));
+    view.jump_match(true);
+    let screen = format!("{}", draw_chat(&mut view, size).backend());
+    assert!(screen.contains("Search: number 1 (1/11)"));
+    assert!(screen.contains("Message number 1 "));
+}
+
+#[tokio::test]
+async fn tui_shows_welcome_then_the_session() {
+    for size in SIZES {
+        let mut tui = tui();
+        assert_screen!(size_name("tui_welcome", size), draw_tui(&mut tui, size));
+
+        tui.add_session("sim", session()).unwrap();
+        for message in conversation() {
+            tui.add_message("sim", message).await.unwrap();
+        }
+        assert_screen!(size_name("tui_session", size), draw_tui(&mut tui, size));
+    }
+}
+
+#[tokio::test]
+async fn tui_help_overlay() {
+    for size in SIZES {
+        let mut tui = tui();
+        tui.add_session("sim", session()).unwrap();
+        tui.handle_key_event(KeyEvent::from(KeyCode::Char('?')))
+            .await
+            .unwrap();
+        assert_screen!(size_name("tui_help", size), draw_tui(&mut tui, size));
+
+        // Typing narrows the list to matching bindings
+        for c in "terminal".chars() {
+            tui.handle_key_event(KeyEvent::from(KeyCode::Char(c)))
+                .await
+                .unwrap();
+        }
+        let searched = format!("{}", draw_tui(&mut tui, size).backend());
+        assert!(searched.contains("Search: terminal"));
+        assert!(!searched.contains("Quit application"));
+
+        // Esc clears the search, then closes it again
+        for _ in 0..2 {
+            tui.handle_key_event(KeyEvent::from(KeyCode::Esc))
+                .await
+                .unwrap();
+        }
+        let closed = draw_tui(&mut tui, size);
+        assert!(!format!("{}", closed.backend()).contains("RAT - Rust Agent Terminal Help"));
+    }
+}
+
+#[tokio::test]
+async fn tui_accessibility_mode_is_plain_ascii() {
+    let config = UiConfig {
+        accessibility: true,
+        ..Default::default()
+    };
+    let (ui_tx, _ui_rx) = mpsc::unbounded_channel();
+    let mut tui = TuiManager::new(config, ui_tx, "sim".to_string()).unwrap();
+    tui.add_session("sim", session()).unwrap();
+    for message in tool_calls() {
+        tui.add_message("sim", message).await.unwrap();
+    }
+
+    let screen = format!("{}", draw_tui(&mut tui, (80, 24)).backend());
+    assert!(screen.is_ascii(), "{}", screen);
+    // Cards read as labelled lines rather than boxes
+    assert!(screen.contains("Tool Call"));
+    assert!(screen.contains("Tool: Run cargo test"));
+    assert!(screen.contains("[done] Done 0.0s - test result: ok. 42 passed"));
+    assert!(!screen.contains("+- Tool Call"));
+}
+
+#[tokio::test]
+async fn tui_confirms_quit_while_a_turn_runs() {
+    let mut config = UiConfig::default();
+    config.effects.enabled = false;
+    let (ui_tx, mut ui_rx) = mpsc::unbounded_channel();
+    let mut tui = TuiManager::new(config, ui_tx, "sim".to_string()).unwrap();
+    tui.add_session("sim", session()).unwrap();
+    let keys = [
+        KeyCode::Enter,
+        KeyCode::Char('h'),
+        KeyCode::Char('i'),
+        KeyCode::Enter,
+    ];
+    for code in keys {
+        tui.handle_key_event(KeyEvent::from(code)).await.unwrap();
+    }
+    assert!(matches!(ui_rx.try_recv(), Ok(UiToApp::SendMessage { .. })));
+
+    // The prompt is still running, so quitting asks first; `n` keeps going
+    tui.handle_key_event(KeyEvent::from(KeyCode::Char('q')))
+        .await
+        .unwrap();
+    let screen = format!("{}", draw_tui(&mut tui, (80, 24)).backend());
+    assert!(screen.contains("Quit RAT?"));
+    assert!(screen.contains("1 session is still waiting on its agent"));
+    tui.handle_key_event(KeyEvent::from(KeyCode::Char('n')))
+        .await
+        .unwrap();
+    assert!(ui_rx.try_recv().is_err());
+
+    for c in ['q', 'y'] {
+        tui.handle_key_event(KeyEvent::from(KeyCode::Char(c)))
+            .await
+            .unwrap();
+    }
+    assert!(matches!(ui_rx.try_recv(), Ok(UiToApp::Quit)));
+}
+
+#[tokio::test]
+async fn tui_stops_the_running_turn() {
+    let mut config = UiConfig::default();
+    config.effects.enabled = false;
+    let (ui_tx, mut ui_rx) = mpsc::unbounded_channel();
+    let mut tui = TuiManager::new(config, ui_tx, "sim".to_string()).unwrap();
+    tui.add_session("sim", session()).unwrap();
+    let interrupt = KeyEvent::new(KeyCode::Char('x'), KeyModifiers::CONTROL);
+
+    // Nothing runs yet, so there is nothing to stop
+    tui.handle_key_event(interrupt).await.unwrap();
+    assert!(ui_rx.try_recv().is_err());
+
+    let keys = [
+        KeyCode::Enter,
+        KeyCode::Char('h'),
+        KeyCode::Char('i'),
+        KeyCode::Enter,
+    ];
+    for code in keys {
+        tui.handle_key_event(KeyEvent::from(code)).await.unwrap();
+    }
+    assert!(matches!(ui_rx.try_recv(), Ok(UiToApp::SendMessage { .. })));
+
+    tui.handle_key_event(interrupt).await.unwrap();
+    match ui_rx.try_recv() {
+        Ok(UiToApp::CancelTurn {
+            agent_name,
+            session_id,
+        }) => {
+            assert_eq!(agent_name, "sim");
+            assert_eq!(session_id, session());
+        }
+        _ => panic!("expected a CancelTurn request"),
+    }
+}
+
+#[tokio::test]
+async fn tui_asks_about_waiting_permissions() {
+    let (ui_tx, mut ui_rx) = mpsc::unbounded_channel();
+    let mut config = UiConfig::default();
+    config.effects.enabled = false;
+    let mut tui = TuiManager::new(config, ui_tx, "sim".to_string()).unwrap();
+    tui.add_session("sim", session()).unwrap();
+
+    let (first, mut first_answer) = PermissionReply::new();
+    let (second, mut second_answer) = PermissionReply::new();
+    let command = Some("cargo test".to_string());
+    tui.permission_requested("sim", &session(), "Run tests".to_string(), command, first);
+    tui.permission_requested("sim", &session(), "Edit main.rs".to_string(), None, second);
+    let screen = format!("{}", draw_tui(&mut tui, (80, 24)).backend());
+    assert!(screen.contains("Permission requested"));
+    assert!(screen.contains("$ cargo test"));
+    assert!(screen.contains("Perms 2 waiting"));
+
+    // The dialog takes every key, so `q` does not quit
+    for c in ['q', 'y'] {
+        tui.handle_key_event(KeyEvent::from(KeyCode::Char(c)))
+            .await
+            .unwrap();
+    }
+    assert!(ui_rx.try_recv().is_err());
+    assert_eq!(first_answer.try_recv(), Ok(true));
+
+    let screen = format!("{}", draw_tui(&mut tui, (80, 24)).backend());
+    assert!(screen.contains("Edit main.rs"));
+    tui.handle_key_event(KeyEvent::from(KeyCode::Esc))
+        .await
+        .unwrap();
+    assert_eq!(second_answer.try_recv(), Ok(false));
+    let screen = format!("{}", draw_tui(&mut tui, (80, 24)).backend());
+    assert!(!screen.contains("Permission requested"));
+}
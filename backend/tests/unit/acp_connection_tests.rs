@@ -1,17 +1,15 @@
 use std::collections::HashMap;
 use std::time::Duration;
-use tokio::sync::mpsc;
 use tokio::time::timeout;
 
 use rat::acp::{AcpClient, Message, MessageContent, Session, SessionId};
 use rat::adapters::claude_code::ClaudeCodeAdapter;
 use rat::adapters::traits::{AgentAdapter, AgentHealth};
-use rat::app::AppMessage;
 use rat::config::agent::ClaudeCodeConfig;
 
 #[tokio::test]
 async fn test_acp_client_creation() {
-    let (message_tx, _message_rx) = mpsc::unbounded_channel::<AppMessage>();
+    let (message_tx, _message_rx) = rat::events::channel(rat::events::DEFAULT_CAPACITY);
 
     // Create ACP client with mock command
     let client = AcpClient::new(
@@ -31,7 +29,7 @@ async fn test_acp_client_creation() {
 async fn test_claude_code_adapter_creation() {
     let config = ClaudeCodeConfig::default();
 
-    let (message_tx, _message_rx) = mpsc::unbounded_channel::<AppMessage>();
+    let (message_tx, _message_rx) = rat::events::channel(rat::events::DEFAULT_CAPACITY);
 
     let adapter = ClaudeCodeAdapter::new(config, message_tx)
         .await
@@ -46,7 +44,7 @@ async fn test_claude_code_adapter_creation() {
 async fn test_adapter_lifecycle() {
     let config = ClaudeCodeConfig::default();
 
-    let (message_tx, _message_rx) = mpsc::unbounded_channel::<AppMessage>();
+    let (message_tx, _message_rx) = rat::events::channel(rat::events::DEFAULT_CAPACITY);
 
     let mut adapter = ClaudeCodeAdapter::new(config, message_tx)
         .await
@@ -224,7 +222,7 @@ async fn test_session_context_defaults() {
 async fn test_adapter_health_monitoring() {
     let config = ClaudeCodeConfig::default();
 
-    let (message_tx, _message_rx) = mpsc::unbounded_channel::<AppMessage>();
+    let (message_tx, _message_rx) = rat::events::channel(rat::events::DEFAULT_CAPACITY);
 
     let mut adapter = ClaudeCodeAdapter::new(config, message_tx)
         .await
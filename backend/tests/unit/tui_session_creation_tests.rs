@@ -15,7 +15,7 @@ async fn test_tui_new_session_key_press_flow() {
 
     // Create channels for testing
     let (ui_tx, mut ui_rx) = mpsc::unbounded_channel::<UiToApp>();
-    let (message_tx, mut message_rx) = mpsc::unbounded_channel::<AppMessage>();
+    let (message_tx, mut message_rx) = rat::events::channel(rat::events::DEFAULT_CAPACITY);
 
     // Create TUI manager
     let mut tui_manager = TuiManager::new(ui_config, ui_tx).expect("Failed to create TUI manager");
@@ -86,7 +86,7 @@ async fn test_app_create_session_message_flow() {
 #[tokio::test]
 async fn test_manager_cmd_create_session() {
     // Setup test channels
-    let (message_tx, mut message_rx) = mpsc::unbounded_channel::<AppMessage>();
+    let (message_tx, mut message_rx) = rat::events::channel(rat::events::DEFAULT_CAPACITY);
     let (manager_tx, mut manager_rx) = mpsc::unbounded_channel::<ManagerCmd>();
 
     // Create a oneshot channel for the response